@@ -0,0 +1,59 @@
+// (C) Copyright 2026 Hewlett Packard Enterprise Development LP
+
+//! Compares the time taken to extract base images with the full parser and
+//! with `quick::from_images()`, e.g.:
+//!
+//!     cargo run --release --example quick_from -- path/to/Dockerfile ...
+//!
+//! If no paths are given, the test Dockerfile from this repository is used.
+
+use std::fs;
+use std::time::{Duration, Instant};
+
+use dockerfile_parser::Dockerfile;
+use dockerfile_parser::quick::from_images;
+
+const ITERATIONS: usize = 1000;
+
+fn time<F: FnMut() -> usize>(mut f: F) -> (Duration, usize) {
+  let start = Instant::now();
+  let mut count = 0;
+  for _ in 0..ITERATIONS {
+    count += f();
+  }
+
+  (start.elapsed(), count)
+}
+
+fn main() {
+  let paths: Vec<String> = std::env::args().skip(1).collect();
+  let inputs: Vec<String> = if paths.is_empty() {
+    vec![include_str!("../Dockerfile.test").to_string()]
+  } else {
+    paths
+      .iter()
+      .map(|path| fs::read_to_string(path).expect("file must be readable"))
+      .collect()
+  };
+
+  let (full, full_count) = time(|| {
+    inputs
+      .iter()
+      .filter_map(|input| Dockerfile::parse(input).ok())
+      .map(|dockerfile| dockerfile.instructions.iter().filter(|i| i.as_from().is_some()).count())
+      .sum()
+  });
+
+  let (quick, quick_count) = time(|| {
+    inputs.iter().map(|input| from_images(input).len()).sum()
+  });
+
+  println!("files:      {}", inputs.len());
+  println!("iterations: {}", ITERATIONS);
+  println!("full parse: {:?} ({} FROMs)", full, full_count / ITERATIONS);
+  println!("quick:      {:?} ({} FROMs)", quick, quick_count / ITERATIONS);
+  println!(
+    "speedup:    {:.1}x",
+    full.as_secs_f64() / quick.as_secs_f64().max(f64::EPSILON)
+  );
+}
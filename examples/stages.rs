@@ -4,7 +4,7 @@ use std::fs::File;
 
 use snafu::ErrorCompat;
 
-use dockerfile_parser::{Result, Dockerfile};
+use dockerfile_parser::prelude::*;
 
 fn wrap() -> Result<()> {
   let args: Vec<String> = std::env::args().collect();
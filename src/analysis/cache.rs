@@ -0,0 +1,135 @@
+// (C) Copyright 2026 Hewlett Packard Enterprise Development LP
+
+//! Recognizes instruction orderings that defeat Docker's layer cache.
+
+use crate::diagnostics::{Diagnostic, DiagnosticKind};
+use crate::dockerfile_parser::Instruction;
+use crate::instructions::CopySourceBreadth;
+use crate::stage::Stage;
+
+impl<'a> Stage<'a> {
+  /// Finds `COPY` instructions copying the whole build context, e.g.
+  /// `COPY . .`, that are followed by a dependency install in the same stage,
+  /// e.g. `RUN npm ci`. Any change to the context invalidates the cache for
+  /// the install, so dependency manifests should be copied and installed
+  /// first.
+  ///
+  /// Each broad `COPY` source is reported once, with related spans for each
+  /// following install (see `RunInstruction::dependency_installs()`).
+  /// `COPY --from` instructions don't copy from the build context and are
+  /// ignored.
+  ///
+  /// ```
+  /// use dockerfile_parser::Dockerfile;
+  ///
+  /// let dockerfile = Dockerfile::parse(r#"
+  ///   FROM node:20
+  ///   COPY . .
+  ///   RUN npm ci
+  /// "#).unwrap();
+  ///
+  /// let diagnostics = dockerfile.stages()[0].broad_copy_before_install();
+  /// assert_eq!(diagnostics.len(), 1);
+  /// assert_eq!(diagnostics[0].related_spans.len(), 1);
+  /// ```
+  pub fn broad_copy_before_install(&self) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+
+    for (index, ins) in self.instructions.iter().enumerate() {
+      let copy = match ins {
+        Instruction::Copy(copy) if copy.from_flag().is_none() => copy,
+        _ => continue
+      };
+
+      let source = copy.sources
+        .iter()
+        .zip(copy.breadth())
+        .find(|(_, breadth)| *breadth == CopySourceBreadth::WholeContext)
+        .map(|(source, _)| source);
+
+      let source = match source {
+        Some(source) => source,
+        None => continue
+      };
+
+      let installs: Vec<_> = self.instructions[index + 1..]
+        .iter()
+        .filter_map(|ins| ins.as_run())
+        .flat_map(|run| run.dependency_installs())
+        .collect();
+
+      let first = match installs.first() {
+        Some(first) => first,
+        None => continue
+      };
+
+      let mut diagnostic = Diagnostic::warning(
+        DiagnosticKind::BroadCopyBeforeInstall,
+        source.span,
+        format!(
+          "COPY {} copies the whole build context before '{} {}', so any change to the context \
+          reruns the install; copy only the files it needs first",
+          source.content, first.command.content, first.subcommand.content
+        )
+      );
+      diagnostic.related_spans = installs.iter().map(|op| op.span()).collect();
+
+      diagnostics.push(diagnostic);
+    }
+
+    diagnostics
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use indoc::indoc;
+  use pretty_assertions::assert_eq;
+
+  use crate::*;
+
+  #[test]
+  fn test_broad_copy_before_install_bad() {
+    let d = Dockerfile::parse(indoc!(r#"
+      FROM node:20 AS build
+      WORKDIR /app
+      COPY . .
+      RUN npm ci && npm run build
+      RUN npm install -g serve
+
+      FROM node:20
+      COPY --from=build . .
+      RUN npm ci
+    "#)).unwrap();
+
+    let stages = d.stages();
+    let diagnostics = stages[0].broad_copy_before_install();
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0].kind, DiagnosticKind::BroadCopyBeforeInstall);
+    assert_eq!(d.text(&diagnostics[0].span), ".");
+    assert_eq!(diagnostics[0].span.start, d.content.find(". .").unwrap());
+    assert_eq!(
+      diagnostics[0].related_spans.iter().map(|s| d.text(s)).collect::<Vec<_>>(),
+      vec!["npm ci", "npm install -g serve"]
+    );
+    assert!(diagnostics[0].message.contains("before 'npm ci'"));
+
+    // copies from other stages don't involve the build context
+    assert_eq!(stages[1].broad_copy_before_install(), vec![]);
+  }
+
+  #[test]
+  fn test_broad_copy_before_install_good() {
+    let d = Dockerfile::parse(indoc!(r#"
+      FROM node:20
+      WORKDIR /app
+      COPY package.json package-lock.json ./
+      RUN npm ci
+      COPY . .
+      RUN npm run build
+      CMD ["node", "dist/index.js"]
+    "#)).unwrap();
+
+    assert_eq!(d.stages()[0].broad_copy_before_install(), vec![]);
+  }
+}
@@ -0,0 +1,276 @@
+// (C) Copyright 2026 Hewlett Packard Enterprise Development LP
+
+//! Recognizes syntax requiring a newer Dockerfile frontend than the one
+//! declared by the `syntax` directive.
+
+use std::fmt;
+
+use crate::diagnostics::{Diagnostic, DiagnosticKind, Severity};
+use crate::directive::FrontendVersion;
+use crate::dockerfile_parser::{Dockerfile, Instruction};
+use crate::splicer::Span;
+
+/// The frontend version assumed when there's no `syntax` directive, i.e. the
+/// oldest release of the `docker/dockerfile` frontend.
+const BASELINE_VERSION: FrontendVersion = FrontendVersion::new(1, Some(0));
+
+/// A Dockerfile feature introduced in a later release of the
+/// `docker/dockerfile` frontend.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Feature {
+  /// `RUN --mount`, e.g. cache and secret mounts
+  RunMount,
+
+  /// `RUN --network`
+  RunNetwork,
+
+  /// Heredocs, e.g. `RUN <<EOF`
+  Heredoc,
+
+  /// `COPY --link` or `ADD --link`
+  Link,
+
+  /// `ADD --checksum`
+  AddChecksum,
+
+  /// A Git repository as an `ADD` source
+  AddGit,
+}
+
+impl Feature {
+  /// All features, in order of their required versions.
+  pub const ALL: &'static [Feature] = &[
+    Feature::RunMount,
+    Feature::RunNetwork,
+    Feature::Heredoc,
+    Feature::Link,
+    Feature::AddChecksum,
+    Feature::AddGit,
+  ];
+
+  /// Returns the first `docker/dockerfile` frontend version supporting this
+  /// feature.
+  pub fn required_version(self) -> FrontendVersion {
+    match self {
+      Feature::RunMount => FrontendVersion::new(1, Some(2)),
+      Feature::RunNetwork => FrontendVersion::new(1, Some(3)),
+      Feature::Heredoc => FrontendVersion::new(1, Some(4)),
+      Feature::Link => FrontendVersion::new(1, Some(4)),
+      Feature::AddChecksum => FrontendVersion::new(1, Some(6)),
+      Feature::AddGit => FrontendVersion::new(1, Some(6)),
+    }
+  }
+}
+
+impl fmt::Display for Feature {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.write_str(match self {
+      Feature::RunMount => "RUN --mount",
+      Feature::RunNetwork => "RUN --network",
+      Feature::Heredoc => "heredoc",
+      Feature::Link => "--link",
+      Feature::AddChecksum => "ADD --checksum",
+      Feature::AddGit => "ADD from a Git repository",
+    })
+  }
+}
+
+/// A use of a feature not supported by the declared frontend, as returned by
+/// `Dockerfile::feature_compatibility()`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IncompatibleFeature {
+  /// The feature used
+  pub feature: Feature,
+
+  /// A diagnostic describing the problem, spanning the feature's use
+  pub diagnostic: Diagnostic,
+}
+
+/// Finds each use of a gated feature, in order.
+fn find_features(dockerfile: &Dockerfile) -> Vec<(Feature, Span)> {
+  let mut features = Vec::new();
+
+  for ins in &dockerfile.instructions {
+    match ins {
+      Instruction::Run(run) => {
+        for flag in &run.flags {
+          match flag.name.content.as_str() {
+            "mount" => features.push((Feature::RunMount, flag.span)),
+            "network" => features.push((Feature::RunNetwork, flag.span)),
+            _ => ()
+          }
+        }
+
+        features.extend(run.heredocs.iter().map(|h| (Feature::Heredoc, h.marker)));
+      },
+      Instruction::Copy(copy) => {
+        features.extend(copy.flags
+          .iter()
+          .filter(|flag| flag.name.content == "link")
+          .map(|flag| (Feature::Link, flag.span)));
+
+        features.extend(copy.heredocs.iter().map(|h| (Feature::Heredoc, h.marker)));
+      },
+      Instruction::Add(add) => {
+        for flag in &add.flags {
+          match flag.name.content.as_str() {
+            "link" => features.push((Feature::Link, flag.span)),
+            "checksum" => features.push((Feature::AddChecksum, flag.span)),
+            _ => ()
+          }
+        }
+
+        features.extend(add.git_sources().into_iter().map(|(source, _)| (Feature::AddGit, source.span)));
+        features.extend(add.heredocs.iter().map(|h| (Feature::Heredoc, h.marker)));
+      },
+      _ => ()
+    }
+  }
+
+  features
+}
+
+impl Dockerfile {
+  /// Finds uses of features newer than the `docker/dockerfile` frontend
+  /// version declared by the `syntax` directive, e.g. a heredoc with
+  /// `# syntax=docker/dockerfile:1.3`.
+  ///
+  /// Without a `syntax` directive, the engine's built-in frontend is used,
+  /// which may be too old for any feature, so all are reported with
+  /// `Severity::Info`. Otherwise, features are reported as warnings, with a
+  /// related span for the directive. Nothing is reported for other frontends
+  /// or for tags not naming a version, e.g. `latest`, as their features
+  /// can't be known.
+  ///
+  /// ```
+  /// use dockerfile_parser::Dockerfile;
+  ///
+  /// let dockerfile = Dockerfile::parse(r#"# syntax=docker/dockerfile:1.3
+  /// FROM alpine:3.19
+  /// RUN --mount=type=cache,target=/var/cache/apk apk add curl
+  /// COPY --link app /app
+  /// "#).unwrap();
+  ///
+  /// let incompatible = dockerfile.feature_compatibility();
+  /// assert_eq!(incompatible.len(), 1);
+  /// assert!(incompatible[0].diagnostic.message.contains("requires docker/dockerfile:1.4"));
+  /// ```
+  pub fn feature_compatibility(&self) -> Vec<IncompatibleFeature> {
+    let directive = self.syntax_directive();
+    let version = match &directive {
+      Some(directive) => match directive.frontend_version() {
+        Some(version) => version,
+        None => return Vec::new()
+      },
+      None => BASELINE_VERSION
+    };
+
+    find_features(self)
+      .into_iter()
+      .filter(|(feature, _)| !version.supports(feature.required_version()))
+      .map(|(feature, span)| {
+        let required = feature.required_version();
+        let diagnostic = match &directive {
+          Some(directive) => Diagnostic::warning(
+            DiagnosticKind::UnsupportedFeature,
+            span,
+            format!(
+              "{} requires docker/dockerfile:{} or later, but the syntax directive declares {}",
+              feature, required, version
+            )
+          ).with_related_span(directive.value.span),
+          None => Diagnostic::new(
+            DiagnosticKind::UnsupportedFeature,
+            Severity::Info,
+            span,
+            format!(
+              "{} requires docker/dockerfile:{} or later; add a syntax directive \
+              if older engines must be supported",
+              feature, required
+            )
+          )
+        };
+
+        IncompatibleFeature { feature, diagnostic }
+      })
+      .collect()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use indoc::indoc;
+  use pretty_assertions::assert_eq;
+
+  use crate::*;
+  use crate::analysis::features::Feature;
+  use crate::diagnostics::Severity;
+
+  const BODY: &str = indoc!(r#"
+    FROM alpine:3.19
+    RUN <<EOF
+    apk add curl
+    EOF
+    COPY --link app /app
+    ADD --checksum=sha256:24454f830cdb571e2c4ad15481119c43b3cafd48dd869a9b2945d1036d1dc68d \
+      https://example.com/a.tar /
+    ADD https://github.com/user/repo.git#v1.0 /src
+  "#);
+
+  fn parse(syntax: &str) -> Dockerfile {
+    Dockerfile::parse(&format!("{}{}", syntax, BODY)).unwrap()
+  }
+
+  #[test]
+  fn test_feature_compatibility() {
+    let d = parse("# syntax=docker/dockerfile:1.0\n");
+    let incompatible = d.feature_compatibility();
+    assert_eq!(
+      incompatible.iter().map(|i| (i.feature, d.text(&i.diagnostic.span))).collect::<Vec<_>>(),
+      vec![
+        (Feature::Heredoc, "<<EOF"),
+        (Feature::Link, "--link"),
+        (Feature::AddChecksum, "--checksum=sha256:24454f830cdb571e2c4ad15481119c43b3cafd48dd869a9b2945d1036d1dc68d"),
+        (Feature::AddGit, "https://github.com/user/repo.git#v1.0"),
+      ]
+    );
+
+    let diagnostic = &incompatible[0].diagnostic;
+    assert_eq!(diagnostic.kind, DiagnosticKind::UnsupportedFeature);
+    assert_eq!(diagnostic.severity, Severity::Warning);
+    assert_eq!(
+      diagnostic.message,
+      "heredoc requires docker/dockerfile:1.4 or later, but the syntax directive declares 1.0"
+    );
+    assert_eq!(d.text(&diagnostic.related_spans[0]), "docker/dockerfile:1.0");
+
+    // 1.5 supports heredocs and --link, but not the newer ADD features
+    let d = parse("# syntax=docker/dockerfile:1.5\n");
+    assert_eq!(
+      d.feature_compatibility().iter().map(|i| i.feature).collect::<Vec<_>>(),
+      vec![Feature::AddChecksum, Feature::AddGit]
+    );
+
+    assert_eq!(parse("# syntax=docker/dockerfile:1.7\n").feature_compatibility(), vec![]);
+    assert_eq!(parse("# syntax=docker/dockerfile:1\n").feature_compatibility(), vec![]);
+    assert_eq!(parse("# syntax=docker/dockerfile:latest\n").feature_compatibility(), vec![]);
+    assert_eq!(parse("# syntax=example.com/custom/frontend:1.0\n").feature_compatibility(), vec![]);
+  }
+
+  #[test]
+  fn test_feature_compatibility_no_directive() {
+    let d = parse("");
+    let incompatible = d.feature_compatibility();
+    assert_eq!(incompatible.len(), 4);
+    assert!(incompatible.iter().all(|i| i.diagnostic.severity == Severity::Info));
+    assert!(incompatible.iter().all(|i| i.diagnostic.related_spans.is_empty()));
+    assert_eq!(
+      incompatible[1].diagnostic.message,
+      "--link requires docker/dockerfile:1.4 or later; add a syntax directive if older \
+      engines must be supported"
+    );
+
+    let d = Dockerfile::parse("FROM alpine:3.19\nRUN --mount=type=cache,target=/root/.cache make\n").unwrap();
+    assert_eq!(d.feature_compatibility()[0].feature, Feature::RunMount);
+  }
+}
@@ -0,0 +1,329 @@
+// (C) Copyright 2026 Hewlett Packard Enterprise Development LP
+
+//! Recognizes image metadata set in stages that don't contribute to the
+//! target image, or that is overridden by later instructions.
+
+use std::collections::HashSet;
+
+use crate::diagnostics::{Diagnostic, DiagnosticKind, Severity};
+use crate::dockerfile_parser::{Dockerfile, Instruction, InstructionKind};
+use crate::options::IneffectiveMetadataOptions;
+use crate::stage::{StageParent, Stages};
+
+/// Instructions that only set metadata of the image being built.
+const METADATA_KEYWORDS: &[&str] = &[
+  "CMD", "ENTRYPOINT", "EXPOSE", "HEALTHCHECK", "LABEL", "STOPSIGNAL",
+];
+
+/// Instructions of which only the last takes effect.
+const OVERRIDDEN_KINDS: &[InstructionKind] = &[
+  InstructionKind::Cmd, InstructionKind::Entrypoint, InstructionKind::Healthcheck,
+];
+
+/// A metadata instruction with no effect on the target image, as returned by
+/// `Dockerfile::ineffective_metadata()`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IneffectiveMetadata<'a> {
+  /// The index of the stage containing the instruction
+  pub stage: usize,
+
+  /// The ineffective instruction
+  pub instruction: &'a Instruction,
+
+  /// A diagnostic describing the problem, spanning the instruction
+  pub diagnostic: Diagnostic,
+}
+
+/// Returns the indices of the given stages and their parent stages.
+fn with_parents(stages: &Stages, targets: impl IntoIterator<Item = usize>) -> HashSet<usize> {
+  let mut effective = HashSet::new();
+
+  for mut index in targets {
+    while effective.insert(index) {
+      match stages.stages.get(index).map(|s| &s.parent) {
+        Some(StageParent::Stage(parent)) => index = *parent,
+        _ => break
+      }
+    }
+  }
+
+  effective
+}
+
+impl Dockerfile {
+  /// Finds metadata instructions, like `CMD` or `EXPOSE`, in stages that
+  /// don't contribute to the image built for `target` (a stage name or
+  /// index), or to the final stage if no target is given. Metadata is only
+  /// kept by the target stage and stages deriving from it via `FROM`, so it
+  /// usually has no effect elsewhere, e.g. in a builder stage.
+  ///
+  /// Aliased stages that no other stage depends on, e.g. a `test` stage, are
+  /// assumed to be targets of other builds and aren't reported; see
+  /// `ineffective_metadata_with_options()`. Returns nothing if the target
+  /// doesn't exist.
+  ///
+  /// ```
+  /// use dockerfile_parser::Dockerfile;
+  ///
+  /// let dockerfile = Dockerfile::parse(r#"
+  ///   FROM golang:1.22 AS build
+  ///   EXPOSE 8080
+  ///   RUN go build -o /app
+  ///
+  ///   FROM alpine:3.19
+  ///   COPY --from=build /app /app
+  ///   CMD ["/app"]
+  /// "#).unwrap();
+  ///
+  /// let ineffective = dockerfile.ineffective_metadata(None);
+  /// assert_eq!(ineffective.len(), 1);
+  /// assert_eq!(ineffective[0].stage, 0);
+  /// ```
+  pub fn ineffective_metadata(&self, target: Option<&str>) -> Vec<IneffectiveMetadata<'_>> {
+    self.ineffective_metadata_with_options(target, &IneffectiveMetadataOptions::default())
+  }
+
+  /// Like `ineffective_metadata()`, with the given options.
+  pub fn ineffective_metadata_with_options(
+    &self,
+    target: Option<&str>,
+    options: &IneffectiveMetadataOptions
+  ) -> Vec<IneffectiveMetadata<'_>> {
+    let stages = self.stages();
+    let target = match target {
+      Some(target) => stages.get(target).map(|s| s.index),
+      None => stages.stages.last().map(|s| s.index)
+    };
+
+    let target = match target {
+      Some(target) => target,
+      None => return Vec::new()
+    };
+
+    let mut targets = vec![target];
+    if options.aliased_targets {
+      let dependencies: HashSet<usize> = (0..stages.stages.len())
+        .flat_map(|index| stages.dependencies(self, index))
+        .map(|dependency| dependency.stage)
+        .collect();
+
+      targets.extend(stages
+        .iter()
+        .filter(|s| s.name.is_some() && !dependencies.contains(&s.index))
+        .map(|s| s.index));
+    }
+
+    let effective = with_parents(&stages, targets);
+
+    let mut ineffective = Vec::new();
+    for ctx in self.iter_with_context() {
+      let stage = match ctx.stage {
+        Some(stage) if !effective.contains(&stage) => stage,
+        _ => continue
+      };
+
+      let keyword = self.text(&ctx.instruction.keyword_span()).to_ascii_uppercase();
+      if !METADATA_KEYWORDS.contains(&keyword.as_str()) {
+        continue;
+      }
+
+      let name = match ctx.stage_name {
+        Some(name) => format!("stage {} ({})", stage, name),
+        None => format!("stage {}", stage)
+      };
+
+      let diagnostic = Diagnostic::new(
+        DiagnosticKind::IneffectiveMetadata,
+        options.severity,
+        ctx.instruction.span(),
+        format!(
+          "{} in {} has no effect on the image built for stage {}, as metadata is only \
+          inherited via FROM",
+          keyword, name, target
+        )
+      ).with_related_span(stages[stage].instructions[0].span());
+
+      ineffective.push(IneffectiveMetadata { stage, instruction: ctx.instruction, diagnostic });
+    }
+
+    ineffective
+  }
+
+  /// Finds `CMD`, `ENTRYPOINT`, and `HEALTHCHECK` instructions overridden by
+  /// a later instruction of the same kind, returning a diagnostic for each
+  /// with a related span pointing at the instruction that takes effect; see
+  /// `Stage::winning()`.
+  ///
+  /// Instructions overridden within their own stage are reported as
+  /// warnings. Those overridden in a stage deriving from theirs via `FROM`
+  /// are reported as `Severity::Info`, as they may still take effect when
+  /// building the parent stage as a target.
+  ///
+  /// ```
+  /// use dockerfile_parser::Dockerfile;
+  ///
+  /// let dockerfile = Dockerfile::parse(r#"
+  ///   FROM alpine:3.19
+  ///   CMD ["sh"]
+  ///   CMD ["/app"]
+  /// "#).unwrap();
+  ///
+  /// let overridden = dockerfile.overridden_metadata();
+  /// assert_eq!(overridden.len(), 1);
+  /// assert_eq!(overridden[0].message, "CMD is overridden by the CMD on line 4");
+  /// ```
+  pub fn overridden_metadata(&self) -> Vec<Diagnostic> {
+    let stages = self.stages();
+    let mut diagnostics = Vec::new();
+
+    for stage in stages.iter() {
+      for kind in OVERRIDDEN_KINDS {
+        let winner = match stage.winning(*kind) {
+          Some(winner) => winner,
+          None => continue
+        };
+
+        let keyword = kind.keyword().unwrap_or_default();
+        let line = winner.span().relative_span(self).0 + 1;
+
+        for ins in &stage.instructions {
+          if ins.kind() == *kind && ins.span() != winner.span() {
+            diagnostics.push(Diagnostic::warning(
+              DiagnosticKind::OverriddenMetadata,
+              ins.span(),
+              format!("{} is overridden by the {} on line {}", keyword, keyword, line)
+            ).with_related_span(winner.span()));
+          }
+        }
+
+        // the nearest parent stage defining one is overridden in this stage
+        let mut parent = &stage.parent;
+        while let StageParent::Stage(index) = parent {
+          let parent_stage = &stages[*index];
+          if let Some(inherited) = parent_stage.winning(*kind) {
+            diagnostics.push(Diagnostic::new(
+              DiagnosticKind::OverriddenMetadata,
+              Severity::Info,
+              inherited.span(),
+              format!(
+                "{} is overridden by the {} on line {} in stage {}",
+                keyword, keyword, line, stage.index
+              )
+            ).with_related_span(winner.span()));
+            break;
+          }
+
+          parent = &parent_stage.parent;
+        }
+      }
+    }
+
+    diagnostics.sort_by_key(|d| d.span);
+    diagnostics
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use indoc::indoc;
+  use pretty_assertions::assert_eq;
+
+  use crate::*;
+  use crate::diagnostics::Severity;
+  use crate::analysis::metadata::IneffectiveMetadata;
+
+  fn texts<'a>(d: &'a Dockerfile, ineffective: &[IneffectiveMetadata<'_>]) -> Vec<&'a str> {
+    ineffective.iter().map(|i| d.text(&i.instruction.span())).collect()
+  }
+
+  #[test]
+  fn test_ineffective_metadata() {
+    let d = Dockerfile::parse(indoc!(r#"
+      FROM golang:1.22 AS builder
+      EXPOSE 8080
+      LABEL stage=builder
+      RUN go build -o /out/app .
+
+      FROM golang:1.22 AS test
+      COPY --from=builder /out/app /app
+      CMD ["go", "test", "./..."]
+
+      FROM alpine:3.19 AS base
+      HEALTHCHECK CMD true
+
+      FROM base
+      COPY --from=builder /out/app /app
+      CMD ["/app"]
+    "#)).unwrap();
+
+    let ineffective = d.ineffective_metadata(None);
+    assert_eq!(texts(&d, &ineffective), vec!["EXPOSE 8080", "LABEL stage=builder"]);
+    assert_eq!(ineffective[0].stage, 0);
+    assert_eq!(ineffective[0].diagnostic.kind, DiagnosticKind::IneffectiveMetadata);
+    assert_eq!(ineffective[0].diagnostic.severity, Severity::Info);
+    assert_eq!(d.text(&ineffective[0].diagnostic.related_spans[0]), "FROM golang:1.22 AS builder");
+    assert!(ineffective[0].diagnostic.message.starts_with("EXPOSE in stage 0 (builder)"));
+
+    // base is the final stage's parent, so its metadata is only ineffective
+    // when targeting another stage
+    assert_eq!(texts(&d, &d.ineffective_metadata(Some("test"))), vec![
+      "EXPOSE 8080", "LABEL stage=builder", "HEALTHCHECK CMD true", "CMD [\"/app\"]",
+    ]);
+    assert_eq!(texts(&d, &d.ineffective_metadata(Some("3"))), vec![
+      "EXPOSE 8080", "LABEL stage=builder",
+    ]);
+    assert_eq!(d.ineffective_metadata(Some("missing")), vec![]);
+
+    // without aliased targets, only the final stage and its parents count
+    let options = IneffectiveMetadataOptions {
+      severity: Severity::Warning,
+      aliased_targets: false,
+    };
+    let ineffective = d.ineffective_metadata_with_options(None, &options);
+    assert_eq!(texts(&d, &ineffective), vec![
+      "EXPOSE 8080", "LABEL stage=builder", "CMD [\"go\", \"test\", \"./...\"]",
+    ]);
+    assert_eq!(ineffective[2].stage, 1);
+    assert_eq!(ineffective[2].diagnostic.severity, Severity::Warning);
+  }
+
+  #[test]
+  fn test_overridden_metadata() {
+    let d = Dockerfile::parse(indoc!(r#"
+      FROM alpine:3.19 AS base
+      ENTRYPOINT ["/entrypoint.sh"]
+      CMD ["sh"]
+      HEALTHCHECK CMD true
+      CMD ["ash"]
+
+      FROM base AS app
+      RUN true
+
+      FROM app
+      CMD ["/app"]
+      HEALTHCHECK NONE
+    "#)).unwrap();
+
+    let overridden = d.overridden_metadata();
+    let summary: Vec<_> = overridden
+      .iter()
+      .map(|o| (d.text(&o.span), d.text(&o.related_spans[0]), o.severity))
+      .collect();
+
+    assert_eq!(summary, vec![
+      ("CMD [\"sh\"]", "CMD [\"ash\"]", Severity::Warning),
+      ("HEALTHCHECK CMD true", "HEALTHCHECK NONE", Severity::Info),
+      ("CMD [\"ash\"]", "CMD [\"/app\"]", Severity::Info),
+    ]);
+
+    assert_eq!(overridden[0].kind, DiagnosticKind::OverriddenMetadata);
+    assert_eq!(overridden[0].message, "CMD is overridden by the CMD on line 5");
+    assert_eq!(overridden[2].message, "CMD is overridden by the CMD on line 11 in stage 2");
+
+    // the summary agrees on the winners
+    let summary = d.target("2").unwrap().summary();
+    assert_eq!(d.text(&summary.cmd.unwrap().span), "CMD [\"/app\"]");
+    assert_eq!(d.text(&summary.entrypoint.unwrap().span), "ENTRYPOINT [\"/entrypoint.sh\"]");
+    assert!(!summary.has_healthcheck());
+  }
+}
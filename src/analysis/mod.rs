@@ -0,0 +1,13 @@
+// (C) Copyright 2026 Hewlett Packard Enterprise Development LP
+
+//! Heuristic analyses of instruction contents, e.g. recognizing the packages
+//! installed by `RUN` commands.
+//!
+//! Unlike the rest of this crate, these analyses interpret shell commands
+//! without emulating a shell, so they may miss commands built dynamically.
+
+mod cache;
+mod platform;
+pub mod features;
+pub mod metadata;
+pub mod packages;
@@ -0,0 +1,421 @@
+// (C) Copyright 2026 Hewlett Packard Enterprise Development LP
+
+//! Recognizes package manager operations in `RUN` instructions, e.g.
+//! `apt-get install -y curl`.
+
+use crate::heredoc::ShellToken;
+use crate::instructions::RunInstruction;
+use crate::splicer::Span;
+use crate::util::SpannedString;
+
+/// A package manager recognized by `RunInstruction::package_operations()`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PackageManager {
+  /// `apt-get` or `apt`
+  Apt,
+
+  /// Alpine's `apk`
+  Apk,
+
+  /// `yum`
+  Yum,
+
+  /// `dnf`
+  Dnf,
+
+  /// `pip`, `pip3`, or `python -m pip`
+  Pip,
+
+  /// `npm`; see `RunInstruction::package_operations()`
+  Npm,
+}
+
+impl PackageManager {
+  /// Finds the package manager invoked by a command name, ignoring its path.
+  fn from_command(command: &str) -> Option<PackageManager> {
+    let name = command.rsplit('/').next().unwrap_or(command);
+
+    match name {
+      "apt-get" | "apt" => Some(PackageManager::Apt),
+      "apk" => Some(PackageManager::Apk),
+      "yum" => Some(PackageManager::Yum),
+      "dnf" | "microdnf" => Some(PackageManager::Dnf),
+      "npm" => Some(PackageManager::Npm),
+      _ if is_versioned(name, "pip") => Some(PackageManager::Pip),
+      _ => None
+    }
+  }
+
+  /// Subcommands that operate on the listed packages.
+  fn subcommands(self) -> &'static [&'static str] {
+    match self {
+      PackageManager::Apt => &["install", "remove", "purge"],
+      PackageManager::Apk => &["add", "del"],
+      PackageManager::Yum | PackageManager::Dnf => &["install", "remove", "erase"],
+      PackageManager::Pip => &["install", "uninstall"],
+      PackageManager::Npm => &["install", "i", "ci", "uninstall"],
+    }
+  }
+
+  /// Flags whose value may be given as a separate word, e.g. `-r file`.
+  fn value_flags(self) -> &'static [&'static str] {
+    match self {
+      PackageManager::Apt => &["-o", "--option", "-t", "--target-release", "-c", "--config-file"],
+      PackageManager::Apk => &[
+        "-X", "--repository", "-t", "--virtual", "-p", "--root", "--arch", "--keys-dir",
+      ],
+      PackageManager::Yum | PackageManager::Dnf => &[
+        "--enablerepo", "--disablerepo", "--setopt", "--installroot", "-c", "--config",
+        "--releasever",
+      ],
+      PackageManager::Pip => &[
+        "-r", "--requirement", "-c", "--constraint", "-i", "--index-url",
+        "--extra-index-url", "-f", "--find-links", "-t", "--target", "--prefix", "--root",
+        "--trusted-host", "--platform", "--python-version",
+      ],
+      PackageManager::Npm => &["--registry", "--prefix"],
+    }
+  }
+}
+
+/// Determines if `name` is `base` optionally followed by a version, e.g.
+/// `pip3.11`.
+fn is_versioned(name: &str, base: &str) -> bool {
+  name.strip_prefix(base)
+    .map(|rest| rest.chars().all(|c| c.is_ascii_digit() || c == '.'))
+    .unwrap_or(false)
+}
+
+/// A package named in a `PackageOperation`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PackageSpec {
+  /// The package spec as written, e.g. `curl=7.88.1-10`
+  pub spec: SpannedString,
+
+  /// The package name, e.g. `curl`
+  pub name: String,
+
+  /// The pinned version or version constraint, if any. Exact pins are given
+  /// without their operator (`curl=7.88` gives `7.88`), while other
+  /// constraints keep it (`flask>=2` gives `>=2`).
+  ///
+  /// Versions aren't recognized for `yum` and `dnf`, whose `name-version`
+  /// syntax is ambiguous.
+  pub version: Option<String>,
+}
+
+impl PackageSpec {
+  fn parse(manager: PackageManager, spec: &SpannedString) -> PackageSpec {
+    let s = spec.content.as_str();
+
+    let (name, rest) = match manager {
+      // scoped packages begin with @, e.g. @angular/cli@17
+      PackageManager::Npm => match s.get(1..).and_then(|t| t.find('@')) {
+        Some(i) => (&s[..i + 1], &s[i + 1..]),
+        None => (s, "")
+      },
+      PackageManager::Yum | PackageManager::Dnf => (s, ""),
+      _ => {
+        let end = s.find(|c| "=<>!~[;@ ".contains(c)).unwrap_or(s.len());
+        (&s[..end], &s[end..])
+      }
+    };
+
+    // skip pip extras, e.g. requests[security]==2.31
+    let rest = match (rest.starts_with('['), rest.find(']')) {
+      (true, Some(end)) => &rest[end + 1..],
+      _ => rest
+    };
+
+    let rest = rest.trim();
+    let version = if rest.is_empty() {
+      None
+    } else if manager == PackageManager::Npm {
+      Some(rest.trim_start_matches('@').to_string())
+    } else if let Some(exact) = rest.strip_prefix("==").or_else(|| rest.strip_prefix('=')) {
+      Some(exact.to_string())
+    } else {
+      Some(rest.to_string())
+    };
+
+    PackageSpec {
+      spec: spec.clone(),
+      name: name.to_string(),
+      version,
+    }
+  }
+}
+
+/// A package manager invocation found by `RunInstruction::package_operations()`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PackageOperation {
+  /// The package manager
+  pub manager: PackageManager,
+
+  /// The command invoking the package manager, e.g. `apt-get`
+  pub command: SpannedString,
+
+  /// The subcommand, e.g. `install`
+  pub subcommand: SpannedString,
+
+  /// The packages operated on, in order
+  pub packages: Vec<PackageSpec>,
+
+  /// All flags given to the command, e.g. `--no-install-recommends`,
+  /// including any value given with `=`
+  pub flags: Vec<SpannedString>,
+}
+
+impl PackageOperation {
+  /// Returns the span of this operation, from its command through its last
+  /// argument.
+  pub fn span(&self) -> Span {
+    let end = self.packages
+      .iter()
+      .map(|p| p.spec.span.end)
+      .chain(self.flags.iter().map(|f| f.span.end))
+      .fold(self.subcommand.span.end, usize::max);
+
+    Span::new(self.command.span.start, end)
+  }
+
+  /// Determines if this operation installs packages, e.g. `apt-get install`
+  /// or `npm ci`, rather than removing them.
+  pub fn is_install(&self) -> bool {
+    matches!(self.subcommand.content.as_str(), "install" | "i" | "ci" | "add")
+  }
+
+  /// Determines if this is a local `npm` operation, which manages a project's
+  /// dependencies rather than packages installed into the image.
+  fn is_local_npm(&self) -> bool {
+    self.manager == PackageManager::Npm && !self.has_flag("-g") && !self.has_flag("--global")
+  }
+
+  /// Determines if the given flag was passed, ignoring any value, e.g.
+  /// `has_flag("--virtual")` matches `--virtual=.build-deps`.
+  pub fn has_flag(&self, flag: &str) -> bool {
+    self.flags.iter().any(|f| f.content.split('=').next() == Some(flag))
+  }
+
+  /// Parses a single simple command, returning `None` if it isn't a
+  /// recognized package operation.
+  fn parse(words: &[&SpannedString]) -> Option<PackageOperation> {
+    // skip variable assignments and sudo, e.g.
+    // `DEBIAN_FRONTEND=noninteractive sudo apt-get install`
+    let mut words = words.iter().copied().skip_while(|w| {
+      let name = w.content.split('=').next().unwrap_or("");
+      w.content == "sudo"
+        || (w.content.contains('=') && !name.is_empty()
+          && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_'))
+    });
+
+    let command = words.next()?;
+    let manager = match PackageManager::from_command(&command.content) {
+      Some(manager) => manager,
+      None if is_versioned(command.content.rsplit('/').next().unwrap_or(""), "python") => {
+        match (words.next(), words.next()) {
+          (Some(m), Some(pip)) if m.content == "-m" && is_versioned(&pip.content, "pip") => {
+            PackageManager::Pip
+          },
+          _ => return None
+        }
+      },
+      None => return None
+    };
+
+    let mut flags = Vec::new();
+    let mut subcommand = None;
+    let mut packages = Vec::new();
+    while let Some(word) = words.next() {
+      if word.content.starts_with('-') && word.content.len() > 1 {
+        flags.push(word.clone());
+        if manager.value_flags().contains(&word.content.as_str()) {
+          words.next();
+        }
+      } else if subcommand.is_none() {
+        if !manager.subcommands().contains(&word.content.as_str()) {
+          return None;
+        }
+
+        subcommand = Some(word.clone());
+      } else {
+        packages.push(PackageSpec::parse(manager, word));
+      }
+    }
+
+    Some(PackageOperation {
+      manager,
+      command: command.clone(),
+      subcommand: subcommand?,
+      packages,
+      flags,
+    })
+  }
+}
+
+impl RunInstruction {
+  /// Finds invocations of common package managers in this instruction, e.g.
+  /// `apt-get install`, `apk add`, `yum` or `dnf install`, `pip install`, and
+  /// `npm install -g`.
+  ///
+  /// Commands are recognized heuristically: each simple command (separated by
+  /// `&&`, `;`, `|`, etc.) is examined on its own, and unrecognized commands
+  /// are ignored. No shell emulation is performed, so e.g. variable references
+  /// in package names are reported as written.
+  ///
+  /// ```
+  /// use dockerfile_parser::Dockerfile;
+  /// use dockerfile_parser::analysis::packages::PackageManager;
+  ///
+  /// let dockerfile = Dockerfile::parse(
+  ///   "RUN apt-get update && apt-get install -y --no-install-recommends curl=7.88.1-10"
+  /// ).unwrap();
+  ///
+  /// let run = dockerfile.instructions[0].as_run().unwrap();
+  /// let ops = run.package_operations();
+  /// assert_eq!(ops.len(), 1);
+  /// assert_eq!(ops[0].manager, PackageManager::Apt);
+  /// assert!(ops[0].has_flag("--no-install-recommends"));
+  /// assert_eq!(ops[0].packages[0].name, "curl");
+  /// assert_eq!(ops[0].packages[0].version.as_deref(), Some("7.88.1-10"));
+  /// ```
+  ///
+  /// Local `npm` operations manage a project's dependencies rather than
+  /// packages, and are only included by `dependency_installs()`.
+  pub fn package_operations(&self) -> Vec<PackageOperation> {
+    self.find_package_operations()
+      .into_iter()
+      .filter(|op| !op.is_local_npm())
+      .collect()
+  }
+
+  /// Finds all package installs in this instruction, like
+  /// `package_operations()`, but including local `npm` installs of a
+  /// project's dependencies, e.g. `npm ci`.
+  pub fn dependency_installs(&self) -> Vec<PackageOperation> {
+    self.find_package_operations()
+      .into_iter()
+      .filter(|op| op.is_install())
+      .collect()
+  }
+
+  fn find_package_operations(&self) -> Vec<PackageOperation> {
+    let tokens = match self.as_exec() {
+      Some(exec) => exec.elements.iter().cloned().map(ShellToken::Word).collect(),
+      None => self.shell_tokens()
+    };
+
+    tokens
+      .split(|t| t.is_separator())
+      .filter_map(|command| {
+        // drop redirections and their targets, e.g. `> /dev/null`
+        let mut words = Vec::new();
+        let mut redirect = false;
+        for token in command {
+          match token {
+            ShellToken::Word(word) if !redirect => words.push(word),
+            ShellToken::Word(_) => redirect = false,
+            ShellToken::Operator(op) => redirect = op.content.contains(&['<', '>'][..]),
+            ShellToken::Heredoc(..) => ()
+          }
+        }
+
+        PackageOperation::parse(&words)
+      })
+      .collect()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use indoc::indoc;
+  use pretty_assertions::assert_eq;
+
+  use super::*;
+  use crate::Dockerfile;
+
+  type Summary<'a> = (PackageManager, &'a str, Vec<(&'a str, Option<&'a str>)>);
+
+  /// Summarizes each operation as (manager, subcommand, [(name, version)]).
+  fn summarize(ops: &[PackageOperation]) -> Vec<Summary<'_>> {
+    ops.iter()
+      .map(|op| (
+        op.manager,
+        op.subcommand.content.as_str(),
+        op.packages.iter().map(|p| (p.name.as_str(), p.version.as_deref())).collect()
+      ))
+      .collect()
+  }
+
+  #[test]
+  fn test_package_operations() {
+    let d = Dockerfile::parse(indoc!(r#"
+      FROM debian:bookworm
+      RUN apt-get update && \
+          DEBIAN_FRONTEND=noninteractive apt-get install -y --no-install-recommends \
+            # pinned for reproducibility
+            curl=7.88.1-10 \
+            ca-certificates \
+          && rm -rf /var/lib/apt/lists/*
+      RUN apk add --no-cache --virtual .build-deps gcc musl-dev~1.2 > /dev/null \
+        && python3 -m pip install --no-cache-dir -r requirements.txt "requests[security]==2.31.0" 'flask>=2'
+      RUN npm install -g typescript@5.3.3 @angular/cli@17 && npm install express
+      RUN sudo dnf -y install git && yum --enablerepo epel install -y jq; pip3 uninstall -y six
+      RUN ["apt-get", "remove", "-y", "vim"]
+      RUN make install && apt-get upgrade -y
+    "#)).unwrap();
+
+    let ops = |i: usize| d.instructions[i].as_run().unwrap().package_operations();
+
+    let apt = ops(1);
+    assert_eq!(
+      summarize(&apt),
+      vec![(PackageManager::Apt, "install", vec![("curl", Some("7.88.1-10")), ("ca-certificates", None)])]
+    );
+    assert!(apt[0].has_flag("-y"));
+    assert!(apt[0].has_flag("--no-install-recommends"));
+    assert!(!apt[0].has_flag("--no-cache"));
+    assert_eq!(d.text(&apt[0].packages[0].spec.span), "curl=7.88.1-10");
+    assert_eq!(d.text(&apt[0].command.span), "apt-get");
+
+    let apk_pip = ops(2);
+    assert_eq!(
+      summarize(&apk_pip),
+      vec![
+        (PackageManager::Apk, "add", vec![("gcc", None), ("musl-dev", Some("~1.2"))]),
+        (PackageManager::Pip, "install", vec![("requests", Some("2.31.0")), ("flask", Some(">=2"))]),
+      ]
+    );
+    assert!(apk_pip[0].has_flag("--no-cache"));
+    assert_eq!(d.text(&apk_pip[1].command.span), "python3");
+    assert_eq!(d.text(&apk_pip[1].packages[0].spec.span), "\"requests[security]==2.31.0\"");
+
+    assert_eq!(
+      summarize(&ops(3)),
+      vec![(
+        PackageManager::Npm, "install",
+        vec![("typescript", Some("5.3.3")), ("@angular/cli", Some("17"))]
+      )]
+    );
+
+    assert_eq!(
+      summarize(&ops(4)),
+      vec![
+        (PackageManager::Dnf, "install", vec![("git", None)]),
+        (PackageManager::Yum, "install", vec![("jq", None)]),
+        (PackageManager::Pip, "uninstall", vec![("six", None)]),
+      ]
+    );
+
+    assert_eq!(summarize(&ops(5)), vec![(PackageManager::Apt, "remove", vec![("vim", None)])]);
+    assert_eq!(ops(6), vec![]);
+
+    let installs = |i: usize| d.instructions[i].as_run().unwrap().dependency_installs();
+    assert_eq!(
+      installs(3).iter().map(|op| d.text(&op.span())).collect::<Vec<_>>(),
+      vec!["npm install -g typescript@5.3.3 @angular/cli@17", "npm install express"]
+    );
+    assert_eq!(installs(4).len(), 2);
+    assert_eq!(installs(5), vec![]);
+  }
+}
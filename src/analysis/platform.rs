@@ -0,0 +1,187 @@
+// (C) Copyright 2026 Hewlett Packard Enterprise Development LP
+
+//! Recognizes files copied between stages built for different platforms.
+
+use crate::diagnostics::{Diagnostic, DiagnosticKind};
+use crate::dockerfile_parser::{Dockerfile, Instruction};
+use crate::platform::PlatformArgs;
+use crate::stage::{Stage, Stages};
+
+/// Determines if a stage declares any of the automatic `TARGET*` args, i.e.
+/// if it appears to cross-compile for the target platform.
+fn uses_target_args(stage: &Stage) -> bool {
+  stage.instructions.iter().any(|ins| match ins {
+    Instruction::Arg(arg) => arg.name.content.starts_with("TARGET"),
+    _ => false
+  })
+}
+
+impl<'a> Stages<'a> {
+  /// Finds `COPY --from` instructions copying from a stage whose effective
+  /// platform (see `Stage::effective_platform()`) is known to differ from
+  /// that of the copying stage, e.g. a binary built natively on the build
+  /// platform and copied into an image for another target platform.
+  ///
+  /// Source stages declaring one of the automatic `TARGET*` args, e.g.
+  /// `ARG TARGETARCH`, are assumed to cross-compile for the target platform
+  /// and are ignored. Stages whose platforms can't be resolved, e.g. a
+  /// variable platform (see `StagePlatform::Variable`) without platform args,
+  /// are assumed to be compatible.
+  /// Diagnostics have related spans for the `FROM` of the source stage and
+  /// of the copying stage.
+  ///
+  /// ```
+  /// use dockerfile_parser::Dockerfile;
+  ///
+  /// let dockerfile = Dockerfile::parse(r#"
+  ///   FROM --platform=linux/amd64 golang:1.22 AS build
+  ///   RUN go build -o /app
+  ///
+  ///   FROM --platform=linux/arm64 alpine:3.19
+  ///   COPY --from=build /app /app
+  /// "#).unwrap();
+  ///
+  /// let diagnostics = dockerfile.stages().cross_platform_copies(&dockerfile, None);
+  /// assert_eq!(diagnostics.len(), 1);
+  /// assert_eq!(diagnostics[0].related_spans.len(), 2);
+  /// ```
+  pub fn cross_platform_copies(
+    &self,
+    dockerfile: &Dockerfile,
+    platform_args: Option<&PlatformArgs>
+  ) -> Vec<Diagnostic> {
+    let platforms: Vec<_> = self.stages
+      .iter()
+      .map(|stage| stage.effective_platform(dockerfile, platform_args))
+      .collect();
+
+    let mut diagnostics = Vec::new();
+    for ctx in dockerfile.iter_with_context() {
+      let (stage, flag) = match (ctx.stage, ctx.instruction.as_copy().and_then(|c| c.from_flag())) {
+        (Some(stage), Some(flag)) => (&self.stages[stage], flag),
+        _ => continue
+      };
+
+      let source = match self.resolve_reference(dockerfile, &flag.value.content) {
+        Some(source) if source != stage.index => &self.stages[source],
+        _ => continue
+      };
+
+      if uses_target_args(source) {
+        continue;
+      }
+
+      let (from, to) = (&platforms[source.index], &platforms[stage.index]);
+      match (from.platform(), to.platform()) {
+        (Some(a), Some(b)) if a != b => (),
+        _ => continue
+      }
+
+      let mut diagnostic = Diagnostic::warning(
+        DiagnosticKind::CrossPlatformCopy,
+        flag.span,
+        format!(
+          "COPY --from={} copies from stage {} built for {} into stage {} built for {}; \
+          copied binaries may not run on {}",
+          flag.value.content, source.index, from, stage.index, to, to
+        )
+      );
+      diagnostic.related_spans = vec![
+        source.instructions[0].span(),
+        stage.instructions[0].span(),
+      ];
+
+      diagnostics.push(diagnostic);
+    }
+
+    diagnostics
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use indoc::indoc;
+  use pretty_assertions::assert_eq;
+
+  use crate::*;
+
+  #[test]
+  fn test_cross_platform_copies() {
+    let d = Dockerfile::parse(indoc!(r#"
+      ARG GO_VERSION=1.22
+      FROM --platform=$BUILDPLATFORM golang:${GO_VERSION} AS build
+      ARG TARGETOS
+      ARG TARGETARCH
+      RUN GOOS=$TARGETOS GOARCH=$TARGETARCH go build -o /out/app .
+
+      FROM --platform=$BUILDPLATFORM golang:${GO_VERSION} AS tools
+      RUN go build -o /out/migrate ./cmd/migrate
+
+      FROM build AS test
+      RUN go test ./...
+
+      FROM alpine:3.19
+      COPY --from=build /out/app /app
+      COPY --from=tools /out/migrate /migrate
+    "#)).unwrap();
+
+    let stages = d.stages();
+    let cross = PlatformArgs::new(
+      Platform::parse("linux/arm64").unwrap(),
+      Platform::parse("linux/amd64").unwrap(),
+    );
+
+    let diagnostics = stages.cross_platform_copies(&d, Some(&cross));
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0].kind, DiagnosticKind::CrossPlatformCopy);
+    assert_eq!(d.text(&diagnostics[0].span), "--from=tools");
+    assert_eq!(
+      diagnostics[0].related_spans.iter().map(|s| d.text(s)).collect::<Vec<_>>(),
+      vec![
+        "FROM --platform=$BUILDPLATFORM golang:${GO_VERSION} AS tools",
+        "FROM alpine:3.19",
+      ]
+    );
+    assert!(diagnostics[0].message.contains("stage 1 built for linux/amd64"));
+    assert!(diagnostics[0].message.contains("stage 3 built for linux/arm64"));
+
+    // native builds and unknown platforms are fine
+    let native = PlatformArgs::new(
+      Platform::parse("linux/amd64").unwrap(),
+      Platform::parse("linux/amd64").unwrap(),
+    );
+    assert_eq!(stages.cross_platform_copies(&d, Some(&native)), vec![]);
+    assert_eq!(stages.cross_platform_copies(&d, None), vec![]);
+  }
+
+  #[test]
+  fn test_cross_platform_copies_two_stages() {
+    let copies = |source: &str, target: &str, args: Option<&PlatformArgs>| -> usize {
+      let d = Dockerfile::parse(&format!(
+        "FROM {} golang:1.22 AS build\nFROM {} alpine:3.19\nCOPY --from=build /app /app\n",
+        source, target
+      )).unwrap();
+
+      d.stages().cross_platform_copies(&d, args).len()
+    };
+
+    let args = PlatformArgs::new(
+      Platform::parse("linux/arm64").unwrap(),
+      Platform::parse("linux/amd64").unwrap(),
+    );
+
+    // explicit platforms
+    assert_eq!(copies("--platform=linux/amd64", "--platform=linux/arm64", None), 1);
+    assert_eq!(copies("--platform=linux/amd64", "--platform=linux/amd64", None), 0);
+
+    // variable platforms are compatible unless resolved by platform args
+    assert_eq!(copies("--platform=$BUILDPLATFORM", "--platform=linux/arm64", None), 0);
+    assert_eq!(copies("--platform=$BUILDPLATFORM", "--platform=linux/arm64", Some(&args)), 1);
+    assert_eq!(copies("--platform=$TARGETPLATFORM", "--platform=linux/arm64", Some(&args)), 0);
+
+    // absent platforms are the target platform, if known
+    assert_eq!(copies("--platform=linux/amd64", "", None), 0);
+    assert_eq!(copies("--platform=linux/amd64", "", Some(&args)), 1);
+    assert_eq!(copies("", "", Some(&args)), 0);
+  }
+}
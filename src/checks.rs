@@ -0,0 +1,842 @@
+// (C) Copyright 2026 Hewlett Packard Enterprise Development LP
+
+use pest::Parser;
+
+use crate::diagnostics::{Diagnostic, DiagnosticKind};
+use crate::dockerfile_parser::{is_instruction_keyword, Dockerfile, Instruction};
+use crate::fixes::Fix;
+use crate::options::TargetOs;
+use crate::parser::{DockerfileParser, Rule};
+use crate::splicer::Span;
+use crate::stage::StageParent;
+use crate::util::{
+  BreakableString, BreakableStringComponent, PairSeparator, QuoteStyle, ShellOrExecExpr,
+  SpannedString
+};
+use crate::validate::is_absolute_path;
+
+/// A named check for problems or policy violations that don't necessarily
+/// break a build, similar to BuildKit's [build checks].
+///
+/// All checks may be run at once with `Dockerfile::run_checks()`.
+///
+/// [build checks]: https://docs.docker.com/reference/build-checks/
+pub struct Check {
+  /// The name of the check, e.g. `UnpinnedSyntaxDirective`
+  pub name: &'static str,
+
+  /// A short description of what the check looks for
+  pub description: &'static str,
+
+  run: fn(&Dockerfile, &mut Vec<Diagnostic>),
+}
+
+impl Check {
+  /// Runs this check against the given Dockerfile.
+  pub fn run(&self, dockerfile: &Dockerfile) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+    (self.run)(&dockerfile.local(), &mut diagnostics);
+
+    dockerfile.rebase(diagnostics)
+  }
+}
+
+/// All available checks.
+pub const CHECKS: &[Check] = &[
+  Check {
+    name: "UnpinnedSyntaxDirective",
+    description: "the `# syntax` directive image is not pinned to a digest",
+    run: check_unpinned_syntax_directive,
+  },
+  Check {
+    name: "EmbeddedInstruction",
+    description: "a shell command contains what looks like another instruction, e.g. `RUN a; RUN b` \
+      (lowercase `env`, `cmd`, `copy`, `user`, and `label` are taken to be commands)",
+    run: check_embedded_instruction,
+  },
+  LEGACY_PAIR_SYNTAX,
+  Check {
+    name: "BuildArgAtRuntime",
+    description: "a `CMD`, `ENTRYPOINT`, or `HEALTHCHECK` references a variable only defined by an `ARG`",
+    run: check_build_arg_at_runtime,
+  },
+  Check {
+    name: "SingleQuotedExecArray",
+    description: "a `RUN`, `CMD`, or `ENTRYPOINT` array uses single quotes, making it a shell-form command",
+    run: check_single_quoted_exec_array,
+  },
+  Check {
+    name: "MaintainerDeprecated",
+    description: "the deprecated `MAINTAINER` instruction is used instead of `LABEL maintainer=...`",
+    run: check_maintainer_deprecated,
+  },
+  Check {
+    name: "WorkdirRelativePath",
+    description: "a relative `WORKDIR` depends on the base image's working directory",
+    run: check_workdir_relative_path,
+  },
+];
+
+pub(crate) const LEGACY_PAIR_SYNTAX: Check = Check {
+  name: "LegacyPairSyntax",
+  description: "a `LABEL` or `ENV` pair uses the legacy `key value` syntax instead of `key=value`",
+  run: check_legacy_pair_syntax,
+};
+
+pub(crate) fn run_checks(dockerfile: &Dockerfile) -> Vec<Diagnostic> {
+  CHECKS.iter().flat_map(|check| check.run(dockerfile)).collect()
+}
+
+fn check_unpinned_syntax_directive(dockerfile: &Dockerfile, diagnostics: &mut Vec<Diagnostic>) {
+  let directive = match dockerfile.syntax_directive() {
+    Some(directive) => directive,
+    None => return
+  };
+
+  if matches!(directive.syntax_image(), Some(image) if image.hash.is_none()) {
+    diagnostics.push(Diagnostic::warning(
+      DiagnosticKind::UnpinnedSyntaxDirective,
+      directive.value.span,
+      format!(
+        "syntax directive image '{}' is not pinned to a digest",
+        directive.value.content
+      )
+    ));
+  }
+}
+
+/// Returns the span of the first token in `string` starting at `pos`, or `None`
+/// if the rest of the string is blank.
+fn next_token(string: &SpannedString, pos: usize) -> Option<Span> {
+  let rest = &string.content[pos..];
+  let start = string.content.len() - rest.trim_start().len();
+  let token = &string.content[start..];
+  let len = token.find(char::is_whitespace).unwrap_or(token.len());
+
+  if len == 0 {
+    None
+  } else {
+    let start = string.span.start + start;
+    Some(Span::new(start, start + len))
+  }
+}
+
+/// Instruction keywords that are also the names of common commands, e.g. the
+/// `env` utility and Windows' `cmd` and `copy`. These aren't reported when
+/// written in lowercase, as commands are.
+const COMMAND_KEYWORDS: &[&str] = &["env", "cmd", "copy", "user", "label"];
+
+/// Finds instruction keywords at the start of a shell statement, i.e. after a
+/// `;`, `&&`, or `||` outside of quotes, possibly on a continued line.
+///
+/// Keywords are matched case-insensitively, like instructions themselves. As a
+/// deliberate heuristic against false positives, the keywords in
+/// `COMMAND_KEYWORDS` are exempt when written entirely in lowercase, since e.g.
+/// `&& env FOO=bar make` is far more likely a command than a misplaced `ENV`;
+/// any other spelling (`ENV`, `Env`) is still reported.
+fn find_embedded_keywords(content: &str, s: &BreakableString) -> Vec<Span> {
+  let mut spans = Vec::new();
+  let mut quote = None;
+  let mut pending = false;
+
+  for component in s.iter_components() {
+    let string = match component {
+      BreakableStringComponent::String(string) => string,
+      BreakableStringComponent::Comment(_) => continue
+    };
+
+    let mut boundaries = Vec::new();
+    if pending {
+      boundaries.push(0);
+    }
+
+    let mut chars = string.content.char_indices().peekable();
+    while let Some((i, c)) = chars.next() {
+      match (quote, c) {
+        (Some(q), _) if c == q => quote = None,
+        (Some(_), _) => (),
+        (None, '\'') | (None, '"') => quote = Some(c),
+        (None, '\\') => { chars.next(); },
+        (None, ';') => boundaries.push(i + 1),
+        (None, '&') | (None, '|') => {
+          if let Some(&(j, next)) = chars.peek() {
+            if next == c {
+              chars.next();
+              boundaries.push(j + 1);
+            }
+          }
+        },
+        _ => ()
+      }
+    }
+
+    pending = false;
+    for pos in boundaries {
+      let span = match next_token(string, pos) {
+        Some(span) => span,
+        None => {
+          pending = true;
+          continue
+        }
+      };
+
+      let token = &content[span.start..span.end];
+      if !is_instruction_keyword(token) {
+        continue;
+      }
+
+      if !COMMAND_KEYWORDS.contains(&token) {
+        spans.push(span);
+      }
+    }
+  }
+
+  spans
+}
+
+fn check_embedded_instruction(dockerfile: &Dockerfile, diagnostics: &mut Vec<Diagnostic>) {
+  for ins in &dockerfile.instructions {
+    let (keyword, expr) = match ins {
+      Instruction::Run(run) => ("RUN", &run.expr),
+      Instruction::Cmd(cmd) => ("CMD", &cmd.expr),
+      Instruction::Entrypoint(entrypoint) => ("ENTRYPOINT", &entrypoint.expr),
+      _ => continue
+    };
+
+    let shell = match expr {
+      ShellOrExecExpr::Shell(shell) => shell,
+      ShellOrExecExpr::Exec(_) => continue
+    };
+
+    for span in find_embedded_keywords(&dockerfile.content, shell) {
+      diagnostics.push(Diagnostic::warning(
+        DiagnosticKind::EmbeddedInstruction,
+        span,
+        format!(
+          "{} command contains '{}' after a command separator: did you mean to start a new instruction?",
+          keyword,
+          &dockerfile.content[span.start..span.end]
+        )
+      ));
+    }
+  }
+}
+
+/// Checks for references in commands run by the container to variables that
+/// are only defined by an `ARG`. Build args aren't persisted into the image, so
+/// these will be empty at runtime unless re-exported with e.g. `ENV X=$X`.
+///
+/// References in exec arrays are included, as they're often passed to a shell,
+/// e.g. `CMD ["sh", "-c", "echo $X"]`.
+fn check_build_arg_at_runtime(dockerfile: &Dockerfile, diagnostics: &mut Vec<Diagnostic>) {
+  for stage in dockerfile.iter_stages() {
+    for (index, ins) in stage.instructions.iter().enumerate() {
+      let keyword = match ins {
+        Instruction::Cmd(_) => "CMD",
+        Instruction::Entrypoint(_) => "ENTRYPOINT",
+        Instruction::Healthcheck(_) => "HEALTHCHECK",
+        _ => continue
+      };
+
+      let refs = ins.all_variable_references();
+      if refs.is_empty() {
+        continue;
+      }
+
+      // dynamic keys could define anything
+      let env = stage.environment_at(dockerfile, index);
+      if !env.unresolved.is_empty() {
+        continue;
+      }
+
+      for r in refs {
+        let is_arg = matches!(stage.arg_index(&r.name), Some(i) if i < index);
+        if !is_arg || env.vars.contains_key(&r.name) {
+          continue;
+        }
+
+        diagnostics.push(Diagnostic::warning(
+          DiagnosticKind::BuildArgAtRuntime,
+          r.span,
+          format!(
+            "{} references '{}', which is only defined by an ARG and won't be set when the \
+            container runs; use ENV to persist it",
+            keyword, r.name
+          )
+        ));
+      }
+    }
+  }
+}
+
+/// Produces a fix rewriting a legacy `key value` pair as `key=value`, given
+/// its separator and, if unquoted, its value (which may need quoting, or joining
+/// onto one line if it contains line continuations).
+///
+/// Returns `None` if the separator or value contains comments, which would be
+/// lost.
+fn legacy_pair_fix(
+  dockerfile: &Dockerfile,
+  separator: Span,
+  value: Option<&BreakableString>
+) -> Option<Fix> {
+  if dockerfile.text(&separator).contains('#') {
+    return None;
+  }
+
+  let value = match value {
+    Some(value) => value,
+    None => return Some(Fix::new(dockerfile, separator, "="))
+  };
+
+  if value.iter_components().any(|c| matches!(c, BreakableStringComponent::Comment(_))) {
+    return None;
+  }
+
+  // a line continuation can't follow `=`, so continued values are rewritten
+  // (and quoted if needed) on a single line
+  let continued = value.iter_components().count() > 1;
+
+  let text = value.to_string();
+  let quoted = QuoteStyle::Unquoted.quote(&text);
+  if quoted == text && !continued {
+    Some(Fix::new(dockerfile, separator, "="))
+  } else {
+    Some(Fix::new(dockerfile, Span::new(separator.start, value.span.end), format!("={}", quoted)))
+  }
+}
+
+fn legacy_pair_diagnostic(keyword: &str, span: Span, fix: Option<Fix>) -> Diagnostic {
+  let diagnostic = Diagnostic::warning(
+    DiagnosticKind::LegacyPairSyntax,
+    span,
+    format!(
+      "\"{} key=value\" should be used instead of the legacy \"{} key value\" syntax",
+      keyword, keyword
+    )
+  );
+
+  match fix {
+    Some(fix) => diagnostic.with_suggested_fix(fix),
+    None => diagnostic
+  }
+}
+
+fn check_legacy_pair_syntax(dockerfile: &Dockerfile, diagnostics: &mut Vec<Diagnostic>) {
+  for ins in &dockerfile.instructions {
+    match ins {
+      Instruction::Label(label) => {
+        for l in &label.labels {
+          if let PairSeparator::Whitespace(separator) = l.separator {
+            diagnostics.push(legacy_pair_diagnostic(
+              "LABEL",
+              Span::new(l.name.span.start, l.value.span.end),
+              legacy_pair_fix(dockerfile, separator, None)
+            ));
+          }
+        }
+      },
+      Instruction::Env(env) => {
+        for var in &env.vars {
+          if let PairSeparator::Whitespace(separator) = var.separator {
+            // quoted values can be kept as-is
+            let value = match var.quote_style {
+              QuoteStyle::Unquoted => Some(&var.value),
+              _ => None
+            };
+
+            diagnostics.push(legacy_pair_diagnostic(
+              "ENV",
+              var.span,
+              legacy_pair_fix(dockerfile, separator, value)
+            ));
+          }
+        }
+      },
+      _ => ()
+    }
+  }
+}
+
+/// Converts the single-quoted strings in a bracketed shell-form command to
+/// double-quoted strings, returning the result if it is then a valid exec-form
+/// array. Returns `None` if there are no single-quoted strings.
+fn requote_exec_array(text: &str) -> Option<String> {
+  let mut result = String::new();
+  let mut requoted = false;
+  let mut chars = text.chars();
+
+  while let Some(c) = chars.next() {
+    match c {
+      '\'' => {
+        let mut content = String::new();
+        loop {
+          match chars.next()? {
+            '\'' => break,
+            c => content.push(c)
+          }
+        }
+
+        result.push_str(&QuoteStyle::Double.quote(&content));
+        requoted = true;
+      },
+      '"' => {
+        result.push(c);
+        loop {
+          let c = chars.next()?;
+          result.push(c);
+          match c {
+            '\\' => result.push(chars.next()?),
+            '"' => break,
+            _ => ()
+          }
+        }
+      },
+      c => result.push(c)
+    }
+  }
+
+  if !requoted {
+    return None;
+  }
+
+  let array = DockerfileParser::parse(Rule::cmd_exec, &result).ok()?.next()?;
+  if array.as_span().end() == result.len() {
+    Some(result)
+  } else {
+    None
+  }
+}
+
+/// Checks for shell-form commands written as an array of single-quoted
+/// strings, e.g. `CMD ['echo', 'hi']`. These aren't valid JSON, so Docker
+/// runs the whole array text in a shell.
+fn check_single_quoted_exec_array(dockerfile: &Dockerfile, diagnostics: &mut Vec<Diagnostic>) {
+  for ins in &dockerfile.instructions {
+    let (keyword, expr) = match ins {
+      Instruction::Run(run) if run.heredocs.is_empty() => ("RUN", &run.expr),
+      Instruction::Cmd(cmd) => ("CMD", &cmd.expr),
+      Instruction::Entrypoint(entrypoint) => ("ENTRYPOINT", &entrypoint.expr),
+      _ => continue
+    };
+
+    let shell = match expr {
+      ShellOrExecExpr::Shell(shell) => shell,
+      ShellOrExecExpr::Exec(_) => continue
+    };
+
+    let text = dockerfile.text(&shell.span);
+    let start = shell.span.start + text.len() - text.trim_start().len();
+    let span = Span::new(start, shell.span.start + text.trim_end().len());
+    let text = dockerfile.text(&span);
+    if !text.starts_with('[') || !text.ends_with(']') {
+      continue;
+    }
+
+    let requoted = match requote_exec_array(text) {
+      Some(requoted) => requoted,
+      None => continue
+    };
+
+    diagnostics.push(Diagnostic::warning(
+      DiagnosticKind::SingleQuotedExecArray,
+      span,
+      format!(
+        "single quotes in JSON array make this {} a shell-form string; use double quotes to \
+        run it directly",
+        keyword
+      )
+    ).with_suggested_fix(Fix::new(dockerfile, span, requoted)));
+  }
+}
+
+/// Checks for deprecated `MAINTAINER` instructions, suggesting an equivalent
+/// `LABEL maintainer=...` instead.
+fn check_maintainer_deprecated(dockerfile: &Dockerfile, diagnostics: &mut Vec<Diagnostic>) {
+  for maintainer in dockerfile.instructions.iter().filter_map(|ins| ins.as_maintainer()) {
+    diagnostics.push(Diagnostic::warning(
+      DiagnosticKind::MaintainerDeprecated,
+      maintainer.span,
+      "MAINTAINER is deprecated; use LABEL maintainer=... instead"
+    ).with_suggested_fix(Fix::new(dockerfile, maintainer.span, maintainer.to_label())));
+  }
+}
+
+/// Checks for relative `WORKDIR` paths in stages where no absolute `WORKDIR`
+/// was set before, either in the stage or the parent stages it derives from,
+/// so the resulting directory depends on the base image's default. As in
+/// BuildKit, only the first such `WORKDIR` of a stage is reported.
+///
+/// `WORKDIR` paths starting with a variable reference, e.g. `$HOME/src`, can't
+/// be classified; they are skipped, along with any relative paths following
+/// them in the stage and stages deriving from it.
+fn check_workdir_relative_path(dockerfile: &Dockerfile, diagnostics: &mut Vec<Diagnostic>) {
+  let stages = dockerfile.stages();
+
+  // whether each stage's working directory is known not to depend on its
+  // base image by the end of the stage
+  let mut independent: Vec<bool> = Vec::with_capacity(stages.stages.len());
+
+  for stage in stages.iter() {
+    let mut root = stage;
+    while let StageParent::Stage(parent) = root.parent {
+      match stages.stages.get(parent) {
+        Some(parent) if parent.index < root.index => root = parent,
+        _ => break
+      }
+    }
+
+    let mut known = match stage.parent {
+      StageParent::Stage(parent) => independent.get(parent).copied().unwrap_or(false),
+      _ => false
+    };
+
+    for workdir in stage.instructions.iter().filter_map(|ins| ins.as_workdir()) {
+      let path = &workdir.path.content;
+
+      // checks don't know the target OS, so accept paths absolute on
+      // Windows, which include all paths absolute on Linux
+      if known || path.starts_with('$') || is_absolute_path(path, TargetOs::Windows) {
+        known = true;
+        continue;
+      }
+
+      let mut diagnostic = Diagnostic::warning(
+        DiagnosticKind::WorkdirRelativePath,
+        workdir.path.span,
+        format!(
+          "relative WORKDIR '{}' depends on the working directory of base image '{}'; set an \
+          absolute WORKDIR first",
+          path, root.root
+        )
+      );
+      if let Some(from) = root.instructions.first().and_then(|ins| ins.as_from()) {
+        diagnostic = diagnostic.with_related_span(from.image.span);
+      }
+
+      diagnostics.push(diagnostic);
+      known = true;
+    }
+
+    independent.push(known);
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use indoc::indoc;
+  use pretty_assertions::assert_eq;
+
+  use crate::*;
+
+  #[test]
+  fn test_unpinned_syntax_directive() {
+    let d = Dockerfile::parse("# syntax=docker/dockerfile:1\nFROM alpine\n").unwrap();
+    let diagnostics = d.run_checks();
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0].kind, DiagnosticKind::UnpinnedSyntaxDirective);
+    assert_eq!(diagnostics[0].severity, Severity::Warning);
+    assert_eq!(diagnostics[0].span, Span::new(9, 28));
+
+    let d = Dockerfile::parse(&format!(
+      "# syntax=docker/dockerfile:1@sha256:{}\nFROM alpine\n",
+      "a".repeat(64)
+    )).unwrap();
+    assert_eq!(d.run_checks(), vec![]);
+
+    let d = Dockerfile::parse("FROM alpine\n").unwrap();
+    assert_eq!(d.run_checks(), vec![]);
+  }
+
+  #[test]
+  fn test_embedded_instruction() {
+    let d = Dockerfile::parse(indoc!(r#"
+      FROM alpine
+      RUN apt update; RUN apt install -y curl
+      RUN make && \
+        COPY foo bar
+      CMD echo "a; RUN b" || cmd /c dir && copy a b
+      ENTRYPOINT ["sh", "-c", "true; RUN"]
+      RUN cd /src && env FOO=bar make; echo ; done
+      RUN id -u app || user add app; label foo; make; run tests
+      RUN make; Env FOO=bar; Label foo; eNV x
+    "#)).unwrap();
+
+    let diagnostics: Vec<_> = d.run_checks()
+      .into_iter()
+      .filter(|d| d.kind == DiagnosticKind::EmbeddedInstruction)
+      .collect();
+
+    // lowercase command names (env, cmd, copy, user, label) are exempt, but
+    // other spellings of those keywords are reported
+    assert_eq!(diagnostics.len(), 6);
+    assert!(diagnostics.iter().all(|diag| diag.severity == Severity::Warning));
+    assert_eq!(
+      diagnostics.iter().map(|diag| &d.content[diag.span.start..diag.span.end]).collect::<Vec<_>>(),
+      vec!["RUN", "COPY", "run", "Env", "Label", "eNV"]
+    );
+    assert_eq!(
+      diagnostics[0].message,
+      "RUN command contains 'RUN' after a command separator: did you mean to start a new instruction?"
+    );
+  }
+
+  #[test]
+  fn test_legacy_pair_syntax() {
+    let d = Dockerfile::parse(indoc!(r#"
+      FROM alpine
+      LABEL maintainer someone
+      LABEL "description" "a \"quoted\" label"
+      LABEL a=b
+      ENV PATH /usr/local/bin:$PATH
+      ENV GREETING hello world
+      ENV QUOTED "a b"
+      ENV C=d
+    "#)).unwrap();
+
+    let diagnostics: Vec<_> = d.run_checks()
+      .into_iter()
+      .filter(|d| d.kind == DiagnosticKind::LegacyPairSyntax)
+      .collect();
+
+    assert_eq!(
+      diagnostics.iter().map(|diag| &d.content[diag.span.start..diag.span.end]).collect::<Vec<_>>(),
+      vec![
+        "maintainer someone",
+        r#""description" "a \"quoted\" label""#,
+        "PATH /usr/local/bin:$PATH",
+        "GREETING hello world",
+        r#"QUOTED "a b""#,
+      ]
+    );
+    assert_eq!(
+      diagnostics[0].message,
+      "\"LABEL key=value\" should be used instead of the legacy \"LABEL key value\" syntax"
+    );
+
+    let mut editor = d.editor();
+    editor.normalize_pair_syntax();
+    assert_eq!(editor.content(), indoc!(r#"
+      FROM alpine
+      LABEL maintainer=someone
+      LABEL "description"="a \"quoted\" label"
+      LABEL a=b
+      ENV PATH=/usr/local/bin:$PATH
+      ENV GREETING="hello world"
+      ENV QUOTED="a b"
+      ENV C=d
+    "#));
+
+    let normalized = Dockerfile::parse(editor.content()).unwrap();
+    assert_eq!(normalized.run_checks(), vec![]);
+    assert_eq!(
+      normalized.instructions[5].as_env().unwrap().vars[0].value.to_string(),
+      "hello world"
+    );
+  }
+
+  #[test]
+  fn test_legacy_pair_syntax_continuation() {
+    let d = Dockerfile::parse("FROM alpine\nENV D f\\\no\nENV E a \\\n  b\n").unwrap();
+    let fixes = d.run_checks()
+      .into_iter()
+      .filter(|d| d.kind == DiagnosticKind::LegacyPairSyntax)
+      .filter_map(|d| d.suggested_fix);
+
+    let content = crate::fixes::apply(&d, fixes).unwrap();
+    assert_eq!(content, "FROM alpine\nENV D=fo\nENV E=\"a   b\"\n");
+
+    let fixed = Dockerfile::parse(&content).unwrap();
+    let values = fixed.instructions[1..]
+      .iter()
+      .map(|i| i.as_env().unwrap().vars[0].value.to_string())
+      .collect::<Vec<_>>();
+    assert_eq!(values, vec!["fo", "a   b"]);
+  }
+
+  #[test]
+  fn test_build_arg_at_runtime() {
+    let d = Dockerfile::parse(indoc!(r#"
+      FROM alpine AS base
+      ARG VERSION=1.0
+      ARG PORT=8080
+      ENV PORT=$PORT
+      CMD echo "$VERSION on $PORT"
+      HEALTHCHECK CMD curl -f localhost:${PORT}/${VERSION}
+
+      FROM base
+      ARG VERSION
+      ENTRYPOINT ["sh", "-c", "echo $VERSION $UNDEFINED"]
+    "#)).unwrap();
+
+    let diagnostics: Vec<_> = d.run_checks()
+      .into_iter()
+      .filter(|d| d.kind == DiagnosticKind::BuildArgAtRuntime)
+      .collect();
+
+    // PORT is re-exported with ENV, and so is available at runtime
+    assert_eq!(
+      diagnostics.iter().map(|diag| d.text(&diag.span)).collect::<Vec<_>>(),
+      vec!["$VERSION", "${VERSION}", "$VERSION"]
+    );
+    assert!(diagnostics[0].message.starts_with("CMD references 'VERSION'"));
+    assert!(diagnostics[1].message.starts_with("HEALTHCHECK"));
+
+    let stages = d.stages();
+    let env = stages[1].runtime_environment(&d);
+    assert_eq!(env.vars.get("PORT").map(String::as_str), Some("8080"));
+    assert!(!env.vars.contains_key("VERSION"));
+  }
+
+  #[test]
+  fn test_single_quoted_exec_array() {
+    let d = Dockerfile::parse(indoc!(r#"
+      FROM alpine
+      RUN ['apk', 'add', "curl"]
+      ENTRYPOINT ['/bin/sh', '-c', 'echo "it works"']
+      CMD ['echo', \
+        'hi']
+      CMD ["echo", "hi"]
+      CMD echo 'hi'
+      CMD ['unterminated]
+    "#)).unwrap();
+
+    // these are shell form, as in Docker
+    assert!(d.instructions[1].as_run().unwrap().as_shell().is_some());
+    assert!(d.instructions[3].as_cmd().unwrap().as_shell().is_some());
+
+    let diagnostics: Vec<_> = d.run_checks()
+      .into_iter()
+      .filter(|d| d.kind == DiagnosticKind::SingleQuotedExecArray)
+      .collect();
+    assert_eq!(
+      diagnostics.iter().map(|diagnostic| d.text(&diagnostic.span)).collect::<Vec<_>>(),
+      vec![
+        "['apk', 'add', \"curl\"]",
+        "['/bin/sh', '-c', 'echo \"it works\"']",
+        "['echo', \\\n  'hi']",
+      ]
+    );
+    assert_eq!(diagnostics[1].severity, Severity::Warning);
+    assert!(diagnostics[2].message.starts_with("single quotes in JSON array make this CMD"));
+
+    let fixed = fixes::apply(&d, diagnostics.into_iter().filter_map(|d| d.suggested_fix)).unwrap();
+    assert_eq!(fixed, indoc!(r#"
+      FROM alpine
+      RUN ["apk", "add", "curl"]
+      ENTRYPOINT ["/bin/sh", "-c", "echo \"it works\""]
+      CMD ["echo", \
+        "hi"]
+      CMD ["echo", "hi"]
+      CMD echo 'hi'
+      CMD ['unterminated]
+    "#));
+
+    let fixed = Dockerfile::parse(&fixed).unwrap();
+    assert_eq!(
+      fixed.instructions[2].as_entrypoint().unwrap().as_exec().unwrap().as_str_vec(),
+      vec!["/bin/sh", "-c", "echo \"it works\""]
+    );
+    assert!(fixed.instructions[3].as_cmd().unwrap().as_exec().is_some());
+  }
+
+  #[test]
+  fn test_workdir_relative_path() {
+    let d = Dockerfile::parse(indoc!(r#"
+      FROM node:20 AS relative
+      WORKDIR app
+      WORKDIR src
+
+      FROM alpine:3.19 AS absolute
+      WORKDIR /app
+      WORKDIR src
+
+      FROM absolute AS inherited
+      WORKDIR dist
+
+      FROM relative AS derived
+      WORKDIR lib
+
+      FROM derived
+      WORKDIR /opt/$VERSION
+      WORKDIR bin
+
+      FROM scratch
+      WORKDIR $HOME/src
+      WORKDIR bin
+      WORKDIR "C:pp"
+    "#)).unwrap();
+
+    let diagnostics: Vec<_> = d.run_checks()
+      .into_iter()
+      .filter(|d| d.kind == DiagnosticKind::WorkdirRelativePath)
+      .collect();
+
+    // stages deriving from a stage whose relative WORKDIR was reported aren't
+    // reported again
+    let summary: Vec<(&str, &str)> = diagnostics
+      .iter()
+      .map(|diagnostic| (d.text(&diagnostic.span), diagnostic.message.as_str()))
+      .collect();
+    assert_eq!(summary, vec![(
+      "app",
+      "relative WORKDIR 'app' depends on the working directory of base image 'node:20'; set an \
+      absolute WORKDIR first"
+    )]);
+    assert_eq!(diagnostics[0].severity, Severity::Warning);
+    assert_eq!(diagnostics[0].code(), "DFP2007");
+    assert_eq!(d.text(&diagnostics[0].related_spans[0]), "node:20");
+
+    // the base image of a derived stage is that of its root stage
+    let d = Dockerfile::parse(indoc!(r#"
+      FROM golang:1.22 AS build
+      RUN go version
+
+      FROM build
+      WORKDIR src
+    "#)).unwrap();
+
+    let diagnostics: Vec<_> = d.run_checks()
+      .into_iter()
+      .filter(|d| d.kind == DiagnosticKind::WorkdirRelativePath)
+      .collect();
+    assert_eq!(diagnostics.len(), 1);
+    assert!(diagnostics[0].message.contains("base image 'golang:1.22'"));
+    assert_eq!(d.text(&diagnostics[0].related_spans[0]), "golang:1.22");
+  }
+
+  #[test]
+  fn test_maintainer_deprecated() {
+    let d = Dockerfile::parse(indoc!(r#"
+      FROM alpine
+      MAINTAINER Jane Doe \
+        <jane@example.com>
+      maintainer "$USER"
+      LABEL maintainer=someone
+    "#)).unwrap();
+
+    let diagnostics: Vec<_> = d.run_checks()
+      .into_iter()
+      .filter(|d| d.kind == DiagnosticKind::MaintainerDeprecated)
+      .collect();
+    assert_eq!(
+      diagnostics.iter().map(|diagnostic| d.text(&diagnostic.span)).collect::<Vec<_>>(),
+      vec!["MAINTAINER Jane Doe \\\n  <jane@example.com>", "maintainer \"$USER\""]
+    );
+    assert_eq!(diagnostics[0].severity, Severity::Warning);
+    assert_eq!(diagnostics[0].message, "MAINTAINER is deprecated; use LABEL maintainer=... instead");
+
+    let fixed = fixes::apply(&d, diagnostics.into_iter().filter_map(|d| d.suggested_fix)).unwrap();
+    assert_eq!(fixed, indoc!(r#"
+      FROM alpine
+      LABEL maintainer="Jane Doe   <jane@example.com>"
+      LABEL maintainer='$USER'
+      LABEL maintainer=someone
+    "#));
+
+    let fixed = Dockerfile::parse(&fixed).unwrap();
+    let label = fixed.instructions[2].as_label().unwrap();
+    assert_eq!(label.labels[0].value.content, "$USER");
+    assert!(label.labels[0].value.is_quoted());
+  }
+}
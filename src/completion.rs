@@ -0,0 +1,283 @@
+// (C) Copyright 2026 Hewlett Packard Enterprise Development LP
+
+use crate::dockerfile_parser::Dockerfile;
+use crate::heredoc::{find_heredocs_lenient, mask_heredocs};
+
+/// The syntactic context at a cursor position, as returned by
+/// `completion_context()`, e.g. to offer completions in an editor.
+///
+/// `prefix` fields contain the partial word typed before the cursor, if any.
+/// Keywords are given in upper case.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CompletionContext {
+  /// At the start of a line, where an instruction keyword is expected
+  InstructionKeyword { prefix: String },
+
+  /// Inside a flag name, after the leading `--`
+  FlagName { instruction: String, prefix: String },
+
+  /// Inside the value of a flag, after the `=`
+  FlagValue { instruction: String, flag: String, prefix: String },
+
+  /// Inside the image of a `FROM` instruction
+  FromImage { prefix: String },
+
+  /// After the image of a `FROM` instruction, where `AS name` is expected
+  FromAlias { prefix: String },
+
+  /// Inside a source or destination path of a `COPY` or `ADD` instruction
+  CopyPath { instruction: String, prefix: String },
+
+  /// Inside any other instruction argument
+  Arguments { instruction: String, prefix: String },
+
+  /// Inside the body of a heredoc
+  HeredocBody,
+
+  /// Inside a comment, including parser directives
+  Comment,
+}
+
+/// Returns the (start, end) offsets of each line, excluding line breaks.
+fn lines(content: &str) -> Vec<(usize, usize)> {
+  let mut lines = Vec::new();
+  let mut start = if content.starts_with('\u{feff}') { '\u{feff}'.len_utf8() } else { 0 };
+
+  for line in content[start..].split('\n') {
+    lines.push((start, start + line.trim_end_matches('\r').len()));
+    start += line.len() + 1;
+  }
+
+  lines
+}
+
+/// Determines the syntactic context at byte `offset` of a Dockerfile's
+/// content.
+///
+/// Unlike most of this crate, this works line by line on the raw text rather
+/// than on a parse result, so it works while an instruction is still being
+/// typed and the document can't be parsed.
+///
+/// ```
+/// use dockerfile_parser::{completion_context, CompletionContext};
+///
+/// // COPY requires at least two paths, so this doesn't parse
+/// let content = "FROM alpine\nCOPY --from=bu";
+/// assert_eq!(
+///   completion_context(content, content.len()),
+///   CompletionContext::FlagValue {
+///     instruction: "COPY".into(),
+///     flag: "from".into(),
+///     prefix: "bu".into(),
+///   }
+/// );
+/// ```
+pub fn completion_context(content: &str, offset: usize) -> CompletionContext {
+  let mut offset = offset.min(content.len());
+  while !content.is_char_boundary(offset) {
+    offset -= 1;
+  }
+
+  // heredocs that haven't been terminated yet run to the end of the content
+  let heredocs = find_heredocs_lenient(content);
+  let in_body = heredocs.iter().any(|h| offset >= h.body.span.start
+    && (offset < h.terminator.start || h.terminator.start == h.terminator.end));
+  if in_body {
+    return CompletionContext::HeredocBody;
+  }
+
+  // find the lines of the (logical) instruction containing the cursor,
+  // excluding comments
+  let masked = mask_heredocs(content, &heredocs);
+  let mut instruction: Vec<(usize, usize)> = Vec::new();
+  let mut continuing = false;
+
+  for (start, end) in lines(&masked) {
+    let line = &masked[start..end];
+    let trimmed = line.trim_start();
+    let is_comment = trimmed.starts_with('#');
+    let has_cursor = offset >= start && offset <= end;
+
+    if has_cursor && is_comment && offset > end - trimmed.len() {
+      return CompletionContext::Comment;
+    }
+
+    if !continuing {
+      instruction.clear();
+    }
+
+    if !is_comment {
+      instruction.push((start, if has_cursor { offset } else { end }));
+    }
+
+    if has_cursor {
+      break;
+    }
+
+    if !is_comment && !trimmed.is_empty() {
+      continuing = line.trim_end().ends_with('\\');
+    }
+  }
+
+  // split into words, dropping line continuations
+  let mut words: Vec<(usize, &str)> = Vec::new();
+  for (start, end) in instruction {
+    let mut line = &masked[start..end];
+    if end != offset {
+      line = line.trim_end().trim_end_matches('\\');
+    }
+
+    let mut pos = start;
+    for word in line.split_whitespace() {
+      let word_start = pos + masked[pos..end].find(word).unwrap_or(0);
+      pos = word_start + word.len();
+      words.push((word_start, word));
+    }
+  }
+
+  let prefix = match words.last() {
+    Some((start, word)) if start + word.len() == offset => {
+      let prefix = word.to_string();
+      words.pop();
+      prefix
+    },
+    _ => String::new()
+  };
+
+  let keyword = match words.first() {
+    Some((_, keyword)) => keyword.to_ascii_uppercase(),
+    None => return CompletionContext::InstructionKeyword { prefix }
+  };
+
+  let args: Vec<&str> = words[1..].iter().map(|(_, w)| *w).collect();
+  if prefix.starts_with("--") && args.iter().all(|a| a.starts_with("--")) {
+    let flag = &prefix[2..];
+    return match flag.split_once('=') {
+      Some((flag, value)) => CompletionContext::FlagValue {
+        instruction: keyword,
+        flag: flag.to_string(),
+        prefix: value.to_string(),
+      },
+      None => CompletionContext::FlagName {
+        instruction: keyword,
+        prefix: flag.to_string(),
+      }
+    };
+  }
+
+  let positional = args.iter().filter(|a| !a.starts_with("--")).count();
+  match keyword.as_str() {
+    "FROM" if positional == 0 => CompletionContext::FromImage { prefix },
+    "FROM" if positional == 1 => CompletionContext::FromAlias { prefix },
+    "COPY" | "ADD" => CompletionContext::CopyPath { instruction: keyword, prefix },
+    _ => CompletionContext::Arguments { instruction: keyword, prefix }
+  }
+}
+
+impl Dockerfile {
+  /// Determines the syntactic context at the given offset, e.g. to offer
+  /// completions in an editor. See `completion_context()`, which also works
+  /// with content that doesn't parse.
+  ///
+  /// Like spans, `offset` is relative to the enclosing document if this
+  /// Dockerfile was parsed with `parse_with_offset()`.
+  pub fn context_at(&self, offset: usize) -> CompletionContext {
+    completion_context(&self.content, offset.saturating_sub(self.base_offset))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use indoc::indoc;
+  use pretty_assertions::assert_eq;
+
+  use super::*;
+  use CompletionContext::*;
+
+  const CURSOR: &str = "<|>";
+
+  /// Finds the context at the position marked by `CURSOR`.
+  fn context(s: &str) -> CompletionContext {
+    let offset = s.find(CURSOR).expect("missing cursor");
+    completion_context(&s.replacen(CURSOR, "", 1), offset)
+  }
+
+  fn s(s: &str) -> String {
+    s.to_string()
+  }
+
+  const FIXTURE: &str = indoc!(r#"
+    # syntax=docker/dockerfile:1
+    FROM --platform=linux/amd64 alpine:3.12 AS build
+    RUN --mount=type=cache,target=/root/.cache \
+      # install tools
+      apk add curl
+    COPY --chown=1000 src/ /app/
+    RUN cat <<EOF > /etc/motd
+    hello
+    EOF
+    ENV A=b
+  "#);
+
+  /// Finds the context `at` bytes into the first occurrence of `needle` in
+  /// `FIXTURE`.
+  fn at(needle: &str, at: usize) -> CompletionContext {
+    completion_context(FIXTURE, FIXTURE.find(needle).expect("missing needle") + at)
+  }
+
+  #[test]
+  fn test_completion_context() {
+    assert_eq!(at("# syntax", 0), InstructionKeyword { prefix: s("") });
+    assert_eq!(at("# syntax", 5), Comment);
+    assert_eq!(at("FROM", 2), InstructionKeyword { prefix: s("FR") });
+    assert_eq!(at("--platform", 6), FlagName { instruction: s("FROM"), prefix: s("plat") });
+    assert_eq!(
+      at("linux/amd64", 6),
+      FlagValue { instruction: s("FROM"), flag: s("platform"), prefix: s("linux/") }
+    );
+    assert_eq!(at("alpine:3.12", 3), FromImage { prefix: s("alp") });
+    assert_eq!(at("AS build", 0), FromAlias { prefix: s("") });
+    assert_eq!(
+      at("--mount", 18),
+      FlagValue { instruction: s("RUN"), flag: s("mount"), prefix: s("type=cache") }
+    );
+    assert_eq!(at("# install", 3), Comment);
+    assert_eq!(at("apk add", 5), Arguments { instruction: s("RUN"), prefix: s("a") });
+    assert_eq!(at("/app/", 3), CopyPath { instruction: s("COPY"), prefix: s("/ap") });
+    assert_eq!(at("hello", 2), HeredocBody);
+    assert_eq!(at("EOF\nENV", 4), InstructionKeyword { prefix: s("") });
+    assert_eq!(at("A=b", 3), Arguments { instruction: s("ENV"), prefix: s("A=b") });
+    assert_eq!(at("ENV A=b\n", 8), InstructionKeyword { prefix: s("") });
+  }
+
+  #[test]
+  fn test_completion_context_broken() {
+    // none of these parse
+    assert_eq!(
+      context("FROM alpine\nCOPY --from=build <|>"),
+      CopyPath { instruction: s("COPY"), prefix: s("") }
+    );
+    assert_eq!(
+      context("FROM alpine\nRUN apt-get update && \\\n  apt-get inst<|>"),
+      Arguments { instruction: s("RUN"), prefix: s("inst") }
+    );
+    assert_eq!(
+      context("FROM alpine\nRUN --mount=type=secret \\\n  --<|>"),
+      FlagName { instruction: s("RUN"), prefix: s("") }
+    );
+    assert_eq!(context("FROM alpine\nRUN cat <<EOF\nunterminated <|>"), HeredocBody);
+    assert_eq!(context("FROM alpine <|>as"), FromAlias { prefix: s("") });
+  }
+
+  #[test]
+  fn test_context_at() {
+    let outer = "dockerfile_inline: |\nFROM alpine:3.12\nRUN echo\n";
+    let d = Dockerfile::parse_with_offset(&outer[21..], 21, 1).unwrap();
+
+    assert_eq!(d.context_at(outer.find("3.12").unwrap()), FromImage { prefix: s("alpine:") });
+    assert_eq!(
+      d.context_at(outer.find("echo").unwrap() + 4),
+      Arguments { instruction: s("RUN"), prefix: s("echo") }
+    );
+  }
+}
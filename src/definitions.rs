@@ -0,0 +1,411 @@
+// (C) Copyright 2026 Hewlett Packard Enterprise Development LP
+
+use std::ptr;
+
+use crate::dockerfile_parser::{Dockerfile, Instruction};
+use crate::instructions::{ArgInstruction, EnvVar, Label};
+use crate::splicer::Span;
+use crate::stage::{Stage, StageParent};
+use crate::variables::{ExpansionBehavior, VarRef};
+
+/// The definition of a label, environment variable, or build arg, as found by
+/// e.g. `Dockerfile::find_label()`.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Definition<'a, T> {
+  /// The instruction containing the definition
+  pub instruction: &'a Instruction,
+
+  /// The defining pair, e.g. a `Label` or `EnvVar`
+  pub pair: &'a T,
+
+  /// The span of the defined value, if any; `ARG`s may be declared without one
+  pub value_span: Option<Span>,
+}
+
+/// The definition of a variable referenced by a `VarRef`, as found by
+/// `Dockerfile::definition_of()`.
+#[derive(Debug, PartialEq, Eq)]
+pub enum VarDefinition<'a> {
+  Arg(Definition<'a, ArgInstruction>),
+  Env(Definition<'a, EnvVar>),
+}
+
+impl<'a> VarDefinition<'a> {
+  /// Returns the instruction containing the definition.
+  pub fn instruction(&self) -> &'a Instruction {
+    match self {
+      VarDefinition::Arg(def) => def.instruction,
+      VarDefinition::Env(def) => def.instruction,
+    }
+  }
+}
+
+fn arg_definition<'a>(ins: &'a Instruction, name: &str) -> Option<Definition<'a, ArgInstruction>> {
+  match ins {
+    Instruction::Arg(arg) if arg.name.content == name => Some(Definition {
+      instruction: ins,
+      pair: arg,
+      value_span: arg.value.as_ref().map(|v| v.span),
+    }),
+    _ => None
+  }
+}
+
+/// Finds the last `ARG` named `name` among the given instructions.
+fn find_last_arg<'a>(
+  instructions: &[&'a Instruction],
+  name: &str
+) -> Option<Definition<'a, ArgInstruction>> {
+  instructions.iter().rev().find_map(|ins| arg_definition(ins, name))
+}
+
+impl<'a> Stage<'a> {
+  /// Finds the definition of the given label key that applies to the image
+  /// built by this stage, including labels inherited from parent stages. The
+  /// last definition wins. Keys are compared exactly as written.
+  pub fn find_label(&self, dockerfile: &'a Dockerfile, key: &str) -> Option<Definition<'a, Label>> {
+    let stages = dockerfile.stages();
+
+    // this stage and its chain of parent stages, root last
+    let mut chain = vec![self.index];
+    while let StageParent::Stage(parent) = stages[chain[chain.len() - 1]].parent {
+      if chain.contains(&parent) {
+        break;
+      }
+
+      chain.push(parent);
+    }
+
+    chain.iter().find_map(|index| {
+      stages[*index].instructions.iter().rev().find_map(|ins| match ins {
+        Instruction::Label(label) => label.labels
+          .iter()
+          .rev()
+          .find(|l| l.name.content == key)
+          .map(|l| Definition { instruction: ins, pair: l, value_span: Some(l.value.span) }),
+        _ => None
+      })
+    })
+  }
+
+  /// Finds the definition of the given environment variable at the end of
+  /// this stage, including variables inherited from parent stages. The last
+  /// definition wins, and keys are compared after substitution, as with
+  /// `environment_at()`.
+  pub fn find_env(&self, dockerfile: &'a Dockerfile, key: &str) -> Option<Definition<'a, EnvVar>> {
+    self.find_env_at(dockerfile, self.instructions.len(), key)
+  }
+
+  /// Finds the last `ARG` declaring `name` in this stage. Note that, per the
+  /// Dockerfile spec, global `ARG`s are only in scope within a stage if it
+  /// redeclares them.
+  pub fn find_arg(&self, name: &str) -> Option<Definition<'a, ArgInstruction>> {
+    find_last_arg(&self.instructions, name)
+  }
+
+  fn find_env_at(
+    &self,
+    dockerfile: &'a Dockerfile,
+    index: usize,
+    key: &str
+  ) -> Option<Definition<'a, EnvVar>> {
+    let env = self.environment_at(dockerfile, index);
+    let var = *env.definitions.get(key)?;
+
+    let instruction = dockerfile.instructions.iter().find(|ins| match ins {
+      Instruction::Env(e) => e.vars.iter().any(|v| ptr::eq(v, var)),
+      _ => false
+    })?;
+
+    Some(Definition { instruction, pair: var, value_span: Some(var.value.span) })
+  }
+}
+
+/// Where the value of an `ARG` comes from within a single stage, as returned
+/// by `Dockerfile::arg_provenance()`.
+#[derive(Debug, PartialEq, Eq)]
+pub struct ArgProvenance<'a> {
+  /// The stage index
+  pub stage: usize,
+
+  /// The first `ARG` declaring the name within the stage, which brings it into
+  /// scope for the following instructions, if any
+  pub declaration: Option<Definition<'a, ArgInstruction>>,
+
+  /// The declaration providing the default value in effect at the end of the
+  /// stage: the last declaration in the stage with a value, or otherwise the
+  /// last global declaration if it has one. `None` if the arg isn't in scope
+  /// or has no default.
+  pub default: Option<Definition<'a, ArgInstruction>>,
+
+  /// References to the arg that aren't in scope, because they precede the
+  /// stage's declaration or the stage doesn't declare it at all, and that
+  /// aren't shadowed by an `ENV`. These expand to an empty string.
+  pub dead_references: Vec<VarRef>,
+}
+
+impl<'a> ArgProvenance<'a> {
+  /// Determines if the arg is in scope by the end of the stage.
+  pub fn in_scope(&self) -> bool {
+    self.declaration.is_some()
+  }
+}
+
+impl Dockerfile {
+  /// Finds the definition of the given label key in the image built by the
+  /// last stage, including labels inherited from its parent stages. The last
+  /// definition wins.
+  pub fn find_label(&self, key: &str) -> Option<Definition<'_, Label>> {
+    self.iter_stages().last()?.find_label(self, key)
+  }
+
+  /// Finds the last global `ARG` (i.e. preceding the first `FROM`) declaring
+  /// the given name. Use `Stage::find_arg()` to find `ARG`s within a stage.
+  pub fn find_arg(&self, name: &str) -> Option<Definition<'_, ArgInstruction>> {
+    let globals: Vec<&Instruction> = self.global_arg_indices
+      .iter()
+      .filter_map(|i| self.instructions.get(*i))
+      .collect();
+
+    find_last_arg(&globals, name)
+  }
+
+  /// Traces the given `ARG` through each stage: whether the stage declares it,
+  /// which declaration provides its default value, and which references to
+  /// it are used without being in scope, e.g. because a stage forgot to
+  /// redeclare a global `ARG`.
+  ///
+  /// References in exec-form instructions and quoted heredocs are never
+  /// expanded and are ignored, as are references in `FROM` instructions,
+  /// which are resolved using global `ARG`s instead.
+  ///
+  /// ```
+  /// use dockerfile_parser::Dockerfile;
+  ///
+  /// let dockerfile = Dockerfile::parse(r#"
+  ///   ARG VERSION=1.0
+  ///   FROM alpine
+  ///   RUN echo $VERSION
+  /// "#).unwrap();
+  ///
+  /// let provenance = dockerfile.arg_provenance("VERSION");
+  /// assert!(!provenance[0].in_scope());
+  /// assert_eq!(provenance[0].dead_references[0].name, "VERSION");
+  /// ```
+  pub fn arg_provenance(&self, name: &str) -> Vec<ArgProvenance<'_>> {
+    let global = self.find_arg(name);
+
+    self.iter_stages().map(|stage| {
+      let declaration = stage.instructions.iter().find_map(|ins| arg_definition(ins, name));
+      let default = match &declaration {
+        Some(_) => stage.instructions
+          .iter()
+          .rev()
+          .filter_map(|ins| arg_definition(ins, name))
+          .find(|def| def.value_span.is_some())
+          .or_else(|| global
+            .as_ref()
+            .filter(|g| g.value_span.is_some())
+            .map(|g| Definition { instruction: g.instruction, pair: g.pair, value_span: g.value_span })),
+        None => None
+      };
+
+      let dead_references = stage.instructions
+        .iter()
+        .filter(|ins| !matches!(ins, Instruction::From(_)))
+        .flat_map(|ins| ins.all_variable_references())
+        .filter(|r| r.name == name && r.expansion != ExpansionBehavior::NoExpansion)
+        .filter(|r| self.definition_of(r).is_none())
+        .collect();
+
+      ArgProvenance { stage: stage.index, declaration, default, dead_references }
+    }).collect()
+  }
+
+  /// Finds the definition in scope for a variable reference, e.g. as found by
+  /// `Instruction::variable_references()`, for "go to definition".
+  ///
+  /// References in global `ARG`s and `FROM` instructions resolve to preceding
+  /// global `ARG`s. Within a stage, `ENV` definitions (including any inherited
+  /// from parent stages) take precedence over `ARG`s declared in the stage,
+  /// as in Docker. Returns `None` if the variable isn't defined before the
+  /// reference.
+  ///
+  /// ```
+  /// use dockerfile_parser::{Dockerfile, VarDefinition};
+  ///
+  /// let dockerfile = Dockerfile::parse(r#"
+  ///   FROM alpine
+  ///   ARG VERSION=1.0
+  ///   ENV VERSION=2.0
+  ///   WORKDIR /opt/$VERSION
+  /// "#).unwrap();
+  ///
+  /// let var = dockerfile.instructions[3].variable_references().remove(0);
+  /// match dockerfile.definition_of(&var) {
+  ///   Some(VarDefinition::Env(def)) => assert_eq!(def.pair.value.to_string(), "2.0"),
+  ///   other => panic!("unexpected definition: {:?}", other),
+  /// }
+  /// ```
+  pub fn definition_of(&self, var: &VarRef) -> Option<VarDefinition<'_>> {
+    let index = self.instructions
+      .iter()
+      .position(|ins| ins.span().start <= var.span.start && var.span.end <= ins.span().end)?;
+
+    let stage = self.iter_stages().find(|s| s.local_index_of(index).is_some());
+    let stage = match (stage, &self.instructions[index]) {
+      (Some(stage), ins) if !matches!(ins, Instruction::From(_)) => stage,
+      _ => {
+        let globals: Vec<&Instruction> = self.global_arg_indices
+          .iter()
+          .filter(|i| **i < index)
+          .filter_map(|i| self.instructions.get(*i))
+          .collect();
+
+        return find_last_arg(&globals, &var.name).map(VarDefinition::Arg);
+      }
+    };
+
+    let local = stage.local_index_of(index)?;
+    if let Some(env) = stage.find_env_at(self, local, &var.name) {
+      return Some(VarDefinition::Env(env));
+    }
+
+    find_last_arg(&stage.instructions[..local], &var.name).map(VarDefinition::Arg)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use indoc::indoc;
+  use pretty_assertions::assert_eq;
+
+  use crate::*;
+
+  const FIXTURE: &str = indoc!(r#"
+    ARG VERSION=1.0
+    ARG BASE=alpine
+    ARG VERSION=1.1
+    FROM $BASE:$VERSION AS base
+    ARG VERSION
+    LABEL version=base stage=base
+    ENV APP_HOME=/opt/app
+    RUN echo $VERSION $APP_HOME
+
+    FROM base
+    ARG VERSION=2.0
+    LABEL version=final
+    ENV APP_HOME=/srv
+    WORKDIR $APP_HOME/$VERSION
+  "#);
+
+  #[test]
+  fn test_find_definitions() {
+    let d = Dockerfile::parse(FIXTURE).unwrap();
+    let stages = d.stages();
+
+    // the last global ARG wins
+    let arg = d.find_arg("VERSION").unwrap();
+    assert_eq!(arg.value_span.map(|s| d.text(&s)), Some("1.1"));
+    assert!(d.find_arg("APP_HOME").is_none());
+
+    let arg = stages[0].find_arg("VERSION").unwrap();
+    assert_eq!(arg.instruction, &d.instructions[4]);
+    assert_eq!(arg.value_span, None);
+    assert_eq!(stages[1].find_arg("VERSION").unwrap().value_span.map(|s| d.text(&s)), Some("2.0"));
+
+    // labels and env are inherited from parent stages
+    let label = d.find_label("version").unwrap();
+    assert_eq!(label.pair.value.content, "final");
+    assert_eq!(d.find_label("stage").unwrap().instruction, &d.instructions[5]);
+    assert_eq!(stages[0].find_label(&d, "version").unwrap().pair.value.content, "base");
+    assert!(d.find_label("missing").is_none());
+
+    let env = stages[0].find_env(&d, "APP_HOME").unwrap();
+    assert_eq!(env.value_span.map(|s| d.text(&s)), Some("/opt/app"));
+    let env = stages[1].find_env(&d, "APP_HOME").unwrap();
+    assert_eq!(env.instruction, &d.instructions[11]);
+  }
+
+  #[test]
+  fn test_definition_of() {
+    let d = Dockerfile::parse(FIXTURE).unwrap();
+
+    let definition = |ins: usize, name: &str| {
+      let var = d.instructions[ins]
+        .all_variable_references()
+        .into_iter()
+        .find(|r| r.name == name)
+        .unwrap();
+
+      d.definition_of(&var).map(|def| {
+        let index = d.instructions.iter().position(|i| i == def.instruction()).unwrap();
+        let kind = match def {
+          VarDefinition::Arg(_) => "ARG",
+          VarDefinition::Env(_) => "ENV",
+        };
+
+        (kind, index)
+      })
+    };
+
+    // FROM resolves global ARGs preceding it
+    assert_eq!(definition(3, "BASE"), Some(("ARG", 1)));
+    assert_eq!(definition(3, "VERSION"), Some(("ARG", 2)));
+
+    // within a stage, the redeclared ARG is in scope
+    assert_eq!(definition(7, "VERSION"), Some(("ARG", 4)));
+    assert_eq!(definition(7, "APP_HOME"), Some(("ENV", 6)));
+
+    // ENV is inherited, but the ARG must be redeclared in each stage
+    assert_eq!(definition(12, "APP_HOME"), Some(("ENV", 11)));
+    assert_eq!(definition(12, "VERSION"), Some(("ARG", 9)));
+  }
+
+  #[test]
+  fn test_arg_provenance() {
+    let d = Dockerfile::parse(indoc!(r#"
+      ARG VERSION=1.0
+      FROM alpine AS imported
+      RUN echo $VERSION
+      ARG VERSION
+      RUN echo $VERSION
+
+      FROM alpine AS overridden
+      ARG VERSION=2.0
+      COPY app-${VERSION}.tar /
+
+      FROM alpine AS forgotten
+      ENV DIR=/opt/$VERSION
+      RUN ["echo", "$VERSION"]
+      ENV VERSION=env
+      RUN echo $VERSION
+    "#)).unwrap();
+
+    let provenance = d.arg_provenance("VERSION");
+    let summary: Vec<(bool, Option<&str>, Vec<usize>)> = provenance
+      .iter()
+      .map(|p| (
+        p.in_scope(),
+        p.default.as_ref().and_then(|def| def.value_span).map(|s| d.text(&s)),
+        p.dead_references.iter().map(|r| r.span.relative_span(&d).0).collect()
+      ))
+      .collect();
+
+    assert_eq!(summary, vec![
+      // the global default applies once redeclared, but not before
+      (true, Some("1.0"), vec![2]),
+      (true, Some("2.0"), vec![]),
+
+      // exec-form references and those shadowed by ENV are ignored
+      (false, None, vec![11]),
+    ]);
+
+    assert_eq!(provenance[0].declaration.as_ref().unwrap().instruction, &d.instructions[3]);
+    assert_eq!(provenance[0].default.as_ref().unwrap().instruction, &d.instructions[0]);
+    assert_eq!(provenance[1].default.as_ref().unwrap().instruction, &d.instructions[6]);
+    assert!(provenance[2].declaration.is_none());
+
+    assert!(d.arg_provenance("MISSING").iter().all(|p| !p.in_scope() && p.dead_references.is_empty()));
+  }
+}
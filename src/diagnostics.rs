@@ -0,0 +1,557 @@
+// (C) Copyright 2026 Hewlett Packard Enterprise Development LP
+
+//! Diagnostics produced by static analysis, e.g. `Dockerfile::validate()`,
+//! and their machine-readable output.
+
+use std::fmt;
+use std::fmt::Write;
+
+use crate::fixes::Fix;
+use crate::splicer::Span;
+
+/// The severity of a [`Diagnostic`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Severity {
+  Info,
+  Warning,
+  Error,
+}
+
+impl fmt::Display for Severity {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      Severity::Info => write!(f, "info"),
+      Severity::Warning => write!(f, "warning"),
+      Severity::Error => write!(f, "error"),
+    }
+  }
+}
+
+/// The kind of problem described by a [`Diagnostic`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DiagnosticKind {
+  /// A `COPY --from` flag with a negative stage index, e.g. `--from=-1`
+  NegativeStageIndex,
+
+  /// A `COPY --from=scratch` flag; the scratch image contains no files
+  CopyFromScratch,
+
+  /// A `COPY --from` flag referencing a stage index that is not defined before
+  /// the instruction
+  UndefinedStageIndex,
+
+  /// A `# syntax` directive whose image is not pinned to a digest
+  UnpinnedSyntaxDirective,
+
+  /// A shell command containing what looks like another instruction, e.g.
+  /// `RUN apt update; RUN apt install curl`. Keywords that are also common
+  /// command names, e.g. `env`, are only reported if not written in lowercase.
+  EmbeddedInstruction,
+
+  /// A `RUN --mount=type=secret` mount with both a `target` and an `env`
+  ConflictingSecretMount,
+
+  /// A `VOLUME` instruction without any paths, e.g. `VOLUME []`
+  EmptyVolume,
+
+  /// A path listed more than once in a single `VOLUME` instruction
+  DuplicateVolumePath,
+
+  /// A `VOLUME` path that is not absolute for the target OS
+  RelativeVolumePath,
+
+  /// A `LABEL` or `ENV` pair using the legacy `key value` syntax
+  LegacyPairSyntax,
+
+  /// A reference in a `CMD`, `ENTRYPOINT`, or `HEALTHCHECK` to a variable only
+  /// defined by an `ARG`, which won't exist when the container runs
+  BuildArgAtRuntime,
+
+  /// A shell-form `RUN`, `CMD`, or `ENTRYPOINT` that looks like an exec-form
+  /// array, but uses single quotes, which aren't valid JSON
+  SingleQuotedExecArray,
+
+  /// A deprecated `MAINTAINER` instruction, which should be replaced with
+  /// `LABEL maintainer=...`
+  MaintainerDeprecated,
+
+  /// A relative `WORKDIR` with no absolute `WORKDIR` before it in the stage
+  /// or its parent stages, which depends on the base image's working
+  /// directory
+  WorkdirRelativePath,
+
+  /// A key set more than once in a single `ENV` instruction
+  DuplicateEnvKey,
+
+  /// A label key set more than once within a stage
+  DuplicateLabelKey,
+
+  /// An instruction spanning more lines than allowed by
+  /// `StyleOptions::max_instruction_lines`
+  MaxInstructionLines,
+
+  /// A line longer than allowed by `StyleOptions::max_line_length`
+  MaxLineLength,
+
+  /// A stage whose image has more layers than allowed by
+  /// `StyleOptions::max_layers`
+  MaxLayers,
+
+  /// A `COPY` of the whole build context followed by a dependency install,
+  /// which then reruns whenever any file changes
+  BroadCopyBeforeInstall,
+
+  /// A `COPY --from` between stages built for different platforms, where the
+  /// source stage doesn't appear to cross-compile for the target platform
+  CrossPlatformCopy,
+
+  /// A metadata instruction, e.g. `CMD` or `EXPOSE`, in a stage that doesn't
+  /// contribute to the target image, so it has no effect
+  IneffectiveMetadata,
+
+  /// A feature, e.g. a heredoc, requiring a newer `docker/dockerfile`
+  /// frontend than declared by the `syntax` directive
+  UnsupportedFeature,
+
+  /// A `CMD`, `ENTRYPOINT`, or `HEALTHCHECK` overridden by a later one in the
+  /// same stage or in a stage deriving from it
+  OverriddenMetadata,
+
+  /// A flag not known to be accepted by its instruction, reported with
+  /// `ValidateOptions::strict_flags`
+  UnknownFlag,
+
+  /// An instruction with an unrecognized keyword, reported with
+  /// `UnknownPolicy::Warn`
+  UnknownInstruction,
+
+  /// A misplaced space around the `=` of an `ENV`, `ARG`, or `LABEL` pair,
+  /// accepted with `ParseOptions::lenient`
+  MisplacedPairSpace,
+
+  /// An `ARG` name containing characters not allowed by Docker, e.g.
+  /// `ARG foo-bar`
+  InvalidArgName,
+
+  /// A `COPY --from` flag or `RUN --mount=from=` option referencing the stage
+  /// containing it, e.g. `FROM alpine AS app` followed by `COPY --from=app`
+  SelfStageReference,
+
+  /// A malformed digest, e.g. in a `FROM` image, `COPY --from` image, `ADD
+  /// --checksum` flag, or `# syntax` directive
+  InvalidDigest,
+
+  /// More than one `HEALTHCHECK` instruction in a single stage, where only
+  /// the last takes effect
+  DuplicateHealthcheck,
+
+  /// A malformed `HEALTHCHECK`, e.g. with no command, an empty exec-form
+  /// command, or an invalid duration or retry count
+  InvalidHealthcheck,
+
+  /// A `STOPSIGNAL` with an unknown signal name or a signal number outside
+  /// 1 to 64
+  InvalidStopsignal,
+}
+
+impl DiagnosticKind {
+  /// All diagnostic kinds, in order of their codes.
+  pub const ALL: &'static [DiagnosticKind] = &[
+    DiagnosticKind::NegativeStageIndex,
+    DiagnosticKind::CopyFromScratch,
+    DiagnosticKind::UndefinedStageIndex,
+    DiagnosticKind::ConflictingSecretMount,
+    DiagnosticKind::EmptyVolume,
+    DiagnosticKind::DuplicateVolumePath,
+    DiagnosticKind::RelativeVolumePath,
+    DiagnosticKind::DuplicateEnvKey,
+    DiagnosticKind::DuplicateLabelKey,
+    DiagnosticKind::UnknownFlag,
+    DiagnosticKind::UnknownInstruction,
+    DiagnosticKind::MisplacedPairSpace,
+    DiagnosticKind::InvalidArgName,
+    DiagnosticKind::SelfStageReference,
+    DiagnosticKind::InvalidDigest,
+    DiagnosticKind::DuplicateHealthcheck,
+    DiagnosticKind::InvalidHealthcheck,
+    DiagnosticKind::InvalidStopsignal,
+    DiagnosticKind::UnpinnedSyntaxDirective,
+    DiagnosticKind::EmbeddedInstruction,
+    DiagnosticKind::LegacyPairSyntax,
+    DiagnosticKind::BuildArgAtRuntime,
+    DiagnosticKind::SingleQuotedExecArray,
+    DiagnosticKind::MaintainerDeprecated,
+    DiagnosticKind::WorkdirRelativePath,
+    DiagnosticKind::MaxInstructionLines,
+    DiagnosticKind::MaxLineLength,
+    DiagnosticKind::MaxLayers,
+    DiagnosticKind::BroadCopyBeforeInstall,
+    DiagnosticKind::CrossPlatformCopy,
+    DiagnosticKind::IneffectiveMetadata,
+    DiagnosticKind::UnsupportedFeature,
+    DiagnosticKind::OverriddenMetadata,
+  ];
+
+  /// Returns the stable code of this kind of diagnostic, e.g. `DFP1001`.
+  ///
+  /// Codes are grouped by the analysis producing them: `DFP1xxx` for
+  /// `Dockerfile::validate()`, `DFP2xxx` for `Dockerfile::run_checks()`,
+  /// `DFP3xxx` for `Dockerfile::check_style()`, and `DFP4xxx` for the
+  /// heuristics in `analysis`. Codes are never changed or reused once
+  /// assigned.
+  pub fn code(self) -> &'static str {
+    match self {
+      DiagnosticKind::NegativeStageIndex => "DFP1001",
+      DiagnosticKind::CopyFromScratch => "DFP1002",
+      DiagnosticKind::UndefinedStageIndex => "DFP1003",
+      DiagnosticKind::ConflictingSecretMount => "DFP1004",
+      DiagnosticKind::EmptyVolume => "DFP1005",
+      DiagnosticKind::DuplicateVolumePath => "DFP1006",
+      DiagnosticKind::RelativeVolumePath => "DFP1007",
+      DiagnosticKind::DuplicateEnvKey => "DFP1008",
+      DiagnosticKind::DuplicateLabelKey => "DFP1009",
+      DiagnosticKind::UnknownFlag => "DFP1010",
+      DiagnosticKind::UnknownInstruction => "DFP1011",
+      DiagnosticKind::MisplacedPairSpace => "DFP1012",
+      DiagnosticKind::InvalidArgName => "DFP1013",
+      DiagnosticKind::SelfStageReference => "DFP1014",
+      DiagnosticKind::InvalidDigest => "DFP1015",
+      DiagnosticKind::DuplicateHealthcheck => "DFP1016",
+      DiagnosticKind::InvalidHealthcheck => "DFP1017",
+      DiagnosticKind::InvalidStopsignal => "DFP1018",
+      DiagnosticKind::UnpinnedSyntaxDirective => "DFP2001",
+      DiagnosticKind::EmbeddedInstruction => "DFP2002",
+      DiagnosticKind::LegacyPairSyntax => "DFP2003",
+      DiagnosticKind::BuildArgAtRuntime => "DFP2004",
+      DiagnosticKind::SingleQuotedExecArray => "DFP2005",
+      DiagnosticKind::MaintainerDeprecated => "DFP2006",
+      DiagnosticKind::WorkdirRelativePath => "DFP2007",
+      DiagnosticKind::MaxInstructionLines => "DFP3001",
+      DiagnosticKind::MaxLineLength => "DFP3002",
+      DiagnosticKind::MaxLayers => "DFP3003",
+      DiagnosticKind::BroadCopyBeforeInstall => "DFP4001",
+      DiagnosticKind::CrossPlatformCopy => "DFP4002",
+      DiagnosticKind::IneffectiveMetadata => "DFP4003",
+      DiagnosticKind::UnsupportedFeature => "DFP4004",
+      DiagnosticKind::OverriddenMetadata => "DFP4005",
+    }
+  }
+}
+
+/// A problem found while statically analyzing a Dockerfile.
+///
+/// Unlike parse errors, diagnostics describe Dockerfiles that are
+/// syntactically valid but are likely to fail at build time or behave
+/// unexpectedly.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+  /// The kind of problem
+  pub kind: DiagnosticKind,
+
+  /// The severity of the problem
+  pub severity: Severity,
+
+  /// A human-readable description of the problem
+  pub message: String,
+
+  /// The primary location of the problem in the original Dockerfile
+  pub span: Span,
+
+  /// Additional locations relevant to the problem, if any
+  pub related_spans: Vec<Span>,
+
+  /// A suggested fix, which may be applied with `fixes::apply()`
+  pub suggested_fix: Option<Fix>,
+}
+
+impl Diagnostic {
+  /// Returns the stable code of this diagnostic's kind, e.g. `DFP1001`.
+  pub fn code(&self) -> &'static str {
+    self.kind.code()
+  }
+
+  /// Creates a new diagnostic with no related spans.
+  pub fn new<S: Into<String>>(
+    kind: DiagnosticKind,
+    severity: Severity,
+    span: Span,
+    message: S
+  ) -> Diagnostic {
+    Diagnostic {
+      kind,
+      severity,
+      message: message.into(),
+      span,
+      related_spans: Vec::new(),
+      suggested_fix: None,
+    }
+  }
+
+  /// Creates a new error-level diagnostic.
+  pub fn error<S: Into<String>>(kind: DiagnosticKind, span: Span, message: S) -> Diagnostic {
+    Diagnostic::new(kind, Severity::Error, span, message)
+  }
+
+  /// Creates a new warning-level diagnostic.
+  pub fn warning<S: Into<String>>(kind: DiagnosticKind, span: Span, message: S) -> Diagnostic {
+    Diagnostic::new(kind, Severity::Warning, span, message)
+  }
+
+  /// Adds a related span to this diagnostic.
+  pub fn with_related_span(mut self, span: Span) -> Diagnostic {
+    self.related_spans.push(span);
+    self
+  }
+
+  /// Sets the suggested fix for this diagnostic.
+  pub fn with_suggested_fix(mut self, fix: Fix) -> Diagnostic {
+    self.suggested_fix = Some(fix);
+    self
+  }
+}
+
+impl fmt::Display for Diagnostic {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "{}: {}", self.severity, self.message)
+  }
+}
+
+/// Maps byte offsets in a Dockerfile to 1-based line and column numbers.
+#[derive(Debug, Clone)]
+pub struct LineIndex<'a> {
+  content: &'a str,
+  line_starts: Vec<usize>,
+}
+
+impl<'a> LineIndex<'a> {
+  pub fn new(content: &'a str) -> LineIndex<'a> {
+    let mut line_starts = vec![0];
+    line_starts.extend(content.match_indices('\n').map(|(i, _)| i + 1));
+
+    LineIndex { content, line_starts }
+  }
+
+  /// Returns the 1-based (line, column) of the given byte offset. Columns
+  /// count characters rather than bytes; offsets past the end of the content
+  /// are clamped to it.
+  pub fn position(&self, offset: usize) -> (usize, usize) {
+    let mut offset = offset.min(self.content.len());
+    while !self.content.is_char_boundary(offset) {
+      offset -= 1;
+    }
+
+    let line = match self.line_starts.binary_search(&offset) {
+      Ok(line) => line,
+      Err(next) => next - 1
+    };
+
+    let column = self.content[self.line_starts[line]..offset].chars().count();
+    (line + 1, column + 1)
+  }
+}
+
+pub(crate) fn write_json_string(out: &mut String, s: &str) {
+  out.push('"');
+  for c in s.chars() {
+    match c {
+      '"' => out.push_str("\\\""),
+      '\\' => out.push_str("\\\\"),
+      '\n' => out.push_str("\\n"),
+      '\r' => out.push_str("\\r"),
+      '\t' => out.push_str("\\t"),
+      c if (c as u32) < 0x20 => write!(out, "\\u{:04x}", c as u32).unwrap(),
+      c => out.push(c)
+    }
+  }
+  out.push('"');
+}
+
+fn write_json_range(out: &mut String, index: &LineIndex, span: &Span) {
+  if span.is_synthetic() {
+    out.push_str("null");
+    return;
+  }
+
+  let (start_line, start_column) = index.position(span.start);
+  let (end_line, end_column) = index.position(span.end);
+
+  write!(
+    out,
+    "{{\"start\": {{\"line\": {}, \"column\": {}}}, \"end\": {{\"line\": {}, \"column\": {}}}}}",
+    start_line, start_column, end_line, end_column
+  ).unwrap();
+}
+
+/// Renders diagnostics as JSON, e.g. for conversion to SARIF or CI
+/// annotations. `source` must be the Dockerfile content the diagnostics'
+/// spans refer to, and `source_name` is recorded as-is, e.g. a file path.
+///
+/// The output is an object with the following schema:
+///
+/// ```text
+/// {
+///   "source": string,
+///   "diagnostics": [{
+///     "code": string,               // e.g. "DFP1001", see DiagnosticKind::code()
+///     "severity": "info" | "warning" | "error",
+///     "message": string,
+///     "range": range,
+///     "related": [range],
+///     "fix": null | {"range": range, "replacement": string}
+///   }]
+/// }
+/// ```
+///
+/// where `range` is `{"start": position, "end": position}` and `position` is
+/// `{"line": number, "column": number}`, both 1-based. The end position is
+/// exclusive, and columns count characters. Synthetic spans (see
+/// `Span::SYNTHETIC`) are given as `null` ranges.
+///
+/// ```
+/// use dockerfile_parser::{diagnostics, Dockerfile};
+///
+/// let dockerfile = Dockerfile::parse("FROM alpine\nVOLUME []\n").unwrap();
+/// let json = diagnostics::to_json(&dockerfile.validate(), "Dockerfile", &dockerfile.content);
+/// assert!(json.contains(r#""code": "DFP1005""#));
+/// ```
+pub fn to_json(diagnostics: &[Diagnostic], source_name: &str, source: &str) -> String {
+  let index = LineIndex::new(source);
+
+  let mut out = String::from("{\n  \"source\": ");
+  write_json_string(&mut out, source_name);
+  out.push_str(",\n  \"diagnostics\": [");
+
+  for (i, diagnostic) in diagnostics.iter().enumerate() {
+    out.push_str(if i == 0 { "\n" } else { ",\n" });
+
+    writeln!(out, "    {{\n      \"code\": \"{}\",", diagnostic.code()).unwrap();
+    writeln!(out, "      \"severity\": \"{}\",", diagnostic.severity).unwrap();
+
+    out.push_str("      \"message\": ");
+    write_json_string(&mut out, &diagnostic.message);
+
+    out.push_str(",\n      \"range\": ");
+    write_json_range(&mut out, &index, &diagnostic.span);
+
+    out.push_str(",\n      \"related\": [");
+    for (j, span) in diagnostic.related_spans.iter().enumerate() {
+      if j > 0 {
+        out.push_str(", ");
+      }
+
+      write_json_range(&mut out, &index, span);
+    }
+
+    out.push_str("],\n      \"fix\": ");
+    match &diagnostic.suggested_fix {
+      Some(fix) => {
+        out.push_str("{\"range\": ");
+        write_json_range(&mut out, &index, &fix.span);
+        out.push_str(", \"replacement\": ");
+        write_json_string(&mut out, &fix.replacement);
+        out.push('}');
+      },
+      None => out.push_str("null")
+    }
+
+    out.push_str("\n    }");
+  }
+
+  if !diagnostics.is_empty() {
+    out.push_str("\n  ");
+  }
+
+  out.push_str("]\n}\n");
+  out
+}
+
+#[cfg(test)]
+mod tests {
+  use std::collections::HashSet;
+
+  use indoc::indoc;
+  use pretty_assertions::assert_eq;
+
+  use super::*;
+  use crate::{Dockerfile, StyleOptions};
+
+  #[test]
+  fn test_codes_unique() {
+    let codes: HashSet<_> = DiagnosticKind::ALL.iter().map(|k| k.code()).collect();
+    assert_eq!(codes.len(), DiagnosticKind::ALL.len());
+    assert!(codes.iter().all(|c| c.len() == 7 && c.starts_with("DFP")));
+  }
+
+  #[test]
+  fn test_line_index() {
+    let index = LineIndex::new("ab\nc\u{e9}d\n");
+    assert_eq!(index.position(0), (1, 1));
+    assert_eq!(index.position(2), (1, 3));
+    assert_eq!(index.position(3), (2, 1));
+    assert_eq!(index.position(6), (2, 3));
+    assert_eq!(index.position(8), (3, 1));
+    assert_eq!(index.position(100), (3, 1));
+  }
+
+  #[test]
+  fn test_to_json() {
+    let dockerfile = Dockerfile::parse(indoc!(r#"
+      FROM alpine
+      ENV a b
+      COPY --from=scratch "/é" /
+      VOLUME []
+    "#)).unwrap();
+
+    let mut diagnostics = dockerfile.validate();
+    diagnostics.extend(dockerfile.run_checks());
+    diagnostics.extend(dockerfile.check_style(&StyleOptions {
+      max_line_length: Some(25),
+      ..Default::default()
+    }));
+
+    assert_eq!(to_json(&diagnostics, "path/to/\"Dockerfile\"", &dockerfile.content), indoc!(r#"
+      {
+        "source": "path/to/\"Dockerfile\"",
+        "diagnostics": [
+          {
+            "code": "DFP1002",
+            "severity": "error",
+            "message": "cannot copy from scratch: the scratch image contains no files",
+            "range": {"start": {"line": 3, "column": 13}, "end": {"line": 3, "column": 20}},
+            "related": [],
+            "fix": null
+          },
+          {
+            "code": "DFP1005",
+            "severity": "error",
+            "message": "VOLUME requires at least one path",
+            "range": {"start": {"line": 4, "column": 1}, "end": {"line": 4, "column": 10}},
+            "related": [],
+            "fix": null
+          },
+          {
+            "code": "DFP2003",
+            "severity": "warning",
+            "message": "\"ENV key=value\" should be used instead of the legacy \"ENV key value\" syntax",
+            "range": {"start": {"line": 2, "column": 5}, "end": {"line": 2, "column": 8}},
+            "related": [],
+            "fix": {"range": {"start": {"line": 2, "column": 6}, "end": {"line": 2, "column": 7}}, "replacement": "="}
+          },
+          {
+            "code": "DFP3002",
+            "severity": "warning",
+            "message": "line is 26 characters long, more than the maximum of 25",
+            "range": {"start": {"line": 3, "column": 1}, "end": {"line": 3, "column": 27}},
+            "related": [],
+            "fix": null
+          }
+        ]
+      }
+    "#));
+
+    let synthetic = Diagnostic::warning(DiagnosticKind::EmptyVolume, Span::SYNTHETIC, "generated");
+    assert!(to_json(&[synthetic], "Dockerfile", "").contains(r#""range": null,"#));
+
+    assert_eq!(to_json(&[], "Dockerfile", ""), "{\n  \"source\": \"Dockerfile\",\n  \"diagnostics\": []\n}\n");
+  }
+}
@@ -0,0 +1,331 @@
+// (C) Copyright 2026 Hewlett Packard Enterprise Development LP
+
+use std::fmt;
+
+use lazy_static::lazy_static;
+use regex::Regex;
+
+use crate::image::ImageRef;
+use crate::splicer::Span;
+use crate::util::SpannedString;
+
+/// The byte order mark, which may precede the first directive.
+pub(crate) const BOM: &str = "\u{feff}";
+
+/// The directive names understood by Docker. Any other `# key=value` comment
+/// is treated as a regular comment, ending directive processing.
+const KNOWN_DIRECTIVES: &[&str] = &["syntax", "escape", "check"];
+
+/// A [parser directive], e.g. `# syntax=docker/dockerfile:1`.
+///
+/// Directives are special comments at the very top of a Dockerfile. Directive
+/// processing ends at the first line that isn't a directive, so directives
+/// following a blank line, regular comment, or instruction are just comments.
+///
+/// [parser directive]: https://docs.docker.com/engine/reference/builder/#parser-directives
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Directive {
+  /// The span of the entire directive line, excluding the line break
+  pub span: Span,
+
+  /// The directive name, as written
+  pub name: SpannedString,
+
+  /// The directive value, with surrounding whitespace removed
+  pub value: SpannedString,
+}
+
+impl Directive {
+  /// Determines if this directive has the given name, ignoring case.
+  pub fn is(&self, name: &str) -> bool {
+    self.name.content.eq_ignore_ascii_case(name)
+  }
+
+  /// If this is a `syntax` directive, parses its value as an image reference.
+  pub fn syntax_image(&self) -> Option<ImageRef> {
+    if self.is("syntax") {
+      Some(ImageRef::parse(&self.value.content))
+    } else {
+      None
+    }
+  }
+
+  /// If this is a `syntax` directive using a versioned release of the
+  /// official `docker/dockerfile` frontend, returns its version.
+  ///
+  /// Returns `None` for other frontends and for tags not naming a version,
+  /// e.g. `docker/dockerfile:latest` or a bare digest.
+  pub fn frontend_version(&self) -> Option<FrontendVersion> {
+    let image = self.syntax_image()?;
+
+    let registry = image.registry.as_deref().unwrap_or("docker.io");
+    let official = matches!(registry, "docker.io" | "index.docker.io")
+      && matches!(image.image.as_str(), "docker/dockerfile" | "docker/dockerfile-upstream");
+    if !official {
+      return None;
+    }
+
+    FrontendVersion::parse(image.tag.as_deref()?)
+  }
+}
+
+/// The version of the official `docker/dockerfile` frontend, as given by the
+/// tag of a `syntax` directive, e.g. `1.4` in `docker/dockerfile:1.4`.
+///
+/// A version without a minor component, e.g. `1`, floats to the newest
+/// release of that major version.
+///
+/// ```
+/// use dockerfile_parser::FrontendVersion;
+///
+/// let version = FrontendVersion::parse("1.4.3-labs").unwrap();
+/// assert_eq!(version, FrontendVersion::new(1, Some(4)));
+/// assert!(version.supports(FrontendVersion::new(1, Some(2))));
+/// assert!(!version.supports(FrontendVersion::new(1, Some(6))));
+/// assert!(FrontendVersion::parse("1").unwrap().supports(FrontendVersion::new(1, Some(6))));
+/// assert_eq!(FrontendVersion::parse("latest"), None);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct FrontendVersion {
+  pub major: u32,
+  pub minor: Option<u32>,
+}
+
+impl FrontendVersion {
+  pub const fn new(major: u32, minor: Option<u32>) -> FrontendVersion {
+    FrontendVersion { major, minor }
+  }
+
+  /// Parses a version tag, e.g. `1`, `1.4`, `1.4.3`, or `1.4-labs`. Patch
+  /// versions and channel suffixes are ignored. Returns `None` for tags not
+  /// naming a version, e.g. `latest` or `labs`.
+  pub fn parse(tag: &str) -> Option<FrontendVersion> {
+    let version = tag.split('-').next().unwrap_or(tag);
+    let mut parts = version.split('.');
+
+    let major = parts.next()?.parse().ok()?;
+    let minor = match parts.next() {
+      Some(minor) => Some(minor.parse().ok()?),
+      None => None
+    };
+
+    Some(FrontendVersion { major, minor })
+  }
+
+  /// Determines if this version includes features introduced in `required`.
+  pub fn supports(&self, required: FrontendVersion) -> bool {
+    if self.major != required.major {
+      return self.major > required.major;
+    }
+
+    match (self.minor, required.minor) {
+      (None, _) => true,
+      (Some(minor), required) => minor >= required.unwrap_or(0)
+    }
+  }
+}
+
+impl fmt::Display for FrontendVersion {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self.minor {
+      Some(minor) => write!(f, "{}.{}", self.major, minor),
+      None => write!(f, "{}", self.major)
+    }
+  }
+}
+
+/// Returns the byte offset just past the BOM, if any.
+pub(crate) fn content_start(content: &str) -> usize {
+  if content.starts_with(BOM) {
+    BOM.len()
+  } else {
+    0
+  }
+}
+
+/// Returns the span of a shebang on the first line (following the BOM, if
+/// any), e.g. `#!/usr/bin/env -S docker build -f`, excluding the line break.
+pub(crate) fn find_shebang(content: &str) -> Option<Span> {
+  let start = content_start(content);
+  if !content[start..].starts_with("#!") {
+    return None;
+  }
+
+  let line = content[start..].split('\n').next().unwrap_or("");
+  let line = line.strip_suffix('\r').unwrap_or(line);
+
+  Some(Span::new(start, start + line.len()))
+}
+
+/// Returns the byte offset at which parser directives may begin: just past
+/// the shebang line, if any, or otherwise past the BOM, if any.
+pub(crate) fn directives_start(content: &str) -> usize {
+  match find_shebang(content) {
+    Some(shebang) => content[shebang.end..]
+      .find('\n')
+      .map(|i| shebang.end + i + 1)
+      .unwrap_or(content.len()),
+    None => content_start(content)
+  }
+}
+
+/// Scans the parser directives at the top of a Dockerfile.
+///
+/// As in BuildKit, a shebang on the first line is skipped, so directives may
+/// follow it.
+pub(crate) fn find_directives(content: &str) -> Vec<Directive> {
+  lazy_static! {
+    static ref DIRECTIVE: Regex =
+      Regex::new(r"^#[ \t]*([a-zA-Z][a-zA-Z0-9]*)[ \t]*=[ \t]*(.*?)[ \t]*$").unwrap();
+  }
+
+  let mut directives: Vec<Directive> = Vec::new();
+  let mut offset = directives_start(content);
+
+  for line in content[offset..].split('\n') {
+    let line_start = offset;
+    offset += line.len() + 1;
+
+    let line = line.strip_suffix('\r').unwrap_or(line);
+    let captures = match DIRECTIVE.captures(line) {
+      Some(captures) => captures,
+      None => break
+    };
+
+    let name = captures.get(1).unwrap();
+    let value = captures.get(2).unwrap();
+    let known = KNOWN_DIRECTIVES.iter().any(|d| d.eq_ignore_ascii_case(name.as_str()));
+    let duplicate = directives.iter().any(|d| d.is(name.as_str()));
+    if !known || duplicate {
+      break;
+    }
+
+    directives.push(Directive {
+      span: Span::new(line_start, line_start + line.len()),
+      name: SpannedString {
+        span: Span::new(line_start + name.start(), line_start + name.end()),
+        content: name.as_str().to_string(),
+        raw_text: None,
+        quoted: false,
+      },
+      value: SpannedString {
+        span: Span::new(line_start + value.start(), line_start + value.end()),
+        content: value.as_str().to_string(),
+        raw_text: None,
+        quoted: false,
+      },
+    });
+  }
+
+  directives
+}
+
+#[cfg(test)]
+mod tests {
+  use indoc::indoc;
+  use pretty_assertions::assert_eq;
+
+  use super::*;
+
+  #[test]
+  fn test_find_directives() {
+    let content = indoc!(r#"
+      # syntax = docker/dockerfile:1.4
+      #ESCAPE=`
+      # check=skip=all
+      # syntax=ignored/duplicate
+      FROM alpine
+    "#);
+
+    let directives = find_directives(content);
+    assert_eq!(
+      directives.iter().map(|d| (d.name.as_ref(), d.value.as_ref())).collect::<Vec<_>>(),
+      vec![
+        ("syntax", "docker/dockerfile:1.4"),
+        ("ESCAPE", "`"),
+        ("check", "skip=all"),
+      ]
+    );
+
+    let d = &directives[0];
+    assert_eq!(&content[d.span.start..d.span.end], "# syntax = docker/dockerfile:1.4");
+    assert_eq!(&content[d.value.span.start..d.value.span.end], "docker/dockerfile:1.4");
+    assert_eq!(
+      d.syntax_image(),
+      Some(ImageRef::parse("docker/dockerfile:1.4"))
+    );
+    assert_eq!(directives[1].syntax_image(), None);
+  }
+
+  #[test]
+  fn test_frontend_version() {
+    let version = |content: &str| find_directives(content)[0].frontend_version();
+
+    assert_eq!(version("# syntax=docker/dockerfile:1.4"), Some(FrontendVersion::new(1, Some(4))));
+    assert_eq!(version("# syntax=docker/dockerfile:1"), Some(FrontendVersion::new(1, None)));
+    assert_eq!(
+      version("# syntax=docker.io/docker/dockerfile-upstream:1.7.1-labs@sha256:abc"),
+      Some(FrontendVersion::new(1, Some(7)))
+    );
+    assert_eq!(version("# syntax=docker/dockerfile"), None);
+    assert_eq!(version("# syntax=docker/dockerfile:labs"), None);
+    assert_eq!(version("# syntax=example.com/docker/dockerfile:1.4"), None);
+    assert_eq!(version("# syntax=tonistiigi/dockerfile:1.4"), None);
+    assert_eq!(version("# escape=`"), None);
+
+    let v1_4 = FrontendVersion::new(1, Some(4));
+    assert!(v1_4.supports(FrontendVersion::new(1, Some(4))));
+    assert!(v1_4.supports(FrontendVersion::new(1, None)));
+    assert!(!v1_4.supports(FrontendVersion::new(1, Some(5))));
+    assert!(!v1_4.supports(FrontendVersion::new(2, Some(0))));
+    assert!(FrontendVersion::new(2, Some(0)).supports(v1_4));
+    assert_eq!(v1_4.to_string(), "1.4");
+  }
+
+  #[test]
+  fn test_find_directives_end() {
+    // a blank line, regular comment, or unknown directive ends directives
+    assert!(find_directives("\n# syntax=docker/dockerfile:1\n").is_empty());
+    assert!(find_directives("# hello\n# syntax=docker/dockerfile:1\n").is_empty());
+    assert!(find_directives("# foo=bar\n# syntax=docker/dockerfile:1\n").is_empty());
+    assert!(find_directives("FROM alpine\n# syntax=docker/dockerfile:1\n").is_empty());
+
+    let content = "\u{feff}# syntax=docker/dockerfile:1\r\nFROM alpine\r\n";
+    let directives = find_directives(content);
+    assert_eq!(directives.len(), 1);
+    assert_eq!(directives[0].value.content, "docker/dockerfile:1");
+    assert_eq!(directives[0].span.start, 3);
+  }
+
+  #[test]
+  fn test_find_directives_shebang() {
+    let content = indoc!(r#"
+      #!/usr/bin/env -S docker build -f
+      # syntax=docker/dockerfile:1
+      # escape=\
+      FROM alpine
+    "#);
+
+    assert_eq!(find_shebang(content), Some(Span::new(0, 33)));
+    let directives = find_directives(content);
+    assert_eq!(
+      directives.iter().map(|d| d.name.as_ref()).collect::<Vec<_>>(),
+      vec!["syntax", "escape"]
+    );
+    assert_eq!(directives[0].span.start, 34);
+
+    let content = "\u{feff}#!/bin/dockerfile\r\n# syntax=docker/dockerfile:1\r\nFROM alpine\r\n";
+    assert_eq!(find_shebang(content), Some(Span::new(3, 20)));
+    assert_eq!(find_directives(content)[0].value.content, "docker/dockerfile:1");
+
+    // only the first line may be a shebang
+    assert_eq!(find_shebang("# syntax=docker/dockerfile:1\n#!/bin/sh\n"), None);
+    assert_eq!(find_directives("# syntax=docker/dockerfile:1\n#!/bin/sh\n").len(), 1);
+    assert_eq!(find_shebang("#!/bin/sh"), Some(Span::new(0, 9)));
+    assert!(find_directives("#!/bin/sh").is_empty());
+
+    // frontmatter fences are regular comments, and end directives
+    let content = "#!/bin/dockerfile\n# ---\n# syntax=docker/dockerfile:1\n# ---\nFROM alpine\n";
+    assert!(find_directives(content).is_empty());
+  }
+}
@@ -1,18 +1,38 @@
 // (C) Copyright 2019-2020 Hewlett Packard Enterprise Development LP
 
+use std::borrow::Cow;
 use std::convert::TryFrom;
 use std::io::{Read, BufReader};
 use std::str::FromStr;
 
 use pest::Parser;
+use pest::error::{InputLocation, LineColLocation};
 use snafu::ResultExt;
 
-pub use crate::image::*;
-pub use crate::error::*;
-pub use crate::parser::*;
-pub use crate::instructions::*;
-pub use crate::splicer::*;
-pub use crate::stage::*;
+use crate::error::*;
+use crate::parser::*;
+use crate::instructions::*;
+use crate::splicer::*;
+use crate::stage::*;
+
+use crate::checks::run_checks;
+use crate::diagnostics::Diagnostic;
+use crate::directive::{find_directives, find_shebang, Directive, BOM};
+use crate::editor::DockerfileEditor;
+use crate::heredoc::{attach_heredocs, find_heredocs_checked, mask_heredocs};
+use crate::incremental::reparse_edit;
+use crate::lock::{image_lock_entries, ImageLockEntry};
+use crate::metrics::check_style;
+use crate::options::{ParseOptions, StyleOptions, UnknownPolicy, ValidateOptions};
+use crate::shift::{ShiftSpans, SpanVisitor, VisitSpans};
+use crate::stage::stage_boundaries;
+use crate::trivia::{find_trivia, TriviaKind, TriviaSpan};
+use crate::util::{
+  BreakableString, BreakableStringComponent, FlagValue, PairSeparator, ShellOrExecExpr,
+  SpannedString
+};
+use crate::validate::validate;
+use crate::variables::{find_references, ExpansionBehavior, VarRef};
 
 /// A single Dockerfile instruction.
 ///
@@ -23,7 +43,7 @@ pub use crate::stage::*;
 ///
 /// ```
 /// use std::convert::TryInto;
-/// use dockerfile_parser::*;
+/// use dockerfile_parser::prelude::*;
 ///
 /// let dockerfile = Dockerfile::parse("FROM alpine:3.11").unwrap();
 /// let from: &FromInstruction = dockerfile.instructions
@@ -41,10 +61,154 @@ pub enum Instruction {
   Entrypoint(EntrypointInstruction),
   Cmd(CmdInstruction),
   Copy(CopyInstruction),
+  Add(AddInstruction),
   Env(EnvInstruction),
+  Volume(VolumeInstruction),
+  User(UserInstruction),
+  Workdir(WorkdirInstruction),
+  Healthcheck(HealthcheckInstruction),
+  Shell(ShellInstruction),
+  Stopsignal(StopsignalInstruction),
+  Onbuild(OnbuildInstruction),
+  Maintainer(MaintainerInstruction),
   Misc(MiscInstruction)
 }
 
+/// The kind of an `Instruction`, without any of its content.
+///
+/// Note that all unsupported instructions share the `Misc` kind.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub enum InstructionKind {
+  From,
+  Arg,
+  Label,
+  Run,
+  Entrypoint,
+  Cmd,
+  Copy,
+  Add,
+  Env,
+  Volume,
+  User,
+  Workdir,
+  Healthcheck,
+  Shell,
+  Stopsignal,
+  Onbuild,
+  Maintainer,
+  Misc
+}
+
+/// All instruction keywords recognized by Docker, including those parsed as
+/// `Instruction::Misc`.
+pub(crate) const INSTRUCTION_KEYWORDS: &[&str] = &[
+  "ADD", "ARG", "CMD", "COPY", "ENTRYPOINT", "ENV", "EXPOSE", "FROM",
+  "HEALTHCHECK", "LABEL", "MAINTAINER", "ONBUILD", "RUN", "SHELL",
+  "STOPSIGNAL", "USER", "VOLUME", "WORKDIR",
+];
+
+/// Determines if a token is an instruction keyword, ignoring case.
+pub(crate) fn is_instruction_keyword(token: &str) -> bool {
+  INSTRUCTION_KEYWORDS.iter().any(|k| k.eq_ignore_ascii_case(token))
+}
+
+/// A misplaced space around the `=` of an `ENV`, `ARG`, or `LABEL` pair, e.g.
+/// `ENV key= value`; see `ParseOptions::lenient`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct PairGap {
+  pub keyword: &'static str,
+  pub span: Span,
+
+  /// If true, the space follows the `=`; otherwise it precedes it
+  pub after: bool,
+}
+
+impl PairGap {
+  pub(crate) fn message(&self) -> String {
+    format!(
+      "unexpected space {} '=' in {} pair",
+      if self.after { "after" } else { "before" },
+      self.keyword
+    )
+  }
+}
+
+/// Finds misplaced spaces around the `=` of key=value pairs. `separator` is
+/// the span between a pair's key and value.
+fn pair_gaps(content: &str, keyword: &'static str, separator: Span, gaps: &mut Vec<PairGap>) {
+  let eq = match content[separator.start..separator.end].find('=') {
+    Some(eq) => separator.start + eq,
+    None => return
+  };
+
+  if eq > separator.start {
+    gaps.push(PairGap { keyword, span: Span::new(separator.start, eq), after: false });
+  }
+
+  if eq + 1 < separator.end {
+    gaps.push(PairGap { keyword, span: Span::new(eq + 1, separator.end), after: true });
+  }
+}
+
+/// Finds all misplaced spaces around the `=` of key=value pairs in the given
+/// instructions, which must have spans relative to `content`.
+pub(crate) fn find_pair_gaps(content: &str, instructions: &[Instruction]) -> Vec<PairGap> {
+  let mut gaps = Vec::new();
+
+  for ins in instructions {
+    match ins {
+      Instruction::Env(env) => for var in &env.vars {
+        if let PairSeparator::Equals(span) = var.separator {
+          pair_gaps(content, "ENV", span, &mut gaps);
+        }
+      },
+      Instruction::Label(label) => for l in &label.labels {
+        if let PairSeparator::Equals(span) = l.separator {
+          pair_gaps(content, "LABEL", span, &mut gaps);
+        }
+      },
+      Instruction::Arg(arg) => if let Some(value) = &arg.value {
+        pair_gaps(content, "ARG", Span::new(arg.name.span.end, value.span.start), &mut gaps);
+      },
+      _ => ()
+    }
+  }
+
+  gaps
+}
+
+/// Returns the edit distance between two strings, ignoring case.
+pub(crate) fn edit_distance(a: &str, b: &str) -> usize {
+  let a: Vec<char> = a.to_ascii_uppercase().chars().collect();
+  let b: Vec<char> = b.to_ascii_uppercase().chars().collect();
+  let mut row: Vec<usize> = (0..=b.len()).collect();
+
+  for i in 1..=a.len() {
+    let mut diagonal = row[0];
+    row[0] = i;
+
+    for j in 1..=b.len() {
+      let cost = if a[i - 1] == b[j - 1] { 0 } else { 1 };
+      let next = (row[j] + 1).min(row[j - 1] + 1).min(diagonal + cost);
+      diagonal = row[j];
+      row[j] = next;
+    }
+  }
+
+  row[b.len()]
+}
+
+/// Finds the instruction keyword most similar to an unknown token, if any is
+/// close enough to plausibly be a typo.
+pub(crate) fn closest_keyword(token: &str) -> Option<&'static str> {
+  INSTRUCTION_KEYWORDS
+    .iter()
+    .map(|k| (edit_distance(token, k), *k))
+    .filter(|(distance, _)| *distance <= 2 && *distance < token.len())
+    .min_by_key(|(distance, _)| *distance)
+    .map(|(_, k)| k)
+}
+
 impl Instruction {
   /// Attempts to convert this instruction into a FromInstruction, returning
   /// None if impossible.
@@ -163,6 +327,24 @@ impl Instruction {
     }
   }
 
+  /// Attempts to convert this instruction into an AddInstruction, returning
+  /// None if impossible.
+  pub fn into_add(self) -> Option<AddInstruction> {
+    match self {
+      Instruction::Add(a) => Some(a),
+      _ => None,
+    }
+  }
+
+  /// Attempts to convert this instruction into an AddInstruction, returning
+  /// None if impossible.
+  pub fn as_add(&self) -> Option<&AddInstruction> {
+    match self {
+      Instruction::Add(a) => Some(a),
+      _ => None,
+    }
+  }
+
   /// Attempts to convert this instruction into a CopyInstruction, returning
   /// None if impossible.
   pub fn as_copy(&self) -> Option<&CopyInstruction> {
@@ -190,6 +372,150 @@ impl Instruction {
     }
   }
 
+  /// Attempts to convert this instruction into a VolumeInstruction, returning
+  /// None if impossible.
+  pub fn into_volume(self) -> Option<VolumeInstruction> {
+    match self {
+      Instruction::Volume(v) => Some(v),
+      _ => None,
+    }
+  }
+
+  /// Attempts to convert this instruction into a VolumeInstruction, returning
+  /// None if impossible.
+  pub fn as_volume(&self) -> Option<&VolumeInstruction> {
+    match self {
+      Instruction::Volume(v) => Some(v),
+      _ => None,
+    }
+  }
+
+  /// Attempts to convert this instruction into a UserInstruction, returning
+  /// None if impossible.
+  pub fn into_user(self) -> Option<UserInstruction> {
+    match self {
+      Instruction::User(u) => Some(u),
+      _ => None,
+    }
+  }
+
+  /// Attempts to convert this instruction into a UserInstruction, returning
+  /// None if impossible.
+  pub fn as_user(&self) -> Option<&UserInstruction> {
+    match self {
+      Instruction::User(u) => Some(u),
+      _ => None,
+    }
+  }
+
+  /// Attempts to convert this instruction into a WorkdirInstruction,
+  /// returning None if impossible.
+  pub fn into_workdir(self) -> Option<WorkdirInstruction> {
+    match self {
+      Instruction::Workdir(w) => Some(w),
+      _ => None,
+    }
+  }
+
+  /// Attempts to convert this instruction into a WorkdirInstruction,
+  /// returning None if impossible.
+  pub fn as_workdir(&self) -> Option<&WorkdirInstruction> {
+    match self {
+      Instruction::Workdir(w) => Some(w),
+      _ => None,
+    }
+  }
+
+  /// Attempts to convert this instruction into a HealthcheckInstruction,
+  /// returning None if impossible.
+  pub fn into_healthcheck(self) -> Option<HealthcheckInstruction> {
+    match self {
+      Instruction::Healthcheck(h) => Some(h),
+      _ => None,
+    }
+  }
+
+  /// Attempts to convert this instruction into a HealthcheckInstruction,
+  /// returning None if impossible.
+  pub fn as_healthcheck(&self) -> Option<&HealthcheckInstruction> {
+    match self {
+      Instruction::Healthcheck(h) => Some(h),
+      _ => None,
+    }
+  }
+
+  /// Attempts to convert this instruction into a ShellInstruction, returning
+  /// None if impossible.
+  pub fn into_shell(self) -> Option<ShellInstruction> {
+    match self {
+      Instruction::Shell(s) => Some(s),
+      _ => None,
+    }
+  }
+
+  /// Attempts to convert this instruction into a ShellInstruction, returning
+  /// None if impossible.
+  pub fn as_shell(&self) -> Option<&ShellInstruction> {
+    match self {
+      Instruction::Shell(s) => Some(s),
+      _ => None,
+    }
+  }
+
+  /// Attempts to convert this instruction into a StopsignalInstruction,
+  /// returning None if impossible.
+  pub fn into_stopsignal(self) -> Option<StopsignalInstruction> {
+    match self {
+      Instruction::Stopsignal(s) => Some(s),
+      _ => None,
+    }
+  }
+
+  /// Attempts to convert this instruction into a StopsignalInstruction,
+  /// returning None if impossible.
+  pub fn as_stopsignal(&self) -> Option<&StopsignalInstruction> {
+    match self {
+      Instruction::Stopsignal(s) => Some(s),
+      _ => None,
+    }
+  }
+
+  /// Attempts to convert this instruction into an OnbuildInstruction,
+  /// returning None if impossible.
+  pub fn into_onbuild(self) -> Option<OnbuildInstruction> {
+    match self {
+      Instruction::Onbuild(o) => Some(o),
+      _ => None,
+    }
+  }
+
+  /// Attempts to convert this instruction into an OnbuildInstruction,
+  /// returning None if impossible.
+  pub fn as_onbuild(&self) -> Option<&OnbuildInstruction> {
+    match self {
+      Instruction::Onbuild(o) => Some(o),
+      _ => None,
+    }
+  }
+
+  /// Attempts to convert this instruction into a MaintainerInstruction,
+  /// returning None if impossible.
+  pub fn into_maintainer(self) -> Option<MaintainerInstruction> {
+    match self {
+      Instruction::Maintainer(m) => Some(m),
+      _ => None,
+    }
+  }
+
+  /// Attempts to convert this instruction into a MaintainerInstruction,
+  /// returning None if impossible.
+  pub fn as_maintainer(&self) -> Option<&MaintainerInstruction> {
+    match self {
+      Instruction::Maintainer(m) => Some(m),
+      _ => None,
+    }
+  }
+
   /// Attempts to convert this instruction into a MiscInstruction, returning
   /// None if impossible.
   pub fn into_misc(self) -> Option<MiscInstruction> {
@@ -208,6 +534,214 @@ impl Instruction {
     }
   }
 
+  /// Gets the kind of the instruction.
+  pub fn kind(&self) -> InstructionKind {
+    match self {
+      Instruction::From(_) => InstructionKind::From,
+      Instruction::Arg(_) => InstructionKind::Arg,
+      Instruction::Label(_) => InstructionKind::Label,
+      Instruction::Run(_) => InstructionKind::Run,
+      Instruction::Entrypoint(_) => InstructionKind::Entrypoint,
+      Instruction::Cmd(_) => InstructionKind::Cmd,
+      Instruction::Copy(_) => InstructionKind::Copy,
+      Instruction::Add(_) => InstructionKind::Add,
+      Instruction::Env(_) => InstructionKind::Env,
+      Instruction::Volume(_) => InstructionKind::Volume,
+      Instruction::User(_) => InstructionKind::User,
+      Instruction::Workdir(_) => InstructionKind::Workdir,
+      Instruction::Healthcheck(_) => InstructionKind::Healthcheck,
+      Instruction::Shell(_) => InstructionKind::Shell,
+      Instruction::Stopsignal(_) => InstructionKind::Stopsignal,
+      Instruction::Onbuild(_) => InstructionKind::Onbuild,
+      Instruction::Maintainer(_) => InstructionKind::Maintainer,
+      Instruction::Misc(_) => InstructionKind::Misc,
+    }
+  }
+
+  /// Determines how variable references in this instruction's arguments are
+  /// expanded.
+  ///
+  /// Shell-form `RUN`, `CMD`, and `ENTRYPOINT` instructions leave references to
+  /// the shell, while their exec forms never expand references at all. Most
+  /// other instructions are expanded by Docker. Unsupported instructions are
+  /// classified by keyword, and unknown keywords are assumed not to expand.
+  pub fn expands_variables(&self) -> ExpansionBehavior {
+    let shell_or_exec = |expr: &ShellOrExecExpr| match expr {
+      ShellOrExecExpr::Shell(_) => ExpansionBehavior::ShellExpands,
+      ShellOrExecExpr::Exec(_) => ExpansionBehavior::NoExpansion,
+    };
+
+    match self {
+      Instruction::From(_)
+      | Instruction::Arg(_)
+      | Instruction::Label(_)
+      | Instruction::Copy(_)
+      | Instruction::Add(_)
+      | Instruction::Env(_)
+      | Instruction::Volume(_)
+      | Instruction::User(_)
+      | Instruction::Workdir(_)
+      | Instruction::Stopsignal(_) => ExpansionBehavior::DockerExpands,
+      Instruction::Run(run) => shell_or_exec(&run.expr),
+      Instruction::Entrypoint(entrypoint) => shell_or_exec(&entrypoint.expr),
+      Instruction::Cmd(cmd) => shell_or_exec(&cmd.expr),
+      // only the CMD form runs anything
+      Instruction::Healthcheck(healthcheck) => match healthcheck.cmd() {
+        Some(cmd) => shell_or_exec(&cmd.expr),
+        None => ExpansionBehavior::NoExpansion
+      },
+      Instruction::Shell(_) => ExpansionBehavior::NoExpansion,
+      // the trigger only runs in downstream builds
+      Instruction::Onbuild(_) => ExpansionBehavior::NoExpansion,
+      Instruction::Maintainer(_) => ExpansionBehavior::NoExpansion,
+      Instruction::Misc(misc) => {
+        match misc.instruction.content.to_ascii_uppercase().as_str() {
+          "EXPOSE" => ExpansionBehavior::DockerExpands,
+          _ => ExpansionBehavior::NoExpansion
+        }
+      },
+    }
+  }
+
+  /// Finds all variable references in this instruction that are substituted
+  /// by Docker itself or by a shell, with spans relative to the original
+  /// Dockerfile.
+  ///
+  /// References are returned if this instruction's `expands_variables()` is
+  /// `ExpansionBehavior::DockerExpands`. References in the bodies of `RUN`
+  /// heredocs with unquoted delimiters are also returned and tagged as
+  /// `ExpansionBehavior::ShellExpands`. Other references left to a shell, as
+  /// in shell-form `RUN`, and references in exec arrays are not included; see
+  /// `all_variable_references()`.
+  pub fn variable_references(&self) -> Vec<VarRef> {
+    self.find_variable_references(false)
+  }
+
+  /// Like `variable_references()`, but also includes references that may be
+  /// expanded by a shell, e.g. in shell-form `RUN`, and references that are
+  /// never expanded, e.g. in exec arrays or quoted-delimiter heredocs. Each
+  /// reference's `expansion` describes how it is treated.
+  pub fn all_variable_references(&self) -> Vec<VarRef> {
+    self.find_variable_references(true)
+  }
+
+  fn find_variable_references(&self, include_shell_context: bool) -> Vec<VarRef> {
+    let expansion = self.expands_variables();
+    let include = include_shell_context || expansion == ExpansionBehavior::DockerExpands;
+
+    let mut strings: Vec<&SpannedString> = Vec::new();
+    let mut breakables: Vec<&BreakableString> = Vec::new();
+    let mut expr = None;
+
+    match self {
+      Instruction::From(from) => {
+        strings.extend(from.flags.iter().map(|f| &f.value));
+        strings.push(&from.image);
+      },
+      Instruction::Arg(arg) => strings.extend(&arg.value),
+      Instruction::Label(label) => {
+        for l in &label.labels {
+          strings.push(&l.name);
+          strings.push(&l.value);
+        }
+      },
+      Instruction::Copy(copy) => {
+        strings.extend(copy.flags.iter().map(|f| &f.value));
+        strings.extend(&copy.sources);
+        strings.push(&copy.destination);
+      },
+      Instruction::Add(add) => {
+        strings.extend(add.flags.iter().map(|f| &f.value));
+        strings.extend(&add.sources);
+        strings.push(&add.destination);
+      },
+      Instruction::Env(env) => {
+        for var in &env.vars {
+          strings.push(&var.key);
+          breakables.push(&var.value);
+        }
+      },
+      Instruction::Volume(volume) => strings.extend(&volume.paths),
+      Instruction::User(user) => {
+        strings.push(&user.user);
+        strings.extend(&user.group);
+      },
+      Instruction::Workdir(workdir) => strings.push(&workdir.path),
+      Instruction::Run(run) => expr = Some(&run.expr),
+      Instruction::Entrypoint(entrypoint) => expr = Some(&entrypoint.expr),
+      Instruction::Cmd(cmd) => expr = Some(&cmd.expr),
+      Instruction::Healthcheck(healthcheck) => {
+        strings.extend(healthcheck.flags.iter().map(|f| &f.value));
+        expr = healthcheck.cmd().map(|cmd| &cmd.expr);
+      },
+      Instruction::Shell(shell) => strings.extend(&shell.shell.elements),
+      Instruction::Stopsignal(stopsignal) => strings.push(&stopsignal.signal),
+      Instruction::Onbuild(onbuild) => {
+        return if include_shell_context {
+          onbuild.trigger
+            .all_variable_references()
+            .into_iter()
+            .map(|r| VarRef { expansion, ..r })
+            .collect()
+        } else {
+          Vec::new()
+        };
+      },
+      // the name is never expanded, and continuations are removed from its
+      // content, so its references can't be located precisely
+      Instruction::Maintainer(_) => (),
+      Instruction::Misc(misc) => breakables.push(&misc.arguments),
+    }
+
+    match expr {
+      Some(ShellOrExecExpr::Shell(shell)) => breakables.push(shell),
+      Some(ShellOrExecExpr::Exec(exec)) => strings.extend(&exec.elements),
+      None => ()
+    }
+
+    let mut refs: Vec<VarRef> = Vec::new();
+    if include {
+      refs.extend(strings.into_iter().flat_map(find_references));
+      for breakable in breakables {
+        refs.extend(breakable.iter_components().flat_map(|c| match c {
+          BreakableStringComponent::String(s) => find_references(s),
+          BreakableStringComponent::Comment(_) => Vec::new()
+        }));
+      }
+
+      for r in &mut refs {
+        r.expansion = expansion;
+      }
+    }
+
+    // heredoc bodies are expanded unless their delimiter is quoted: by the
+    // shell for RUN, and by Docker itself for COPY and ADD
+    let (heredocs, body_expansion) = match self {
+      Instruction::Run(run) => (run.heredocs.as_slice(), ExpansionBehavior::ShellExpands),
+      Instruction::Copy(copy) => (copy.heredocs.as_slice(), ExpansionBehavior::DockerExpands),
+      Instruction::Add(add) => (add.heredocs.as_slice(), ExpansionBehavior::DockerExpands),
+      _ => (&[][..], ExpansionBehavior::NoExpansion)
+    };
+
+    for heredoc in heredocs {
+      if !heredoc.expand && !include_shell_context {
+        continue;
+      }
+
+      refs.extend(find_references(&heredoc.body).into_iter().map(|r| VarRef {
+        expansion: if heredoc.expand {
+          body_expansion
+        } else {
+          ExpansionBehavior::NoExpansion
+        },
+        ..r
+      }));
+    }
+
+    refs.sort_by_key(|r| r.span.start);
+    refs
+  }
+
   /// Gets the span of the instruction.
   pub fn span(&self) -> Span {
     match self {
@@ -218,10 +752,118 @@ impl Instruction {
       Instruction::Entrypoint(instruction) => instruction.span,
       Instruction::Cmd(instruction) => instruction.span,
       Instruction::Copy(instruction) => instruction.span,
+      Instruction::Add(instruction) => instruction.span,
       Instruction::Env(instruction) => instruction.span,
+      Instruction::Volume(instruction) => instruction.span,
+      Instruction::User(instruction) => instruction.span,
+      Instruction::Workdir(instruction) => instruction.span,
+      Instruction::Healthcheck(instruction) => instruction.span,
+      Instruction::Shell(instruction) => instruction.span,
+      Instruction::Stopsignal(instruction) => instruction.span,
+      Instruction::Onbuild(instruction) => instruction.span,
+      Instruction::Maintainer(instruction) => instruction.span,
       Instruction::Misc(instruction) => instruction.span,
     }
   }
+
+  fn span_mut(&mut self) -> &mut Span {
+    match self {
+      Instruction::From(instruction) => &mut instruction.span,
+      Instruction::Arg(instruction) => &mut instruction.span,
+      Instruction::Label(instruction) => &mut instruction.span,
+      Instruction::Run(instruction) => &mut instruction.span,
+      Instruction::Entrypoint(instruction) => &mut instruction.span,
+      Instruction::Cmd(instruction) => &mut instruction.span,
+      Instruction::Copy(instruction) => &mut instruction.span,
+      Instruction::Add(instruction) => &mut instruction.span,
+      Instruction::Env(instruction) => &mut instruction.span,
+      Instruction::Volume(instruction) => &mut instruction.span,
+      Instruction::User(instruction) => &mut instruction.span,
+      Instruction::Workdir(instruction) => &mut instruction.span,
+      Instruction::Healthcheck(instruction) => &mut instruction.span,
+      Instruction::Shell(instruction) => &mut instruction.span,
+      Instruction::Stopsignal(instruction) => &mut instruction.span,
+      Instruction::Onbuild(instruction) => &mut instruction.span,
+      Instruction::Maintainer(instruction) => &mut instruction.span,
+      Instruction::Misc(instruction) => &mut instruction.span,
+    }
+  }
+
+  /// Returns the value of the last flag of this instruction with the given
+  /// name, compared case-insensitively, if any. Only `FROM`, `RUN`, `COPY`,
+  /// `ADD`, and `HEALTHCHECK` instructions have flags.
+  pub fn flag_value(&self, name: &str) -> Option<FlagValue> {
+    let matches = |n: &SpannedString| n.content.eq_ignore_ascii_case(name);
+
+    match self {
+      Instruction::From(from) => from.flags.iter().rev().find(|f| matches(&f.name)).map(|f| f.flag_value()),
+      Instruction::Run(run) => run.flags.iter().rev().find(|f| matches(&f.name)).map(|f| f.flag_value()),
+      Instruction::Copy(copy) => copy.flag(name).map(|f| f.flag_value()),
+      Instruction::Add(add) => add.flag(name).map(|f| f.flag_value()),
+      Instruction::Healthcheck(healthcheck) => {
+        healthcheck.flags.iter().rev().find(|f| matches(&f.name)).map(|f| f.flag_value())
+      },
+      _ => None
+    }
+  }
+
+  /// Returns the value of a boolean-style flag of this instruction, e.g.
+  /// `COPY --link`, if it is set. Bare flags are true, and `true`, `false`,
+  /// `1`, and `0` are accepted as values, ignoring case; other values return
+  /// `Error::InvalidBoolFlag`.
+  ///
+  /// ```
+  /// use dockerfile_parser::Dockerfile;
+  ///
+  /// let dockerfile = Dockerfile::parse(
+  ///   "COPY --link --parents=false --chmod=755 a /b"
+  /// ).unwrap();
+  ///
+  /// let copy = &dockerfile.instructions[0];
+  /// assert_eq!(copy.flag_bool("link").unwrap().unwrap(), true);
+  /// assert_eq!(copy.flag_bool("parents").unwrap().unwrap(), false);
+  /// assert!(copy.flag_bool("chmod").unwrap().is_err());
+  /// assert!(copy.flag_bool("from").is_none());
+  /// ```
+  pub fn flag_bool(&self, name: &str) -> Option<Result<bool>> {
+    self.flag_value(name).map(|value| value.to_bool(name))
+  }
+
+  /// Returns the span of this instruction's keyword, e.g. `COPY`.
+  pub fn keyword_span(&self) -> Span {
+    let start = self.span().start;
+    match (self, self.kind().keyword()) {
+      (Instruction::Misc(misc), _) => misc.instruction.span,
+      (_, Some(keyword)) => Span::new(start, start + keyword.len()),
+      (_, None) => Span::new(start, start),
+    }
+  }
+
+  /// Returns the whitespace preceding this instruction on its first line,
+  /// e.g. `"  "` for `  RUN true`. Instruction spans always start at the
+  /// keyword, so the indentation isn't part of the instruction.
+  ///
+  /// Returns an empty string for synthetic spans.
+  ///
+  /// ```
+  /// use dockerfile_parser::Dockerfile;
+  ///
+  /// let dockerfile = Dockerfile::parse("FROM alpine\n\t  RUN true\n").unwrap();
+  /// assert_eq!(dockerfile.instructions[0].indentation(&dockerfile), "");
+  /// assert_eq!(dockerfile.instructions[1].indentation(&dockerfile), "\t  ");
+  /// ```
+  pub fn indentation<'d>(&self, dockerfile: &'d Dockerfile) -> &'d str {
+    let span = self.span();
+    if span.is_synthetic() {
+      return "";
+    }
+
+    let start = span.start - dockerfile.base_offset;
+    let line_start = dockerfile.content[..start].rfind('\n').map(|i| i + 1).unwrap_or(0);
+    let prefix = dockerfile.content[line_start..start].trim_start_matches(BOM);
+
+    &prefix[prefix.trim_end_matches([' ', '\t']).len()..]
+  }
 }
 
 /// Maps an instruction struct to its enum variant, implementing From<T> on
@@ -243,15 +885,28 @@ impl_from_instruction!(RunInstruction, Instruction::Run);
 impl_from_instruction!(EntrypointInstruction, Instruction::Entrypoint);
 impl_from_instruction!(CmdInstruction, Instruction::Cmd);
 impl_from_instruction!(CopyInstruction, Instruction::Copy);
+impl_from_instruction!(AddInstruction, Instruction::Add);
 impl_from_instruction!(EnvInstruction, Instruction::Env);
+impl_from_instruction!(VolumeInstruction, Instruction::Volume);
+impl_from_instruction!(UserInstruction, Instruction::User);
+impl_from_instruction!(WorkdirInstruction, Instruction::Workdir);
+impl_from_instruction!(HealthcheckInstruction, Instruction::Healthcheck);
+impl_from_instruction!(ShellInstruction, Instruction::Shell);
+impl_from_instruction!(StopsignalInstruction, Instruction::Stopsignal);
+impl_from_instruction!(OnbuildInstruction, Instruction::Onbuild);
+impl_from_instruction!(MaintainerInstruction, Instruction::Maintainer);
 impl_from_instruction!(MiscInstruction, Instruction::Misc);
 
 impl TryFrom<Pair<'_>> for Instruction {
   type Error = Error;
 
   fn try_from(record: Pair) -> std::result::Result<Self, Self::Error> {
-    let instruction: Instruction = match record.as_rule() {
-      Rule::from => FromInstruction::from_record(record, 0)?.into(),
+    // pair lists may consume trailing whitespace, e.g. `ENV a=b  `, which is
+    // excluded so that spans end at the last argument character
+    let end = record.as_span().start() + record.as_str().trim_end().len();
+
+    let mut instruction: Instruction = match record.as_rule() {
+      Rule::from => FromInstruction::from_record(record)?.into(),
       Rule::arg => ArgInstruction::from_record(record)?.into(),
       Rule::label => LabelInstruction::from_record(record)?.into(),
 
@@ -262,9 +917,19 @@ impl TryFrom<Pair<'_>> for Instruction {
       Rule::cmd => CmdInstruction::from_record(record)?.into(),
 
       Rule::copy => Instruction::Copy(CopyInstruction::from_record(record)?),
+      Rule::add => AddInstruction::from_record(record)?.into(),
 
       Rule::env => EnvInstruction::from_record(record)?.into(),
 
+      Rule::volume => VolumeInstruction::from_record(record)?.into(),
+      Rule::user => UserInstruction::from_record(record)?.into(),
+      Rule::workdir => WorkdirInstruction::from_record(record)?.into(),
+      Rule::healthcheck => HealthcheckInstruction::from_record(record)?.into(),
+      Rule::shell => ShellInstruction::from_record(record)?.into(),
+      Rule::stopsignal => StopsignalInstruction::from_record(record)?.into(),
+      Rule::onbuild => OnbuildInstruction::from_record(record)?.into(),
+      Rule::maintainer => MaintainerInstruction::from_record(record)?.into(),
+
       Rule::misc => MiscInstruction::from_record(record)?.into(),
 
       // TODO: consider exposing comments
@@ -272,6 +937,7 @@ impl TryFrom<Pair<'_>> for Instruction {
       _ => return Err(unexpected_token(record))
     };
 
+    instruction.span_mut().end = end;
     Ok(instruction)
   }
 }
@@ -306,22 +972,123 @@ pub struct Dockerfile {
   /// The raw content of the Dockerfile
   pub content: String,
 
-  /// An ordered list of parsed ARG instructions preceding the first FROM
-  pub global_args: Vec<ArgInstruction>,
+  /// The indices in `instructions` of all ARG instructions preceding the
+  /// first FROM, in order; see `global_args()`.
+  ///
+  /// If instructions are inserted into or removed from `instructions`, these
+  /// must be updated to match. Instructions may be modified in place, e.g. to
+  /// change an ARG's default, without updating them.
+  pub global_arg_indices: Vec<usize>,
+
+  /// An ordered list of all parsed instructions, including global ARGs
+  ///
+  /// Instruction spans are ordered and never overlap. Comments and whitespace
+  /// between instructions (including parser directives) are not part of any
+  /// instruction's span.
+  pub instructions: Vec<Instruction>,
+
+  /// Whitespace, blank lines, and comments between instructions, if parsed
+  /// with `ParseOptions::track_trivia`; otherwise empty.
+  pub trivia: Vec<TriviaSpan>,
+
+  /// The byte offset of `content` within an enclosing document, if parsed with
+  /// `Dockerfile::parse_with_offset()`; otherwise 0. All spans, including those
+  /// in diagnostics, are relative to the enclosing document.
+  pub base_offset: usize,
+
+  /// The 0-indexed line of the enclosing document on which `content` begins;
+  /// see `base_offset`.
+  pub base_line: usize,
+
+  /// The options this Dockerfile was parsed with
+  pub options: ParseOptions,
+}
+
+/// Produces a friendlier error for a parse failure on a line that appears to
+/// contain two instructions, e.g. `FROM alpine RUN echo hi`, i.e. where parsing
+/// failed at a keyword following the line's instruction.
+fn multiple_instructions_error(
+  input: &str,
+  error: &pest::error::Error<Rule>
+) -> Option<Error> {
+  let (line, col) = match error.line_col {
+    LineColLocation::Pos(pos) => pos,
+    LineColLocation::Span(pos, _) => pos,
+  };
+
+  let text = input.lines().nth(line - 1)?;
+  let (before, rest) = text.split_at(text.char_indices().nth(col - 1)?.0);
+  let first = before.trim_start_matches(BOM).split_whitespace().next()?;
+  if !is_instruction_keyword(first) || !before.ends_with(char::is_whitespace) {
+    return None;
+  }
 
-  /// An ordered list of all parsed instructions, including global_args
-  pub instructions: Vec<Instruction>
+  let second = rest.split(char::is_whitespace).next().filter(|t| is_instruction_keyword(t))?;
+
+  Some(Error::MultipleInstructionsOnLine {
+    line,
+    first: first.to_ascii_uppercase(),
+    second: second.to_ascii_uppercase(),
+  })
 }
 
-fn parse_dockerfile(input: &str) -> Result<Dockerfile> {
-  let dockerfile = DockerfileParser::parse(Rule::dockerfile, input)
-    .context(ParseError)?
+/// Records the original text of quoted strings, see
+/// `ParseOptions::keep_raw_strings`.
+struct KeepRawStrings<'a>(&'a str);
+
+impl SpanVisitor for KeepRawStrings<'_> {
+  fn span(&mut self, _span: &mut Span) {}
+
+  fn string(&mut self, string: &mut SpannedString) {
+    if string.is_quoted() {
+      string.raw_text = Some(self.0[string.span.start..string.span.end].to_string());
+    }
+  }
+}
+
+/// Releases the unused capacity of every string and vector, see
+/// `ParseOptions::compact`.
+struct Compact;
+
+impl SpanVisitor for Compact {
+  fn span(&mut self, _span: &mut Span) {}
+
+  fn string(&mut self, string: &mut SpannedString) {
+    string.content.shrink_to_fit();
+  }
+
+  fn vec<T>(&mut self, vec: &mut Vec<T>) {
+    vec.shrink_to_fit();
+  }
+}
+
+/// Finds the indices of all ARG instructions preceding the first FROM, which
+/// may be substituted into all subsequent FROM image refs.
+pub(crate) fn find_global_arg_indices(instructions: &[Instruction]) -> Vec<usize> {
+  instructions
+    .iter()
+    .take_while(|ins| ins.as_from().is_none())
+    .enumerate()
+    .filter(|(_, ins)| ins.as_arg().is_some())
+    .map(|(i, _)| i)
+    .collect()
+}
+
+fn parse_dockerfile(input: &str, options: &ParseOptions) -> Result<Dockerfile> {
+  // heredoc bodies aren't line-oriented, so they're found ahead of time and
+  // hidden from the grammar
+  let heredocs = find_heredocs_checked(input)?;
+  let masked = mask_heredocs(input, &heredocs);
+
+  let dockerfile = DockerfileParser::parse(Rule::dockerfile, &masked)
+    .map_err(|e| match multiple_instructions_error(&masked, &e) {
+      Some(err) => err,
+      None => Error::ParseError { source: e }
+    })?
     .next()
     .ok_or(Error::UnknownParseError)?;
 
   let mut instructions = Vec::new();
-  let mut global_args = Vec::new();
-  let mut from_found = false;
   let mut from_index = 0;
 
   for record in dockerfile.into_inner() {
@@ -334,37 +1101,227 @@ fn parse_dockerfile(input: &str) -> Result<Dockerfile> {
       continue;
     }
 
-    let mut instruction = Instruction::try_from(record)?;
-    match &mut instruction {
-      Instruction::From(ref mut from) => {
-        // fix the from index since we can't know that in parse_instruction()
-        from.index = from_index;
-        from_index += 1;
-        from_found = true;
-      },
-      Instruction::Arg(ref arg) => {
-        // args preceding the first FROM instruction may be substituted into
-        // all subsequent FROM image refs
-        if !from_found {
-          global_args.push(arg.clone());
-        }
-      },
-      _ => ()
+    let mut instruction = match record.as_rule() {
+      Rule::from => FromInstruction::from_record_with_options(record, options.strict_digests)?.into(),
+      _ => Instruction::try_from(record)?
     };
 
+    if let Instruction::From(ref mut from) = instruction {
+      // the index is only known in the context of the whole Dockerfile
+      from.index = Some(from_index);
+      from_index += 1;
+    }
+
     instructions.push(instruction);
   }
 
+  attach_heredocs(&mut instructions, heredocs);
+
+  if !options.lenient {
+    if let Some(gap) = find_pair_gaps(input, &instructions).first() {
+      let line = input[..gap.span.start].matches('\n').count() + 1;
+      return Err(Error::GenericParseError {
+        message: format!("{} on line {}", gap.message(), line)
+      });
+    }
+  }
+
+  if options.unknown_instructions == UnknownPolicy::Deny {
+    let unknown = instructions
+      .iter()
+      .filter_map(|ins| ins.as_misc())
+      .find(|misc| !is_instruction_keyword(&misc.instruction.content));
+
+    if let Some(misc) = unknown {
+      return Err(Error::UnknownInstruction {
+        keyword: misc.instruction.content.clone(),
+        span: misc.instruction.span,
+      });
+    }
+  }
+
+  if options.keep_raw_strings {
+    let mut visitor = KeepRawStrings(input);
+    instructions.visit_spans(&mut visitor);
+  }
+
+  debug_assert!(
+    instructions.windows(2).all(|w| w[0].span().end <= w[1].span().start),
+    "instruction spans must be ordered and non-overlapping"
+  );
+
+  let mut trivia = if options.track_trivia {
+    find_trivia(input, &instructions)
+  } else {
+    Vec::new()
+  };
+
+  if options.compact {
+    instructions.visit_spans(&mut Compact);
+    trivia.shrink_to_fit();
+  }
+
   Ok(Dockerfile {
     content: input.into(),
-    global_args, instructions
+    global_arg_indices: find_global_arg_indices(&instructions),
+    instructions, trivia,
+    base_offset: 0,
+    base_line: 0,
+    options: options.clone(),
   })
 }
 
 impl Dockerfile {
   /// Parses a Dockerfile from a string.
   pub fn parse(input: &str) -> Result<Dockerfile> {
-    parse_dockerfile(input)
+    parse_dockerfile(input, &ParseOptions::default())
+  }
+
+  /// Parses a Dockerfile from a string with the given options.
+  pub fn parse_with_options(input: &str, options: &ParseOptions) -> Result<Dockerfile> {
+    parse_dockerfile(input, options)
+  }
+
+  /// Parses a Dockerfile embedded in a larger document, e.g. an inline
+  /// Dockerfile in a YAML file, that begins at byte `base_offset` and on
+  /// (0-indexed) line `base_line` of the enclosing document.
+  ///
+  /// All spans are shifted to be relative to the enclosing document, as are
+  /// line numbers reported by `Span::relative_span()` and parse errors. Such
+  /// spans can be spliced into either this Dockerfile's `splicer()` or a
+  /// `Splicer::new()` for the enclosing document.
+  ///
+  /// ```
+  /// use dockerfile_parser::{Dockerfile, Splicer};
+  ///
+  /// let outer = "dockerfile_inline: |\n  FROM alpine:3.12\n";
+  /// let dockerfile = Dockerfile::parse_with_offset(&outer[21..], 21, 1).unwrap();
+  ///
+  /// let from = dockerfile.instructions[0].as_from().unwrap();
+  /// assert_eq!(&outer[from.image.span.start..from.image.span.end], "alpine:3.12");
+  /// assert_eq!(from.image.span.relative_span(&dockerfile).0, 1);
+  ///
+  /// let mut splicer = Splicer::new(outer);
+  /// splicer.splice(&from.image.span, "alpine:3.13");
+  /// assert_eq!(splicer.content, "dockerfile_inline: |\n  FROM alpine:3.13\n");
+  /// ```
+  pub fn parse_with_offset(input: &str, base_offset: usize, base_line: usize) -> Result<Dockerfile> {
+    let mut dockerfile = Dockerfile::parse(input).map_err(|e| match e {
+      Error::ParseError { mut source } => {
+        match &mut source.location {
+          InputLocation::Pos(pos) => *pos += base_offset,
+          InputLocation::Span((start, end)) => {
+            *start += base_offset;
+            *end += base_offset;
+          },
+        }
+
+        match &mut source.line_col {
+          LineColLocation::Pos((line, _)) => *line += base_line,
+          LineColLocation::Span((start, _), (end, _)) => {
+            *start += base_line;
+            *end += base_line;
+          },
+        }
+
+        Error::ParseError { source }
+      },
+      Error::MultipleInstructionsOnLine { line, first, second } => {
+        Error::MultipleInstructionsOnLine { line: line + base_line, first, second }
+      },
+      Error::UnterminatedHeredoc { delimiter, line, span } => Error::UnterminatedHeredoc {
+        delimiter,
+        line: line + base_line,
+        span: Span::new(span.start + base_offset, span.end + base_offset),
+      },
+      e => e
+    })?;
+
+    dockerfile.shift_spans(base_offset as isize);
+    dockerfile.base_offset = base_offset;
+    dockerfile.base_line = base_line;
+
+    Ok(dockerfile)
+  }
+
+  /// Parses several Dockerfiles concatenated in one input, separated by lines
+  /// containing only `delimiter` (ignoring surrounding whitespace), e.g.
+  /// `# ---`.
+  ///
+  /// Returns the span of each document, excluding delimiter lines, with the
+  /// result of parsing it as with `Dockerfile::parse_with_offset()`, so all
+  /// spans are relative to the whole input. A document that fails to parse
+  /// doesn't affect the others.
+  ///
+  /// ```
+  /// use dockerfile_parser::Dockerfile;
+  ///
+  /// let input = "FROM alpine\n# ---\nFROM ubuntu\n";
+  /// let documents = Dockerfile::parse_many(input, "# ---");
+  /// assert_eq!(documents.len(), 2);
+  ///
+  /// let (span, dockerfile) = &documents[1];
+  /// assert_eq!(&input[span.start..span.end], "FROM ubuntu\n");
+  ///
+  /// let from = dockerfile.as_ref().unwrap().instructions[0].as_from().unwrap();
+  /// assert_eq!(&input[from.image.span.start..from.image.span.end], "ubuntu");
+  /// ```
+  pub fn parse_many(input: &str, delimiter: &str) -> Vec<(Span, Result<Dockerfile>)> {
+    let mut documents = Vec::new();
+    let mut start = 0;
+    let mut start_line = 0;
+    let mut offset = 0;
+
+    for (line_index, line) in input.split_inclusive('\n').enumerate() {
+      if line.trim() == delimiter {
+        documents.push((Span::new(start, offset), start_line));
+        start = offset + line.len();
+        start_line = line_index + 1;
+      }
+
+      offset += line.len();
+    }
+
+    documents.push((Span::new(start, input.len()), start_line));
+
+    documents
+      .into_iter()
+      .map(|(span, line)| {
+        let dockerfile = Dockerfile::parse_with_offset(&input[span.start..span.end], span.start, line);
+        (span, dockerfile)
+      })
+      .collect()
+  }
+
+  /// Returns this Dockerfile with spans relative to `content` rather than an
+  /// enclosing document, for analyses that index into `content` by span.
+  pub(crate) fn local(&self) -> Cow<'_, Dockerfile> {
+    if self.base_offset == 0 {
+      return Cow::Borrowed(self);
+    }
+
+    let mut local = self.clone();
+    local.shift_spans(-(self.base_offset as isize));
+    local.base_offset = 0;
+
+    Cow::Owned(local)
+  }
+
+  /// Shifts spans found in `local()` back to be relative to the enclosing
+  /// document.
+  pub(crate) fn rebase<T: ShiftSpans>(&self, mut value: T) -> T {
+    value.shift_spans(self.base_offset as isize);
+    value
+  }
+
+  /// Returns the text of `content` covered by `span`, or an empty string if
+  /// the span is synthetic.
+  pub(crate) fn text(&self, span: &Span) -> &str {
+    if span.is_synthetic() {
+      return "";
+    }
+
+    &self.content[span.start - self.base_offset..span.end - self.base_offset]
   }
 
   /// Parses a Dockerfile from a reader.
@@ -379,6 +1336,66 @@ impl Dockerfile {
     Dockerfile::parse(&buf)
   }
 
+  /// Parses this Dockerfile again after replacing the text at `edit_span` with
+  /// `replacement`, returning the updated Dockerfile.
+  ///
+  /// Only instructions near the edit are re-parsed, with the spans of later
+  /// instructions shifted to match, making this suitable for re-parsing on
+  /// every keystroke in an editor. The result is equivalent to a full parse of
+  /// the edited content, which is used as a fallback when the edit may affect
+  /// stage structure or an unterminated quote anywhere before it.
+  pub fn reparse_edit(&self, edit_span: Span, replacement: &str) -> Result<Dockerfile> {
+    let edit_span = Span::new(edit_span.start - self.base_offset, edit_span.end - self.base_offset);
+    let mut dockerfile = reparse_edit(&self.local(), edit_span, replacement)?;
+
+    dockerfile.shift_spans(self.base_offset as isize);
+    dockerfile.base_offset = self.base_offset;
+    dockerfile.base_line = self.base_line;
+
+    Ok(dockerfile)
+  }
+
+  /// Returns the instructions whose spans overlap the given span, e.g. to map
+  /// a diff hunk to the instructions it touches.
+  ///
+  /// Spans are half-open, so an instruction ending exactly where `span` starts
+  /// does not overlap it; an empty span overlaps the instruction containing
+  /// its position, if any. Spans covering only whitespace or comments between
+  /// instructions return an empty slice.
+  ///
+  /// ```
+  /// use dockerfile_parser::{Dockerfile, Span};
+  ///
+  /// let dockerfile = Dockerfile::parse("FROM alpine\nRUN a\n\nRUN b\n").unwrap();
+  /// assert_eq!(dockerfile.instructions_overlapping(&Span::new(14, 22)).len(), 2);
+  /// assert!(dockerfile.instructions_overlapping(&Span::new(18, 19)).is_empty());
+  /// ```
+  pub fn instructions_overlapping(&self, span: &Span) -> &[Instruction] {
+    overlapping(&self.instructions, span, |i| i.span())
+  }
+
+  /// Returns the standalone comments overlapping the given span. Comments
+  /// within a multi-line instruction are part of that instruction; see
+  /// `instructions_overlapping()`.
+  ///
+  /// Comments are taken from `trivia` if it was tracked, and are otherwise
+  /// found on demand.
+  pub fn comments_overlapping(&self, span: &Span) -> Vec<TriviaSpan> {
+    let found;
+    let trivia = if self.trivia.is_empty() {
+      found = self.rebase(find_trivia(&self.content, &self.local().instructions));
+      &found
+    } else {
+      &self.trivia
+    };
+
+    overlapping(trivia, span, |t| t.span)
+      .iter()
+      .filter(|t| t.kind == TriviaKind::Comment)
+      .copied()
+      .collect()
+  }
+
   /// Returns a `Stages`, which splits this Dockerfile into its build stages.
   pub fn stages(&self) -> Stages {
     Stages::new(self)
@@ -388,6 +1405,101 @@ impl Dockerfile {
     self.stages().into_iter()
   }
 
+  /// Returns an iterator over this Dockerfile's build stages that, unlike
+  /// `iter_stages()`, finds each stage only as it is needed.
+  pub fn iter_stages_lazy(&self) -> StageIterator<'_> {
+    StageIterator::new(self)
+  }
+
+  /// Returns an iterator over all instructions along with their global index
+  /// and the index, alias, and local index of the stage containing them, to
+  /// avoid nested iteration over stages and their instructions.
+  ///
+  /// ```
+  /// use dockerfile_parser::Dockerfile;
+  ///
+  /// let dockerfile = Dockerfile::parse(r#"
+  ///   ARG VERSION=3.19
+  ///   FROM alpine:${VERSION} AS base
+  ///   RUN apk add curl
+  /// "#).unwrap();
+  ///
+  /// let run = dockerfile.iter_with_context().last().unwrap();
+  /// assert_eq!(run.index, 2);
+  /// assert_eq!(run.stage, Some(0));
+  /// assert_eq!(run.stage_name, Some("base"));
+  /// assert_eq!(run.local_index, 1);
+  /// ```
+  pub fn iter_with_context(&self) -> impl Iterator<Item = InstructionContext<'_>> {
+    let stages = self.stages();
+    let preamble_len = stages.stages
+      .first()
+      .map(|s| s.instruction_indices[0])
+      .unwrap_or(self.instructions.len());
+
+    let mut contexts: Vec<InstructionContext> = self.instructions[..preamble_len]
+      .iter()
+      .enumerate()
+      .map(|(index, instruction)| InstructionContext {
+        instruction,
+        index,
+        stage: None,
+        stage_name: None,
+        local_index: index,
+      })
+      .collect();
+
+    for stage in stages.iter() {
+      let stage_name = stage.instructions[0]
+        .as_from()
+        .and_then(|from| from.alias.as_ref())
+        .map(|alias| alias.content.as_str());
+
+      contexts.extend(stage.instruction_indices
+        .iter()
+        .enumerate()
+        .map(|(local_index, &index)| InstructionContext {
+          instruction: &self.instructions[index],
+          index,
+          stage: Some(stage.index),
+          stage_name,
+          local_index,
+        }));
+    }
+
+    contexts.into_iter()
+  }
+
+  /// Splits this Dockerfile's content into one slice per build stage, returning
+  /// each stage's index, covering span, and text.
+  ///
+  /// Unlike instruction spans, slices include any whitespace and comments
+  /// between instructions: each stage runs from its `FROM` (including comments
+  /// directly above it) up to the start of the next stage or EOF. Concatenating
+  /// `preamble_slice()` and all stage slices reproduces `content` exactly.
+  pub fn stage_slices(&self) -> Vec<(usize, Span, &str)> {
+    let boundaries = stage_boundaries(&self.local());
+
+    boundaries.iter()
+      .enumerate()
+      .map(|(i, &start)| {
+        let end = boundaries.get(i + 1).copied().unwrap_or(self.content.len());
+        (i, self.rebase(Span::new(start, end)), &self.content[start..end])
+      })
+      .collect()
+  }
+
+  /// Returns the span and text preceding the first stage slice, e.g. parser
+  /// directives and global `ARG`s. See `stage_slices()`.
+  pub fn preamble_slice(&self) -> (Span, &str) {
+    let end = stage_boundaries(&self.local())
+      .first()
+      .copied()
+      .unwrap_or(self.content.len());
+
+    (self.rebase(Span::new(0, end)), &self.content[..end])
+  }
+
   /// Creates a `Splicer` for this Dockerfile.
   ///
   /// Note that the original input string is needed to actually perform any
@@ -396,23 +1508,104 @@ impl Dockerfile {
     Splicer::from(self)
   }
 
+  /// Creates a `DockerfileEditor` for making structured edits to this
+  /// Dockerfile.
+  pub fn editor(&self) -> DockerfileEditor<'_> {
+    DockerfileEditor::new(self)
+  }
+
+  /// Returns a lock entry for each `FROM` instruction that references an
+  /// external image, i.e. excluding `scratch` and references to earlier
+  /// stages.
+  ///
+  /// Entries may be stored alongside the Dockerfile and later applied with
+  /// [`DockerfileEditor::apply_image_lock`] to pin each image to a digest.
+  pub fn image_lock_entries(&self) -> Vec<ImageLockEntry> {
+    image_lock_entries(self)
+  }
+
+  /// Statically checks this Dockerfile for problems that would cause a build
+  /// to fail or behave unexpectedly, returning a list of diagnostics.
+  ///
+  /// An empty list does not guarantee the Dockerfile will build successfully.
+  pub fn validate(&self) -> Vec<Diagnostic> {
+    self.validate_with_options(&ValidateOptions::default())
+  }
+
+  /// Validates this Dockerfile like `validate()`, with the given options.
+  pub fn validate_with_options(&self, options: &ValidateOptions) -> Vec<Diagnostic> {
+    self.rebase(validate(&self.local(), options))
+  }
+
+  /// Runs all available `CHECKS` against this Dockerfile, returning a list of
+  /// diagnostics.
+  pub fn run_checks(&self) -> Vec<Diagnostic> {
+    run_checks(self)
+  }
+
+  /// Checks this Dockerfile's instructions against the given style limits,
+  /// e.g. a maximum line length, returning a list of diagnostics.
+  pub fn check_style(&self, options: &StyleOptions) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+    check_style(&self.local(), options, &mut diagnostics);
+
+    self.rebase(diagnostics)
+  }
+
+  /// Returns the parser directives at the top of this Dockerfile, e.g.
+  /// `# syntax=docker/dockerfile:1`.
+  pub fn directives(&self) -> Vec<Directive> {
+    self.rebase(find_directives(&self.content))
+  }
+
+  /// Returns the shebang on the first line of this Dockerfile, if any, e.g.
+  /// `#!/usr/bin/env -S docker build -f`. The span covers the whole line
+  /// (excluding the line break), while the content is the command following
+  /// the `#!`, with surrounding whitespace removed.
+  ///
+  /// To Docker, a shebang is just a comment, though parser directives may
+  /// follow it.
+  ///
+  /// ```
+  /// use dockerfile_parser::Dockerfile;
+  ///
+  /// let dockerfile = Dockerfile::parse(
+  ///   "#!/usr/bin/env -S docker build -f\n# syntax=docker/dockerfile:1\nFROM alpine\n"
+  /// ).unwrap();
+  ///
+  /// assert_eq!(dockerfile.shebang().unwrap().content, "/usr/bin/env -S docker build -f");
+  /// assert!(dockerfile.syntax_directive().is_some());
+  /// ```
+  pub fn shebang(&self) -> Option<SpannedString> {
+    let span = find_shebang(&self.content)?;
+    let line = &self.content[span.start..span.end];
+
+    Some(self.rebase(SpannedString {
+      span,
+      content: line[2..].trim().to_string(),
+      raw_text: None,
+      quoted: false,
+    }))
+  }
+
+  /// Returns the `# syntax` parser directive, if any.
+  pub fn syntax_directive(&self) -> Option<Directive> {
+    self.directives().into_iter().find(|d| d.is("syntax"))
+  }
+
+  /// Returns the global ARG instructions, i.e. those preceding the first FROM,
+  /// in order. These are read from `instructions` via `global_arg_indices`.
+  pub fn global_args(&self) -> Vec<&ArgInstruction> {
+    self.global_arg_indices
+      .iter()
+      .filter_map(|i| self.instructions.get(*i).and_then(|ins| ins.as_arg()))
+      .collect()
+  }
+
   /// Attempts to find a global argument by name. Returns None if no global ARG
   /// with the given name exists.
   pub fn get_global_arg(&self, name: &str) -> Option<&ArgInstruction> {
-    for ins in &self.instructions {
-      match ins {
-        Instruction::Arg(a) => {
-          if a.name.content == name {
-            return Some(a);
-          } else {
-            continue
-          }
-        },
-        _ => return None
-      }
-    }
-
-    None
+    self.global_args().into_iter().find(|a| a.name.content == name)
   }
 }
 
@@ -0,0 +1,761 @@
+// (C) Copyright 2026 Hewlett Packard Enterprise Development LP
+
+use std::collections::HashSet;
+
+use crate::checks::LEGACY_PAIR_SYNTAX;
+use crate::directive::{content_start, directives_start};
+use crate::dockerfile_parser::{Dockerfile, Instruction};
+use crate::error::*;
+use crate::heredoc::{reindent_body, Heredoc, ShellToken};
+use crate::image::Digest;
+use crate::instructions::EnvVar;
+use crate::lock::ImageLockEntry;
+use crate::options::HeredocFormatOptions;
+use crate::spec::instruction_spec;
+use crate::splicer::{Span, Splicer};
+
+/// Keyword conversions supported by `DockerfileEditor::replace_keyword()`.
+const CONVERSIONS: &[(&str, &str)] = &[
+  ("ADD", "COPY"), ("COPY", "ADD"), ("CMD", "ENTRYPOINT"), ("ENTRYPOINT", "CMD"),
+];
+
+/// File extensions of archives that `ADD` extracts but `COPY` doesn't.
+const ARCHIVE_EXTENSIONS: &[&str] = &[
+  ".tar", ".tar.gz", ".tgz", ".tar.bz2", ".tbz2", ".tar.xz", ".txz", ".tar.zst",
+];
+
+/// A flag name or path with its span.
+type SpannedWord = (String, Span);
+
+/// Returns the flags (by name) and source paths of a `COPY` or `ADD`
+/// instruction, with their spans.
+fn copy_arguments(ins: &Instruction) -> (Vec<SpannedWord>, Vec<SpannedWord>) {
+  match ins {
+    Instruction::Copy(copy) => (
+      copy.flags.iter().map(|f| (f.name.content.clone(), f.span)).collect(),
+      copy.sources.iter().map(|s| (s.content.clone(), s.span)).collect(),
+    ),
+    Instruction::Add(add) => (
+      add.flags.iter().map(|f| (f.name.content.clone(), f.span)).collect(),
+      add.sources.iter().map(|s| (s.content.clone(), s.span)).collect(),
+    ),
+    _ => (Vec::new(), Vec::new())
+  }
+}
+
+/// Finds the first feature of an instruction that would be lost or change
+/// meaning if its keyword were replaced, returning a description and its span.
+fn conversion_blocker(ins: &Instruction, to: &str) -> Option<(String, Span)> {
+  let (flags, sources) = copy_arguments(ins);
+
+  let allowed = instruction_spec(to).map(|s| s.flags).unwrap_or(&[]);
+  for (name, span) in flags {
+    if !allowed.iter().any(|f| f.name.eq_ignore_ascii_case(&name)) {
+      return Some((format!("flag '--{}' is not supported by {}", name, to), span));
+    }
+  }
+
+  for (source, span) in sources {
+    let lower = source.to_ascii_lowercase();
+    if to == "COPY" && (lower.contains("://") || lower.starts_with("git@")) {
+      return Some((format!("remote source '{}' is not supported by COPY", source), span));
+    }
+
+    if ARCHIVE_EXTENSIONS.iter().any(|ext| lower.ends_with(ext)) {
+      let reason = if to == "COPY" {
+        format!("archive '{}' would no longer be extracted", source)
+      } else {
+        format!("archive '{}' would be extracted", source)
+      };
+
+      return Some((reason, span));
+    }
+  }
+
+  None
+}
+
+/// Determines if any command in the pipeline consuming a heredoc matches one
+/// of the given patterns; see `HeredocFormatOptions::skip_commands`.
+fn is_skipped_heredoc(tokens: &[ShellToken], heredoc: &Heredoc, patterns: &[String]) -> bool {
+  let is_pipe = |t: &ShellToken| matches!(t, ShellToken::Operator(op) if op.content == "|");
+
+  let pipeline = match tokens
+    .split(|t| t.is_separator() && !is_pipe(t))
+    .find(|pipeline| pipeline.iter().any(|t| t.span() == heredoc.marker))
+  {
+    Some(pipeline) => pipeline,
+    None => return false
+  };
+
+  pipeline.split(is_pipe).any(|command| {
+    let words: Vec<&str> = command
+      .iter()
+      .filter_map(|t| match t {
+        ShellToken::Word(word) => Some(word.content.as_str()),
+        _ => None
+      })
+      .collect();
+
+    let name = match words.first() {
+      Some(name) => name.rsplit('/').next().unwrap_or(name),
+      None => return false
+    };
+
+    patterns.iter().any(|pattern| {
+      let mut pattern = pattern.split_whitespace();
+      pattern.next() == Some(name) && pattern.all(|arg| words[1..].contains(&arg))
+    })
+  })
+}
+
+/// A higher-level wrapper around a [`Splicer`] for making structured edits to
+/// a parsed Dockerfile.
+///
+/// All edits are relative to the original Dockerfile; the modified content can
+/// be retrieved with `content()` or `into_content()`.
+///
+/// ```
+/// use dockerfile_parser::{Digest, Dockerfile};
+///
+/// let dockerfile = Dockerfile::parse("FROM alpine:3.12\n").unwrap();
+/// let mut entries = dockerfile.image_lock_entries();
+/// entries[0].digest = Some(Digest::parse(
+///   "sha256:e7d92cdc71feacf90708cb59182d0df1b911f8ae022d29e8e95d75ca6a99776a"
+/// ).unwrap());
+///
+/// let mut editor = dockerfile.editor();
+/// assert!(editor.apply_image_lock(&entries).is_empty());
+/// assert_eq!(
+///   editor.content(),
+///   "FROM alpine:3.12@sha256:e7d92cdc71feacf90708cb59182d0df1b911f8ae022d29e8e95d75ca6a99776a\n"
+/// );
+/// ```
+pub struct DockerfileEditor<'a> {
+  dockerfile: &'a Dockerfile,
+  splicer: Splicer,
+}
+
+impl<'a> DockerfileEditor<'a> {
+  /// Creates a new editor for the given Dockerfile.
+  pub fn new(dockerfile: &'a Dockerfile) -> DockerfileEditor<'a> {
+    DockerfileEditor {
+      dockerfile,
+      splicer: dockerfile.splicer(),
+    }
+  }
+
+  /// Returns the original (unmodified) Dockerfile.
+  pub fn dockerfile(&self) -> &'a Dockerfile {
+    self.dockerfile
+  }
+
+  /// Returns the current, possibly modified, content.
+  pub fn content(&self) -> &str {
+    &self.splicer.content
+  }
+
+  /// Consumes this editor, returning the modified content.
+  pub fn into_content(self) -> String {
+    self.splicer.content
+  }
+
+  /// Pins base images to the digests in the given lock entries.
+  ///
+  /// Each entry is matched against the image lock entries of the current
+  /// Dockerfile by stage index and canonicalized image reference, so
+  /// formatting-only changes (e.g. `alpine` vs `docker.io/library/alpine`)
+  /// still match. Any existing digest on a matched image is replaced.
+  ///
+  /// Returns the entries that no longer match any image in the Dockerfile,
+  /// i.e. those whose image has changed since the lock was generated. Entries
+  /// without a digest are ignored.
+  pub fn apply_image_lock<'e>(
+    &mut self,
+    entries: &'e [ImageLockEntry]
+  ) -> Vec<&'e ImageLockEntry> {
+    let current = self.dockerfile.image_lock_entries();
+    let mut applied = HashSet::new();
+    let mut stale = Vec::new();
+
+    for entry in entries {
+      let digest = match &entry.digest {
+        Some(digest) => digest,
+        None => continue
+      };
+
+      let target = current.iter()
+        .find(|c| c.matches(entry) && !applied.contains(&c.stage_index));
+
+      let target = match target {
+        Some(target) => target,
+        None => {
+          stale.push(entry);
+          continue;
+        }
+      };
+
+      let image = match target.image.find('@') {
+        Some(pos) => &target.image[..pos],
+        None => target.image.as_str()
+      };
+
+      self.splicer.splice(&target.span, &format!("{}@{}", image, digest));
+      applied.insert(target.stage_index);
+    }
+
+    stale
+  }
+
+  /// Renames an environment variable, leaving its value exactly as written.
+  pub fn set_env_key(&mut self, var: &EnvVar, key: &str) {
+    self.splicer.splice(&var.key.span, key);
+  }
+
+  /// Replaces the value of an environment variable, preserving its original
+  /// quoting style where possible.
+  ///
+  /// Values are escaped as needed; values that can't be represented in the
+  /// original style are double-quoted (see `QuoteStyle::quote()`).
+  pub fn set_env_value(&mut self, var: &EnvVar, value: &str) {
+    self.splicer.splice(&var.value.span, &var.quote_style.quote(value));
+  }
+
+  /// Pins the image in the `# syntax` directive to the given digest, replacing
+  /// any existing digest.
+  ///
+  /// Returns `Error::MissingSyntaxDirective` if the Dockerfile has no syntax
+  /// directive; see `pin_or_insert_syntax_directive()` to add one instead.
+  pub fn pin_syntax_directive(&mut self, digest: &Digest) -> Result<()> {
+    let directive = self.dockerfile
+      .syntax_directive()
+      .ok_or(Error::MissingSyntaxDirective)?;
+
+    let value = &directive.value.content;
+    let image = match value.find('@') {
+      Some(pos) => &value[..pos],
+      None => value.as_str()
+    };
+
+    self.splicer.splice(&directive.value.span, &format!("{}@{}", image, digest));
+    Ok(())
+  }
+
+  /// Rewrites all `LABEL` and `ENV` pairs written in the legacy `key value`
+  /// syntax as `key=value`, quoting values as necessary.
+  ///
+  /// Pairs with comments inside them are left as-is; see the
+  /// `LegacyPairSyntax` check.
+  pub fn normalize_pair_syntax(&mut self) {
+    for diagnostic in LEGACY_PAIR_SYNTAX.run(self.dockerfile) {
+      if let Some(fix) = diagnostic.suggested_fix {
+        self.splicer.splice(&fix.span, &fix.replacement);
+      }
+    }
+  }
+
+  /// Re-indents the bodies of `RUN` heredocs so that each indentation level
+  /// is `options.indent_width` spaces, preserving the relative nesting of
+  /// lines. Heredoc markers and terminators are never modified.
+  ///
+  /// Heredocs are left as-is if their delimiter is quoted (e.g. `<<'EOF'`),
+  /// if they strip leading tabs (`<<-EOF`), if they are piped or passed to a
+  /// command matching `options.skip_commands`, or if their indentation can't
+  /// be changed safely, e.g. a quoted string spanning several lines. The bodies of heredocs
+  /// nested within a heredoc script are also kept as-is.
+  ///
+  /// ```
+  /// use dockerfile_parser::{Dockerfile, HeredocFormatOptions};
+  ///
+  /// let dockerfile = Dockerfile::parse(
+  ///   "FROM alpine\nRUN <<EOF\n   if true; then\n         echo hi\n   fi\nEOF\n"
+  /// ).unwrap();
+  ///
+  /// let mut editor = dockerfile.editor();
+  /// editor.format_heredocs(&HeredocFormatOptions::default());
+  /// assert_eq!(
+  ///   editor.content(),
+  ///   "FROM alpine\nRUN <<EOF\nif true; then\n  echo hi\nfi\nEOF\n"
+  /// );
+  /// ```
+  pub fn format_heredocs(&mut self, options: &HeredocFormatOptions) {
+    for ins in &self.dockerfile.instructions {
+      let run = match ins.as_run() {
+        Some(run) => run,
+        None => continue
+      };
+
+      let tokens = run.shell_tokens();
+      for heredoc in &run.heredocs {
+        if !heredoc.expand
+          || heredoc.strip_tabs
+          || is_skipped_heredoc(&tokens, heredoc, &options.skip_commands) {
+          continue;
+        }
+
+        let body = self.dockerfile.text(&heredoc.body.span);
+        match reindent_body(body, options.indent_width) {
+          Some(formatted) if formatted != body => {
+            self.splicer.splice(&heredoc.body.span, &formatted);
+          },
+          _ => ()
+        }
+      }
+    }
+  }
+
+  /// Replaces an instruction's keyword, e.g. to convert `CMD` to `ENTRYPOINT`,
+  /// leaving its arguments exactly as written. The new keyword is written in
+  /// lower case if the original keyword was.
+  ///
+  /// Only conversions between `ADD` and `COPY`, and between `CMD` and
+  /// `ENTRYPOINT`, are supported. Returns `Error::KeywordConversion` without
+  /// splicing anything if the conversion isn't supported or would change the
+  /// instruction's meaning, e.g. an `ADD` with a URL source or an archive that
+  /// would be extracted, or a flag the new instruction doesn't accept. The
+  /// error's span points at the blocking feature.
+  ///
+  /// Replacing a keyword with itself does nothing.
+  pub fn replace_keyword(&mut self, ins: &Instruction, new_keyword: &str) -> Result<()> {
+    let span = ins.keyword_span();
+    let current = self.dockerfile.text(&span);
+    let from = current.to_ascii_uppercase();
+    let to = new_keyword.to_ascii_uppercase();
+
+    if from == to {
+      return Ok(());
+    }
+
+    let blocked = |reason: String, span: Span| Error::KeywordConversion {
+      from: from.clone(),
+      to: to.clone(),
+      reason,
+      span,
+    };
+
+    if !CONVERSIONS.contains(&(from.as_str(), to.as_str())) {
+      return Err(blocked("unsupported conversion".into(), span));
+    }
+
+    if let Some((reason, span)) = conversion_blocker(ins, &to) {
+      return Err(blocked(reason, span));
+    }
+
+    let keyword = if current.chars().all(|c| c.is_ascii_lowercase()) {
+      to.to_ascii_lowercase()
+    } else {
+      to.clone()
+    };
+
+    self.splicer.splice(&span, &keyword);
+    Ok(())
+  }
+
+  /// Converts an `ADD` instruction to `COPY` if it uses no `ADD`-specific
+  /// features; see `replace_keyword()`.
+  ///
+  /// ```
+  /// use dockerfile_parser::Dockerfile;
+  ///
+  /// let dockerfile = Dockerfile::parse(
+  ///   "FROM alpine\nADD --chown=1000 app/ /app/\nADD https://example.com/a.sh /\n"
+  /// ).unwrap();
+  ///
+  /// let mut editor = dockerfile.editor();
+  /// editor.add_to_copy(&dockerfile.instructions[1]).unwrap();
+  /// assert!(editor.add_to_copy(&dockerfile.instructions[2]).is_err());
+  /// assert_eq!(
+  ///   editor.content(),
+  ///   "FROM alpine\nCOPY --chown=1000 app/ /app/\nADD https://example.com/a.sh /\n"
+  /// );
+  /// ```
+  pub fn add_to_copy(&mut self, ins: &Instruction) -> Result<()> {
+    self.replace_keyword(ins, "COPY")
+  }
+
+  /// Pins the image in the `# syntax` directive to the given digest, or if no
+  /// syntax directive exists, inserts `# syntax=<image>@<digest>` as the first
+  /// line (following the byte order mark and shebang line, if any).
+  pub fn pin_or_insert_syntax_directive(&mut self, image: &str, digest: &Digest) {
+    if self.pin_syntax_directive(digest).is_ok() {
+      return;
+    }
+
+    let content = &self.dockerfile.content;
+    let newline = if content.contains("\r\n") { "\r\n" } else { "\n" };
+    let start = directives_start(content);
+
+    // a shebang may be the only line, without a trailing line break
+    let prefix = if start > content_start(content) && !content[..start].ends_with('\n') {
+      newline
+    } else {
+      ""
+    };
+
+    self.splicer.splice(
+      &self.dockerfile.rebase(Span::new(start, start)),
+      &format!("{}# syntax={}@{}{}", prefix, image, digest, newline)
+    );
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use indoc::indoc;
+  use pretty_assertions::assert_eq;
+
+  use crate::*;
+
+  fn digest(c: char) -> Digest {
+    Digest::parse(&format!("sha256:{}", c.to_string().repeat(64))).unwrap()
+  }
+
+  #[test]
+  fn test_apply_image_lock_round_trip() {
+    let original = Dockerfile::parse(indoc!(r#"
+      FROM alpine:3.12 as build
+      RUN echo hello
+
+      FROM node:lts
+      COPY --from=build /foo /foo
+    "#)).unwrap();
+
+    let mut entries = original.image_lock_entries();
+    entries[0].digest = Some(digest('a'));
+    entries[1].digest = Some(digest('b'));
+
+    // pinning the unchanged Dockerfile applies every entry
+    let mut editor = original.editor();
+    assert!(editor.apply_image_lock(&entries).is_empty());
+
+    let pinned = Dockerfile::parse(editor.content()).unwrap();
+    let pinned_entries = pinned.image_lock_entries();
+    assert_eq!(pinned_entries[0].digest, Some(digest('a')));
+    assert_eq!(pinned_entries[1].digest, Some(digest('b')));
+
+    // re-applying the lock to the pinned file is a no-op
+    let mut editor = pinned.editor();
+    assert!(editor.apply_image_lock(&entries).is_empty());
+    assert_eq!(editor.content(), pinned.content);
+
+    // the node image changed since the lock was generated
+    let modified = Dockerfile::parse(indoc!(r#"
+      FROM alpine:3.12 as build
+      RUN echo hello
+
+      FROM node:14
+      COPY --from=build /foo /foo
+    "#)).unwrap();
+
+    let mut editor = modified.editor();
+    let stale = editor.apply_image_lock(&entries);
+    assert_eq!(stale, vec![&entries[1]]);
+    assert_eq!(editor.into_content(), format!(indoc!(r#"
+      FROM alpine:3.12@{} as build
+      RUN echo hello
+
+      FROM node:14
+      COPY --from=build /foo /foo
+    "#), digest('a')));
+  }
+
+  #[test]
+  fn test_set_env() {
+    let d = Dockerfile::parse(indoc!(r#"
+      FROM alpine:3.12
+      ENV PRICE='$5' NAME="foo bar" PLAIN=baz
+      ENV SINGLE 'costs $5'
+    "#)).unwrap();
+
+    let env = d.instructions[1].as_env().unwrap();
+    let single = d.instructions[2].as_env().unwrap();
+    assert_eq!(env.vars[0].quote_style, QuoteStyle::Single);
+    assert_eq!(env.vars[0].raw_value_text(&d), "'$5'");
+    assert_eq!(env.vars[1].quote_style, QuoteStyle::Double);
+    assert_eq!(env.vars[2].quote_style, QuoteStyle::Unquoted);
+    assert_eq!(single.vars[0].raw_value_text(&d), "'costs $5'");
+
+    let mut editor = d.editor();
+    editor.set_env_key(&env.vars[0], "COST");
+    editor.set_env_value(&env.vars[1], "baz \"qux\"");
+    editor.set_env_value(&env.vars[2], "two words");
+    editor.set_env_key(&single.vars[0], "OTHER");
+
+    let edited = Dockerfile::parse(editor.content()).unwrap();
+    assert_eq!(edited.content, indoc!(r#"
+      FROM alpine:3.12
+      ENV COST='$5' NAME="baz \"qux\"" PLAIN="two words"
+      ENV OTHER 'costs $5'
+    "#));
+
+    let env = edited.instructions[1].as_env().unwrap();
+    assert_eq!(env.vars[0].quote_style, QuoteStyle::Single);
+    assert_eq!(env.vars[0].value.to_string(), "$5");
+    assert_eq!(env.vars[1].value.to_string(), "baz \"qux\"");
+    assert_eq!(env.vars[2].value.to_string(), "two words");
+
+    // single-quoted values stay single-quoted when possible
+    let mut editor = edited.editor();
+    editor.set_env_value(&env.vars[0], "$10");
+    let edited = Dockerfile::parse(editor.content()).unwrap();
+    assert_eq!(
+      edited.instructions[1].as_env().unwrap().vars[0].raw_value_text(&edited),
+      "'$10'"
+    );
+
+    // falling back to double quotes mustn't make the value expandable
+    let env = edited.instructions[1].as_env().unwrap();
+    let mut editor = edited.editor();
+    editor.set_env_value(&env.vars[0], "it's $HOME");
+    let edited = Dockerfile::parse(editor.content()).unwrap();
+    let var = &edited.instructions[1].as_env().unwrap().vars[0];
+    assert_eq!(var.raw_value_text(&edited), r#""it's \$HOME""#);
+    assert_eq!(var.value.to_string(), "it's $HOME");
+  }
+
+  #[test]
+  fn test_pin_syntax_directive() {
+    let d = Dockerfile::parse(indoc!(r#"
+      # syntax = docker/dockerfile:1.4
+      # escape=\
+      FROM alpine:3.12
+    "#)).unwrap();
+
+    let mut editor = d.editor();
+    editor.pin_syntax_directive(&digest('a')).unwrap();
+
+    let pinned = Dockerfile::parse(editor.content()).unwrap();
+    assert_eq!(pinned.content, format!(indoc!(r#"
+      # syntax = docker/dockerfile:1.4@{}
+      # escape=\
+      FROM alpine:3.12
+    "#), digest('a')));
+    assert_eq!(pinned.directives().len(), 2);
+
+    // repinning replaces the existing digest
+    let mut editor = pinned.editor();
+    editor.pin_syntax_directive(&digest('b')).unwrap();
+    assert_eq!(
+      Dockerfile::parse(editor.content()).unwrap()
+        .syntax_directive().unwrap()
+        .syntax_image().unwrap()
+        .hash,
+      Some(digest('b').to_string())
+    );
+  }
+
+  #[test]
+  fn test_insert_syntax_directive() {
+    let d = Dockerfile::parse("FROM alpine:3.12\n").unwrap();
+
+    let mut editor = d.editor();
+    match editor.pin_syntax_directive(&digest('a')) {
+      Err(Error::MissingSyntaxDirective) => (),
+      other => panic!("expected MissingSyntaxDirective, got {:?}", other)
+    }
+    assert_eq!(editor.content(), d.content);
+
+    editor.pin_or_insert_syntax_directive("docker/dockerfile:1", &digest('a'));
+    assert_eq!(
+      editor.content(),
+      format!("# syntax=docker/dockerfile:1@{}\nFROM alpine:3.12\n", digest('a'))
+    );
+
+    // the directive is inserted after a byte order mark
+    let d = Dockerfile::parse("\u{feff}FROM alpine:3.12\r\n").unwrap();
+    let mut editor = d.editor();
+    editor.pin_or_insert_syntax_directive("docker/dockerfile:1", &digest('a'));
+
+    let inserted = Dockerfile::parse(editor.content()).unwrap();
+    assert!(inserted.content.starts_with("\u{feff}# syntax="));
+    assert!(inserted.content.ends_with("\r\nFROM alpine:3.12\r\n"));
+    assert_eq!(inserted.instructions.len(), 1);
+
+    let directive = inserted.syntax_directive().unwrap();
+    assert_eq!(
+      directive.value.content,
+      format!("docker/dockerfile:1@{}", digest('a'))
+    );
+    assert_eq!(inserted.run_checks(), vec![]);
+
+    // ... and after a shebang
+    let d = Dockerfile::parse("#!/usr/bin/env -S docker build -f\nFROM alpine:3.12\n").unwrap();
+    let mut editor = d.editor();
+    editor.pin_or_insert_syntax_directive("docker/dockerfile:1", &digest('a'));
+    assert_eq!(
+      editor.content(),
+      format!(
+        "#!/usr/bin/env -S docker build -f\n# syntax=docker/dockerfile:1@{}\nFROM alpine:3.12\n",
+        digest('a')
+      )
+    );
+  }
+
+  #[test]
+  fn test_apply_image_lock_formatting_change() {
+    let original = Dockerfile::parse("FROM alpine\n").unwrap();
+    let mut entries = original.image_lock_entries();
+    entries[0].digest = Some(digest('c'));
+
+    let reformatted = Dockerfile::parse(
+      "FROM docker.io/library/alpine:latest\n"
+    ).unwrap();
+
+    let mut editor = reformatted.editor();
+    assert!(editor.apply_image_lock(&entries).is_empty());
+    assert_eq!(
+      editor.into_content(),
+      format!("FROM docker.io/library/alpine:latest@{}\n", digest('c'))
+    );
+  }
+
+  #[test]
+  fn test_replace_keyword() {
+    let d = Dockerfile::parse(indoc!(r#"
+      FROM alpine:3.12
+      add --chown=1000 app/ /app/
+      ADD --checksum=sha256:abc https://example.com/a.sh /
+      ADD https://example.com/b.sh /
+      ADD rootfs.tar.gz /
+      COPY --from=0 /a /b
+      COPY /c /d
+      CMD ["/app"]
+    "#)).unwrap();
+
+    let mut editor = d.editor();
+    editor.add_to_copy(&d.instructions[1]).unwrap();
+    editor.replace_keyword(&d.instructions[6], "ADD").unwrap();
+    editor.replace_keyword(&d.instructions[7], "entrypoint").unwrap();
+
+    let blocked = |ins: usize, to: &str| match d.editor().replace_keyword(&d.instructions[ins], to) {
+      Err(Error::KeywordConversion { reason, span, .. }) => (reason, d.text(&span).to_string()),
+      other => panic!("expected KeywordConversion, got {:?}", other)
+    };
+
+    assert_eq!(
+      blocked(2, "COPY"),
+      ("flag '--checksum' is not supported by COPY".into(), "--checksum=sha256:abc".into())
+    );
+    assert_eq!(
+      blocked(3, "COPY"),
+      ("remote source 'https://example.com/b.sh' is not supported by COPY".into(), "https://example.com/b.sh".into())
+    );
+    assert_eq!(
+      blocked(4, "COPY"),
+      ("archive 'rootfs.tar.gz' would no longer be extracted".into(), "rootfs.tar.gz".into())
+    );
+    assert_eq!(
+      blocked(5, "ADD"),
+      ("flag '--from' is not supported by ADD".into(), "--from=0".into())
+    );
+    assert_eq!(blocked(5, "RUN"), ("unsupported conversion".into(), "COPY".into()));
+
+    let converted = editor.into_content();
+    assert_eq!(converted, indoc!(r#"
+      FROM alpine:3.12
+      copy --chown=1000 app/ /app/
+      ADD --checksum=sha256:abc https://example.com/a.sh /
+      ADD https://example.com/b.sh /
+      ADD rootfs.tar.gz /
+      COPY --from=0 /a /b
+      ADD /c /d
+      ENTRYPOINT ["/app"]
+    "#));
+
+    // converting again is a no-op
+    let d = Dockerfile::parse(&converted).unwrap();
+    let mut editor = d.editor();
+    editor.add_to_copy(&d.instructions[1]).unwrap();
+    editor.replace_keyword(&d.instructions[7], "ENTRYPOINT").unwrap();
+    assert_eq!(editor.content(), converted);
+  }
+
+  #[test]
+  fn test_format_heredocs() {
+    let dockerfile = Dockerfile::parse(indoc!(r#"
+      FROM alpine
+      RUN <<EOF
+         set -e
+         for f in a b; do
+              if [ -f "$f" ]; then
+                      cat > "$f.conf" <<CONF
+          keep   this
+      CONF
+              fi
+         done
+      EOF
+      RUN <<'EOF'
+          literal
+      EOF
+      RUN cat <<EOF | base64 -d > /bin/tool
+          aGVsbG8=
+      EOF
+      RUN python3 <<EOF
+      	print("a
+      	b")
+      EOF
+    "#)).unwrap();
+
+    let mut editor = dockerfile.editor();
+    editor.format_heredocs(&HeredocFormatOptions { indent_width: 4, ..Default::default() });
+
+    let formatted = Dockerfile::parse(editor.content()).unwrap();
+    let bodies: Vec<&str> = formatted.instructions
+      .iter()
+      .filter_map(|ins| ins.as_run())
+      .map(|run| run.heredocs[0].body.content.as_str())
+      .collect();
+
+    assert_eq!(bodies, vec![
+      concat!(
+        "set -e\n",
+        "for f in a b; do\n",
+        "    if [ -f \"$f\" ]; then\n",
+        "        cat > \"$f.conf\" <<CONF\n",
+        "    keep   this\n",
+        "CONF\n",
+        "    fi\n",
+        "done\n",
+      ),
+      "    literal\n",
+      "    aGVsbG8=\n",
+      "\tprint(\"a\n\tb\")\n",
+    ]);
+
+    // terminators and the rest of the file are untouched
+    for (a, b) in dockerfile.instructions.iter().zip(&formatted.instructions) {
+      if let (Some(a), Some(b)) = (a.as_run(), b.as_run()) {
+        assert_eq!(dockerfile.text(&a.heredocs[0].terminator), formatted.text(&b.heredocs[0].terminator));
+      }
+    }
+
+    // formatting is idempotent
+    let mut editor = formatted.editor();
+    editor.format_heredocs(&HeredocFormatOptions { indent_width: 4, ..Default::default() });
+    assert_eq!(editor.content(), formatted.content);
+  }
+
+  #[test]
+  fn test_format_heredocs_skip_commands() {
+    let dockerfile = Dockerfile::parse(indoc!(r#"
+      FROM alpine
+      RUN cat <<EOF | /usr/bin/patch -p1 && echo done
+         context
+        -removed
+      EOF
+      RUN cat <<EOF > /a.sh
+         echo a
+      EOF
+    "#)).unwrap();
+
+    let mut options = HeredocFormatOptions::default();
+    let mut editor = dockerfile.editor();
+    editor.format_heredocs(&options);
+    assert_eq!(editor.content(), dockerfile.content.replace("   echo a", "echo a"));
+
+    options.skip_commands = vec!["cat".into()];
+    let mut editor = dockerfile.editor();
+    editor.format_heredocs(&options);
+    assert_eq!(editor.content(), dockerfile.content);
+  }
+}
@@ -4,6 +4,7 @@ use pest::iterators::Pair;
 use snafu::Snafu;
 
 use crate::parser::*;
+use crate::splicer::Span;
 
 /// A Dockerfile parsing error.
 #[derive(Debug, Snafu)]
@@ -35,6 +36,46 @@ pub enum Error {
   ))]
   UnknownParseError,
 
+  #[snafu(display(
+    "unable to parse Dockerfile: line {} contains both {} and {} instructions; \
+     each instruction must start on a new line",
+    line, first, second
+  ))]
+  MultipleInstructionsOnLine {
+    line: usize,
+    first: String,
+    second: String
+  },
+
+  #[snafu(display(
+    "unable to parse Dockerfile: heredoc '{}' on line {} is never terminated",
+    delimiter, line
+  ))]
+  UnterminatedHeredoc {
+    delimiter: String,
+    line: usize,
+
+    /// The span of the heredoc marker, e.g. `<<EOF`
+    span: Span
+  },
+
+  #[snafu(display(
+    "unable to parse Dockerfile: unknown instruction '{}'", keyword
+  ))]
+  UnknownInstruction {
+    keyword: String,
+    span: Span
+  },
+
+  #[snafu(display(
+    "unable to parse Dockerfile: unknown flag '--{}' for {}", flag, instruction
+  ))]
+  UnknownFlag {
+    instruction: String,
+    flag: String,
+    span: Span
+  },
+
   #[snafu(display(
     "could not read Dockerfile: {}", source
   ))]
@@ -48,7 +89,111 @@ pub enum Error {
   ConversionError {
     from: String,
     to: String
-  }
+  },
+
+  #[snafu(display(
+    "{}: {}", name, message
+  ))]
+  RequiredVariableUnset {
+    name: String,
+    message: String
+  },
+
+  #[snafu(display(
+    "variable '{}' is not defined", name
+  ))]
+  UndefinedVariable {
+    name: String
+  },
+
+  #[snafu(display(
+    "maximum variable substitution depth exceeded"
+  ))]
+  SubstitutionDepthExceeded,
+
+  #[snafu(display(
+    "variable substitution output exceeds the maximum length of {} bytes", limit
+  ))]
+  ExpansionTooLarge {
+    limit: usize
+  },
+
+  #[snafu(display(
+    "invalid digest '{}': {}", digest, reason
+  ))]
+  InvalidDigest {
+    digest: String,
+    reason: String
+  },
+
+  #[snafu(display(
+    "invalid value '{}' for flag --{}: expected true, false, 1, or 0", value, flag
+  ))]
+  InvalidBoolFlag {
+    flag: String,
+    value: String
+  },
+
+  #[snafu(display("no syntax directive found"))]
+  MissingSyntaxDirective,
+
+  #[snafu(display(
+    "invalid platform '{}': {}", platform, reason
+  ))]
+  InvalidPlatform {
+    platform: String,
+    reason: String
+  },
+
+  #[snafu(display(
+    "target stage '{}' not found", target
+  ))]
+  UnknownTarget {
+    target: String,
+
+    /// Names of existing stages similar to `target`, most similar first
+    suggestions: Vec<String>
+  },
+
+  #[snafu(display(
+    "{}", cycle
+  ))]
+  StageCycle {
+    cycle: crate::stage::CycleError
+  },
+
+  #[snafu(display(
+    "cannot convert {} to {}: {}", from, to, reason
+  ))]
+  KeywordConversion {
+    from: String,
+    to: String,
+
+    /// The feature of the instruction preventing the conversion
+    reason: String,
+
+    /// The span of the blocking feature
+    span: Span
+  },
+
+  #[snafu(display(
+    "unable to safely reorder flag '{}': {}", flag, reason
+  ))]
+  UnsafeFlagReorder {
+    flag: String,
+    reason: String
+  },
+
+  #[snafu(display(
+    "unable to remove element {}: {}", index, reason
+  ))]
+  InvalidRemoval {
+    index: usize,
+    reason: String
+  },
+
+  #[snafu(display("cannot splice a synthetic span"))]
+  SyntheticSpan,
 }
 
 /// A Dockerfile parsing Result.
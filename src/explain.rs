@@ -0,0 +1,365 @@
+// (C) Copyright 2026 Hewlett Packard Enterprise Development LP
+
+use crate::dockerfile_parser::Instruction;
+use crate::SpannedString;
+use crate::instructions::*;
+use crate::util::{BreakableString, BreakableStringComponent, FlagValue, ShellOrExecExpr};
+
+/// Wraps a value in backticks.
+fn code(s: &str) -> String {
+  format!("`{}`", s)
+}
+
+/// Joins items into an English list, e.g. "a, b and c".
+fn list(items: &[String]) -> String {
+  match items {
+    [] => String::new(),
+    [only] => only.clone(),
+    [init @ .., last] => format!("{} and {}", init.join(", "), last)
+  }
+}
+
+/// Formats a possibly multi-line string on a single line, joining continued
+/// lines with a single space and omitting comments.
+fn single_line(s: &BreakableString) -> String {
+  s.iter_components()
+    .filter_map(|c| match c {
+      BreakableStringComponent::String(s) => Some(s.content.trim()),
+      BreakableStringComponent::Comment(_) => None
+    })
+    .filter(|s| !s.is_empty())
+    .collect::<Vec<_>>()
+    .join(" ")
+}
+
+/// Notes that a value contains variable references, if it does.
+fn variable_note(s: &str) -> &'static str {
+  if s.contains('$') {
+    " (with variables substituted at build time)"
+  } else {
+    ""
+  }
+}
+
+/// Describes a command in shell or exec form, e.g. "`echo hi` in the default
+/// shell".
+fn command(expr: &ShellOrExecExpr) -> String {
+  match expr {
+    ShellOrExecExpr::Shell(s) => format!("{} in the default shell", code(&single_line(s))),
+    ShellOrExecExpr::Exec(array) => match array.as_str_vec().as_slice() {
+      [] => "an empty command".to_string(),
+      [executable] => format!("{} directly, without a shell", code(executable)),
+      [executable, args @ ..] => format!(
+        "{} with arguments {} directly, without a shell",
+        code(executable),
+        list(&args.iter().map(|a| code(a)).collect::<Vec<_>>())
+      ),
+    }
+  }
+}
+
+fn explain_healthcheck(healthcheck: &HealthcheckInstruction) -> String {
+  let explanation = match &healthcheck.command {
+    Some(HealthcheckCommand::None(_)) => {
+      return "Disable any healthcheck inherited from the base image".to_string();
+    },
+    Some(HealthcheckCommand::Cmd(cmd)) => {
+      format!("Check container health by running {}", command(&cmd.expr))
+    },
+    None => "Declare a healthcheck without a command".to_string()
+  };
+
+  let flags: Vec<String> = healthcheck.flags
+    .iter()
+    .map(|f| code(&format!("--{}={}", f.name, f.value)))
+    .collect();
+
+  match flags.len() {
+    0 => explanation,
+    1 => format!("{}, with flag {}", explanation, list(&flags)),
+    _ => format!("{}, with flags {}", explanation, list(&flags)),
+  }
+}
+
+fn explain_from(from: &FromInstruction) -> String {
+  let image = from.image.as_ref();
+  let mut s = String::from("Start a new build stage");
+
+  if let Some(alias) = &from.alias {
+    s.push_str(&format!(" named {}", code(alias.as_ref())));
+  }
+
+  if image.eq_ignore_ascii_case("scratch") {
+    s.push_str(" from an empty image (`scratch`)");
+  } else {
+    s.push_str(&format!(" from image {}{}", code(image), variable_note(image)));
+  }
+
+  for flag in &from.flags {
+    if flag.name.content.eq_ignore_ascii_case("platform") {
+      s.push_str(&format!(" targeting platform {}", flag.value.content));
+    } else {
+      s.push_str(&format!(" with flag `--{}={}`", flag.name, flag.value));
+    }
+  }
+
+  s
+}
+
+fn explain_copy(copy: &CopyInstruction) -> String {
+  let sources = copy.sources.iter().map(|s| code(s.as_ref())).collect::<Vec<_>>();
+  let mut s = format!("Copy {}", list(&sources));
+
+  match copy.from_target() {
+    Some(CopyFromTarget::Index(index)) => s.push_str(&format!(" from stage {}", index)),
+    Some(CopyFromTarget::Name(name)) => {
+      // stage names can't contain these, but image references often do
+      if name.contains([':', '/', '@']) {
+        s.push_str(&format!(" from image {}", code(&name)));
+      } else {
+        s.push_str(&format!(" from stage {}", code(&name)));
+      }
+    },
+    Some(CopyFromTarget::NegativeIndex(_)) | Some(CopyFromTarget::Scratch) => {
+      let value = &copy.from_flag().unwrap().value;
+      s.push_str(&format!(" from {} (which is invalid)", code(value.as_ref())));
+    },
+    None => s.push_str(" from the build context"),
+  }
+
+  s.push_str(&format!(" into {}", code(copy.destination.as_ref())));
+  push_copy_details(&mut s, &copy.flags, &copy.sources, &copy.destination);
+
+  s
+}
+
+fn explain_add(add: &AddInstruction) -> String {
+  let sources = add.sources.iter().map(|s| code(s.as_ref())).collect::<Vec<_>>();
+  let mut s = format!("Add {} into {}", list(&sources), code(add.destination.as_ref()));
+  push_copy_details(&mut s, &add.flags, &add.sources, &add.destination);
+
+  s
+}
+
+/// Appends the details of `COPY` or `ADD` flags other than `--from`, and a
+/// note if any path contains variables.
+fn push_copy_details(
+  s: &mut String,
+  flags: &[CopyFlag],
+  sources: &[SpannedString],
+  destination: &SpannedString
+) {
+  let mut details = Vec::new();
+  for flag in flags {
+    let name = flag.name.content.to_ascii_lowercase();
+    match name.as_str() {
+      "from" => continue,
+      "chown" => details.push(format!("changing ownership to {}", code(flag.value.as_ref()))),
+      "chmod" => details.push(format!("setting permissions to {}", code(flag.value.as_ref()))),
+      "checksum" => details.push(format!("verifying checksum {}", code(flag.value.as_ref()))),
+      _ if flag.flag_value() == FlagValue::Bool(true, None) => {
+        details.push(format!("with flag `--{}`", flag.name))
+      },
+      _ => details.push(format!("with flag `--{}={}`", flag.name, flag.value)),
+    }
+  }
+
+  if !details.is_empty() {
+    s.push_str(", ");
+    s.push_str(&list(&details));
+  }
+
+  if sources.iter().chain(Some(destination)).any(|p| p.content.contains('$')) {
+    s.push_str(variable_note("$"));
+  }
+}
+
+impl Instruction {
+  /// Describes this instruction in plain English, e.g. "Run `make` in the
+  /// default shell".
+  ///
+  /// Explanations are deterministic and purely syntactic: variable references
+  /// are noted but not resolved, and unsupported instructions receive a
+  /// generic description.
+  ///
+  /// ```
+  /// use dockerfile_parser::Dockerfile;
+  ///
+  /// let dockerfile = Dockerfile::parse(r#"
+  ///   FROM alpine:3.19 AS builder
+  ///   COPY --from=builder --chown=app:app /app /usr/local/app
+  /// "#).unwrap();
+  ///
+  /// assert_eq!(
+  ///   dockerfile.instructions[0].explain(),
+  ///   "Start a new build stage named `builder` from image `alpine:3.19`"
+  /// );
+  /// assert_eq!(
+  ///   dockerfile.instructions[1].explain(),
+  ///   "Copy `/app` from stage `builder` into `/usr/local/app`, changing ownership to `app:app`"
+  /// );
+  /// ```
+  pub fn explain(&self) -> String {
+    match self {
+      Instruction::From(from) => explain_from(from),
+      Instruction::Arg(arg) => match &arg.value {
+        Some(value) => format!(
+          "Declare build argument {} with default value {}",
+          code(arg.name.as_ref()),
+          code(value.as_ref())
+        ),
+        None => format!("Declare build argument {}", code(arg.name.as_ref())),
+      },
+      Instruction::Label(label) => {
+        let pairs = label.labels
+          .iter()
+          .map(|l| format!("{} to {}", code(l.name.as_ref()), code(l.value.as_ref())))
+          .collect::<Vec<_>>();
+
+        let noun = if pairs.len() == 1 { "label" } else { "labels" };
+        format!("Set {} {}", noun, list(&pairs))
+      },
+      Instruction::Run(run) => format!("Run {}", command(&run.expr)),
+      Instruction::Entrypoint(entrypoint) => {
+        format!("Set the container entrypoint to run {}", command(&entrypoint.expr))
+      },
+      Instruction::Cmd(cmd) => {
+        format!("Set the default container command to run {}", command(&cmd.expr))
+      },
+      Instruction::Copy(copy) => explain_copy(copy),
+      Instruction::Add(add) => explain_add(add),
+      Instruction::Env(env) => {
+        let pairs = env.vars
+          .iter()
+          .map(|v| format!("{} to {}", code(v.key.as_ref()), code(&single_line(&v.value))))
+          .collect::<Vec<_>>();
+
+        let noun = if pairs.len() == 1 { "variable" } else { "variables" };
+        format!("Set environment {} {}", noun, list(&pairs))
+      },
+      Instruction::Volume(volume) => {
+        let paths = volume.paths.iter().map(|p| code(p.as_ref())).collect::<Vec<_>>();
+        let noun = if paths.len() == 1 { "mount point" } else { "mount points" };
+        format!("Declare volume {} {}", noun, list(&paths))
+      },
+      Instruction::User(user) => match &user.group {
+        Some(group) => format!(
+          "Run as user {} and group {}", code(user.user.as_ref()), code(group.as_ref())
+        ),
+        None => format!("Run as user {}", code(user.user.as_ref())),
+      },
+      Instruction::Workdir(workdir) => {
+        format!("Set the working directory to {}", code(workdir.path.as_ref()))
+      },
+      Instruction::Healthcheck(healthcheck) => explain_healthcheck(healthcheck),
+      Instruction::Shell(shell) => match shell.shell.as_str_vec().as_slice() {
+        [] => "Set the default shell to an empty command".to_string(),
+        [executable] => format!("Set the default shell to {}", code(executable)),
+        [executable, args @ ..] => format!(
+          "Set the default shell to {} with arguments {}",
+          code(executable),
+          list(&args.iter().map(|a| code(a)).collect::<Vec<_>>())
+        ),
+      },
+      Instruction::Stopsignal(stopsignal) => format!(
+        "Stop the container with signal {}", code(stopsignal.signal.as_ref())
+      ),
+      Instruction::Onbuild(onbuild) => format!(
+        "In builds using this image as a base, first: {}", onbuild.trigger.explain()
+      ),
+      Instruction::Maintainer(maintainer) => format!(
+        "Set the image author to {} (deprecated; prefer a `maintainer` label)",
+        code(&maintainer.name.content)
+      ),
+      Instruction::Misc(misc) => format!(
+        "Run the {} instruction with arguments {}",
+        code(&misc.instruction.content.to_ascii_uppercase()),
+        code(&single_line(&misc.arguments))
+      ),
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use indoc::indoc;
+  use pretty_assertions::assert_eq;
+
+  use crate::*;
+
+  #[test]
+  fn test_explain() {
+    let dockerfile = Dockerfile::parse(indoc!(r#"
+      ARG BASE_TAG=3.19
+      ARG VERSION
+      FROM --platform=linux/amd64 alpine:$BASE_TAG AS builder
+      RUN apk add --no-cache curl && \
+        # fetch the sources
+        curl -fsSL https://example.com/src.tar.gz | tar xz
+      ENV APP_HOME=/app LANG="C.UTF-8"
+      ENV single value with spaces
+      COPY . /src
+
+      FROM scratch
+      LABEL org.opencontainers.image.version=$VERSION
+      LABEL maintainer="me" vendor=example
+      COPY --from=builder --chown=app:app --chmod=0755 /app /usr/local/app
+      COPY --from=0 --link=true a b /opt/
+      COPY --from=alpine:3.19 /etc/passwd /etc/passwd
+      ADD --checksum=sha256:abc --chown=app https://example.com/app.tar.gz /opt/
+      ENTRYPOINT ["/usr/local/app/bin/server", "--port", "8080"]
+      CMD ["--help"]
+      CMD echo $HOME
+      RUN []
+      VOLUME ["/data", "/logs"]
+      WORKDIR /usr/local/app
+      HEALTHCHECK --interval=30s --retries=5 CMD curl -f localhost
+      HEALTHCHECK NONE
+      SHELL ["/bin/bash", "-o", "pipefail", "-c"]
+      STOPSIGNAL SIGQUIT
+      ONBUILD COPY . /src
+      MAINTAINER Jane Doe
+      EXPOSE 8080
+    "#)).unwrap();
+
+    assert_eq!(
+      dockerfile.instructions.iter().map(|i| i.explain()).collect::<Vec<_>>(),
+      vec![
+        "Declare build argument `BASE_TAG` with default value `3.19`",
+        "Declare build argument `VERSION`",
+        "Start a new build stage named `builder` from image `alpine:$BASE_TAG` \
+          (with variables substituted at build time) targeting platform linux/amd64",
+        "Run `apk add --no-cache curl && curl -fsSL https://example.com/src.tar.gz | tar xz` \
+          in the default shell",
+        "Set environment variables `APP_HOME` to `/app` and `LANG` to `C.UTF-8`",
+        "Set environment variable `single` to `value with spaces`",
+        "Copy `.` from the build context into `/src`",
+        "Start a new build stage from an empty image (`scratch`)",
+        "Set label `org.opencontainers.image.version` to `$VERSION`",
+        "Set labels `maintainer` to `me` and `vendor` to `example`",
+        "Copy `/app` from stage `builder` into `/usr/local/app`, \
+          changing ownership to `app:app` and setting permissions to `0755`",
+        "Copy `a` and `b` from stage 0 into `/opt/`, with flag `--link=true`",
+        "Copy `/etc/passwd` from image `alpine:3.19` into `/etc/passwd`",
+        "Add `https://example.com/app.tar.gz` into `/opt/`, \
+          verifying checksum `sha256:abc` and changing ownership to `app`",
+        "Set the container entrypoint to run `/usr/local/app/bin/server` \
+          with arguments `--port` and `8080` directly, without a shell",
+        "Set the default container command to run `--help` directly, without a shell",
+        "Set the default container command to run `echo $HOME` in the default shell",
+        "Run an empty command",
+        "Declare volume mount points `/data` and `/logs`",
+        "Set the working directory to `/usr/local/app`",
+        "Check container health by running `curl -f localhost` in the default shell, \
+          with flags `--interval=30s` and `--retries=5`",
+        "Disable any healthcheck inherited from the base image",
+        "Set the default shell to `/bin/bash` with arguments `-o`, `pipefail` and `-c`",
+        "Stop the container with signal `SIGQUIT`",
+        "In builds using this image as a base, first: \
+          Copy `.` from the build context into `/src`",
+        "Set the image author to `Jane Doe` (deprecated; prefer a `maintainer` label)",
+        "Run the `EXPOSE` instruction with arguments `8080`",
+      ]
+    );
+  }
+}
@@ -0,0 +1,212 @@
+// (C) Copyright 2026 Hewlett Packard Enterprise Development LP
+
+//! Machine-applicable fixes, e.g. as suggested by a [`Diagnostic`].
+//!
+//! [`Diagnostic`]: crate::Diagnostic
+
+use snafu::Snafu;
+
+use crate::dockerfile_parser::Dockerfile;
+use crate::splicer::Span;
+
+/// A single machine-applicable edit to a Dockerfile.
+///
+/// Fixes record the original text they replace, so they can be checked for
+/// staleness before being applied.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Fix {
+  /// The span of the original Dockerfile to replace
+  pub span: Span,
+
+  /// The original text at `span` when the fix was created
+  pub expected: String,
+
+  /// The replacement text
+  pub replacement: String,
+}
+
+impl Fix {
+  /// Creates a fix replacing `span` of the given Dockerfile, recording its
+  /// current text as the expected original.
+  pub fn new<S: Into<String>>(dockerfile: &Dockerfile, span: Span, replacement: S) -> Fix {
+    Fix {
+      span,
+      expected: dockerfile.text(&span).to_string(),
+      replacement: replacement.into(),
+    }
+  }
+}
+
+/// An error preventing a set of fixes from being applied by `apply()`.
+///
+/// Fixes are identified by their index in the order they were given.
+#[derive(Debug, Snafu, PartialEq, Eq)]
+pub enum FixConflict {
+  #[snafu(display(
+    "fix {} at {:?} overlaps fix {} at {:?}", first, first_span, second, second_span
+  ))]
+  Overlap {
+    first: usize,
+    first_span: Span,
+    second: usize,
+    second_span: Span,
+  },
+
+  #[snafu(display(
+    "fix {} at {:?} is stale: expected {:?} but found {:?}", index, span, expected, actual
+  ))]
+  Stale {
+    index: usize,
+    span: Span,
+    expected: String,
+
+    /// The text currently at `span`, or `None` if it is out of bounds
+    actual: Option<String>,
+  },
+}
+
+/// Applies a set of fixes to a Dockerfile in a single pass, returning the new
+/// content.
+///
+/// Fixes may be given in any order. If any two fixes overlap (or insert at the
+/// same position, where their order would be ambiguous), or if any fix's
+/// expected text no longer matches the Dockerfile, no fixes are applied and
+/// the conflict is returned.
+///
+/// ```
+/// use dockerfile_parser::{Dockerfile, fixes};
+///
+/// let dockerfile = Dockerfile::parse("FROM alpine\nENV a b\nLABEL c d\n").unwrap();
+/// let fixes = dockerfile.run_checks()
+///   .into_iter()
+///   .filter_map(|d| d.suggested_fix);
+///
+/// let content = fixes::apply(&dockerfile, fixes).unwrap();
+/// assert_eq!(content, "FROM alpine\nENV a=b\nLABEL c=d\n");
+/// ```
+pub fn apply(
+  dockerfile: &Dockerfile,
+  fixes: impl IntoIterator<Item = Fix>
+) -> Result<String, FixConflict> {
+  let mut fixes: Vec<(usize, Fix)> = fixes.into_iter().enumerate().collect();
+
+  for (index, fix) in &fixes {
+    let actual = fix.span.start
+      .checked_sub(dockerfile.base_offset)
+      .and_then(|start| dockerfile.content.get(start..fix.span.end - dockerfile.base_offset));
+
+    if actual != Some(fix.expected.as_str()) {
+      return Err(FixConflict::Stale {
+        index: *index,
+        span: fix.span,
+        expected: fix.expected.clone(),
+        actual: actual.map(String::from),
+      });
+    }
+  }
+
+  fixes.sort_by_key(|(index, fix)| (fix.span, *index));
+  for pair in fixes.windows(2) {
+    let (first, a) = &pair[0];
+    let (second, b) = &pair[1];
+
+    if b.span.start < a.span.end || b.span.start == a.span.start {
+      return Err(FixConflict::Overlap {
+        first: *first.min(second),
+        first_span: if first < second { a.span } else { b.span },
+        second: *first.max(second),
+        second_span: if first < second { b.span } else { a.span },
+      });
+    }
+  }
+
+  let mut splicer = dockerfile.splicer();
+  for (_, fix) in &fixes {
+    splicer.splice(&fix.span, &fix.replacement);
+  }
+
+  Ok(splicer.content)
+}
+
+#[cfg(test)]
+mod tests {
+  use indoc::indoc;
+  use pretty_assertions::assert_eq;
+
+  use super::*;
+
+  const FIXTURE: &str = indoc!(r#"
+    FROM alpine:3.12
+    ENV A 1
+    LABEL b c
+  "#);
+
+  #[test]
+  fn test_apply() {
+    let d = Dockerfile::parse(FIXTURE).unwrap();
+
+    // fixes may be given out of order
+    let fixes = vec![
+      Fix::new(&d, Span::new(32, 33), "="),
+      Fix::new(&d, Span::new(5, 16), "alpine:3.13"),
+      Fix::new(&d, Span::new(22, 23), "="),
+    ];
+    assert_eq!(apply(&d, fixes).unwrap(), indoc!(r#"
+      FROM alpine:3.13
+      ENV A=1
+      LABEL b=c
+    "#));
+
+    assert_eq!(apply(&d, vec![]).unwrap(), FIXTURE);
+  }
+
+  #[test]
+  fn test_apply_conflict() {
+    let d = Dockerfile::parse(FIXTURE).unwrap();
+
+    let fixes = vec![
+      Fix::new(&d, Span::new(22, 23), "="),
+      Fix::new(&d, Span::new(5, 16), "alpine:3.13"),
+      Fix::new(&d, Span::new(17, 24), "ENV A=2"),
+    ];
+    assert_eq!(apply(&d, fixes), Err(FixConflict::Overlap {
+      first: 0,
+      first_span: Span::new(22, 23),
+      second: 2,
+      second_span: Span::new(17, 24),
+    }));
+
+    // insertions at the same position are ambiguous
+    let fixes = vec![
+      Fix::new(&d, Span::new(17, 17), "# a\n"),
+      Fix::new(&d, Span::new(17, 17), "# b\n"),
+    ];
+    assert!(matches!(apply(&d, fixes), Err(FixConflict::Overlap { first: 0, second: 1, .. })));
+  }
+
+  #[test]
+  fn test_apply_stale() {
+    let d = Dockerfile::parse(FIXTURE).unwrap();
+    let rename = Fix::new(&d, Span::new(21, 22), "ALPHA");
+    let separator = Fix::new(&d, Span::new(22, 23), "=");
+
+    // once the first fix is applied, the second is stale
+    let renamed = Dockerfile::parse(&apply(&d, vec![rename]).unwrap()).unwrap();
+    assert_eq!(apply(&renamed, vec![separator.clone()]), Err(FixConflict::Stale {
+      index: 0,
+      span: Span::new(22, 23),
+      expected: " ".to_string(),
+      actual: Some("L".to_string()),
+    }));
+
+    let out_of_bounds = Fix {
+      span: Span::new(100, 101),
+      expected: "x".to_string(),
+      replacement: "y".to_string(),
+    };
+    assert!(matches!(
+      apply(&d, vec![separator, out_of_bounds]),
+      Err(FixConflict::Stale { index: 1, actual: None, .. })
+    ));
+  }
+}
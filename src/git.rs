@@ -0,0 +1,176 @@
+// (C) Copyright 2026 Hewlett Packard Enterprise Development LP
+
+use std::fmt;
+
+use crate::util::starts_with_ignore_case;
+
+/// A remote Git repository used as a build source, e.g.
+/// `https://github.com/user/repo.git#branch:subdir`.
+///
+/// BuildKit accepts a fragment after the repository URL in the form
+/// `#ref:subdir`, where either part may be omitted.
+///
+/// ```
+/// use dockerfile_parser::GitSource;
+///
+/// let source = GitSource::parse("https://github.com/user/repo.git#v1.0:docs").unwrap();
+/// assert_eq!(source.url, "https://github.com/user/repo.git");
+/// assert_eq!(source.reference, Some("v1.0".to_string()));
+/// assert_eq!(source.subdir, Some("docs".to_string()));
+///
+/// assert_eq!(GitSource::parse("https://example.com/file.tar.gz"), None);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GitSource {
+  /// The repository URL, without any fragment
+  pub url: String,
+
+  /// The branch, tag, or commit to check out, if given
+  pub reference: Option<String>,
+
+  /// The subdirectory of the repository to use as the source, if given
+  pub subdir: Option<String>,
+}
+
+/// Determines if a URL (without a fragment) refers to a Git repository.
+///
+/// Based on the URL forms recognized by BuildKit.
+fn is_git_url(url: &str) -> bool {
+  if starts_with_ignore_case(url, "git://") || starts_with_ignore_case(url, "ssh://") {
+    return true;
+  }
+
+  if starts_with_ignore_case(url, "http://") || starts_with_ignore_case(url, "https://") {
+    return url.ends_with(".git");
+  }
+
+  // scp-style ssh urls, e.g. git@github.com:org/repo.git
+  if starts_with_ignore_case(url, "git@") {
+    return url[4..].contains(':');
+  }
+
+  url.starts_with("github.com/")
+}
+
+fn non_empty(s: &str) -> Option<String> {
+  if s.is_empty() {
+    None
+  } else {
+    Some(s.to_string())
+  }
+}
+
+impl GitSource {
+  /// Parses a `GitSource` from an `ADD` source, returning `None` if the source
+  /// does not appear to be a Git repository.
+  pub fn parse(s: &str) -> Option<GitSource> {
+    let (url, fragment) = match s.find('#') {
+      Some(pos) => (&s[..pos], Some(&s[pos + 1..])),
+      None => (s, None)
+    };
+
+    if !is_git_url(url) {
+      return None;
+    }
+
+    let (reference, subdir) = match fragment {
+      Some(fragment) => match fragment.find(':') {
+        Some(pos) => (non_empty(&fragment[..pos]), non_empty(&fragment[pos + 1..])),
+        None => (non_empty(fragment), None)
+      },
+      None => (None, None)
+    };
+
+    Some(GitSource {
+      url: url.to_string(),
+      reference,
+      subdir,
+    })
+  }
+}
+
+impl fmt::Display for GitSource {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    write!(f, "{}", self.url)?;
+
+    if self.reference.is_some() || self.subdir.is_some() {
+      write!(f, "#{}", self.reference.as_deref().unwrap_or(""))?;
+    }
+
+    if let Some(subdir) = &self.subdir {
+      write!(f, ":{}", subdir)?;
+    }
+
+    Ok(())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use pretty_assertions::assert_eq;
+
+  use super::*;
+
+  fn git(url: &str, reference: Option<&str>, subdir: Option<&str>) -> GitSource {
+    GitSource {
+      url: url.to_string(),
+      reference: reference.map(String::from),
+      subdir: subdir.map(String::from),
+    }
+  }
+
+  #[test]
+  fn test_parse_git_source() {
+    assert_eq!(
+      GitSource::parse("https://github.com/user/repo.git"),
+      Some(git("https://github.com/user/repo.git", None, None))
+    );
+
+    // ref only
+    assert_eq!(
+      GitSource::parse("https://github.com/user/repo.git#main"),
+      Some(git("https://github.com/user/repo.git", Some("main"), None))
+    );
+
+    // subdir only
+    assert_eq!(
+      GitSource::parse("https://github.com/user/repo.git#:docs/api"),
+      Some(git("https://github.com/user/repo.git", None, Some("docs/api")))
+    );
+
+    // both
+    assert_eq!(
+      GitSource::parse("git@github.com:org/repo.git#v1.2.3:src"),
+      Some(git("git@github.com:org/repo.git", Some("v1.2.3"), Some("src")))
+    );
+
+    assert_eq!(
+      GitSource::parse("git://example.com/repo#abc123"),
+      Some(git("git://example.com/repo", Some("abc123"), None))
+    );
+    assert_eq!(
+      GitSource::parse("ssh://git@example.com/repo.git"),
+      Some(git("ssh://git@example.com/repo.git", None, None))
+    );
+    assert_eq!(
+      GitSource::parse("github.com/user/repo"),
+      Some(git("github.com/user/repo", None, None))
+    );
+
+    assert_eq!(GitSource::parse("https://example.com/archive.tar.gz"), None);
+    assert_eq!(GitSource::parse("./src"), None);
+    assert_eq!(GitSource::parse("git@localhost"), None);
+  }
+
+  #[test]
+  fn test_git_source_display() {
+    for s in &[
+      "https://github.com/user/repo.git",
+      "https://github.com/user/repo.git#main",
+      "https://github.com/user/repo.git#:docs",
+      "git@github.com:org/repo.git#v1:src",
+    ] {
+      assert_eq!(GitSource::parse(s).unwrap().to_string(), *s);
+    }
+  }
+}
@@ -0,0 +1,263 @@
+// (C) Copyright 2026 Hewlett Packard Enterprise Development LP
+
+use crate::dockerfile_parser::{Dockerfile, Instruction};
+use crate::stage::Stage;
+
+const FNV_OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+/// A 64-bit [FNV-1a] hasher, used for source hashes.
+///
+/// Unlike `std::collections::hash_map::DefaultHasher`, the output of FNV-1a is
+/// fully specified, so hashes are stable across processes, platforms, and
+/// Rust versions and may be persisted, e.g. as cache keys. It is not a
+/// cryptographic hash.
+///
+/// ```
+/// use dockerfile_parser::SourceHasher;
+///
+/// let mut hasher = SourceHasher::new();
+/// hasher.write(b"a");
+/// assert_eq!(hasher.finish(), 0xaf63dc4c8601ec8c);
+/// ```
+///
+/// [FNV-1a]: http://www.isthe.com/chongo/tech/comp/fnv/
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SourceHasher(u64);
+
+impl SourceHasher {
+  pub fn new() -> SourceHasher {
+    SourceHasher(FNV_OFFSET_BASIS)
+  }
+
+  /// Adds the given bytes to the hash.
+  pub fn write(&mut self, bytes: &[u8]) {
+    for byte in bytes {
+      self.0 ^= u64::from(*byte);
+      self.0 = self.0.wrapping_mul(FNV_PRIME);
+    }
+  }
+
+  /// Adds a `u64` to the hash as 8 little-endian bytes, regardless of
+  /// platform.
+  pub fn write_u64(&mut self, value: u64) {
+    self.write(&value.to_le_bytes());
+  }
+
+  /// Returns the hash of everything written so far.
+  pub fn finish(&self) -> u64 {
+    self.0
+  }
+}
+
+impl Default for SourceHasher {
+  fn default() -> Self {
+    SourceHasher::new()
+  }
+}
+
+/// Hashes a string with `SourceHasher`.
+fn hash_str(s: &str) -> u64 {
+  let mut hasher = SourceHasher::new();
+  hasher.write(s.as_bytes());
+  hasher.finish()
+}
+
+/// Combines the preamble hash with the given instruction hashes, in order.
+fn combine(preamble: u64, instructions: impl IntoIterator<Item = u64>) -> u64 {
+  let mut hasher = SourceHasher::new();
+  hasher.write_u64(preamble);
+  for hash in instructions {
+    hasher.write_u64(hash);
+  }
+
+  hasher.finish()
+}
+
+/// Source hashes of a Dockerfile, its stages, and its instructions, as
+/// returned by `Dockerfile::hashes()`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SourceHashes {
+  /// The hash of the preamble; see `Dockerfile::preamble_hash()`
+  pub preamble: u64,
+
+  /// The hash of each instruction, by index in `Dockerfile::instructions`
+  pub instructions: Vec<u64>,
+
+  /// The hash of each stage, by stage index
+  pub stages: Vec<u64>,
+}
+
+impl Instruction {
+  /// Returns a stable hash of this instruction's source text, as covered by
+  /// its span; see `SourceHasher`.
+  ///
+  /// Only the instruction's own text is hashed, so the hash doesn't change
+  /// when other instructions, comments, or blank lines around it are edited,
+  /// or when it moves. Instructions with synthetic spans have no source text
+  /// and all share the same hash.
+  ///
+  /// ```
+  /// use dockerfile_parser::Dockerfile;
+  ///
+  /// let a = Dockerfile::parse("FROM alpine\nRUN make\n").unwrap();
+  /// let b = Dockerfile::parse("FROM alpine\n\n# build\nRUN make\n").unwrap();
+  /// assert_eq!(a.instructions[1].source_hash(&a), b.instructions[1].source_hash(&b));
+  /// ```
+  pub fn source_hash(&self, dockerfile: &Dockerfile) -> u64 {
+    hash_str(dockerfile.text(&self.span()))
+  }
+}
+
+impl<'a> Stage<'a> {
+  /// Returns a stable hash of this stage's source, combining the
+  /// `Dockerfile::preamble_hash()` with the `Instruction::source_hash()` of
+  /// each of its instructions, in order.
+  ///
+  /// The preamble is included as global `ARG`s and parser directives may
+  /// change the meaning of any stage. Comments and blank lines within the
+  /// stage aren't included.
+  pub fn source_hash(&self, dockerfile: &Dockerfile) -> u64 {
+    combine(
+      dockerfile.preamble_hash(),
+      self.instructions.iter().map(|ins| ins.source_hash(dockerfile))
+    )
+  }
+}
+
+impl Dockerfile {
+  /// Returns a stable hash of the text preceding the first stage, i.e. parser
+  /// directives, global `ARG`s, and any comments; see `preamble_slice()`.
+  pub fn preamble_hash(&self) -> u64 {
+    hash_str(self.preamble_slice().1)
+  }
+
+  /// Computes the source hashes of the preamble, every instruction, and every
+  /// stage in one pass, hashing each instruction only once. The results are
+  /// the same as those of `Dockerfile::preamble_hash()`,
+  /// `Instruction::source_hash()`, and `Stage::source_hash()`.
+  ///
+  /// Hashes are stable across processes and may be used as keys for caching
+  /// analysis results; see `SourceHasher`.
+  ///
+  /// ```
+  /// use dockerfile_parser::Dockerfile;
+  ///
+  /// let before = Dockerfile::parse("FROM alpine AS a\nRUN make\n\nFROM a\nRUN test\n").unwrap();
+  /// let after = Dockerfile::parse("FROM alpine AS a\nRUN make\n\nFROM a\nRUN tests\n").unwrap();
+  ///
+  /// let (before, after) = (before.hashes(), after.hashes());
+  /// assert_eq!(before.stages[0], after.stages[0]);
+  /// assert_ne!(before.stages[1], after.stages[1]);
+  /// ```
+  pub fn hashes(&self) -> SourceHashes {
+    let preamble = self.preamble_hash();
+    let instructions: Vec<u64> = self.instructions
+      .iter()
+      .map(|ins| ins.source_hash(self))
+      .collect();
+
+    let stages = self.stages()
+      .iter()
+      .map(|stage| combine(preamble, stage.instruction_indices.iter().map(|i| instructions[*i])))
+      .collect();
+
+    SourceHashes { preamble, instructions, stages }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use indoc::indoc;
+  use pretty_assertions::assert_eq;
+
+  use super::*;
+
+  #[test]
+  fn test_source_hasher() {
+    // reference values for 64-bit FNV-1a
+    assert_eq!(hash_str(""), 0xcbf29ce484222325);
+    assert_eq!(hash_str("a"), 0xaf63dc4c8601ec8c);
+    assert_eq!(hash_str("foobar"), 0x85944171f73967e8);
+
+    // hashes are persisted, so must never change
+    let d = Dockerfile::parse("FROM alpine:3.19\n").unwrap();
+    assert_eq!(d.instructions[0].source_hash(&d), 0xa03ebe5ccdac8349);
+  }
+
+  #[test]
+  fn test_hashes() {
+    let before = Dockerfile::parse(indoc!(r#"
+      # syntax=docker/dockerfile:1
+      ARG VERSION=1.0
+
+      FROM alpine:3.19 AS build
+      RUN make VERSION=$VERSION
+      COPY . /src
+
+      FROM scratch
+      COPY --from=build /out /
+    "#)).unwrap();
+
+    let hashes = before.hashes();
+    assert_eq!(hashes.preamble, before.preamble_hash());
+    assert_eq!(hashes.instructions.len(), 6);
+    assert_eq!(
+      hashes.stages,
+      before.iter_stages().map(|s| s.source_hash(&before)).collect::<Vec<_>>()
+    );
+
+    // a one-character change only affects its instruction and stage
+    let after = Dockerfile::parse(indoc!(r#"
+      # syntax=docker/dockerfile:1
+      ARG VERSION=1.0
+
+      FROM alpine:3.19 AS build
+      RUN make VERSION=$VERSION
+      COPY . /src/
+
+      FROM scratch
+      COPY --from=build /out /
+    "#)).unwrap().hashes();
+
+    assert_eq!(after.preamble, hashes.preamble);
+    let changed: Vec<usize> = (0..6)
+      .filter(|i| after.instructions[*i] != hashes.instructions[*i])
+      .collect();
+    assert_eq!(changed, vec![3]);
+    assert_ne!(after.stages[0], hashes.stages[0]);
+    assert_eq!(after.stages[1], hashes.stages[1]);
+
+    // comments and blank lines within stages aren't hashed
+    let comments = Dockerfile::parse(indoc!(r#"
+      # syntax=docker/dockerfile:1
+      ARG VERSION=1.0
+
+      FROM alpine:3.19 AS build
+      # build it
+      RUN make VERSION=$VERSION
+
+      COPY . /src
+
+      FROM scratch
+      COPY --from=build /out /
+    "#)).unwrap().hashes();
+    assert_eq!(comments, hashes);
+
+    // the preamble affects every stage
+    let preamble = Dockerfile::parse(indoc!(r#"
+      # syntax=docker/dockerfile:1
+      ARG VERSION=1.1
+
+      FROM alpine:3.19 AS build
+      RUN make VERSION=$VERSION
+      COPY . /src
+
+      FROM scratch
+      COPY --from=build /out /
+    "#)).unwrap().hashes();
+    assert_eq!(&preamble.instructions[1..], &hashes.instructions[1..]);
+    assert_ne!(preamble.stages[0], hashes.stages[0]);
+    assert_ne!(preamble.stages[1], hashes.stages[1]);
+  }
+}
@@ -0,0 +1,1021 @@
+// (C) Copyright 2026 Hewlett Packard Enterprise Development LP
+
+use std::borrow::Cow;
+
+use crate::dockerfile_parser::Instruction;
+use crate::error::*;
+use crate::splicer::Span;
+use crate::util::SpannedString;
+
+/// A [heredoc] passed to an instruction, e.g. `RUN <<EOF`.
+///
+/// The heredoc's body follows the instruction on subsequent lines, up to a
+/// line containing only the delimiter. The span of the instruction is
+/// extended to cover the body and the terminating line.
+///
+/// With `<<-`, the terminating line may be indented with tabs, and leading
+/// tabs are removed from each line of the body; see `dedented()`.
+///
+/// [heredoc]: https://docs.docker.com/reference/dockerfile/#here-documents
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Heredoc {
+  /// The span of the marker within the instruction, e.g. `<<EOF` or `<<-EOF`
+  pub marker: Span,
+
+  /// The delimiter word without any quotes, e.g. `EOF`
+  pub delimiter: String,
+
+  /// If true, the delimiter was unquoted and the body is subject to shell
+  /// expansion; quoting any part of the delimiter, e.g. `<<'EOF'`, `<<"EOF"`,
+  /// or `<<\EOF`, keeps the body literal
+  pub expand: bool,
+
+  /// If true, the marker was `<<-` and leading tabs are stripped from the
+  /// body and terminating line
+  pub strip_tabs: bool,
+
+  /// The body of the heredoc as written, including its final line break
+  pub body: SpannedString,
+
+  /// The span of the terminating delimiter line, including any leading tabs
+  /// but excluding its line break
+  pub terminator: Span,
+}
+
+impl Heredoc {
+  /// Returns the body as it is passed to the instruction: for `<<-` heredocs,
+  /// leading tabs are removed from each line, as in the shell. Spaces, and any
+  /// tabs following them, are kept. Other bodies are returned as written.
+  ///
+  /// ```
+  /// use dockerfile_parser::Dockerfile;
+  ///
+  /// let dockerfile = Dockerfile::parse(
+  ///   "FROM alpine\nRUN <<-EOF\n\tif true; then\n\t\techo hi\n\tfi\n\tEOF\n"
+  /// ).unwrap();
+  ///
+  /// let heredoc = &dockerfile.instructions[1].as_run().unwrap().heredocs[0];
+  /// assert_eq!(heredoc.body.content, "\tif true; then\n\t\techo hi\n\tfi\n");
+  /// assert_eq!(heredoc.dedented(), "if true; then\necho hi\nfi\n");
+  /// ```
+  pub fn dedented(&self) -> Cow<'_, str> {
+    if !self.strip_tabs {
+      return Cow::Borrowed(&self.body.content);
+    }
+
+    Cow::Owned(
+      self.body.content
+        .split_inclusive('\n')
+        .map(|line| line.trim_start_matches('\t'))
+        .collect()
+    )
+  }
+
+  /// Returns the first line of the body if it is a shebang, e.g.
+  /// `#!/usr/bin/env python3`, excluding its line break.
+  pub fn shebang(&self) -> Option<SpannedString> {
+    let body = &self.body.content;
+    if !body.starts_with("#!") {
+      return None;
+    }
+
+    let line = body.split('\n').next().unwrap_or_default().trim_end_matches('\r');
+    Some(SpannedString {
+      span: Span::new(self.body.span.start, self.body.span.start + line.len()),
+      content: line.to_string(),
+      raw_text: None,
+      quoted: false,
+    })
+  }
+
+  /// Returns the interpreter named by the body's shebang, if any.
+  ///
+  /// For shebangs using `env`, e.g. `#!/usr/bin/env -S python3 -u`, this is
+  /// the program it runs (`python3`); otherwise it is the interpreter's path,
+  /// e.g. `/bin/sh`.
+  ///
+  /// ```
+  /// use dockerfile_parser::Dockerfile;
+  ///
+  /// let dockerfile = Dockerfile::parse(
+  ///   "FROM python:3\nRUN <<EOF\n#!/usr/bin/env python3\nprint('hi')\nEOF\n"
+  /// ).unwrap();
+  ///
+  /// let heredoc = &dockerfile.instructions[1].as_run().unwrap().heredocs[0];
+  /// assert_eq!(heredoc.shebang().unwrap().content, "#!/usr/bin/env python3");
+  /// assert_eq!(heredoc.interpreter().unwrap().content, "python3");
+  /// ```
+  pub fn interpreter(&self) -> Option<SpannedString> {
+    let shebang = self.shebang()?;
+
+    let mut words = shebang.content[2..]
+      .split([' ', '\t'])
+      .scan(2, |pos, word| {
+        let start = *pos;
+        *pos += word.len() + 1;
+        Some((start, word))
+      })
+      .filter(|(_, word)| !word.is_empty())
+      .map(|(start, word)| SpannedString {
+        span: Span::new(shebang.span.start + start, shebang.span.start + start + word.len()),
+        content: word.to_string(),
+        raw_text: None,
+        quoted: false,
+      });
+
+    let path = words.next()?;
+    if path.content != "env" && !path.content.ends_with("/env") {
+      return Some(path);
+    }
+
+    // skip options and variable assignments passed to env
+    words
+      .find(|w| !w.content.starts_with('-') && !w.content.contains('='))
+      .or(Some(path))
+  }
+}
+
+/// A token of shell text as split by `tokenize()`.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub(crate) enum ShellToken {
+  /// A word, with quotes and escapes removed
+  Word(SpannedString),
+
+  /// A control or redirection operator, e.g. `&&` or `>>`
+  Operator(SpannedString),
+
+  /// A heredoc marker, e.g. `<<EOF`, with its unquoted delimiter, whether its
+  /// body is expanded, and whether leading tabs are stripped (`<<-`)
+  Heredoc(Span, String, bool, bool),
+}
+
+impl ShellToken {
+  pub(crate) fn span(&self) -> Span {
+    match self {
+      ShellToken::Word(s) | ShellToken::Operator(s) => s.span,
+      ShellToken::Heredoc(span, ..) => *span,
+    }
+  }
+
+  /// Determines if this token separates commands, e.g. `&&` or `|`.
+  pub(crate) fn is_separator(&self) -> bool {
+    matches!(
+      self,
+      ShellToken::Operator(op) if matches!(op.content.as_str(), "&&" | "||" | ";" | "|" | "&" | "\n")
+    )
+  }
+}
+
+const OPERATORS: &[&str] = &[
+  "<<<", "&&", "||", ">>", ">&", "<&", ">|", "&", ";", "|", "<", ">", "(", ")", "\n",
+];
+
+fn is_delimiter_char(c: char) -> bool {
+  c.is_ascii_alphanumeric() || c == '_'
+}
+
+/// Scans the delimiter word following a heredoc's `<<` (or `<<-`), returning
+/// the unquoted delimiter, the length of the word as written, and whether any
+/// part of it was quoted, e.g. `<<'EOF'`, `<<"EOF"`, `<<\EOF`, or `<<E"O"F`.
+///
+/// As in the shell, quoting any part of the delimiter disables expansion of
+/// the body. Returns `None` if there is no delimiter or a quote is unclosed.
+fn scan_delimiter(s: &str) -> Option<(String, usize, bool)> {
+  let mut delimiter = String::new();
+  let mut quoted = false;
+  let mut len = 0;
+
+  let mut chars = s.char_indices().peekable();
+  while let Some((i, c)) = chars.next() {
+    match c {
+      '\'' | '"' => {
+        let close = s[i + 1..].find([c, '\n'])?;
+        if !s[i + 1 + close..].starts_with(c) {
+          return None;
+        }
+
+        delimiter.push_str(&s[i + 1..i + 1 + close]);
+        len = i + close + 2;
+        quoted = true;
+        while chars.next_if(|(j, _)| *j < len).is_some() {}
+      },
+      '\\' => match chars.next() {
+        Some((j, next)) if next != '\n' => {
+          delimiter.push(next);
+          len = j + next.len_utf8();
+          quoted = true;
+        },
+        _ => break
+      },
+      c if is_delimiter_char(c) => {
+        delimiter.push(c);
+        len = i + c.len_utf8();
+      },
+      _ => break
+    }
+  }
+
+  if delimiter.is_empty() {
+    None
+  } else {
+    Some((delimiter, len, quoted))
+  }
+}
+
+/// Splits a shell command into words and operators, roughly following POSIX
+/// shell rules. `offset` is the position of `s` in the Dockerfile and is added
+/// to all spans.
+///
+/// This is a best-effort tokenizer intended for simple analysis: expansions
+/// are left as-is and unterminated quotes extend to the end of the string.
+pub(crate) fn tokenize(s: &str, offset: usize) -> Vec<ShellToken> {
+  tokenize_mode(s, offset, true)
+}
+
+/// Splits non-shell text into words on unquoted whitespace, with quotes and
+/// escapes removed. Unlike `tokenize()`, operators and heredoc markers are
+/// treated as ordinary text.
+pub(crate) fn split_words(s: &str, offset: usize) -> Vec<SpannedString> {
+  tokenize_mode(s, offset, false)
+    .into_iter()
+    .filter_map(|token| match token {
+      ShellToken::Word(word) => Some(word),
+      _ => None
+    })
+    .collect()
+}
+
+fn tokenize_mode(s: &str, offset: usize, shell: bool) -> Vec<ShellToken> {
+  let mut tokens = Vec::new();
+  let mut word: Option<(usize, String)> = None;
+  let mut chars = s.char_indices().peekable();
+
+  let finish = |word: &mut Option<(usize, String)>, end: usize, tokens: &mut Vec<ShellToken>| {
+    if let Some((start, content)) = word.take() {
+      tokens.push(ShellToken::Word(SpannedString {
+        span: Span::new(offset + start, offset + end),
+        content,
+        raw_text: None,
+        quoted: false,
+      }));
+    }
+  };
+
+  while let Some((i, c)) = chars.next() {
+    match c {
+      '\n' if shell => {
+        finish(&mut word, i, &mut tokens);
+        tokens.push(ShellToken::Operator(SpannedString {
+          span: Span::new(offset + i, offset + i + 1),
+          content: "\n".into(),
+          raw_text: None,
+          quoted: false,
+        }));
+      },
+      c if c.is_whitespace() => finish(&mut word, i, &mut tokens),
+      '\'' => {
+        let content = &mut word.get_or_insert_with(|| (i, String::new())).1;
+        for (_, c) in chars.by_ref() {
+          if c == '\'' {
+            break;
+          }
+
+          content.push(c);
+        }
+      },
+      '"' => {
+        let content = &mut word.get_or_insert_with(|| (i, String::new())).1;
+        while let Some((_, c)) = chars.next() {
+          match c {
+            '"' => break,
+            '\\' => match chars.peek() {
+              Some((_, next @ ('"' | '\\' | '$' | '`'))) => {
+                content.push(*next);
+                chars.next();
+              },
+              _ => content.push(c)
+            },
+            _ => content.push(c)
+          }
+        }
+      },
+      '\\' => {
+        let content = &mut word.get_or_insert_with(|| (i, String::new())).1;
+        if let Some((_, next)) = chars.next() {
+          content.push(next);
+        }
+      },
+      '$' if s[i..].starts_with("${") => {
+        // keep braced variable references intact, even if they contain
+        // whitespace, e.g. `${FOO:-a b}`
+        let content = &mut word.get_or_insert_with(|| (i, String::new())).1;
+        content.push(c);
+
+        let mut depth = 0;
+        for (_, c) in chars.by_ref() {
+          content.push(c);
+          match c {
+            '{' => depth += 1,
+            '}' if depth == 1 => break,
+            '}' => depth -= 1,
+            _ => ()
+          }
+        }
+      },
+      _ if shell && s[i..].starts_with("<<") && !s[i..].starts_with("<<<") => {
+        finish(&mut word, i, &mut tokens);
+        chars.next();
+
+        // `<<-` strips leading tabs, and either may be followed by blanks
+        // before the delimiter
+        let strip_tabs = s[i + 2..].starts_with('-');
+        let operator_len = if strip_tabs { 3 } else { 2 };
+        let rest = &s[i + operator_len..];
+        let start = i + operator_len + rest.len() - rest.trim_start_matches([' ', '\t']).len();
+
+        match scan_delimiter(&s[start..]) {
+          Some((delimiter, len, quoted)) => {
+            let end = start + len;
+            while chars.next_if(|(j, _)| *j < end).is_some() {}
+
+            tokens.push(ShellToken::Heredoc(
+              Span::new(offset + i, offset + end),
+              delimiter,
+              !quoted,
+              strip_tabs
+            ));
+          },
+          None => tokens.push(ShellToken::Operator(SpannedString {
+            span: Span::new(offset + i, offset + i + 2),
+            content: "<<".into(),
+            raw_text: None,
+            quoted: false,
+          }))
+        }
+      },
+      _ => match OPERATORS.iter().find(|op| shell && s[i..].starts_with(*op)) {
+        Some(op) => {
+          // a numeric word directly preceding a redirection is a file
+          // descriptor, e.g. `2>`
+          let fd = match &word {
+            Some((_, content)) if (op.starts_with('>') || op.starts_with('<'))
+              && content.chars().all(|c| c.is_ascii_digit()) => word.take(),
+            _ => {
+              finish(&mut word, i, &mut tokens);
+              None
+            }
+          };
+
+          for _ in 1..op.len() {
+            chars.next();
+          }
+
+          let (start, content) = match fd {
+            Some((start, fd)) => (start, format!("{}{}", fd, op)),
+            None => (i, op.to_string())
+          };
+
+          tokens.push(ShellToken::Operator(SpannedString {
+            span: Span::new(offset + start, offset + i + op.len()),
+            content,
+            raw_text: None,
+            quoted: false,
+          }));
+        },
+        None => word.get_or_insert_with(|| (i, String::new())).1.push(c)
+      }
+    }
+  }
+
+  finish(&mut word, s.len(), &mut tokens);
+
+  tokens
+}
+
+/// Returns the (start, end, next) offsets of the line starting at `pos`,
+/// where `end` excludes the line break and `next` is the start of the
+/// following line.
+pub(crate) fn line_at(content: &str, pos: usize) -> (usize, usize, usize) {
+  let (end, next) = match content[pos..].find('\n') {
+    Some(i) => (pos + i, pos + i + 1),
+    None => (content.len(), content.len())
+  };
+
+  let end = if content[pos..end].ends_with('\r') { end - 1 } else { end };
+
+  (pos, end, next)
+}
+
+fn ends_with_continuation(line: &str) -> bool {
+  line.trim_end().ends_with('\\')
+}
+
+/// Finds the bodies of all heredocs following `RUN`, `COPY`, and `ADD`
+/// instructions.
+///
+/// Like BuildKit, this works line by line: heredoc markers are collected from
+/// each line of an instruction, and once the instruction ends, each marker
+/// consumes the following lines up to its delimiter, in order. Markers without
+/// a terminating line are ignored.
+pub(crate) fn find_heredocs(content: &str) -> Vec<Heredoc> {
+  scan_heredocs(content, false).0
+}
+
+/// Like `find_heredocs()`, but an unterminated heredoc extends to the end of
+/// the content with an empty terminator, e.g. while it is still being typed.
+pub(crate) fn find_heredocs_lenient(content: &str) -> Vec<Heredoc> {
+  scan_heredocs(content, true).0
+}
+
+/// Like `find_heredocs()`, but returns `Error::UnterminatedHeredoc` for the
+/// first `COPY` or `ADD` heredoc without a terminating line.
+///
+/// Unterminated `RUN` heredocs are still ignored, as `<<` may have other
+/// meanings in shell commands.
+pub(crate) fn find_heredocs_checked(content: &str) -> Result<Vec<Heredoc>> {
+  match scan_heredocs(content, false) {
+    (_, Some((span, delimiter))) => Err(Error::UnterminatedHeredoc {
+      line: content[..span.start].matches('\n').count() + 1,
+      delimiter,
+      span,
+    }),
+    (heredocs, None) => Ok(heredocs)
+  }
+}
+
+/// Scans for heredocs, also returning the marker and delimiter of the first
+/// unterminated `COPY` or `ADD` heredoc, if any.
+fn scan_heredocs(content: &str, lenient: bool) -> (Vec<Heredoc>, Option<(Span, String)>) {
+  let mut heredocs = Vec::new();
+  let mut unterminated = None;
+  let mut pos = if content.starts_with('\u{feff}') { '\u{feff}'.len_utf8() } else { 0 };
+
+  let mut continuing = false;
+  let mut has_heredocs = false;
+  let mut is_copy_or_add = false;
+  let mut markers: Vec<(Span, String, bool, bool)> = Vec::new();
+
+  while pos < content.len() {
+    let (start, end, next) = line_at(content, pos);
+    let line = &content[start..end];
+    let trimmed = line.trim_start();
+    pos = next;
+
+    if trimmed.is_empty() || trimmed.starts_with('#') {
+      continue;
+    }
+
+    if !continuing {
+      let keyword = trimmed.split_whitespace().next().unwrap_or("");
+      is_copy_or_add = keyword.eq_ignore_ascii_case("copy") || keyword.eq_ignore_ascii_case("add");
+      has_heredocs = is_copy_or_add || keyword.eq_ignore_ascii_case("run");
+    }
+
+    continuing = ends_with_continuation(line);
+    if !has_heredocs {
+      continue;
+    }
+
+    // COPY and ADD arguments aren't shell text, so only whole words are heredocs, not
+    // e.g. a `<<` within a flag value
+    markers.extend(tokenize(line, start).into_iter().filter_map(|token| match token {
+      ShellToken::Heredoc(span, delimiter, expand, strip_tabs) => {
+        Some((span, delimiter, expand, strip_tabs))
+      },
+      _ => None
+    }).filter(|(span, ..)| {
+      !is_copy_or_add || content[..span.start].ends_with(char::is_whitespace)
+    }));
+
+    if continuing {
+      continue;
+    }
+
+    for (marker, delimiter, expand, strip_tabs) in markers.drain(..) {
+      let body_start = pos;
+      let mut terminator = None;
+
+      while pos < content.len() {
+        let (start, end, next) = line_at(content, pos);
+        pos = next;
+
+        let line = &content[start..end];
+        let line = if strip_tabs { line.trim_start_matches('\t') } else { line };
+        if line == delimiter {
+          terminator = Some(Span::new(start, end));
+          break;
+        }
+      }
+
+      let terminator = match terminator {
+        None if lenient => Some(Span::new(content.len(), content.len())),
+        terminator => terminator
+      };
+
+      match terminator {
+        Some(terminator) => heredocs.push(Heredoc {
+          marker,
+          delimiter,
+          expand,
+          strip_tabs,
+          body: SpannedString {
+            span: Span::new(body_start, terminator.start),
+            content: content[body_start..terminator.start].to_string(),
+            raw_text: None,
+            quoted: false,
+          },
+          terminator,
+        }),
+        None => {
+          if is_copy_or_add && unterminated.is_none() {
+            unterminated = Some((marker, delimiter));
+          }
+
+          pos = body_start;
+          break;
+        }
+      }
+    }
+  }
+
+  (heredocs, unterminated)
+}
+
+/// Blanks out the bodies and terminating lines of the given heredocs so the
+/// remaining content can be parsed line by line. Line breaks are preserved,
+/// so all offsets remain valid.
+pub(crate) fn mask_heredocs<'a>(content: &'a str, heredocs: &[Heredoc]) -> Cow<'a, str> {
+  if heredocs.is_empty() {
+    return Cow::Borrowed(content);
+  }
+
+  let mut masked = content.as_bytes().to_vec();
+  for heredoc in heredocs {
+    for b in &mut masked[heredoc.body.span.start..heredoc.terminator.end] {
+      if *b != b'\n' && *b != b'\r' {
+        *b = b' ';
+      }
+    }
+  }
+
+  // only ASCII bytes were written over whole lines, so the result is valid
+  Cow::Owned(String::from_utf8(masked).expect("masked content must be valid utf-8"))
+}
+
+/// Attaches heredocs to the instructions whose text contains their markers,
+/// extending the instructions' spans to cover their bodies. Heredocs whose
+/// markers don't fall within any heredoc-capable instruction are dropped.
+pub(crate) fn attach_heredocs(instructions: &mut [Instruction], heredocs: Vec<Heredoc>) {
+  let mut heredocs = heredocs.into_iter().peekable();
+
+  for ins in instructions {
+    let (span, attached) = match ins {
+      Instruction::Run(run) => (&mut run.span, &mut run.heredocs),
+      Instruction::Copy(copy) => (&mut copy.span, &mut copy.heredocs),
+      Instruction::Add(add) => (&mut add.span, &mut add.heredocs),
+      _ => continue
+    };
+
+    // markers before this instruction's span belong to no instruction
+    while heredocs.next_if(|h| h.marker.start < span.start).is_some() {}
+
+    while let Some(heredoc) = heredocs.next_if(|h| h.marker.start < span.end) {
+      span.end = heredoc.terminator.end;
+      attached.push(heredoc);
+    }
+  }
+}
+
+/// Tracks quoting across the lines of a shell script, as used by
+/// `reindent_body()`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum QuoteState {
+  None,
+  Single,
+  Double,
+}
+
+/// Scans a line of shell text starting in the given quote state, returning the
+/// state at the end of the line. Comments are skipped.
+fn scan_quotes(line: &str, mut state: QuoteState) -> QuoteState {
+  let mut chars = line.chars();
+  let mut word_start = true;
+
+  while let Some(c) = chars.next() {
+    state = match (state, c) {
+      (QuoteState::None, '#') if word_start => break,
+      (QuoteState::None, '\\') | (QuoteState::Double, '\\') => {
+        chars.next();
+        state
+      },
+      (QuoteState::None, '\'') => QuoteState::Single,
+      (QuoteState::None, '"') => QuoteState::Double,
+      (QuoteState::Single, '\'') | (QuoteState::Double, '"') => QuoteState::None,
+      _ => state
+    };
+
+    word_start = c.is_whitespace();
+  }
+
+  state
+}
+
+/// Re-indents the lines of a shell script heredoc body so that each distinct
+/// indentation depth becomes a multiple of `width` spaces, starting from no
+/// indentation. The relative nesting of lines is preserved and whitespace-only
+/// lines are emptied.
+///
+/// The bodies and terminators of heredocs nested within the script are kept
+/// as-is. Returns `None` if the body can't be safely re-indented: if it mixes
+/// tab and space indentation, has a quoted string spanning multiple lines, or
+/// uses `<<-`, whose terminators may be indented.
+pub(crate) fn reindent_body(body: &str, width: usize) -> Option<String> {
+  let lines: Vec<&str> = body.split_inclusive('\n').collect();
+
+  // whether each line may be re-indented, i.e. is not part of a nested heredoc
+  let mut movable = vec![true; lines.len()];
+  let mut markers: Vec<String> = Vec::new();
+  let mut quotes = QuoteState::None;
+  let mut i = 0;
+
+  while i < lines.len() {
+    let line = lines[i].trim_end_matches('\n').trim_end_matches('\r');
+    if line.contains("<<-") {
+      return None;
+    }
+
+    quotes = scan_quotes(line, quotes);
+    if quotes != QuoteState::None {
+      return None;
+    }
+
+    markers.extend(tokenize(line, 0).into_iter().filter_map(|token| match token {
+      ShellToken::Heredoc(_, delimiter, ..) => Some(delimiter),
+      _ => None
+    }));
+
+    i += 1;
+    if ends_with_continuation(line) {
+      continue;
+    }
+
+    for delimiter in markers.drain(..) {
+      while i < lines.len() {
+        movable[i] = false;
+        i += 1;
+
+        if lines[i - 1].trim_end_matches('\n').trim_end_matches('\r') == delimiter {
+          break;
+        }
+      }
+    }
+  }
+
+  let indent = |line: &str| line.len() - line.trim_start_matches([' ', '\t']).len();
+  let is_blank = |line: &str| line.trim().is_empty();
+
+  let mut depths: Vec<usize> = Vec::new();
+  let mut uses_tabs = false;
+  let mut uses_spaces = false;
+  for (line, _) in lines.iter().zip(&movable).filter(|(l, m)| **m && !is_blank(l)) {
+    let leading = &line[..indent(line)];
+    uses_tabs |= leading.contains('\t');
+    uses_spaces |= leading.contains(' ');
+    depths.push(leading.len());
+  }
+
+  if uses_tabs && uses_spaces {
+    return None;
+  }
+
+  depths.sort_unstable();
+  depths.dedup();
+
+  let mut formatted = String::with_capacity(body.len());
+  for (line, movable) in lines.iter().zip(&movable) {
+    if !movable {
+      formatted.push_str(line);
+    } else if is_blank(line) {
+      formatted.push_str(&line[line.trim_end_matches(['\n', '\r']).len()..]);
+    } else {
+      let depth = depths.binary_search(&indent(line)).unwrap_or(0);
+      formatted.push_str(&" ".repeat(depth * width));
+      formatted.push_str(&line[indent(line)..]);
+    }
+  }
+
+  Some(formatted)
+}
+
+#[cfg(test)]
+mod tests {
+  use indoc::indoc;
+  use pretty_assertions::assert_eq;
+
+  use super::*;
+
+  fn words(s: &str) -> Vec<String> {
+    tokenize(s, 0)
+      .into_iter()
+      .map(|t| match t {
+        ShellToken::Word(w) => w.content,
+        ShellToken::Operator(op) => format!("op:{}", op.content),
+        ShellToken::Heredoc(_, delimiter, expand, strip_tabs) => format!(
+          "heredoc:{}{}",
+          if strip_tabs { "-" } else { "" },
+          if expand { delimiter } else { format!("'{}'", delimiter) }
+        ),
+      })
+      .collect()
+  }
+
+  #[test]
+  fn test_tokenize() {
+    assert_eq!(
+      words(r#"cat <<EOF >/etc/a.conf && echo "a b"'c'\ d 2>&1 | tee -a x"#),
+      vec![
+        "cat", "heredoc:EOF", "op:>", "/etc/a.conf", "op:&&", "echo", "a bc d",
+        "op:2>&", "1", "op:|", "tee", "-a", "x",
+      ]
+    );
+
+    assert_eq!(words("cat <<< foo << \"<<EOF\""), vec!["cat", "op:<<<", "foo", "heredoc:'<<EOF'"]);
+    assert_eq!(words("cat << \n"), vec!["cat", "op:<<", "op:\n"]);
+    assert_eq!(
+      words(r#"cat <<'EOF' <<"END" <<'X"#),
+      vec!["cat", "heredoc:'EOF'", "heredoc:'END'", "op:<<", "X"]
+    );
+    assert_eq!(words("echo ${A:-a b}x $B"), vec!["echo", "${A:-a b}x", "$B"]);
+    assert_eq!(
+      words(r#"cat <<\EOF <<E"N"D <<"A B"x <<'a'"b" <<"X"#),
+      vec!["cat", "heredoc:'EOF'", "heredoc:'END'", "heredoc:'A Bx'", "heredoc:'ab'", "op:<<", "X"]
+    );
+    assert_eq!(
+      words("cat <<-EOF <<-'END' <<- X <<-\t'Y' << Z"),
+      vec!["cat", "heredoc:-EOF", "heredoc:-'END'", "heredoc:-X", "heredoc:-'Y'", "heredoc:Z"]
+    );
+    assert_eq!(
+      tokenize("echo 'a b' >>out", 10),
+      vec![
+        ShellToken::Word(SpannedString { span: Span::new(10, 14), content: "echo".into(), raw_text: None, quoted: false }),
+        ShellToken::Word(SpannedString { span: Span::new(15, 20), content: "a b".into(), raw_text: None, quoted: false }),
+        ShellToken::Operator(SpannedString { span: Span::new(21, 23), content: ">>".into(), raw_text: None, quoted: false }),
+        ShellToken::Word(SpannedString { span: Span::new(23, 26), content: "out".into(), raw_text: None, quoted: false }),
+      ]
+    );
+  }
+
+  #[test]
+  fn test_find_heredocs() {
+    let content = indoc!(r#"
+      FROM alpine
+      RUN <<EOF cat >/a && \
+        cat <<END >/b
+      a
+      EOF
+      b
+      END
+      RUN echo "<<EOF"
+      EOF
+    "#);
+
+    let heredocs = find_heredocs(content);
+    assert_eq!(heredocs.len(), 2);
+    assert_eq!(&content[heredocs[0].marker.start..heredocs[0].marker.end], "<<EOF");
+    assert_eq!(heredocs[0].body.content, "a\n");
+    assert_eq!(&content[heredocs[0].terminator.start..heredocs[0].terminator.end], "EOF");
+    assert_eq!(heredocs[1].delimiter, "END");
+    assert_eq!(heredocs[1].body.content, "b\n");
+
+    // unterminated heredocs are ignored
+    assert_eq!(find_heredocs("FROM alpine\nRUN cat <<EOF\nfoo\n"), vec![]);
+    assert_eq!(find_heredocs_checked("FROM alpine\nRUN cat <<EOF\nfoo\n").unwrap(), vec![]);
+    assert!(find_heredocs_checked("FROM alpine\nCOPY <<EOF /foo\nfoo\n").is_err());
+
+    let masked = mask_heredocs(content, &heredocs);
+    assert_eq!(masked.len(), content.len());
+    assert!(masked.contains("\n \n   \n \n   \nRUN echo"));
+  }
+
+  #[test]
+  fn test_strip_tabs() {
+    let content = indoc!("
+      FROM alpine
+      RUN <<-EOF
+      \tif true; then
+      \t\tcat <<-END > /etc/motd
+      \t\t\thello
+      \t\tEND
+      \t  \tfi
+      \tEOF
+      COPY <<-EOF /empty
+      \t\tEOF
+      RUN <<EOF
+      \tEOF
+      EOF
+    ");
+
+    let heredocs = find_heredocs(content);
+    assert_eq!(heredocs.len(), 3);
+
+    // the inner heredoc is part of the script's body
+    assert!(heredocs[0].strip_tabs);
+    assert_eq!(
+      heredocs[0].body.content,
+      "\tif true; then\n\t\tcat <<-END > /etc/motd\n\t\t\thello\n\t\tEND\n\t  \tfi\n"
+    );
+    assert_eq!(&content[heredocs[0].terminator.start..heredocs[0].terminator.end], "\tEOF");
+
+    // only leading tabs are stripped, not spaces or tabs following them
+    assert_eq!(
+      heredocs[0].dedented(),
+      "if true; then\ncat <<-END > /etc/motd\nhello\nEND\n  \tfi\n"
+    );
+
+    assert!(heredocs[1].strip_tabs);
+    assert_eq!(heredocs[1].body.content, "");
+    assert_eq!(heredocs[1].dedented(), "");
+
+    // without `<<-`, indented delimiters are part of the body
+    assert!(!heredocs[2].strip_tabs);
+    assert_eq!(heredocs[2].body.content, "\tEOF\n");
+    assert_eq!(heredocs[2].dedented(), "\tEOF\n");
+
+    let dockerfile = crate::Dockerfile::parse(content).unwrap();
+    assert_eq!(dockerfile.instructions.len(), 4);
+    let run = dockerfile.instructions[1].as_run().unwrap();
+    assert!(dockerfile.text(&run.span).ends_with("fi\n\tEOF"));
+    let copy = dockerfile.instructions[2].as_copy().unwrap();
+    assert_eq!(dockerfile.text(&copy.heredocs[0].marker), "<<-EOF");
+    assert_eq!(copy.sources[0].content, "<<-EOF");
+  }
+
+  #[test]
+  fn test_shebang() {
+    let content = indoc!(r#"
+      FROM alpine
+      RUN <<EOF
+      #!/usr/bin/env -S PYTHONUNBUFFERED=1 python3 -u
+      print("hi")
+      EOF
+      RUN <<EOF
+      #! /bin/sh -e
+      echo hi
+      EOF
+      RUN <<EOF
+      # not a shebang
+      EOF
+      RUN <<EOF
+      #!/usr/bin/env
+      EOF
+    "#);
+
+    let heredocs = find_heredocs(content);
+    assert_eq!(heredocs.len(), 4);
+
+    let shebang = heredocs[0].shebang().unwrap();
+    assert_eq!(shebang.content, "#!/usr/bin/env -S PYTHONUNBUFFERED=1 python3 -u");
+    assert_eq!(&content[shebang.span.start..shebang.span.end], shebang.content);
+    let interpreter = heredocs[0].interpreter().unwrap();
+    assert_eq!(interpreter.content, "python3");
+    assert_eq!(&content[interpreter.span.start..interpreter.span.end], "python3");
+
+    // the body is kept as written
+    assert_eq!(heredocs[0].body.content, "#!/usr/bin/env -S PYTHONUNBUFFERED=1 python3 -u\nprint(\"hi\")\n");
+
+    let interpreter = heredocs[1].interpreter().unwrap();
+    assert_eq!(interpreter.content, "/bin/sh");
+    assert_eq!(&content[interpreter.span.start..interpreter.span.end], "/bin/sh");
+
+    assert_eq!(heredocs[2].shebang(), None);
+    assert_eq!(heredocs[2].interpreter(), None);
+
+    assert_eq!(heredocs[3].interpreter().unwrap().content, "/usr/bin/env");
+
+    let heredocs = find_heredocs("RUN <<EOF\r\n#!/bin/bash\r\necho hi\r\nEOF\r\n");
+    assert_eq!(heredocs[0].shebang().unwrap().content, "#!/bin/bash");
+    assert_eq!(heredocs[0].body.content, "#!/bin/bash\r\necho hi\r\n");
+  }
+
+  #[test]
+  fn test_heredocs_with_flags() {
+    use crate::Dockerfile;
+
+    let cases: [(&str, &str, &[&str]); 4] = [
+      ("RUN", "--mount=type=secret,id=tok --network=none ", &["mount", "network"]),
+      ("RUN", "", &[]),
+      ("COPY", "--chmod=644 --link ", &["chmod", "link"]),
+      ("COPY", "", &[]),
+    ];
+
+    for (keyword, flags, flag_names) in cases {
+      for multi in [false, true] {
+        let (arguments, delimiters, markers) = match (keyword, multi) {
+          ("RUN", false) => ("<<EOF", vec!["EOF"], vec!["<<EOF"]),
+          ("RUN", true) => ("<<A cat >/a && cat <<'B' >/b", vec!["A", "B"], vec!["<<A", "<<'B'"]),
+          (_, false) => ("<<EOF /app/config", vec!["EOF"], vec!["<<EOF"]),
+          (_, true) => ("<<A <<\"B\" /app/", vec!["A", "B"], vec!["<<A", "<<\"B\""]),
+        };
+
+        let bodies: String = delimiters.iter().map(|d| format!("body {}\n{}\n", d, d)).collect();
+        let content = format!("FROM alpine\n{} {}{}\n{}USER app\n", keyword, flags, arguments, bodies);
+        let case = format!("{:?}", content);
+
+        let d = Dockerfile::parse(&content).unwrap();
+        assert_eq!(d.instructions.len(), 3, "{}", case);
+        assert!(d.instructions[2].as_user().is_some(), "{}", case);
+
+        let ins = &d.instructions[1];
+        assert!(d.text(&ins.span()).ends_with(&format!("\n{}", delimiters.last().unwrap())), "{}", case);
+
+        let (names, heredocs): (Vec<&str>, _) = match ins {
+          Instruction::Run(run) => {
+            let shell = d.text(&run.as_shell().unwrap().span);
+            assert_eq!(shell, arguments, "{}", case);
+            (run.flags.iter().map(|f| f.name.content.as_str()).collect(), &run.heredocs)
+          },
+          Instruction::Copy(copy) => {
+            assert_eq!(
+              copy.sources.iter().map(|s| d.text(&s.span)).collect::<Vec<_>>(),
+              markers,
+              "{}", case
+            );
+            assert!(copy.destination.content.starts_with("/app/"), "{}", case);
+            (copy.flags.iter().map(|f| f.name.content.as_str()).collect(), &copy.heredocs)
+          },
+          _ => panic!("unexpected instruction for {}: {:?}", case, ins)
+        };
+
+        assert_eq!(names, flag_names, "{}", case);
+        assert_eq!(
+          heredocs.iter().map(|h| d.text(&h.marker)).collect::<Vec<_>>(),
+          markers,
+          "{}", case
+        );
+        assert_eq!(
+          heredocs.iter().map(|h| h.delimiter.as_str()).collect::<Vec<_>>(),
+          delimiters,
+          "{}", case
+        );
+        for (heredoc, delimiter) in heredocs.iter().zip(&delimiters) {
+          assert_eq!(heredoc.body.content, format!("body {}\n", delimiter), "{}", case);
+        }
+      }
+    }
+  }
+
+  #[test]
+  fn test_unattached_heredocs() {
+    use crate::{Dockerfile, StyleOptions};
+
+    // the heredoc belongs to no instruction, so mustn't extend the span of
+    // the following one
+    let d = Dockerfile::parse("i#, c\nRUN <<EOF\nx\nEOF\nRUN y").unwrap();
+    let last = d.instructions.last().unwrap();
+    assert_eq!(d.text(&last.span()), "RUN y");
+    assert!(last.as_run().unwrap().heredocs.is_empty());
+    for ins in &d.instructions {
+      assert!(ins.span().start <= ins.span().end, "{:?}", ins);
+      ins.metrics(&d);
+    }
+
+    d.check_style(&StyleOptions::default());
+    d.hashes();
+  }
+
+  #[test]
+  fn test_marker_whitespace() {
+    use crate::Dockerfile;
+
+    let d = Dockerfile::parse(indoc!("
+      FROM alpine
+      RUN <<- 'EOF'
+      \techo $HOME
+      \tEOF
+      COPY << EOF <<-\t\"B\" /etc/
+      a
+      EOF
+      b
+      B
+      USER app
+    ")).unwrap();
+
+    assert_eq!(d.instructions.len(), 4);
+    let run = d.instructions[1].as_run().unwrap();
+    assert_eq!(d.text(&run.heredocs[0].marker), "<<- 'EOF'");
+    assert_eq!(run.heredocs[0].delimiter, "EOF");
+    assert!(run.heredocs[0].strip_tabs);
+    assert!(!run.heredocs[0].expand);
+    assert_eq!(run.heredocs[0].dedented(), "echo $HOME\n");
+
+    let copy = d.instructions[2].as_copy().unwrap();
+    assert_eq!(
+      copy.sources.iter().map(|s| d.text(&s.span)).collect::<Vec<_>>(),
+      vec!["<< EOF", "<<-\t\"B\""]
+    );
+    assert_eq!(
+      copy.heredocs.iter().map(|h| h.body.content.as_str()).collect::<Vec<_>>(),
+      vec!["a\n", "b\n"]
+    );
+  }
+}
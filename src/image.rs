@@ -3,11 +3,13 @@
 use std::collections::{HashMap, HashSet};
 use std::fmt;
 use std::iter::FromIterator;
+use std::str::FromStr;
 
-use lazy_static::lazy_static;
-use regex::Regex;
-
-use crate::{Dockerfile, Span, Splicer};
+use crate::Dockerfile;
+use crate::error::*;
+use crate::platform::PlatformArgs;
+use crate::util::QuoteStyle;
+use crate::variables::try_substitute_with_literals;
 
 /// A parsed docker image reference
 ///
@@ -24,6 +26,10 @@ use crate::{Dockerfile, Span, Splicer};
 /// ```
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct ImageRef {
+  /// An optional BuildKit source scheme, e.g. `docker-image://`, as used in
+  /// named build contexts. Unset for plain image references.
+  pub scheme: Option<ImageScheme>,
+
   /// an optional registry, generally Docker Hub if unset
   pub registry: Option<String>,
 
@@ -34,63 +40,83 @@ pub struct ImageRef {
   /// to mean `:latest` if unset
   pub tag: Option<String>,
 
-  /// An optional embedded image hash, e.g. `sha256:...`. Conflicts with `tag`.
+  /// An optional embedded image hash, e.g. `sha256:...`. If both a tag and a
+  /// hash are given (e.g. `alpine:3.12@sha256:...`), the hash takes precedence
+  /// when pulling.
   pub hash: Option<String>
 }
 
-/// Determines if an ImageRef token refers to a registry hostname or not
+/// A BuildKit source scheme that may prefix an image reference, e.g. in
+/// `docker-image://alpine:3.19`.
 ///
-/// Based on rules from https://stackoverflow.com/a/42116190
-fn is_registry(token: &str) -> bool {
-  token == "localhost" || token.contains('.') || token.contains(':')
+/// ```
+/// use dockerfile_parser::{ImageRef, ImageScheme};
+///
+/// let image = ImageRef::parse("docker-image://alpine:3.19");
+/// assert_eq!(image.scheme, Some(ImageScheme::DockerImage));
+/// assert_eq!(image.image, "alpine");
+/// assert_eq!(image.to_string(), "docker-image://alpine:3.19");
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ImageScheme {
+  /// `docker-image://`, an image pulled from a registry
+  DockerImage,
+
+  /// `oci-layout://`, an image from a local OCI layout directory
+  OciLayout,
 }
 
-/// Given a map of key/value pairs, perform variable substitution on a given
-/// input string. `max_recursion_depth` controls the maximum allowed recursion
-/// depth if variables refer to other strings themselves containing variable
-/// references. A small number but reasonable is recommended by default, e.g.
-/// 16.
-/// If None is returned, substitution was impossible, either because a
-/// referenced variable did not exist, or recursion depth was exceeded.
-pub fn substitute<'a, 'b>(
-  s: &'a str,
-  vars: &'b HashMap<&'b str, &'b str>,
-  used_vars: &mut HashSet<String>,
-  max_recursion_depth: u8
-) -> Option<String> {
-  lazy_static! {
-    static ref VAR: Regex = Regex::new(r"\$(?:([A-Za-z0-9_]+)|\{([A-Za-z0-9_]+)\})").unwrap();
-  }
-
-  // note: docker also allows defaults in FROMs, e.g.
-  //   ARG tag
-  //   FROM alpine:${tag:-3.12}
-  // this isn't currently supported.
-
-  let mut splicer = Splicer::from_str(s);
-
-  for caps in VAR.captures_iter(s) {
-    if max_recursion_depth == 0 {
-      // can't substitute, so give up
-      return None;
+impl ImageScheme {
+  /// All known schemes.
+  pub const ALL: &'static [ImageScheme] = &[
+    ImageScheme::DockerImage,
+    ImageScheme::OciLayout,
+  ];
+
+  /// Returns the scheme name, without the trailing `://`.
+  pub fn as_str(&self) -> &'static str {
+    match self {
+      ImageScheme::DockerImage => "docker-image",
+      ImageScheme::OciLayout => "oci-layout",
     }
+  }
 
-    let full_range = caps.get(0)?.range();
-    let var_name = caps.get(1).or_else(|| caps.get(2))?;
-    let var_content = vars.get(var_name.as_str())?;
-    let substituted_content = substitute(
-      var_content,
-      vars,
-      used_vars,
-      max_recursion_depth.saturating_sub(1)
-    )?;
-    used_vars.insert(var_name.as_str().to_string());
+  /// Splits a known scheme prefix from the given string, if any.
+  fn strip(s: &str) -> (Option<ImageScheme>, &str) {
+    for scheme in ImageScheme::ALL {
+      if let Some(rest) = s
+        .strip_prefix(scheme.as_str())
+        .and_then(|rest| rest.strip_prefix("://"))
+      {
+        return (Some(*scheme), rest);
+      }
+    }
 
-    // splice the substituted content back into the output string
-    splicer.splice(&Span::new(full_range.start, full_range.end), &substituted_content);
+    (None, s)
   }
+}
 
-  Some(splicer.content)
+impl fmt::Display for ImageScheme {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "{}", self.as_str())
+  }
+}
+
+/// The canonical name of the default (Docker Hub) registry.
+const DEFAULT_REGISTRY: &str = "docker.io";
+
+/// Alternate hostnames that refer to the default registry.
+const DEFAULT_REGISTRY_ALIASES: &[&str] = &[
+  "index.docker.io",
+  "registry-1.docker.io",
+  "registry.hub.docker.com",
+];
+
+/// Determines if an ImageRef token refers to a registry hostname or not
+///
+/// Based on rules from https://stackoverflow.com/a/42116190
+fn is_registry(token: &str) -> bool {
+  token == "localhost" || token.contains('.') || token.contains(':')
 }
 
 impl ImageRef {
@@ -114,8 +140,16 @@ impl ImageRef {
     // we don't attempt to actually validate tags otherwise, so invalid
     // characters could slip through
 
+    // BuildKit sources may be prefixed with a scheme, e.g. docker-image://;
+    // OCI layouts are local paths and never have a registry
+    let (scheme, s) = ImageScheme::strip(s);
+
     let parts: Vec<&str> = s.splitn(2, '/').collect();
-    let (registry, image_full) = if parts.len() == 2 && is_registry(parts[0]) {
+    let has_registry = scheme != Some(ImageScheme::OciLayout)
+      && parts.len() == 2
+      && is_registry(parts[0]);
+
+    let (registry, image_full) = if has_registry {
       // some 3rd party registry
       (Some(parts[0].to_string()), parts[1])
     } else {
@@ -123,26 +157,89 @@ impl ImageRef {
       (None, s)
     };
 
-    if let Some(at_pos) = image_full.find('@') {
-      // parts length is guaranteed to be at least 1 given an empty string
-      let (image, hash) = image_full.split_at(at_pos);
+    // a digest may follow the tag, e.g. alpine:3.12@sha256:...
+    let (image_tagged, hash) = match image_full.find('@') {
+      Some(at_pos) => (
+        &image_full[..at_pos],
+        Some(image_full[at_pos + 1..].to_string())
+      ),
+      None => (image_full, None)
+    };
 
-      ImageRef {
-        registry,
-        image: image.to_string(),
-        hash: Some(hash[1..].to_string()),
-        tag: None
-      }
+    // parts length is guaranteed to be at least 1 given an empty string
+    let parts: Vec<&str> = image_tagged.splitn(2, ':').collect();
+    let image = parts[0].to_string();
+    let tag = parts.get(1).map(|p| String::from(*p));
+
+    ImageRef { scheme, registry, image, tag, hash }
+  }
+
+  /// Returns a copy of this `ImageRef` with Docker's implicit defaults made
+  /// explicit, e.g. `alpine` becomes `docker.io/library/alpine:latest`.
+  ///
+  /// Canonicalized references can be compared to determine if two differently
+  /// formatted references point to the same image. Hashes are preserved as-is,
+  /// and `latest` is only implied if neither a tag nor hash is set. Images in
+  /// OCI layouts are local and are returned unchanged.
+  pub fn canonicalize(&self) -> ImageRef {
+    if self.scheme == Some(ImageScheme::OciLayout) {
+      return self.clone();
+    }
+
+    let registry = match &self.registry {
+      Some(r) => {
+        let r = r.to_ascii_lowercase();
+        if DEFAULT_REGISTRY_ALIASES.contains(&r.as_str()) {
+          DEFAULT_REGISTRY.to_string()
+        } else {
+          r
+        }
+      },
+      None => DEFAULT_REGISTRY.to_string()
+    };
+
+    let image = if registry == DEFAULT_REGISTRY && !self.image.contains('/') {
+      format!("library/{}", self.image)
     } else {
-      // parts length is guaranteed to be at least 1 given an empty string
-      let parts: Vec<&str> = image_full.splitn(2, ':').collect();
-      let image = parts[0].to_string();
-      let tag = parts.get(1).map(|p| String::from(*p));
+      self.image.clone()
+    };
 
-      ImageRef { registry, image, tag, hash: None }
+    let tag = match (&self.tag, &self.hash) {
+      (None, None) => Some("latest".to_string()),
+      (tag, _) => tag.clone()
+    };
+
+    ImageRef {
+      scheme: self.scheme,
+      registry: Some(registry),
+      image,
+      tag,
+      hash: self.hash.clone(),
     }
   }
 
+  /// Given a Dockerfile (and its global `ARG`s), perform any necessary
+  /// variable substitution to resolve any variable references in this
+  /// `ImageRef` and returns a list of variables included in the end result.
+  ///
+  /// If this `ImageRef` contains any unknown variables, a required variable
+  /// (e.g. `${tag:?message}`) is unset, or if any references are excessively
+  /// recursive, returns an error describing the problem. Errors for required
+  /// variables include the author-provided message verbatim.
+  pub fn try_resolve_vars_with_context(
+    &self, dockerfile: &Dockerfile
+  ) -> Result<(ImageRef, HashSet<String>)> {
+    self.try_resolve_vars_impl(dockerfile, None)
+  }
+
+  fn try_resolve_vars_impl(
+    &self, dockerfile: &Dockerfile, platform: Option<&PlatformArgs>
+  ) -> Result<(ImageRef, HashSet<String>)> {
+    let (s, used_vars) = substitute_global_vars(dockerfile, &self.to_string(), platform)?;
+
+    Ok((ImageRef::parse(&s), used_vars))
+  }
+
   /// Given a Dockerfile (and its global `ARG`s), perform any necessary
   /// variable substitution to resolve any variable references in this
   /// `ImageRef` and returns a list of variables included in the end result.
@@ -150,25 +247,17 @@ impl ImageRef {
   /// If this `ImageRef` contains any unknown variables or if any references are
   /// excessively recursive, returns None; otherwise, returns the
   /// fully-substituted string.
-  pub fn resolve_vars_with_context<'a>(
-    &self, dockerfile: &'a Dockerfile
+  pub fn resolve_vars_with_context(
+    &self, dockerfile: &Dockerfile
   ) -> Option<(ImageRef, HashSet<String>)> {
-    let vars: HashMap<&'a str, &'a str> = HashMap::from_iter(
-      dockerfile.global_args
-        .iter()
-        .filter_map(|a| match a.value.as_ref() {
-          Some(v) => Some((a.name.as_ref(), v.as_ref())),
-          None => None
-        })
-    );
-
-    let mut used_vars = HashSet::new();
+    self.try_resolve_vars_with_context(dockerfile).ok()
+  }
 
-    if let Some(s) = substitute(&self.to_string(), &vars, &mut used_vars, 16) {
-      Some((ImageRef::parse(&s), used_vars))
-    } else {
-      None
-    }
+  /// Given a Dockerfile (and its global `ARG`s), perform any necessary
+  /// variable substitution to resolve any variable references in this
+  /// `ImageRef`, returning an error describing why resolution failed, if so.
+  pub fn try_resolve_vars(&self, dockerfile: &Dockerfile) -> Result<ImageRef> {
+    self.try_resolve_vars_with_context(dockerfile).map(|(image, _vars)| image)
   }
 
   /// Given a Dockerfile (and its global `ARG`s), perform any necessary
@@ -181,10 +270,68 @@ impl ImageRef {
   pub fn resolve_vars(&self, dockerfile: &Dockerfile) -> Option<ImageRef> {
     self.resolve_vars_with_context(dockerfile).map(|(image, _vars)| image)
   }
+
+  /// Like `try_resolve_vars()`, but also resolves BuildKit's automatic
+  /// platform args (e.g. `$BUILDPLATFORM`, `$TARGETARCH`) from the given
+  /// platforms. As `FROM` instructions are in the global scope, these don't
+  /// need to be declared.
+  pub fn try_resolve_vars_with_args(
+    &self, dockerfile: &Dockerfile, args: &PlatformArgs
+  ) -> Result<ImageRef> {
+    self.try_resolve_vars_impl(dockerfile, Some(args)).map(|(image, _vars)| image)
+  }
+
+  /// Like `resolve_vars()`, but also resolves BuildKit's automatic platform
+  /// args from the given platforms. See `try_resolve_vars_with_args()`.
+  pub fn resolve_vars_with_args(
+    &self, dockerfile: &Dockerfile, args: &PlatformArgs
+  ) -> Option<ImageRef> {
+    self.try_resolve_vars_with_args(dockerfile, args).ok()
+  }
+}
+
+/// Substitutes references to global `ARG`s and, if given, BuildKit's automatic
+/// platform args in a string in the global scope, e.g. a `FROM` image or flag.
+/// Returns the substituted string and the names of the variables used.
+pub(crate) fn substitute_global_vars(
+  dockerfile: &Dockerfile, s: &str, platform: Option<&PlatformArgs>
+) -> Result<(String, HashSet<String>)> {
+  let platform_vars = platform.map(|p| p.vars()).unwrap_or_default();
+
+  // automatic platform args take precedence over global defaults, as they're
+  // passed like `--build-arg`s
+  let vars: HashMap<&str, &str> = HashMap::from_iter(
+    dockerfile.global_args()
+      .into_iter()
+      .filter_map(|a| match a.value.as_ref() {
+        Some(v) => Some((a.name.as_ref(), v.as_ref())),
+        None => None
+      })
+      .chain(platform_vars.iter().map(|(k, v)| (*k, v.as_str())))
+  );
+
+  // as in Docker, references in single-quoted defaults aren't expanded
+  let literals: HashSet<&str> = dockerfile.global_args()
+    .into_iter()
+    .filter(|a| a.quote_style == QuoteStyle::Single)
+    .map(|a| a.name.as_ref())
+    .filter(|name| !platform_vars.iter().any(|(k, _)| k == name))
+    .collect();
+
+  let mut used_vars = HashSet::new();
+  let s = try_substitute_with_literals(
+    s, &vars, &literals, &mut used_vars, 16, dockerfile.options.max_expansion_len
+  )?;
+
+  Ok((s, used_vars))
 }
 
 impl fmt::Display for ImageRef {
   fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    if let Some(scheme) = &self.scheme {
+      write!(f, "{}://", scheme)?;
+    }
+
     if let Some(registry) = &self.registry {
       write!(f, "{}/", registry)?;
     }
@@ -193,7 +340,9 @@ impl fmt::Display for ImageRef {
 
     if let Some(tag) = &self.tag {
       write!(f, ":{}", tag)?;
-    } else if let Some(hash) = &self.hash {
+    }
+
+    if let Some(hash) = &self.hash {
       write!(f, "@{}", hash)?;
     }
 
@@ -201,6 +350,76 @@ impl fmt::Display for ImageRef {
   }
 }
 
+/// A content-addressable digest, e.g. `sha256:e7d92cdc...`.
+///
+/// ```
+/// use dockerfile_parser::Digest;
+///
+/// let digest = Digest::parse(
+///   "sha256:e7d92cdc71feacf90708cb59182d0df1b911f8ae022d29e8e95d75ca6a99776a"
+/// ).unwrap();
+/// assert_eq!(digest.algorithm, "sha256");
+/// assert!(Digest::parse("sha256:1234").is_err());
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Digest {
+  /// The digest algorithm, e.g. `sha256`
+  pub algorithm: String,
+
+  /// The hex-encoded digest value
+  pub hex: String,
+}
+
+impl Digest {
+  /// Parses and validates a digest string of the form `algorithm:hex`.
+  ///
+  /// Only the `sha256`, `sha384`, and `sha512` algorithms are accepted, and
+  /// the hex portion must have the appropriate length for the algorithm.
+  pub fn parse(s: &str) -> Result<Digest> {
+    let invalid = |reason: &str| Error::InvalidDigest {
+      digest: s.to_string(),
+      reason: reason.to_string(),
+    };
+
+    let (algorithm, hex) = match s.split_once(':') {
+      Some(parts) => parts,
+      None => return Err(invalid("missing algorithm, expected e.g. sha256:<hex>"))
+    };
+
+    let expected_len = match algorithm {
+      "sha256" => 64,
+      "sha384" => 96,
+      "sha512" => 128,
+      _ => return Err(invalid("unsupported algorithm"))
+    };
+
+    if hex.len() != expected_len || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+      return Err(invalid(&format!(
+        "expected {} hex characters for {}", expected_len, algorithm
+      )));
+    }
+
+    Ok(Digest {
+      algorithm: algorithm.to_string(),
+      hex: hex.to_string(),
+    })
+  }
+}
+
+impl FromStr for Digest {
+  type Err = Error;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    Digest::parse(s)
+  }
+}
+
+impl fmt::Display for Digest {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "{}:{}", self.algorithm, self.hex)
+  }
+}
+
 #[cfg(test)]
 mod tests {
   use super::*;
@@ -208,12 +427,14 @@ mod tests {
   use std::convert::TryInto;
   use indoc::indoc;
   use crate::instructions::*;
+  use crate::Instruction;
 
   #[test]
   fn test_image_parse_dockerhub() {
     assert_eq!(
       ImageRef::parse("alpine:3.10"),
       ImageRef {
+        scheme: None,
         registry: None,
         image: "alpine".into(),
         tag: Some("3.10".into()),
@@ -224,6 +445,7 @@ mod tests {
     assert_eq!(
       ImageRef::parse("foo/bar"),
       ImageRef {
+        scheme: None,
         registry: None,
         image: "foo/bar".into(),
         tag: None,
@@ -234,6 +456,7 @@ mod tests {
     assert_eq!(
       ImageRef::parse("clux/muslrust"),
       ImageRef {
+        scheme: None,
         registry: None,
         image: "clux/muslrust".into(),
         tag: None,
@@ -244,6 +467,7 @@ mod tests {
     assert_eq!(
       ImageRef::parse("clux/muslrust:1.41.0-stable"),
       ImageRef {
+        scheme: None,
         registry: None,
         image: "clux/muslrust".into(),
         tag: Some("1.41.0-stable".into()),
@@ -254,6 +478,7 @@ mod tests {
     assert_eq!(
       ImageRef::parse("fake_project/fake_image@fake_hash"),
       ImageRef {
+        scheme: None,
         registry: None,
         image: "fake_project/fake_image".into(),
         tag: None,
@@ -265,6 +490,7 @@ mod tests {
     assert_eq!(
       ImageRef::parse("fake_project/fake_image@"),
       ImageRef {
+        scheme: None,
         registry: None,
         image: "fake_project/fake_image".into(),
         tag: None,
@@ -275,6 +501,7 @@ mod tests {
     assert_eq!(
       ImageRef::parse("fake_project/fake_image@sha256:"),
       ImageRef {
+        scheme: None,
         registry: None,
         image: "fake_project/fake_image".into(),
         tag: None,
@@ -283,11 +510,73 @@ mod tests {
     );
   }
 
+  #[test]
+  fn test_image_parse_tag_and_hash() {
+    let image = ImageRef::parse("quay.io/foo/bar:1.2.3@sha256:abcd");
+    assert_eq!(
+      image,
+      ImageRef {
+        scheme: None,
+        registry: Some("quay.io".into()),
+        image: "foo/bar".into(),
+        tag: Some("1.2.3".into()),
+        hash: Some("sha256:abcd".into())
+      }
+    );
+    assert_eq!(image.to_string(), "quay.io/foo/bar:1.2.3@sha256:abcd");
+  }
+
+  #[test]
+  fn test_image_canonicalize() {
+    let canonical = ImageRef::parse("docker.io/library/alpine:latest");
+    assert_eq!(canonical.canonicalize(), canonical);
+
+    for s in &[
+      "alpine",
+      "alpine:latest",
+      "docker.io/alpine",
+      "index.docker.io/library/alpine",
+      "registry-1.docker.io/library/alpine:latest",
+    ] {
+      assert_eq!(ImageRef::parse(s).canonicalize(), canonical, "{}", s);
+    }
+
+    assert_eq!(
+      ImageRef::parse("clux/muslrust").canonicalize().to_string(),
+      "docker.io/clux/muslrust:latest"
+    );
+    assert_eq!(
+      ImageRef::parse("Quay.io/foo/bar@sha256:abcd").canonicalize().to_string(),
+      "quay.io/foo/bar@sha256:abcd"
+    );
+    assert_eq!(
+      ImageRef::parse("localhost:5000/foo:1.0").canonicalize().to_string(),
+      "localhost:5000/foo:1.0"
+    );
+  }
+
+  #[test]
+  fn test_digest_parse() {
+    let hex = "e7d92cdc71feacf90708cb59182d0df1b911f8ae022d29e8e95d75ca6a99776a";
+    let digest = Digest::parse(&format!("sha256:{}", hex)).unwrap();
+    assert_eq!(digest, Digest {
+      algorithm: "sha256".into(),
+      hex: hex.into(),
+    });
+    assert_eq!(digest.to_string(), format!("sha256:{}", hex));
+
+    assert!(Digest::parse(hex).is_err());
+    assert!(Digest::parse(&format!("sha257:{}", hex)).is_err());
+    assert!(Digest::parse(&format!("sha256:{}", &hex[1..])).is_err());
+    assert!(Digest::parse(&format!("sha256:{}z", &hex[1..])).is_err());
+  }
+
   #[test]
   fn test_image_parse_registry() {
     assert_eq!(
       ImageRef::parse("quay.io/prometheus/node-exporter:v0.18.1"),
       ImageRef {
+        scheme: None,
         registry: Some("quay.io".into()),
         image: "prometheus/node-exporter".into(),
         tag: Some("v0.18.1".into()),
@@ -298,6 +587,7 @@ mod tests {
     assert_eq!(
       ImageRef::parse("gcr.io/fake_project/fake_image:fake_tag"),
       ImageRef {
+        scheme: None,
         registry: Some("gcr.io".into()),
         image: "fake_project/fake_image".into(),
         tag: Some("fake_tag".into()),
@@ -308,6 +598,7 @@ mod tests {
     assert_eq!(
       ImageRef::parse("gcr.io/fake_project/fake_image"),
       ImageRef {
+        scheme: None,
         registry: Some("gcr.io".into()),
         image: "fake_project/fake_image".into(),
         tag: None,
@@ -318,6 +609,7 @@ mod tests {
     assert_eq!(
       ImageRef::parse("gcr.io/fake_image"),
       ImageRef {
+        scheme: None,
         registry: Some("gcr.io".into()),
         image: "fake_image".into(),
         tag: None,
@@ -328,6 +620,7 @@ mod tests {
     assert_eq!(
       ImageRef::parse("gcr.io/fake_image:fake_tag"),
       ImageRef {
+        scheme: None,
         registry: Some("gcr.io".into()),
         image: "fake_image".into(),
         tag: Some("fake_tag".into()),
@@ -338,6 +631,7 @@ mod tests {
     assert_eq!(
       ImageRef::parse("quay.io/fake_project/fake_image@fake_hash"),
       ImageRef {
+        scheme: None,
         registry: Some("quay.io".into()),
         image: "fake_project/fake_image".into(),
         tag: None,
@@ -351,6 +645,7 @@ mod tests {
     assert_eq!(
       ImageRef::parse("localhost/foo"),
       ImageRef {
+        scheme: None,
         registry: Some("localhost".into()),
         image: "foo".into(),
         tag: None,
@@ -361,6 +656,7 @@ mod tests {
     assert_eq!(
       ImageRef::parse("localhost/foo:bar"),
       ImageRef {
+        scheme: None,
         registry: Some("localhost".into()),
         image: "foo".into(),
         tag: Some("bar".into()),
@@ -371,6 +667,7 @@ mod tests {
     assert_eq!(
       ImageRef::parse("localhost/foo/bar"),
       ImageRef {
+        scheme: None,
         registry: Some("localhost".into()),
         image: "foo/bar".into(),
         tag: None,
@@ -381,6 +678,7 @@ mod tests {
     assert_eq!(
       ImageRef::parse("localhost/foo/bar:baz"),
       ImageRef {
+        scheme: None,
         registry: Some("localhost".into()),
         image: "foo/bar".into(),
         tag: Some("baz".into()),
@@ -394,6 +692,7 @@ mod tests {
     assert_eq!(
       ImageRef::parse("example.com:1234/foo"),
       ImageRef {
+        scheme: None,
         registry: Some("example.com:1234".into()),
         image: "foo".into(),
         tag: None,
@@ -404,6 +703,7 @@ mod tests {
     assert_eq!(
       ImageRef::parse("example.com:1234/foo:bar"),
       ImageRef {
+        scheme: None,
         registry: Some("example.com:1234".into()),
         image: "foo".into(),
         tag: Some("bar".into()),
@@ -414,6 +714,7 @@ mod tests {
     assert_eq!(
       ImageRef::parse("example.com:1234/foo/bar"),
       ImageRef {
+        scheme: None,
         registry: Some("example.com:1234".into()),
         image: "foo/bar".into(),
         tag: None,
@@ -424,6 +725,7 @@ mod tests {
     assert_eq!(
       ImageRef::parse("example.com:1234/foo/bar:baz"),
       ImageRef {
+        scheme: None,
         registry: Some("example.com:1234".into()),
         image: "foo/bar".into(),
         tag: Some("baz".into()),
@@ -436,6 +738,7 @@ mod tests {
     assert_eq!(
       ImageRef::parse("example.com:1234/foo/bar/baz:qux"),
       ImageRef {
+        scheme: None,
         registry: Some("example.com:1234".into()),
         image: "foo/bar/baz".into(),
         tag: Some("qux".into()),
@@ -445,107 +748,78 @@ mod tests {
   }
 
   #[test]
-  fn test_substitute() {
-    let mut vars = HashMap::new();
-    vars.insert("foo", "bar");
-    vars.insert("baz", "qux");
-    vars.insert("lorem", "$foo");
-    vars.insert("ipsum", "${lorem}");
-    vars.insert("recursion1", "$recursion2");
-    vars.insert("recursion2", "$recursion1");
-
-    let mut used_vars = HashSet::new();
-    assert_eq!(
-      substitute("hello world", &vars, &mut used_vars, 16).as_deref(),
-      Some("hello world")
-    );
-
-    let mut used_vars = HashSet::new();
-    assert_eq!(
-      substitute("hello $foo", &vars, &mut used_vars, 16).as_deref(),
-      Some("hello bar")
-    );
-    assert_eq!(used_vars, {
-      let mut h = HashSet::new();
-      h.insert("foo".to_string());
-      h
-    });
+  fn test_image_parse_scheme() {
+    let digest = "sha256:e7d92cdc71feacf90708cb59182d0df1b911f8ae022d29e8e95d75ca6a99776a";
 
-    let mut used_vars = HashSet::new();
     assert_eq!(
-      substitute("hello $foo", &vars, &mut used_vars, 0).as_deref(),
-      None
+      ImageRef::parse("docker-image://alpine:3.19"),
+      ImageRef {
+        scheme: Some(ImageScheme::DockerImage),
+        registry: None,
+        image: "alpine".into(),
+        tag: Some("3.19".into()),
+        hash: None
+      }
     );
-    assert!(used_vars.is_empty());
 
-    let mut used_vars = HashSet::new();
     assert_eq!(
-      substitute("hello ${foo}", &vars, &mut used_vars, 16).as_deref(),
-      Some("hello bar")
+      ImageRef::parse(&format!("docker-image://example.com:1234/foo/bar:baz@{}", digest)),
+      ImageRef {
+        scheme: Some(ImageScheme::DockerImage),
+        registry: Some("example.com:1234".into()),
+        image: "foo/bar".into(),
+        tag: Some("baz".into()),
+        hash: Some(digest.into())
+      }
     );
-    assert_eq!(used_vars, {
-      let mut h = HashSet::new();
-      h.insert("foo".to_string());
-      h
-    });
 
-    let mut used_vars = HashSet::new();
+    // layouts are local paths, even if they look like a registry
     assert_eq!(
-      substitute("$baz $foo", &vars, &mut used_vars, 16).as_deref(),
-      Some("qux bar")
+      ImageRef::parse(&format!("oci-layout://./build/layout@{}", digest)),
+      ImageRef {
+        scheme: Some(ImageScheme::OciLayout),
+        registry: None,
+        image: "./build/layout".into(),
+        tag: None,
+        hash: Some(digest.into())
+      }
     );
-    assert_eq!(used_vars, {
-      let mut h = HashSet::new();
-      h.insert("baz".to_string());
-      h.insert("foo".to_string());
-      h
-    });
 
-    let mut used_vars = HashSet::new();
     assert_eq!(
-      substitute("hello $lorem", &vars, &mut used_vars, 16).as_deref(),
-      Some("hello bar")
+      ImageRef::parse("oci-layout://layout:v1"),
+      ImageRef {
+        scheme: Some(ImageScheme::OciLayout),
+        registry: None,
+        image: "layout".into(),
+        tag: Some("v1".into()),
+        hash: None
+      }
     );
-    assert_eq!(used_vars, {
-      let mut h = HashSet::new();
-      h.insert("foo".to_string());
-      h.insert("lorem".to_string());
-      h
-    });
 
-    let mut used_vars = HashSet::new();
-    assert_eq!(
-      substitute("hello $lorem", &vars, &mut used_vars, 1).as_deref(),
-      None
-    );
-    assert!(used_vars.is_empty());
+    for s in &[
+      "docker-image://alpine:3.19".to_string(),
+      format!("docker-image://example.com:1234/foo/bar:baz@{}", digest),
+      format!("oci-layout://./build/layout@{}", digest),
+      "oci-layout://layout:v1".to_string(),
+    ] {
+      assert_eq!(&ImageRef::parse(s).to_string(), s);
+    }
 
-    let mut used_vars = HashSet::new();
     assert_eq!(
-      substitute("hello $ipsum", &vars, &mut used_vars, 16).as_deref(),
-      Some("hello bar")
+      ImageRef::parse("docker-image://alpine").canonicalize().to_string(),
+      "docker-image://docker.io/library/alpine:latest"
     );
-    assert_eq!(used_vars, {
-      let mut h = HashSet::new();
-      h.insert("foo".to_string());
-      h.insert("lorem".to_string());
-      h.insert("ipsum".to_string());
-      h
-    });
-
-    let mut used_vars = HashSet::new();
     assert_eq!(
-      substitute("hello $ipsum", &vars, &mut used_vars, 2).as_deref(),
-      None
+      ImageRef::parse("oci-layout://layout").canonicalize().to_string(),
+      "oci-layout://layout"
     );
-    assert!(used_vars.is_empty());
 
-    let mut used_vars = HashSet::new();
-    assert_eq!(
-      substitute("hello $recursion1", &vars, &mut used_vars, 16).as_deref(),
-      None
-    );
-    assert!(used_vars.is_empty());
+    // plain references and unknown schemes don't gain a scheme
+    let plain = ImageRef::parse("localhost:5000/docker-image:1.0");
+    assert_eq!(plain.scheme, None);
+    assert_eq!(plain.registry, Some("localhost:5000".into()));
+    assert_eq!(plain.to_string(), "localhost:5000/docker-image:1.0");
+    assert_eq!(ImageRef::parse("oci-layout:v1").scheme, None);
   }
 
   #[test]
@@ -565,6 +839,57 @@ mod tests {
     );
   }
 
+  #[test]
+  fn test_resolve_vars_mutated_arg() {
+    let mut d = Dockerfile::parse(indoc!(r#"
+      ARG image=alpine
+      ARG tag=3.12
+      FROM $image:$tag
+    "#)).unwrap();
+
+    assert_eq!(d.global_arg_indices, vec![0, 1]);
+
+    if let Instruction::Arg(arg) = &mut d.instructions[1] {
+      arg.value.as_mut().unwrap().content = "3.13".into();
+    }
+
+    // global args are read through the instructions, so changes are visible
+    let image = d.instructions[2].as_from().unwrap().image_parsed.resolve_vars(&d);
+    assert_eq!(image, Some(ImageRef::parse("alpine:3.13")));
+    assert_eq!(d.get_global_arg("tag").and_then(|a| a.value.as_ref()).unwrap().content, "3.13");
+  }
+
+  #[test]
+  fn test_resolve_vars_quoted() {
+    let d = Dockerfile::parse(indoc!(r#"
+      ARG tag=3.12
+      ARG double="alpine:$tag"
+      ARG single='alpine:$tag'
+      ARG nested=$single
+      FROM $double
+      FROM $single
+      FROM $nested
+    "#)).unwrap();
+
+    assert_eq!(d.global_args()[1].quote_style, QuoteStyle::Double);
+    assert_eq!(d.global_args()[2].quote_style, QuoteStyle::Single);
+
+    let resolve = |i: usize| d.instructions[i].as_from().unwrap().image_parsed.resolve_vars(&d);
+
+    // references in double-quoted defaults are expanded...
+    assert_eq!(resolve(4), Some(ImageRef::parse("alpine:3.12")));
+
+    // ...but single-quoted defaults are used verbatim
+    assert_eq!(resolve(5), Some(ImageRef::parse("alpine:$tag")));
+    assert_eq!(resolve(6), Some(ImageRef::parse("alpine:$tag")));
+
+    let (_, used) = d.instructions[5].as_from().unwrap()
+      .image_parsed
+      .resolve_vars_with_context(&d)
+      .unwrap();
+    assert!(!used.contains("tag"));
+  }
+
   #[test]
   fn test_resolve_vars_nested() {
     let d = Dockerfile::parse(indoc!(r#"
@@ -584,6 +909,55 @@ mod tests {
     );
   }
 
+  #[test]
+  fn test_resolve_vars_default() {
+    let d = Dockerfile::parse(indoc!(r#"
+      ARG image=alpine
+      FROM ${image}:${tag:-3.12}
+    "#)).unwrap();
+
+    let from: &FromInstruction = d.instructions
+      .get(1).unwrap()
+      .try_into().unwrap();
+
+    assert_eq!(
+      from.image_parsed.resolve_vars(&d),
+      Some(ImageRef::parse("alpine:3.12"))
+    );
+  }
+
+  #[test]
+  fn test_resolve_vars_required() {
+    let d = Dockerfile::parse(indoc!(r#"
+      ARG tag
+      FROM alpine:${tag:?please set a tag, e.g. --build-arg tag=3.12}
+      FROM ${image:?image is required \} (really)} as foo
+    "#)).unwrap();
+
+    let from: &FromInstruction = d.instructions
+      .get(1).unwrap()
+      .try_into().unwrap();
+
+    assert_eq!(from.image.content, "alpine:${tag:?please set a tag, e.g. --build-arg tag=3.12}");
+    assert_eq!(from.image_parsed.resolve_vars(&d), None);
+
+    let err = from.image_parsed.try_resolve_vars(&d).unwrap_err();
+    assert_eq!(err.to_string(), "tag: please set a tag, e.g. --build-arg tag=3.12");
+
+    let from: &FromInstruction = d.instructions
+      .get(2).unwrap()
+      .try_into().unwrap();
+
+    assert_eq!(from.alias.as_ref().unwrap().content, "foo");
+    match from.image_parsed.try_resolve_vars(&d) {
+      Err(Error::RequiredVariableUnset { name, message }) => {
+        assert_eq!(name, "image");
+        assert_eq!(message, "image is required } (really)");
+      },
+      other => panic!("expected RequiredVariableUnset, got {:?}", other)
+    }
+  }
+
   #[test]
   fn test_resolve_vars_technically_invalid() {
     // docker allows this, but we can't give an answer
@@ -637,4 +1011,31 @@ mod tests {
       None
     );
   }
+
+  #[test]
+  fn test_resolve_vars_with_args() {
+    let d = Dockerfile::parse(indoc!(r#"
+      ARG GO_VERSION=1.21
+      FROM --platform=$BUILDPLATFORM tonistiigi/xx:$BUILDARCH AS xx
+      FROM golang:${GO_VERSION}-$TARGETOS
+    "#)).unwrap();
+
+    let args = crate::PlatformArgs::new(
+      crate::Platform::parse("windows/amd64").unwrap(),
+      crate::Platform::parse("linux/arm64").unwrap(),
+    );
+
+    let xx: &FromInstruction = d.instructions[1].as_from().unwrap();
+    assert_eq!(xx.image_parsed.resolve_vars(&d), None);
+    assert_eq!(
+      xx.image_parsed.resolve_vars_with_args(&d, &args),
+      Some(ImageRef::parse("tonistiigi/xx:arm64"))
+    );
+
+    let golang: &FromInstruction = d.instructions[2].as_from().unwrap();
+    assert_eq!(
+      golang.image_parsed.try_resolve_vars_with_args(&d, &args).unwrap(),
+      ImageRef::parse("golang:1.21-windows")
+    );
+  }
 }
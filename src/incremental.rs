@@ -0,0 +1,277 @@
+// (C) Copyright 2026 Hewlett Packard Enterprise Development LP
+
+use crate::dockerfile_parser::{find_global_arg_indices, Dockerfile, Instruction};
+use crate::error::*;
+use crate::instructions::*;
+use crate::shift::ShiftSpans;
+use crate::splicer::Span;
+use crate::trivia::find_trivia;
+use crate::util::*;
+
+/// Returns the offset of the start of the line containing `pos`.
+fn line_start(content: &str, pos: usize) -> usize {
+  content[..pos].rfind('\n').map(|i| i + 1).unwrap_or(0)
+}
+
+/// Returns the offset of the end of the line containing `pos`, excluding the
+/// line break.
+fn line_end(content: &str, pos: usize) -> usize {
+  content[pos..].find('\n').map(|i| pos + i).unwrap_or(content.len())
+}
+
+/// Determines if an instruction contains a value that begins with a quote (or
+/// bracket) but was not parsed as a quoted string (or array), presumably
+/// because its closing quote was missing.
+///
+/// When re-parsing a region of a document, a closing quote past the end of the
+/// region could make the full document parse differently.
+fn has_unterminated_quote(ins: &Instruction) -> bool {
+  let starts_with_quote = |s: &str| s.starts_with('"') || s.starts_with('\'');
+  let shell_starts_with_bracket = |expr: &ShellOrExecExpr| {
+    matches!(expr, ShellOrExecExpr::Shell(s) if s.to_string().trim_start().starts_with('['))
+  };
+
+  match ins {
+    Instruction::Arg(arg) => arg.value.iter().any(|v| starts_with_quote(&v.content)),
+    Instruction::Label(label) => label.labels
+      .iter()
+      .any(|l| starts_with_quote(&l.name.content) || starts_with_quote(&l.value.content)),
+    Instruction::Env(env) => env.vars
+      .iter()
+      .any(|v| v.quote_style == QuoteStyle::Unquoted && starts_with_quote(&v.value.to_string())),
+    Instruction::Run(run) => shell_starts_with_bracket(&run.expr),
+    Instruction::Cmd(cmd) => shell_starts_with_bracket(&cmd.expr),
+    Instruction::Entrypoint(entrypoint) => shell_starts_with_bracket(&entrypoint.expr),
+    Instruction::Healthcheck(healthcheck) => {
+      healthcheck.cmd().map(|cmd| shell_starts_with_bracket(&cmd.expr)).unwrap_or(false)
+    },
+    Instruction::Volume(volume) => volume.form == VolumeForm::Shell
+      && volume.paths.first().map(|p| p.content.starts_with('[')).unwrap_or(false),
+    _ => false
+  }
+}
+
+/// Re-parses a Dockerfile after replacing the text at `edit` with
+/// `replacement`, re-using parsed instructions outside of the edited region.
+///
+/// The re-parsed region covers every instruction overlapping the edited lines
+/// plus one full instruction on either side, so that added or removed line
+/// continuations are accounted for. Parsing falls back to the full document
+/// for edits that may change stage structure (anything before the first
+/// `FROM`, or any region containing a `FROM`), for regions with unterminated
+/// quotes or heredoc markers that could extend past the region, and for edits
+/// that fail to parse.
+///
+/// Trivia is recomputed for the whole document if it was parsed with
+/// `ParseOptions::track_trivia`.
+pub(crate) fn reparse_edit(
+  dockerfile: &Dockerfile,
+  edit: Span,
+  replacement: &str
+) -> Result<Dockerfile> {
+  let old = &dockerfile.content;
+  let mut content = String::with_capacity(old.len() + replacement.len());
+  content.push_str(&old[..edit.start]);
+  content.push_str(replacement);
+  content.push_str(&old[edit.end..]);
+
+  let options = dockerfile.options.clone();
+  let full = |content: &str| Dockerfile::parse_with_options(content, &options);
+
+  let instructions = &dockerfile.instructions;
+  let first_from = instructions.iter().position(|i| matches!(i, Instruction::From(_)));
+  let first_from_start = match first_from {
+    Some(index) => instructions[index].span().start,
+    None => return full(&content)
+  };
+
+  let edit_line_start = line_start(old, edit.start);
+  let edit_line_end = line_end(old, edit.end);
+
+  // affected instructions, padded by one instruction on either side
+  let lo = instructions
+    .iter()
+    .position(|i| i.span().end >= edit_line_start)
+    .unwrap_or(instructions.len())
+    .saturating_sub(1);
+  let hi = instructions
+    .iter()
+    .position(|i| i.span().start > edit_line_end)
+    .map(|i| (i + 1).min(instructions.len()))
+    .unwrap_or(instructions.len());
+
+  if lo >= instructions.len() {
+    return full(&content);
+  }
+
+  let region_start = line_start(old, instructions[lo].span().start);
+  if region_start <= first_from_start || edit.start < region_start {
+    return full(&content);
+  }
+
+  let old_region_end = match instructions.get(hi) {
+    Some(next) => line_start(old, next.span().start),
+    None => old.len()
+  };
+
+  if instructions[lo..hi].iter().any(|i| matches!(i, Instruction::From(_))) {
+    return full(&content);
+  }
+
+  // an unterminated quote anywhere before the region may be closed by the edit
+  if instructions[..lo].iter().any(has_unterminated_quote) {
+    return full(&content);
+  }
+
+  let delta = replacement.len() as isize - (edit.end - edit.start) as isize;
+  let region_end = (old_region_end as isize + delta) as usize;
+
+  // a heredoc body may end past the region
+  if content[region_start..region_end].contains("<<") {
+    return full(&content);
+  }
+
+  let mut region = match Dockerfile::parse_with_options(&content[region_start..region_end], &options) {
+    Ok(region) => region.instructions,
+    Err(_) => return full(&content)
+  };
+
+  let unsafe_region = region
+    .iter()
+    .any(|i| matches!(i, Instruction::From(_)) || has_unterminated_quote(i));
+  if unsafe_region {
+    return full(&content);
+  }
+
+  region.shift_spans(region_start as isize);
+
+  let mut tail = instructions[hi..].to_vec();
+  tail.shift_spans(delta);
+
+  let mut new_instructions = instructions[..lo].to_vec();
+  new_instructions.extend(region);
+  new_instructions.extend(tail);
+
+  let trivia = if options.track_trivia {
+    find_trivia(&content, &new_instructions)
+  } else {
+    Vec::new()
+  };
+
+  Ok(Dockerfile {
+    content,
+    global_arg_indices: find_global_arg_indices(&new_instructions),
+    instructions: new_instructions,
+    trivia,
+    base_offset: 0,
+    base_line: 0,
+    options,
+  })
+}
+
+#[cfg(test)]
+mod tests {
+  use indoc::indoc;
+  use pretty_assertions::assert_eq;
+
+  use crate::*;
+
+  const FIXTURES: &[&str] = &[
+    indoc!(r#"
+      # syntax=docker/dockerfile:1
+      ARG TAG=3.12
+      FROM alpine:$TAG as build
+      RUN apk add --no-cache curl && \
+        # fetch sources
+        curl -fsSL https://example.com | sh
+
+      ENV foo=bar baz="qux quux"
+      COPY --from=build /src /dst
+      LABEL a=b
+
+      FROM build
+      CMD ["echo", "hello"]
+      ENTRYPOINT echo \
+        world
+      WORKDIR /app
+      RUN --mount=type=cache,target=/root/.cache make
+    "#),
+    "FROM alpine\r\nRUN foo\r\n\r\n# comment\r\nENV a b\r\nRUN bar \\\r\n  baz\r\n",
+    "FROM a\nRUN x\nRUN y\nRUN z\n",
+    "FROM a\nrun [\"foo\",\\\n \"\\\n]\nRUN m\nentrypoint [\"foo\", \"bar\"]\n",
+  ];
+
+  const REPLACEMENTS: &[&str] = &[
+    "", " ", "\n", "\\\n", "x", "RUN foo", "\nRUN bar\n", "# comment\n", "\"",
+    "$foo", "FROM b\n", "ARG x=1\n", "\\", "[\"a\"]", "=",
+  ];
+
+  /// A tiny deterministic PRNG (xorshift64), to avoid a dependency.
+  struct Rng(u64);
+
+  impl Rng {
+    fn next(&mut self, bound: usize) -> usize {
+      self.0 ^= self.0 << 13;
+      self.0 ^= self.0 >> 7;
+      self.0 ^= self.0 << 17;
+      (self.0 % bound as u64) as usize
+    }
+  }
+
+  /// Asserts that re-parsing an edit gives the same result as a full parse of
+  /// the edited content.
+  fn assert_reparse_matches(fixture: &str, options: &ParseOptions, edit: Span, replacement: &str) {
+    let dockerfile = Dockerfile::parse_with_options(fixture, options).unwrap();
+
+    let mut expected = fixture.to_string();
+    expected.replace_range(edit.start..edit.end, replacement);
+
+    let full = Dockerfile::parse_with_options(&expected, options);
+    match (full, dockerfile.reparse_edit(edit, replacement)) {
+      (Ok(full), Ok(incremental)) => assert_eq!(
+        full, incremental,
+        "edit {:?} -> {:?} of {:?}", edit, replacement, fixture
+      ),
+      (Err(_), Err(_)) => (),
+      (full, incremental) => panic!(
+        "edit {:?} -> {:?} of {:?}: full parse {:?}, incremental parse {:?}",
+        edit, replacement, fixture, full.is_ok(), incremental.is_ok()
+      )
+    }
+  }
+
+  #[test]
+  fn test_reparse_edit_matches_full_parse() {
+    let mut rng = Rng(0x2545_f491_4f6c_dd1d);
+
+    for (fixture, track_trivia) in FIXTURES.iter().flat_map(|f| vec![(f, false), (f, true)]) {
+      let options = ParseOptions {
+        track_trivia,
+        ..Default::default()
+      };
+
+      for _ in 0..500 {
+        let a = rng.next(fixture.len() + 1);
+        let b = (a + rng.next(8)).min(fixture.len());
+        let replacement = REPLACEMENTS[rng.next(REPLACEMENTS.len())];
+
+        assert_reparse_matches(fixture, &options, Span::new(a, b), replacement);
+      }
+    }
+
+    // a quote left open by an earlier instruction is closed by the edit
+    let fixture = FIXTURES[3];
+    let start = fixture.find("int [\"f").unwrap();
+    assert_reparse_matches(fixture, &ParseOptions::default(), Span::new(start, start + 7), "\"ARG \"");
+  }
+
+  #[test]
+  fn test_reparse_edit() {
+    let dockerfile = Dockerfile::parse(FIXTURES[2]).unwrap();
+    let edit = Span::new(17, 18);
+    let reparsed = dockerfile.reparse_edit(edit, "echo hello").unwrap();
+
+    assert_eq!(reparsed.content, "FROM a\nRUN x\nRUN echo hello\nRUN z\n");
+    assert_eq!(reparsed, Dockerfile::parse(&reparsed.content).unwrap());
+  }
+}
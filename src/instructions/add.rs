@@ -0,0 +1,471 @@
+// (C) Copyright 2026 Hewlett Packard Enterprise Development LP
+
+use std::convert::TryFrom;
+
+use crate::dockerfile_parser::Instruction;
+use crate::parser::Pair;
+use crate::Span;
+use crate::SpannedString;
+use crate::error::*;
+use crate::git::GitSource;
+use crate::heredoc::Heredoc;
+use crate::image::Digest;
+use crate::instructions::{parse_copy_arguments, CopyFlag};
+use crate::util::starts_with_ignore_case;
+
+/// The kind of an `ADD` source, as classified by
+/// `AddInstruction::source_kinds()`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AddSourceKind {
+  /// A path in the build context, e.g. `./app` or `rootfs.tar.gz`
+  LocalPath,
+
+  /// A remote file fetched over HTTP(S), e.g. `https://example.com/file`
+  Url,
+
+  /// A remote Git repository, e.g. `git@github.com:org/repo.git`; see
+  /// `GitSource`
+  Git,
+
+  /// A heredoc, e.g. `<<EOF`
+  Heredoc,
+
+  /// A source containing variables, which can't be classified until they're
+  /// expanded
+  Unknown,
+}
+
+/// A Dockerfile [`ADD` instruction][add].
+///
+/// `ADD` takes the same flags and paths as `COPY`, but its sources may also be
+/// URLs, Git repositories, or local archives to extract. Sources are kept as
+/// plain strings; see `git_sources()` to find Git repositories.
+///
+/// As with `COPY`, heredoc markers are kept in `sources` as written, while
+/// their bodies are in `heredocs`.
+///
+/// [add]: https://docs.docker.com/engine/reference/builder/#add
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct AddInstruction {
+  pub span: Span,
+  pub flags: Vec<CopyFlag>,
+  pub sources: Vec<SpannedString>,
+  pub destination: SpannedString,
+
+  /// Heredocs used as sources, in order of their markers
+  pub heredocs: Vec<Heredoc>,
+}
+
+impl AddInstruction {
+  pub(crate) fn from_record(record: Pair) -> Result<AddInstruction> {
+    let span = Span::from_pair(&record);
+    let (flags, sources, destination) = parse_copy_arguments(record, "add")?;
+
+    Ok(AddInstruction {
+      span,
+      flags,
+      sources,
+      destination,
+      heredocs: Vec::new(),
+    })
+  }
+
+  /// Returns all flags whose names are not in `known`, in order, comparing
+  /// names case-insensitively.
+  pub fn unknown_flags(&self, known: &[&str]) -> Vec<&CopyFlag> {
+    self.flags
+      .iter()
+      .filter(|f| !known.iter().any(|k| f.name.content.eq_ignore_ascii_case(k)))
+      .collect()
+  }
+
+  /// Returns the last flag with the given name, compared case-insensitively,
+  /// if any.
+  pub fn flag(&self, name: &str) -> Option<&CopyFlag> {
+    self.flags
+      .iter()
+      .rev()
+      .find(|f| f.name.content.eq_ignore_ascii_case(name))
+  }
+
+  /// Returns the value of the `--chown` flag, if any.
+  pub fn chown(&self) -> Option<&SpannedString> {
+    self.flag("chown").map(|f| &f.value)
+  }
+
+  /// Returns the value of the `--chmod` flag, if any.
+  pub fn chmod(&self) -> Option<&SpannedString> {
+    self.flag("chmod").map(|f| &f.value)
+  }
+
+  /// Returns the value of the `--checksum` flag, if any. Its span may be used
+  /// to replace the checksum in place.
+  pub fn checksum(&self) -> Option<&SpannedString> {
+    self.flag("checksum").map(|f| &f.value)
+  }
+
+  /// Parses the value of the `--checksum` flag, if any, into its algorithm
+  /// and hex digest, validated as for image digests; see `Digest::parse()`.
+  ///
+  /// Values containing variables are parsed as written, and so are likely
+  /// to be invalid.
+  ///
+  /// ```
+  /// use dockerfile_parser::Dockerfile;
+  ///
+  /// let dockerfile = Dockerfile::parse(concat!(
+  ///   "ADD --checksum=sha256:24454f830cdb571e2c4ad15481119c43b3cafd48dd869a9b2945d1036d1dc68d ",
+  ///   "https://example.com/app.tar.gz /opt/"
+  /// )).unwrap();
+  /// let add = dockerfile.instructions[0].as_add().unwrap();
+  ///
+  /// let digest = add.checksum_digest().unwrap().unwrap();
+  /// assert_eq!(digest.algorithm, "sha256");
+  /// assert!(digest.hex.starts_with("24454f83"));
+  /// ```
+  pub fn checksum_digest(&self) -> Option<Result<Digest>> {
+    self.checksum().map(|c| Digest::parse(&c.content))
+  }
+
+  /// Returns the value of the `--keep-git-dir` flag, if any. See
+  /// `Instruction::flag_bool()`.
+  pub fn keep_git_dir(&self) -> Option<Result<bool>> {
+    self.flag("keep-git-dir").map(|f| f.flag_value().to_bool("keep-git-dir"))
+  }
+
+  /// Returns the value of the `--link` flag, if any. See
+  /// `Instruction::flag_bool()`.
+  pub fn link(&self) -> Option<Result<bool>> {
+    self.flag("link").map(|f| f.flag_value().to_bool("link"))
+  }
+
+  /// Returns each source that refers to a Git repository, with its parsed
+  /// form, in order.
+  ///
+  /// ```
+  /// use dockerfile_parser::Dockerfile;
+  ///
+  /// let dockerfile = Dockerfile::parse(
+  ///   "ADD https://github.com/user/repo.git#v1.0 https://example.com/a.tar.gz /src/"
+  /// ).unwrap();
+  /// let add = dockerfile.instructions[0].as_add().unwrap();
+  ///
+  /// let sources = add.git_sources();
+  /// assert_eq!(sources.len(), 1);
+  /// assert_eq!(sources[0].1.reference, Some("v1.0".to_string()));
+  /// ```
+  pub fn git_sources(&self) -> Vec<(&SpannedString, GitSource)> {
+    self.sources
+      .iter()
+      .filter_map(|s| GitSource::parse(&s.content).map(|git| (s, git)))
+      .collect()
+  }
+
+  /// Classifies each of this instruction's sources as a local path, a remote
+  /// URL, a Git repository, or a heredoc, in order. Sources containing
+  /// variables are classified as `AddSourceKind::Unknown`.
+  ///
+  /// ```
+  /// use dockerfile_parser::{AddSourceKind, Dockerfile};
+  ///
+  /// let dockerfile = Dockerfile::parse(
+  ///   "ADD ./local https://host/file git@github.com:org/repo.git /app/"
+  /// ).unwrap();
+  /// let add = dockerfile.instructions[0].as_add().unwrap();
+  ///
+  /// assert_eq!(
+  ///   add.source_kinds(),
+  ///   vec![AddSourceKind::LocalPath, AddSourceKind::Url, AddSourceKind::Git]
+  /// );
+  /// ```
+  pub fn source_kinds(&self) -> Vec<AddSourceKind> {
+    self.sources
+      .iter()
+      .map(|s| {
+        if s.content.contains('$') {
+          AddSourceKind::Unknown
+        } else if self.heredocs.iter().any(|h| h.marker == s.span) {
+          AddSourceKind::Heredoc
+        } else if GitSource::parse(&s.content).is_some() {
+          AddSourceKind::Git
+        } else if starts_with_ignore_case(&s.content, "http://")
+          || starts_with_ignore_case(&s.content, "https://") {
+          AddSourceKind::Url
+        } else {
+          AddSourceKind::LocalPath
+        }
+      })
+      .collect()
+  }
+}
+
+impl<'a> TryFrom<&'a Instruction> for &'a AddInstruction {
+  type Error = Error;
+
+  fn try_from(instruction: &'a Instruction) -> std::result::Result<Self, Self::Error> {
+    if let Instruction::Add(a) = instruction {
+      Ok(a)
+    } else {
+      Err(Error::ConversionError {
+        from: format!("{:?}", instruction),
+        to: "AddInstruction".into()
+      })
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use indoc::indoc;
+  use pretty_assertions::assert_eq;
+
+  use super::*;
+  use crate::Dockerfile;
+  use crate::parser::Rule;
+  use crate::test_util::*;
+  use crate::util::FlagValue;
+
+  #[test]
+  fn add_basic() -> Result<()> {
+    assert_eq!(
+      parse_single("add foo bar", Rule::add)?,
+      AddInstruction {
+        span: Span::new(0, 11),
+        flags: vec![],
+        sources: vec![SpannedString {
+          span: Span::new(4, 7),
+          content: "foo".to_string(),
+          raw_text: None,
+          quoted: false,
+        }],
+        destination: SpannedString {
+          span: Span::new(8, 11),
+          content: "bar".to_string(),
+          raw_text: None,
+          quoted: false,
+        },
+        heredocs: vec![],
+      }.into()
+    );
+
+    assert!(parse_single("ADD foo", Rule::add).is_err());
+
+    Ok(())
+  }
+
+  #[test]
+  fn add_remote_sources() -> Result<()> {
+    let dockerfile = Dockerfile::parse(indoc!(r#"
+      FROM alpine
+      ADD --checksum=sha256:24454f830cdb571e2c4ad15481119c43b3cafd48dd869a9b2945d1036d1dc68d \
+        https://example.com/app.tar.gz rootfs.tar.xz git@github.com:user/repo.git#main /opt/
+      ADD --keep-git-dir https://github.com/user/repo.git /src
+    "#))?;
+
+    let add = dockerfile.instructions[1].as_add().unwrap();
+    assert_eq!(
+      add.sources.iter().map(|s| s.content.as_str()).collect::<Vec<_>>(),
+      vec!["https://example.com/app.tar.gz", "rootfs.tar.xz", "git@github.com:user/repo.git#main"]
+    );
+    assert_eq!(dockerfile.text(&add.sources[0].span), "https://example.com/app.tar.gz");
+    assert_eq!(add.destination.content, "/opt/");
+    assert!(add.checksum().unwrap().content.starts_with("sha256:2445"));
+
+    let git = add.git_sources();
+    assert_eq!(git.len(), 1);
+    assert_eq!(git[0].0.content, "git@github.com:user/repo.git#main");
+    assert_eq!(git[0].1.reference, Some("main".to_string()));
+
+    // flags may contain dashes
+    let add = dockerfile.instructions[2].as_add().unwrap();
+    assert_eq!(add.flags[0].name.content, "keep-git-dir");
+    assert_eq!(add.flags[0].value.content, "");
+    assert_eq!(add.unknown_flags(&["checksum"]).len(), 1);
+
+    Ok(())
+  }
+
+  #[test]
+  fn add_source_kinds() -> Result<()> {
+    let dockerfile = Dockerfile::parse(indoc!(r#"
+      FROM alpine
+      ADD ./local rootfs.tar.xz /app/
+      ADD https://host/file?token=abc&v=2 http://host/a.tar.gz#frag HTTP://host/b Https://host/c /app/
+      ADD https://github.com/org/repo.git ssh://git@host/repo git@github.com:org/repo.git#main GIT@host:r.git /src/
+      ADD $url ${repo}.git https://host/$file <<EOF /app/
+      hello
+      EOF
+    "#))?;
+
+    let kinds: Vec<_> = dockerfile.instructions[1..]
+      .iter()
+      .map(|ins| ins.as_add().unwrap().source_kinds())
+      .collect();
+
+    assert_eq!(kinds, vec![
+      vec![AddSourceKind::LocalPath, AddSourceKind::LocalPath],
+      vec![AddSourceKind::Url, AddSourceKind::Url, AddSourceKind::Url, AddSourceKind::Url],
+      vec![AddSourceKind::Git, AddSourceKind::Git, AddSourceKind::Git, AddSourceKind::Git],
+      vec![
+        AddSourceKind::Unknown, AddSourceKind::Unknown, AddSourceKind::Unknown,
+        AddSourceKind::Heredoc
+      ],
+    ]);
+
+    // sources are kept as written
+    let add = dockerfile.instructions[2].as_add().unwrap();
+    assert_eq!(add.sources[0].content, "https://host/file?token=abc&v=2");
+    assert_eq!(dockerfile.text(&add.sources[0].span), "https://host/file?token=abc&v=2");
+
+    Ok(())
+  }
+
+  #[test]
+  fn add_heredocs() -> Result<()> {
+    let dockerfile = Dockerfile::parse(indoc!(r#"
+      FROM alpine
+      ADD --chmod=644 <<motd <<"issue" /etc/
+      Welcome to $HOSTNAME
+      motd
+      Authorized use only
+      issue
+      RUN true
+    "#))?;
+
+    let add = dockerfile.instructions[1].as_add().unwrap();
+    assert_eq!(
+      add.sources.iter().map(|s| s.content.as_str()).collect::<Vec<_>>(),
+      vec!["<<motd", "<<\"issue\""]
+    );
+    assert_eq!(add.destination.content, "/etc/");
+    assert_eq!(add.heredocs.len(), 2);
+    assert_eq!(dockerfile.text(&add.heredocs[0].marker), "<<motd");
+    assert_eq!(add.heredocs[0].body.content, "Welcome to $HOSTNAME\n");
+    assert!(add.heredocs[0].expand);
+    assert_eq!(add.heredocs[1].delimiter, "issue");
+    assert!(!add.heredocs[1].expand);
+    assert!(dockerfile.text(&add.span).ends_with("Authorized use only\nissue"));
+    assert!(dockerfile.instructions[2].as_run().is_some());
+
+    // bodies can be rewritten in place
+    let mut splicer = dockerfile.splicer();
+    splicer.splice(&add.heredocs[1].body.span, "Go away\n");
+    let spliced = Dockerfile::parse(&splicer.content)?;
+    assert_eq!(spliced.instructions[1].as_add().unwrap().heredocs[1].body.content, "Go away\n");
+
+    Ok(())
+  }
+
+  #[test]
+  fn add_unterminated_heredoc() {
+    let content = "FROM alpine\nADD <<EOF /etc/motd\nhello\nEO\n";
+    match Dockerfile::parse(content) {
+      Err(Error::UnterminatedHeredoc { delimiter, line, span }) => {
+        assert_eq!(delimiter, "EOF");
+        assert_eq!(line, 2);
+        assert_eq!(&content[span.start..span.end], "<<EOF");
+      },
+      other => panic!("expected an unterminated heredoc error, got {:?}", other)
+    }
+  }
+
+  #[test]
+  fn add_checksum() -> Result<()> {
+    let sha256 = "24454f830cdb571e2c4ad15481119c43b3cafd48dd869a9b2945d1036d1dc68d";
+    let dockerfile = Dockerfile::parse(&format!(indoc!(r#"
+      FROM alpine
+      ADD --checksum=sha256:{} https://example.com/app.tar.gz /opt/
+      ADD --checksum=md5:d41d8cd98f00b204e9800998ecf8427e https://example.com/a /a
+      ADD https://example.com/b /b
+    "#), sha256))?;
+
+    let add = dockerfile.instructions[1].as_add().unwrap();
+    let digest = add.checksum_digest().unwrap()?;
+    assert_eq!(digest, Digest { algorithm: "sha256".into(), hex: sha256.into() });
+    assert_eq!(dockerfile.text(&add.checksum().unwrap().span), format!("sha256:{}", sha256));
+
+    let add = dockerfile.instructions[2].as_add().unwrap();
+    assert!(matches!(add.checksum_digest(), Some(Err(Error::InvalidDigest { .. }))));
+
+    assert!(dockerfile.instructions[3].as_add().unwrap().checksum_digest().is_none());
+
+    // the checksum can be replaced in place
+    let mut splicer = dockerfile.splicer();
+    let replacement = format!("sha512:{}", "ab".repeat(64));
+    splicer.splice(&dockerfile.instructions[1].as_add().unwrap().checksum().unwrap().span, &replacement);
+
+    let spliced = Dockerfile::parse(&splicer.content)?;
+    let add = spliced.instructions[1].as_add().unwrap();
+    assert_eq!(add.checksum_digest().unwrap()?.algorithm, "sha512");
+    assert_eq!(spliced.text(&add.span), format!(
+      "ADD --checksum={} https://example.com/app.tar.gz /opt/", replacement
+    ));
+
+    Ok(())
+  }
+
+  #[test]
+  fn add_boolean_flags() -> Result<()> {
+    let dockerfile = Dockerfile::parse(indoc!(r#"
+      FROM alpine
+      ADD --keep-git-dir=true https://github.com/foo/bar.git /src
+      ADD --keep-git-dir --link https://github.com/foo/bar.git /src
+      ADD --keep-git-dir=FALSE https://github.com/foo/bar.git /src
+      ADD --keep-git-dir=maybe --unknown https://github.com/foo/bar.git /src
+    "#))?;
+
+    let add = dockerfile.instructions[1].as_add().unwrap();
+    assert_eq!(add.keep_git_dir().unwrap()?, true);
+    assert_eq!(dockerfile.text(&add.flags[0].value.span), "true");
+    assert!(add.link().is_none());
+
+    let add = dockerfile.instructions[2].as_add().unwrap();
+    assert_eq!(add.keep_git_dir().unwrap()?, true);
+    assert_eq!(add.link().unwrap()?, true);
+    assert_eq!(dockerfile.instructions[2].flag_bool("link").unwrap()?, true);
+
+    let add = dockerfile.instructions[3].as_add().unwrap();
+    assert_eq!(add.keep_git_dir().unwrap()?, false);
+
+    // unknown bare flags still parse
+    let add = dockerfile.instructions[4].as_add().unwrap();
+    assert!(add.keep_git_dir().unwrap().is_err());
+    assert_eq!(add.flags[1].name.content, "unknown");
+    assert_eq!(add.flags[1].flag_value(), FlagValue::Bool(true, None));
+    assert_eq!(add.sources[0].content, "https://github.com/foo/bar.git");
+
+    Ok(())
+  }
+
+  #[test]
+  fn add_multiline() -> Result<()> {
+    let dockerfile = Dockerfile::parse(indoc!(r#"
+      FROM alpine
+      ADD --chown=app:app \
+        # the config
+        config.yaml \
+
+        https://example.com/data.json \
+        /etc/app/
+      ADD <<EOF /etc/motd
+      hello
+      EOF
+    "#))?;
+
+    let add = dockerfile.instructions[1].as_add().unwrap();
+    assert_eq!(add.chown().unwrap().content, "app:app");
+    assert_eq!(
+      add.sources.iter().map(|s| s.content.as_str()).collect::<Vec<_>>(),
+      vec!["config.yaml", "https://example.com/data.json"]
+    );
+    assert_eq!(dockerfile.text(&add.destination.span), "/etc/app/");
+    assert!(dockerfile.text(&add.span).ends_with("/etc/app/"));
+
+    let add = dockerfile.instructions[2].as_add().unwrap();
+    assert_eq!(add.sources[0].content, "<<EOF");
+    assert_eq!(add.heredocs[0].body.content, "hello\n");
+    assert_eq!(dockerfile.instructions.len(), 3);
+
+    Ok(())
+  }
+}
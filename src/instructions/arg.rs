@@ -3,12 +3,27 @@
 use std::convert::TryFrom;
 
 use crate::dockerfile_parser::Instruction;
-use crate::SpannedString;
+use crate::{QuoteStyle, SpannedString};
 use crate::error::*;
-use crate::parse_string;
+use crate::util::parse_string;
 use crate::parser::{Pair, Rule};
 use crate::splicer::Span;
 
+/// The names of `ARG`s [predefined] by Docker and BuildKit, including the
+/// automatic platform args (see `PLATFORM_ARG_NAMES`). Names are
+/// case-sensitive.
+///
+/// [predefined]: https://docs.docker.com/reference/dockerfile/#predefined-args
+pub const PREDEFINED_ARG_NAMES: &[&str] = &[
+  "HTTP_PROXY", "http_proxy", "HTTPS_PROXY", "https_proxy", "FTP_PROXY", "ftp_proxy",
+  "NO_PROXY", "no_proxy", "ALL_PROXY", "all_proxy",
+  "TARGETPLATFORM", "TARGETOS", "TARGETARCH", "TARGETVARIANT",
+  "BUILDPLATFORM", "BUILDOS", "BUILDARCH", "BUILDVARIANT",
+  "BUILDKIT_CACHE_MOUNT_NS", "BUILDKIT_CONTEXT_KEEP_GIT_DIR", "BUILDKIT_INLINE_CACHE",
+  "BUILDKIT_MULTI_PLATFORM", "BUILDKIT_SANDBOX_HOSTNAME", "BUILDKIT_SYNTAX",
+  "SOURCE_DATE_EPOCH",
+];
+
 /// A Dockerfile [`ARG` instruction][arg].
 ///
 /// [arg]: https://docs.docker.com/engine/reference/builder/#arg
@@ -26,18 +41,44 @@ pub struct ArgInstruction {
   ///
   /// [build]: https://docs.docker.com/develop/develop-images/multistage-build/
   pub value: Option<SpannedString>,
+
+  /// The quoting style of the value as written. Docker doesn't expand
+  /// variable references in single-quoted values.
+  pub quote_style: QuoteStyle,
 }
 
 impl ArgInstruction {
+  /// Determines if this arg redefines one of Docker's predefined args (see
+  /// `PREDEFINED_ARG_NAMES`), e.g. `HTTP_PROXY`. Proxy args, for example, are
+  /// excluded from the image history.
+  pub fn is_predefined(&self) -> bool {
+    PREDEFINED_ARG_NAMES.contains(&self.name.content.as_str())
+  }
+
+  /// Returns the span of the first character of this arg's name that isn't
+  /// allowed by Docker, if any. Names must match `[a-zA-Z_][a-zA-Z0-9_]*`.
+  pub fn invalid_name_char(&self) -> Option<Span> {
+    let start = self.name.content_span().start;
+
+    self.name.content
+      .char_indices()
+      .find(|(i, c)| !(c.is_ascii_alphabetic() || *c == '_' || (*i > 0 && c.is_ascii_digit())))
+      .map(|(i, c)| Span::new(start + i, start + i + c.len_utf8()))
+  }
+
   pub(crate) fn from_record(record: Pair) -> Result<ArgInstruction> {
     let span = Span::from_pair(&record);
     let mut name = None;
     let mut value = None;
+    let mut quote_style = QuoteStyle::Unquoted;
 
     for field in record.into_inner() {
       match field.as_rule() {
         Rule::arg_name => name = Some(parse_string(&field)?),
-        Rule::arg_quoted_value => value = Some(parse_string(&field)?),
+        Rule::arg_quoted_value => {
+          quote_style = QuoteStyle::of(field.as_str());
+          value = Some(parse_string(&field)?);
+        },
         Rule::arg_value => value = Some(parse_string(&field)?),
         Rule::comment => continue,
         _ => return Err(unexpected_token(field))
@@ -55,6 +96,7 @@ impl ArgInstruction {
       span,
       name,
       value,
+      quote_style,
     })
   }
 }
@@ -91,11 +133,16 @@ mod tests {
         name: SpannedString {
           span: Span::new(4, 7),
           content: "foo".into(),
+          raw_text: None,
+          quoted: false,
         },
         value: Some(SpannedString {
           span: Span::new(8, 11),
           content: "bar".into(),
+          raw_text: None,
+          quoted: false,
         }),
+        quote_style: QuoteStyle::Unquoted,
       }.into()
     );
 
@@ -106,11 +153,16 @@ mod tests {
         name: SpannedString {
           span: Span::new(4, 7),
           content: "foo".into(),
+          raw_text: None,
+          quoted: false,
         },
         value: Some(SpannedString {
           span: Span::new(8, 13),
           content: "bar".into(),
+          raw_text: None,
+          quoted: true,
         }),
+        quote_style: QuoteStyle::Double,
       }.into()
     );
 
@@ -121,11 +173,16 @@ mod tests {
         name: SpannedString {
           span: Span::new(4, 7),
           content: "foo".into(),
+          raw_text: None,
+          quoted: false,
         },
         value: Some(SpannedString {
           span: Span::new(8, 13),
           content: "bar".into(),
+          raw_text: None,
+          quoted: true,
         }),
+        quote_style: QuoteStyle::Single,
       }.into()
     );
 
@@ -134,4 +191,36 @@ mod tests {
 
     Ok(())
   }
+
+  #[test]
+  fn arg_names() -> Result<()> {
+    let d = Dockerfile::parse(indoc::indoc!(r#"
+      ARG foo-bar=1
+      ARG HTTP_PROXY
+      ARG Http_Proxy
+      ARG _valid2
+      ARG 2nd
+    "#))?;
+
+    let args: Vec<&ArgInstruction> = d.instructions
+      .iter()
+      .filter_map(|i| i.as_arg())
+      .collect();
+
+    assert_eq!(args[0].name.content, "foo-bar");
+    assert_eq!(args[0].invalid_name_char().map(|s| d.text(&s)), Some("-"));
+    assert_eq!(args[0].value.as_ref().map(|v| v.content.as_str()), Some("1"));
+    assert!(!args[0].is_predefined());
+
+    assert!(args[1].is_predefined());
+    assert_eq!(args[1].invalid_name_char(), None);
+
+    // predefined names are case-sensitive
+    assert!(!args[2].is_predefined());
+
+    assert_eq!(args[3].invalid_name_char(), None);
+    assert_eq!(args[4].invalid_name_char().map(|s| d.text(&s)), Some("2"));
+
+    Ok(())
+  }
 }
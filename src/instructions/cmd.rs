@@ -114,9 +114,13 @@ mod tests {
           elements: vec![SpannedString {
             span: Span::new(5, 11),
             content: "echo".to_string(),
+            raw_text: None,
+            quoted: true,
           }, SpannedString {
             span: Span::new(13, 26),
             content: "hello world".to_string(),
+            raw_text: None,
+            quoted: true,
           }]
         }),
       }.into()
@@ -140,9 +144,13 @@ mod tests {
           elements: vec![SpannedString {
             span: Span::new(24, 30),
             content: "echo".to_string(),
+            raw_text: None,
+            quoted: true,
           }, SpannedString {
             span: Span::new(42, 55),
             content: "hello world".to_string(),
+            raw_text: None,
+            quoted: true,
           }]
         }),
       }.into()
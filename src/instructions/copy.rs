@@ -6,13 +6,22 @@ use snafu::ensure;
 
 use crate::dockerfile_parser::Instruction;
 use crate::parser::{Pair, Rule};
-use crate::{Span, parse_string};
+use crate::Span;
 use crate::SpannedString;
 use crate::error::*;
+use crate::heredoc::Heredoc;
+use crate::splicer::Splicer;
+use crate::util::{parse_string, removal_span, reorder_flags, FlagValue, ShortList};
 
-/// A key/value pair passed to a `COPY` instruction as a flag.
+/// The canonical order of `COPY` flags, for use with
+/// `CopyInstruction::reorder_flags()`. Other flags follow alphabetically.
+pub const CANONICAL_COPY_FLAG_ORDER: &[&str] = &["from", "chown", "chmod", "link"];
+
+/// A key/value pair passed to a `COPY` or `ADD` instruction as a flag.
 ///
-/// Examples include: `COPY --from=foo /to /from`
+/// Examples include: `COPY --from=foo /to /from`. Boolean flags like `--link`
+/// may be given without a value, in which case `value` is empty; see
+/// `flag_value()`.
 #[derive(Debug, PartialEq, Eq, Clone)]
 pub struct CopyFlag {
   pub span: Span,
@@ -38,59 +47,274 @@ impl CopyFlag {
       message: "copy flags require a key".into(),
     })?;
 
-    let value = value.ok_or_else(|| Error::GenericParseError {
-      message: "copy flags require a value".into()
-    })?;
+    // boolean flags may be given bare, e.g. `--link`
+    let value = value.unwrap_or_else(|| SpannedString {
+      span: Span::new(name.span.end, name.span.end),
+      content: String::new(),
+      raw_text: None,
+      quoted: false,
+    });
 
     Ok(CopyFlag {
       span, name, value
     })
   }
+
+  /// Returns the value of this flag, interpreting boolean-style values.
+  pub fn flag_value(&self) -> FlagValue {
+    FlagValue::of(self.span, &self.name, &self.value)
+  }
+}
+
+/// The classified value of a `COPY --from=...` flag.
+///
+/// Docker accepts a previous stage's index, a stage alias, or an external
+/// image reference. Some values are syntactically fine but can never work, and
+/// are classified separately so they can be reported before build time.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum CopyFromTarget {
+  /// A build stage referenced by index, e.g. `--from=0`
+  Index(usize),
+
+  /// A negative stage index, e.g. `--from=-1`, which is never valid
+  NegativeIndex(i64),
+
+  /// The empty `scratch` image, which has no files to copy
+  Scratch,
+
+  /// A build stage alias or an external image reference
+  Name(String),
+}
+
+/// How much of the build context a `COPY` source covers, as classified by
+/// `CopyInstruction::breadth()`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CopySourceBreadth {
+  /// The entire build context, e.g. `.`, `./`, or `*`
+  WholeContext,
+
+  /// Any other path containing a wildcard, e.g. `package*.json`
+  Glob,
+
+  /// A directory, i.e. a path ending with `/` or whose name has no extension,
+  /// e.g. `src`
+  Directory,
+
+  /// A file, i.e. a path whose name has an extension or begins with a `.`,
+  /// e.g. `package.json` or `.npmrc`
+  File,
+}
+
+impl CopySourceBreadth {
+  fn of(path: &str) -> CopySourceBreadth {
+    if matches!(path, "." | "./" | "*" | "**" | "./*" | "./**" | "/") {
+      return CopySourceBreadth::WholeContext;
+    }
+
+    if path.contains(['*', '?', '[']) {
+      return CopySourceBreadth::Glob;
+    }
+
+    if path.ends_with('/') {
+      return CopySourceBreadth::Directory;
+    }
+
+    let name = path.rsplit('/').next().unwrap_or(path);
+    if name.contains('.') {
+      CopySourceBreadth::File
+    } else {
+      CopySourceBreadth::Directory
+    }
+  }
 }
 
 /// A Dockerfile [`COPY` instruction][copy].
 ///
+/// Sources may be [heredocs][heredoc], e.g. `COPY <<EOF /app/config`. Their
+/// markers are kept in `sources` as written, while their bodies are in
+/// `heredocs`, and the span of the instruction is extended to cover them.
+///
 /// [copy]: https://docs.docker.com/engine/reference/builder/#copy
+/// [heredoc]: https://docs.docker.com/reference/dockerfile/#here-documents
 #[derive(Debug, PartialEq, Eq, Clone)]
 pub struct CopyInstruction {
   pub span: Span,
   pub flags: Vec<CopyFlag>,
   pub sources: Vec<SpannedString>,
-  pub destination: SpannedString
+  pub destination: SpannedString,
+
+  /// Heredocs used as sources, in order of their markers
+  pub heredocs: Vec<Heredoc>,
 }
 
-impl CopyInstruction {
-  pub(crate) fn from_record(record: Pair) -> Result<CopyInstruction> {
-    let span = Span::from_pair(&record);
-    let mut flags = Vec::new();
-    let mut paths = Vec::new();
+/// Parses the flags, sources, and destination shared by `COPY` and `ADD`
+/// instructions.
+pub(crate) fn parse_copy_arguments(
+  record: Pair,
+  keyword: &str
+) -> Result<(Vec<CopyFlag>, Vec<SpannedString>, SpannedString)> {
+  let mut flags = ShortList::new();
+  let mut paths = ShortList::new();
+
+  for field in record.into_inner() {
+    match field.as_rule() {
+      Rule::copy_flag => flags.push(CopyFlag::from_record(field)?),
+      Rule::copy_pathspec => paths.push(parse_string(&field)?),
+      Rule::comment => continue,
+      _ => return Err(unexpected_token(field))
+    }
+  }
 
-    for field in record.into_inner() {
-      match field.as_rule() {
-        Rule::copy_flag => flags.push(CopyFlag::from_record(field)?),
-        Rule::copy_pathspec => paths.push(parse_string(&field)?),
-        Rule::comment => continue,
-        _ => return Err(unexpected_token(field))
-      }
+  ensure!(
+    paths.len() >= 2,
+    GenericParseError {
+      message: format!("{} requires at least one source and a destination", keyword)
     }
+  );
 
-    ensure!(
-      paths.len() >= 2,
-      GenericParseError {
-        message: "copy requires at least one source and a destination"
-      }
-    );
+  // naughty unwrap, but we know there's something to pop
+  let destination = paths.pop().unwrap();
 
-    // naughty unwrap, but we know there's something to pop
-    let destination = paths.pop().unwrap();
+  Ok((flags.into_vec(), paths.into_vec(), destination))
+}
+
+impl CopyInstruction {
+  pub(crate) fn from_record(record: Pair) -> Result<CopyInstruction> {
+    let span = Span::from_pair(&record);
+    let (flags, sources, destination) = parse_copy_arguments(record, "copy")?;
 
     Ok(CopyInstruction {
       span,
       flags,
-      sources: paths,
-      destination
+      sources,
+      destination,
+      heredocs: Vec::new(),
     })
   }
+
+  /// Returns all flags whose names are not in `known`, in order, comparing
+  /// names case-insensitively.
+  ///
+  /// This allows tolerating flags added by newer versions of Docker while
+  /// still catching typos, e.g. `--chmown`.
+  pub fn unknown_flags(&self, known: &[&str]) -> Vec<&CopyFlag> {
+    self.flags
+      .iter()
+      .filter(|f| !known.iter().any(|k| f.name.content.eq_ignore_ascii_case(k)))
+      .collect()
+  }
+
+  /// Reorders this instruction's flags in the source text using the given
+  /// splicer, e.g. `CANONICAL_COPY_FLAG_ORDER`. Flags named in `order` come
+  /// first, in that order, followed by any others alphabetically.
+  ///
+  /// Only the flag tokens are moved; whitespace and line continuations between
+  /// them, and the paths, are left untouched. If any flag can't be moved
+  /// safely, returns `Error::UnsafeFlagReorder` without splicing anything.
+  pub fn reorder_flags(&self, splicer: &mut Splicer, order: &[&str]) -> Result<()> {
+    let flags: Vec<_> = self.flags
+      .iter()
+      .map(|f| (f.span, f.name.content.as_str(), f.value.content.as_str()))
+      .collect();
+
+    reorder_flags(splicer, &flags, order)
+  }
+
+  /// Removes the source at `index` from the source text using the given
+  /// splicer, along with the whitespace and any line continuation separating
+  /// it from the next path, e.g. turning `COPY a  b c /dst` into
+  /// `COPY a c /dst`.
+  ///
+  /// Returns `Error::InvalidRemoval` if there is no such source or if it is
+  /// the only source.
+  pub fn remove_source(&self, splicer: &mut Splicer, index: usize) -> Result<()> {
+    let reason = if index >= self.sources.len() {
+      format!("COPY only has {} sources", self.sources.len())
+    } else if self.sources.len() == 1 {
+      "COPY requires at least one source".to_string()
+    } else {
+      let spans: Vec<Span> = self.sources
+        .iter()
+        .chain(std::iter::once(&self.destination))
+        .map(|s| s.span)
+        .collect();
+
+      splicer.splice(&removal_span(&spans, index), "");
+      return Ok(());
+    };
+
+    Err(Error::InvalidRemoval { index, reason })
+  }
+
+  /// Heuristically classifies each of this instruction's sources by how much
+  /// of the build context it covers, in order.
+  ///
+  /// Names without an extension are assumed to be directories, so e.g.
+  /// `Makefile` is classified as a `CopySourceBreadth::Directory`. Sources
+  /// copied from another stage or image (see `from_flag()`) are classified
+  /// the same way.
+  pub fn breadth(&self) -> Vec<CopySourceBreadth> {
+    self.sources.iter().map(|s| CopySourceBreadth::of(&s.content)).collect()
+  }
+
+  /// Returns the `--from` flag of this instruction, if any.
+  pub fn from_flag(&self) -> Option<&CopyFlag> {
+    self.flags
+      .iter()
+      .find(|f| f.name.content.eq_ignore_ascii_case("from"))
+  }
+
+  /// Returns the last flag with the given name, compared case-insensitively,
+  /// if any.
+  pub fn flag(&self, name: &str) -> Option<&CopyFlag> {
+    self.flags
+      .iter()
+      .rev()
+      .find(|f| f.name.content.eq_ignore_ascii_case(name))
+  }
+
+  /// Returns the value of the `--chown` flag, if any.
+  pub fn chown(&self) -> Option<&SpannedString> {
+    self.flag("chown").map(|f| &f.value)
+  }
+
+  /// Returns the value of the `--chmod` flag, if any.
+  pub fn chmod(&self) -> Option<&SpannedString> {
+    self.flag("chmod").map(|f| &f.value)
+  }
+
+  /// Returns the value of the `--link` flag, if any. See
+  /// `Instruction::flag_bool()`.
+  pub fn link(&self) -> Option<Result<bool>> {
+    self.flag("link").map(|f| f.flag_value().to_bool("link"))
+  }
+
+  /// Returns the value of the `--parents` flag, if any. See
+  /// `Instruction::flag_bool()`.
+  pub fn parents(&self) -> Option<Result<bool>> {
+    self.flag("parents").map(|f| f.flag_value().to_bool("parents"))
+  }
+
+  /// Classifies the value of this instruction's `--from` flag, if any.
+  ///
+  /// Note that the classification is purely syntactic; use
+  /// `Dockerfile::validate()` to check that referenced stages actually exist.
+  /// The span of the value is available via `from_flag()`.
+  pub fn from_target(&self) -> Option<CopyFromTarget> {
+    let value = self.from_flag()?.value.content.as_str();
+
+    let target = if let Ok(index) = value.parse::<usize>() {
+      CopyFromTarget::Index(index)
+    } else if let Ok(index) = value.parse::<i64>() {
+      CopyFromTarget::NegativeIndex(index)
+    } else if value.eq_ignore_ascii_case("scratch") {
+      CopyFromTarget::Scratch
+    } else {
+      CopyFromTarget::Name(value.to_string())
+    };
+
+    Some(target)
+  }
 }
 
 impl<'a> TryFrom<&'a Instruction> for &'a CopyInstruction {
@@ -114,6 +338,7 @@ mod tests {
   use pretty_assertions::assert_eq;
 
   use super::*;
+  use crate::{Dockerfile, ExpansionBehavior};
   use crate::test_util::*;
 
   #[test]
@@ -125,12 +350,17 @@ mod tests {
         flags: vec![],
         sources: vec![SpannedString {
           span: Span::new(5, 8),
-          content: "foo".to_string()
+          content: "foo".to_string(),
+          raw_text: None,
+          quoted: false,
         }],
         destination: SpannedString {
           span: Span::new(9, 12),
-          content: "bar".to_string()
+          content: "bar".to_string(),
+          raw_text: None,
+          quoted: false,
         },
+        heredocs: vec![],
       }.into()
     );
 
@@ -147,17 +377,26 @@ mod tests {
         sources: vec![SpannedString {
           span: Span::new(5, 8),
           content: "foo".to_string(),
+          raw_text: None,
+          quoted: false,
         }, SpannedString {
           span: Span::new(9, 12),
-          content: "bar".to_string()
+          content: "bar".to_string(),
+          raw_text: None,
+          quoted: false,
         }, SpannedString {
           span: Span::new(13, 16),
-          content: "baz".to_string()
+          content: "baz".to_string(),
+          raw_text: None,
+          quoted: false,
         }],
         destination: SpannedString {
           span: Span::new(17, 20),
-          content: "qux".to_string()
+          content: "qux".to_string(),
+          raw_text: None,
+          quoted: false,
         },
+        heredocs: vec![],
       }.into()
     );
 
@@ -175,11 +414,16 @@ mod tests {
         sources: vec![SpannedString {
           span: Span::new(5, 8),
           content: "foo".to_string(),
+          raw_text: None,
+          quoted: false,
         }],
         destination: SpannedString {
           span: Span::new(11, 14),
           content: "bar".to_string(),
+          raw_text: None,
+          quoted: false,
         },
+        heredocs: vec![],
       }.into()
     );
 
@@ -207,21 +451,30 @@ mod tests {
             name: SpannedString {
               content: "from".into(),
               span: Span { start: 7, end: 11 },
+              raw_text: None,
+              quoted: false,
             },
             value: SpannedString {
               content: "alpine:3.10".into(),
               span: Span { start: 12, end: 23 },
+              raw_text: None,
+              quoted: false,
             }
           }
         ],
         sources: vec![SpannedString {
           span: Span::new(24, 46),
           content: "/usr/lib/libssl.so.1.1".to_string(),
+          raw_text: None,
+          quoted: false,
         }],
         destination: SpannedString {
           span: Span::new(47, 52),
           content: "/tmp/".into(),
-        }
+          raw_text: None,
+          quoted: false,
+        },
+        heredocs: vec![],
       }.into()
     );
 
@@ -252,24 +505,277 @@ mod tests {
             name: SpannedString {
               span: Span { start: 11, end: 15 },
               content: "from".into(),
+              raw_text: None,
+              quoted: false,
             },
             value: SpannedString {
               span: Span { start: 16, end: 27 },
               content: "alpine:3.10".into(),
+              raw_text: None,
+              quoted: false,
             },
           }
         ],
         sources: vec![SpannedString {
           span: Span::new(44, 66),
           content: "/usr/lib/libssl.so.1.1".to_string(),
+          raw_text: None,
+          quoted: false,
         }],
         destination: SpannedString {
           span: Span::new(81, 86),
           content: "/tmp/".into(),
+          raw_text: None,
+          quoted: false,
         },
+        heredocs: vec![],
       }.into()
     );
 
+    Ok(())
+  }
+  #[test]
+  fn copy_from_target() -> Result<()> {
+    let target = |s| -> Result<Option<CopyFromTarget>> {
+      Ok(parse_single(s, Rule::copy)?.into_copy().unwrap().from_target())
+    };
+
+    assert_eq!(target("copy /a /b")?, None);
+    assert_eq!(target("copy --from=0 /a /b")?, Some(CopyFromTarget::Index(0)));
+    assert_eq!(
+      target("copy --from=-1 /a /b")?,
+      Some(CopyFromTarget::NegativeIndex(-1))
+    );
+    assert_eq!(target("copy --from=SCRATCH /a /b")?, Some(CopyFromTarget::Scratch));
+    assert_eq!(
+      target("copy --FROM=build /a /b")?,
+      Some(CopyFromTarget::Name("build".into()))
+    );
+    assert_eq!(
+      target("copy --from=alpine:3.10 /a /b")?,
+      Some(CopyFromTarget::Name("alpine:3.10".into()))
+    );
+
+    Ok(())
+  }
+
+  #[test]
+  fn copy_unknown_flags() -> Result<()> {
+    let copy = parse_single(
+      "copy --from=alpine --platform=linux/arm64 --CHOWN=1:1 --chmown=1:1 --platform=linux/amd64 /a /b",
+      Rule::copy
+    )?.into_copy().unwrap();
+
+    let unknown = copy.unknown_flags(&["from", "chown", "chmod", "link"]);
+    assert_eq!(
+      unknown.iter().map(|f| (f.name.as_ref(), f.value.as_ref())).collect::<Vec<_>>(),
+      vec![
+        ("platform", "linux/arm64"),
+        ("chmown", "1:1"),
+        ("platform", "linux/amd64"),
+      ]
+    );
+    assert_eq!(unknown[1].span, Span::new(54, 66));
+
+    assert!(copy.unknown_flags(&["from", "platform", "chown", "chmown"]).is_empty());
+
+    Ok(())
+  }
+
+  #[test]
+  fn copy_reorder_flags() -> Result<()> {
+    let reorder = |s: &str| -> Result<String> {
+      let dockerfile = crate::Dockerfile::parse(s)?;
+      let mut splicer = dockerfile.splicer();
+      dockerfile.instructions[0]
+        .as_copy()
+        .unwrap()
+        .reorder_flags(&mut splicer, CANONICAL_COPY_FLAG_ORDER)?;
+
+      Ok(splicer.content)
+    };
+
+    assert_eq!(
+      reorder("COPY --chmod=644 --platform=linux/amd64 --from=build --chown=1:1 --foo=bar /a /b")?,
+      "COPY --from=build --chown=1:1 --chmod=644 --foo=bar --platform=linux/amd64 /a /b"
+    );
+
+    // already ordered flags are left alone
+    let ordered = "copy --FROM=build  --chown=1:1 /a /b";
+    assert_eq!(reorder(ordered)?, ordered);
+
+    // continuations and comments between flags stay in place
+    assert_eq!(
+      reorder(indoc!(r#"
+        COPY --chmod=644 \
+          # comment
+          --from=build \
+          --chown=1:1 /a /b
+      "#))?,
+      indoc!(r#"
+        COPY --from=build \
+          # comment
+          --chown=1:1 \
+          --chmod=644 /a /b
+      "#)
+    );
+
+    // bare flags are moved as written
+    assert_eq!(reorder("COPY --link --from=build /a /b")?, "COPY --from=build --link /a /b");
+
+    for unsafe_copy in &["COPY --chown=1 --from=\"build\" /a /b", "COPY --chown=1 --from=<<EOF /a /b"] {
+      match reorder(unsafe_copy) {
+        Err(Error::UnsafeFlagReorder { flag, .. }) => assert_eq!(flag, "from"),
+        other => panic!("unexpected result for {:?}: {:?}", unsafe_copy, other),
+      }
+    }
+
+    Ok(())
+  }
+
+  #[test]
+  fn copy_bool_flags() -> Result<()> {
+    let cases = [
+      ("--link", Some(true)),
+      ("--link=true", Some(true)),
+      ("--link=TRUE", Some(true)),
+      ("--link=1", Some(true)),
+      ("--link=false", Some(false)),
+      ("--link=False", Some(false)),
+      ("--link=0", Some(false)),
+      ("--link=yes", None),
+    ];
+
+    for (flag, expected) in &cases {
+      let d = Dockerfile::parse(&format!("COPY {} /a /b", flag))?;
+      let ins = &d.instructions[0];
+      let copy = ins.as_copy().unwrap();
+      assert_eq!(copy.sources.len(), 1, "{}", flag);
+
+      match (expected, ins.flag_bool("LINK").unwrap()) {
+        (Some(expected), Ok(actual)) => assert_eq!(*expected, actual, "{}", flag),
+        (None, Err(Error::InvalidBoolFlag { flag: name, .. })) => assert_eq!(name, "LINK"),
+        (_, other) => panic!("unexpected result for {}: {:?}", flag, other)
+      }
+
+      assert_eq!(copy.link().unwrap().ok(), *expected, "{}", flag);
+      assert_eq!(ins.flag_value("link").unwrap().as_bool(), *expected, "{}", flag);
+    }
+
+    let d = Dockerfile::parse("COPY --link \\\n  --parents=0 --chown=1:1 --chmod=0755 a/b /c")?;
+    let copy = d.instructions[0].as_copy().unwrap();
+    assert_eq!(copy.flags[0].flag_value(), FlagValue::Bool(true, None));
+    assert_eq!(copy.flags[0].value.span, Span::new(11, 11));
+    assert_eq!(d.text(&copy.flags[0].span), "--link");
+    assert_eq!(
+      copy.flags[1].flag_value(),
+      FlagValue::Bool(false, Some(copy.flags[1].value.span))
+    );
+    assert_eq!(copy.parents().unwrap()?, false);
+    assert_eq!(copy.chown().unwrap().content, "1:1");
+    assert_eq!(copy.flags[3].flag_value(), FlagValue::Str(copy.flags[3].value.clone()));
+    assert_eq!(copy.chmod().unwrap().content, "0755");
+    assert!(d.instructions[0].flag_bool("from").is_none());
+
+    // paths that merely start with dashes aren't flags
+    let d = Dockerfile::parse("COPY --a.txt /b")?;
+    assert_eq!(d.instructions[0].as_copy().unwrap().flags, vec![]);
+
+    // other instructions' flags always have values
+    let d = Dockerfile::parse("FROM --platform=linux/amd64 alpine\nRUN --network=none true")?;
+    assert!(d.instructions[0].flag_bool("platform").unwrap().is_err());
+    assert_eq!(
+      d.instructions[1].flag_value("network"),
+      Some(FlagValue::Str(d.instructions[1].as_run().unwrap().flags[0].value.clone()))
+    );
+
+    Ok(())
+  }
+
+  #[test]
+  fn copy_remove_source() -> Result<()> {
+    let d = Dockerfile::parse(indoc!(r#"
+      COPY --from=build a   b \
+        c  d \
+        /dst/
+    "#))?;
+    let copy = d.instructions[0].as_copy().unwrap();
+
+    let remove = |index: usize| {
+      let mut splicer = d.splicer();
+      copy.remove_source(&mut splicer, index).map(|_| splicer.content)
+    };
+
+    assert_eq!(remove(0)?, "COPY --from=build b \\\n  c  d \\\n  /dst/\n");
+    assert_eq!(remove(1)?, "COPY --from=build a   c  d \\\n  /dst/\n");
+    assert_eq!(remove(2)?, "COPY --from=build a   b \\\n  d \\\n  /dst/\n");
+    assert_eq!(remove(3)?, "COPY --from=build a   b \\\n  c  /dst/\n");
+    assert!(matches!(remove(4), Err(Error::InvalidRemoval { index: 4, .. })));
+
+    let d = Dockerfile::parse("COPY a /dst")?;
+    let mut splicer = d.splicer();
+    assert!(matches!(
+      d.instructions[0].as_copy().unwrap().remove_source(&mut splicer, 0),
+      Err(Error::InvalidRemoval { index: 0, .. })
+    ));
+
+    Ok(())
+  }
+
+  #[test]
+  fn copy_breadth() -> Result<()> {
+    let d = Dockerfile::parse("COPY . ./ * package*.json src/ lib app.js .npmrc /app/")?;
+    assert_eq!(d.instructions[0].as_copy().unwrap().breadth(), vec![
+      CopySourceBreadth::WholeContext,
+      CopySourceBreadth::WholeContext,
+      CopySourceBreadth::WholeContext,
+      CopySourceBreadth::Glob,
+      CopySourceBreadth::Directory,
+      CopySourceBreadth::Directory,
+      CopySourceBreadth::File,
+      CopySourceBreadth::File,
+    ]);
+
+    Ok(())
+  }
+
+  #[test]
+  fn copy_heredoc() -> Result<()> {
+    let dockerfile = Dockerfile::parse(indoc!(r#"
+      FROM alpine
+      COPY --chmod=755 <<EOF <<'RAW' /usr/local/bin/
+      echo $GREETING
+      EOF
+      echo $HOME
+      RAW
+    "#))?;
+
+    let copy = dockerfile.instructions[1].as_copy().unwrap();
+    assert_eq!(copy.chmod().unwrap().content, "755");
+    assert_eq!(copy.heredocs.len(), 2);
+    assert!(copy.heredocs[0].expand);
+    assert!(!copy.heredocs[1].expand);
+    assert_eq!(dockerfile.text(&copy.span).lines().last(), Some("RAW"));
+
+    // unquoted heredoc bodies are expanded by Docker, not a shell
+    let references = dockerfile.instructions[1].variable_references();
+    assert_eq!(references.len(), 1);
+    assert_eq!(references[0].name, "GREETING");
+    assert_eq!(references[0].expansion, ExpansionBehavior::DockerExpands);
+
+    let references = dockerfile.instructions[1].all_variable_references();
+    assert_eq!(references[1].name, "HOME");
+    assert_eq!(references[1].expansion, ExpansionBehavior::NoExpansion);
+
+    assert_eq!(
+      dockerfile.instructions[1].to_string(),
+      "COPY --chmod=755 <<EOF <<'RAW' /usr/local/bin/\necho $GREETING\nEOF\necho $HOME\nRAW"
+    );
+
+    // only whole words are heredoc markers
+    let dockerfile = Dockerfile::parse("FROM alpine\nCOPY --from=a<<EOF b<<EOF /c\n")?;
+    assert!(dockerfile.instructions[1].as_copy().unwrap().heredocs.is_empty());
+
     Ok(())
   }
 }
@@ -106,9 +106,13 @@ mod tests {
           elements: vec![SpannedString {
             span: Span::new(12, 18),
             content: "echo".to_string(),
+            raw_text: None,
+            quoted: true,
           }, SpannedString {
             span: Span::new(20, 33),
             content: "hello world".to_string(),
+            raw_text: None,
+            quoted: true,
           }]
         })
       }.into()
@@ -132,9 +136,13 @@ mod tests {
           elements: vec![SpannedString {
             span: Span::new(31, 37),
             content: "echo".to_string(),
+            raw_text: None,
+            quoted: true,
           }, SpannedString {
             span: Span::new(49, 62),
             content: "hello world".to_string(),
+            raw_text: None,
+            quoted: true,
           }]
         }),
       }.into()
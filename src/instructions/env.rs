@@ -1,14 +1,15 @@
 // (C) Copyright 2019-2020 Hewlett Packard Enterprise Development LP
 
+use std::collections::{HashMap, HashSet};
 use std::convert::TryFrom;
 
 use crate::dockerfile_parser::Instruction;
-use crate::Span;
+use crate::{Dockerfile, Span};
 use crate::error::*;
 use crate::parser::{Pair, Rule};
 use crate::util::*;
+use crate::variables::{find_expansions, try_substitute};
 
-use enquote::unquote;
 use snafu::ResultExt;
 
 /// An environment variable key/value pair
@@ -16,17 +17,62 @@ use snafu::ResultExt;
 pub struct EnvVar {
   pub span: Span,
   pub key: SpannedString,
+
+  /// The unquoted value. Its span covers the raw value as written, including
+  /// any quotes.
   pub value: BreakableString,
+
+  /// The quoting style of the value as written
+  pub quote_style: QuoteStyle,
+
+  /// The separator between the key and value as written
+  pub separator: PairSeparator,
 }
 
 impl EnvVar {
+  /// Creates a new variable, assuming the key and value are separated by `=`.
   pub fn new(span: Span, key: SpannedString, value: impl Into<BreakableString>) -> Self {
+    let value = value.into();
+
     EnvVar {
       span,
+      separator: PairSeparator::Equals(Span::new(key.span.end, value.span.start)),
       key: key,
-      value: value.into(),
+      value,
+      quote_style: QuoteStyle::Unquoted,
     }
   }
+
+  /// Sets the quoting style of this variable's value.
+  pub fn with_quote_style(mut self, quote_style: QuoteStyle) -> Self {
+    self.quote_style = quote_style;
+    self
+  }
+
+  /// Sets the separator between this variable's key and value.
+  pub fn with_separator(mut self, separator: PairSeparator) -> Self {
+    self.separator = separator;
+    self
+  }
+
+  /// Returns the raw text of this variable's value as written in the given
+  /// Dockerfile, including any quotes and line continuations.
+  pub fn raw_value_text<'d>(&self, dockerfile: &'d Dockerfile) -> &'d str {
+    dockerfile.text(&self.value.span)
+  }
+
+  /// Determines if this variable's key contains variable references, e.g.
+  /// `ENV ${PREFIX}_PATH=/foo`, in which case its actual name can only be known
+  /// once references are substituted. The key is stored as written.
+  pub fn is_dynamic(&self) -> bool {
+    !find_expansions(&self.key.content).is_empty()
+  }
+
+  /// Substitutes any variable references in this variable's key using the
+  /// given variables, returning an error if a reference can't be resolved.
+  pub fn resolve_key(&self, vars: &HashMap<&str, &str>) -> Result<String> {
+    try_substitute(&self.key.content, vars, &mut HashSet::new(), 16)
+  }
 }
 
 /// A Dockerfile [`ENV` instruction][env].
@@ -43,6 +89,7 @@ fn parse_env_pair(record: Pair) -> Result<EnvVar> {
   let span = Span::from_pair(&record);
   let mut key = None;
   let mut value = None;
+  let mut quote_style = QuoteStyle::Unquoted;
 
   for field in record.into_inner() {
     match field.as_rule() {
@@ -54,10 +101,10 @@ fn parse_env_pair(record: Pair) -> Result<EnvVar> {
       },
       Rule::env_pair_quoted_value => {
         let v = unquote(field.as_str()).context(UnescapeError)?;
+        let s = quoted_string(Span::from_pair(&field), field.as_str(), v);
+        quote_style = QuoteStyle::of(field.as_str());
 
-        value = Some(
-          BreakableString::new(&field).add_string(&field, v)
-        );
+        value = Some(BreakableString::new(&field).add(s));
       },
       _ => return Err(unexpected_token(field))
     }
@@ -73,12 +120,23 @@ fn parse_env_pair(record: Pair) -> Result<EnvVar> {
 
   Ok(EnvVar {
     span,
+    separator: PairSeparator::Equals(Span::new(key.span.end, value.span.start)),
     key,
     value,
+    quote_style,
   })
 }
 
 impl EnvInstruction {
+  /// Finds keys set more than once in this instruction, e.g.
+  /// `ENV PATH=/a PATH=/b`, returning each duplicated key with the spans of
+  /// all of its occurrences. Only the last occurrence takes effect.
+  ///
+  /// Keys are compared exactly as written, as Docker's are case-sensitive.
+  pub fn duplicate_keys(&self) -> Vec<(String, Vec<Span>)> {
+    find_duplicates(self.vars.iter().map(|v| (v.key.content.as_str(), v.key.span)))
+  }
+
   pub(crate) fn from_record(record: Pair) -> Result<EnvInstruction> {
     let span = Span::from_pair(&record);
     let field = record.into_inner().next().unwrap();
@@ -91,7 +149,7 @@ impl EnvInstruction {
   }
 
   fn from_pairs_record(span: Span, record: Pair) -> Result<EnvInstruction> {
-    let mut vars = Vec::new();
+    let mut vars = ShortList::new();
 
     for field in record.into_inner() {
       match field.as_rule() {
@@ -103,13 +161,14 @@ impl EnvInstruction {
 
     Ok(EnvInstruction {
       span,
-      vars,
+      vars: vars.into_vec(),
     })
   }
 
   fn from_single_record(span: Span, record: Pair) -> Result<EnvInstruction> {
     let mut key = None;
     let mut value = None;
+    let mut quote_style = QuoteStyle::Unquoted;
 
     for field in record.into_inner() {
       match field.as_rule() {
@@ -117,10 +176,10 @@ impl EnvInstruction {
         Rule::env_single_value => value = Some(parse_any_breakable(field)?),
         Rule::env_single_quoted_value => {
           let v = unquote(field.as_str()).context(UnescapeError)?;
+          let s = quoted_string(Span::from_pair(&field), field.as_str(), v);
+          quote_style = QuoteStyle::of(field.as_str());
 
-          value = Some(
-            BreakableString::new(&field).add_string(&field, v)
-          );
+          value = Some(BreakableString::new(&field).add(s));
         },
         Rule::comment => continue,
         _ => return Err(unexpected_token(field))
@@ -139,8 +198,10 @@ impl EnvInstruction {
       span,
       vars: vec![EnvVar {
         span: Span::new(key.span.start, value.span.end),
+        separator: PairSeparator::Whitespace(Span::new(key.span.end, value.span.start)),
         key,
         value,
+        quote_style,
       }],
     })
   }
@@ -181,6 +242,8 @@ mod tests {
           SpannedString {
             span: Span::new(4, 7),
             content: "foo".to_string(),
+            raw_text: None,
+            quoted: false,
           },
           ((8, 11), "bar"),
         )],
@@ -196,9 +259,11 @@ mod tests {
           SpannedString {
             span: Span::new(4, 11),
             content: "FOO_BAR".to_string(),
+            raw_text: None,
+            quoted: false,
           },
-          ((12, 17), "baz"),
-        )],
+          BreakableString::new((12, 17)).add_quoted_string((12, 17), "baz"),
+        ).with_quote_style(QuoteStyle::Double)],
       }.into()
     );
 
@@ -211,9 +276,12 @@ mod tests {
           SpannedString {
             span: Span::new(4, 11),
             content: "FOO_BAR".to_string(),
+            raw_text: None,
+            quoted: false,
           },
-          ((12, 17), "baz")),
-        ],
+          BreakableString::new((12, 17)).add_quoted_string((12, 17), "baz"),
+        ).with_quote_style(QuoteStyle::Double)
+          .with_separator(PairSeparator::Whitespace(Span::new(11, 12)))],
       }.into()
     );
 
@@ -226,9 +294,16 @@ mod tests {
           SpannedString {
             span: Span::new(4, 7),
             content: "foo".to_string(),
+            raw_text: None,
+            quoted: false,
           },
-          ((8, 18), "bar\"baz"),
-        )],
+          BreakableString::new((8, 18)).add(SpannedString {
+            span: Span::new(8, 18),
+            content: "bar\"baz".to_string(),
+            raw_text: Some(r#""bar\"baz""#.to_string()),
+            quoted: true,
+          }),
+        ).with_quote_style(QuoteStyle::Double)],
       }.into()
     );
 
@@ -241,9 +316,11 @@ mod tests {
           SpannedString {
             span: Span::new(4, 7),
             content: "foo".to_string(),
+            raw_text: None,
+            quoted: false,
           },
-          ((8, 13), "bar"),
-        )],
+          BreakableString::new((8, 13)).add_quoted_string((8, 13), "bar"),
+        ).with_quote_style(QuoteStyle::Single)],
       }.into()
     );
 
@@ -256,9 +333,16 @@ mod tests {
           SpannedString {
             span: Span::new(4, 7),
             content: "foo".to_string(),
+            raw_text: None,
+            quoted: false,
           },
-          ((8, 18), "bar'baz"),
-        )],
+          BreakableString::new((8, 18)).add(SpannedString {
+            span: Span::new(8, 18),
+            content: "bar'baz".to_string(),
+            raw_text: Some(r#"'bar\'baz'"#.to_string()),
+            quoted: true,
+          }),
+        ).with_quote_style(QuoteStyle::Single)],
       }.into()
     );
 
@@ -272,22 +356,28 @@ mod tests {
             SpannedString {
               span: Span::new(4, 7),
               content: "foo".to_string(),
+              raw_text: None,
+              quoted: false,
             },
-            ((8, 13), "123")
-          ),
+            BreakableString::new((8, 13)).add_quoted_string((8, 13), "123")
+          ).with_quote_style(QuoteStyle::Double),
           EnvVar::new(
             Span::new(14, 23),
             SpannedString {
               span: Span::new(14, 17),
               content: "bar".to_string(),
+              raw_text: None,
+              quoted: false,
             },
-            ((18, 23), "456")
-          ),
+            BreakableString::new((18, 23)).add_quoted_string((18, 23), "456")
+          ).with_quote_style(QuoteStyle::Single),
           EnvVar::new(
             Span::new(24, 31),
             SpannedString {
               span: Span::new(24, 27),
               content: "baz".to_string(),
+              raw_text: None,
+              quoted: false,
             },
             ((28, 31), "789")
           ),
@@ -301,6 +391,32 @@ mod tests {
     Ok(())
   }
 
+  #[test]
+  fn env_dynamic_keys() -> Result<()> {
+    let env = parse_single(r#"env ${PREFIX}_PATH=/x $ns_HOME="/y" PLAIN=z"#, Rule::env)?
+      .into_env().unwrap();
+
+    assert_eq!(
+      env.vars.iter().map(|v| (v.key.as_ref(), v.is_dynamic())).collect::<Vec<_>>(),
+      vec![("${PREFIX}_PATH", true), ("$ns_HOME", true), ("PLAIN", false)]
+    );
+    assert_eq!(env.vars[0].key.span, Span::new(4, 18));
+
+    let mut vars = HashMap::new();
+    vars.insert("PREFIX", "APP");
+    assert_eq!(env.vars[0].resolve_key(&vars)?, "APP_PATH");
+    assert_eq!(env.vars[2].resolve_key(&vars)?, "PLAIN");
+    assert!(env.vars[1].resolve_key(&vars).is_err());
+
+    let env = parse_single(r#"env ${PREFIX:-APP}_PATH /x y"#, Rule::env)?
+      .into_env().unwrap();
+    assert_eq!(env.vars[0].key.content, "${PREFIX:-APP}_PATH");
+    assert_eq!(env.vars[0].value.to_string(), "/x y");
+    assert_eq!(env.vars[0].resolve_key(&HashMap::new())?, "APP_PATH");
+
+    Ok(())
+  }
+
   #[test]
   fn test_multiline_pairs() -> Result<()> {
     // note: docker allows empty line continuations (but may print a warning)
@@ -320,6 +436,8 @@ mod tests {
           SpannedString {
             span: Span::new(4, 7),
             content: "foo".to_string(),
+            raw_text: None,
+            quoted: false,
           },
           ((8, 9), "a")
         ),
@@ -328,6 +446,8 @@ mod tests {
           SpannedString {
             span: Span::new(14, 17),
             content: "bar".to_string(),
+            raw_text: None,
+            quoted: false,
           },
           ((18, 19), "b")
         ),
@@ -336,6 +456,8 @@ mod tests {
           SpannedString {
             span: Span::new(24, 27),
             content: "baz".to_string(),
+            raw_text: None,
+            quoted: false,
           },
           ((28, 29), "c")
         )
@@ -363,13 +485,15 @@ mod tests {
           SpannedString {
             span: Span::new(4, 7),
             content: "foo".to_string(),
+            raw_text: None,
+            quoted: false,
           },
           BreakableString::new((8, 143))
             .add_string((8, 36), "Lorem ipsum dolor sit amet, ")
             .add_string((38, 69), "  consectetur adipiscing elit, ")
             .add_string((71, 109), "  sed do eiusmod tempor incididunt ut ")
             .add_string((111, 143), "  labore et dolore magna aliqua.")
-        )
+        ).with_separator(PairSeparator::Whitespace(Span::new(7, 8)))
       ]
     );
 
@@ -391,11 +515,13 @@ mod tests {
           SpannedString {
             span: Span::new(8, 11),
             content: "foo".to_string(),
+            raw_text: None,
+            quoted: false,
           },
           BreakableString::new((16, 75))
             .add_string((16, 44), "Lorem ipsum dolor sit amet, ")
             .add_string((46, 75), "  consectetur adipiscing elit")
-        )
+        ).with_separator(PairSeparator::Whitespace(Span::new(11, 16)))
       ]
     );
 
@@ -417,15 +543,38 @@ mod tests {
           SpannedString {
             span: Span::new(8, 11),
             content: "foo".to_string(),
+            raw_text: None,
+            quoted: false,
           },
           BreakableString::new((24, 91))
             .add_string((24, 52), "Lorem ipsum dolor sit amet, ")
             .add_comment((56, 61), "# baz")
             .add_string((62, 91), "  consectetur adipiscing elit")
-        )
+        ).with_separator(PairSeparator::Whitespace(Span::new(11, 24)))
+      ]
+    );
+
+    Ok(())
+  }
+
+  #[test]
+  fn test_duplicate_keys() -> Result<()> {
+    let env = parse_single(
+      "ENV A=1 B=2 \\\n  a=3 A=4 \\\n  B=5 A=6",
+      Rule::env
+    )?.into_env().unwrap();
+
+    assert_eq!(
+      env.duplicate_keys(),
+      vec![
+        ("A".to_string(), vec![Span::new(4, 5), Span::new(20, 21), Span::new(32, 33)]),
+        ("B".to_string(), vec![Span::new(8, 9), Span::new(28, 29)]),
       ]
     );
 
+    let env = parse_single("ENV A 1", Rule::env)?.into_env().unwrap();
+    assert!(env.duplicate_keys().is_empty());
+
     Ok(())
   }
 }
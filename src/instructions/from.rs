@@ -3,15 +3,13 @@
 use std::convert::TryFrom;
 
 use crate::dockerfile_parser::Instruction;
-use crate::image::ImageRef;
+use crate::image::{Digest, ImageRef};
 use crate::parser::{Pair, Rule};
-use crate::parse_string;
+use crate::util::{parse_string, ShortList};
 use crate::SpannedString;
 use crate::splicer::*;
 use crate::error::*;
-
-use lazy_static::lazy_static;
-use regex::Regex;
+use crate::util::{reorder_flags, FlagValue};
 
 /// A key/value pair passed to a `FROM` instruction as a flag.
 ///
@@ -49,6 +47,11 @@ impl FromFlag {
       span, name, value
     })
   }
+
+  /// Returns the value of this flag, interpreting boolean-style values.
+  pub fn flag_value(&self) -> FlagValue {
+    FlagValue::of(self.span, &self.name, &self.value)
+  }
 }
 
 
@@ -65,21 +68,29 @@ pub struct FromInstruction {
   pub image: SpannedString,
   pub image_parsed: ImageRef,
 
-  pub index: usize,
+  /// The index of this instruction among the `FROM` instructions of its
+  /// Dockerfile, i.e. the index of the stage it begins. This is assigned when
+  /// parsing a whole Dockerfile, and is None for instructions parsed on their
+  /// own, e.g. an `ONBUILD` trigger.
+  pub index: Option<usize>,
   pub alias: Option<SpannedString>,
 }
 
 impl FromInstruction {
-  pub(crate) fn from_record(record: Pair, index: usize) -> Result<FromInstruction> {
-    lazy_static! {
-      static ref HEX: Regex =
-          Regex::new(r"[0-9a-fA-F]+").unwrap();
-    }
+  pub(crate) fn from_record(record: Pair) -> Result<FromInstruction> {
+    FromInstruction::from_record_with_options(record, true)
+  }
 
+  /// Parses a `FROM` record, only rejecting invalid image digests if
+  /// `strict_digests` is set; see `ParseOptions::strict_digests`.
+  pub(crate) fn from_record_with_options(
+    record: Pair,
+    strict_digests: bool
+  ) -> Result<FromInstruction> {
     let span = Span::from_pair(&record);
     let mut image_field = None;
     let mut alias_field = None;
-    let mut flags = Vec::new();
+    let mut flags = ShortList::new();
 
     for field in record.into_inner() {
       match field.as_rule() {
@@ -102,12 +113,7 @@ impl FromInstruction {
     let image_parsed = ImageRef::parse(&image.as_ref());
 
     if let Some(hash) = &image_parsed.hash {
-      let parts: Vec<&str> = hash.split(":").collect();
-      if let ["sha256", hexdata] = parts[..] {
-        if !HEX.is_match(hexdata) || hexdata.len() != 64 {
-          return Err(Error::GenericParseError { message: "image reference digest is invalid".into() });
-        }
-      } else {
+      if strict_digests && Digest::parse(hash).is_err() {
         return Err(Error::GenericParseError { message: "image reference digest is invalid".into() });
       }
     }
@@ -119,12 +125,37 @@ impl FromInstruction {
     };
 
     Ok(FromInstruction {
-      span, index,
+      span,
+      index: None,
       image, image_parsed,
-      flags, alias,
+      flags: flags.into_vec(),
+      alias,
     })
   }
 
+  /// Returns all flags whose names are not in `known`, in order, comparing
+  /// names case-insensitively.
+  ///
+  /// This allows tolerating flags added by newer versions of Docker while
+  /// still catching typos, e.g. `--platfrom`.
+  pub fn unknown_flags(&self, known: &[&str]) -> Vec<&FromFlag> {
+    self.flags
+      .iter()
+      .filter(|f| !known.iter().any(|k| f.name.content.eq_ignore_ascii_case(k)))
+      .collect()
+  }
+
+  /// Reorders this instruction's flags in the source text using the given
+  /// splicer. See `CopyInstruction::reorder_flags()`.
+  pub fn reorder_flags(&self, splicer: &mut Splicer, order: &[&str]) -> Result<()> {
+    let flags: Vec<_> = self.flags
+      .iter()
+      .map(|f| (f.span, f.name.content.as_str(), f.value.content.as_str()))
+      .collect();
+
+    reorder_flags(splicer, &flags, order)
+  }
+
   // TODO: util for converting to an ImageRef while resolving ARG
   // per the docs, ARG instructions are only honored in FROMs if they occur
   // before the *first* FROM (but this should be verified)
@@ -168,7 +199,7 @@ use indoc::indoc;
       let result = parse_direct(
         case,
         Rule::from,
-        |p| FromInstruction::from_record(p, 0)
+        FromInstruction::from_record
       );
 
       match result {
@@ -186,17 +217,20 @@ use indoc::indoc;
     let from = parse_direct(
       "from alpine:3.10",
       Rule::from,
-      |p| FromInstruction::from_record(p, 0)
+      FromInstruction::from_record
     )?;
 
     assert_eq!(from, FromInstruction {
       span: Span { start: 0, end: 16 },
-      index: 0,
+      index: None,
       image: SpannedString {
         span: Span { start: 5, end: 16 },
         content: "alpine:3.10".into(),
+        raw_text: None,
+        quoted: false,
       },
       image_parsed: ImageRef {
+        scheme: None,
         registry: None,
         image: "alpine".into(),
         tag: Some("3.10".into()),
@@ -239,7 +273,7 @@ use indoc::indoc;
         Rule::from
       )?,
       FromInstruction {
-        index: 0,
+        index: None,
         span: Span { start: 0, end: 39 },
         flags: vec![
           FromFlag {
@@ -247,18 +281,25 @@ use indoc::indoc;
             name: SpannedString {
               content: "platform".into(),
               span: Span { start: 7, end: 15 },
+              raw_text: None,
+              quoted: false,
             },
             value: SpannedString {
               content: "linux/amd64".into(),
               span: Span { start: 16, end: 27 },
+              raw_text: None,
+              quoted: false,
             }
           }
         ],
         image: SpannedString {
           span: Span { start: 28, end: 39 },
           content: "alpine:3.10".into(),
+          raw_text: None,
+          quoted: false,
         },
         image_parsed: ImageRef {
+          scheme: None,
           registry: None,
           image: "alpine".into(),
           tag: Some("3.10".into()),
@@ -288,17 +329,20 @@ use indoc::indoc;
           test
       "#),
       Rule::from,
-      |p| FromInstruction::from_record(p, 0)
+      FromInstruction::from_record
     )?;
 
     assert_eq!(from, FromInstruction {
       span: Span { start: 0, end: 68 },
-      index: 0,
+      index: None,
       image: SpannedString {
         span: Span { start: 17, end: 28 },
         content: "alpine:3.10".into(),
+        raw_text: None,
+        quoted: false,
       },
       image_parsed: ImageRef {
+        scheme: None,
         registry: None,
         image: "alpine".into(),
         tag: Some("3.10".into()),
@@ -307,10 +351,77 @@ use indoc::indoc;
       alias: Some(SpannedString {
         span: (64, 68).into(),
         content: "test".into(),
+        raw_text: None,
+        quoted: false,
       }),
       flags: vec![],
     });
 
     Ok(())
   }
+
+  #[test]
+  fn from_index() -> Result<()> {
+    let dockerfile = crate::Dockerfile::parse(indoc!(r#"
+      FROM alpine AS build
+      ONBUILD FROM scratch
+      FROM build
+      RUN true
+      FROM scratch
+    "#))?;
+
+    let indices: Vec<Option<usize>> = dockerfile.instructions
+      .iter()
+      .filter_map(|ins| ins.as_from())
+      .map(|from| from.index)
+      .collect();
+    assert_eq!(indices, vec![Some(0), Some(1), Some(2)]);
+
+    for stage in dockerfile.iter_stages() {
+      let from = stage.instructions[0].as_from().unwrap();
+      assert_eq!(from.index, Some(stage.index));
+    }
+
+    // instructions parsed outside of a Dockerfile don't claim a stage
+    let trigger = dockerfile.instructions[1].as_onbuild().unwrap();
+    assert_eq!(trigger.trigger.as_from().unwrap().index, None);
+
+    let from = parse_single("FROM alpine", Rule::from)?.into_from().unwrap();
+    assert_eq!(from.index, None);
+
+    Ok(())
+  }
+
+  #[test]
+  fn from_unknown_flags() -> Result<()> {
+    let from = parse_single(
+      "from --platform=linux/amd64 --platfrom=linux/arm64 --PLATFORM=x alpine",
+      Rule::from
+    )?.into_from().unwrap();
+
+    let unknown = from.unknown_flags(&["platform"]);
+    assert_eq!(unknown.len(), 1);
+    assert_eq!(unknown[0].name.content, "platfrom");
+    assert_eq!(unknown[0].span, Span::new(28, 50));
+
+    assert_eq!(from.unknown_flags(&[]).len(), 3);
+
+    Ok(())
+  }
+
+  #[test]
+  fn from_reorder_flags() -> Result<()> {
+    let dockerfile = crate::Dockerfile::parse(
+      "FROM --foo=bar \\\n  --platform=$BUILDPLATFORM alpine AS build"
+    )?;
+
+    let mut splicer = dockerfile.splicer();
+    dockerfile.instructions[0].as_from().unwrap().reorder_flags(&mut splicer, &["platform"])?;
+    assert_eq!(
+      splicer.content,
+      "FROM --platform=$BUILDPLATFORM \\\n  --foo=bar alpine AS build"
+    );
+
+    Ok(())
+  }
 }
@@ -0,0 +1,276 @@
+// (C) Copyright 2026 Hewlett Packard Enterprise Development LP
+
+use std::convert::TryFrom;
+use std::time::Duration;
+
+use crate::Span;
+use crate::dockerfile_parser::Instruction;
+use crate::error::*;
+use crate::instructions::CmdInstruction;
+use crate::parser::*;
+use crate::spec::instruction_spec;
+use crate::units::{parse_duration, UnitError};
+use crate::util::*;
+
+/// The flags of a `HEALTHCHECK` instruction whose values are durations.
+pub const HEALTHCHECK_DURATION_FLAGS: &[&str] = &[
+  "interval", "timeout", "start-period", "start-interval",
+];
+
+/// A `--name=value` flag of a `HEALTHCHECK` instruction.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct HealthcheckFlag {
+  pub span: Span,
+  pub name: SpannedString,
+  pub value: SpannedString,
+}
+
+impl HealthcheckFlag {
+  fn from_record(record: Pair) -> Result<HealthcheckFlag> {
+    let span = Span::from_pair(&record);
+    let mut name = None;
+    let mut value = None;
+
+    for field in record.into_inner() {
+      match field.as_rule() {
+        Rule::healthcheck_flag_name => name = Some(parse_string(&field)?),
+        Rule::healthcheck_flag_value => value = Some(parse_string(&field)?),
+        _ => return Err(unexpected_token(field))
+      }
+    }
+
+    let name = name.ok_or_else(|| Error::GenericParseError {
+      message: "healthcheck flags require a key".into(),
+    })?;
+
+    let value = value.ok_or_else(|| Error::GenericParseError {
+      message: "healthcheck flags require a value".into()
+    })?;
+
+    let known = instruction_spec("HEALTHCHECK")
+      .and_then(|spec| spec.flag(&name.content))
+      .is_some();
+    if !known {
+      return Err(Error::UnknownFlag {
+        instruction: "HEALTHCHECK".into(),
+        flag: name.content,
+        span,
+      });
+    }
+
+    Ok(HealthcheckFlag {
+      span, name, value
+    })
+  }
+
+  /// Returns the value of this flag, interpreting boolean-style values.
+  pub fn flag_value(&self) -> FlagValue {
+    FlagValue::of(self.span, &self.name, &self.value)
+  }
+}
+
+/// The command of a `HEALTHCHECK` instruction.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum HealthcheckCommand {
+  /// `HEALTHCHECK NONE`, disabling any healthcheck inherited from the parent
+  /// image; the span covers the `NONE` keyword
+  None(Span),
+
+  /// `HEALTHCHECK CMD ...`, parsed like a `CMD` instruction
+  Cmd(CmdInstruction),
+}
+
+/// A Dockerfile [`HEALTHCHECK` instruction][healthcheck], e.g.
+/// `HEALTHCHECK --interval=30s CMD curl -f localhost`.
+///
+/// Each flag is kept with its own span, so values like `--interval` can be
+/// replaced in place with a `Splicer`. Flags other than `--interval`,
+/// `--timeout`, `--start-period`, `--start-interval`, and `--retries` are
+/// rejected with `Error::UnknownFlag`.
+///
+/// ```
+/// use std::time::Duration;
+/// use dockerfile_parser::{Dockerfile, HealthcheckCommand};
+///
+/// let dockerfile = Dockerfile::parse(
+///   "HEALTHCHECK --interval=1m30s --retries=5 CMD curl -f localhost"
+/// ).unwrap();
+///
+/// let healthcheck = dockerfile.instructions[0].as_healthcheck().unwrap();
+/// assert_eq!(healthcheck.duration("interval").unwrap().unwrap(), Duration::from_secs(90));
+/// assert_eq!(healthcheck.flag("retries"), Some("5"));
+/// assert!(matches!(healthcheck.command, Some(HealthcheckCommand::Cmd(_))));
+///
+/// assert!(Dockerfile::parse("HEALTHCHECK --intervall=5s CMD true").is_err());
+/// ```
+///
+/// [healthcheck]: https://docs.docker.com/engine/reference/builder/#healthcheck
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct HealthcheckInstruction {
+  pub span: Span,
+  pub flags: Vec<HealthcheckFlag>,
+
+  /// The command, or None if it is missing, e.g. `HEALTHCHECK --retries=3`
+  pub command: Option<HealthcheckCommand>,
+}
+
+impl HealthcheckInstruction {
+  pub(crate) fn from_record(record: Pair) -> Result<HealthcheckInstruction> {
+    let span = Span::from_pair(&record);
+    let mut flags = ShortList::new();
+    let mut command = None;
+
+    for field in record.into_inner() {
+      match field.as_rule() {
+        Rule::healthcheck_flag => flags.push(HealthcheckFlag::from_record(field)?),
+        Rule::healthcheck_none => {
+          command = Some(HealthcheckCommand::None(Span::from_pair(&field)));
+        },
+        Rule::cmd => command = Some(HealthcheckCommand::Cmd(CmdInstruction::from_record(field)?)),
+        Rule::comment => continue,
+        _ => return Err(unexpected_token(field))
+      }
+    }
+
+    Ok(HealthcheckInstruction { span, flags: flags.into_vec(), command })
+  }
+
+  /// Determines if this is `HEALTHCHECK NONE`.
+  pub fn is_none(&self) -> bool {
+    matches!(self.command, Some(HealthcheckCommand::None(_)))
+  }
+
+  /// Returns the `CMD` of this healthcheck, if any.
+  pub fn cmd(&self) -> Option<&CmdInstruction> {
+    match &self.command {
+      Some(HealthcheckCommand::Cmd(cmd)) => Some(cmd),
+      _ => None
+    }
+  }
+
+  /// Returns the value of the last flag with the given name, if any.
+  pub fn flag(&self, name: &str) -> Option<&str> {
+    self.flags
+      .iter()
+      .rev()
+      .find(|f| f.name.content == name)
+      .map(|f| f.value.content.as_str())
+  }
+
+  /// Parses the value of the given duration flag, e.g. `interval`, if set.
+  /// See `parse_duration()`.
+  pub fn duration(&self, name: &str) -> Option<std::result::Result<Duration, UnitError>> {
+    self.flag(name).map(parse_duration)
+  }
+}
+
+impl<'a> TryFrom<&'a Instruction> for &'a HealthcheckInstruction {
+  type Error = Error;
+
+  fn try_from(instruction: &'a Instruction) -> std::result::Result<Self, Self::Error> {
+    if let Instruction::Healthcheck(h) = instruction {
+      Ok(h)
+    } else {
+      Err(Error::ConversionError {
+        from: format!("{:?}", instruction),
+        to: "HealthcheckInstruction".into()
+      })
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use indoc::indoc;
+  use pretty_assertions::assert_eq;
+
+  use super::*;
+  use crate::Dockerfile;
+  use crate::test_util::*;
+
+  #[test]
+  fn test_healthcheck() -> Result<()> {
+    let dockerfile = Dockerfile::parse(indoc!(r#"
+      HEALTHCHECK --interval=5s --start-period=1m \
+        CMD ["curl", \
+          # comment
+          "-f", "localhost"]
+      HEALTHCHECK none
+      HEALTHCHECK CMD curl -f localhost || exit 1
+      HEALTHCHECK --retries=3
+      EXPOSE 80
+    "#))?;
+
+    let healthchecks: Vec<Option<&HealthcheckInstruction>> = dockerfile.instructions
+      .iter()
+      .map(|ins| ins.as_healthcheck())
+      .collect();
+
+    let exec = healthchecks[0].unwrap();
+    assert_eq!(exec.flag("interval"), Some("5s"));
+    assert_eq!(exec.duration("start-period").unwrap().unwrap(), Duration::from_secs(60));
+    assert_eq!(dockerfile.text(&exec.flags[1].value.span), "1m");
+
+    let elements: Vec<&str> = exec.cmd().unwrap().as_exec().unwrap().elements
+      .iter()
+      .map(|e| dockerfile.text(&e.span))
+      .collect();
+    assert_eq!(elements, vec!["\"curl\"", "\"-f\"", "\"localhost\""]);
+
+    let none = healthchecks[1].unwrap();
+    assert!(none.is_none());
+    assert!(none.cmd().is_none());
+
+    let shell = healthchecks[2].unwrap();
+    let cmd = shell.cmd().unwrap();
+    assert_eq!(dockerfile.text(&cmd.span), "CMD curl -f localhost || exit 1");
+    assert_eq!(cmd.as_shell().unwrap().to_string(), "curl -f localhost || exit 1");
+
+    let missing = healthchecks[3].unwrap();
+    assert_eq!(missing.command, None);
+    assert_eq!(missing.flag("retries"), Some("3"));
+    assert!(missing.duration("interval").is_none());
+
+    assert!(healthchecks[4].is_none());
+
+    Ok(())
+  }
+
+  #[test]
+  fn test_healthcheck_flags() -> Result<()> {
+    let healthcheck = parse_single(
+      "HEALTHCHECK --timeout=3s \\\n  --start-interval=1s NONE",
+      Rule::healthcheck
+    )?.into_healthcheck().unwrap();
+
+    assert_eq!(healthcheck.flags, vec![
+      HealthcheckFlag {
+        span: Span::new(12, 24),
+        name: SpannedString { span: Span::new(14, 21), content: "timeout".into(), raw_text: None, quoted: false },
+        value: SpannedString { span: Span::new(22, 24), content: "3s".into(), raw_text: None, quoted: false },
+      },
+      HealthcheckFlag {
+        span: Span::new(29, 48),
+        name: SpannedString {
+          span: Span::new(31, 45),
+          content: "start-interval".into(),
+          raw_text: None,
+          quoted: false
+        },
+        value: SpannedString { span: Span::new(46, 48), content: "1s".into(), raw_text: None, quoted: false },
+      },
+    ]);
+    assert_eq!(healthcheck.command, Some(HealthcheckCommand::None(Span::new(49, 53))));
+
+    match Dockerfile::parse("FROM alpine\nHEALTHCHECK --interval=5s --tries=3 CMD true\n") {
+      Err(Error::UnknownFlag { instruction, flag, span }) => {
+        assert_eq!(instruction, "HEALTHCHECK");
+        assert_eq!(flag, "tries");
+        assert_eq!(span, Span::new(38, 47));
+      },
+      other => panic!("expected an unknown flag error, got {:?}", other)
+    }
+
+    Ok(())
+  }
+}
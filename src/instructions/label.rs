@@ -1,5 +1,6 @@
 // (C) Copyright 2019-2020 Hewlett Packard Enterprise Development LP
 
+use std::collections::{HashMap, HashSet};
 use std::convert::TryFrom;
 
 use crate::dockerfile_parser::Instruction;
@@ -7,8 +8,8 @@ use crate::parser::{Pair, Rule};
 use crate::Span;
 use crate::util::*;
 use crate::error::*;
+use crate::variables::{find_expansions, try_substitute};
 
-use enquote::unquote;
 use snafu::ResultExt;
 
 /// A single label key/value pair.
@@ -16,21 +17,46 @@ use snafu::ResultExt;
 pub struct Label {
   pub span: Span,
   pub name: SpannedString,
-  pub value: SpannedString
+  pub value: SpannedString,
+
+  /// The separator between the name and value as written
+  pub separator: PairSeparator,
 }
 
 impl Label {
+  /// Creates a new label, assuming the name and value are separated by `=`.
   pub fn new(span: Span, name: SpannedString, value: SpannedString) -> Label
   {
     Label {
       span,
+      separator: PairSeparator::Equals(Span::new(name.span.end, value.span.start)),
       name,
       value,
     }
   }
 
+  /// Sets the separator between this label's name and value.
+  pub fn with_separator(mut self, separator: PairSeparator) -> Self {
+    self.separator = separator;
+    self
+  }
+
+  /// Determines if this label's name contains variable references, e.g.
+  /// `LABEL $ns.version=1`, in which case its actual name can only be known
+  /// once references are substituted. The name is stored as written.
+  pub fn is_dynamic(&self) -> bool {
+    !find_expansions(&self.name.content).is_empty()
+  }
+
+  /// Substitutes any variable references in this label's name using the given
+  /// variables, returning an error if a reference can't be resolved.
+  pub fn resolve_name(&self, vars: &HashMap<&str, &str>) -> Result<String> {
+    try_substitute(&self.name.content, vars, &mut HashSet::new(), 16)
+  }
+
   pub(crate) fn from_record(record: Pair) -> Result<Label> {
     let span = Span::from_pair(&record);
+    let single = record.as_rule() == Rule::label_single;
     let mut name = None;
     let mut value = None;
 
@@ -42,10 +68,7 @@ impl Label {
           let v = unquote(&clean_escaped_breaks(field.as_str()))
             .context(UnescapeError)?;
 
-          name = Some(SpannedString {
-            content: v,
-            span: Span::from_pair(&field),
-          });
+          name = Some(quoted_string(Span::from_pair(&field), field.as_str(), v));
         },
 
         Rule::label_value => value = Some(parse_string(&field)?),
@@ -53,10 +76,7 @@ impl Label {
           let v = unquote(&clean_escaped_breaks(field.as_str()))
             .context(UnescapeError)?;
 
-          value = Some(SpannedString {
-            content: v,
-            span: Span::from_pair(&field),
-          });
+          value = Some(quoted_string(Span::from_pair(&field), field.as_str(), v));
         },
         Rule::comment => continue,
         _ => return Err(unexpected_token(field))
@@ -71,7 +91,16 @@ impl Label {
       message: "label value is required".into()
     })?;
 
-    Ok(Label::new(span, name, value))
+    // the single-pair rule also covers the whitespace preceding the name,
+    // which isn't part of the label
+    let separator = Span::new(name.span.end, value.span.start);
+    let (span, separator) = if single {
+      (Span::new(name.span.start, span.end), PairSeparator::Whitespace(separator))
+    } else {
+      (span, PairSeparator::Equals(separator))
+    };
+
+    Ok(Label::new(span, name, value).with_separator(separator))
   }
 }
 
@@ -89,7 +118,7 @@ pub struct LabelInstruction {
 impl LabelInstruction {
   pub(crate) fn from_record(record: Pair) -> Result<LabelInstruction> {
     let span = Span::from_pair(&record);
-    let mut labels = Vec::new();
+    let mut labels = ShortList::new();
 
     for field in record.into_inner() {
       match field.as_rule() {
@@ -102,7 +131,7 @@ impl LabelInstruction {
 
     Ok(LabelInstruction {
       span,
-      labels,
+      labels: labels.into_vec(),
     })
   }
 }
@@ -142,9 +171,13 @@ mod tests {
             SpannedString {
               span: Span::new(6, 9),
               content: "foo".to_string(),
+              raw_text: None,
+              quoted: false,
             }, SpannedString {
               span: Span::new(10, 13),
-              content: "bar".to_string()
+              content: "bar".to_string(),
+              raw_text: None,
+              quoted: false,
             },
           )
         ]
@@ -161,10 +194,14 @@ mod tests {
             SpannedString {
               span: Span::new(6, 13),
               content: "foo.bar".to_string(),
+              raw_text: None,
+              quoted: false,
             },
             SpannedString {
               span: Span::new(14, 17),
-              content: "baz".to_string()
+              content: "baz".to_string(),
+              raw_text: None,
+              quoted: false,
             }
           )
         ]
@@ -181,9 +218,13 @@ mod tests {
             SpannedString {
               span: Span::new(6, 15),
               content: "foo.bar".to_string(),
+              raw_text: None,
+              quoted: true,
             }, SpannedString {
               span: Span::new(16, 25),
               content: "baz qux".to_string(),
+              raw_text: None,
+              quoted: true,
             },
           )
         ]
@@ -197,16 +238,20 @@ mod tests {
         span: Span::new(0, 17),
         labels: vec![
           Label::new(
-            Span::new(5, 17),
+            Span::new(6, 17),
             SpannedString {
               span: Span::new(6, 13),
               content: "foo.bar".to_string(),
+              raw_text: None,
+              quoted: false,
             },
             SpannedString {
               span: Span::new(14, 17),
               content: "baz".to_string(),
+              raw_text: None,
+              quoted: false,
             }
-          )
+          ).with_separator(PairSeparator::Whitespace(Span::new(13, 14)))
         ]
       }.into()
     );
@@ -216,16 +261,20 @@ mod tests {
         span: Span::new(0, 25),
         labels: vec![
           Label::new(
-            Span::new(5, 25),
+            Span::new(6, 25),
             SpannedString {
               span: Span::new(6, 15),
               content: "foo.bar".to_string(),
+              raw_text: None,
+              quoted: true,
             },
             SpannedString {
               span: Span::new(16, 25),
               content: "baz qux".to_string(),
+              raw_text: None,
+              quoted: true,
             },
-          )
+          ).with_separator(PairSeparator::Whitespace(Span::new(15, 16)))
         ]
       }.into()
     );
@@ -233,6 +282,25 @@ mod tests {
     Ok(())
   }
 
+  #[test]
+  fn label_dynamic_names() -> Result<()> {
+    let label = parse_single(r#"label $ns.version=1 ${ns}.name=foo plain=bar"#, Rule::label)?
+      .into_label().unwrap();
+
+    assert_eq!(
+      label.labels.iter().map(|l| (l.name.as_ref(), l.is_dynamic())).collect::<Vec<_>>(),
+      vec![("$ns.version", true), ("${ns}.name", true), ("plain", false)]
+    );
+
+    let mut vars = HashMap::new();
+    vars.insert("ns", "com.example");
+    assert_eq!(label.labels[0].resolve_name(&vars)?, "com.example.version");
+    assert_eq!(label.labels[1].resolve_name(&vars)?, "com.example.name");
+    assert!(label.labels[0].resolve_name(&HashMap::new()).is_err());
+
+    Ok(())
+  }
+
   #[test]
   fn label_multi() -> Result<()> {
     assert_eq!(
@@ -245,10 +313,14 @@ mod tests {
             SpannedString {
               span: Span::new(6, 9),
               content: "foo".to_string(),
+              raw_text: None,
+              quoted: false,
             },
             SpannedString {
               span: Span::new(10, 13),
               content: "bar".to_string(),
+              raw_text: None,
+              quoted: false,
             },
           ),
           Label::new(
@@ -256,10 +328,14 @@ mod tests {
             SpannedString {
               span: Span::new(14, 17),
               content: "baz".to_string(),
+              raw_text: None,
+              quoted: false,
             },
             SpannedString {
               span: Span::new(18, 23),
               content: "qux".to_string(),
+              raw_text: None,
+              quoted: true,
             },
           ),
           Label::new(
@@ -267,10 +343,14 @@ mod tests {
             SpannedString {
               span: Span::new(24, 35),
               content: "quux quuz".to_string(),
+              raw_text: None,
+              quoted: true,
             },
             SpannedString {
               span: Span::new(36, 50),
               content: "corge grault".to_string(),
+              raw_text: None,
+              quoted: true,
             },
           )
         ]
@@ -292,10 +372,14 @@ mod tests {
             SpannedString {
               span: Span::new(6, 9),
               content: "foo".to_string(),
+              raw_text: None,
+              quoted: false,
             },
             SpannedString {
               span: Span::new(10, 13),
               content: "bar".to_string(),
+              raw_text: None,
+              quoted: false,
             },
           ),
           Label::new(
@@ -303,10 +387,14 @@ mod tests {
             SpannedString {
               span: Span::new(26, 29),
               content: "baz".to_string(),
+              raw_text: None,
+              quoted: false,
             },
             SpannedString {
               span: Span::new(30, 35),
               content: "qux".to_string(),
+              raw_text: None,
+              quoted: true,
             },
           ),
           Label::new(
@@ -314,10 +402,14 @@ mod tests {
             SpannedString {
               span: Span::new(48, 59),
               content: "quux quuz".to_string(),
+              raw_text: None,
+              quoted: true,
             },
             SpannedString {
               span: Span::new(60, 74),
               content: "corge grault".to_string(),
+              raw_text: None,
+              quoted: true,
             },
           )
         ]
@@ -339,10 +431,14 @@ mod tests {
             SpannedString {
               span: Span::new(6, 15),
               content: "foo.bar".to_string(),
+              raw_text: None,
+              quoted: true,
             },
             SpannedString {
               span: Span::new(16, 27),
               content: "baz\n qux".to_string(),
+              raw_text: Some(r#""baz\n qux""#.to_string()),
+              quoted: true,
             },
           )
         ]
@@ -359,10 +455,14 @@ mod tests {
             SpannedString {
               span: Span::new(6, 16),
               content: "foo\nbar".to_string(),
+              raw_text: Some(r#""foo\nbar""#.to_string()),
+              quoted: true,
             },
             SpannedString {
               span: Span::new(17, 28),
               content: "baz\n qux".to_string(),
+              raw_text: Some(r#""baz\n qux""#.to_string()),
+              quoted: true,
             },
           )
         ]
@@ -392,10 +492,14 @@ mod tests {
             SpannedString {
               span: Span::new(6, 9),
               content: "foo".to_string(),
+              raw_text: None,
+              quoted: false,
             },
             SpannedString {
               span: Span::new(10, 13),
               content: "bar".to_string(),
+              raw_text: None,
+              quoted: false,
             },
           ),
           Label::new(
@@ -403,10 +507,14 @@ mod tests {
             SpannedString {
               span: Span::new(26, 66),
               content: "lorem ipsum\n          dolor\n          ".to_string(),
+              raw_text: None,
+              quoted: true,
             },
             SpannedString {
               span: Span::new(67, 87),
               content: "sit\n          amet".to_string(),
+              raw_text: None,
+              quoted: true,
             },
           ),
           Label::new(
@@ -414,10 +522,14 @@ mod tests {
             SpannedString {
               span: Span::new(100, 103),
               content: "baz".to_string(),
+              raw_text: None,
+              quoted: false,
             },
             SpannedString {
               span: Span::new(104, 107),
               content: "qux".to_string(),
+              raw_text: None,
+              quoted: false,
             },
           )
         ]
@@ -446,10 +558,14 @@ mod tests {
           SpannedString {
           span: Span::new(6, 9),
             content: "foo".to_string(),
+          raw_text: None,
+          quoted: false,
           },
           SpannedString {
             span: Span::new(10, 11),
             content: "a".to_string(),
+            raw_text: None,
+            quoted: false,
           },
         ),
         Label::new(
@@ -457,10 +573,14 @@ mod tests {
           SpannedString {
             span: Span::new(16, 19),
             content: "bar".to_string(),
+            raw_text: None,
+            quoted: false,
           },
           SpannedString {
             span: Span::new(20, 21),
             content: "b".to_string(),
+            raw_text: None,
+            quoted: false,
           },
         ),
         Label::new(
@@ -468,10 +588,14 @@ mod tests {
           SpannedString {
             span: Span::new(26, 29),
             content: "baz".to_string(),
+            raw_text: None,
+            quoted: false,
           },
           SpannedString {
             span: Span::new(30, 31),
             content: "c".to_string(),
+            raw_text: None,
+            quoted: false,
           },
         ),
       ]
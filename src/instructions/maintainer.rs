@@ -0,0 +1,221 @@
+// (C) Copyright 2026 Hewlett Packard Enterprise Development LP
+
+use std::convert::TryFrom;
+
+use snafu::ResultExt;
+
+use crate::Span;
+use crate::dockerfile_parser::Instruction;
+use crate::error::*;
+use crate::util::*;
+use crate::parser::*;
+
+/// Removes line continuations from a quoted name, as Docker joins lines before
+/// unquoting.
+fn join_continuations(text: &str) -> String {
+  let mut joined = String::with_capacity(text.len());
+  let mut lines = text.split('\n').peekable();
+  while let Some(line) = lines.next() {
+    let continued = line.trim_end_matches([' ', '\t', '\r']).strip_suffix('\\');
+    match continued {
+      Some(line) if lines.peek().is_some() => joined.push_str(line),
+      _ => {
+        joined.push_str(line);
+        if lines.peek().is_some() {
+          joined.push('\n');
+        }
+      }
+    }
+  }
+
+  joined
+}
+
+/// A Dockerfile [`MAINTAINER` instruction][maintainer], e.g.
+/// `MAINTAINER Jane Doe <jane@example.com>`.
+///
+/// `MAINTAINER` is deprecated in favor of `LABEL maintainer=...`; see
+/// `to_label()`. It is reported by the `MaintainerDeprecated` check.
+///
+/// [maintainer]: https://docs.docker.com/engine/reference/builder/#maintainer-deprecated
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct MaintainerInstruction {
+  pub span: Span,
+
+  /// The maintainer's name, as written. Line continuations and comments are
+  /// removed, and a name quoted in its entirety, e.g. `MAINTAINER "Jane Doe"`,
+  /// is unquoted. Otherwise, quotes are kept, as Docker doesn't remove them.
+  ///
+  /// For names continued onto several lines, the span covers the line
+  /// continuations and any comments between them.
+  pub name: SpannedString,
+}
+
+impl MaintainerInstruction {
+  pub(crate) fn from_record(record: Pair) -> Result<MaintainerInstruction> {
+    let span = Span::from_pair(&record);
+    let field = record.into_inner().next().unwrap();
+
+    let name = match field.as_rule() {
+      Rule::maintainer_quoted_name => SpannedString {
+        span: Span::from_pair(&field),
+        content: unquote(&join_continuations(field.as_str())).context(UnescapeError)?,
+        raw_text: None,
+        quoted: true,
+      },
+      Rule::maintainer_name => {
+        let breakable = parse_any_breakable(field)?;
+        let content = breakable.iter_components()
+          .filter_map(|c| match c {
+            BreakableStringComponent::String(s) => Some(s.content.as_str()),
+            BreakableStringComponent::Comment(_) => None,
+          })
+          .collect();
+
+        SpannedString {
+          span: breakable.span,
+          content,
+          raw_text: None,
+          quoted: false,
+        }
+      },
+      _ => return Err(unexpected_token(field))
+    };
+
+    Ok(MaintainerInstruction { span, name })
+  }
+
+  /// Determines if this instruction is deprecated, which `MAINTAINER` always
+  /// is. Provided so tools can treat deprecated instructions uniformly.
+  pub fn deprecated(&self) -> bool {
+    true
+  }
+
+  /// Returns the equivalent `LABEL` instruction text, suitable for replacing
+  /// this instruction's span.
+  ///
+  /// The name is quoted, using single quotes if it contains a `$`, as `LABEL`
+  /// values expand variables while `MAINTAINER` names don't.
+  ///
+  /// ```
+  /// use dockerfile_parser::Dockerfile;
+  ///
+  /// let dockerfile = Dockerfile::parse("MAINTAINER Jane Doe <jane@example.com>").unwrap();
+  /// let maintainer = dockerfile.instructions[0].as_maintainer().unwrap();
+  /// assert_eq!(maintainer.to_label(), r#"LABEL maintainer="Jane Doe <jane@example.com>""#);
+  /// ```
+  pub fn to_label(&self) -> String {
+    let style = if self.name.content.contains('$') {
+      QuoteStyle::Single
+    } else {
+      QuoteStyle::Double
+    };
+
+    format!("LABEL maintainer={}", style.quote(&self.name.content))
+  }
+}
+
+impl<'a> TryFrom<&'a Instruction> for &'a MaintainerInstruction {
+  type Error = Error;
+
+  fn try_from(instruction: &'a Instruction) -> std::result::Result<Self, Self::Error> {
+    if let Instruction::Maintainer(m) = instruction {
+      Ok(m)
+    } else {
+      Err(Error::ConversionError {
+        from: format!("{:?}", instruction),
+        to: "MaintainerInstruction".into()
+      })
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::convert::TryInto;
+
+  use indoc::indoc;
+  use pretty_assertions::assert_eq;
+
+  use super::*;
+  use crate::Dockerfile;
+  use crate::test_util::*;
+
+  #[test]
+  fn maintainer_basic() -> Result<()> {
+    assert_eq!(
+      parse_single("MAINTAINER Jane Doe <jane@example.com>", Rule::maintainer)?,
+      MaintainerInstruction {
+        span: Span::new(0, 38),
+        name: SpannedString {
+          span: Span::new(11, 38),
+          content: "Jane Doe <jane@example.com>".to_string(),
+          raw_text: None,
+          quoted: false
+        },
+      }.into()
+    );
+
+    let maintainer = parse_single("maintainer  jane ", Rule::maintainer)?
+      .into_maintainer()
+      .unwrap();
+    assert_eq!(maintainer.name.content, "jane");
+    assert_eq!(maintainer.name.span, Span::new(12, 16));
+    assert!(maintainer.deprecated());
+
+    Ok(())
+  }
+
+  #[test]
+  fn maintainer_quoted() -> Result<()> {
+    let dockerfile = Dockerfile::parse(indoc!(r#"
+      FROM alpine
+      MAINTAINER "Jane \"JD\" Doe"
+      MAINTAINER "Jane Doe" <jane@example.com>
+      MAINTAINER 'Jane $Doe'
+    "#))?;
+
+    let maintainer = dockerfile.instructions[1].as_maintainer().unwrap();
+    assert_eq!(maintainer.name.content, "Jane \"JD\" Doe");
+    assert_eq!(dockerfile.text(&maintainer.name.span), r#""Jane \"JD\" Doe""#);
+    assert_eq!(maintainer.to_label(), r#"LABEL maintainer="Jane \"JD\" Doe""#);
+
+    // partially quoted names are kept as written
+    let maintainer: &MaintainerInstruction = (&dockerfile.instructions[2]).try_into()?;
+    assert_eq!(maintainer.name.content, r#""Jane Doe" <jane@example.com>"#);
+    assert_eq!(maintainer.to_label(), r#"LABEL maintainer="\"Jane Doe\" <jane@example.com>""#);
+
+    let maintainer = dockerfile.instructions[3].as_maintainer().unwrap();
+    assert_eq!(maintainer.name.content, "Jane $Doe");
+    assert_eq!(maintainer.to_label(), "LABEL maintainer='Jane $Doe'");
+
+    Ok(())
+  }
+
+  #[test]
+  fn maintainer_multiline() -> Result<()> {
+    let dockerfile = Dockerfile::parse(indoc!(r#"
+      FROM alpine
+      MAINTAINER Jane Doe \
+        # work address
+        <jane@example.com>
+      MAINTAINER \
+        "Jane \
+      Doe"
+      RUN true
+    "#))?;
+
+    let maintainer = dockerfile.instructions[1].as_maintainer().unwrap();
+    assert_eq!(maintainer.name.content, "Jane Doe   <jane@example.com>");
+    assert!(dockerfile.text(&maintainer.name.span).starts_with("Jane Doe \\"));
+    assert!(dockerfile.text(&maintainer.span).ends_with("<jane@example.com>"));
+
+    let maintainer = dockerfile.instructions[2].as_maintainer().unwrap();
+    assert_eq!(maintainer.name.content, "Jane Doe");
+    assert_eq!(dockerfile.text(&maintainer.name.span), "\"Jane \\\nDoe\"");
+
+    assert!(dockerfile.instructions[3].as_run().is_some());
+
+    Ok(())
+  }
+}
@@ -5,6 +5,7 @@ use std::convert::TryFrom;
 use crate::Span;
 use crate::dockerfile_parser::Instruction;
 use crate::error::*;
+use crate::heredoc::split_words;
 use crate::util::*;
 use crate::parser::*;
 
@@ -13,8 +14,7 @@ use crate::parser::*;
 /// These are instructions that aren't explicitly parsed. They may be invalid,
 /// deprecated, or otherwise unsupported by this library.
 ///
-/// Unsupported but valid commands include: `MAINTAINER`, `EXPOSE`, `VOLUME`,
-/// `USER`, `WORKDIR`, `ONBUILD`, `STOPSIGNAL`, `HEALTHCHECK`, `SHELL`
+/// Unsupported but valid commands include `EXPOSE`
 #[derive(Debug, PartialEq, Eq, Clone)]
 pub struct MiscInstruction {
   pub span: Span,
@@ -49,6 +49,44 @@ impl MiscInstruction {
       instruction, arguments
     })
   }
+
+  /// Splits this instruction's arguments into words on unquoted whitespace,
+  /// skipping comments and line continuations.
+  ///
+  /// Quotes and escapes are removed from each word's content, while its span
+  /// covers the original text. Variable references are left as-is, and braced
+  /// references like `${FOO:-a b}` are never split.
+  ///
+  /// ```
+  /// use dockerfile_parser::Dockerfile;
+  ///
+  /// let dockerfile = Dockerfile::parse(r#"EXPOSE 80 "443/udp""#).unwrap();
+  /// let misc = dockerfile.instructions[0].as_misc().unwrap();
+  /// let words: Vec<String> = misc.argument_words().into_iter().map(|w| w.content).collect();
+  /// assert_eq!(words, vec!["80", "443/udp"]);
+  /// ```
+  pub fn argument_words(&self) -> Vec<SpannedString> {
+    // join the string components so quotes may span line continuations,
+    // remembering the original position of each byte
+    let mut joined = String::new();
+    let mut positions = Vec::new();
+    for component in &self.arguments.components {
+      if let BreakableStringComponent::String(s) = component {
+        joined.push_str(&s.content);
+        positions.extend(s.span.start..s.span.start + s.content.len());
+      }
+    }
+
+    split_words(&joined, 0)
+      .into_iter()
+      .map(|word| SpannedString {
+        span: Span::new(positions[word.span.start], positions[word.span.end - 1] + 1),
+        content: word.content,
+        raw_text: None,
+        quoted: false,
+      })
+      .collect()
+  }
 }
 
 impl<'a> TryFrom<&'a Instruction> for &'a MiscInstruction {
@@ -65,3 +103,39 @@ impl<'a> TryFrom<&'a Instruction> for &'a MiscInstruction {
     }
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use indoc::indoc;
+  use pretty_assertions::assert_eq;
+
+  use super::*;
+  use crate::Dockerfile;
+
+  #[test]
+  fn test_argument_words() -> Result<()> {
+    let content = indoc!(r#"
+      FROBNICATE --level=3 "two words" 'a b'c \
+        # comment
+        ${FOO:-x y} "split \
+        quote" $BAR
+    "#);
+
+    let dockerfile = Dockerfile::parse(content)?;
+    let misc = dockerfile.instructions[0].as_misc().unwrap();
+    let words = misc.argument_words();
+
+    assert_eq!(
+      words.iter().map(|w| w.content.as_str()).collect::<Vec<_>>(),
+      vec!["--level=3", "two words", "a bc", "${FOO:-x y}", "split   quote", "$BAR"]
+    );
+    assert_eq!(
+      words.iter().map(|w| dockerfile.text(&w.span)).collect::<Vec<_>>(),
+      vec![
+        "--level=3", "\"two words\"", "'a b'c", "${FOO:-x y}", "\"split \\\n  quote\"", "$BAR",
+      ]
+    );
+
+    Ok(())
+  }
+}
@@ -6,6 +6,9 @@ pub use from::*;
 mod copy;
 pub use copy::*;
 
+mod add;
+pub use add::*;
+
 mod arg;
 pub use arg::*;
 
@@ -18,12 +21,38 @@ pub use env::*;
 mod run;
 pub use run::*;
 
+mod mount;
+pub use mount::*;
+
 mod entrypoint;
 pub use entrypoint::*;
 
 mod cmd;
 pub use cmd::*;
 
+mod volume;
+pub use volume::*;
+
+mod user;
+pub use user::*;
+
+mod workdir;
+pub use workdir::*;
+
 mod misc;
 pub use misc::*;
 
+mod healthcheck;
+pub use healthcheck::*;
+
+mod shell;
+pub use shell::*;
+
+mod stopsignal;
+pub use stopsignal::*;
+
+mod onbuild;
+pub use onbuild::*;
+
+mod maintainer;
+pub use maintainer::*;
@@ -0,0 +1,402 @@
+// (C) Copyright 2026 Hewlett Packard Enterprise Development LP
+
+use crate::Span;
+use crate::SpannedString;
+use crate::units::{parse_size, UnitError};
+use crate::util::parse_bool;
+
+/// A single `key` or `key=value` option of a `RUN --mount` flag.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct MountOption {
+  pub span: Span,
+  pub key: SpannedString,
+  pub value: Option<SpannedString>,
+}
+
+/// A generic `RUN --mount=...` flag value, parsed into its comma-separated
+/// options.
+///
+/// Typed views of specific mount types are available via `as_secret()`,
+/// `as_ssh()`, and `as_tmpfs()`.
+///
+/// ```
+/// use dockerfile_parser::Dockerfile;
+///
+/// let dockerfile = Dockerfile::parse(
+///   "RUN --mount=type=cache,target=/root/.cache,sharing=locked make"
+/// ).unwrap();
+///
+/// let mounts = dockerfile.instructions[0].as_run().unwrap().mounts();
+/// assert_eq!(mounts[0].mount_type(), "cache");
+/// assert_eq!(mounts[0].get("sharing"), Some("locked"));
+/// ```
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Mount {
+  pub span: Span,
+  pub options: Vec<MountOption>,
+}
+
+/// A `RUN --mount=type=secret` mount, exposing a build secret to the command.
+///
+/// See the [BuildKit documentation][secret] for details.
+///
+/// [secret]: https://docs.docker.com/reference/dockerfile/#run---mounttypesecret
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct SecretMount {
+  pub span: Span,
+
+  /// The secret ID, defaulting to the basename of `target` (or the `env` name)
+  /// if not given explicitly
+  pub id: String,
+
+  /// The path to mount the secret at, if given explicitly. See
+  /// `target_path()` for the effective path.
+  pub target: Option<String>,
+
+  /// The name of an environment variable to expose the secret as, if any
+  pub env: Option<String>,
+
+  /// If true, the build fails if the secret is unavailable
+  pub required: bool,
+
+  /// The file mode of the secret file, if given
+  pub mode: Option<u32>,
+
+  /// The user ID owning the secret file, if given
+  pub uid: Option<u32>,
+
+  /// The group ID owning the secret file, if given
+  pub gid: Option<u32>,
+}
+
+/// A `RUN --mount=type=ssh` mount, exposing an SSH agent socket to the command.
+///
+/// See the [BuildKit documentation][ssh] for details.
+///
+/// [ssh]: https://docs.docker.com/reference/dockerfile/#run---mounttypessh
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct SshMount {
+  pub span: Span,
+
+  /// The SSH agent socket or key ID, defaulting to `default`
+  pub id: String,
+
+  /// The path to mount the agent socket at, if given explicitly
+  pub target: Option<String>,
+
+  /// If true, the build fails if the key is unavailable
+  pub required: bool,
+
+  /// The file mode of the socket, if given
+  pub mode: Option<u32>,
+
+  /// The user ID owning the socket, if given
+  pub uid: Option<u32>,
+
+  /// The group ID owning the socket, if given
+  pub gid: Option<u32>,
+}
+
+/// A `RUN --mount=type=tmpfs` mount, mounting an in-memory filesystem.
+///
+/// See the [BuildKit documentation][tmpfs] for details.
+///
+/// [tmpfs]: https://docs.docker.com/reference/dockerfile/#run---mounttypetmpfs
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct TmpfsMount {
+  pub span: Span,
+
+  /// The path to mount the filesystem at, if given
+  pub target: Option<String>,
+
+  /// The `size` option as written, e.g. `64m`, if given. See `size_bytes()`.
+  pub size: Option<SpannedString>,
+}
+
+/// Parses a boolean option leniently: a bare key, `true`, or `1` are true.
+fn is_true(value: &str) -> bool {
+  value.is_empty() || parse_bool(value) == Some(true)
+}
+
+fn basename(path: &str) -> &str {
+  path.trim_end_matches('/').rsplit('/').next().unwrap_or(path)
+}
+
+impl Mount {
+  /// Parses a mount from the value of a `--mount` flag, e.g.
+  /// `type=secret,id=foo`.
+  pub fn parse(value: &SpannedString) -> Mount {
+    let mut options = Vec::new();
+    let mut offset = value.span.start;
+
+    for option in value.content.split(',') {
+      let start = offset;
+      offset += option.len() + 1;
+
+      if option.is_empty() {
+        continue;
+      }
+
+      let (key, value) = match option.find('=') {
+        Some(pos) => (&option[..pos], Some((pos + 1, &option[pos + 1..]))),
+        None => (option, None)
+      };
+
+      options.push(MountOption {
+        span: Span::new(start, start + option.len()),
+        key: SpannedString {
+          span: Span::new(start, start + key.len()),
+          content: key.to_string(),
+          raw_text: None,
+          quoted: false,
+        },
+        value: value.map(|(pos, v)| SpannedString {
+          span: Span::new(start + pos, start + pos + v.len()),
+          content: v.to_string(),
+          raw_text: None,
+          quoted: false,
+        }),
+      });
+    }
+
+    Mount {
+      span: value.span,
+      options,
+    }
+  }
+
+  /// Returns the option with the given key, if any. Keys are matched
+  /// case-insensitively.
+  pub fn option(&self, key: &str) -> Option<&MountOption> {
+    self.options.iter().find(|o| o.key.content.eq_ignore_ascii_case(key))
+  }
+
+  /// Returns the value of the option with the given key, if any. A key given
+  /// without a value returns an empty string.
+  pub fn get(&self, key: &str) -> Option<&str> {
+    self.option(key)
+      .map(|o| o.value.as_ref().map(|v| v.content.as_str()).unwrap_or(""))
+  }
+
+  /// Returns the first value of any of the given keys, e.g. to handle aliases
+  /// like `target` and `dst`.
+  fn get_any(&self, keys: &[&str]) -> Option<&str> {
+    keys.iter().find_map(|k| self.get(k))
+  }
+
+  /// Returns the mount type, defaulting to `bind`.
+  pub fn mount_type(&self) -> &str {
+    self.get("type").unwrap_or("bind")
+  }
+
+  /// Returns a typed view of this mount if it is a secret mount.
+  pub fn as_secret(&self) -> Option<SecretMount> {
+    if !self.mount_type().eq_ignore_ascii_case("secret") {
+      return None;
+    }
+
+    let target = self.get_any(&["target", "dst", "destination"]).map(String::from);
+    let env = self.get("env").map(String::from);
+    let id = self.get("id")
+      .map(String::from)
+      .or_else(|| target.as_deref().map(|t| basename(t).to_string()))
+      .or_else(|| env.clone())
+      .unwrap_or_default();
+
+    Some(SecretMount {
+      span: self.span,
+      id,
+      target,
+      env,
+      required: self.get("required").map(is_true).unwrap_or(false),
+      mode: self.get("mode").and_then(|m| u32::from_str_radix(m, 8).ok()),
+      uid: self.get("uid").and_then(|u| u.parse().ok()),
+      gid: self.get("gid").and_then(|g| g.parse().ok()),
+    })
+  }
+
+  /// Returns a typed view of this mount if it is an SSH mount.
+  pub fn as_ssh(&self) -> Option<SshMount> {
+    if !self.mount_type().eq_ignore_ascii_case("ssh") {
+      return None;
+    }
+
+    Some(SshMount {
+      span: self.span,
+      id: self.get("id").filter(|id| !id.is_empty()).unwrap_or("default").to_string(),
+      target: self.get_any(&["target", "dst", "destination"]).map(String::from),
+      required: self.get("required").map(is_true).unwrap_or(false),
+      mode: self.get("mode").and_then(|m| u32::from_str_radix(m, 8).ok()),
+      uid: self.get("uid").and_then(|u| u.parse().ok()),
+      gid: self.get("gid").and_then(|g| g.parse().ok()),
+    })
+  }
+
+  /// Returns a typed view of this mount if it is a tmpfs mount.
+  pub fn as_tmpfs(&self) -> Option<TmpfsMount> {
+    if !self.mount_type().eq_ignore_ascii_case("tmpfs") {
+      return None;
+    }
+
+    Some(TmpfsMount {
+      span: self.span,
+      target: self.get_any(&["target", "dst", "destination"]).map(String::from),
+      size: self.option("size").and_then(|o| o.value.clone()),
+    })
+  }
+}
+
+impl TmpfsMount {
+  /// Parses the `size` option into a number of bytes, if given. See
+  /// `parse_size()`.
+  ///
+  /// ```
+  /// use dockerfile_parser::Dockerfile;
+  ///
+  /// let dockerfile = Dockerfile::parse(
+  ///   "RUN --mount=type=tmpfs,target=/tmp,size=64m make"
+  /// ).unwrap();
+  ///
+  /// let run = dockerfile.instructions[0].as_run().unwrap();
+  /// let tmpfs = run.tmpfs_mounts().next().unwrap();
+  /// assert_eq!(tmpfs.target.as_deref(), Some("/tmp"));
+  /// assert_eq!(tmpfs.size_bytes().unwrap().unwrap(), 64 * 1024 * 1024);
+  /// ```
+  pub fn size_bytes(&self) -> Option<Result<u64, UnitError>> {
+    self.size.as_ref().map(|size| parse_size(&size.content))
+  }
+}
+
+impl SecretMount {
+  /// Returns the path the secret will be mounted at, if any.
+  ///
+  /// Secrets exposed as an environment variable are not mounted as a file
+  /// unless a target is also given; otherwise, the default target is
+  /// `/run/secrets/<id>`.
+  pub fn target_path(&self) -> Option<String> {
+    match (&self.target, &self.env) {
+      (Some(target), _) => Some(target.clone()),
+      (None, Some(_)) => None,
+      (None, None) => Some(format!("/run/secrets/{}", self.id)),
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use indoc::indoc;
+  use pretty_assertions::assert_eq;
+
+  use crate::*;
+
+  fn run(s: &str) -> RunInstruction {
+    Dockerfile::parse(s).unwrap().instructions.remove(0).into_run().unwrap()
+  }
+
+  #[test]
+  fn test_run_flags() {
+    let dockerfile = Dockerfile::parse(indoc!(r#"
+      RUN --network=none --mount=type=cache,target=/root/.cache \
+        pip install -r requirements.txt
+    "#)).unwrap();
+
+    let run = dockerfile.instructions[0].as_run().unwrap();
+    assert_eq!(run.flags.len(), 2);
+    assert_eq!(run.flags[0].name.content, "network");
+    assert_eq!(run.flags[0].value.content, "none");
+    assert_eq!(run.as_shell().unwrap().to_string().trim(), "pip install -r requirements.txt");
+
+    let mounts = run.mounts();
+    assert_eq!(mounts.len(), 1);
+    assert_eq!(mounts[0].mount_type(), "cache");
+
+    let target = mounts[0].option("target").unwrap().value.as_ref().unwrap();
+    assert_eq!(&dockerfile.content[target.span.start..target.span.end], "/root/.cache");
+
+    assert_eq!(run.secret_mounts().count(), 0);
+    assert_eq!(run.ssh_mounts().count(), 0);
+  }
+
+  #[test]
+  fn test_secret_mounts() {
+    let secret = run(
+      "RUN --mount=type=secret,id=aws,target=/root/.aws/credentials aws s3 cp s3://a b"
+    ).secret_mounts().next().unwrap();
+    assert_eq!(secret.id, "aws");
+    assert_eq!(secret.target_path(), Some("/root/.aws/credentials".to_string()));
+    assert_eq!(secret.env, None);
+    assert!(!secret.required);
+
+    let secret = run("RUN --mount=type=secret,id=API_KEY,env=API_KEY some-command")
+      .secret_mounts().next().unwrap();
+    assert_eq!(secret.id, "API_KEY");
+    assert_eq!(secret.env, Some("API_KEY".to_string()));
+    assert_eq!(secret.target_path(), None);
+
+    // the id defaults to the target's basename, and the target to the id
+    let secret = run("RUN --mount=type=secret,dst=/run/secrets/mytoken cat /run/secrets/mytoken")
+      .secret_mounts().next().unwrap();
+    assert_eq!(secret.id, "mytoken");
+
+    let secret = run("RUN --mount=type=secret,id=mytoken cat /run/secrets/mytoken")
+      .secret_mounts().next().unwrap();
+    assert_eq!(secret.target_path(), Some("/run/secrets/mytoken".to_string()));
+
+    let secret = run("RUN --mount=type=secret,id=a,required,mode=0440,uid=1000,gid=1001 true")
+      .secret_mounts().next().unwrap();
+    assert!(secret.required);
+    assert_eq!(secret.mode, Some(0o440));
+    assert_eq!(secret.uid, Some(1000));
+    assert_eq!(secret.gid, Some(1001));
+
+    for (value, required) in &[("true", true), ("1", true), ("TRUE", true), ("false", false), ("0", false)] {
+      let secret = run(&format!("RUN --mount=type=secret,id=a,required={} true", value))
+        .secret_mounts().next().unwrap();
+      assert_eq!(secret.required, *required, "required={}", value);
+    }
+  }
+
+  #[test]
+  fn test_tmpfs_mounts() {
+    let dockerfile = Dockerfile::parse(indoc!(r#"
+      RUN --mount=type=tmpfs,target=/tmp,size=1.5g \
+        --mount=type=tmpfs,dst=/cache \
+        --mount=type=tmpfs,target=/scratch,size=64q \
+        --mount=type=cache,target=/root/.cache make
+    "#)).unwrap();
+
+    let run = dockerfile.instructions[0].as_run().unwrap();
+    let mounts: Vec<_> = run.tmpfs_mounts().collect();
+    assert_eq!(mounts.len(), 3);
+
+    assert_eq!(mounts[0].target, Some("/tmp".to_string()));
+    assert_eq!(mounts[0].size_bytes().unwrap().unwrap(), 3 << 29);
+
+    assert_eq!(mounts[1].target, Some("/cache".to_string()));
+    assert_eq!(mounts[1].size, None);
+    assert!(mounts[1].size_bytes().is_none());
+
+    // errors point into the option's value
+    let size = mounts[2].size.as_ref().unwrap();
+    let err = mounts[2].size_bytes().unwrap().unwrap_err();
+    assert_eq!(err.offset, 2);
+    assert_eq!(&dockerfile.content[size.span.start + err.offset..size.span.end], "q");
+  }
+
+  #[test]
+  fn test_ssh_mounts() {
+    let run = run(indoc!(r#"
+      RUN --mount=type=ssh --mount=type=ssh,id=github,required=true,target=/ssh \
+        git clone git@github.com:org/repo.git
+    "#));
+
+    let mounts: Vec<_> = run.ssh_mounts().collect();
+    assert_eq!(mounts.len(), 2);
+    assert_eq!(mounts[0].id, "default");
+    assert_eq!(mounts[0].target, None);
+    assert!(!mounts[0].required);
+    assert_eq!(mounts[1].id, "github");
+    assert_eq!(mounts[1].target, Some("/ssh".to_string()));
+    assert!(mounts[1].required);
+  }
+}
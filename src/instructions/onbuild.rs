@@ -0,0 +1,189 @@
+// (C) Copyright 2026 Hewlett Packard Enterprise Development LP
+
+use std::convert::TryFrom;
+
+use pest::Parser;
+use snafu::ResultExt;
+
+use crate::Span;
+use crate::dockerfile_parser::Instruction;
+use crate::error::*;
+use crate::instructions::MiscInstruction;
+use crate::shift::ShiftSpans;
+use crate::util::*;
+use crate::parser::*;
+
+/// Parses the text of a trigger instruction on its own, with spans relative to
+/// the start of the text.
+fn parse_trigger(text: &str) -> Result<Instruction> {
+  let step = DockerfileParser::parse(Rule::onbuild_step, text)
+    .context(ParseError)?
+    .next()
+    .ok_or(Error::UnknownParseError)?;
+
+  let record = step.into_inner().next().ok_or(Error::UnknownParseError)?;
+  Instruction::try_from(record)
+}
+
+/// Wraps a trigger that can't be parsed in a `MiscInstruction`, splitting its
+/// keyword from its arguments.
+fn misc_trigger(text: &str) -> Instruction {
+  let keyword_end = text
+    .find(|c: char| !c.is_ascii_alphabetic())
+    .unwrap_or(text.len());
+
+  MiscInstruction {
+    span: Span::new(0, text.len()),
+    instruction: SpannedString {
+      span: Span::new(0, keyword_end),
+      content: text[..keyword_end].to_string(),
+      raw_text: None,
+      quoted: false,
+    },
+    arguments: BreakableString::new((keyword_end, text.len()))
+      .add_string((keyword_end, text.len()), &text[keyword_end..]),
+  }.into()
+}
+
+/// A Dockerfile [`ONBUILD` instruction][onbuild], e.g. `ONBUILD COPY . /app`.
+///
+/// The trigger instruction is parsed like any other, with spans relative to
+/// the whole Dockerfile. It only runs in downstream builds using this image as
+/// a base, so no variables in it are expanded by this build.
+///
+/// If the trigger can't be parsed, e.g. `ONBUILD SHELL sh`, it is kept as an
+/// `Instruction::Misc` rather than failing to parse the Dockerfile.
+///
+/// [onbuild]: https://docs.docker.com/engine/reference/builder/#onbuild
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct OnbuildInstruction {
+  pub span: Span,
+  pub trigger: Box<Instruction>,
+}
+
+impl OnbuildInstruction {
+  pub(crate) fn from_record(record: Pair) -> Result<OnbuildInstruction> {
+    let span = Span::from_pair(&record);
+    let field = record.into_inner().next().unwrap();
+    if field.as_rule() != Rule::onbuild_trigger {
+      return Err(unexpected_token(field));
+    }
+
+    let text = field.as_str();
+    let mut trigger = parse_trigger(text).unwrap_or_else(|_| misc_trigger(text));
+    trigger.shift_spans(field.as_span().start() as isize);
+
+    Ok(OnbuildInstruction {
+      span,
+      trigger: Box::new(trigger),
+    })
+  }
+}
+
+impl<'a> TryFrom<&'a Instruction> for &'a OnbuildInstruction {
+  type Error = Error;
+
+  fn try_from(instruction: &'a Instruction) -> std::result::Result<Self, Self::Error> {
+    if let Instruction::Onbuild(o) = instruction {
+      Ok(o)
+    } else {
+      Err(Error::ConversionError {
+        from: format!("{:?}", instruction),
+        to: "OnbuildInstruction".into()
+      })
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use indoc::indoc;
+  use pretty_assertions::assert_eq;
+
+  use super::*;
+  use crate::{Dockerfile, ExpansionBehavior};
+  use crate::test_util::*;
+
+  #[test]
+  fn onbuild_basic() -> Result<()> {
+    let onbuild = parse_single("ONBUILD COPY . /app", Rule::onbuild)?
+      .into_onbuild()
+      .unwrap();
+
+    assert_eq!(onbuild.span, Span::new(0, 19));
+    assert_eq!(
+      onbuild.trigger.as_copy().unwrap().destination,
+      SpannedString {
+        span: Span::new(15, 19),
+        content: "/app".to_string(),
+        raw_text: None,
+        quoted: false,
+      }
+    );
+    assert_eq!(onbuild.trigger.span(), Span::new(8, 19));
+
+    Ok(())
+  }
+
+  #[test]
+  fn onbuild_nested() -> Result<()> {
+    let dockerfile = Dockerfile::parse(indoc!(r#"
+      FROM alpine:3.19
+      ONBUILD RUN ["make", "install"]
+      onbuild COPY --from=builder \
+        # the build output
+        /out /app
+      ONBUILD  healthcheck --interval=5s CMD true
+    "#))?;
+
+    let run = dockerfile.instructions[1].as_onbuild().unwrap().trigger.as_run().unwrap();
+    assert_eq!(run.as_exec().unwrap().as_str_vec(), vec!["make", "install"]);
+    assert_eq!(dockerfile.text(&run.span), r#"RUN ["make", "install"]"#);
+
+    let onbuild = dockerfile.instructions[2].as_onbuild().unwrap();
+    let copy = onbuild.trigger.as_copy().unwrap();
+    assert_eq!(copy.flags[0].name.content, "from");
+    assert_eq!(dockerfile.text(&copy.flags[0].value.span), "builder");
+    assert_eq!(dockerfile.text(&copy.destination.span), "/app");
+    assert!(dockerfile.text(&onbuild.span).ends_with("/out /app"));
+
+    let healthcheck = dockerfile.instructions[3].as_onbuild().unwrap().trigger.as_healthcheck().unwrap();
+    assert_eq!(dockerfile.text(&healthcheck.flags[0].value.span), "5s");
+
+    Ok(())
+  }
+
+  #[test]
+  fn onbuild_variables() -> Result<()> {
+    let dockerfile = Dockerfile::parse("FROM alpine\nONBUILD COPY $src /app\n")?;
+    let onbuild = &dockerfile.instructions[1];
+
+    // references are only expanded by downstream builds
+    assert_eq!(onbuild.variable_references(), vec![]);
+
+    let references = onbuild.all_variable_references();
+    assert_eq!(references.len(), 1);
+    assert_eq!(references[0].name, "src");
+    assert_eq!(references[0].expansion, ExpansionBehavior::NoExpansion);
+    assert_eq!(dockerfile.text(&references[0].span), "$src");
+
+    Ok(())
+  }
+
+  #[test]
+  fn onbuild_invalid_trigger() -> Result<()> {
+    let dockerfile = Dockerfile::parse("FROM alpine\nONBUILD SHELL sh -c\nONBUILD RUN\n")?;
+
+    let misc = dockerfile.instructions[1].as_onbuild().unwrap().trigger.as_misc().unwrap();
+    assert_eq!(misc.instruction.content, "SHELL");
+    assert_eq!(dockerfile.text(&misc.instruction.span), "SHELL");
+    assert_eq!(misc.arguments.to_string(), " sh -c");
+    assert_eq!(dockerfile.text(&misc.span), "SHELL sh -c");
+
+    let misc = dockerfile.instructions[2].as_onbuild().unwrap().trigger.as_misc().unwrap();
+    assert_eq!(misc.instruction.content, "RUN");
+    assert_eq!(misc.arguments.to_string(), "");
+
+    Ok(())
+  }
+}
@@ -5,9 +5,157 @@ use std::convert::TryFrom;
 use crate::Span;
 use crate::dockerfile_parser::Instruction;
 use crate::error::*;
+use crate::heredoc::{Heredoc, ShellToken, tokenize};
+use crate::instructions::{Mount, SecretMount, SshMount, TmpfsMount};
 use crate::util::*;
 use crate::parser::*;
 
+/// A key/value pair passed to a `RUN` instruction as a flag.
+///
+/// Examples include: `RUN --mount=type=cache,target=/root/.cache make`
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct RunFlag {
+  pub span: Span,
+  pub name: SpannedString,
+  pub value: SpannedString,
+}
+
+impl RunFlag {
+  fn from_record(record: Pair) -> Result<RunFlag> {
+    let span = Span::from_pair(&record);
+    let mut name = None;
+    let mut value = None;
+
+    for field in record.into_inner() {
+      match field.as_rule() {
+        Rule::run_flag_name => name = Some(parse_string(&field)?),
+        Rule::run_flag_value => value = Some(parse_string(&field)?),
+        _ => return Err(unexpected_token(field))
+      }
+    }
+
+    let name = name.ok_or_else(|| Error::GenericParseError {
+      message: "run flags require a key".into(),
+    })?;
+
+    let value = value.ok_or_else(|| Error::GenericParseError {
+      message: "run flags require a value".into()
+    })?;
+
+    Ok(RunFlag {
+      span, name, value
+    })
+  }
+
+  /// Returns the value of this flag, interpreting boolean-style values.
+  pub fn flag_value(&self) -> FlagValue {
+    FlagValue::of(self.span, &self.name, &self.value)
+  }
+}
+
+/// The destination of a heredoc, as determined by
+/// `RunInstruction::heredoc_file_writes()`.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum HeredocTarget {
+  /// The heredoc is written to the file at `path`, either truncating it (`>`)
+  /// or appending to it (`>>`)
+  File {
+    path: SpannedString,
+    append: bool,
+  },
+
+  /// The heredoc's destination could not be determined, e.g. because it is
+  /// the script to run or is passed to a command other than `cat`
+  Unknown,
+}
+
+/// A heredoc of a `RUN` instruction paired with the file it is written to.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct HeredocFileWrite<'a> {
+  pub heredoc: &'a Heredoc,
+  pub target: HeredocTarget,
+}
+
+/// A heredoc of a `RUN` instruction paired with the command it is passed to,
+/// as returned by `RunInstruction::heredoc_commands()`.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct HeredocCommand<'a> {
+  pub heredoc: &'a Heredoc,
+
+  /// The words of the simple command containing the heredoc marker, excluding
+  /// redirections, e.g. `python3` for `RUN <<EOF python3`. Empty if the
+  /// heredoc is itself the script to run, e.g. `RUN <<EOF`.
+  pub command: Vec<SpannedString>,
+}
+
+impl<'a> HeredocCommand<'a> {
+  /// Returns the program that will read the heredoc: the command's first word
+  /// if there is a command, otherwise the interpreter named by the body's
+  /// shebang, if any. Returns None if the body is run by the default shell.
+  pub fn interpreter(&self) -> Option<SpannedString> {
+    match self.command.first() {
+      Some(word) => Some(word.clone()),
+      None => self.heredoc.interpreter(),
+    }
+  }
+}
+
+/// Returns the words of a simple command, skipping heredoc markers and
+/// redirections along with their targets.
+fn command_words(command: &[ShellToken]) -> Vec<SpannedString> {
+  let mut words = Vec::new();
+
+  let mut tokens = command.iter();
+  while let Some(token) = tokens.next() {
+    match token {
+      ShellToken::Word(word) => words.push(word.clone()),
+      ShellToken::Heredoc(..) => (),
+      ShellToken::Operator(_) => {
+        tokens.next();
+      },
+    }
+  }
+
+  words
+}
+
+/// Finds the file written by a simple command of the form `cat <<EOF >path`
+/// (in any order), if any.
+fn cat_redirect_target(command: &[ShellToken]) -> Option<HeredocTarget> {
+  let mut words = Vec::new();
+  let mut heredocs = 0;
+  let mut target = None;
+
+  let mut tokens = command.iter();
+  while let Some(token) = tokens.next() {
+    match token {
+      ShellToken::Word(word) => words.push(word.content.as_str()),
+      ShellToken::Heredoc(..) => heredocs += 1,
+      ShellToken::Operator(op) => {
+        let append = match op.content.as_str() {
+          ">" => false,
+          ">>" => true,
+          _ => return None
+        };
+
+        match (&target, tokens.next()) {
+          (None, Some(ShellToken::Word(path))) => target = Some(HeredocTarget::File {
+            path: path.clone(),
+            append,
+          }),
+          _ => return None
+        }
+      }
+    }
+  }
+
+  if heredocs == 1 && words == ["cat"] {
+    target
+  } else {
+    None
+  }
+}
+
 /// A Dockerfile [`RUN` instruction][run].
 ///
 /// An run command may be defined as either a single string (to be run in the
@@ -17,25 +165,161 @@ use crate::parser::*;
 #[derive(Debug, PartialEq, Eq, Clone)]
 pub struct RunInstruction {
   pub span: Span,
+  pub flags: Vec<RunFlag>,
   pub expr: ShellOrExecExpr,
+
+  /// Heredocs passed to the command, e.g. `RUN <<EOF`, in order
+  pub heredocs: Vec<Heredoc>,
 }
 
 impl RunInstruction {
   pub(crate) fn from_record(record: Pair) -> Result<RunInstruction> {
     let span = Span::from_pair(&record);
-    let field = record.into_inner().next().unwrap();
-
-    match field.as_rule() {
-      Rule::run_exec => Ok(RunInstruction {
-        span,
-        expr: ShellOrExecExpr::Exec(parse_string_array(field)?),
-      }),
-      Rule::run_shell => Ok(RunInstruction {
-        span,
-        expr: ShellOrExecExpr::Shell(parse_any_breakable(field)?),
-      }),
-      _ => Err(unexpected_token(field)),
+    let mut flags = ShortList::new();
+
+    for field in record.into_inner() {
+      match field.as_rule() {
+        Rule::run_flag => flags.push(RunFlag::from_record(field)?),
+        Rule::run_exec => return Ok(RunInstruction {
+          span,
+          flags: flags.into_vec(),
+          expr: ShellOrExecExpr::Exec(parse_string_array(field)?),
+          heredocs: Vec::new(),
+        }),
+        Rule::run_shell => return Ok(RunInstruction {
+          span,
+          flags: flags.into_vec(),
+          expr: ShellOrExecExpr::Shell(parse_any_breakable(field)?),
+          heredocs: Vec::new(),
+        }),
+        _ => return Err(unexpected_token(field)),
+      }
     }
+
+    Err(Error::GenericParseError {
+      message: "run requires a command".into()
+    })
+  }
+
+  /// Returns all flags whose names are not in `known`, in order, comparing
+  /// names case-insensitively.
+  ///
+  /// This allows tolerating flags added by newer versions of Docker while
+  /// still catching typos, e.g. `--mnt`.
+  pub fn unknown_flags(&self, known: &[&str]) -> Vec<&RunFlag> {
+    self.flags
+      .iter()
+      .filter(|f| !known.iter().any(|k| f.name.content.eq_ignore_ascii_case(k)))
+      .collect()
+  }
+
+  /// Returns all `--mount` flags of this instruction, parsed as mounts.
+  pub fn mounts(&self) -> Vec<Mount> {
+    self.flags
+      .iter()
+      .filter(|f| f.name.content.eq_ignore_ascii_case("mount"))
+      .map(|f| Mount::parse(&f.value))
+      .collect()
+  }
+
+  /// Returns an iterator over all secret (`--mount=type=secret`) mounts.
+  pub fn secret_mounts(&self) -> impl Iterator<Item = SecretMount> {
+    self.mounts().into_iter().filter_map(|m| m.as_secret())
+  }
+
+  /// Returns an iterator over all SSH agent (`--mount=type=ssh`) mounts.
+  pub fn ssh_mounts(&self) -> impl Iterator<Item = SshMount> {
+    self.mounts().into_iter().filter_map(|m| m.as_ssh())
+  }
+
+  /// Returns an iterator over all in-memory (`--mount=type=tmpfs`) mounts.
+  pub fn tmpfs_mounts(&self) -> impl Iterator<Item = TmpfsMount> {
+    self.mounts().into_iter().filter_map(|m| m.as_tmpfs())
+  }
+
+  /// Splits the command of a shell-form instruction into words and operators,
+  /// skipping comments. Returns nothing for exec-form instructions.
+  pub(crate) fn shell_tokens(&self) -> Vec<ShellToken> {
+    self.as_shell()
+      .map(|shell| shell.components.iter().filter_map(|c| match c {
+        BreakableStringComponent::String(s) => Some(tokenize(&s.content, s.span.start)),
+        BreakableStringComponent::Comment(_) => None,
+      }).flatten().collect())
+      .unwrap_or_default()
+  }
+
+  /// Pairs each heredoc with the file it is written to, if it is redirected
+  /// to a file by `cat` in the surrounding shell text, e.g.
+  /// `RUN cat <<EOF > /etc/nginx/nginx.conf`.
+  ///
+  /// Only plain `>` and `>>` redirections in the same simple command as the
+  /// heredoc marker are detected. All other heredocs, including those of
+  /// exec-form instructions, have an `Unknown` target.
+  ///
+  /// ```
+  /// use dockerfile_parser::{Dockerfile, HeredocTarget};
+  ///
+  /// let dockerfile = Dockerfile::parse(
+  ///   "FROM alpine\nRUN cat <<EOF >> /etc/hosts\n10.0.0.1 db\nEOF\n"
+  /// ).unwrap();
+  ///
+  /// let run = dockerfile.instructions[1].as_run().unwrap();
+  /// let writes = run.heredoc_file_writes();
+  /// assert_eq!(writes[0].heredoc.body.content, "10.0.0.1 db\n");
+  /// assert!(matches!(
+  ///   &writes[0].target,
+  ///   HeredocTarget::File { path, append: true } if path.content == "/etc/hosts"
+  /// ));
+  /// ```
+  pub fn heredoc_file_writes(&self) -> Vec<HeredocFileWrite<'_>> {
+    let tokens = self.shell_tokens();
+    let commands: Vec<&[ShellToken]> = tokens.split(|t| t.is_separator()).collect();
+
+    self.heredocs
+      .iter()
+      .map(|heredoc| HeredocFileWrite {
+        heredoc,
+        target: commands
+          .iter()
+          .find(|command| command.iter().any(|t| t.span() == heredoc.marker))
+          .and_then(|command| cat_redirect_target(command))
+          .unwrap_or(HeredocTarget::Unknown),
+      })
+      .collect()
+  }
+
+  /// Pairs each heredoc with the command it is passed to, e.g. `python3` for
+  /// `RUN <<EOF python3`, kept separately from the heredoc's body.
+  ///
+  /// Heredocs of exec-form instructions have an empty command.
+  ///
+  /// ```
+  /// use dockerfile_parser::Dockerfile;
+  ///
+  /// let dockerfile = Dockerfile::parse(
+  ///   "FROM python:3\nRUN <<EOF python3 -u\nprint('hi')\nEOF\n"
+  /// ).unwrap();
+  ///
+  /// let run = dockerfile.instructions[1].as_run().unwrap();
+  /// let commands = run.heredoc_commands();
+  /// assert_eq!(commands[0].command.iter().map(|w| w.content.as_str()).collect::<Vec<_>>(), ["python3", "-u"]);
+  /// assert_eq!(commands[0].heredoc.body.content, "print('hi')\n");
+  /// ```
+  pub fn heredoc_commands(&self) -> Vec<HeredocCommand<'_>> {
+    let tokens = self.shell_tokens();
+    let commands: Vec<&[ShellToken]> = tokens.split(|t| t.is_separator()).collect();
+
+    self.heredocs
+      .iter()
+      .map(|heredoc| HeredocCommand {
+        heredoc,
+        command: commands
+          .iter()
+          .find(|command| command.iter().any(|t| t.span() == heredoc.marker))
+          .map(|command| command_words(command))
+          .unwrap_or_default(),
+      })
+      .collect()
   }
 
   /// Unpacks this instruction into its inner value if it is a Shell-form
@@ -84,7 +368,7 @@ mod tests {
   use pretty_assertions::assert_eq;
 
   use super::*;
-  use crate::Span;
+  use crate::{Dockerfile, ExpansionBehavior, Span};
   use crate::test_util::*;
 
   #[test]
@@ -101,16 +385,22 @@ mod tests {
       parse_single(r#"run ["echo", "hello world"]"#, Rule::run)?,
       RunInstruction {
         span: Span::new(0, 27),
+        flags: vec![],
         expr: ShellOrExecExpr::Exec(StringArray {
           span: Span::new(4, 27),
           elements: vec![SpannedString {
             span: Span::new(5, 11),
             content: "echo".to_string(),
+            raw_text: None,
+            quoted: true,
           }, SpannedString {
             span: Span::new(13, 26),
             content: "hello world".to_string(),
+            raw_text: None,
+            quoted: true,
           }]
         }),
+        heredocs: vec![],
       }.into()
     );
 
@@ -181,26 +471,25 @@ mod tests {
     Ok(())
   }
 
+  // note: the trailing `\` at the end is _almost_ nonsense and generates a
+  // warning from docker
+  const LARGE_RUN: &str = indoc!(r#"
+    run set -x && \
+        # lorem ipsum
+        echo "hello world" && \
+        # dolor sit amet,
+        # consectetur \
+        # adipiscing elit, \
+        # sed do eiusmod
+        # tempor incididunt ut labore
+        echo foo && \
+        echo 'bar' \
+        && echo baz \
+        # et dolore magna aliqua."#);
+
   #[test]
   fn run_multiline_shell_large() -> Result<()> {
-    // note: the trailing `\` at the end is _almost_ nonsense and generates a
-    // warning from docker
-    let ins = parse_single(
-      indoc!(r#"
-        run set -x && \
-            # lorem ipsum
-            echo "hello world" && \
-            # dolor sit amet,
-            # consectetur \
-            # adipiscing elit, \
-            # sed do eiusmod
-            # tempor incididunt ut labore
-            echo foo && \
-            echo 'bar' \
-            && echo baz \
-            # et dolore magna aliqua."#),
-      Rule::run
-    )?.into_run().unwrap().into_shell().unwrap();
+    let ins = parse_single(LARGE_RUN, Rule::run)?.into_run().unwrap().into_shell().unwrap();
 
     assert_eq!(
       ins,
@@ -227,6 +516,43 @@ mod tests {
     Ok(())
   }
 
+  #[test]
+  fn run_multiline_shell_large_continuations() -> Result<()> {
+    let d = Dockerfile::parse(LARGE_RUN)?;
+    let ins = d.instructions[0].as_run().unwrap().as_shell().unwrap();
+
+    let continuations = ins.continuation_spans(&d);
+    assert_eq!(continuations.len(), 5);
+    assert!(continuations.iter().all(|s| d.text(s) == "\\\n"));
+
+    // every gap between components is either a continuation (possibly
+    // followed by a comment's indentation) or a comment's line break
+    for pair in ins.components.windows(2) {
+      let gap = Span::new(pair[0].span().end, pair[1].span().start);
+      match continuations.iter().find(|s| s.start == gap.start) {
+        Some(continuation) => {
+          assert!(matches!(pair[0], BreakableStringComponent::String(_)));
+          assert!(d.text(&Span::new(continuation.end, gap.end)).trim().is_empty());
+        },
+        None => {
+          assert!(matches!(pair[0], BreakableStringComponent::Comment(_)));
+          assert!(d.text(&gap).starts_with('\n'));
+          assert!(d.text(&gap).trim().is_empty());
+        }
+      }
+    }
+
+    let lines = ins.full_span_lines(&d);
+    assert_eq!(lines.len(), 12);
+    assert_eq!(d.text(&lines[0]), "set -x && \\");
+    assert_eq!(d.text(&lines[4]), "    # consectetur \\");
+    assert_eq!(d.text(&lines[11]), "    # et dolore magna aliqua.");
+    assert_eq!(lines.first().unwrap().start, ins.span.start);
+    assert_eq!(lines.last().unwrap().end, ins.span.end);
+
+    Ok(())
+  }
+
   #[test]
   fn run_multline_exec() -> Result<()> {
     assert_eq!(
@@ -237,16 +563,22 @@ mod tests {
         ]"#, Rule::run)?,
       RunInstruction {
         span: Span::new(0, 66),
+        flags: vec![],
         expr: ShellOrExecExpr::Exec(StringArray {
           span: Span::new(13, 66),
           elements: vec![SpannedString {
             span: Span::new(24, 30),
             content: "echo".to_string(),
+            raw_text: None,
+            quoted: true,
           }, SpannedString {
             span: Span::new(42, 55),
             content: "hello world".to_string(),
+            raw_text: None,
+            quoted: true,
           }],
         }),
+        heredocs: vec![],
       }.into()
     );
 
@@ -263,19 +595,222 @@ mod tests {
         ]"#, Rule::run)?,
       RunInstruction {
         span: Span::new(0, 66),
+        flags: vec![],
         expr: ShellOrExecExpr::Exec(StringArray {
           span: Span::new(13, 66),
           elements: vec![SpannedString {
             span: Span::new(24, 30),
             content: "echo".to_string(),
+            raw_text: None,
+            quoted: true,
           }, SpannedString {
             span: Span::new(42, 55),
             content: "hello world".to_string(),
+            raw_text: None,
+            quoted: true,
           }],
-        })
+        }),
+        heredocs: vec![],
       }.into()
     );
 
     Ok(())
   }
+
+  #[test]
+  fn run_unknown_flags() -> Result<()> {
+    let run = parse_single(
+      "run --mount=type=cache,target=/a --network=none --mnt=type=tmpfs --mount=type=ssh make",
+      Rule::run
+    )?.into_run().unwrap();
+
+    let unknown = run.unknown_flags(&["mount", "network", "security"]);
+    assert_eq!(unknown.len(), 1);
+    assert_eq!(unknown[0].name.content, "mnt");
+    assert_eq!(unknown[0].value.content, "type=tmpfs");
+
+    Ok(())
+  }
+
+  #[test]
+  fn run_heredoc_file_writes() -> Result<()> {
+    let dockerfile = Dockerfile::parse(indoc!(r#"
+      FROM nginx:1.25
+      RUN cat <<EOF > /etc/nginx/conf.d/default.conf
+      server {
+          listen 80;
+          location / {
+              proxy_pass http://app:8080;
+          }
+      }
+      EOF
+      RUN <<EOF cat >> /etc/hosts && \
+        cat >"/docker-entrypoint.sh" <<SCRIPT && chmod +x /docker-entrypoint.sh
+      10.0.0.1 app
+      EOF
+      #!/bin/sh
+      set -e
+      exec "$@"
+      SCRIPT
+      CMD ["nginx", "-g", "daemon off;"]
+    "#))?;
+
+    assert_eq!(dockerfile.instructions.len(), 4);
+
+    let nginx = dockerfile.instructions[1].as_run().unwrap();
+    assert_eq!(nginx.span.end, dockerfile.content.find("EOF\nRUN").unwrap() + 3);
+
+    let writes = nginx.heredoc_file_writes();
+    assert_eq!(writes.len(), 1);
+    assert_eq!(writes[0].target, HeredocTarget::File {
+      path: SpannedString {
+        span: Span::new(32, 62),
+        content: "/etc/nginx/conf.d/default.conf".into(),
+        raw_text: None,
+        quoted: false,
+      },
+      append: false,
+    });
+    assert!(writes[0].heredoc.body.content.starts_with("server {\n    listen 80;"));
+    assert!(writes[0].heredoc.body.content.ends_with("    }\n}\n"));
+
+    let entrypoint = dockerfile.instructions[2].as_run().unwrap();
+    let writes = entrypoint.heredoc_file_writes();
+    assert_eq!(writes.len(), 2);
+    assert_eq!(writes[0].heredoc.body.content, "10.0.0.1 app\n");
+    assert!(matches!(
+      &writes[0].target,
+      HeredocTarget::File { path, append: true } if path.content == "/etc/hosts"
+    ));
+    assert_eq!(writes[1].heredoc.delimiter, "SCRIPT");
+    assert_eq!(writes[1].heredoc.body.content, "#!/bin/sh\nset -e\nexec \"$@\"\n");
+
+    let path = match &writes[1].target {
+      HeredocTarget::File { path, append: false } => path,
+      target => panic!("unexpected target: {:?}", target)
+    };
+    assert_eq!(path.content, "/docker-entrypoint.sh");
+    assert_eq!(dockerfile.text(&path.span), "\"/docker-entrypoint.sh\"");
+
+    Ok(())
+  }
+
+  #[test]
+  fn run_heredoc_commands() -> Result<()> {
+    let dockerfile = Dockerfile::parse(indoc!(r#"
+      FROM python:3.12
+      RUN <<EOF python3
+      print("hello")
+      EOF
+      RUN <<EOF
+      #!/usr/bin/env python3
+      print("hello")
+      EOF
+      RUN <<EOF
+      apt-get update
+      EOF
+      RUN cat <<EOF > /tmp/a && sh -e
+      echo a
+      EOF
+    "#))?;
+
+    let words = |c: &HeredocCommand| c.command.iter().map(|w| w.content.clone()).collect::<Vec<_>>();
+
+    let run = dockerfile.instructions[1].as_run().unwrap();
+    let commands = run.heredoc_commands();
+    assert_eq!(commands.len(), 1);
+    assert_eq!(words(&commands[0]), ["python3"]);
+    assert_eq!(dockerfile.text(&commands[0].command[0].span), "python3");
+    assert_eq!(commands[0].heredoc.body.content, "print(\"hello\")\n");
+    assert_eq!(commands[0].interpreter().unwrap().content, "python3");
+
+    let run = dockerfile.instructions[2].as_run().unwrap();
+    let commands = run.heredoc_commands();
+    assert!(commands[0].command.is_empty());
+    assert_eq!(commands[0].interpreter().unwrap().content, "python3");
+
+    let run = dockerfile.instructions[3].as_run().unwrap();
+    let commands = run.heredoc_commands();
+    assert!(commands[0].command.is_empty());
+    assert_eq!(commands[0].interpreter(), None);
+
+    // redirections are skipped and other commands are ignored
+    let run = dockerfile.instructions[4].as_run().unwrap();
+    assert_eq!(words(&run.heredoc_commands()[0]), ["cat"]);
+
+    Ok(())
+  }
+
+  #[test]
+  fn run_heredoc_quoted_delimiters() -> Result<()> {
+    let dockerfile = Dockerfile::parse(indoc!(r#"
+      FROM alpine
+      RUN <<"EOF" cat >/a.sh && cat <<END >/b.txt && cat <<\RAW >/c.txt
+      echo "$HOME"
+      EOF
+      home is $HOME
+      END
+      ${LITERAL}
+      RAW
+      USER app
+    "#))?;
+
+    assert_eq!(dockerfile.instructions.len(), 3);
+    let ins = &dockerfile.instructions[1];
+    let run = ins.as_run().unwrap();
+
+    // closing delimiters are matched without their quotes
+    let heredocs: Vec<(&str, bool, &str)> = run.heredocs
+      .iter()
+      .map(|h| (h.delimiter.as_str(), h.expand, dockerfile.text(&h.terminator)))
+      .collect();
+    assert_eq!(heredocs, vec![("EOF", false, "EOF"), ("END", true, "END"), ("RAW", false, "RAW")]);
+    assert_eq!(dockerfile.text(&run.heredocs[0].marker), "<<\"EOF\"");
+    assert_eq!(dockerfile.text(&run.heredocs[2].marker), "<<\\RAW");
+    assert_eq!(run.heredocs[2].body.content, "${LITERAL}\n");
+
+    // only the unquoted heredoc's body is expanded
+    let refs: Vec<(&str, ExpansionBehavior)> = ins.variable_references()
+      .iter()
+      .map(|r| (dockerfile.text(&r.span), r.expansion))
+      .collect();
+    assert_eq!(refs, vec![("$HOME", ExpansionBehavior::ShellExpands)]);
+    assert!(dockerfile.text(&run.heredocs[1].body.span).contains(refs[0].0));
+
+    let refs: Vec<(&str, ExpansionBehavior)> = ins.all_variable_references()
+      .iter()
+      .map(|r| (dockerfile.text(&r.span), r.expansion))
+      .collect();
+    assert_eq!(refs, vec![
+      ("$HOME", ExpansionBehavior::NoExpansion),
+      ("$HOME", ExpansionBehavior::ShellExpands),
+      ("${LITERAL}", ExpansionBehavior::NoExpansion),
+    ]);
+
+    Ok(())
+  }
+
+  #[test]
+  fn run_heredoc_unknown_targets() -> Result<()> {
+    let dockerfile = Dockerfile::parse(indoc!(r#"
+      FROM alpine
+      RUN <<EOF
+      apk add --no-cache curl
+      EOF
+      RUN python3 <<EOF > /tmp/out.txt
+      print("hello")
+      EOF
+      RUN cat <<EOF | tee /etc/motd
+      hello
+      EOF
+    "#))?;
+
+    for ins in &dockerfile.instructions[1..] {
+      let run = ins.as_run().unwrap();
+      assert_eq!(run.heredocs.len(), 1);
+      assert_eq!(run.heredoc_file_writes()[0].target, HeredocTarget::Unknown);
+    }
+
+    Ok(())
+  }
 }
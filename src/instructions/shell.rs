@@ -0,0 +1,134 @@
+// (C) Copyright 2026 Hewlett Packard Enterprise Development LP
+
+use std::convert::TryFrom;
+
+use crate::Span;
+use crate::dockerfile_parser::Instruction;
+use crate::error::*;
+use crate::util::*;
+use crate::parser::*;
+
+/// A Dockerfile [`SHELL` instruction][shell], e.g.
+/// `SHELL ["powershell", "-Command"]`.
+///
+/// Like Docker, only the JSON array form is accepted; any other arguments
+/// produce a parse error.
+///
+/// [shell]: https://docs.docker.com/engine/reference/builder/#shell
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct ShellInstruction {
+  pub span: Span,
+
+  /// The shell executable and its arguments
+  pub shell: StringArray,
+}
+
+impl ShellInstruction {
+  pub(crate) fn from_record(record: Pair) -> Result<ShellInstruction> {
+    let span = Span::from_pair(&record);
+    let field = record.into_inner().next().unwrap();
+
+    match field.as_rule() {
+      Rule::shell_exec => Ok(ShellInstruction {
+        span,
+        shell: parse_string_array(field)?,
+      }),
+      Rule::shell_invalid => Err(Error::GenericParseError {
+        message: format!(
+          "SHELL requires the arguments to be in JSON array form, e.g. \
+          [\"/bin/sh\", \"-c\"], not: {}",
+          field.as_str().trim()
+        )
+      }),
+      _ => Err(unexpected_token(field)),
+    }
+  }
+}
+
+impl<'a> TryFrom<&'a Instruction> for &'a ShellInstruction {
+  type Error = Error;
+
+  fn try_from(instruction: &'a Instruction) -> std::result::Result<Self, Self::Error> {
+    if let Instruction::Shell(s) = instruction {
+      Ok(s)
+    } else {
+      Err(Error::ConversionError {
+        from: format!("{:?}", instruction),
+        to: "ShellInstruction".into()
+      })
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use indoc::indoc;
+  use pretty_assertions::assert_eq;
+
+  use super::*;
+  use crate::Dockerfile;
+  use crate::test_util::*;
+
+  #[test]
+  fn shell_basic() -> Result<()> {
+    assert_eq!(
+      parse_single(r#"SHELL ["powershell", "-Command"]"#, Rule::shell)?,
+      ShellInstruction {
+        span: Span::new(0, 32),
+        shell: StringArray {
+          span: Span::new(6, 32),
+          elements: vec![
+            SpannedString {
+              span: Span::new(7, 19),
+              content: "powershell".to_string(),
+              raw_text: None,
+              quoted: true
+            },
+            SpannedString {
+              span: Span::new(21, 31),
+              content: "-Command".to_string(),
+              raw_text: None,
+              quoted: true
+            },
+          ],
+        },
+      }.into()
+    );
+
+    Ok(())
+  }
+
+  #[test]
+  fn shell_multiline() -> Result<()> {
+    let dockerfile = Dockerfile::parse(indoc!(r#"
+      FROM mcr.microsoft.com/windows/servercore:ltsc2022
+      shell [ \
+        # use powershell for all RUNs
+        "powershell", \
+        "-Command", "$ErrorActionPreference = 'Stop';" \
+      ]
+      RUN Write-Host hello
+    "#))?;
+
+    let shell = dockerfile.instructions[1].as_shell().unwrap();
+    assert_eq!(
+      shell.shell.as_str_vec(),
+      vec!["powershell", "-Command", "$ErrorActionPreference = 'Stop';"]
+    );
+    assert!(dockerfile.text(&shell.span).ends_with(']'));
+
+    Ok(())
+  }
+
+  #[test]
+  fn shell_invalid() {
+    for invalid in &["SHELL powershell -Command", "SHELL ['/bin/sh', '-c']", "SHELL [\"sh\"] -c"] {
+      match Dockerfile::parse(invalid) {
+        Err(Error::GenericParseError { message }) => {
+          assert!(message.starts_with("SHELL requires the arguments to be in JSON array form"));
+        },
+        other => panic!("expected a parse error for {}, got {:?}", invalid, other)
+      }
+    }
+  }
+}
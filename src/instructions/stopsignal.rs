@@ -0,0 +1,213 @@
+// (C) Copyright 2026 Hewlett Packard Enterprise Development LP
+
+use std::convert::TryFrom;
+
+use crate::Span;
+use crate::dockerfile_parser::Instruction;
+use crate::error::*;
+use crate::util::*;
+use crate::parser::*;
+
+/// The largest valid signal number; Linux defines signals 1 through 64.
+pub const MAX_SIGNAL: u64 = 64;
+
+/// Signal names accepted by Docker, without the `SIG` prefix. Real-time
+/// signals may also be given relative to `RTMIN` or `RTMAX`, e.g. `RTMIN+3`.
+const SIGNAL_NAMES: &[&str] = &[
+  "ABRT", "ALRM", "BUS", "CHLD", "CLD", "CONT", "FPE", "HUP", "ILL", "INT",
+  "IO", "IOT", "KILL", "PIPE", "POLL", "PROF", "PWR", "QUIT", "SEGV", "STKFLT",
+  "STOP", "SYS", "TERM", "TRAP", "TSTP", "TTIN", "TTOU", "URG", "USR1", "USR2",
+  "VTALRM", "WINCH", "XCPU", "XFSZ", "RTMIN", "RTMAX",
+];
+
+/// Determines if the given real-time signal offset, e.g. `3` in `RTMIN+3`, is
+/// valid. There are 30 real-time signals between `RTMIN` and `RTMAX`.
+fn is_realtime_offset(offset: &str) -> bool {
+  !offset.starts_with('0')
+    && offset.parse::<u8>().map(|n| (1..=15).contains(&n)).unwrap_or(false)
+}
+
+/// A parsed `STOPSIGNAL` signal, as returned by
+/// `StopsignalInstruction::parsed_signal()`.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum Signal {
+  /// A signal number, e.g. `9`. Numbers too large to represent saturate at
+  /// `u64::MAX`.
+  Number(u64),
+
+  /// A signal name, uppercased and with a `SIG` prefix, e.g. `SIGTERM` for
+  /// `STOPSIGNAL term`
+  Name(String),
+}
+
+impl Signal {
+  /// Parses a signal number or name. Like Docker, names are case-insensitive
+  /// and the `SIG` prefix is optional.
+  ///
+  /// Returns `None` if the signal is empty or contains characters that can't
+  /// appear in a signal name.
+  pub fn parse(s: &str) -> Option<Signal> {
+    if !s.is_empty() && s.chars().all(|c| c.is_ascii_digit()) {
+      return Some(Signal::Number(s.parse().unwrap_or(u64::MAX)));
+    }
+
+    let name = s.to_ascii_uppercase();
+    let name = name.strip_prefix("SIG").unwrap_or(&name);
+    if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '+' || c == '-') {
+      return None;
+    }
+
+    Some(Signal::Name(format!("SIG{}", name)))
+  }
+
+  /// Determines if this signal is valid: a number from 1 to `MAX_SIGNAL`, or
+  /// a signal name known to Docker.
+  pub fn is_valid(&self) -> bool {
+    match self {
+      Signal::Number(n) => (1..=MAX_SIGNAL).contains(n),
+      Signal::Name(name) => {
+        let name = &name[3..];
+        if SIGNAL_NAMES.contains(&name) {
+          return true;
+        }
+
+        match (name.strip_prefix("RTMIN+"), name.strip_prefix("RTMAX-")) {
+          (Some(offset), _) | (_, Some(offset)) => is_realtime_offset(offset),
+          _ => false
+        }
+      }
+    }
+  }
+}
+
+/// A Dockerfile [`STOPSIGNAL` instruction][stopsignal], e.g.
+/// `STOPSIGNAL SIGTERM` or `STOPSIGNAL 9`.
+///
+/// The signal is kept as written, as it may be a variable reference like
+/// `STOPSIGNAL $SIG`. Invalid signals aren't rejected when parsing, but are
+/// reported by `Dockerfile::validate()`.
+///
+/// [stopsignal]: https://docs.docker.com/engine/reference/builder/#stopsignal
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct StopsignalInstruction {
+  pub span: Span,
+  pub signal: SpannedString,
+}
+
+impl StopsignalInstruction {
+  pub(crate) fn from_record(record: Pair) -> Result<StopsignalInstruction> {
+    let span = Span::from_pair(&record);
+    let field = record.into_inner().next().unwrap();
+
+    match field.as_rule() {
+      Rule::stopsignal_signal => Ok(StopsignalInstruction {
+        span,
+        signal: parse_string(&field)?,
+      }),
+      _ => Err(unexpected_token(field))
+    }
+  }
+
+  /// Parses the signal into a number or name. Returns `None` if it contains a
+  /// variable reference, which can only be resolved at build time, or can't
+  /// be a signal at all.
+  pub fn parsed_signal(&self) -> Option<Signal> {
+    if self.signal.content.contains('$') {
+      return None;
+    }
+
+    Signal::parse(&self.signal.content)
+  }
+}
+
+impl<'a> TryFrom<&'a Instruction> for &'a StopsignalInstruction {
+  type Error = Error;
+
+  fn try_from(instruction: &'a Instruction) -> std::result::Result<Self, Self::Error> {
+    if let Instruction::Stopsignal(s) = instruction {
+      Ok(s)
+    } else {
+      Err(Error::ConversionError {
+        from: format!("{:?}", instruction),
+        to: "StopsignalInstruction".into()
+      })
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use pretty_assertions::assert_eq;
+
+  use super::*;
+  use crate::Dockerfile;
+  use crate::test_util::*;
+
+  #[test]
+  fn stopsignal_basic() -> Result<()> {
+    assert_eq!(
+      parse_single("STOPSIGNAL SIGTERM", Rule::stopsignal)?,
+      StopsignalInstruction {
+        span: Span::new(0, 18),
+        signal: SpannedString {
+          span: Span::new(11, 18),
+          content: "SIGTERM".to_string(),
+          raw_text: None,
+          quoted: false
+        },
+      }.into()
+    );
+
+    let stopsignal = parse_single("stopsignal 9", Rule::stopsignal)?
+      .into_stopsignal()
+      .unwrap();
+    assert_eq!(stopsignal.signal.span, Span::new(11, 12));
+    assert_eq!(stopsignal.parsed_signal(), Some(Signal::Number(9)));
+
+    Ok(())
+  }
+
+  #[test]
+  fn stopsignal_parsed() {
+    let parsed = |s: &str| Signal::parse(s);
+
+    assert_eq!(parsed("SIGKILL"), Some(Signal::Name("SIGKILL".into())));
+    assert_eq!(parsed("term"), Some(Signal::Name("SIGTERM".into())));
+    assert_eq!(parsed("SIGRTMIN+3"), Some(Signal::Name("SIGRTMIN+3".into())));
+    assert_eq!(parsed("15"), Some(Signal::Number(15)));
+    assert_eq!(parsed("99999999999999999999999"), Some(Signal::Number(u64::MAX)));
+    assert_eq!(parsed("SIG"), None);
+    assert_eq!(parsed("9;"), None);
+
+    for valid in &["SIGTERM", "quit", "SIGRTMIN", "RTMIN+15", "SIGRTMAX-1", "1", "64"] {
+      assert!(parsed(valid).unwrap().is_valid(), "{}", valid);
+    }
+
+    for invalid in &["SIGFOO", "SIGRTMIN+16", "RTMAX-0", "RTMIN+03", "0", "65"] {
+      assert!(!parsed(invalid).unwrap().is_valid(), "{}", invalid);
+    }
+  }
+
+  #[test]
+  fn stopsignal_dockerfile() -> Result<()> {
+    let dockerfile = Dockerfile::parse(
+      "FROM alpine:3.19\nARG SIG=SIGQUIT\nSTOPSIGNAL $SIG \nSTOPSIGNAL SIGINT SIGTERM\n"
+    )?;
+
+    // variables are kept as written
+    let stopsignal = dockerfile.instructions[2].as_stopsignal().unwrap();
+    assert_eq!(stopsignal.signal.content, "$SIG");
+    assert_eq!(stopsignal.parsed_signal(), None);
+    assert_eq!(dockerfile.text(&stopsignal.span), "STOPSIGNAL $SIG");
+
+    let references = dockerfile.instructions[2].variable_references();
+    assert_eq!(references.len(), 1);
+    assert_eq!(references[0].name, "SIG");
+    assert_eq!(dockerfile.text(&references[0].span), "$SIG");
+
+    // extra arguments are left unparsed
+    assert!(dockerfile.instructions[3].as_misc().is_some());
+
+    Ok(())
+  }
+}
@@ -0,0 +1,160 @@
+// (C) Copyright 2026 Hewlett Packard Enterprise Development LP
+
+use std::convert::TryFrom;
+
+use crate::Span;
+use crate::dockerfile_parser::Instruction;
+use crate::error::*;
+use crate::util::*;
+use crate::parser::*;
+
+/// Finds the colon separating the user from the group, ignoring any within
+/// braced variable references like `${APP_USER:-app}`.
+fn find_separator(spec: &str) -> Option<usize> {
+  let mut depth = 0usize;
+
+  for (i, c) in spec.char_indices() {
+    match c {
+      '{' => depth += 1,
+      '}' => depth = depth.saturating_sub(1),
+      ':' if depth == 0 => return Some(i),
+      _ => ()
+    }
+  }
+
+  None
+}
+
+/// A Dockerfile [`USER` instruction][user], e.g. `USER app` or
+/// `USER 1000:1000`.
+///
+/// The user and group are kept verbatim, so variable references like
+/// `USER $APP_USER` may be resolved later, e.g. with `try_substitute()`.
+///
+/// [user]: https://docs.docker.com/engine/reference/builder/#user
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct UserInstruction {
+  pub span: Span,
+
+  /// The user name or UID
+  pub user: SpannedString,
+
+  /// The group name or GID, if given after a `:`
+  pub group: Option<SpannedString>,
+}
+
+impl UserInstruction {
+  pub(crate) fn from_record(record: Pair) -> Result<UserInstruction> {
+    let span = Span::from_pair(&record);
+    let field = record.into_inner().next().unwrap();
+    if field.as_rule() != Rule::user_spec {
+      return Err(unexpected_token(field));
+    }
+
+    let spec = field.as_str();
+    let start = field.as_span().start();
+    let spanned = |from: usize, to: usize| SpannedString {
+      span: Span::new(start + from, start + to),
+      content: spec[from..to].to_string(),
+      raw_text: None,
+      quoted: false,
+    };
+
+    let (user, group) = match find_separator(spec) {
+      Some(pos) => (spanned(0, pos), Some(spanned(pos + 1, spec.len()))),
+      None => (spanned(0, spec.len()), None)
+    };
+
+    Ok(UserInstruction { span, user, group })
+  }
+
+  /// Determines if this instruction switches to the root user, i.e. `root` or
+  /// UID `0`. Users given as variable references are not resolved.
+  pub fn is_root(&self) -> bool {
+    self.user.content == "root" || self.user.content == "0"
+  }
+}
+
+impl<'a> TryFrom<&'a Instruction> for &'a UserInstruction {
+  type Error = Error;
+
+  fn try_from(instruction: &'a Instruction) -> std::result::Result<Self, Self::Error> {
+    if let Instruction::User(u) = instruction {
+      Ok(u)
+    } else {
+      Err(Error::ConversionError {
+        from: format!("{:?}", instruction),
+        to: "UserInstruction".into()
+      })
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use pretty_assertions::assert_eq;
+
+  use super::*;
+  use crate::Dockerfile;
+  use crate::test_util::*;
+
+  #[test]
+  fn user_basic() -> Result<()> {
+    assert_eq!(
+      parse_single("USER app", Rule::user)?,
+      UserInstruction {
+        span: Span::new(0, 8),
+        user: SpannedString { span: Span::new(5, 8), content: "app".to_string(), raw_text: None, quoted: false },
+        group: None,
+      }.into()
+    );
+
+    assert_eq!(
+      parse_single("user 1000:1000", Rule::user)?,
+      UserInstruction {
+        span: Span::new(0, 14),
+        user: SpannedString { span: Span::new(5, 9), content: "1000".to_string(), raw_text: None, quoted: false },
+        group: Some(SpannedString {
+          span: Span::new(10, 14),
+          content: "1000".to_string(),
+          raw_text: None,
+          quoted: false
+        }),
+      }.into()
+    );
+
+    let user = parse_single("USER \\\n  app:staff", Rule::user)?.into_user().unwrap();
+    assert_eq!(user.user.span, Span::new(9, 12));
+    assert_eq!(user.group.unwrap().span, Span::new(13, 18));
+
+    assert!(parse_single("USER root", Rule::user)?.into_user().unwrap().is_root());
+    assert!(parse_single("USER 0:0", Rule::user)?.into_user().unwrap().is_root());
+    assert!(!parse_single("USER $APP_USER", Rule::user)?.into_user().unwrap().is_root());
+
+    Ok(())
+  }
+
+  #[test]
+  fn user_variables() -> Result<()> {
+    let dockerfile = Dockerfile::parse(
+      "FROM alpine:3.19\nUSER ${APP_USER:-app}:$APP_GROUP\nUSER app extra\n"
+    )?;
+
+    let user = dockerfile.instructions[1].as_user().unwrap();
+    assert_eq!(user.user.content, "${APP_USER:-app}");
+    assert_eq!(user.group.as_ref().unwrap().content, "$APP_GROUP");
+
+    // the group can be spliced on its own
+    let mut splicer = dockerfile.splicer();
+    splicer.splice(&user.group.as_ref().unwrap().span, "1000");
+    assert_eq!(
+      splicer.content,
+      "FROM alpine:3.19\nUSER ${APP_USER:-app}:1000\nUSER app extra\n"
+    );
+
+    // invalid instructions are left unparsed
+    assert!(dockerfile.instructions[2].as_misc().is_some());
+
+    Ok(())
+  }
+}
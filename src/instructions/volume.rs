@@ -0,0 +1,164 @@
+// (C) Copyright 2026 Hewlett Packard Enterprise Development LP
+
+use std::convert::TryFrom;
+
+use crate::Span;
+use crate::dockerfile_parser::Instruction;
+use crate::error::*;
+use crate::util::*;
+use crate::parser::*;
+
+/// The syntax used to write a `VOLUME` instruction's paths.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum VolumeForm {
+  /// Whitespace-separated paths, e.g. `VOLUME /data /logs`
+  Shell,
+
+  /// A JSON array of paths, e.g. `VOLUME ["/data", "/logs"]`
+  Exec,
+}
+
+/// A Dockerfile [`VOLUME` instruction][volume].
+///
+/// Paths in the shell form are split on whitespace and kept verbatim, quotes
+/// included, as Docker does; paths in the exec form are unquoted.
+///
+/// [volume]: https://docs.docker.com/engine/reference/builder/#volume
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct VolumeInstruction {
+  pub span: Span,
+  pub form: VolumeForm,
+  pub paths: Vec<SpannedString>,
+}
+
+impl VolumeInstruction {
+  pub(crate) fn from_record(record: Pair) -> Result<VolumeInstruction> {
+    let span = Span::from_pair(&record);
+    let mut form = VolumeForm::Shell;
+    let mut paths = Vec::new();
+
+    for field in record.into_inner() {
+      match field.as_rule() {
+        Rule::volume_exec => {
+          form = VolumeForm::Exec;
+          paths = parse_string_array(field)?.elements;
+        },
+        Rule::volume_path => paths.push(SpannedString {
+          span: Span::from_pair(&field),
+          content: field.as_str().to_string(),
+          raw_text: None,
+          quoted: false,
+        }),
+        Rule::comment => continue,
+        _ => return Err(unexpected_token(field))
+      }
+    }
+
+    Ok(VolumeInstruction {
+      span,
+      form,
+      paths,
+    })
+  }
+}
+
+impl<'a> TryFrom<&'a Instruction> for &'a VolumeInstruction {
+  type Error = Error;
+
+  fn try_from(instruction: &'a Instruction) -> std::result::Result<Self, Self::Error> {
+    if let Instruction::Volume(v) = instruction {
+      Ok(v)
+    } else {
+      Err(Error::ConversionError {
+        from: format!("{:?}", instruction),
+        to: "VolumeInstruction".into()
+      })
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use indoc::indoc;
+  use pretty_assertions::assert_eq;
+
+  use super::*;
+  use crate::Dockerfile;
+  use crate::test_util::*;
+
+  #[test]
+  fn volume_shell() -> Result<()> {
+    assert_eq!(
+      parse_single("VOLUME /data \"/my\" \\\n  # comment\n  /logs", Rule::volume)?,
+      VolumeInstruction {
+        span: Span::new(0, 40),
+        form: VolumeForm::Shell,
+        paths: vec![
+          SpannedString { span: Span::new(7, 12), content: "/data".to_string(), raw_text: None, quoted: false },
+          SpannedString { span: Span::new(13, 18), content: "\"/my\"".to_string(), raw_text: None, quoted: false },
+          SpannedString { span: Span::new(35, 40), content: "/logs".to_string(), raw_text: None, quoted: false },
+        ],
+      }.into()
+    );
+
+    Ok(())
+  }
+
+  #[test]
+  fn volume_exec() -> Result<()> {
+    assert_eq!(
+      parse_single(indoc!(r#"
+        volume ["/data", \
+          "/my data"]
+      "#), Rule::volume)?,
+      VolumeInstruction {
+        span: Span::new(0, 32),
+        form: VolumeForm::Exec,
+        paths: vec![
+          SpannedString { span: Span::new(8, 15), content: "/data".to_string(), raw_text: None, quoted: true },
+          SpannedString { span: Span::new(21, 31), content: "/my data".to_string(), raw_text: None, quoted: true },
+        ],
+      }.into()
+    );
+
+    let empty = parse_single("VOLUME []", Rule::volume)?.into_volume().unwrap();
+    assert_eq!(empty.form, VolumeForm::Exec);
+    assert!(empty.paths.is_empty());
+
+    // invalid JSON falls back to the shell form, as in Docker
+    let invalid = parse_single("VOLUME [/data]", Rule::volume)?.into_volume().unwrap();
+    assert_eq!(invalid.form, VolumeForm::Shell);
+    assert_eq!(invalid.paths[0].content, "[/data]");
+
+    Ok(())
+  }
+
+  #[test]
+  fn volume_exec_splice() -> Result<()> {
+    let dockerfile = Dockerfile::parse(indoc!(r#"
+      FROM alpine:3.19
+      VOLUME [ \
+        # application data
+        "/var/lib/my app", \
+        "/logs" ]
+    "#))?;
+
+    let volume = dockerfile.instructions[1].as_volume().unwrap();
+    assert_eq!(volume.form, VolumeForm::Exec);
+    assert_eq!(
+      volume.paths.iter().map(|p| p.as_ref()).collect::<Vec<&str>>(),
+      vec!["/var/lib/my app", "/logs"]
+    );
+    assert_eq!(dockerfile.text(&volume.paths[0].span), "\"/var/lib/my app\"");
+
+    let mut splicer = dockerfile.splicer();
+    splicer.splice(&volume.paths[0].span, "\"/srv/my data\"");
+
+    let spliced = Dockerfile::parse(&splicer.content)?;
+    let volume = spliced.instructions[1].as_volume().unwrap();
+    assert_eq!(volume.paths[0].content, "/srv/my data");
+    assert_eq!(volume.paths[1].content, "/logs");
+
+    Ok(())
+  }
+}
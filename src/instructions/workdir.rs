@@ -0,0 +1,102 @@
+// (C) Copyright 2026 Hewlett Packard Enterprise Development LP
+
+use std::convert::TryFrom;
+
+use crate::Span;
+use crate::dockerfile_parser::Instruction;
+use crate::error::*;
+use crate::util::*;
+use crate::parser::*;
+
+/// A Dockerfile [`WORKDIR` instruction][workdir], e.g. `WORKDIR /app`.
+///
+/// A quoted path is unquoted, while an unquoted path is kept as written,
+/// including any spaces or variable references, e.g. `${APP_HOME}/bin`.
+///
+/// [workdir]: https://docs.docker.com/engine/reference/builder/#workdir
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct WorkdirInstruction {
+  pub span: Span,
+  pub path: SpannedString,
+}
+
+impl WorkdirInstruction {
+  pub(crate) fn from_record(record: Pair) -> Result<WorkdirInstruction> {
+    let span = Span::from_pair(&record);
+    let field = record.into_inner().next().unwrap();
+
+    match field.as_rule() {
+      Rule::workdir_path | Rule::workdir_quoted_path => Ok(WorkdirInstruction {
+        span,
+        path: parse_string(&field)?,
+      }),
+      _ => Err(unexpected_token(field))
+    }
+  }
+}
+
+impl<'a> TryFrom<&'a Instruction> for &'a WorkdirInstruction {
+  type Error = Error;
+
+  fn try_from(instruction: &'a Instruction) -> std::result::Result<Self, Self::Error> {
+    if let Instruction::Workdir(w) = instruction {
+      Ok(w)
+    } else {
+      Err(Error::ConversionError {
+        from: format!("{:?}", instruction),
+        to: "WorkdirInstruction".into()
+      })
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use pretty_assertions::assert_eq;
+
+  use super::*;
+  use crate::Dockerfile;
+  use crate::test_util::*;
+
+  #[test]
+  fn workdir_basic() -> Result<()> {
+    assert_eq!(
+      parse_single("WORKDIR /app", Rule::workdir)?,
+      WorkdirInstruction {
+        span: Span::new(0, 12),
+        path: SpannedString { span: Span::new(8, 12), content: "/app".to_string(), raw_text: None, quoted: false },
+      }.into()
+    );
+
+    let workdir = parse_single("workdir \\\n  ${APP_HOME}/bin", Rule::workdir)?
+      .into_workdir()
+      .unwrap();
+    assert_eq!(workdir.path.content, "${APP_HOME}/bin");
+    assert_eq!(workdir.path.span, Span::new(12, 27));
+
+    let workdir = parse_single("WORKDIR /my app", Rule::workdir)?.into_workdir().unwrap();
+    assert_eq!(workdir.path.content, "/my app");
+
+    let workdir = parse_single("WORKDIR \"/my app\"", Rule::workdir)?.into_workdir().unwrap();
+    assert_eq!(workdir.path.content, "/my app");
+    assert_eq!(workdir.path.span, Span::new(8, 17));
+
+    Ok(())
+  }
+
+  #[test]
+  fn workdir_dockerfile() -> Result<()> {
+    let dockerfile = Dockerfile::parse(
+      "FROM alpine:3.19\nWORKDIR $HOME/src \nWORKDIR /a \\\n  /b\n"
+    )?;
+
+    let workdir = dockerfile.instructions[1].as_workdir().unwrap();
+    assert_eq!(workdir.path.content, "$HOME/src");
+    assert_eq!(dockerfile.text(&workdir.span), "WORKDIR $HOME/src");
+
+    // paths continued over several lines are left unparsed
+    assert!(dockerfile.instructions[2].as_misc().is_some());
+
+    Ok(())
+  }
+}
@@ -0,0 +1,377 @@
+// (C) Copyright 2026 Hewlett Packard Enterprise Development LP
+
+use std::fmt::Write;
+
+use crate::diagnostics::write_json_string;
+use crate::dockerfile_parser::{Dockerfile, Instruction};
+use crate::instructions::PREDEFINED_ARG_NAMES;
+use crate::stage::{Stage, StageParent, Stages};
+use crate::variables::find_references;
+
+/// A stage of a Dockerfile, as listed by `Dockerfile::build_interface()`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BuildStage {
+  /// The stage index
+  pub index: usize,
+
+  /// The stage's `FROM` alias, if any, usable with `docker build --target`
+  pub name: Option<String>,
+}
+
+/// A build arg accepted by a Dockerfile, as listed by
+/// `Dockerfile::build_interface()`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BuildArg {
+  /// The arg name
+  pub name: String,
+
+  /// The default value as written, if any
+  pub default: Option<String>,
+}
+
+/// A port exposed by the image built from a Dockerfile, as listed by
+/// `Dockerfile::build_interface()`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExposedPort {
+  /// The port or port range as written, e.g. `8080`, `8000-8010`, or `$PORT`
+  pub port: String,
+
+  /// The protocol, `tcp` unless given explicitly, e.g. `53/udp`
+  pub protocol: String,
+}
+
+/// A summary of how a Dockerfile is built and run, e.g. for describing build
+/// targets to orchestration tooling; see `Dockerfile::build_interface()`.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct BuildInterface {
+  /// All stages, in order
+  pub stages: Vec<BuildStage>,
+
+  /// The index in `stages` of the stage built by default, i.e. the last
+  /// stage, if any
+  pub default_target: Option<usize>,
+
+  /// Args used when building the default target that have no default value,
+  /// sorted by name
+  pub required_args: Vec<BuildArg>,
+
+  /// Args used when building the default target that have a default value,
+  /// sorted by name
+  pub optional_args: Vec<BuildArg>,
+
+  /// The ports exposed by the default target's image, including any exposed
+  /// by its parent stages, in order
+  pub exposed_ports: Vec<ExposedPort>,
+
+  /// If true, the default target's image defines a `HEALTHCHECK` (other than
+  /// `HEALTHCHECK NONE`)
+  pub healthcheck: bool,
+}
+
+impl BuildInterface {
+  /// Renders this interface as JSON, with the following schema:
+  ///
+  /// ```text
+  /// {
+  ///   "stages": [{"index": number, "name": string | null}],
+  ///   "default_target": number | null,
+  ///   "args": {
+  ///     "required": [{"name": string}],
+  ///     "optional": [{"name": string, "default": string}]
+  ///   },
+  ///   "exposed_ports": [{"port": string, "protocol": string}],
+  ///   "healthcheck": boolean
+  /// }
+  /// ```
+  pub fn to_json(&self) -> String {
+    let mut out = String::from("{\n  \"stages\": [");
+    for (i, stage) in self.stages.iter().enumerate() {
+      out.push_str(if i == 0 { "\n    " } else { ",\n    " });
+      write!(out, "{{\"index\": {}, \"name\": ", stage.index).unwrap();
+      match &stage.name {
+        Some(name) => write_json_string(&mut out, name),
+        None => out.push_str("null")
+      }
+      out.push('}');
+    }
+
+    out.push_str(if self.stages.is_empty() { "],\n" } else { "\n  ],\n" });
+    match self.default_target {
+      Some(index) => writeln!(out, "  \"default_target\": {},", index).unwrap(),
+      None => out.push_str("  \"default_target\": null,\n")
+    }
+
+    out.push_str("  \"args\": {\n    \"required\": [");
+    for (i, arg) in self.required_args.iter().enumerate() {
+      out.push_str(if i == 0 { "" } else { ", " });
+      out.push_str("{\"name\": ");
+      write_json_string(&mut out, &arg.name);
+      out.push('}');
+    }
+
+    out.push_str("],\n    \"optional\": [");
+    for (i, arg) in self.optional_args.iter().enumerate() {
+      out.push_str(if i == 0 { "\n      " } else { ",\n      " });
+      out.push_str("{\"name\": ");
+      write_json_string(&mut out, &arg.name);
+      out.push_str(", \"default\": ");
+      write_json_string(&mut out, arg.default.as_deref().unwrap_or(""));
+      out.push('}');
+    }
+
+    out.push_str(if self.optional_args.is_empty() { "]\n  },\n" } else { "\n    ]\n  },\n" });
+    out.push_str("  \"exposed_ports\": [");
+    for (i, port) in self.exposed_ports.iter().enumerate() {
+      out.push_str(if i == 0 { "" } else { ", " });
+      out.push_str("{\"port\": ");
+      write_json_string(&mut out, &port.port);
+      out.push_str(", \"protocol\": ");
+      write_json_string(&mut out, &port.protocol);
+      out.push('}');
+    }
+
+    writeln!(out, "],\n  \"healthcheck\": {}\n}}", self.healthcheck).unwrap();
+    out
+  }
+}
+
+/// Returns the given stage and its chain of parent stages, root first.
+fn stage_chain<'s, 'a>(stages: &'s Stages<'a>, stage: &'s Stage<'a>) -> Vec<&'s Stage<'a>> {
+  let mut chain = vec![stage];
+  while let StageParent::Stage(parent) = chain[chain.len() - 1].parent {
+    if chain.iter().any(|s| s.index == parent) {
+      break;
+    }
+
+    chain.push(&stages[parent]);
+  }
+
+  chain.reverse();
+  chain
+}
+
+impl Dockerfile {
+  /// Summarizes how this Dockerfile is built and run: its stages, the build
+  /// args used by the default target (the last stage), and the ports and
+  /// healthcheck of the resulting image.
+  ///
+  /// Only args that can affect the default target are included: those
+  /// declared in the stages it depends on (see `Dockerfile::target()`), and
+  /// global args referenced by those stages' `FROM` instructions or
+  /// redeclared within them. An arg is required if none of these declarations
+  /// (including a global declaration of a redeclared arg) has a default
+  /// value. Predefined args, e.g. `HTTP_PROXY` or `TARGETARCH`, are never
+  /// included.
+  ///
+  /// ```
+  /// use dockerfile_parser::Dockerfile;
+  ///
+  /// let dockerfile = Dockerfile::parse(r#"
+  ///   FROM alpine AS unused
+  ///   ARG UNUSED_TOKEN
+  ///
+  ///   FROM alpine
+  ///   ARG VERSION
+  ///   EXPOSE 8080
+  /// "#).unwrap();
+  ///
+  /// let interface = dockerfile.build_interface();
+  /// assert_eq!(interface.required_args[0].name, "VERSION");
+  /// assert_eq!(interface.required_args.len(), 1);
+  /// assert_eq!(interface.exposed_ports[0].port, "8080");
+  /// ```
+  pub fn build_interface(&self) -> BuildInterface {
+    let stages = self.stages();
+    let mut interface = BuildInterface {
+      stages: stages
+        .iter()
+        .map(|s| BuildStage { index: s.index, name: s.name.clone() })
+        .collect(),
+      default_target: stages.iter().last().map(|s| s.index),
+      ..Default::default()
+    };
+
+    let target = match stages.iter().last() {
+      Some(target) => target,
+      None => return interface
+    };
+
+    // stages in a cycle can't be built, so only the target itself is used
+    let used: Vec<&Stage> = stages
+      .reachable_from(self, target.index)
+      .unwrap_or_else(|_| vec![target.index])
+      .into_iter()
+      .map(|i| &stages[i])
+      .collect();
+
+    // each in-scope arg with its default, if any, in order of declaration
+    let mut args: Vec<(&str, Option<&str>)> = Vec::new();
+    for stage in &used {
+      let from_refs: Vec<String> = stage.instructions[0]
+        .as_from()
+        .map(|from| find_references(&from.image).into_iter().map(|r| r.name).collect())
+        .unwrap_or_default();
+
+      for global in self.global_args() {
+        let name = global.name.content.as_str();
+        if from_refs.iter().any(|r| r == name) || stage.arg_index(name).is_some() {
+          args.push((name, global.value.as_ref().map(|v| v.content.as_str())));
+        }
+      }
+
+      for ins in &stage.instructions {
+        if let Instruction::Arg(arg) = ins {
+          args.push((&arg.name.content, arg.value.as_ref().map(|v| v.content.as_str())));
+        }
+      }
+    }
+
+    let mut names: Vec<&str> = args
+      .iter()
+      .map(|(name, _)| *name)
+      .filter(|name| !PREDEFINED_ARG_NAMES.contains(name))
+      .collect();
+    names.sort_unstable();
+    names.dedup();
+
+    for name in names {
+      let default = args
+        .iter()
+        .find_map(|(n, default)| if *n == name { *default } else { None });
+
+      let arg = BuildArg { name: name.to_string(), default: default.map(String::from) };
+      if arg.default.is_some() {
+        interface.optional_args.push(arg);
+      } else {
+        interface.required_args.push(arg);
+      }
+    }
+
+    for stage in stage_chain(&stages, target) {
+      for ins in &stage.instructions {
+        match ins {
+          Instruction::Misc(misc) if misc.instruction.content.eq_ignore_ascii_case("expose") => {
+            interface.exposed_ports.extend(misc.argument_words().into_iter().map(|word| {
+              let (port, protocol) = match word.content.split_once('/') {
+                Some((port, protocol)) => (port.to_string(), protocol.to_ascii_lowercase()),
+                None => (word.content.clone(), "tcp".to_string())
+              };
+
+              ExposedPort { port, protocol }
+            }));
+          },
+          Instruction::Healthcheck(healthcheck) => interface.healthcheck = !healthcheck.is_none(),
+          _ => ()
+        }
+      }
+    }
+
+    interface
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use indoc::indoc;
+  use pretty_assertions::assert_eq;
+
+  use crate::*;
+
+  const APP: &str = indoc!(r#"
+    # syntax=docker/dockerfile:1
+    ARG NODE_VERSION=20
+    ARG REGISTRY
+    ARG DOCS_THEME
+    FROM ${REGISTRY}node:${NODE_VERSION}-alpine AS deps
+    ARG NPM_TOKEN
+    ARG HTTP_PROXY
+    RUN --mount=type=secret,id=npmrc npm ci
+
+    FROM deps AS build
+    ARG BUILD_ENV=production
+    RUN npm run build
+
+    FROM alpine AS docs
+    ARG DOCS_THEME
+    ARG DOCS_TOKEN
+    RUN make docs
+
+    FROM deps AS base
+    ARG NODE_VERSION
+    EXPOSE 9229/tcp
+    HEALTHCHECK NONE
+
+    FROM base AS app
+    ARG PORT=8080
+    COPY --from=build /app/dist /app
+    EXPOSE $PORT 9100/UDP
+    HEALTHCHECK --interval=30s CMD wget -q -O- localhost:$PORT/health
+    CMD ["node", "/app/server.js"]
+  "#);
+
+  #[test]
+  fn test_build_interface() {
+    let d = Dockerfile::parse(APP).unwrap();
+    let interface = d.build_interface();
+
+    let stage = |index: usize, name: &str| BuildStage { index, name: Some(name.into()) };
+    let arg = |name: &str, default: Option<&str>| BuildArg {
+      name: name.into(),
+      default: default.map(String::from),
+    };
+    let port = |port: &str, protocol: &str| ExposedPort { port: port.into(), protocol: protocol.into() };
+
+    assert_eq!(interface, BuildInterface {
+      stages: vec![
+        stage(0, "deps"), stage(1, "build"), stage(2, "docs"), stage(3, "base"), stage(4, "app"),
+      ],
+      default_target: Some(4),
+
+      // the docs stage isn't needed by the default target, and NODE_VERSION
+      // inherits its global default when redeclared
+      required_args: vec![arg("NPM_TOKEN", None), arg("REGISTRY", None)],
+      optional_args: vec![
+        arg("BUILD_ENV", Some("production")),
+        arg("NODE_VERSION", Some("20")),
+        arg("PORT", Some("8080")),
+      ],
+
+      exposed_ports: vec![port("9229", "tcp"), port("$PORT", "tcp"), port("9100", "udp")],
+      healthcheck: true,
+    });
+
+    // HEALTHCHECK NONE in the target disables an inherited healthcheck
+    let base = Dockerfile::parse(&APP[..APP.find("FROM base AS app").unwrap()]).unwrap();
+    assert!(!base.build_interface().healthcheck);
+
+    assert_eq!(Dockerfile::parse("").unwrap().build_interface(), BuildInterface::default());
+  }
+
+  #[test]
+  fn test_build_interface_json() {
+    let d = Dockerfile::parse(APP).unwrap();
+
+    assert_eq!(d.build_interface().to_json(), indoc!(r#"
+      {
+        "stages": [
+          {"index": 0, "name": "deps"},
+          {"index": 1, "name": "build"},
+          {"index": 2, "name": "docs"},
+          {"index": 3, "name": "base"},
+          {"index": 4, "name": "app"}
+        ],
+        "default_target": 4,
+        "args": {
+          "required": [{"name": "NPM_TOKEN"}, {"name": "REGISTRY"}],
+          "optional": [
+            {"name": "BUILD_ENV", "default": "production"},
+            {"name": "NODE_VERSION", "default": "20"},
+            {"name": "PORT", "default": "8080"}
+          ]
+        },
+        "exposed_ports": [{"port": "9229", "protocol": "tcp"}, {"port": "$PORT", "protocol": "tcp"}, {"port": "9100", "protocol": "udp"}],
+        "healthcheck": true
+      }
+    "#));
+  }
+}
@@ -12,7 +12,7 @@
 //! ## Quick start
 //!
 //! ```rust
-//! use dockerfile_parser::Dockerfile;
+//! use dockerfile_parser::prelude::*;
 //!
 //! let dockerfile = Dockerfile::parse(r#"
 //!   FROM alpine:3.11 as builder
@@ -36,18 +36,78 @@ mod error;
 mod parser;
 mod util;
 mod image;
+mod git;
 mod instructions;
+mod heredoc;
 mod splicer;
 mod stage;
+mod variables;
+mod lock;
+mod editor;
+pub mod diagnostics;
+mod validate;
+mod merge;
+mod metrics;
+mod hash;
+mod directive;
+mod checks;
+mod completion;
+mod explain;
+pub mod analysis;
+pub mod fixes;
+mod incremental;
+mod shift;
+mod options;
+mod spec;
+mod platform;
+mod trivia;
+mod strip;
+mod units;
+mod target;
+mod definitions;
+mod workspace;
+mod interface;
+mod render;
+pub mod quick;
+pub mod prelude;
 mod dockerfile_parser;
 
 pub use image::*;
+pub use git::*;
 pub use error::*;
-pub use parser::*;
+// the grammar rules are internal, but are named by `Error::ParseError`
+pub use parser::Rule;
 pub use instructions::*;
+pub use heredoc::Heredoc;
 pub use splicer::*;
 pub use stage::*;
-pub use util::*;
-pub use crate::dockerfile_parser::*;
+pub use util::{
+  BreakableString, BreakableStringComponent, CommentAttachment, FlagValue, LogicalLine,
+  PairSeparator, QuoteStyle, ShellOrExecExpr, SpannedComment, SpannedString, StringArray
+};
+pub use variables::{
+  find_references, substitute, try_substitute, try_substitute_with_max_len,
+  ExpansionBehavior, ExpansionOp, VarRef
+};
+pub use lock::*;
+pub use editor::*;
+pub use diagnostics::{Diagnostic, DiagnosticKind, Severity};
+pub use merge::*;
+pub use metrics::*;
+pub use hash::*;
+pub use directive::*;
+pub use checks::*;
+pub use completion::*;
+pub use options::*;
+pub use spec::*;
+pub use platform::*;
+pub use trivia::*;
+pub use strip::*;
+pub use units::*;
+pub use target::*;
+pub use definitions::*;
+pub use workspace::*;
+pub use interface::*;
+pub use crate::dockerfile_parser::{Dockerfile, Instruction, InstructionKind};
 
 #[cfg(test)] mod test_util;
@@ -0,0 +1,111 @@
+// (C) Copyright 2026 Hewlett Packard Enterprise Development LP
+
+use crate::dockerfile_parser::Dockerfile;
+use crate::image::{Digest, ImageRef};
+use crate::splicer::Span;
+use crate::stage::StageParent;
+
+/// A single base image occurrence in a Dockerfile, suitable for storing in a
+/// lock file that maps each `FROM` to a pinned digest.
+///
+/// Entries are produced by [`Dockerfile::image_lock_entries`] and may later be
+/// applied to a (possibly modified) Dockerfile with
+/// [`DockerfileEditor::apply_image_lock`](crate::DockerfileEditor::apply_image_lock).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ImageLockEntry {
+  /// The index of the stage whose `FROM` references this image.
+  pub stage_index: usize,
+
+  /// The image as written in the Dockerfile, before variable substitution.
+  pub image: String,
+
+  /// The canonicalized image reference, with variables resolved if possible.
+  pub canonical: ImageRef,
+
+  /// The span of the image in the original Dockerfile.
+  pub span: Span,
+
+  /// The digest to pin this image to, if known.
+  ///
+  /// This is initially set from the image's existing digest, if any.
+  pub digest: Option<Digest>,
+}
+
+impl ImageLockEntry {
+  /// Determines if this entry refers to the same image as `other`, ignoring
+  /// any digests.
+  pub fn matches(&self, other: &ImageLockEntry) -> bool {
+    self.stage_index == other.stage_index
+      && strip_hash(&self.canonical) == strip_hash(&other.canonical)
+  }
+}
+
+fn strip_hash(image: &ImageRef) -> ImageRef {
+  ImageRef {
+    hash: None,
+    ..image.clone()
+  }
+}
+
+pub(crate) fn image_lock_entries(dockerfile: &Dockerfile) -> Vec<ImageLockEntry> {
+  let mut entries = Vec::new();
+
+  for stage in dockerfile.iter_stages() {
+    if !matches!(stage.parent, StageParent::Image(_)) {
+      continue;
+    }
+
+    let from = match stage.instructions.first().and_then(|i| i.as_from()) {
+      Some(from) => from,
+      None => continue
+    };
+
+    let resolved = from.image_parsed
+      .resolve_vars(dockerfile)
+      .unwrap_or_else(|| from.image_parsed.clone());
+
+    entries.push(ImageLockEntry {
+      stage_index: stage.index,
+      image: from.image.content.clone(),
+      canonical: resolved.canonicalize(),
+      span: from.image.span,
+      digest: resolved.hash.as_deref().and_then(|h| Digest::parse(h).ok()),
+    });
+  }
+
+  entries
+}
+
+#[cfg(test)]
+mod tests {
+  use indoc::indoc;
+  use pretty_assertions::assert_eq;
+
+  use crate::*;
+
+  #[test]
+  fn test_image_lock_entries() {
+    let d = Dockerfile::parse(indoc!(r#"
+      ARG TAG=3.12
+      FROM alpine:$TAG as build
+      FROM build as test
+      FROM scratch
+      FROM quay.io/foo/bar
+    "#)).unwrap();
+
+    let entries = d.image_lock_entries();
+    assert_eq!(entries.len(), 2);
+
+    assert_eq!(entries[0].stage_index, 0);
+    assert_eq!(entries[0].image, "alpine:$TAG");
+    assert_eq!(
+      entries[0].canonical.to_string(),
+      "docker.io/library/alpine:3.12"
+    );
+    assert_eq!(&d.content[entries[0].span.start..entries[0].span.end], "alpine:$TAG");
+    assert_eq!(entries[0].digest, None);
+
+    assert_eq!(entries[1].stage_index, 3);
+    assert_eq!(entries[1].canonical.to_string(), "quay.io/foo/bar:latest");
+  }
+}
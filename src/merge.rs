@@ -0,0 +1,275 @@
+// (C) Copyright 2026 Hewlett Packard Enterprise Development LP
+
+use crate::dockerfile_parser::{Dockerfile, Instruction};
+use crate::instructions::{EnvVar, Label};
+use crate::shift::ShiftSpans;
+use crate::splicer::Span;
+use crate::util::{PairSeparator, QuoteStyle};
+
+/// Returns the leading whitespace of the line containing `pos`, or an empty
+/// string if `pos` is not the first non-whitespace character on its line.
+fn line_indent(content: &str, pos: usize) -> &str {
+  let line_start = content[..pos].rfind('\n').map(|i| i + 1).unwrap_or(0);
+  let prefix = &content[line_start..pos];
+
+  if prefix.chars().all(|c| c == ' ' || c == '\t') {
+    prefix
+  } else {
+    ""
+  }
+}
+
+/// Returns the instruction keyword as written, e.g. `RUN` or `run`.
+fn keyword<'a>(content: &'a str, span: &Span) -> &'a str {
+  let text = &content[span.start..span.end];
+  let len = text.find(|c: char| !c.is_ascii_alphabetic()).unwrap_or(text.len());
+
+  &text[..len]
+}
+
+/// Collects the comment lines between two offsets.
+fn comments_between(content: &str, start: usize, end: usize) -> Vec<&str> {
+  content[start..end]
+    .lines()
+    .map(str::trim)
+    .filter(|line| line.starts_with('#'))
+    .collect()
+}
+
+/// Joins parts of a shell command with `&&`, one part per continued line.
+fn rewrap_shell(parts: &[&str], indent: &str) -> String {
+  parts.join(&format!(" && \\\n{}    ", indent))
+}
+
+fn text<'a>(content: &'a str, span: &Span) -> &'a str {
+  &content[span.start..span.end]
+}
+
+/// Formats an env var as a `key=value` pair, quoting the value if it was
+/// written unquoted in the single (`ENV key value`) form.
+fn env_pair(content: &str, var: &EnvVar) -> String {
+  if let PairSeparator::Equals(_) = var.separator {
+    return text(content, &var.span).to_string();
+  }
+
+  match var.quote_style {
+    QuoteStyle::Unquoted => format!(
+      "{}={}",
+      var.key.content,
+      QuoteStyle::Double.quote(&var.value.to_string())
+    ),
+    _ => format!("{}={}", var.key.content, text(content, &var.value.span))
+  }
+}
+
+fn label_pair(content: &str, label: &Label) -> String {
+  format!(
+    "{}={}",
+    text(content, &label.name.span),
+    text(content, &label.value.span)
+  )
+}
+
+/// Produces a replacement merging a group of consecutive instructions, e.g.
+/// as returned by `Stage::consecutive_groups()`, into a single instruction.
+///
+/// Shell-form `RUN` instructions are joined with `&&` and line continuations,
+/// and `ENV` and `LABEL` pairs are concatenated into a single instruction.
+/// Comments between the instructions are preserved above the merged
+/// instruction.
+///
+/// Returns the span covering the whole group and its replacement text, which
+/// may be applied with a `Splicer`. Returns `None` if the group contains fewer
+/// than two instructions, mixes instruction kinds, or contains instructions
+/// that can't be merged, like exec-form `RUN`s.
+///
+/// ```
+/// use dockerfile_parser::{Dockerfile, InstructionKind, suggest_merge};
+///
+/// let dockerfile = Dockerfile::parse("FROM alpine\nENV A=1\nENV B=2\n").unwrap();
+/// let stage = &dockerfile.stages()[0];
+/// let group = &stage.consecutive_groups(InstructionKind::Env)[0];
+///
+/// let (span, replacement) = suggest_merge(&dockerfile, group).unwrap();
+/// let mut splicer = dockerfile.splicer();
+/// splicer.splice(&span, &replacement);
+/// assert_eq!(splicer.content, "FROM alpine\nENV A=1 B=2\n");
+/// ```
+pub fn suggest_merge(
+  dockerfile: &Dockerfile,
+  group: &[&Instruction]
+) -> Option<(Span, String)> {
+  // work with spans relative to `content`, in case of a base offset
+  let base = dockerfile.base_offset as isize;
+  let group: Vec<Instruction> = group
+    .iter()
+    .map(|ins| {
+      let mut ins = (*ins).clone();
+      ins.shift_spans(-base);
+      ins
+    })
+    .collect();
+
+  let first = group.first()?;
+  let last = group.last()?;
+  if group.len() < 2 || group.iter().any(|ins| ins.kind() != first.kind()) {
+    return None;
+  }
+
+  let content = &dockerfile.content;
+  let first_span = first.span();
+  let indent = line_indent(content, first_span.start);
+
+  let merged = match first {
+    Instruction::Run(_) => {
+      let mut parts = Vec::new();
+      for ins in &group {
+        // flags like --mount apply to a single command and can't be merged,
+        // nor can heredoc bodies
+        let run = ins.as_run()?;
+        if !run.flags.is_empty() || !run.heredocs.is_empty() {
+          return None;
+        }
+
+        parts.push(text(content, &run.as_shell()?.span));
+      }
+
+      rewrap_shell(&parts, indent)
+    },
+    Instruction::Env(_) => group
+      .iter()
+      .filter_map(|ins| ins.as_env())
+      .flat_map(|env| env.vars.iter().map(|var| env_pair(content, var)))
+      .collect::<Vec<_>>()
+      .join(" "),
+    Instruction::Label(_) => group
+      .iter()
+      .filter_map(|ins| ins.as_label())
+      .flat_map(|label| label.labels.iter().map(|l| label_pair(content, l)))
+      .collect::<Vec<_>>()
+      .join(" "),
+    _ => return None
+  };
+
+  let mut replacement = String::new();
+  for pair in group.windows(2) {
+    for comment in comments_between(content, pair[0].span().end, pair[1].span().start) {
+      replacement.push_str(comment);
+      replacement.push('\n');
+      replacement.push_str(indent);
+    }
+  }
+
+  replacement.push_str(keyword(content, &first_span));
+  replacement.push(' ');
+  replacement.push_str(&merged);
+
+  Some((dockerfile.rebase(Span::new(first_span.start, last.span().end)), replacement))
+}
+
+#[cfg(test)]
+mod tests {
+  use indoc::indoc;
+  use pretty_assertions::assert_eq;
+
+  use crate::*;
+
+  fn apply_merge(dockerfile: &Dockerfile, kind: InstructionKind) -> Dockerfile {
+    let stage = &dockerfile.stages()[0];
+    let groups = stage.consecutive_groups(kind);
+    assert_eq!(groups.len(), 1);
+
+    let (span, replacement) = suggest_merge(dockerfile, &groups[0]).unwrap();
+    let mut splicer = dockerfile.splicer();
+    splicer.splice(&span, &replacement);
+
+    Dockerfile::parse(&splicer.content).unwrap()
+  }
+
+  #[test]
+  fn test_merge_run() {
+    let d = Dockerfile::parse(indoc!(r#"
+      FROM alpine:3.12
+      RUN apk add curl
+
+      # needed for fetching sources
+      RUN apk add git \
+        openssh
+      COPY /foo /bar
+    "#)).unwrap();
+
+    let merged = apply_merge(&d, InstructionKind::Run);
+    assert_eq!(merged.content, indoc!(r#"
+      FROM alpine:3.12
+      # needed for fetching sources
+      RUN apk add curl && \
+          apk add git \
+        openssh
+      COPY /foo /bar
+    "#));
+
+    assert_eq!(merged.instructions.len(), 3);
+    assert_eq!(
+      merged.instructions[1].as_run().unwrap().as_shell().unwrap().to_string(),
+      "apk add curl &&     apk add git   openssh"
+    );
+  }
+
+  #[test]
+  fn test_merge_env() {
+    let d = Dockerfile::parse(indoc!(r#"
+      FROM alpine:3.12
+      ENV A=1 B="two words"
+      ENV C three "words"
+    "#)).unwrap();
+
+    let merged = apply_merge(&d, InstructionKind::Env);
+    assert_eq!(merged.content, indoc!(r#"
+      FROM alpine:3.12
+      ENV A=1 B="two words" C="three \"words\""
+    "#));
+
+    let env = merged.instructions[1].as_env().unwrap();
+    assert_eq!(
+      env.vars.iter().map(|v| (v.key.as_ref(), v.value.to_string())).collect::<Vec<_>>(),
+      vec![
+        ("A", "1".to_string()),
+        ("B", "two words".to_string()),
+        ("C", "three \"words\"".to_string()),
+      ]
+    );
+  }
+
+  #[test]
+  fn test_merge_label() {
+    let d = Dockerfile::parse(indoc!(r#"
+      FROM alpine:3.12
+      LABEL foo=bar
+      # a comment
+      LABEL "baz qux"="quux"
+      LABEL single value
+    "#)).unwrap();
+
+    let merged = apply_merge(&d, InstructionKind::Label);
+    assert_eq!(merged.content, indoc!(r#"
+      FROM alpine:3.12
+      # a comment
+      LABEL foo=bar "baz qux"="quux" single=value
+    "#));
+    assert_eq!(merged.instructions[1].as_label().unwrap().labels.len(), 3);
+  }
+
+  #[test]
+  fn test_merge_unsupported() {
+    let d = Dockerfile::parse(indoc!(r#"
+      FROM alpine:3.12
+      RUN echo hello
+      RUN ["echo", "world"]
+    "#)).unwrap();
+
+    let stage = &d.stages()[0];
+    let groups = stage.consecutive_groups(InstructionKind::Run);
+    assert_eq!(suggest_merge(&d, &groups[0]), None);
+    assert_eq!(suggest_merge(&d, &groups[0][..1]), None);
+  }
+}
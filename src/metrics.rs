@@ -0,0 +1,411 @@
+// (C) Copyright 2026 Hewlett Packard Enterprise Development LP
+
+use crate::diagnostics::{Diagnostic, DiagnosticKind};
+use crate::dockerfile_parser::{Dockerfile, Instruction};
+use crate::error::*;
+use crate::options::StyleOptions;
+use crate::splicer::Span;
+use crate::stage::{Stage, StageParent};
+
+/// Size metrics of a single instruction as written, as returned by
+/// `Instruction::metrics()`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InstructionMetrics {
+  /// The length of the instruction in bytes
+  pub bytes: usize,
+
+  /// The number of physical lines the instruction spans, including comment
+  /// and blank lines within line continuations
+  pub physical_lines: usize,
+
+  /// The length in characters of the longest physical line, excluding the
+  /// line break. Lines are measured in full, including any text before the
+  /// start of the instruction, e.g. indentation.
+  pub longest_line: usize,
+
+  /// The number of line continuations
+  pub continuation_count: usize,
+
+  /// The number of comment lines within the instruction
+  pub comment_count: usize,
+}
+
+/// Returns the full physical lines covering `span` (relative to `content`) as
+/// spans excluding their line breaks, `\n` or `\r\n`.
+fn physical_lines(content: &str, span: &Span) -> Vec<Span> {
+  let start = content[..span.start].rfind('\n').map(|i| i + 1).unwrap_or(0);
+  let end = content[span.end..].find('\n').map(|i| span.end + i).unwrap_or(content.len());
+
+  let mut lines = Vec::new();
+  let mut line_start = start;
+  for line in content[start..end].split('\n') {
+    let line_end = line_start + line.trim_end_matches('\r').len();
+    lines.push(Span::new(line_start, line_end));
+    line_start += line.len() + 1;
+  }
+
+  lines
+}
+
+impl Instruction {
+  /// Computes size metrics for this instruction, e.g. for enforcing style
+  /// limits on instruction or line length.
+  ///
+  /// ```
+  /// use dockerfile_parser::Dockerfile;
+  ///
+  /// let dockerfile = Dockerfile::parse("RUN apk add \\\r\n  # tools\r\n  curl\r\n").unwrap();
+  /// let metrics = dockerfile.instructions[0].metrics(&dockerfile);
+  /// assert_eq!(metrics.physical_lines, 3);
+  /// assert_eq!(metrics.longest_line, 13);
+  /// assert_eq!(metrics.continuation_count, 1);
+  /// assert_eq!(metrics.comment_count, 1);
+  /// ```
+  pub fn metrics(&self, dockerfile: &Dockerfile) -> InstructionMetrics {
+    let base = dockerfile.base_offset;
+    let span = self.span();
+    let lines = physical_lines(
+      &dockerfile.content,
+      &Span::new(span.start - base, span.end - base)
+    );
+
+    let text = |line: &Span| &dockerfile.content[line.start..line.end];
+    let is_comment = |line: &Span| text(line).trim_start().starts_with('#');
+
+    InstructionMetrics {
+      bytes: span.end - span.start,
+      physical_lines: lines.len(),
+      longest_line: lines.iter().map(|l| text(l).chars().count()).max().unwrap_or(0),
+      continuation_count: lines[..lines.len() - 1]
+        .iter()
+        .filter(|l| !is_comment(l) && text(l).trim_end().ends_with('\\'))
+        .count(),
+      comment_count: lines[1..].iter().filter(|l| is_comment(l)).count(),
+    }
+  }
+}
+
+/// An instruction estimated to add a filesystem layer to an image; see
+/// `Stage::layer_estimate()`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Layer<'a> {
+  /// The index of the stage containing the instruction
+  pub stage: usize,
+
+  /// The instruction adding the layer
+  pub instruction: &'a Instruction,
+
+  /// The span of the instruction
+  pub span: Span,
+}
+
+/// An estimate of the layers added to an image, in build order.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct LayerEstimate<'a> {
+  /// The instructions adding layers, in order
+  pub layers: Vec<Layer<'a>>,
+}
+
+impl<'a> LayerEstimate<'a> {
+  /// Returns the estimated number of layers.
+  pub fn count(&self) -> usize {
+    self.layers.len()
+  }
+}
+
+impl Instruction {
+  /// Determines if this instruction adds a filesystem layer when built.
+  ///
+  /// `RUN`, `COPY`, and `ADD` each add exactly one layer, regardless of any
+  /// flags like `--mount` or the number of heredocs. All other instructions
+  /// only change the image's metadata.
+  pub fn adds_layer(&self) -> bool {
+    matches!(self, Instruction::Run(_) | Instruction::Copy(_) | Instruction::Add(_))
+  }
+}
+
+impl<'a> Stage<'a> {
+  /// Estimates the layers added by this stage's own instructions, excluding
+  /// any layers of its parent image or stage; see `Instruction::adds_layer()`.
+  pub fn layer_estimate(&self) -> LayerEstimate<'a> {
+    LayerEstimate {
+      layers: self.instructions
+        .iter()
+        .filter(|ins| ins.adds_layer())
+        .map(|ins| Layer { stage: self.index, instruction: ins, span: ins.span() })
+        .collect()
+    }
+  }
+
+  /// Estimates the layers of the image built by this stage, including those
+  /// inherited from parent stages (e.g. `FROM build`), root first. Layers of
+  /// external base images are unknown and not included.
+  pub fn layer_estimate_with_parents(&self, dockerfile: &'a Dockerfile) -> LayerEstimate<'a> {
+    let stages = dockerfile.stages();
+
+    let mut chain = vec![self.index];
+    while let StageParent::Stage(parent) = stages[chain[chain.len() - 1]].parent {
+      if chain.contains(&parent) {
+        break;
+      }
+
+      chain.push(parent);
+    }
+
+    LayerEstimate {
+      layers: chain
+        .iter()
+        .rev()
+        .flat_map(|index| stages[*index].layer_estimate().layers)
+        .collect()
+    }
+  }
+}
+
+impl Dockerfile {
+  /// Estimates the layers of the image built for the given target stage (by
+  /// name or index), like `docker build --target`. See
+  /// `Stage::layer_estimate_with_parents()`.
+  ///
+  /// Stages only referenced via `COPY --from` don't add layers to the target.
+  /// Returns `Error::UnknownTarget` if no such stage exists.
+  ///
+  /// ```
+  /// use dockerfile_parser::Dockerfile;
+  ///
+  /// let dockerfile = Dockerfile::parse(r#"
+  ///   FROM alpine AS base
+  ///   RUN apk add curl
+  ///   ENV PATH=/opt/bin:$PATH
+  ///
+  ///   FROM base AS app
+  ///   COPY app /opt/bin/app
+  /// "#).unwrap();
+  ///
+  /// assert_eq!(dockerfile.layer_estimate_for_target("base").unwrap().count(), 1);
+  /// assert_eq!(dockerfile.layer_estimate_for_target("app").unwrap().count(), 2);
+  /// ```
+  pub fn layer_estimate_for_target(&self, target: &str) -> Result<LayerEstimate<'_>> {
+    let view = self.target(target)?;
+    Ok(view.target_stage().layer_estimate_with_parents(self))
+  }
+}
+
+/// Checks instructions against the configurable style limits in `options`,
+/// reporting the first offending line of each instruction.
+pub(crate) fn check_style(
+  dockerfile: &Dockerfile,
+  options: &StyleOptions,
+  diagnostics: &mut Vec<Diagnostic>
+) {
+  for ins in &dockerfile.instructions {
+    let metrics = ins.metrics(dockerfile);
+    let lines = physical_lines(&dockerfile.content, &ins.span());
+
+    if let Some(max) = options.max_instruction_lines {
+      if metrics.physical_lines > max {
+        diagnostics.push(Diagnostic::warning(
+          DiagnosticKind::MaxInstructionLines,
+          lines[max],
+          format!(
+            "instruction spans {} lines, more than the maximum of {}",
+            metrics.physical_lines, max
+          )
+        ));
+      }
+    }
+
+    if let Some(max) = options.max_line_length {
+      let line = lines
+        .iter()
+        .map(|l| (l, dockerfile.content[l.start..l.end].chars().count()))
+        .find(|(_, len)| *len > max);
+
+      if let Some((line, len)) = line {
+        diagnostics.push(Diagnostic::warning(
+          DiagnosticKind::MaxLineLength,
+          *line,
+          format!("line is {} characters long, more than the maximum of {}", len, max)
+        ));
+      }
+    }
+  }
+
+  if let Some(max) = options.max_layers {
+    let mut reported = Vec::new();
+
+    for stage in dockerfile.iter_stages() {
+      let estimate = stage.layer_estimate_with_parents(dockerfile);
+      let layer = match estimate.layers.get(max) {
+        Some(layer) if !reported.contains(&layer.span) => layer,
+        _ => continue
+      };
+
+      reported.push(layer.span);
+      diagnostics.push(Diagnostic::warning(
+        DiagnosticKind::MaxLayers,
+        layer.span,
+        format!(
+          "stage {} builds an image with an estimated {} layers, more than the maximum of {}",
+          stage.index, estimate.count(), max
+        )
+      ));
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use indoc::indoc;
+  use pretty_assertions::assert_eq;
+
+  use crate::*;
+
+  const FIXTURE: &str = indoc!(r#"
+    FROM alpine:3.12
+      RUN apk add --no-cache \
+        # build tools
+        build-base \
+        cmake \
+        # runtime dependencies
+        curl \
+        git && \
+        echo done
+    RUN true
+  "#);
+
+  #[test]
+  fn test_metrics() {
+    let d = Dockerfile::parse(FIXTURE).unwrap();
+
+    assert_eq!(d.instructions[1].metrics(&d), InstructionMetrics {
+      bytes: 136,
+      physical_lines: 8,
+      longest_line: 26,
+      continuation_count: 5,
+      comment_count: 2,
+    });
+
+    assert_eq!(d.instructions[2].metrics(&d), InstructionMetrics {
+      bytes: 8,
+      physical_lines: 1,
+      longest_line: 8,
+      continuation_count: 0,
+      comment_count: 0,
+    });
+
+    // line breaks are excluded from line lengths
+    let crlf = Dockerfile::parse(&FIXTURE.replace('\n', "\r\n")).unwrap();
+    assert_eq!(crlf.instructions[1].metrics(&crlf), InstructionMetrics {
+      bytes: 143,
+      ..d.instructions[1].metrics(&d)
+    });
+  }
+
+  #[test]
+  fn test_check_style() {
+    let d = Dockerfile::parse(FIXTURE).unwrap();
+    let options = StyleOptions {
+      max_instruction_lines: Some(4),
+      max_line_length: Some(20),
+      ..Default::default()
+    };
+
+    let diagnostics = d.check_style(&options);
+    assert_eq!(
+      diagnostics.iter().map(|diag| (diag.kind, &d.content[diag.span.start..diag.span.end])).collect::<Vec<_>>(),
+      vec![
+        (DiagnosticKind::MaxInstructionLines, "    # runtime dependencies"),
+        (DiagnosticKind::MaxLineLength, "  RUN apk add --no-cache \\"),
+      ]
+    );
+    assert_eq!(diagnostics[0].span.relative_span(&d).0, 5);
+    assert_eq!(
+      diagnostics[0].message,
+      "instruction spans 8 lines, more than the maximum of 4"
+    );
+
+    let crlf = Dockerfile::parse(&FIXTURE.replace('\n', "\r\n")).unwrap();
+    let diagnostics = crlf.check_style(&StyleOptions {
+      max_line_length: Some(26),
+      ..Default::default()
+    });
+    assert_eq!(diagnostics, vec![]);
+
+    assert_eq!(d.check_style(&StyleOptions::default()), vec![]);
+  }
+
+  const STAGES: &str = indoc!(r#"
+    FROM alpine:3.12 AS base
+    ENV LANG=C.UTF-8
+    RUN --mount=type=cache,target=/var/cache/apk apk add curl
+    RUN <<EOF
+    echo a
+    EOF
+
+    FROM base AS build
+    WORKDIR /src
+    COPY . .
+    RUN make
+
+    FROM base AS app
+    COPY --from=build /src/app /usr/bin/app
+    ADD https://example.com/config.tar /etc/app/
+    LABEL version=1
+    CMD ["app"]
+  "#);
+
+  #[test]
+  fn test_layer_estimate() {
+    let d = Dockerfile::parse(STAGES).unwrap();
+    let stages = d.stages();
+
+    assert_eq!(
+      stages.iter().map(|s| s.layer_estimate().count()).collect::<Vec<_>>(),
+      vec![2, 2, 2]
+    );
+
+    // targets include layers inherited from parent stages, but not stages
+    // only copied from
+    let app = d.layer_estimate_for_target("app").unwrap();
+    assert_eq!(app.count(), 4);
+    assert_eq!(
+      app.layers.iter().map(|l| (l.stage, d.text(&l.span).lines().next().unwrap())).collect::<Vec<_>>(),
+      vec![
+        (0, "RUN --mount=type=cache,target=/var/cache/apk apk add curl"),
+        (0, "RUN <<EOF"),
+        (2, "COPY --from=build /src/app /usr/bin/app"),
+        (2, "ADD https://example.com/config.tar /etc/app/"),
+      ]
+    );
+
+    assert_eq!(d.layer_estimate_for_target("build").unwrap().count(), 4);
+    assert_eq!(d.layer_estimate_for_target("0").unwrap(), stages[0].layer_estimate());
+    assert!(matches!(d.layer_estimate_for_target("ap"), Err(Error::UnknownTarget { .. })));
+  }
+
+  #[test]
+  fn test_check_max_layers() {
+    let d = Dockerfile::parse(STAGES).unwrap();
+    let diagnostics = d.check_style(&StyleOptions {
+      max_layers: Some(3),
+      ..Default::default()
+    });
+
+    // each offending layer is reported once
+    assert_eq!(
+      diagnostics.iter().map(|diag| (diag.kind, d.text(&diag.span))).collect::<Vec<_>>(),
+      vec![
+        (DiagnosticKind::MaxLayers, "RUN make"),
+        (DiagnosticKind::MaxLayers, "ADD https://example.com/config.tar /etc/app/"),
+      ]
+    );
+    assert_eq!(
+      diagnostics[0].message,
+      "stage 1 builds an image with an estimated 4 layers, more than the maximum of 3"
+    );
+
+    let diagnostics = d.check_style(&StyleOptions { max_layers: Some(4), ..Default::default() });
+    assert_eq!(diagnostics, vec![]);
+  }
+}
@@ -0,0 +1,436 @@
+// (C) Copyright 2026 Hewlett Packard Enterprise Development LP
+
+use std::fmt;
+use std::str::FromStr;
+
+use crate::dockerfile_parser::Dockerfile;
+use crate::error::*;
+use crate::image::substitute_global_vars;
+use crate::instructions::FromInstruction;
+use crate::stage::{Stage, StageParent};
+
+/// A build or target platform, e.g. `linux/arm64/v8`.
+///
+/// ```
+/// use dockerfile_parser::Platform;
+///
+/// let platform = Platform::parse("linux/arm/v7").unwrap();
+/// assert_eq!(platform.os, "linux");
+/// assert_eq!(platform.architecture, "arm");
+/// assert_eq!(platform.variant.as_deref(), Some("v7"));
+/// assert_eq!(platform.to_string(), "linux/arm/v7");
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Platform {
+  /// The operating system, e.g. `linux`
+  pub os: String,
+
+  /// The CPU architecture, e.g. `amd64`
+  pub architecture: String,
+
+  /// The architecture variant, if any, e.g. `v7`
+  pub variant: Option<String>,
+}
+
+impl Platform {
+  /// Creates a new platform without a variant.
+  pub fn new<S1: Into<String>, S2: Into<String>>(os: S1, architecture: S2) -> Platform {
+    Platform {
+      os: os.into(),
+      architecture: architecture.into(),
+      variant: None,
+    }
+  }
+
+  /// Parses a platform string of the form `os/arch[/variant]`.
+  pub fn parse(s: &str) -> Result<Platform> {
+    let invalid = |reason: &str| Error::InvalidPlatform {
+      platform: s.to_string(),
+      reason: reason.to_string(),
+    };
+
+    let parts: Vec<&str> = s.split('/').collect();
+    if parts.len() < 2 || parts.len() > 3 {
+      return Err(invalid("expected os/arch[/variant]"));
+    }
+
+    if parts.iter().any(|p| p.is_empty()) {
+      return Err(invalid("empty platform component"));
+    }
+
+    Ok(Platform {
+      os: parts[0].to_string(),
+      architecture: parts[1].to_string(),
+      variant: parts.get(2).map(|v| v.to_string()),
+    })
+  }
+}
+
+impl FromStr for Platform {
+  type Err = Error;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    Platform::parse(s)
+  }
+}
+
+impl fmt::Display for Platform {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "{}/{}", self.os, self.architecture)?;
+
+    if let Some(variant) = &self.variant {
+      write!(f, "/{}", variant)?;
+    }
+
+    Ok(())
+  }
+}
+
+/// The value of a stage's `FROM --platform` flag, as written; see
+/// `Stage::platform`.
+///
+/// Platforms set by variables, e.g. `$BUILDPLATFORM`, are explicitly dynamic,
+/// and are resolved by `Stage::effective_platform()` given platform args.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StagePlatform {
+  /// A literal platform, e.g. `linux/amd64`
+  Literal(Platform),
+
+  /// A value containing variables, e.g. `$BUILDPLATFORM`
+  Variable(String),
+
+  /// A literal value that isn't a valid platform, e.g. `linux`
+  Invalid(String),
+}
+
+impl StagePlatform {
+  /// Classifies the last `--platform` flag of a `FROM` instruction, if any.
+  pub(crate) fn of(from: &FromInstruction) -> Option<StagePlatform> {
+    let flag = from.flags.iter().rev().find(|f| f.name.content.eq_ignore_ascii_case("platform"))?;
+    let value = &flag.value.content;
+
+    let platform = if value.contains('$') {
+      StagePlatform::Variable(value.clone())
+    } else {
+      match Platform::parse(value) {
+        Ok(platform) => StagePlatform::Literal(platform),
+        Err(_) => StagePlatform::Invalid(value.clone())
+      }
+    };
+
+    Some(platform)
+  }
+}
+
+/// The names of the [automatic platform `ARG`s][args] predefined by BuildKit.
+///
+/// [args]: https://docs.docker.com/reference/dockerfile/#automatic-platform-args-in-the-global-scope
+pub const PLATFORM_ARG_NAMES: &[&str] = &[
+  "TARGETPLATFORM", "TARGETOS", "TARGETARCH", "TARGETVARIANT",
+  "BUILDPLATFORM", "BUILDOS", "BUILDARCH", "BUILDVARIANT",
+];
+
+/// The build and target platforms of a build, used to resolve BuildKit's
+/// automatic platform `ARG`s like `TARGETARCH` and `BUILDPLATFORM`.
+///
+/// Per Docker, these args are defined in the global scope: they're available
+/// to `FROM` instructions, but only available within a stage if it declares
+/// them, e.g. with `ARG TARGETARCH`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PlatformArgs {
+  /// The platform being built for, i.e. `--platform`
+  pub target: Platform,
+
+  /// The platform performing the build
+  pub build: Platform,
+}
+
+impl PlatformArgs {
+  pub fn new(target: Platform, build: Platform) -> PlatformArgs {
+    PlatformArgs { target, build }
+  }
+
+  /// Returns the value of the given automatic platform arg, or `None` if
+  /// `name` isn't one of `PLATFORM_ARG_NAMES`.
+  ///
+  /// `TARGETVARIANT` and `BUILDVARIANT` are empty if the platform has no
+  /// variant.
+  pub fn get(&self, name: &str) -> Option<String> {
+    let (platform, field) = if let Some(field) = name.strip_prefix("TARGET") {
+      (&self.target, field)
+    } else if let Some(field) = name.strip_prefix("BUILD") {
+      (&self.build, field)
+    } else {
+      return None;
+    };
+
+    match field {
+      "PLATFORM" => Some(platform.to_string()),
+      "OS" => Some(platform.os.clone()),
+      "ARCH" => Some(platform.architecture.clone()),
+      "VARIANT" => Some(platform.variant.clone().unwrap_or_default()),
+      _ => None
+    }
+  }
+
+  /// Returns all automatic platform args and their values.
+  pub fn vars(&self) -> Vec<(&'static str, String)> {
+    PLATFORM_ARG_NAMES
+      .iter()
+      .filter_map(|name| self.get(name).map(|value| (*name, value)))
+      .collect()
+  }
+}
+
+/// The platform a stage is built for, as returned by
+/// `Stage::effective_platform()`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PlatformResolution {
+  /// An explicit `FROM --platform` flag, with any variables substituted
+  Explicit(Platform),
+
+  /// An explicit `FROM --platform` flag whose value couldn't be resolved to a
+  /// platform, e.g. `$BUILDPLATFORM` without platform args; contains the
+  /// value as written
+  Unresolved(String),
+
+  /// No `--platform` flag, with a parent stage that has a platform of its
+  /// own. The parent's `--platform` isn't inherited, so the stage is built
+  /// for the default platform on top of a filesystem from the parent's
+  /// platform.
+  LocalParent {
+    /// The index of the parent stage
+    parent: usize,
+
+    /// The default platform, if known
+    default: Option<Platform>,
+  },
+
+  /// No `--platform` flag: the target platform of the build, if known from
+  /// the given platform args
+  Default(Option<Platform>),
+}
+
+impl PlatformResolution {
+  /// Returns the platform the stage's instructions run on, if known. Stages
+  /// with a local parent (see `LocalParent`) report the default platform.
+  pub fn platform(&self) -> Option<&Platform> {
+    match self {
+      PlatformResolution::Explicit(platform) => Some(platform),
+      PlatformResolution::LocalParent { default, .. } => default.as_ref(),
+      PlatformResolution::Default(default) => default.as_ref(),
+      PlatformResolution::Unresolved(_) => None
+    }
+  }
+
+  /// Determines if the platform was set explicitly by a `--platform` flag,
+  /// whether or not it could be resolved.
+  pub fn is_explicit(&self) -> bool {
+    matches!(self, PlatformResolution::Explicit(_) | PlatformResolution::Unresolved(_))
+  }
+}
+
+impl fmt::Display for PlatformResolution {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      PlatformResolution::Explicit(platform) => platform.fmt(f),
+      PlatformResolution::Unresolved(value) => value.fmt(f),
+      PlatformResolution::LocalParent { parent, .. } => {
+        write!(f, "the default platform (on stage {})", parent)
+      },
+      PlatformResolution::Default(Some(platform)) => platform.fmt(f),
+      PlatformResolution::Default(None) => write!(f, "the default platform")
+    }
+  }
+}
+
+impl<'a> Stage<'a> {
+  /// Resolves the platform this stage is built for: the stage's `FROM
+  /// --platform` flag, with global `ARG`s and the automatic platform args (if
+  /// given) substituted, or otherwise the target platform.
+  ///
+  /// Docker doesn't propagate `--platform` from a parent stage, e.g. in `FROM
+  /// build AS test`; these are reported as `PlatformResolution::LocalParent`
+  /// if the parent has a platform of its own.
+  ///
+  /// ```
+  /// use dockerfile_parser::{Dockerfile, Platform, PlatformArgs, PlatformResolution};
+  ///
+  /// let dockerfile = Dockerfile::parse(r#"
+  ///   FROM --platform=$BUILDPLATFORM golang:1.22 AS build
+  ///   FROM alpine:3.19
+  /// "#).unwrap();
+  ///
+  /// let stages = dockerfile.stages();
+  /// assert_eq!(
+  ///   stages[0].effective_platform(&dockerfile, None),
+  ///   PlatformResolution::Unresolved("$BUILDPLATFORM".into())
+  /// );
+  ///
+  /// let args = PlatformArgs::new(
+  ///   Platform::parse("linux/arm64").unwrap(),
+  ///   Platform::parse("linux/amd64").unwrap(),
+  /// );
+  /// assert_eq!(
+  ///   stages[0].effective_platform(&dockerfile, Some(&args)),
+  ///   PlatformResolution::Explicit(Platform::new("linux", "amd64"))
+  /// );
+  /// assert_eq!(
+  ///   stages[1].effective_platform(&dockerfile, Some(&args)),
+  ///   PlatformResolution::Default(Some(Platform::new("linux", "arm64")))
+  /// );
+  /// ```
+  pub fn effective_platform(
+    &self,
+    dockerfile: &Dockerfile,
+    platform_args: Option<&PlatformArgs>
+  ) -> PlatformResolution {
+    let default = platform_args.map(|args| args.target.clone());
+
+    match &self.platform {
+      Some(StagePlatform::Literal(platform)) => {
+        return PlatformResolution::Explicit(platform.clone());
+      },
+      Some(StagePlatform::Variable(raw)) => {
+        return substitute_global_vars(dockerfile, raw, platform_args)
+          .ok()
+          .and_then(|(value, _)| Platform::parse(&value).ok())
+          .map(PlatformResolution::Explicit)
+          .unwrap_or_else(|| PlatformResolution::Unresolved(raw.clone()));
+      },
+      Some(StagePlatform::Invalid(raw)) => return PlatformResolution::Unresolved(raw.clone()),
+      None => ()
+    }
+
+    if let StageParent::Stage(parent) = self.parent {
+      let stages = dockerfile.stages();
+      let parent_platform = stages
+        .iter()
+        .find(|s| s.index == parent)
+        .map(|s| s.effective_platform(dockerfile, platform_args));
+
+      return match parent_platform {
+        None | Some(PlatformResolution::Default(_)) => PlatformResolution::Default(default),
+        Some(p) if default.is_some() && p.platform() == default.as_ref() => {
+          PlatformResolution::Default(default)
+        },
+        Some(_) => PlatformResolution::LocalParent { parent, default }
+      };
+    }
+
+    PlatformResolution::Default(default)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use indoc::indoc;
+  use pretty_assertions::assert_eq;
+
+  use super::*;
+
+  #[test]
+  fn test_platform_parse() {
+    assert_eq!(Platform::parse("linux/amd64").unwrap(), Platform::new("linux", "amd64"));
+    assert!(Platform::parse("linux").is_err());
+    assert!(Platform::parse("linux//v7").is_err());
+    assert!(Platform::parse("linux/arm/v7/extra").is_err());
+  }
+
+  #[test]
+  fn test_platform_args() {
+    let args = PlatformArgs::new(
+      Platform::parse("linux/arm/v7").unwrap(),
+      Platform::parse("darwin/arm64").unwrap(),
+    );
+
+    assert_eq!(args.get("TARGETPLATFORM").as_deref(), Some("linux/arm/v7"));
+    assert_eq!(args.get("TARGETOS").as_deref(), Some("linux"));
+    assert_eq!(args.get("TARGETARCH").as_deref(), Some("arm"));
+    assert_eq!(args.get("TARGETVARIANT").as_deref(), Some("v7"));
+    assert_eq!(args.get("BUILDPLATFORM").as_deref(), Some("darwin/arm64"));
+    assert_eq!(args.get("BUILDVARIANT").as_deref(), Some(""));
+    assert_eq!(args.get("TARGET"), None);
+    assert_eq!(args.get("PATH"), None);
+    assert_eq!(args.vars().len(), PLATFORM_ARG_NAMES.len());
+  }
+
+  #[test]
+  fn test_stage_platform() {
+    let d = Dockerfile::parse(indoc!(r#"
+      FROM --platform=linux/arm/v7 alpine:3.19 AS arm
+      FROM --platform=${BUILDPLATFORM} golang:1.22 AS build
+      FROM --platform=linux alpine:3.19 AS invalid
+      FROM --platform=linux/amd64 --PLATFORM=$TARGETPLATFORM alpine:3.19 AS last
+      FROM arm
+    "#)).unwrap();
+
+    let platforms: Vec<_> = d.stages().iter().map(|s| s.platform.clone()).collect();
+    assert_eq!(platforms, vec![
+      Some(StagePlatform::Literal(Platform::parse("linux/arm/v7").unwrap())),
+      Some(StagePlatform::Variable("${BUILDPLATFORM}".into())),
+      Some(StagePlatform::Invalid("linux".into())),
+      Some(StagePlatform::Variable("$TARGETPLATFORM".into())),
+      None,
+    ]);
+
+    // lazily iterated stages agree
+    let iterated: Vec<_> = d.iter_stages().map(|s| s.platform).collect();
+    assert_eq!(iterated, platforms);
+  }
+
+  #[test]
+  fn test_effective_platform() {
+    let d = Dockerfile::parse(indoc!(r#"
+      ARG ARM=linux/arm/v7
+      FROM --platform=$BUILDPLATFORM golang:1.22 AS build
+      FROM --platform=$ARM alpine:3.19 AS arm
+      FROM --platform=linux alpine:3.19 AS invalid
+      FROM build AS test
+      FROM arm AS arm-test
+      FROM scratch
+      FROM alpine:3.19 AS default
+      FROM default
+    "#)).unwrap();
+
+    let args = PlatformArgs::new(
+      Platform::parse("linux/arm64").unwrap(),
+      Platform::parse("linux/amd64").unwrap(),
+    );
+
+    let resolve = |args: Option<&PlatformArgs>| -> Vec<PlatformResolution> {
+      d.stages().iter().map(|s| s.effective_platform(&d, args)).collect()
+    };
+
+    let arm64 = Some(Platform::new("linux", "arm64"));
+    assert_eq!(resolve(Some(&args)), vec![
+      PlatformResolution::Explicit(Platform::new("linux", "amd64")),
+      PlatformResolution::Explicit(Platform::parse("linux/arm/v7").unwrap()),
+      PlatformResolution::Unresolved("linux".into()),
+      PlatformResolution::LocalParent { parent: 0, default: arm64.clone() },
+      PlatformResolution::LocalParent { parent: 1, default: arm64.clone() },
+      PlatformResolution::Default(arm64.clone()),
+      PlatformResolution::Default(arm64.clone()),
+      PlatformResolution::Default(arm64),
+    ]);
+
+    let resolved = resolve(None);
+    assert_eq!(resolved[0], PlatformResolution::Unresolved("$BUILDPLATFORM".into()));
+    assert_eq!(resolved[0].platform(), None);
+    assert!(resolved[0].is_explicit());
+    assert_eq!(resolved[1].to_string(), "linux/arm/v7");
+    assert_eq!(resolved[3], PlatformResolution::LocalParent { parent: 0, default: None });
+    assert_eq!(resolved[3].to_string(), "the default platform (on stage 0)");
+    assert_eq!(resolved[7], PlatformResolution::Default(None));
+
+    // a parent built for the target platform is equivalent to the default
+    let native = PlatformArgs::new(
+      Platform::parse("linux/amd64").unwrap(),
+      Platform::parse("linux/amd64").unwrap(),
+    );
+    assert_eq!(
+      resolve(Some(&native))[3],
+      PlatformResolution::Default(Some(Platform::new("linux", "amd64")))
+    );
+  }
+}
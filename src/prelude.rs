@@ -0,0 +1,32 @@
+// (C) Copyright 2026 Hewlett Packard Enterprise Development LP
+
+//! The commonly used types of this crate, for glob importing:
+//!
+//! ```
+//! use dockerfile_parser::prelude::*;
+//!
+//! let dockerfile = Dockerfile::parse("FROM alpine:3.11\nRUN apk add curl").unwrap();
+//! let from: &FromInstruction = dockerfile.instructions[0].as_from().unwrap();
+//! assert_eq!(from.image_parsed.tag, Some("3.11".to_string()));
+//!
+//! let mut splicer: Splicer = dockerfile.splicer();
+//! splicer.splice(&from.image.span, "alpine:3.12");
+//! assert_eq!(splicer.content, "FROM alpine:3.12\nRUN apk add curl");
+//! ```
+//!
+//! Less common types, e.g. those of the `diagnostics` and `analysis` modules,
+//! remain available from their own modules or the crate root.
+
+pub use crate::dockerfile_parser::{Dockerfile, Instruction};
+pub use crate::error::{Error, Result};
+pub use crate::image::ImageRef;
+pub use crate::instructions::{
+  AddInstruction, ArgInstruction, CmdInstruction, CopyInstruction, EntrypointInstruction,
+  EnvInstruction, FromInstruction, HealthcheckInstruction, LabelInstruction,
+  MaintainerInstruction, MiscInstruction, OnbuildInstruction, RunInstruction,
+  ShellInstruction, StopsignalInstruction, UserInstruction, VolumeInstruction,
+  WorkdirInstruction
+};
+pub use crate::splicer::{Span, Splicer};
+pub use crate::stage::{Stage, Stages};
+pub use crate::util::SpannedString;
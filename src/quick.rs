@@ -0,0 +1,550 @@
+// (C) Copyright 2026 Hewlett Packard Enterprise Development LP
+
+//! Fast paths for extracting specific information from a Dockerfile without
+//! building a full syntax tree, e.g. to scan base images across a large
+//! number of Dockerfiles.
+//!
+//! ```
+//! use dockerfile_parser::quick::from_images;
+//!
+//! let froms = from_images(r#"
+//!   FROM --platform=$BUILDPLATFORM golang:1.22 AS build
+//!   RUN go build -o /app
+//!
+//!   FROM \
+//!     gcr.io/distroless/static
+//!   COPY --from=build /app /app
+//! "#);
+//!
+//! assert_eq!(froms[0].image, "golang:1.22");
+//! assert_eq!(froms[0].alias.as_deref(), Some("build"));
+//! assert_eq!(froms[1].image, "gcr.io/distroless/static");
+//! assert_eq!(froms[1].alias, None);
+//! ```
+
+use crate::directive::{content_start, find_directives};
+use crate::heredoc::{find_heredocs, line_at};
+use crate::splicer::Span;
+
+/// A `FROM` instruction found by `from_images()`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QuickFrom {
+  /// The image as written, without any variable substitution
+  pub image: String,
+
+  /// The stage alias, if any
+  pub alias: Option<String>,
+
+  /// The span of the image as written, i.e. `FromInstruction::image.span`
+  pub span: Span,
+}
+
+/// Returns the image and alias of every `FROM` instruction in the input, in
+/// order, without fully parsing it.
+///
+/// This scans the input line by line, handling line continuations, comments,
+/// heredoc bodies, case-insensitive instructions, and the `escape` parser
+/// directive, and is much cheaper than `Dockerfile::parse()`.
+///
+/// For any input that `Dockerfile::parse()` accepts, the results match its
+/// `FROM` instructions exactly, provided that:
+///  * the default escape character is used, as the full parser doesn't
+///    support the `escape` directive
+///  * instructions only span multiple lines using line continuations or
+///    heredocs, as Docker requires; the full parser is more lenient, e.g.
+///    quoted strings may contain unescaped line breaks
+///  * lines end with `\n` or `\r\n`, rather than a lone `\r`
+///
+/// Invalid input never causes an error, though results for it are
+/// unspecified.
+pub fn from_images(input: &str) -> Vec<QuickFrom> {
+  let escape = find_directives(input)
+    .iter()
+    .find(|d| d.is("escape"))
+    .and_then(|d| d.value.content.chars().next())
+    .unwrap_or('\\');
+
+  // heredoc bodies can contain anything, including lines that look like FROM
+  // instructions; finding them is comparatively expensive, so only bother if
+  // there might be any
+  let heredocs = if input.contains("<<") {
+    find_heredocs(input)
+  } else {
+    Vec::new()
+  };
+
+  let mut heredocs = heredocs.iter().peekable();
+  let mut segments: Vec<(usize, &str)> = Vec::new();
+  let mut froms = Vec::new();
+  let mut pos = content_start(input);
+
+  while pos < input.len() {
+    while let Some(heredoc) = heredocs.peek() {
+      if heredoc.body.span.start > pos {
+        break;
+      }
+
+      if pos <= heredoc.terminator.end {
+        pos = line_at(input, heredoc.terminator.end).2;
+      }
+
+      heredocs.next();
+    }
+
+    if pos >= input.len() {
+      break;
+    }
+
+    let (start, end, next) = line_at(input, pos);
+    pos = next;
+
+    if is_trivia(&input[start..end]) {
+      continue;
+    }
+
+    // collect all lines of the instruction, excluding continuations and any
+    // comments or empty lines between them
+    segments.clear();
+    let (mut start, mut end) = (start, end);
+    loop {
+      let line = &input[start..end];
+      let content = match strip_continuation(line, escape) {
+        Some(content) => content,
+        None => {
+          segments.push((start, line));
+          break;
+        }
+      };
+
+      segments.push((start, content));
+
+      let mut found = false;
+      while pos < input.len() {
+        let (next_start, next_end, next) = line_at(input, pos);
+        pos = next;
+
+        if !is_trivia(&input[next_start..next_end]) {
+          start = next_start;
+          end = next_end;
+          found = true;
+          break;
+        }
+      }
+
+      if !found {
+        break;
+      }
+    }
+
+    if let Some(from) = parse_from(input, &segments) {
+      froms.push(from);
+    }
+  }
+
+  froms
+}
+
+/// Determines if a line is empty or a comment.
+fn is_trivia(line: &str) -> bool {
+  let trimmed = line.trim_start_matches(&[' ', '\t'][..]);
+  trimmed.is_empty() || trimmed.starts_with('#')
+}
+
+/// If the line ends with a continuation, returns the line without it.
+fn strip_continuation(line: &str, escape: char) -> Option<&str> {
+  line.trim_end_matches(&[' ', '\t'][..]).strip_suffix(escape)
+}
+
+/// A cursor over the words of an instruction, split across the segments of
+/// each of its lines.
+struct Cursor<'s, 'a> {
+  segments: &'s [(usize, &'a str)],
+  index: usize,
+  pos: usize,
+}
+
+impl<'s, 'a> Cursor<'s, 'a> {
+  /// Skips any whitespace, returning the remainder of the current segment, if
+  /// any segments remain.
+  fn rest(&mut self) -> Option<&'a str> {
+    while let Some((_, segment)) = self.segments.get(self.index) {
+      let rest = &segment[self.pos..];
+      let trimmed = rest.trim_start_matches(&[' ', '\t'][..]);
+      if trimmed.is_empty() {
+        self.index += 1;
+        self.pos = 0;
+        continue;
+      }
+
+      self.pos += rest.len() - trimmed.len();
+      return Some(trimmed);
+    }
+
+    None
+  }
+
+  /// Returns the absolute offset of the cursor.
+  fn offset(&self) -> usize {
+    self.segments[self.index].0 + self.pos
+  }
+
+  /// Moves the cursor to the given absolute offset, or to the start of the
+  /// following segment if the offset is between segments.
+  fn seek(&mut self, offset: usize) {
+    self.index = self.segments
+      .iter()
+      .position(|(start, segment)| offset <= start + segment.len())
+      .unwrap_or(self.segments.len());
+
+    self.pos = match self.segments.get(self.index) {
+      Some((start, _)) => offset.saturating_sub(*start),
+      None => 0
+    };
+  }
+
+  /// Consumes the next whitespace-delimited word.
+  fn word(&mut self) -> Option<(Span, &'a str)> {
+    let rest = self.rest()?;
+    let len = rest.find(&[' ', '\t'][..]).unwrap_or(rest.len());
+    let start = self.offset();
+    self.pos += len;
+
+    Some((Span::new(start, start + len), &rest[..len]))
+  }
+}
+
+/// Determines if a word is a `FROM` flag, e.g. `--platform=linux/amd64`.
+fn is_flag(word: &str) -> bool {
+  let name = match word.strip_prefix("--") {
+    Some(name) => name,
+    None => return false
+  };
+
+  match name.find('=') {
+    Some(eq) => {
+      eq > 0
+        && name[..eq].bytes().all(|b| b.is_ascii_alphabetic())
+        && eq + 1 < name.len()
+    },
+    None => false
+  }
+}
+
+/// Returns the end offset of the image starting at `start`, per the grammar's
+/// `from_image` rule.
+fn image_end(input: &str, start: usize) -> usize {
+  let bytes = input.as_bytes();
+  let mut i = start;
+
+  while i < bytes.len() {
+    // a braced expansion may contain any character, e.g. `${tag:-a b}`, and
+    // even escaped line breaks, but otherwise its characters are treated
+    // individually
+    if bytes[i..].starts_with(b"${") {
+      let mut j = i + 2;
+      while j < bytes.len() && !matches!(bytes[j], b'}' | b'\r' | b'\n') {
+        if bytes[j] == b'\\' {
+          j += input[j + 1..].chars().next().map(char::len_utf8).unwrap_or(0);
+        }
+
+        j += 1;
+      }
+
+      if j < bytes.len() && bytes[j] == b'}' {
+        i = j + 1;
+        continue;
+      }
+    }
+
+    match bytes[i] {
+      b'_' | b'-' | b'.' | b':' | b'/' | b'$' | b'{' | b'}' | b'@' => i += 1,
+      b if b.is_ascii_alphanumeric() => i += 1,
+      _ => break
+    }
+  }
+
+  i
+}
+
+/// Extracts the image and alias from an instruction's segments, if it is a
+/// valid `FROM` instruction.
+///
+/// This mirrors the grammar: flags are only recognized if they're well-formed,
+/// and if no image follows, the full parser treats the instruction as an
+/// unknown `from` instruction instead.
+fn parse_from(input: &str, segments: &[(usize, &str)]) -> Option<QuickFrom> {
+  // cheaply skip other instructions before looking any closer
+  let first = segments[0].1.trim_start_matches(&[' ', '\t'][..]);
+  if first.len() < 4 || !first.as_bytes()[..4].eq_ignore_ascii_case(b"from") {
+    return None;
+  }
+
+  let mut cursor = Cursor { segments, index: 0, pos: 0 };
+  match cursor.word() {
+    Some((_, keyword)) if keyword.eq_ignore_ascii_case("from") => (),
+    _ => return None
+  }
+
+  loop {
+    let rest = cursor.rest()?;
+    let len = rest.find(&[' ', '\t'][..]).unwrap_or(rest.len());
+    if !is_flag(&rest[..len]) {
+      break;
+    }
+
+    cursor.pos += len;
+  }
+
+  let start = cursor.offset();
+  let end = image_end(input, start);
+  if end == start {
+    return None;
+  }
+
+  cursor.seek(end);
+  let alias = match (cursor.word(), cursor.word()) {
+    (Some((_, as_keyword)), Some((_, alias))) if as_keyword.eq_ignore_ascii_case("as") => {
+      Some(alias.to_string())
+    },
+    _ => None
+  };
+
+  Some(QuickFrom {
+    image: input[start..end].to_string(),
+    alias,
+    span: Span::new(start, end),
+  })
+}
+
+#[cfg(test)]
+mod tests {
+  use indoc::indoc;
+  use pretty_assertions::assert_eq;
+
+  use super::*;
+  use crate::*;
+
+  fn full_froms(dockerfile: &Dockerfile) -> Vec<QuickFrom> {
+    dockerfile.instructions
+      .iter()
+      .filter_map(|ins| ins.as_from())
+      .map(|from| QuickFrom {
+        image: from.image.content.clone(),
+        alias: from.alias.as_ref().map(|a| a.content.clone()),
+        span: from.image.span,
+      })
+      .collect()
+  }
+
+  fn summary(input: &str) -> Vec<(String, Option<String>, String)> {
+    from_images(input)
+      .into_iter()
+      .map(|f| {
+        let text = input[f.span.start..f.span.end].to_string();
+        (f.image, f.alias, text)
+      })
+      .collect()
+  }
+
+  fn from(image: &str, alias: Option<&str>) -> (String, Option<String>, String) {
+    (image.to_string(), alias.map(String::from), image.to_string())
+  }
+
+  #[test]
+  fn test_from_images() {
+    assert_eq!(summary(indoc!(r#"
+      # FROM commented:out
+      ARG TAG=3.19
+      from --platform=linux/amd64 alpine:${TAG} As base
+      RUN echo \
+        FROM not-an:instruction
+
+      FROM \
+        # comments and empty lines may interrupt continuations
+
+        --platform=$BUILDPLATFORM \
+        base \
+        AS \
+        final
+      FROMAGE x
+      From ${REGISTRY:-my registry}/app@sha256:1234
+    "#)), vec![
+      from("alpine:${TAG}", Some("base")),
+      from("base", Some("final")),
+      from("${REGISTRY:-my registry}/app@sha256:1234", None),
+    ]);
+
+    assert_eq!(summary("\u{feff}FROM alpine\r\nRUN true\r\nFROM scratch AS out\r\n"), vec![
+      from("alpine", None),
+      from("scratch", Some("out")),
+    ]);
+
+    assert_eq!(summary(""), vec![]);
+    assert_eq!(summary("FROM"), vec![]);
+  }
+
+  #[test]
+  fn test_from_images_heredocs() {
+    assert_eq!(summary(indoc!(r#"
+      FROM alpine
+      RUN <<EOF cat > /a && \
+        cat <<'END' > /b
+      FROM fake:heredoc
+      EOF
+        FROM also:fake
+      END
+      FROM real
+    "#)), vec![
+      from("alpine", None),
+      from("real", None),
+    ]);
+  }
+
+  #[test]
+  fn test_from_images_escape() {
+    assert_eq!(summary(indoc!(r#"
+      # escape=`
+      FROM `
+        mcr.microsoft.com/windows/servercore:ltsc2022 AS base
+      RUN dir c:\
+      FROM base
+    "#)), vec![
+      from("mcr.microsoft.com/windows/servercore:ltsc2022", Some("base")),
+      from("base", None),
+    ]);
+  }
+
+  /// A small xorshift generator, so mutations are random but reproducible.
+  struct Rng(u64);
+
+  impl Rng {
+    fn next(&mut self, bound: usize) -> usize {
+      self.0 ^= self.0 << 13;
+      self.0 ^= self.0 >> 7;
+      self.0 ^= self.0 << 17;
+      (self.0 % bound as u64) as usize
+    }
+  }
+
+  const FRAGMENTS: &[&str] = &[
+    " ", "\t", "\n", "\r\n", " \\\n", "\\", "#", "# FROM x\n", "\n\n",
+    "FROM ", "from alpine\n", "FROM a AS b\n", " as ", " AS stage", "--platform=linux/arm64 ",
+    "${TAG:-a b}", "$TAG", "@sha256:", ":1.0", "RUN <<EOF\nFROM fake\nEOF\n", "<<EOF",
+    "EOF\n", "RUN echo \\\n", "ARG X=1\n", "\u{feff}", "F", "M", "-", "=", "+", "${A",
+    "FROM --foo AS x\n", "FROM \"quoted\"\n", "FROM --a=${B C} d\n",
+  ];
+
+  fn mutate(input: &str, rng: &mut Rng) -> String {
+    let boundaries: Vec<usize> = (0..=input.len())
+      .filter(|i| input.is_char_boundary(*i))
+      .collect();
+
+    let i = rng.next(boundaries.len());
+    let a = boundaries[i];
+
+    match rng.next(3) {
+      // insert a fragment
+      0 => format!("{}{}{}", &input[..a], FRAGMENTS[rng.next(FRAGMENTS.len())], &input[a..]),
+
+      // delete a few characters
+      1 => {
+        let b = boundaries[(i + 1 + rng.next(8)).min(boundaries.len() - 1)];
+        format!("{}{}", &input[..a], &input[b..])
+      },
+
+      // duplicate a range
+      _ => {
+        let b = boundaries[rng.next(boundaries.len())];
+        let (a, b) = (a.min(b), a.max(b));
+        format!("{}{}{}", &input[..b], &input[a..b], &input[b..])
+      }
+    }
+  }
+
+  /// Determines if all instructions only span lines using continuations or
+  /// heredocs, the precondition for `from_images()` to match the parser.
+  fn is_line_oriented(input: &str, dockerfile: &Dockerfile) -> bool {
+    if input.replace("\r\n", "\n").contains('\r') {
+      return false;
+    }
+
+    dockerfile.instructions.iter().all(|ins| {
+      let span = ins.span();
+      let heredocs: Vec<(usize, usize)> = match ins {
+        Instruction::Run(run) => &run.heredocs[..],
+        Instruction::Copy(copy) => &copy.heredocs[..],
+        Instruction::Add(add) => &add.heredocs[..],
+        _ => &[]
+      }
+        .iter()
+        .map(|h| (h.body.span.start.saturating_sub(1), h.terminator.end))
+        .collect();
+
+      input[span.start..span.end]
+        .match_indices('\n')
+        .map(|(i, _)| span.start + i)
+        .filter(|pos| !heredocs.iter().any(|(start, end)| start <= pos && pos <= end))
+        .all(|pos| {
+          let line_start = input[..pos].rfind('\n').map(|i| i + 1).unwrap_or(0);
+          let line = input[line_start..pos].trim_end();
+          line.ends_with('\\') || is_trivia(line)
+        })
+    })
+  }
+
+  #[test]
+  fn test_from_images_matches_parser() {
+    let corpus = vec![
+      include_str!("../Dockerfile.test"),
+      indoc!(r#"
+        # syntax=docker/dockerfile:1
+        ARG BASE=alpine
+        FROM --platform=$BUILDPLATFORM ${BASE}:3.19 AS build
+        RUN <<EOF
+        apk add curl
+        EOF
+        COPY --from=build /a /b
+        FROM build as final
+        ENV FOO=bar
+        CMD ["sh"]
+      "#),
+      "from\t\\\n\n  # comment\n  alpine\tas\t\\\n  a\nrun true\n",
+    ];
+
+    // set QUICK_PROPTEST_ITERS for a longer run, e.g. 1000
+    let iterations: usize = std::env::var("QUICK_PROPTEST_ITERS")
+      .ok()
+      .and_then(|iters| iters.parse().ok())
+      .unwrap_or(50);
+
+    let mut rng = Rng(0x2545_f491_4f6c_dd1d);
+    let mut checked = 0;
+
+    for input in &corpus {
+      let mut current = input.to_string();
+
+      for _ in 0..iterations {
+        let parsed = Dockerfile::parse(&current)
+          .ok()
+          .filter(|dockerfile| is_line_oriented(&current, dockerfile));
+
+        if let Some(dockerfile) = parsed {
+          assert_eq!(from_images(&current), full_froms(&dockerfile), "input: {:?}", current);
+          checked += 1;
+        }
+
+        // mutations accumulate, but restart from the original once they no
+        // longer parse so most inputs remain valid
+        let mutated = mutate(&current, &mut rng);
+        current = if Dockerfile::parse(&mutated).is_ok() || rng.next(4) == 0 {
+          mutated
+        } else {
+          mutate(input, &mut rng)
+        };
+      }
+    }
+
+    // make sure the comparison isn't vacuous
+    assert!(checked > iterations * corpus.len() / 3, "only {} mutations parsed", checked);
+  }
+}
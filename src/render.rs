@@ -0,0 +1,457 @@
+// (C) Copyright 2026 Hewlett Packard Enterprise Development LP
+
+use std::fmt;
+
+use crate::dockerfile_parser::{Dockerfile, Instruction};
+use crate::error::*;
+use crate::heredoc::Heredoc;
+use crate::instructions::*;
+use crate::util::{QuoteStyle, ShellOrExecExpr, StringArray};
+
+/// Writes `--name=value` flags, each followed by a space. Flags without a
+/// value, e.g. `COPY --link`, are written bare.
+fn write_flags<'a>(
+  f: &mut fmt::Formatter<'_>,
+  flags: impl IntoIterator<Item = (&'a str, &'a str)>
+) -> fmt::Result {
+  for (name, value) in flags {
+    if value.is_empty() {
+      write!(f, "--{} ", name)?;
+    } else {
+      write!(f, "--{}={} ", name, value)?;
+    }
+  }
+
+  Ok(())
+}
+
+/// Writes the body and terminating delimiter of each heredoc, each starting on
+/// a new line.
+fn write_heredocs(f: &mut fmt::Formatter<'_>, heredocs: &[Heredoc]) -> fmt::Result {
+  for heredoc in heredocs {
+    write!(f, "\n{}", heredoc.body)?;
+    if !heredoc.body.content.is_empty() && !heredoc.body.content.ends_with('\n') {
+      writeln!(f)?;
+    }
+
+    write!(f, "{}", heredoc.delimiter)?;
+  }
+
+  Ok(())
+}
+
+/// Writes a JSON array of strings, e.g. `["sh", "-c"]`.
+fn write_array(f: &mut fmt::Formatter<'_>, array: &StringArray) -> fmt::Result {
+  let elements: Vec<String> = array.elements
+    .iter()
+    .map(|e| QuoteStyle::Double.quote(&e.content))
+    .collect();
+
+  write!(f, "[{}]", elements.join(", "))
+}
+
+impl fmt::Display for ShellOrExecExpr {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      ShellOrExecExpr::Shell(s) => s.fmt(f),
+      ShellOrExecExpr::Exec(array) => write_array(f, array)
+    }
+  }
+}
+
+impl fmt::Display for FromInstruction {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "FROM ")?;
+    write_flags(f, self.flags.iter().map(|flag| (flag.name.as_ref(), flag.value.as_ref())))?;
+
+    if self.image.content.is_empty() {
+      write!(f, "{}", self.image_parsed)?;
+    } else {
+      write!(f, "{}", self.image)?;
+    }
+
+    if let Some(alias) = &self.alias {
+      write!(f, " AS {}", alias)?;
+    }
+
+    Ok(())
+  }
+}
+
+impl fmt::Display for ArgInstruction {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "ARG {}", self.name)?;
+
+    match &self.value {
+      Some(value) => write!(f, "={}", self.quote_style.quote(&value.content)),
+      None => Ok(())
+    }
+  }
+}
+
+impl fmt::Display for LabelInstruction {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "LABEL")?;
+
+    for label in &self.labels {
+      write!(
+        f,
+        " {}={}",
+        QuoteStyle::Unquoted.quote(&label.name.content),
+        QuoteStyle::Unquoted.quote(&label.value.content)
+      )?;
+    }
+
+    Ok(())
+  }
+}
+
+impl fmt::Display for RunInstruction {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "RUN ")?;
+    write_flags(f, self.flags.iter().map(|flag| (flag.name.as_ref(), flag.value.as_ref())))?;
+    write!(f, "{}", self.expr)?;
+    write_heredocs(f, &self.heredocs)
+  }
+}
+
+impl fmt::Display for EntrypointInstruction {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "ENTRYPOINT {}", self.expr)
+  }
+}
+
+impl fmt::Display for CmdInstruction {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "CMD {}", self.expr)
+  }
+}
+
+impl fmt::Display for CopyInstruction {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "COPY ")?;
+    write_flags(f, self.flags.iter().map(|flag| (flag.name.as_ref(), flag.value.as_ref())))?;
+
+    for source in &self.sources {
+      write!(f, "{} ", source)?;
+    }
+
+    write!(f, "{}", self.destination)?;
+    write_heredocs(f, &self.heredocs)
+  }
+}
+
+impl fmt::Display for AddInstruction {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "ADD ")?;
+    write_flags(f, self.flags.iter().map(|flag| (flag.name.as_ref(), flag.value.as_ref())))?;
+
+    for source in &self.sources {
+      write!(f, "{} ", source)?;
+    }
+
+    write!(f, "{}", self.destination)?;
+    write_heredocs(f, &self.heredocs)
+  }
+}
+
+impl fmt::Display for EnvInstruction {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "ENV")?;
+
+    for var in &self.vars {
+      write!(f, " {}={}", var.key, var.quote_style.quote(&var.value.to_string()))?;
+    }
+
+    Ok(())
+  }
+}
+
+impl fmt::Display for VolumeInstruction {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "VOLUME ")?;
+
+    match self.form {
+      VolumeForm::Exec => write_array(f, &StringArray {
+        span: self.span,
+        elements: self.paths.clone(),
+      }),
+      VolumeForm::Shell => {
+        let paths: Vec<&str> = self.paths.iter().map(|p| p.as_ref()).collect();
+        write!(f, "{}", paths.join(" "))
+      }
+    }
+  }
+}
+
+impl fmt::Display for UserInstruction {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "USER {}", self.user)?;
+
+    match &self.group {
+      Some(group) => write!(f, ":{}", group),
+      None => Ok(())
+    }
+  }
+}
+
+impl fmt::Display for WorkdirInstruction {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "WORKDIR {}", QuoteStyle::Unquoted.quote(&self.path.content))
+  }
+}
+
+impl fmt::Display for HealthcheckInstruction {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "HEALTHCHECK")?;
+    for flag in &self.flags {
+      write!(f, " --{}={}", flag.name, flag.value)?;
+    }
+
+    match &self.command {
+      Some(HealthcheckCommand::None(_)) => write!(f, " NONE"),
+      Some(HealthcheckCommand::Cmd(cmd)) => write!(f, " {}", cmd),
+      None => Ok(())
+    }
+  }
+}
+
+impl fmt::Display for ShellInstruction {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "SHELL ")?;
+    write_array(f, &self.shell)
+  }
+}
+
+impl fmt::Display for StopsignalInstruction {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "STOPSIGNAL {}", self.signal)
+  }
+}
+
+impl fmt::Display for OnbuildInstruction {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "ONBUILD {}", self.trigger)
+  }
+}
+
+impl fmt::Display for MaintainerInstruction {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "MAINTAINER {}", self.name.content)
+  }
+}
+
+impl fmt::Display for MiscInstruction {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    let arguments = self.arguments.to_string();
+    if arguments.is_empty() {
+      write!(f, "{}", self.instruction)
+    } else {
+      write!(f, "{} {}", self.instruction, arguments.trim_start())
+    }
+  }
+}
+
+/// Formats this instruction as Dockerfile syntax, normalizing it onto a single
+/// line (aside from any heredocs) with line continuations and comments
+/// removed. Keywords are uppercased, except those of `Misc` instructions,
+/// which are kept as written.
+impl fmt::Display for Instruction {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      Instruction::From(instruction) => instruction.fmt(f),
+      Instruction::Arg(instruction) => instruction.fmt(f),
+      Instruction::Label(instruction) => instruction.fmt(f),
+      Instruction::Run(instruction) => instruction.fmt(f),
+      Instruction::Entrypoint(instruction) => instruction.fmt(f),
+      Instruction::Cmd(instruction) => instruction.fmt(f),
+      Instruction::Copy(instruction) => instruction.fmt(f),
+      Instruction::Add(instruction) => instruction.fmt(f),
+      Instruction::Env(instruction) => instruction.fmt(f),
+      Instruction::Volume(instruction) => instruction.fmt(f),
+      Instruction::User(instruction) => instruction.fmt(f),
+      Instruction::Workdir(instruction) => instruction.fmt(f),
+      Instruction::Healthcheck(instruction) => instruction.fmt(f),
+      Instruction::Shell(instruction) => instruction.fmt(f),
+      Instruction::Stopsignal(instruction) => instruction.fmt(f),
+      Instruction::Onbuild(instruction) => instruction.fmt(f),
+      Instruction::Maintainer(instruction) => instruction.fmt(f),
+      Instruction::Misc(instruction) => instruction.fmt(f),
+    }
+  }
+}
+
+impl Dockerfile {
+  /// Renders a Dockerfile from a list of instructions, e.g. built with
+  /// synthetic spans, and parses the result so that all spans refer to the
+  /// rendered `content`.
+  ///
+  /// Each instruction is rendered on its own line via its `Display` impl,
+  /// with a blank line before each `FROM` but the first instruction. Returns
+  /// a parse error if an instruction renders to invalid syntax.
+  ///
+  /// ```
+  /// use dockerfile_parser::Dockerfile;
+  ///
+  /// let base = Dockerfile::parse("FROM alpine:3.19\nRUN apk add curl").unwrap();
+  /// let mut instructions = base.instructions.clone();
+  /// instructions.push(Dockerfile::parse("CMD [\"curl\"]").unwrap().instructions.remove(0));
+  ///
+  /// let dockerfile = Dockerfile::from_instructions(instructions).unwrap();
+  /// assert_eq!(dockerfile.content, "FROM alpine:3.19\nRUN apk add curl\nCMD [\"curl\"]\n");
+  /// ```
+  pub fn from_instructions(instructions: Vec<Instruction>) -> Result<Dockerfile> {
+    let mut content = String::new();
+
+    for (index, instruction) in instructions.iter().enumerate() {
+      if index > 0 && instruction.as_from().is_some() {
+        content.push('\n');
+      }
+
+      content.push_str(&instruction.to_string());
+      content.push('\n');
+    }
+
+    Dockerfile::parse(&content)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use indoc::indoc;
+  use pretty_assertions::assert_eq;
+
+  use crate::*;
+
+  fn from(image: &str, alias: Option<&str>) -> Instruction {
+    FromInstruction {
+      span: Span::SYNTHETIC,
+      flags: vec![],
+      image: image.into(),
+      image_parsed: ImageRef::parse(image),
+      index: None,
+      alias: alias.map(SpannedString::from),
+    }.into()
+  }
+
+  fn flag(name: &str, value: &str) -> CopyFlag {
+    CopyFlag { span: Span::SYNTHETIC, name: name.into(), value: value.into() }
+  }
+
+  #[test]
+  fn test_from_instructions() -> Result<()> {
+    let instructions: Vec<Instruction> = vec![
+      from("golang:1.22", Some("build")),
+      ArgInstruction {
+        span: Span::SYNTHETIC,
+        name: "VERSION".into(),
+        value: Some("dev build".into()),
+        quote_style: QuoteStyle::Unquoted,
+      }.into(),
+      RunInstruction {
+        span: Span::SYNTHETIC,
+        flags: vec![],
+        expr: ShellOrExecExpr::Shell("go build -ldflags \"-X main.v=$VERSION\" -o /app".into()),
+        heredocs: vec![],
+      }.into(),
+      from("alpine:3.19", None),
+      CopyInstruction {
+        span: Span::SYNTHETIC,
+        flags: vec![flag("from", "build"), flag("link", "")],
+        sources: vec!["/app".into()],
+        destination: "/usr/local/bin/app".into(),
+        heredocs: vec![],
+      }.into(),
+      EnvInstruction {
+        span: Span::SYNTHETIC,
+        vars: vec![
+          EnvVar::new(Span::SYNTHETIC, "GREETING".into(), "hello world"),
+          EnvVar::new(Span::SYNTHETIC, "MODE".into(), "prod").with_quote_style(QuoteStyle::Single),
+        ],
+      }.into(),
+      LabelInstruction {
+        span: Span::SYNTHETIC,
+        labels: vec![Label::new(Span::SYNTHETIC, "org.example.name".into(), "app".into())],
+      }.into(),
+      CmdInstruction {
+        span: Span::SYNTHETIC,
+        expr: ShellOrExecExpr::Exec(vec!["app", "--say", "\"hi\""].into()),
+      }.into(),
+    ];
+
+    let d = Dockerfile::from_instructions(instructions)?;
+    assert_eq!(d.content, indoc!(r#"
+      FROM golang:1.22 AS build
+      ARG VERSION="dev build"
+      RUN go build -ldflags "-X main.v=$VERSION" -o /app
+
+      FROM alpine:3.19
+      COPY --from=build --link /app /usr/local/bin/app
+      ENV GREETING="hello world" MODE='prod'
+      LABEL org.example.name=app
+      CMD ["app", "--say", "\"hi\""]
+    "#));
+
+    let stages = d.stages();
+    assert_eq!(stages.stages.len(), 2);
+    assert_eq!(stages[0].name.as_deref(), Some("build"));
+    assert_eq!(stages[1].instructions.len(), 5);
+
+    let copy = d.instructions[4].as_copy().unwrap();
+    assert_eq!(d.text(&copy.span), "COPY --from=build --link /app /usr/local/bin/app");
+    assert_eq!(copy.link().unwrap()?, true);
+    assert_eq!(d.instructions[1].as_arg().unwrap().value.as_ref().unwrap().content, "dev build");
+    assert_eq!(
+      d.instructions[7].as_cmd().unwrap().as_exec().unwrap().as_str_vec(),
+      vec!["app", "--say", "\"hi\""]
+    );
+
+    // rendering is stable once spans are real
+    let again = Dockerfile::from_instructions(d.instructions.clone())?;
+    assert_eq!(again.content, d.content);
+    assert_eq!(again.instructions, d.instructions);
+
+    Ok(())
+  }
+
+  #[test]
+  fn test_from_instructions_normalizes() -> Result<()> {
+    let d = Dockerfile::parse(indoc!(r#"
+      from alpine:3.19 as base
+      run apk add \
+        # comment
+        curl
+      RUN <<EOF
+      echo hi
+      EOF
+      volume ["/data", "/logs"]
+      shell ["/bin/bash", \
+        "-c"]
+      onbuild RUN make
+      stopsignal SIGQUIT
+      healthcheck --interval=5s \
+        cmd curl -f localhost
+      entrypoint ./run.sh
+    "#))?;
+
+    let rendered = Dockerfile::from_instructions(d.instructions.clone())?;
+    assert_eq!(rendered.content, indoc!(r#"
+      FROM alpine:3.19 AS base
+      RUN apk add   curl
+      RUN <<EOF
+      echo hi
+      EOF
+      VOLUME ["/data", "/logs"]
+      SHELL ["/bin/bash", "-c"]
+      ONBUILD RUN make
+      STOPSIGNAL SIGQUIT
+      HEALTHCHECK --interval=5s CMD curl -f localhost
+      ENTRYPOINT ./run.sh
+    "#));
+
+    // an alias can't contain whitespace
+    assert!(Dockerfile::from_instructions(vec![from("alpine", Some("my stage"))]).is_err());
+
+    Ok(())
+  }
+}
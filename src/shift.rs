@@ -0,0 +1,202 @@
+// (C) Copyright 2026 Hewlett Packard Enterprise Development LP
+
+use crate::diagnostics::Diagnostic;
+use crate::directive::Directive;
+use crate::fixes::Fix;
+use crate::heredoc::Heredoc;
+use crate::dockerfile_parser::{Dockerfile, Instruction};
+use crate::instructions::*;
+use crate::splicer::Span;
+use crate::trivia::TriviaSpan;
+use crate::util::*;
+
+/// A visitor over all spans (and spanned strings) within a parsed value, see
+/// `VisitSpans`.
+pub(crate) trait SpanVisitor {
+  fn span(&mut self, span: &mut Span);
+
+  fn string(&mut self, _string: &mut SpannedString) {}
+
+  fn vec<T>(&mut self, _vec: &mut Vec<T>) {}
+}
+
+/// Visits all spans within a parsed value, e.g. to shift them by a fixed
+/// offset with `ShiftSpans`.
+pub(crate) trait VisitSpans {
+  fn visit_spans<V: SpanVisitor>(&mut self, visitor: &mut V);
+}
+
+/// Moves all spans within a parsed value by a fixed offset.
+pub(crate) trait ShiftSpans {
+  fn shift_spans(&mut self, delta: isize);
+}
+
+struct Shift(isize);
+
+impl SpanVisitor for Shift {
+  fn span(&mut self, span: &mut Span) {
+    if span.is_synthetic() {
+      return;
+    }
+
+    span.start = (span.start as isize + self.0) as usize;
+    span.end = (span.end as isize + self.0) as usize;
+  }
+}
+
+impl<T: VisitSpans> ShiftSpans for T {
+  fn shift_spans(&mut self, delta: isize) {
+    self.visit_spans(&mut Shift(delta));
+  }
+}
+
+impl VisitSpans for Span {
+  fn visit_spans<V: SpanVisitor>(&mut self, visitor: &mut V) {
+    visitor.span(self);
+  }
+}
+
+impl VisitSpans for SpannedString {
+  fn visit_spans<V: SpanVisitor>(&mut self, visitor: &mut V) {
+    visitor.string(self);
+    visitor.span(&mut self.span);
+  }
+}
+
+impl<T: VisitSpans> VisitSpans for Vec<T> {
+  fn visit_spans<V: SpanVisitor>(&mut self, visitor: &mut V) {
+    visitor.vec(self);
+
+    for item in self {
+      item.visit_spans(visitor);
+    }
+  }
+}
+
+impl<T: VisitSpans> VisitSpans for Box<T> {
+  fn visit_spans<V: SpanVisitor>(&mut self, visitor: &mut V) {
+    self.as_mut().visit_spans(visitor);
+  }
+}
+
+impl<T: VisitSpans> VisitSpans for Option<T> {
+  fn visit_spans<V: SpanVisitor>(&mut self, visitor: &mut V) {
+    if let Some(item) = self {
+      item.visit_spans(visitor);
+    }
+  }
+}
+
+/// Implements `VisitSpans` for a struct by visiting each of the given fields.
+macro_rules! span_fields {
+  ($type:ty: $($field:ident),+) => {
+    impl VisitSpans for $type {
+      fn visit_spans<V: SpanVisitor>(&mut self, visitor: &mut V) {
+        $(self.$field.visit_spans(visitor);)+
+      }
+    }
+  };
+}
+
+span_fields!(SpannedComment: span);
+span_fields!(StringArray: span, elements);
+span_fields!(BreakableString: span, components);
+span_fields!(FromFlag: span, name, value);
+span_fields!(FromInstruction: span, flags, image, alias);
+span_fields!(ArgInstruction: span, name, value);
+span_fields!(Label: span, name, value, separator);
+span_fields!(LabelInstruction: span, labels);
+span_fields!(RunFlag: span, name, value);
+span_fields!(Heredoc: marker, body, terminator);
+span_fields!(RunInstruction: span, flags, expr, heredocs);
+span_fields!(EntrypointInstruction: span, expr);
+span_fields!(CmdInstruction: span, expr);
+span_fields!(CopyFlag: span, name, value);
+span_fields!(CopyInstruction: span, flags, sources, destination, heredocs);
+span_fields!(AddInstruction: span, flags, sources, destination, heredocs);
+span_fields!(EnvVar: span, key, value, separator);
+span_fields!(EnvInstruction: span, vars);
+span_fields!(VolumeInstruction: span, paths);
+span_fields!(UserInstruction: span, user, group);
+span_fields!(WorkdirInstruction: span, path);
+span_fields!(HealthcheckFlag: span, name, value);
+span_fields!(HealthcheckInstruction: span, flags, command);
+span_fields!(ShellInstruction: span, shell);
+span_fields!(StopsignalInstruction: span, signal);
+span_fields!(OnbuildInstruction: span, trigger);
+span_fields!(MaintainerInstruction: span, name);
+span_fields!(MiscInstruction: span, instruction, arguments);
+
+impl VisitSpans for BreakableStringComponent {
+  fn visit_spans<V: SpanVisitor>(&mut self, visitor: &mut V) {
+    match self {
+      BreakableStringComponent::String(s) => s.visit_spans(visitor),
+      BreakableStringComponent::Comment(c) => c.visit_spans(visitor),
+    }
+  }
+}
+
+impl VisitSpans for PairSeparator {
+  fn visit_spans<V: SpanVisitor>(&mut self, visitor: &mut V) {
+    match self {
+      PairSeparator::Equals(span) | PairSeparator::Whitespace(span) => span.visit_spans(visitor),
+    }
+  }
+}
+
+impl VisitSpans for HealthcheckCommand {
+  fn visit_spans<V: SpanVisitor>(&mut self, visitor: &mut V) {
+    match self {
+      HealthcheckCommand::None(span) => span.visit_spans(visitor),
+      HealthcheckCommand::Cmd(cmd) => cmd.visit_spans(visitor),
+    }
+  }
+}
+
+impl VisitSpans for ShellOrExecExpr {
+  fn visit_spans<V: SpanVisitor>(&mut self, visitor: &mut V) {
+    match self {
+      ShellOrExecExpr::Shell(s) => s.visit_spans(visitor),
+      ShellOrExecExpr::Exec(a) => a.visit_spans(visitor),
+    }
+  }
+}
+
+impl VisitSpans for Instruction {
+  fn visit_spans<V: SpanVisitor>(&mut self, visitor: &mut V) {
+    match self {
+      Instruction::From(i) => i.visit_spans(visitor),
+      Instruction::Arg(i) => i.visit_spans(visitor),
+      Instruction::Label(i) => i.visit_spans(visitor),
+      Instruction::Run(i) => i.visit_spans(visitor),
+      Instruction::Entrypoint(i) => i.visit_spans(visitor),
+      Instruction::Cmd(i) => i.visit_spans(visitor),
+      Instruction::Copy(i) => i.visit_spans(visitor),
+      Instruction::Add(i) => i.visit_spans(visitor),
+      Instruction::Env(i) => i.visit_spans(visitor),
+      Instruction::Volume(i) => i.visit_spans(visitor),
+      Instruction::User(i) => i.visit_spans(visitor),
+      Instruction::Workdir(i) => i.visit_spans(visitor),
+      Instruction::Healthcheck(i) => i.visit_spans(visitor),
+      Instruction::Shell(i) => i.visit_spans(visitor),
+      Instruction::Stopsignal(i) => i.visit_spans(visitor),
+      Instruction::Onbuild(i) => i.visit_spans(visitor),
+      Instruction::Maintainer(i) => i.visit_spans(visitor),
+      Instruction::Misc(i) => i.visit_spans(visitor),
+    }
+  }
+}
+
+span_fields!(TriviaSpan: span);
+span_fields!(Directive: span, name, value);
+span_fields!(Diagnostic: span, related_spans, suggested_fix);
+
+span_fields!(Fix: span);
+
+/// Visits the spans of instructions and trivia, but not `base_offset`.
+impl VisitSpans for Dockerfile {
+  fn visit_spans<V: SpanVisitor>(&mut self, visitor: &mut V) {
+    self.instructions.visit_spans(visitor);
+    self.trivia.visit_spans(visitor);
+  }
+}
@@ -0,0 +1,244 @@
+// (C) Copyright 2026 Hewlett Packard Enterprise Development LP
+
+use crate::dockerfile_parser::{Instruction, InstructionKind};
+
+const REFERENCE_URL: &str = "https://docs.docker.com/reference/dockerfile/";
+
+/// A flag accepted by an instruction, e.g. `--platform` for `FROM`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FlagSpec {
+  /// The flag's name, without the leading `--`
+  pub name: &'static str,
+
+  /// If true, the flag requires a value (`--name=value`); otherwise it is a
+  /// boolean flag that may be given alone, e.g. `--link`
+  pub takes_value: bool,
+}
+
+const fn flag(name: &'static str) -> FlagSpec {
+  FlagSpec { name, takes_value: true }
+}
+
+const fn switch(name: &'static str) -> FlagSpec {
+  FlagSpec { name, takes_value: false }
+}
+
+/// Static metadata about a Dockerfile instruction, as listed in
+/// `INSTRUCTION_SPECS`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InstructionSpec {
+  /// The instruction's keyword, in upper case
+  pub keyword: &'static str,
+
+  /// The anchor of the instruction's section in the Dockerfile reference
+  pub anchor: &'static str,
+
+  /// All flags known to be accepted by the instruction
+  pub flags: &'static [FlagSpec],
+}
+
+impl InstructionSpec {
+  /// Returns the URL of this instruction's documentation.
+  pub fn reference_url(&self) -> String {
+    format!("{}#{}", REFERENCE_URL, self.anchor)
+  }
+
+  /// Finds the spec of the given flag, ignoring case.
+  pub fn flag(&self, name: &str) -> Option<&'static FlagSpec> {
+    self.flags.iter().find(|f| f.name.eq_ignore_ascii_case(name))
+  }
+
+  /// Returns the names of all known flags, e.g. for use with
+  /// `RunInstruction::unknown_flags()`.
+  pub fn flag_names(&self) -> Vec<&'static str> {
+    self.flags.iter().map(|f| f.name).collect()
+  }
+}
+
+/// Specs for every instruction recognized by Docker, used by strict flag
+/// validation (`ValidateOptions::strict_flags`) and available to editors for
+/// documentation and completion.
+pub const INSTRUCTION_SPECS: &[InstructionSpec] = &[
+  InstructionSpec {
+    keyword: "ADD",
+    anchor: "add",
+    flags: &[
+      flag("chown"), flag("chmod"), switch("link"), flag("checksum"),
+      switch("keep-git-dir"), flag("exclude"),
+    ],
+  },
+  InstructionSpec { keyword: "ARG", anchor: "arg", flags: &[] },
+  InstructionSpec { keyword: "CMD", anchor: "cmd", flags: &[] },
+  InstructionSpec {
+    keyword: "COPY",
+    anchor: "copy",
+    flags: &[
+      flag("from"), flag("chown"), flag("chmod"), switch("link"),
+      switch("parents"), flag("exclude"),
+    ],
+  },
+  InstructionSpec { keyword: "ENTRYPOINT", anchor: "entrypoint", flags: &[] },
+  InstructionSpec { keyword: "ENV", anchor: "env", flags: &[] },
+  InstructionSpec { keyword: "EXPOSE", anchor: "expose", flags: &[] },
+  InstructionSpec { keyword: "FROM", anchor: "from", flags: &[flag("platform")] },
+  InstructionSpec {
+    keyword: "HEALTHCHECK",
+    anchor: "healthcheck",
+    flags: &[
+      flag("interval"), flag("timeout"), flag("start-period"),
+      flag("start-interval"), flag("retries"),
+    ],
+  },
+  InstructionSpec { keyword: "LABEL", anchor: "label", flags: &[] },
+  InstructionSpec { keyword: "MAINTAINER", anchor: "maintainer-deprecated", flags: &[] },
+  InstructionSpec { keyword: "ONBUILD", anchor: "onbuild", flags: &[] },
+  InstructionSpec {
+    keyword: "RUN",
+    anchor: "run",
+    flags: &[flag("mount"), flag("network"), flag("security")],
+  },
+  InstructionSpec { keyword: "SHELL", anchor: "shell", flags: &[] },
+  InstructionSpec { keyword: "STOPSIGNAL", anchor: "stopsignal", flags: &[] },
+  InstructionSpec { keyword: "USER", anchor: "user", flags: &[] },
+  InstructionSpec { keyword: "VOLUME", anchor: "volume", flags: &[] },
+  InstructionSpec { keyword: "WORKDIR", anchor: "workdir", flags: &[] },
+];
+
+/// Finds the spec of the instruction with the given keyword, ignoring case.
+///
+/// ```
+/// use dockerfile_parser::instruction_spec;
+///
+/// let spec = instruction_spec("healthcheck").unwrap();
+/// assert_eq!(spec.reference_url(), "https://docs.docker.com/reference/dockerfile/#healthcheck");
+/// assert!(spec.flag("retries").unwrap().takes_value);
+/// ```
+pub fn instruction_spec(keyword: &str) -> Option<&'static InstructionSpec> {
+  INSTRUCTION_SPECS.iter().find(|s| s.keyword.eq_ignore_ascii_case(keyword))
+}
+
+impl InstructionKind {
+  /// Returns this kind's keyword, or `None` for `Misc`, which covers several
+  /// keywords.
+  pub fn keyword(&self) -> Option<&'static str> {
+    match self {
+      InstructionKind::From => Some("FROM"),
+      InstructionKind::Arg => Some("ARG"),
+      InstructionKind::Label => Some("LABEL"),
+      InstructionKind::Run => Some("RUN"),
+      InstructionKind::Entrypoint => Some("ENTRYPOINT"),
+      InstructionKind::Cmd => Some("CMD"),
+      InstructionKind::Copy => Some("COPY"),
+      InstructionKind::Add => Some("ADD"),
+      InstructionKind::Env => Some("ENV"),
+      InstructionKind::Volume => Some("VOLUME"),
+      InstructionKind::User => Some("USER"),
+      InstructionKind::Workdir => Some("WORKDIR"),
+      InstructionKind::Healthcheck => Some("HEALTHCHECK"),
+      InstructionKind::Shell => Some("SHELL"),
+      InstructionKind::Stopsignal => Some("STOPSIGNAL"),
+      InstructionKind::Onbuild => Some("ONBUILD"),
+      InstructionKind::Maintainer => Some("MAINTAINER"),
+      InstructionKind::Misc => None,
+    }
+  }
+
+  /// Returns this kind's spec, or `None` for `Misc`; see `Instruction::spec()`.
+  pub fn spec(&self) -> Option<&'static InstructionSpec> {
+    self.keyword().and_then(instruction_spec)
+  }
+
+  /// Returns the URL of this kind's documentation, e.g. for editor hovers.
+  ///
+  /// ```
+  /// use dockerfile_parser::InstructionKind;
+  ///
+  /// assert_eq!(
+  ///   InstructionKind::Copy.reference_url().as_deref(),
+  ///   Some("https://docs.docker.com/reference/dockerfile/#copy")
+  /// );
+  /// ```
+  pub fn reference_url(&self) -> Option<String> {
+    self.spec().map(|s| s.reference_url())
+  }
+
+  /// Returns the flags known to be accepted by this kind of instruction.
+  pub fn allowed_flags(&self) -> &'static [FlagSpec] {
+    self.spec().map(|s| s.flags).unwrap_or(&[])
+  }
+}
+
+impl Instruction {
+  /// Returns the spec of this instruction, looking up `Misc` instructions by
+  /// their keyword. Returns `None` for unrecognized instructions.
+  pub fn spec(&self) -> Option<&'static InstructionSpec> {
+    match self {
+      Instruction::Misc(misc) => instruction_spec(&misc.instruction.content),
+      _ => self.kind().spec()
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use pretty_assertions::assert_eq;
+
+  use super::*;
+  use crate::dockerfile_parser::{is_instruction_keyword, Dockerfile, INSTRUCTION_KEYWORDS};
+
+  fn names(flags: &[FlagSpec]) -> Vec<(&str, bool)> {
+    flags.iter().map(|f| (f.name, f.takes_value)).collect()
+  }
+
+  #[test]
+  fn test_instruction_specs() {
+    assert_eq!(
+      INSTRUCTION_SPECS.iter().map(|s| s.keyword).collect::<Vec<_>>(),
+      INSTRUCTION_KEYWORDS
+    );
+    assert!(INSTRUCTION_SPECS.iter().all(|s| is_instruction_keyword(s.keyword)));
+
+    assert_eq!(names(InstructionKind::From.allowed_flags()), vec![("platform", true)]);
+    assert_eq!(
+      names(InstructionKind::Run.allowed_flags()),
+      vec![("mount", true), ("network", true), ("security", true)]
+    );
+    assert_eq!(
+      names(InstructionKind::Copy.allowed_flags()),
+      vec![
+        ("from", true), ("chown", true), ("chmod", true), ("link", false),
+        ("parents", false), ("exclude", true),
+      ]
+    );
+    assert_eq!(
+      names(instruction_spec("add").unwrap().flags),
+      vec![
+        ("chown", true), ("chmod", true), ("link", false), ("checksum", true),
+        ("keep-git-dir", false), ("exclude", true),
+      ]
+    );
+    assert_eq!(
+      names(instruction_spec("HEALTHCHECK").unwrap().flags),
+      vec![
+        ("interval", true), ("timeout", true), ("start-period", true),
+        ("start-interval", true), ("retries", true),
+      ]
+    );
+    assert_eq!(InstructionKind::Arg.allowed_flags(), &[]);
+
+    assert_eq!(InstructionKind::Misc.reference_url(), None);
+    assert_eq!(instruction_spec("nope"), None);
+  }
+
+  #[test]
+  fn test_instruction_spec() {
+    let d = Dockerfile::parse("FROM alpine\nADD a b\nFOO bar\n").unwrap();
+
+    assert_eq!(d.instructions[0].spec().map(|s| s.keyword), Some("FROM"));
+    assert_eq!(
+      d.instructions[1].spec().map(|s| s.reference_url()).as_deref(),
+      Some("https://docs.docker.com/reference/dockerfile/#add")
+    );
+    assert_eq!(d.instructions[2].spec(), None);
+  }
+}
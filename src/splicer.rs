@@ -5,6 +5,7 @@ use std::fmt;
 
 use crate::parser::Pair;
 use crate::dockerfile_parser::Dockerfile;
+use crate::error::*;
 
 /// An offset used to adjust proceeding Spans after content has been spliced
 #[derive(Debug)]
@@ -14,6 +15,9 @@ struct SpliceOffset {
 }
 
 /// A byte-index tuple representing a span of characters in a string
+///
+/// Nodes constructed programmatically rather than parsed have no position in
+/// any input, and use the `Span::SYNTHETIC` sentinel instead.
 #[derive(PartialEq, Eq, Clone, Ord, PartialOrd, Copy)]
 pub struct Span {
   pub start: usize,
@@ -21,10 +25,21 @@ pub struct Span {
 }
 
 impl Span {
+  /// The span of a synthetic node, i.e. one that wasn't parsed from any input.
+  ///
+  /// Synthetic spans can't be spliced, are never shifted, and are rendered as
+  /// `<generated>`.
+  pub const SYNTHETIC: Span = Span { start: usize::MAX, end: usize::MAX };
+
   pub fn new(start: usize, end: usize) -> Span {
     Span { start, end }
   }
 
+  /// Determines if this is the `Span::SYNTHETIC` sentinel.
+  pub fn is_synthetic(&self) -> bool {
+    *self == Span::SYNTHETIC
+  }
+
   pub(crate) fn from_pair(record: &Pair) -> Span {
     let pest_span = record.as_span();
 
@@ -59,11 +74,23 @@ impl Span {
   /// A reference to the Dockerfile is necessary to examine the original input
   /// string. Note that if the original span crosses a newline boundary, the
   /// relative span's `end` field will be larger than the line length.
+  ///
+  /// For Dockerfiles parsed with `Dockerfile::parse_with_offset()`, the line
+  /// number is relative to the enclosing document. Positions on the
+  /// Dockerfile's first line are relative to `base_offset`, as the enclosing
+  /// document's text is unknown.
+  ///
+  /// Synthetic spans have no position and are returned as-is, on line 0.
   pub fn relative_span(&self, dockerfile: &Dockerfile) -> (usize, Span) {
+    if self.is_synthetic() {
+      return (0, *self);
+    }
+
+    let self_start = self.start - dockerfile.base_offset;
     let mut line_start_offset = 0;
-    let mut lines = 0;
+    let mut lines = dockerfile.base_line;
     for (i, c) in dockerfile.content.as_bytes().iter().enumerate() {
-      if i == self.start {
+      if i == self_start {
         break;
       }
 
@@ -73,13 +100,30 @@ impl Span {
       }
     }
 
-    let start = self.start - line_start_offset;
+    let start = self_start - line_start_offset;
     let end = start + (self.end - self.start);
 
     (lines, Span { start, end })
   }
 }
 
+/// Finds the range of items overlapping `span` within a list of items whose
+/// spans are ordered and non-overlapping, using a binary search.
+///
+/// Spans are treated as half-open: an item overlaps if it contains at least
+/// one byte of `span`. An empty `span` overlaps any item containing its
+/// position.
+pub(crate) fn overlapping<'a, T>(
+  items: &'a [T],
+  span: &Span,
+  span_of: impl Fn(&T) -> Span
+) -> &'a [T] {
+  let start = items.partition_point(|item| span_of(item).end <= span.start);
+  let end = items.partition_point(|item| span_of(item).start < span.end.max(span.start + 1));
+
+  &items[start..end.max(start)]
+}
+
 impl From<(usize, usize)> for Span {
   fn from(tup: (usize, usize)) -> Span {
     Span::new(tup.0, tup.1)
@@ -94,6 +138,10 @@ impl From<&Pair<'_>> for Span {
 
 impl fmt::Debug for Span {
   fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    if self.is_synthetic() {
+      return write!(f, "<generated>");
+    }
+
     f.debug_tuple("")
       .field(&self.start)
       .field(&self.end)
@@ -133,22 +181,32 @@ pub struct Splicer {
   /// The current content of the splice buffer.
   pub content: String,
 
-  splice_offsets: Vec<SpliceOffset>
+  splice_offsets: Vec<SpliceOffset>,
+
+  /// The offset of `content` within the document that spans refer to
+  base_offset: usize
 }
 
 impl Splicer {
   /// Creates a new Splicer from the given Dockerfile.
+  ///
+  /// If the Dockerfile was parsed with `Dockerfile::parse_with_offset()`, spans
+  /// are adjusted to apply to its `content` rather than the enclosing document.
   pub(crate) fn from(dockerfile: &Dockerfile) -> Splicer {
     Splicer {
       content: dockerfile.content.clone(),
-      splice_offsets: Vec::new()
+      splice_offsets: Vec::new(),
+      base_offset: dockerfile.base_offset
     }
   }
 
-  pub(crate) fn from_str(s: &str) -> Splicer {
+  /// Creates a new Splicer for arbitrary content, e.g. the document enclosing
+  /// a Dockerfile parsed with `Dockerfile::parse_with_offset()`.
+  pub fn new(content: &str) -> Splicer {
     Splicer {
-      content: s.to_string(),
-      splice_offsets: Vec::new()
+      content: content.to_string(),
+      splice_offsets: Vec::new(),
+      base_offset: 0
     }
   }
 
@@ -160,8 +218,13 @@ impl Splicer {
   /// Note that spans are always relative to the *original input document*.
   /// Span offsets are recalculated at call-time to account for previous calls
   /// to `splice(...)` that may have shifted one or both of the span bounds.
+  ///
+  /// Panics if `span` is synthetic; see `try_splice()`.
   pub fn splice(&mut self, span: &Span, replacement: &str) {
-    let span = span.adjust_offsets(&self.splice_offsets);
+    assert!(!span.is_synthetic(), "cannot splice a synthetic span");
+
+    let span = Span::new(span.start - self.base_offset, span.end - self.base_offset)
+      .adjust_offsets(&self.splice_offsets);
 
     // determine the splice offset (only used on subsequent splices)
     let prev_len = span.end - span.start;
@@ -176,6 +239,17 @@ impl Splicer {
     let (_, end) = rest.split_at(span.end - span.start);
     self.content = format!("{}{}{}", beginning, replacement, end);
   }
+
+  /// Like `splice()`, but returns `Error::SyntheticSpan` rather than
+  /// panicking if `span` is synthetic.
+  pub fn try_splice(&mut self, span: &Span, replacement: &str) -> Result<()> {
+    if span.is_synthetic() {
+      return Err(Error::SyntheticSpan);
+    }
+
+    self.splice(span, replacement);
+    Ok(())
+  }
 }
 
 #[cfg(test)]
@@ -233,4 +307,94 @@ mod tests {
       (5, (12, 17).into())
     );
   }
+
+  #[test]
+  fn test_parse_with_offset() {
+    let outer = indoc!(r#"
+      services:
+        app:
+          build:
+            dockerfile_inline: |
+      # syntax=docker/dockerfile:1
+      FROM alpine:3.10 as build
+      RUN echo hello; RUN echo world
+      ENV A=1
+      ENV B=2
+      COPY --from=-1 /foo /bar
+      "#);
+
+    let base_offset = outer.find("# syntax").unwrap();
+    let d = Dockerfile::parse_with_offset(&outer[base_offset..], base_offset, 4).unwrap();
+    let text = |span: &Span| &outer[span.start..span.end];
+
+    let from = d.instructions[0].as_from().unwrap();
+    assert_eq!(text(&from.image.span), "alpine:3.10");
+    assert_eq!(from.image.span.relative_span(&d), (5, (5, 16).into()));
+    assert_eq!(text(&d.syntax_directive().unwrap().value.span), "docker/dockerfile:1");
+
+    // diagnostics point into the enclosing document
+    let checks = d.run_checks();
+    assert_eq!(checks.iter().map(|c| text(&c.span)).collect::<Vec<_>>(), vec!["docker/dockerfile:1", "RUN"]);
+    assert_eq!(checks[1].span.relative_span(&d).0, 6);
+    assert_eq!(text(&d.validate()[0].span), "-1");
+
+    let (preamble, _) = d.preamble_slice();
+    assert_eq!(text(&preamble), "# syntax=docker/dockerfile:1\n");
+    let (_, span, slice) = d.stage_slices().remove(0);
+    assert_eq!(text(&span), slice);
+
+    // spans may be spliced into either the Dockerfile or the enclosing document
+    let mut splicer = d.splicer();
+    splicer.splice(&from.image.span, "alpine:3.11");
+    assert!(splicer.content.starts_with("# syntax=docker/dockerfile:1\nFROM alpine:3.11 as build"));
+
+    let mut splicer = Splicer::new(outer);
+    splicer.splice(&from.image.span, "alpine:3.11");
+    assert_eq!(splicer.content, outer.replace("alpine:3.10", "alpine:3.11"));
+
+    let stage = &d.stages()[0];
+    let group = &stage.consecutive_groups(InstructionKind::Env)[0];
+    let (span, replacement) = suggest_merge(&d, group).unwrap();
+    assert_eq!(text(&span), "ENV A=1\nENV B=2");
+    assert_eq!(replacement, "ENV A=1 B=2");
+
+    // edits are relative to the enclosing document too
+    let edit = from.image.span;
+    let edited = d.reparse_edit(edit, "alpine:3.12").unwrap();
+    assert_eq!(edited.base_offset, base_offset);
+    let mut expected = outer[base_offset..].to_string();
+    expected.replace_range(edit.start - base_offset..edit.end - base_offset, "alpine:3.12");
+    assert_eq!(edited, Dockerfile::parse_with_offset(&expected, base_offset, 4).unwrap());
+
+    // as are parse errors
+    match Dockerfile::parse_with_offset("FROM alpine\nFROM alpine RUN foo\n", base_offset, 4) {
+      Err(Error::MultipleInstructionsOnLine { line, .. }) => assert_eq!(line, 6),
+      other => panic!("unexpected result: {:?}", other),
+    }
+    match Dockerfile::parse_with_offset("FROM alpine\nARG foo=\"bar\"bar\n", base_offset, 4) {
+      Err(Error::ParseError { source }) => match source.line_col {
+        pest::error::LineColLocation::Pos((line, _)) => assert_eq!(line, 6),
+        other => panic!("unexpected location: {:?}", other),
+      },
+      other => panic!("unexpected result: {:?}", other),
+    }
+  }
+
+  #[test]
+  fn test_synthetic_span() {
+    let d = Dockerfile::parse("FROM alpine\n").unwrap();
+
+    // distinct from legitimately empty spans at offset 0
+    assert!(Span::SYNTHETIC.is_synthetic());
+    assert!(!Span::new(0, 0).is_synthetic());
+
+    assert_eq!(format!("{:?}", Span::SYNTHETIC), "<generated>");
+    assert_eq!(format!("{:?}", Span::new(0, 0)), "(0, 0)");
+    assert_eq!(Span::SYNTHETIC.relative_span(&d), (0, Span::SYNTHETIC));
+
+    let mut splicer = d.splicer();
+    assert!(matches!(splicer.try_splice(&Span::SYNTHETIC, "foo"), Err(Error::SyntheticSpan)));
+    splicer.try_splice(&Span::new(0, 0), "# foo\n").unwrap();
+    assert_eq!(splicer.content, "# foo\nFROM alpine\n");
+  }
 }
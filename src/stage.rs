@@ -1,10 +1,17 @@
 // (C) Copyright 2020 Hewlett Packard Enterprise Development LP
 
+use std::collections::{HashMap, HashSet};
 use std::fmt;
 use std::ops::Index;
 
-use crate::dockerfile_parser::{Dockerfile, Instruction};
+use crate::directive::{content_start, find_shebang};
+use crate::dockerfile_parser::{Dockerfile, Instruction, InstructionKind};
 use crate::image::ImageRef;
+use crate::instructions::EnvVar;
+use crate::platform::{PlatformArgs, StagePlatform};
+use crate::splicer::{overlapping, Span};
+use crate::util::{find_duplicates, QuoteStyle};
+use crate::variables::try_substitute_with_literals;
 
 /// The parent image of a Docker build stage
 #[derive(Debug, Eq, PartialEq, Clone)]
@@ -29,6 +36,92 @@ impl<'a> fmt::Display for StageParent<'a> {
   }
 }
 
+/// The cumulative `ENV` environment of a stage at a particular instruction, as
+/// returned by `Stage::environment_at()`.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct StageEnvironment<'a> {
+  /// Environment variables by name. References in keys and values are
+  /// substituted where possible; values that can't be fully substituted are
+  /// kept as written.
+  pub vars: HashMap<String, String>,
+
+  /// Variables with dynamic keys (e.g. `ENV ${PREFIX}_PATH=/foo`) that could
+  /// not be resolved. As their names are unknown, they may shadow any of the
+  /// entries in `vars`.
+  pub unresolved: Vec<&'a EnvVar>,
+
+  /// The variable defining each entry in `vars`.
+  pub definitions: HashMap<String, &'a EnvVar>,
+
+  /// Definitions that were later overridden by another definition of the same
+  /// key, in order.
+  pub shadowed: Vec<&'a EnvVar>,
+}
+
+/// A dependency of a stage on another stage of the same Dockerfile, as returned
+/// by `Stages::dependencies()`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StageDependency {
+  /// The index of the stage depended upon
+  pub stage: usize,
+
+  /// The span of the reference, e.g. the image of a `FROM` or the value of a
+  /// `COPY --from` flag
+  pub span: Span,
+}
+
+/// An instruction along with its position in a Dockerfile, as returned by
+/// `Dockerfile::iter_with_context()`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InstructionContext<'a> {
+  /// The instruction
+  pub instruction: &'a Instruction,
+
+  /// The index of the instruction in `Dockerfile::instructions`
+  pub index: usize,
+
+  /// The index of the stage containing the instruction, or `None` for
+  /// instructions preceding the first `FROM`
+  pub stage: Option<usize>,
+
+  /// The alias of the stage containing the instruction, as written in its
+  /// `FROM`, if any
+  pub stage_name: Option<&'a str>,
+
+  /// The index of the instruction within its stage (`Stage::instructions`),
+  /// or within the instructions preceding the first `FROM`
+  pub local_index: usize,
+}
+
+/// A cycle in the dependencies between stages, e.g. two stages using each
+/// other as their parent image via `ARG` indirection.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CycleError {
+  /// The indices of the stages forming the cycle, each depending on the next
+  /// and the last depending on the first
+  pub stages: Vec<usize>,
+
+  /// The span of each reference in the cycle, such that `spans[i]` is the
+  /// reference from `stages[i]` to the next stage
+  pub spans: Vec<Span>,
+}
+
+impl fmt::Display for CycleError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "stage dependency cycle: ")?;
+    for stage in &self.stages {
+      write!(f, "{} -> ", stage)?;
+    }
+
+    match self.stages.first() {
+      Some(first) => write!(f, "{}", first),
+      None => Ok(())
+    }
+  }
+}
+
+impl std::error::Error for CycleError {}
+
 /// A single stage in a [multi-stage build].
 ///
 /// A stage begins with (and includes) a `FROM` instruction and continues until
@@ -53,6 +146,10 @@ pub struct Stage<'a> {
   /// An ordered list of instructions in this stage.
   pub instructions: Vec<&'a Instruction>,
 
+  /// The index of each instruction in `instructions` within the Dockerfile's
+  /// full list of instructions, `Dockerfile::instructions`.
+  pub instruction_indices: Vec<usize>,
+
   /// The direct parent of this stage.
   ///
   /// If this is the first stage, it will be equal to the root stage.
@@ -60,7 +157,12 @@ pub struct Stage<'a> {
 
   /// The root image of this stage, either an external reference (possibly from
   /// a remote registry) or `scratch`.
-  pub root: StageParent<'a>
+  pub root: StageParent<'a>,
+
+  /// The stage's `FROM --platform` flag, if any. Unlike
+  /// `effective_platform()`, variables aren't resolved and parent stages
+  /// aren't considered, so `None` means the platform is unspecified.
+  pub platform: Option<StagePlatform>,
 }
 
 impl<'a> Ord for Stage<'a> {
@@ -82,6 +184,18 @@ impl<'a> PartialEq for Stage<'a> {
 }
 
 impl<'a> Stage<'a> {
+  /// Returns the span from the start of this stage's `FROM` to the end of its
+  /// last instruction.
+  ///
+  /// Unlike `Dockerfile::stage_slices()`, this excludes comments above the
+  /// `FROM` and any trailing whitespace or comments.
+  pub fn span(&self) -> Span {
+    let start = self.instructions.first().map(|i| i.span().start).unwrap_or(0);
+    let end = self.instructions.last().map(|i| i.span().end).unwrap_or(start);
+
+    Span::new(start, end)
+  }
+
   /// Finds the index, relative to this stage, of an ARG instruction defining
   /// the given name. Per the Dockerfile spec, only instructions following the
   /// ARG definition in a particular stage will have the value in scope, even
@@ -95,6 +209,201 @@ impl<'a> Stage<'a> {
         _ => None
       })
   }
+
+  /// Maps an instruction index relative to this stage to its index in
+  /// `Dockerfile::instructions`, if it exists.
+  pub fn global_index_of(&self, local_index: usize) -> Option<usize> {
+    self.instruction_indices.get(local_index).copied()
+  }
+
+  /// Maps an index in `Dockerfile::instructions` to its index relative to this
+  /// stage, if the instruction belongs to this stage.
+  pub fn local_index_of(&self, global_index: usize) -> Option<usize> {
+    self.instruction_indices.iter().position(|i| *i == global_index)
+  }
+
+  /// Computes the environment set by `ENV` instructions preceding the
+  /// instruction at `index` (relative to this stage), including any
+  /// environment inherited from a parent stage.
+  ///
+  /// Variable references are substituted using the `ARG`s and `ENV`s in scope
+  /// at each instruction. Global `ARG`s are only in scope if redeclared within
+  /// the stage, per the Dockerfile spec.
+  pub fn environment_at(&self, dockerfile: &'a Dockerfile, index: usize) -> StageEnvironment<'a> {
+    self.environment_at_impl(dockerfile, index, None)
+  }
+
+  /// Computes the environment persisted into the image built from this stage,
+  /// i.e. the variables set by `ENV` instructions in this stage and its
+  /// parents. Unlike `ENV`s, `ARG`s only exist at build time, so they're used
+  /// to substitute values but are never included.
+  pub fn runtime_environment(&self, dockerfile: &'a Dockerfile) -> StageEnvironment<'a> {
+    self.environment_at(dockerfile, self.instructions.len())
+  }
+
+  /// Like `environment_at()`, but also resolves BuildKit's automatic platform
+  /// args (e.g. `TARGETARCH`) from the given platforms.
+  ///
+  /// Per Docker's scoping rules, an automatic arg is only in scope following
+  /// an `ARG` declaring it within the stage, e.g. `ARG TARGETARCH`. Its value
+  /// takes precedence over any default given in the declaration.
+  pub fn environment_at_with_args(
+    &self,
+    dockerfile: &'a Dockerfile,
+    index: usize,
+    platform: &PlatformArgs
+  ) -> StageEnvironment<'a> {
+    self.environment_at_impl(dockerfile, index, Some(platform))
+  }
+
+  fn environment_at_impl(
+    &self,
+    dockerfile: &'a Dockerfile,
+    index: usize,
+    platform: Option<&PlatformArgs>
+  ) -> StageEnvironment<'a> {
+    let mut env = match self.parent {
+      StageParent::Stage(parent) => {
+        let stages = dockerfile.stages();
+        let parent = &stages[parent];
+        parent.environment_at_impl(dockerfile, parent.instructions.len(), platform)
+      },
+      _ => StageEnvironment::default()
+    };
+
+    let mut args: HashMap<String, String> = HashMap::new();
+    for ins in self.instructions.iter().take(index) {
+      // env vars take precedence over args of the same name
+      let context: HashMap<&str, &str> = args.iter()
+        .chain(env.vars.iter())
+        .map(|(k, v)| (k.as_str(), v.as_str()))
+        .collect();
+
+      // values in the context have already been expanded, so they're
+      // substituted as-is
+      let literals: HashSet<&str> = context.keys().copied().collect();
+      let substitute = |s: &str| {
+        try_substitute_with_literals(
+          s, &context, &literals, &mut HashSet::new(), 16, dockerfile.options.max_expansion_len
+        )
+      };
+
+      match ins {
+        Instruction::Arg(arg) => {
+          let automatic = platform.and_then(|p| p.get(&arg.name.content));
+          if let Some(value) = automatic {
+            args.insert(arg.name.content.clone(), value);
+            continue;
+          }
+
+          let value = arg.value.as_ref().or_else(|| {
+            dockerfile.get_global_arg(&arg.name.content)
+              .and_then(|global| global.value.as_ref())
+          });
+
+          if let Some(value) = value {
+            // references in single-quoted values aren't expanded
+            let literal = match &arg.value {
+              Some(_) => arg.quote_style == QuoteStyle::Single,
+              None => dockerfile.get_global_arg(&arg.name.content)
+                .map(|global| global.quote_style == QuoteStyle::Single)
+                .unwrap_or(false)
+            };
+
+            let value = if literal {
+              value.content.clone()
+            } else {
+              substitute(&value.content).unwrap_or_else(|_| value.content.clone())
+            };
+            args.insert(arg.name.content.clone(), value);
+          }
+        },
+        Instruction::Env(e) => {
+          // all values in a single ENV instruction are substituted using the
+          // environment from before the instruction
+          let mut resolved = Vec::new();
+          for var in &e.vars {
+            let key = match var.resolve_key(&context) {
+              Ok(key) => key,
+              Err(_) => {
+                env.unresolved.push(var);
+                continue;
+              }
+            };
+
+            let raw = var.value.to_string();
+            let value = substitute(&raw).unwrap_or(raw);
+            resolved.push((key, value, var));
+          }
+
+          for (key, value, var) in resolved {
+            if let Some(previous) = env.definitions.insert(key.clone(), var) {
+              env.shadowed.push(previous);
+            }
+
+            env.vars.insert(key, value);
+          }
+        },
+        _ => ()
+      }
+    }
+
+    env
+  }
+
+  /// Finds label keys set more than once within this stage, either in a
+  /// single `LABEL` instruction or across several, returning each duplicated
+  /// key with the spans of all of its occurrences. Only the last occurrence
+  /// takes effect.
+  ///
+  /// Keys are compared exactly as written, as Docker's are case-sensitive.
+  pub fn duplicate_label_keys(&self) -> Vec<(String, Vec<Span>)> {
+    find_duplicates(
+      self.instructions
+        .iter()
+        .filter_map(|ins| ins.as_label())
+        .flat_map(|label| label.labels.iter())
+        .map(|l| (l.name.content.as_str(), l.name.span))
+    )
+  }
+
+  /// Finds runs of two or more consecutive instructions of the given kind
+  /// within this stage, e.g. for suggesting that they be merged into a single
+  /// instruction with `suggest_merge()`.
+  ///
+  /// Comments between instructions don't interrupt a run.
+  pub fn consecutive_groups(&self, kind: InstructionKind) -> Vec<Vec<&'a Instruction>> {
+    let mut groups = Vec::new();
+    let mut current: Vec<&'a Instruction> = Vec::new();
+
+    for ins in &self.instructions {
+      if ins.kind() == kind {
+        current.push(ins);
+        continue;
+      }
+
+      if current.len() >= 2 {
+        groups.push(current);
+      }
+      current = Vec::new();
+    }
+
+    if current.len() >= 2 {
+      groups.push(current);
+    }
+
+    groups
+  }
+
+  /// Returns the instruction of the given kind that takes effect in this
+  /// stage, i.e. the last one, for kinds like `CMD`, `ENTRYPOINT`, and
+  /// `HEALTHCHECK` where each overrides any before it.
+  ///
+  /// Only this stage's own instructions are considered; if there are none, the
+  /// instruction may be inherited from a parent stage.
+  pub fn winning(&self, kind: InstructionKind) -> Option<&'a Instruction> {
+    self.instructions.iter().rev().find(|ins| ins.kind() == kind).copied()
+  }
 }
 
 /// A collection of stages in a [multi-stage build].
@@ -128,7 +437,7 @@ impl<'a> Stages<'a> {
     let mut stages = Stages { stages: vec![] };
     let mut next_stage_index = 0;
 
-    for ins in &dockerfile.instructions {
+    for (global_index, ins) in dockerfile.instructions.iter().enumerate() {
       if let Instruction::From(from) = ins {
         let image_name = from.image.as_ref().to_ascii_lowercase();
         let parent = if image_name == "scratch" {
@@ -149,8 +458,10 @@ impl<'a> Stages<'a> {
           index: next_stage_index,
           name: from.alias.as_ref().map(|a| a.as_ref().to_ascii_lowercase()),
           instructions: vec![ins],
+          instruction_indices: vec![global_index],
           parent,
-          root
+          root,
+          platform: StagePlatform::of(from),
         });
 
         next_stage_index += 1;
@@ -158,6 +469,7 @@ impl<'a> Stages<'a> {
         let len = stages.stages.len();
         if let Some(stage) = stages.stages.get_mut(len - 1) {
           stage.instructions.push(ins);
+          stage.instruction_indices.push(global_index);
         }
       }
     }
@@ -181,10 +493,159 @@ impl<'a> Stages<'a> {
     }
   }
 
+  /// Returns the stages whose spans (see `Stage::span()`) overlap the given
+  /// span, e.g. to find the stages touched by a diff hunk.
+  pub fn overlapping(&self, span: &Span) -> &[Stage<'a>] {
+    overlapping(&self.stages, span, |s| s.span())
+  }
+
   /// Returns an iterator over `stages`, wrapping the underlying `Vec::iter()`.
   pub fn iter(&self) -> std::slice::Iter<'_, Stage<'a>> {
     self.stages.iter()
   }
+
+  /// Finds the stage referenced by a `FROM` image or `COPY --from` value,
+  /// substituting global `ARG`s.
+  pub(crate) fn resolve_reference(&self, dockerfile: &Dockerfile, reference: &str) -> Option<usize> {
+    let resolved = ImageRef::parse(reference)
+      .try_resolve_vars(dockerfile)
+      .map(|image| image.to_string())
+      .unwrap_or_else(|_| reference.to_string());
+
+    match resolved.parse::<usize>() {
+      Ok(index) => self.stages.get(index).map(|s| s.index),
+      Err(_) => {
+        let name = resolved.to_ascii_lowercase();
+        self.stages.iter().find(|s| s.name.as_ref() == Some(&name)).map(|s| s.index)
+      }
+    }
+  }
+
+  /// Returns the stages the given stage depends on, in order: its parent, if
+  /// the `FROM` image names a stage, followed by any stages referenced by
+  /// `COPY --from`.
+  ///
+  /// Unlike `Stage::parent`, references are resolved after substituting
+  /// global `ARG`s and may refer to any stage, so dependencies may form
+  /// cycles; see `topological_order()`.
+  pub fn dependencies(&self, dockerfile: &Dockerfile, index: usize) -> Vec<StageDependency> {
+    let stage = match self.stages.get(index) {
+      Some(stage) => stage,
+      None => return Vec::new()
+    };
+
+    let mut dependencies = Vec::new();
+    for ins in &stage.instructions {
+      let (reference, span) = match ins {
+        Instruction::From(from) => (&from.image.content, from.image.span),
+        Instruction::Copy(copy) => match copy.from_flag() {
+          Some(flag) => (&flag.value.content, flag.value.span),
+          None => continue
+        },
+        _ => continue
+      };
+
+      // a stage can't be its own parent, e.g. `FROM golang AS golang`
+      let stage = match self.resolve_reference(dockerfile, reference) {
+        Some(stage) if stage == index && ins.as_from().is_some() => continue,
+        Some(stage) => stage,
+        None => continue
+      };
+
+      dependencies.push(StageDependency { stage, span });
+    }
+
+    dependencies
+  }
+
+  /// Visits `index` and then its dependencies depth-first, appending each
+  /// stage to `order` after all of its dependencies.
+  fn visit(
+    &self,
+    dockerfile: &Dockerfile,
+    index: usize,
+    visited: &mut [bool],
+    path: &mut Vec<(usize, Span)>,
+    order: &mut Vec<usize>
+  ) -> Result<(), CycleError> {
+    if visited[index] {
+      return Ok(());
+    }
+
+    for dependency in self.dependencies(dockerfile, index) {
+      if dependency.stage == index {
+        return Err(CycleError { stages: vec![index], spans: vec![dependency.span] });
+      }
+
+      if let Some(start) = path.iter().position(|(stage, _)| *stage == dependency.stage) {
+        let mut cycle = path[start..].to_vec();
+        cycle.push((index, dependency.span));
+
+        return Err(CycleError {
+          stages: cycle.iter().map(|(stage, _)| *stage).collect(),
+          spans: cycle.iter().map(|(_, span)| *span).collect(),
+        });
+      }
+
+      path.push((index, dependency.span));
+      let result = self.visit(dockerfile, dependency.stage, visited, path, order);
+      path.pop();
+      result?;
+    }
+
+    visited[index] = true;
+    order.push(index);
+
+    Ok(())
+  }
+
+  /// Returns all stage indices ordered such that each stage follows all of
+  /// its dependencies (see `dependencies()`), or an error if the dependencies
+  /// contain a cycle.
+  ///
+  /// The order is deterministic: stages are otherwise kept in the order they
+  /// are first needed, starting from the first stage.
+  ///
+  /// ```
+  /// use dockerfile_parser::Dockerfile;
+  ///
+  /// let dockerfile = Dockerfile::parse(r#"
+  ///   FROM alpine:3.12
+  ///   COPY --from=build /app /app
+  ///
+  ///   FROM alpine:3.12 AS build
+  ///   RUN make
+  /// "#).unwrap();
+  ///
+  /// let stages = dockerfile.stages();
+  /// assert_eq!(stages.topological_order(&dockerfile).unwrap(), vec![1, 0]);
+  /// ```
+  pub fn topological_order(&self, dockerfile: &Dockerfile) -> Result<Vec<usize>, CycleError> {
+    let mut visited = vec![false; self.stages.len()];
+    let mut order = Vec::new();
+
+    for index in 0..self.stages.len() {
+      self.visit(dockerfile, index, &mut visited, &mut Vec::new(), &mut order)?;
+    }
+
+    Ok(order)
+  }
+
+  /// Returns the indices of all stages needed to build the given target stage,
+  /// including the target itself, in topological order.
+  ///
+  /// Returns an error if the target's dependencies contain a cycle. Cycles
+  /// elsewhere in the Dockerfile are ignored, as in Docker.
+  pub fn reachable_from(&self, dockerfile: &Dockerfile, target: usize) -> Result<Vec<usize>, CycleError> {
+    let mut visited = vec![false; self.stages.len()];
+    let mut order = Vec::new();
+
+    if target < self.stages.len() {
+      self.visit(dockerfile, target, &mut visited, &mut Vec::new(), &mut order)?;
+    }
+
+    Ok(order)
+  }
 }
 
 impl<'a> Index<usize> for Stages<'a> {
@@ -204,11 +665,329 @@ impl<'a> IntoIterator for Stages<'a> {
   }
 }
 
+/// A lazy iterator over the stages of a Dockerfile, as returned by
+/// `Dockerfile::iter_stages_lazy()`.
+///
+/// Stages are produced one at a time as the Dockerfile's instructions are
+/// scanned, so stopping early avoids examining later instructions. Yields the
+/// same stages as `Stages::new()`.
+pub struct StageIterator<'a> {
+  dockerfile: &'a Dockerfile,
+
+  /// The global index of the next instruction to examine
+  position: usize,
+
+  /// The name and root of each stage yielded so far, to resolve parents
+  previous: Vec<(Option<String>, StageParent<'a>)>,
+}
+
+impl<'a> StageIterator<'a> {
+  pub(crate) fn new(dockerfile: &'a Dockerfile) -> StageIterator<'a> {
+    StageIterator {
+      dockerfile,
+      position: 0,
+      previous: Vec::new(),
+    }
+  }
+}
+
+impl<'a> Iterator for StageIterator<'a> {
+  type Item = Stage<'a>;
+
+  fn next(&mut self) -> Option<Stage<'a>> {
+    let instructions = &self.dockerfile.instructions;
+
+    // skip any instructions preceding the first FROM
+    let start = self.position + instructions[self.position..]
+      .iter()
+      .position(|ins| matches!(ins, Instruction::From(_)))?;
+    let from = instructions[start].as_from()?;
+
+    let end = instructions[start + 1..]
+      .iter()
+      .position(|ins| matches!(ins, Instruction::From(_)))
+      .map(|i| start + 1 + i)
+      .unwrap_or(instructions.len());
+    self.position = end;
+
+    let image_name = from.image.as_ref().to_ascii_lowercase();
+    let parent_index = self.previous
+      .iter()
+      .position(|(name, _)| name.as_deref() == Some(image_name.as_str()));
+
+    let parent = if image_name == "scratch" {
+      StageParent::Scratch
+    } else if let Some(index) = parent_index {
+      StageParent::Stage(index)
+    } else {
+      StageParent::Image(&from.image_parsed)
+    };
+
+    let root = match parent {
+      StageParent::Stage(index) => self.previous[index].1.clone(),
+      _ => parent.clone()
+    };
+
+    let name = from.alias.as_ref().map(|a| a.as_ref().to_ascii_lowercase());
+    self.previous.push((name.clone(), root.clone()));
+
+    Some(Stage {
+      index: self.previous.len() - 1,
+      name,
+      instructions: instructions[start..end].iter().collect(),
+      instruction_indices: (start..end).collect(),
+      parent,
+      root,
+      platform: StagePlatform::of(from),
+    })
+  }
+}
+
+/// Returns the byte offset at which each stage's slice of the Dockerfile
+/// begins.
+///
+/// A stage begins at the start of the line containing its `FROM`, extended
+/// backwards to include any comment lines immediately above it (with no blank
+/// line in between). Comments are never attached across a preceding
+/// instruction, parser directive, or byte order mark.
+pub(crate) fn stage_boundaries(dockerfile: &Dockerfile) -> Vec<usize> {
+  let content = &dockerfile.content;
+  let mut boundaries = Vec::new();
+
+  let mut limit = dockerfile.directives()
+    .last()
+    .map(|d| d.span.end)
+    .or_else(|| find_shebang(content).map(|s| s.end))
+    .unwrap_or_else(|| content_start(content));
+
+  for ins in &dockerfile.instructions {
+    let span = ins.span();
+
+    if let Instruction::From(_) = ins {
+      let mut start = content[..span.start].rfind('\n').map(|i| i + 1).unwrap_or(0).max(limit);
+
+      while start > limit {
+        let prev_start = content[..start - 1].rfind('\n').map(|i| i + 1).unwrap_or(0);
+        if prev_start < limit || !content[prev_start..start].trim().starts_with('#') {
+          break;
+        }
+
+        start = prev_start;
+      }
+
+      boundaries.push(start);
+    }
+
+    limit = span.end;
+  }
+
+  boundaries
+}
+
 #[cfg(test)]
 mod tests {
   use super::*;
   use indoc::indoc;
 
+  use crate::Platform;
+
+  #[test]
+  fn test_iter_with_context() {
+    let dockerfile = Dockerfile::parse(indoc!(r#"
+      ARG BASE=alpine:3.19
+      ARG VERSION
+      FROM ${BASE} AS Base
+      RUN apk add curl
+
+      FROM golang:1.22
+      ARG VERSION
+      RUN go build -o /app
+
+      FROM base AS final
+      COPY --from=1 /app /app
+      CMD ["/app"]
+    "#)).unwrap();
+
+    let contexts: Vec<_> = dockerfile.iter_with_context().collect();
+    assert_eq!(contexts.len(), dockerfile.instructions.len());
+    assert_eq!(
+      contexts.iter().map(|c| (c.index, c.stage, c.stage_name, c.local_index)).collect::<Vec<_>>(),
+      vec![
+        (0, None, None, 0),
+        (1, None, None, 1),
+        (2, Some(0), Some("Base"), 0),
+        (3, Some(0), Some("Base"), 1),
+        (4, Some(1), None, 0),
+        (5, Some(1), None, 1),
+        (6, Some(1), None, 2),
+        (7, Some(2), Some("final"), 0),
+        (8, Some(2), Some("final"), 1),
+        (9, Some(2), Some("final"), 2),
+      ]
+    );
+
+    for ctx in &contexts {
+      assert!(std::ptr::eq(ctx.instruction, &dockerfile.instructions[ctx.index]));
+    }
+
+    let stages = dockerfile.stages();
+    let copy = &contexts[8];
+    assert_eq!(stages[copy.stage.unwrap()].instructions[copy.local_index], copy.instruction);
+
+    let empty = Dockerfile::parse("ARG A\nARG B\n").unwrap();
+    assert_eq!(
+      empty.iter_with_context().map(|c| (c.stage, c.local_index)).collect::<Vec<_>>(),
+      vec![(None, 0), (None, 1)]
+    );
+  }
+
+  #[test]
+  fn test_environment_at() {
+    let dockerfile = Dockerfile::parse(indoc!(r#"
+      ARG PREFIX=APP
+      FROM alpine:3.12 as base
+      ARG PREFIX
+      ENV ${PREFIX}_HOME=/app ${PREFIX}_BIN=/app/bin
+      ENV ${UNKNOWN}_DIR=/tmp PATH="$APP_BIN:/usr/bin"
+
+      FROM base
+      ENV APP_HOME=/srv
+    "#)).unwrap();
+
+    let stages = Stages::new(&dockerfile);
+
+    let env = stages[0].environment_at(&dockerfile, 2);
+    assert!(env.vars.is_empty());
+
+    let env = stages[0].environment_at(&dockerfile, 3);
+    assert_eq!(env.vars.get("APP_HOME").map(String::as_str), Some("/app"));
+    assert_eq!(env.vars.get("APP_BIN").map(String::as_str), Some("/app/bin"));
+    assert!(env.unresolved.is_empty());
+
+    let env = stages[0].environment_at(&dockerfile, 4);
+    assert_eq!(env.vars.get("PATH").map(String::as_str), Some("/app/bin:/usr/bin"));
+    assert_eq!(env.unresolved.len(), 1);
+    assert_eq!(env.unresolved[0].key.content, "${UNKNOWN}_DIR");
+    assert!(env.unresolved[0].is_dynamic());
+
+    // env is inherited from the parent stage
+    let env = stages[1].environment_at(&dockerfile, 2);
+    assert_eq!(env.vars.get("APP_HOME").map(String::as_str), Some("/srv"));
+    assert_eq!(env.vars.get("APP_BIN").map(String::as_str), Some("/app/bin"));
+    assert_eq!(env.unresolved.len(), 1);
+
+    // overridden definitions are kept
+    assert_eq!(env.shadowed.len(), 1);
+    assert_eq!(env.shadowed[0].key.content, "${PREFIX}_HOME");
+    assert_eq!(env.definitions["APP_HOME"].value.to_string(), "/srv");
+  }
+
+  #[test]
+  fn test_environment_at_quoted_args() {
+    let dockerfile = Dockerfile::parse(indoc!(r#"
+      ARG GLOBAL='$HOME/global'
+      FROM alpine
+      ARG HOME=/root
+      ARG GLOBAL
+      ARG DOUBLE="$HOME/double"
+      ARG SINGLE='$HOME/single'
+      ENV G=$GLOBAL D=$DOUBLE S=$SINGLE
+    "#)).unwrap();
+
+    let stages = Stages::new(&dockerfile);
+    let env = stages[0].environment_at(&dockerfile, 6);
+    let get = |name: &str| env.vars.get(name).map(String::as_str);
+    assert_eq!(get("G"), Some("$HOME/global"));
+    assert_eq!(get("D"), Some("/root/double"));
+    assert_eq!(get("S"), Some("$HOME/single"));
+  }
+
+  #[test]
+  fn test_environment_at_with_args() {
+    // cross-compilation example from the Docker docs
+    let dockerfile = Dockerfile::parse(indoc!(r#"
+      FROM --platform=$BUILDPLATFORM golang:alpine AS build
+      ARG TARGETPLATFORM
+      ARG BUILDPLATFORM
+      ARG TARGETOS
+      ARG TARGETARCH=amd64
+      ENV GOOS=$TARGETOS GOARCH=$TARGETARCH
+      ENV MSG="running on $BUILDPLATFORM, building for $TARGETPLATFORM" HOST=$BUILDARCH
+
+      FROM alpine
+      ENV ARCH=$TARGETARCH
+    "#)).unwrap();
+
+    let platform = PlatformArgs::new(
+      Platform::parse("linux/arm64").unwrap(),
+      Platform::parse("linux/amd64").unwrap(),
+    );
+
+    let stages = Stages::new(&dockerfile);
+    let env = stages[0].environment_at_with_args(&dockerfile, 7, &platform);
+    let get = |name: &str| env.vars.get(name).map(String::as_str);
+    assert_eq!(get("GOOS"), Some("linux"));
+    assert_eq!(get("GOARCH"), Some("arm64"));
+    assert_eq!(get("MSG"), Some("running on linux/amd64, building for linux/arm64"));
+
+    // BUILDARCH is not declared in the stage, so it isn't in scope
+    assert_eq!(get("HOST"), Some("$BUILDARCH"));
+
+    // without platform args, undeclared values are left as-is
+    let env = stages[0].environment_at(&dockerfile, 7);
+    assert_eq!(env.vars.get("GOOS").map(String::as_str), Some("$TARGETOS"));
+    assert_eq!(env.vars.get("GOARCH").map(String::as_str), Some("amd64"));
+
+    // args don't carry over between stages
+    let env = stages[1].environment_at_with_args(&dockerfile, 2, &platform);
+    assert_eq!(env.vars.get("ARCH").map(String::as_str), Some("$TARGETARCH"));
+  }
+
+  #[test]
+  fn test_consecutive_groups() {
+    let dockerfile = Dockerfile::parse(indoc!(r#"
+      FROM alpine:3.12
+      RUN echo a
+      # comments don't break up a group
+      RUN echo b
+      COPY /foo /bar
+      RUN echo c
+      RUN echo d
+      RUN echo e
+      ENV foo=bar
+
+      FROM alpine:3.12
+      RUN echo f
+    "#)).unwrap();
+
+    let stages = Stages::new(&dockerfile);
+    let ins = &dockerfile.instructions;
+    assert_eq!(
+      stages[0].consecutive_groups(InstructionKind::Run),
+      vec![vec![&ins[1], &ins[2]], vec![&ins[4], &ins[5], &ins[6]]]
+    );
+    assert!(stages[0].consecutive_groups(InstructionKind::Env).is_empty());
+    assert!(stages[1].consecutive_groups(InstructionKind::Run).is_empty());
+  }
+
+  #[test]
+  fn test_winning() {
+    let dockerfile = Dockerfile::parse(indoc!(r#"
+      FROM alpine:3.19 AS base
+      CMD ["a"]
+      ONBUILD CMD ["b"]
+      CMD ["c"]
+
+      FROM base
+      RUN true
+    "#)).unwrap();
+
+    let stages = Stages::new(&dockerfile);
+    assert_eq!(stages[0].winning(InstructionKind::Cmd), Some(&dockerfile.instructions[3]));
+    assert_eq!(stages[0].winning(InstructionKind::Entrypoint), None);
+    assert_eq!(stages[1].winning(InstructionKind::Cmd), None);
+  }
+
   #[test]
   fn test_stages() {
     let dockerfile = Dockerfile::parse(indoc!(r#"
@@ -226,31 +1005,79 @@ mod tests {
 
     let stages = Stages::new(&dockerfile);
     assert_eq!(stages.stages.len(), 4);
-    assert_eq!(stages[1], Stage {
+
+    // stages compare equal by index alone, so compare every field
+    let assert_stage = |actual: &Stage, expected: Stage| {
+      assert_eq!(actual.index, expected.index);
+      assert_eq!(actual.name, expected.name);
+      assert_eq!(actual.instructions, expected.instructions);
+      assert_eq!(actual.instruction_indices, expected.instruction_indices);
+      assert_eq!(actual.parent, expected.parent);
+      assert_eq!(actual.root, expected.root);
+      assert_eq!(actual.platform, expected.platform);
+    };
+
+    assert_stage(&stages[1], Stage {
       index: 1,
       name: Some("build".into()),
       instructions: vec![&dockerfile.instructions[1], &dockerfile.instructions[2]],
+      instruction_indices: vec![1, 2],
       parent: StageParent::Image(&ImageRef::parse("ubuntu:18.04")),
       root: StageParent::Image(&ImageRef::parse("ubuntu:18.04")),
+      platform: None,
     });
 
-    assert_eq!(stages[2], Stage {
+    assert_stage(&stages[2], Stage {
       index: 2,
       name: Some("build2".into()),
-      instructions: dockerfile.instructions[3..5].iter().collect(),
+      instructions: dockerfile.instructions[3..6].iter().collect(),
+      instruction_indices: vec![3, 4, 5],
       parent: StageParent::Stage(1),
       root: StageParent::Image(&ImageRef::parse("ubuntu:18.04")),
+      platform: None,
     });
 
-    assert_eq!(stages[3], Stage {
+    assert_stage(&stages[3], Stage {
       index: 3,
       name: Some("build3".into()),
       instructions: vec![&dockerfile.instructions[6]],
-      parent: StageParent::Stage(2),
+      instruction_indices: vec![6],
+      parent: StageParent::Stage(1),
       root: StageParent::Image(&ImageRef::parse("ubuntu:18.04")),
+      platform: None,
     });
   }
 
+  #[test]
+  fn test_stage_instruction_indices() {
+    let dockerfile = Dockerfile::parse(indoc!(r#"
+      ARG version=3.12
+      ARG foo=bar
+      FROM alpine:$version as build
+      RUN echo "hello world"
+
+      FROM build
+      COPY /foo /bar
+      RUN true
+    "#)).unwrap();
+
+    let stages = Stages::new(&dockerfile);
+    assert_eq!(stages[0].instruction_indices, vec![2, 3]);
+    assert_eq!(stages[1].instruction_indices, vec![4, 5, 6]);
+
+    for stage in stages.iter() {
+      for (local, ins) in stage.instructions.iter().enumerate() {
+        let global = stage.global_index_of(local).unwrap();
+        assert_eq!(*ins, &dockerfile.instructions[global]);
+        assert_eq!(stage.local_index_of(global), Some(local));
+      }
+    }
+
+    assert_eq!(stages[0].global_index_of(2), None);
+    assert_eq!(stages[0].local_index_of(0), None);
+    assert_eq!(stages[1].local_index_of(3), None);
+  }
+
   #[test]
   fn test_stages_get() {
     let dockerfile = Dockerfile::parse(indoc!(r#"
@@ -266,4 +1093,250 @@ mod tests {
     assert_eq!(stages.get("1"), stages.get("build"));
     assert_eq!(stages.get("2"), stages.get("build2"));
   }
+
+  #[test]
+  fn test_stage_slices() {
+    let fixtures = &[
+      "",
+      "FROM alpine:3.12",
+      indoc!(r#"
+        # syntax=docker/dockerfile:1
+        ARG TAG=3.12
+
+        # the build stage
+        FROM alpine:$TAG as build
+        RUN echo "hello world"
+        # trailing comment
+
+        # describes the next stage
+        # over two lines
+          FROM build
+        COPY /foo /bar
+      "#),
+      "# syntax=docker/dockerfile:1\r\n# attached\r\nFROM alpine\r\nRUN true\r\n",
+      "\u{feff}FROM a\nRUN foo \\\n  # FROM b\n  bar\nFROM c\n",
+    ];
+
+    for fixture in fixtures {
+      let dockerfile = Dockerfile::parse(fixture).unwrap();
+      let (preamble_span, preamble) = dockerfile.preamble_slice();
+      assert_eq!(preamble_span.start, 0);
+
+      let mut rebuilt = preamble.to_string();
+      let mut end = preamble_span.end;
+      for (index, (stage_index, span, text)) in dockerfile.stage_slices().into_iter().enumerate() {
+        assert_eq!(stage_index, index);
+        assert_eq!(span.start, end);
+        assert_eq!(&dockerfile.content[span.start..span.end], text);
+
+        rebuilt.push_str(text);
+        end = span.end;
+      }
+
+      assert_eq!(end, fixture.len());
+      assert_eq!(&rebuilt, fixture);
+    }
+
+    let dockerfile = Dockerfile::parse(fixtures[2]).unwrap();
+    let slices = dockerfile.stage_slices();
+    assert_eq!(dockerfile.preamble_slice().1, "# syntax=docker/dockerfile:1\nARG TAG=3.12\n\n");
+    assert_eq!(slices[0].2, indoc!(r#"
+      # the build stage
+      FROM alpine:$TAG as build
+      RUN echo "hello world"
+      # trailing comment
+
+    "#));
+    assert!(slices[1].2.starts_with("# describes the next stage\n# over two lines\n  FROM build"));
+
+    let dockerfile = Dockerfile::parse(fixtures[3]).unwrap();
+    assert_eq!(dockerfile.preamble_slice().1, "# syntax=docker/dockerfile:1\r\n");
+
+    let dockerfile = Dockerfile::parse(fixtures[4]).unwrap();
+    assert_eq!(dockerfile.preamble_slice().1, "\u{feff}");
+    assert_eq!(dockerfile.stage_slices()[1].2, "FROM c\n");
+  }
+
+  #[test]
+  fn test_topological_order() {
+    // a diamond: both middle stages build on base, and app depends on both
+    let dockerfile = Dockerfile::parse(indoc!(r#"
+      FROM alpine:3.12 AS app
+      COPY --from=tools /bin/tool /bin/
+      COPY --from=libs /lib /lib
+
+      FROM alpine:3.12 AS base
+      RUN apk add build-base
+
+      FROM base AS tools
+      RUN make tools
+
+      FROM base AS libs
+      RUN make libs
+
+      FROM golang AS golang
+    "#)).unwrap();
+    let stages = dockerfile.stages();
+
+    assert_eq!(
+      stages.dependencies(&dockerfile, 0).iter().map(|d| d.stage).collect::<Vec<_>>(),
+      vec![2, 3]
+    );
+    assert_eq!(stages.dependencies(&dockerfile, 4), vec![]);
+    assert_eq!(stages.topological_order(&dockerfile), Ok(vec![1, 2, 3, 0, 4]));
+    assert_eq!(stages.reachable_from(&dockerfile, 2), Ok(vec![1, 2]));
+    assert_eq!(stages.reachable_from(&dockerfile, 0), Ok(vec![1, 2, 3, 0]));
+  }
+
+  #[test]
+  fn test_topological_order_cycle() {
+    let dockerfile = Dockerfile::parse(indoc!(r#"
+      ARG BASE=b
+      FROM $BASE AS a
+      RUN true
+
+      FROM a AS b
+      RUN true
+
+      FROM alpine:3.12 AS c
+      COPY --from=c /a /b
+
+      FROM alpine:3.12
+    "#)).unwrap();
+    let stages = dockerfile.stages();
+
+    let err = stages.topological_order(&dockerfile).unwrap_err();
+    assert_eq!(err.stages, vec![0, 1]);
+    assert_eq!(
+      err.spans.iter().map(|s| dockerfile.text(s)).collect::<Vec<_>>(),
+      vec!["$BASE", "a"]
+    );
+    assert_eq!(err.to_string(), "stage dependency cycle: 0 -> 1 -> 0");
+
+    let err = stages.reachable_from(&dockerfile, 2).unwrap_err();
+    assert_eq!(err.stages, vec![2]);
+    assert_eq!(dockerfile.text(&err.spans[0]), "c");
+
+    // cycles elsewhere don't affect unrelated stages
+    assert_eq!(stages.reachable_from(&dockerfile, 3), Ok(vec![3]));
+  }
+
+  #[test]
+  fn test_overlapping() {
+    let dockerfile = Dockerfile::parse(indoc!(r#"
+      # syntax=docker/dockerfile:1
+      ARG TAG=3.12
+      FROM alpine:$TAG as build
+      RUN apk add curl && \
+        # fetch sources
+        curl -fsSL https://example.com | sh
+
+      # the runtime stage
+      FROM build
+      COPY /foo /bar
+    "#)).unwrap();
+
+    let content = &dockerfile.content;
+    let span_of = |needle: &str| {
+      let start = content.find(needle).unwrap();
+      Span::new(start, start + needle.len())
+    };
+    let texts = |instructions: &[Instruction]| instructions
+      .iter()
+      .map(|i| { let s = i.span(); content[s.start..s.end].lines().next().unwrap() })
+      .collect::<Vec<_>>();
+
+    // a hunk spanning the stage boundary, starting mid-instruction
+    let hunk = Span::new(span_of("curl -fsSL").start, span_of("FROM build").end);
+    assert_eq!(
+      texts(dockerfile.instructions_overlapping(&hunk)),
+      vec!["RUN apk add curl && \\", "FROM build"]
+    );
+
+    let stages = dockerfile.stages();
+    let overlapping = stages.overlapping(&hunk);
+    assert_eq!(overlapping.iter().map(|s| s.index).collect::<Vec<_>>(), vec![0, 1]);
+    assert_eq!(stages.overlapping(&span_of("COPY"))[0].index, 1);
+    assert!(stages.overlapping(&span_of("ARG TAG")).is_empty());
+
+    let comments = dockerfile.comments_overlapping(&hunk);
+    assert_eq!(comments.len(), 1);
+    assert_eq!(&content[comments[0].span.start..comments[0].span.end], "# the runtime stage");
+
+    // the directive isn't part of any instruction
+    let directive = span_of("# syntax");
+    assert!(dockerfile.instructions_overlapping(&directive).is_empty());
+    assert_eq!(dockerfile.comments_overlapping(&directive).len(), 1);
+
+    // pure whitespace between instructions
+    let blank = content.find("\n\n").unwrap();
+    assert!(dockerfile.instructions_overlapping(&Span::new(blank, blank + 2)).is_empty());
+    assert!(dockerfile.comments_overlapping(&Span::new(blank, blank + 2)).is_empty());
+
+    // empty spans overlap the instruction containing them, and spans are
+    // half-open
+    let from = span_of("FROM build");
+    assert_eq!(texts(dockerfile.instructions_overlapping(&Span::new(from.start, from.start))), vec!["FROM build"]);
+    assert!(dockerfile.instructions_overlapping(&Span::new(from.end, from.end)).is_empty());
+    assert!(dockerfile.instructions_overlapping(&Span::new(content.len(), content.len())).is_empty());
+
+    // comments are also found when trivia is tracked
+    let tracked = Dockerfile::parse_with_options(content, &crate::ParseOptions { track_trivia: true, ..Default::default() }).unwrap();
+    assert_eq!(tracked.comments_overlapping(&hunk), comments);
+  }
+
+  #[test]
+  fn test_iter_stages_lazy() {
+    let fixtures = &[
+      "",
+      "ARG foo=bar",
+      indoc!(r#"
+        ARG version=3.12
+        FROM alpine:$version as build
+        RUN echo "hello world"
+
+        FROM scratch as Empty
+        FROM build as build2
+        COPY /foo /bar
+        COPY /bar /baz
+
+        FROM build2
+        FROM empty
+        FROM BUILD
+        RUN true
+      "#),
+    ];
+
+    fn fields<'a>(stage: &Stage<'a>) -> impl std::fmt::Debug + PartialEq + 'a {
+      (
+        stage.index,
+        stage.name.clone(),
+        stage.instructions.clone(),
+        stage.instruction_indices.clone(),
+        stage.parent.clone(),
+        stage.root.clone(),
+      )
+    }
+
+    for fixture in fixtures {
+      let dockerfile = Dockerfile::parse(fixture).unwrap();
+      assert_eq!(
+        dockerfile.iter_stages_lazy().map(|s| fields(&s)).collect::<Vec<_>>(),
+        Stages::new(&dockerfile).iter().map(fields).collect::<Vec<_>>()
+      );
+    }
+
+    let dockerfile = Dockerfile::parse(fixtures[2]).unwrap();
+    let stages: Vec<_> = dockerfile.iter_stages_lazy().collect();
+    assert_eq!(stages.len(), 6);
+    assert_eq!(stages[2].parent, StageParent::Stage(0));
+    assert_eq!(stages[3].root, StageParent::Image(&ImageRef::parse("alpine:$version")));
+    assert_eq!(stages[4].root, StageParent::Scratch);
+    assert_eq!(stages[5].instruction_indices, vec![9, 10]);
+
+    // stops scanning once enough stages have been found
+    let mut iter = dockerfile.iter_stages_lazy();
+    assert_eq!(iter.next().unwrap().instruction_indices, vec![1, 2]);
+    assert_eq!(iter.position, 3);
+  }
 }
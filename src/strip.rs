@@ -0,0 +1,204 @@
+// (C) Copyright 2026 Hewlett Packard Enterprise Development LP
+
+use crate::directive::BOM;
+use crate::dockerfile_parser::Dockerfile;
+use crate::heredoc::find_heredocs;
+use crate::splicer::Span;
+
+/// Maps offsets in the text returned by `Dockerfile::stripped()` to offsets in
+/// the original Dockerfile, and back.
+///
+/// Original offsets are relative to the same document as the Dockerfile's
+/// spans, i.e. they include any `base_offset`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SourceMap {
+  /// Each run of kept text, as its offset in the stripped text and its span in
+  /// the original, in order
+  segments: Vec<(usize, Span)>,
+
+  /// The original offset of the start of the stripped text, used if nothing
+  /// was kept
+  base_offset: usize,
+}
+
+impl SourceMap {
+  /// Returns the segment containing the given offset in the stripped text,
+  /// preferring the later of two segments meeting at the offset.
+  fn segment_at(&self, offset: usize) -> Option<&(usize, Span)> {
+    let index = self.segments.partition_point(|(start, _)| *start <= offset);
+    self.segments.get(index.checked_sub(1)?)
+  }
+
+  /// Maps an offset in the stripped text to the original Dockerfile.
+  ///
+  /// An offset where removed text used to be maps to the start of the
+  /// following kept text; see `span_to_original()` to map the end of a span.
+  pub fn to_original(&self, offset: usize) -> usize {
+    match self.segment_at(offset) {
+      Some((start, span)) => span.start + (offset - start),
+      None => self.base_offset
+    }
+  }
+
+  /// Maps a span in the stripped text to the original Dockerfile. Its end is
+  /// mapped to the end of the preceding kept text, so the original span
+  /// covers exactly the same text unless it spans removed lines.
+  pub fn span_to_original(&self, span: &Span) -> Span {
+    let start = self.to_original(span.start);
+    if span.end <= span.start {
+      return Span::new(start, start);
+    }
+
+    Span::new(start, self.to_original(span.end - 1) + 1)
+  }
+
+  /// Maps an offset in the original Dockerfile to the stripped text, or
+  /// returns `None` if it falls within a removed comment or blank line.
+  pub fn to_stripped(&self, offset: usize) -> Option<usize> {
+    self.segments
+      .iter()
+      .find(|(_, span)| span.start <= offset && offset <= span.end)
+      .map(|(start, span)| start + (offset - span.start))
+  }
+}
+
+impl Dockerfile {
+  /// Returns this Dockerfile's text with all comments (including parser
+  /// directives) and blank lines removed, along with a `SourceMap` relating
+  /// offsets in the stripped text to the original.
+  ///
+  /// Like Docker, comment and blank lines within multi-line instructions are
+  /// removed while their line continuations are kept, so the stripped text
+  /// parses to equivalent instructions. Heredoc bodies are kept as written.
+  ///
+  /// ```
+  /// use dockerfile_parser::{Dockerfile, Span};
+  ///
+  /// let dockerfile = Dockerfile::parse(
+  ///   "# syntax=docker/dockerfile:1\nFROM alpine\n\nRUN apk add \\\n  # curl\n  curl\n"
+  /// ).unwrap();
+  ///
+  /// let (stripped, map) = dockerfile.stripped();
+  /// assert_eq!(stripped, "FROM alpine\nRUN apk add \\\n  curl\n");
+  ///
+  /// let start = stripped.find("curl").unwrap();
+  /// let span = map.span_to_original(&Span::new(start, start + 4));
+  /// assert_eq!(&dockerfile.content[span.start..span.end], "curl");
+  /// ```
+  pub fn stripped(&self) -> (String, SourceMap) {
+    let heredocs: Vec<Span> = find_heredocs(&self.content)
+      .iter()
+      .map(|h| Span::new(h.body.span.start, h.terminator.end))
+      .collect();
+
+    let mut stripped = String::with_capacity(self.content.len());
+    let mut segments: Vec<(usize, Span)> = Vec::new();
+    let mut pos = 0;
+
+    for line in self.content.split_inclusive('\n') {
+      let start = pos;
+      pos += line.len();
+
+      let trimmed = line.trim_start_matches(BOM).trim();
+      let in_heredoc = heredocs.iter().any(|h| h.start <= start && start < h.end);
+      if !in_heredoc && (trimmed.is_empty() || trimmed.starts_with('#')) {
+        continue;
+      }
+
+      let original = Span::new(start + self.base_offset, pos + self.base_offset);
+      match segments.last_mut() {
+        Some((_, span)) if span.end == original.start => span.end = original.end,
+        _ => segments.push((stripped.len(), original)),
+      }
+
+      stripped.push_str(line);
+    }
+
+    (stripped, SourceMap { segments, base_offset: self.base_offset })
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use indoc::indoc;
+  use pretty_assertions::assert_eq;
+
+  use crate::*;
+
+  #[test]
+  fn test_stripped() {
+    let content = indoc!(r#"
+      # syntax=docker/dockerfile:1
+
+      # the build stage
+      FROM alpine:3.19 AS build
+        # indented comment
+      RUN apk add \
+        # the client
+        curl \
+        jq
+      COPY <<EOF /etc/motd
+      # not a comment
+
+      EOF
+      LABEL a=b
+      # trailing"#);
+
+    let dockerfile = Dockerfile::parse(content).unwrap();
+    let (stripped, map) = dockerfile.stripped();
+    assert_eq!(stripped, indoc!(r#"
+      FROM alpine:3.19 AS build
+      RUN apk add \
+        curl \
+        jq
+      COPY <<EOF /etc/motd
+      # not a comment
+
+      EOF
+      LABEL a=b
+    "#));
+
+    // the stripped text parses to the same instructions
+    let reparsed = Dockerfile::parse(&stripped).unwrap();
+    assert_eq!(reparsed.instructions.len(), dockerfile.instructions.len());
+
+    // spans found in the stripped text map back to the same original text
+    let jq = stripped.find("jq").unwrap();
+    let span = map.span_to_original(&Span::new(jq, jq + 2));
+    assert_eq!(&content[span.start..span.end], "jq");
+
+    let label = reparsed.instructions[3].span();
+    let span = map.span_to_original(&label);
+    assert_eq!(&content[span.start..span.end], "LABEL a=b");
+    assert_eq!(map.to_stripped(span.start), Some(label.start));
+    assert_eq!(map.to_stripped(span.end), Some(label.end));
+
+    // removed text has no stripped position
+    assert_eq!(map.to_stripped(content.find("# the build").unwrap()), None);
+    assert_eq!(map.to_stripped(content.find("# the client").unwrap()), None);
+
+    // a span covering removed lines includes them in the original
+    let run = reparsed.instructions[1].span();
+    let span = map.span_to_original(&run);
+    assert!(content[span.start..span.end].contains("# the client"));
+  }
+
+  #[test]
+  fn test_stripped_offset() {
+    let outer = "steps:\n  # comment\n  FROM alpine\n";
+    let dockerfile = Dockerfile::parse_with_offset(&outer[7..], 7, 1).unwrap();
+
+    let (stripped, map) = dockerfile.stripped();
+    assert_eq!(stripped, "  FROM alpine\n");
+
+    let from = stripped.find("FROM").unwrap();
+    let original = map.to_original(from);
+    assert_eq!(&outer[original..original + 4], "FROM");
+    assert_eq!(map.to_stripped(original), Some(from));
+
+    let (stripped, map) = Dockerfile::parse("# only a comment\n").unwrap().stripped();
+    assert_eq!(stripped, "");
+    assert_eq!(map.to_original(0), 0);
+    assert_eq!(map.to_stripped(0), None);
+  }
+}
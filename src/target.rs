@@ -0,0 +1,343 @@
+// (C) Copyright 2026 Hewlett Packard Enterprise Development LP
+
+use std::collections::HashMap;
+
+use crate::dockerfile_parser::{edit_distance, Dockerfile, Instruction, InstructionKind};
+use crate::error::*;
+use crate::image::ImageRef;
+use crate::instructions::{
+  CmdInstruction, CopyFromTarget, EntrypointInstruction, HealthcheckInstruction
+};
+use crate::stage::{Stage, StageEnvironment, StageParent, Stages};
+
+/// The image produced by building a target stage, as summarized by
+/// `TargetView::summary()`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ImageSummary<'a> {
+  /// The root image of the target stage
+  pub root: StageParent<'a>,
+
+  /// The environment of the image, including any inherited from parent stages
+  pub env: StageEnvironment<'a>,
+
+  /// The image's labels, including any inherited from parent stages, with
+  /// later definitions taking precedence. Values are kept as written.
+  pub labels: HashMap<String, String>,
+
+  /// The effective `ENTRYPOINT`, if any
+  pub entrypoint: Option<&'a EntrypointInstruction>,
+
+  /// The effective `CMD`, if any
+  pub cmd: Option<&'a CmdInstruction>,
+
+  /// The effective `HEALTHCHECK`, i.e. the last one in the target or its
+  /// parent stages, if any. A `HEALTHCHECK NONE` is kept, as it also disables
+  /// any healthcheck inherited from the root image; see
+  /// `ImageSummary::has_healthcheck()`.
+  pub healthcheck: Option<&'a HealthcheckInstruction>,
+}
+
+impl<'a> ImageSummary<'a> {
+  /// Determines if the image defines a healthcheck, i.e. its effective
+  /// `HEALTHCHECK` isn't `HEALTHCHECK NONE`. Healthchecks inherited from the
+  /// root image aren't known.
+  pub fn has_healthcheck(&self) -> bool {
+    self.healthcheck.as_ref().map(|h| !h.is_none()).unwrap_or(false)
+  }
+}
+
+/// A Dockerfile as seen when building a single target stage, like
+/// `docker build --target`; see `Dockerfile::target()`.
+///
+/// Only the target and the stages it depends on (see `Stages::dependencies()`)
+/// are included, as Docker skips all other stages.
+#[derive(Debug)]
+pub struct TargetView<'a> {
+  dockerfile: &'a Dockerfile,
+  stages: Stages<'a>,
+
+  /// The index of the target stage
+  pub target: usize,
+
+  /// The indices of all stages needed to build the target, including the
+  /// target itself, in topological order
+  pub stage_indices: Vec<usize>,
+}
+
+impl<'a> TargetView<'a> {
+  /// Returns the target stage.
+  pub fn target_stage(&self) -> &Stage<'a> {
+    &self.stages[self.target]
+  }
+
+  /// Returns all stages needed to build the target, in topological order.
+  pub fn stages(&self) -> Vec<&Stage<'a>> {
+    self.stage_indices.iter().map(|i| &self.stages[*i]).collect()
+  }
+
+  /// Determines if the given stage is needed to build the target.
+  pub fn includes(&self, index: usize) -> bool {
+    self.stage_indices.contains(&index)
+  }
+
+  /// Summarizes the image produced by the target stage, as if it were the last
+  /// stage of the Dockerfile.
+  pub fn summary(&self) -> ImageSummary<'a> {
+    // the target and its chain of parent stages, root first
+    let mut chain = vec![self.target];
+    while let StageParent::Stage(parent) = self.stages[chain[0]].parent {
+      if chain.contains(&parent) {
+        break;
+      }
+
+      chain.insert(0, parent);
+    }
+
+    let mut labels = HashMap::new();
+    for index in &chain {
+      for label in self.stages[*index].instructions.iter().filter_map(|ins| ins.as_label()) {
+        for l in &label.labels {
+          labels.insert(l.name.content.clone(), l.value.content.clone());
+        }
+      }
+    }
+
+    // later stages override their parents
+    let winning = |kind| chain.iter().rev().find_map(|index| self.stages[*index].winning(kind));
+
+    let target = self.target_stage();
+    ImageSummary {
+      root: target.root.clone(),
+      env: target.environment_at(self.dockerfile, target.instructions.len()),
+      labels,
+      entrypoint: winning(InstructionKind::Entrypoint).and_then(|ins| ins.as_entrypoint()),
+      cmd: winning(InstructionKind::Cmd).and_then(|ins| ins.as_cmd()),
+      healthcheck: winning(InstructionKind::Healthcheck).and_then(|ins| ins.as_healthcheck()),
+    }
+  }
+
+  /// Returns the external images needed to build the target, i.e. the parent
+  /// images and `COPY --from` images of included stages that don't refer to
+  /// another stage, in order and without duplicates.
+  ///
+  /// Variables are substituted where possible.
+  pub fn external_images(&self) -> Vec<ImageRef> {
+    let mut images: Vec<ImageRef> = Vec::new();
+
+    for index in &self.stage_indices {
+      let internal: Vec<_> = self.stages
+        .dependencies(self.dockerfile, *index)
+        .into_iter()
+        .map(|d| d.span)
+        .collect();
+
+      for ins in &self.stages[*index].instructions {
+        let (image, span) = match ins {
+          Instruction::From(from) if !from.image.content.eq_ignore_ascii_case("scratch") => {
+            (from.image_parsed.clone(), from.image.span)
+          },
+          Instruction::Copy(copy) => match (copy.from_target(), copy.from_flag()) {
+            (Some(CopyFromTarget::Name(name)), Some(flag)) => (ImageRef::parse(&name), flag.value.span),
+            _ => continue
+          },
+          _ => continue
+        };
+
+        if internal.contains(&span) {
+          continue;
+        }
+
+        let image = image.resolve_vars(self.dockerfile).unwrap_or(image);
+        if !images.contains(&image) {
+          images.push(image);
+        }
+      }
+    }
+
+    images
+  }
+}
+
+impl Dockerfile {
+  /// Selects a target stage by alias (ignoring case) or index, like
+  /// `docker build --target`, returning a view of only the stages needed to
+  /// build it.
+  ///
+  /// Returns `Error::UnknownTarget`, with the names of similar stages, if no
+  /// such stage exists, or `Error::StageCycle` if the target's dependencies
+  /// contain a cycle.
+  ///
+  /// ```
+  /// use dockerfile_parser::Dockerfile;
+  ///
+  /// let dockerfile = Dockerfile::parse(r#"
+  ///   FROM golang:1.21 AS build
+  ///   RUN go build -o /app
+  ///
+  ///   FROM build AS test
+  ///   RUN go test
+  ///
+  ///   FROM alpine:3.18
+  ///   COPY --from=build /app /app
+  /// "#).unwrap();
+  ///
+  /// let target = dockerfile.target("TEST").unwrap();
+  /// assert_eq!(target.stage_indices, vec![0, 1]);
+  /// assert!(dockerfile.target("tset").is_err());
+  /// ```
+  pub fn target(&self, name_or_index: &str) -> Result<TargetView<'_>> {
+    let stages = self.stages();
+
+    let target = match stages.get(name_or_index) {
+      Some(stage) => stage.index,
+      None => {
+        let mut similar: Vec<(usize, &String)> = stages
+          .iter()
+          .filter_map(|s| s.name.as_ref())
+          .map(|name| (edit_distance(name_or_index, name), name))
+          .filter(|(distance, name)| *distance <= 2.max(name.len() / 3))
+          .collect();
+        similar.sort();
+
+        return Err(Error::UnknownTarget {
+          target: name_or_index.to_string(),
+          suggestions: similar.into_iter().map(|(_, name)| name.clone()).collect(),
+        });
+      }
+    };
+
+    let stage_indices = stages
+      .reachable_from(self, target)
+      .map_err(|cycle| Error::StageCycle { cycle })?;
+
+    Ok(TargetView {
+      dockerfile: self,
+      stages,
+      target,
+      stage_indices,
+    })
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use indoc::indoc;
+  use pretty_assertions::assert_eq;
+
+  use crate::*;
+
+  const FIXTURE: &str = indoc!(r#"
+    ARG GO_VERSION=1.21
+    FROM golang:${GO_VERSION} AS build
+    LABEL stage=build
+    ENV CGO_ENABLED=0
+    RUN go build -o /app
+
+    FROM build AS test
+    COPY --from=docker.io/library/busybox:1.36 /bin/sh /bin/sh
+    LABEL stage=test
+    CMD ["go", "test"]
+
+    FROM alpine:3.18 AS release
+    COPY --from=build /app /app
+    COPY --from=gcr.io/distroless/base /etc/ssl /etc/ssl
+    ENTRYPOINT ["/app"]
+  "#);
+
+  #[test]
+  fn test_target_middle_stage() -> Result<()> {
+    let d = Dockerfile::parse(FIXTURE)?;
+
+    let target = d.target("Test")?;
+    assert_eq!(target.target, 1);
+    assert_eq!(target.stage_indices, vec![0, 1]);
+    assert!(!target.includes(2));
+    assert_eq!(d.target("1")?.target, 1);
+
+    // later stages don't contribute their images
+    assert_eq!(
+      target.external_images().iter().map(|i| i.to_string()).collect::<Vec<_>>(),
+      vec!["golang:1.21", "docker.io/library/busybox:1.36"]
+    );
+
+    let summary = target.summary();
+    assert_eq!(summary.root.to_string(), "golang:${GO_VERSION}");
+    assert_eq!(summary.env.vars.get("CGO_ENABLED").map(String::as_str), Some("0"));
+    assert_eq!(summary.labels.get("stage").map(String::as_str), Some("test"));
+    assert!(summary.cmd.is_some());
+    assert!(summary.entrypoint.is_none());
+
+    Ok(())
+  }
+
+  #[test]
+  fn test_target_last_stage() -> Result<()> {
+    let d = Dockerfile::parse(FIXTURE)?;
+
+    let target = d.target("release")?;
+    assert_eq!(target.stage_indices, vec![0, 2]);
+    assert_eq!(
+      target.external_images().iter().map(|i| i.to_string()).collect::<Vec<_>>(),
+      vec!["golang:1.21", "alpine:3.18", "gcr.io/distroless/base"]
+    );
+
+    let summary = target.summary();
+    assert!(summary.labels.is_empty());
+    assert!(summary.entrypoint.is_some());
+    assert!(summary.cmd.is_none());
+
+    Ok(())
+  }
+
+  #[test]
+  fn test_target_healthcheck() -> Result<()> {
+    let d = Dockerfile::parse(indoc!(r#"
+      FROM alpine AS base
+      HEALTHCHECK --interval=10s CMD wget -q -O- localhost
+
+      FROM base AS inherited
+      RUN apk add curl
+
+      FROM base AS disabled
+      HEALTHCHECK NONE
+
+      FROM disabled AS restored
+      HEALTHCHECK CMD ["curl", "-f", "localhost"]
+    "#))?;
+
+    let inherited = d.target("inherited")?.summary();
+    assert!(inherited.has_healthcheck());
+    assert_eq!(inherited.healthcheck.unwrap().flag("interval"), Some("10s"));
+
+    // NONE overrides the parent's healthcheck, but is still reported
+    let disabled = d.target("disabled")?.summary();
+    assert!(!disabled.has_healthcheck());
+    assert!(disabled.healthcheck.unwrap().is_none());
+
+    let restored = d.target("restored")?.summary();
+    assert!(restored.has_healthcheck());
+    assert!(restored.healthcheck.unwrap().cmd().unwrap().as_exec().is_some());
+
+    assert!(Dockerfile::parse("FROM alpine")?.target("0")?.summary().healthcheck.is_none());
+
+    Ok(())
+  }
+
+  #[test]
+  fn test_target_unknown() {
+    let d = Dockerfile::parse(FIXTURE).unwrap();
+
+    match d.target("tset") {
+      Err(Error::UnknownTarget { target, suggestions }) => {
+        assert_eq!(target, "tset");
+        assert_eq!(suggestions, vec!["test"]);
+      },
+      other => panic!("expected UnknownTarget, got {:?}", other.map(|t| t.target))
+    }
+
+    assert!(matches!(d.target("3"), Err(Error::UnknownTarget { .. })));
+
+    let cyclic = Dockerfile::parse("ARG B=b\nFROM $B AS a\nFROM a AS b\n").unwrap();
+    assert!(matches!(cyclic.target("b"), Err(Error::StageCycle { .. })));
+  }
+}
@@ -14,7 +14,7 @@ use crate::parser::{DockerfileParser, Pair, Rule};
 /// This is technically over-constrained as we could just parse any single
 /// instruction using `Rule::step`, however doing so isn't ideal for
 /// per-instruction unit tests.
-pub fn parse_single(input: &str, rule: Rule) -> Result<Instruction> {
+pub(crate) fn parse_single(input: &str, rule: Rule) -> Result<Instruction> {
   let record = DockerfileParser::parse(rule, input)
     .context(ParseError)?
     .next()
@@ -23,7 +23,7 @@ pub fn parse_single(input: &str, rule: Rule) -> Result<Instruction> {
   Instruction::try_from(record)
 }
 
-pub fn parse_direct<T, F>(input: &str, rule: Rule, func: F) -> Result<T>
+pub(crate) fn parse_direct<T, F>(input: &str, rule: Rule, func: F) -> Result<T>
 where
   F: Fn(Pair) -> Result<T>
 {
@@ -0,0 +1,190 @@
+// (C) Copyright 2026 Hewlett Packard Enterprise Development LP
+
+use crate::directive::BOM;
+use crate::dockerfile_parser::Instruction;
+use crate::splicer::Span;
+
+/// The kind of a [`TriviaSpan`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TriviaKind {
+  /// A byte order mark at the start of the file
+  ByteOrderMark,
+
+  /// Whitespace that doesn't contain any blank lines, e.g. the line break
+  /// after an instruction or a comment's indentation
+  Whitespace,
+
+  /// Whitespace containing the given number of blank lines
+  BlankLines(usize),
+
+  /// A standalone comment (including parser directives), from the `#` to the
+  /// end of the line, excluding the line break
+  Comment,
+}
+
+/// A span of text between instructions, recorded when parsing with
+/// `ParseOptions::track_trivia`.
+///
+/// Together, instruction spans and trivia spans cover the entire input without
+/// overlapping, so the original text may be reconstructed from them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TriviaSpan {
+  pub kind: TriviaKind,
+  pub span: Span,
+}
+
+/// Classifies a run of whitespace, given whether anything precedes it in the
+/// file.
+fn whitespace(text: &str, span: Span, at_file_start: bool) -> TriviaSpan {
+  let newlines = text.matches('\n').count();
+  let blank_lines = if at_file_start {
+    newlines
+  } else {
+    newlines.saturating_sub(1)
+  };
+
+  TriviaSpan {
+    kind: if blank_lines > 0 {
+      TriviaKind::BlankLines(blank_lines)
+    } else {
+      TriviaKind::Whitespace
+    },
+    span,
+  }
+}
+
+/// Splits the text of `content` between `start` and `end` into trivia spans.
+fn split_gap(content: &str, start: usize, end: usize, trivia: &mut Vec<TriviaSpan>) {
+  let mut pos = start;
+
+  while pos < end {
+    let rest = &content[pos..end];
+    let ws_len = rest.find(|c: char| !c.is_whitespace()).unwrap_or(rest.len());
+
+    if ws_len > 0 {
+      let at_file_start = content[..pos].trim_start_matches(BOM).is_empty();
+      trivia.push(whitespace(&rest[..ws_len], Span::new(pos, pos + ws_len), at_file_start));
+      pos += ws_len;
+      continue;
+    }
+
+    // anything else runs to the end of the line, excluding a trailing `\r`
+    let line_len = rest.find('\n').unwrap_or(rest.len());
+    let line_len = rest[..line_len].trim_end_matches('\r').len();
+    trivia.push(TriviaSpan {
+      kind: TriviaKind::Comment,
+      span: Span::new(pos, pos + line_len),
+    });
+    pos += line_len;
+  }
+}
+
+/// Finds all trivia between the given instructions.
+pub(crate) fn find_trivia(content: &str, instructions: &[Instruction]) -> Vec<TriviaSpan> {
+  let mut trivia = Vec::new();
+  let mut pos = 0;
+
+  if content.starts_with(BOM) {
+    trivia.push(TriviaSpan {
+      kind: TriviaKind::ByteOrderMark,
+      span: Span::new(0, BOM.len()),
+    });
+    pos = BOM.len();
+  }
+
+  for ins in instructions {
+    let span = ins.span();
+    split_gap(content, pos, span.start, &mut trivia);
+    pos = span.end;
+  }
+
+  split_gap(content, pos, content.len(), &mut trivia);
+
+  trivia
+}
+
+#[cfg(test)]
+mod tests {
+  use indoc::indoc;
+  use pretty_assertions::assert_eq;
+
+  use crate::*;
+
+  fn parse(s: &str) -> Dockerfile {
+    Dockerfile::parse_with_options(s, &ParseOptions {
+      track_trivia: true,
+      ..Default::default()
+    }).unwrap()
+  }
+
+  #[test]
+  fn test_trivia_reconstruction() {
+    let fixtures = &[
+      "",
+      "FROM alpine",
+      "\n\nFROM alpine\n\n\n",
+      indoc!(r#"
+        # syntax=docker/dockerfile:1
+        ARG TAG=3.12
+
+        # the build stage
+        FROM alpine:$TAG as build
+        RUN apk add --no-cache curl && \
+          # fetch sources
+          curl -fsSL https://example.com | sh
+          # indented comment
+
+        ENV foo=bar baz="qux quux"
+
+
+        FROM build
+          COPY --from=build /src /dst   
+        LABEL a=b
+      "#),
+      "\u{feff}# comment\r\nFROM alpine\r\n\r\nRUN foo \\\r\n  bar\r\n",
+    ];
+
+    for fixture in fixtures {
+      let dockerfile = parse(fixture);
+
+      let mut spans: Vec<Span> = dockerfile.instructions.iter().map(|i| i.span()).collect();
+      spans.extend(dockerfile.trivia.iter().map(|t| t.span));
+      spans.sort();
+
+      let rebuilt: String = spans.iter().map(|s| &fixture[s.start..s.end]).collect();
+      assert_eq!(&rebuilt, fixture);
+
+      // spans must be contiguous
+      let mut pos = 0;
+      for span in spans {
+        assert_eq!(span.start, pos, "{:?}", fixture);
+        pos = span.end;
+      }
+    }
+  }
+
+  #[test]
+  fn test_trivia_kinds() {
+    let content = "\u{feff}# comment\r\nFROM alpine\r\n\r\n\r\n  # indented\nRUN true\n";
+    let dockerfile = parse(content);
+
+    assert_eq!(
+      dockerfile.trivia
+        .iter()
+        .map(|t| (t.kind, &content[t.span.start..t.span.end]))
+        .collect::<Vec<_>>(),
+      vec![
+        (TriviaKind::ByteOrderMark, "\u{feff}"),
+        (TriviaKind::Comment, "# comment"),
+        (TriviaKind::Whitespace, "\r\n"),
+        (TriviaKind::BlankLines(2), "\r\n\r\n\r\n  "),
+        (TriviaKind::Comment, "# indented"),
+        (TriviaKind::Whitespace, "\n"),
+        (TriviaKind::Whitespace, "\n"),
+      ]
+    );
+
+    assert_eq!(parse("\n\nFROM alpine").trivia[0].kind, TriviaKind::BlankLines(2));
+    assert!(Dockerfile::parse(content).unwrap().trivia.is_empty());
+  }
+}
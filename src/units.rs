@@ -0,0 +1,273 @@
+// (C) Copyright 2026 Hewlett Packard Enterprise Development LP
+
+use std::fmt;
+use std::time::Duration;
+
+/// An error parsing a duration or size, e.g. the value of `HEALTHCHECK
+/// --interval` or of a tmpfs mount's `size` option.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnitError {
+  /// The value that failed to parse
+  pub value: String,
+
+  /// The byte offset within `value` of the offending character, or of the
+  /// number lacking a unit
+  pub offset: usize,
+
+  /// Why the value is invalid, e.g. `missing unit, expected e.g. s or m`
+  pub reason: String,
+}
+
+impl UnitError {
+  fn new(value: &str, offset: usize, reason: &str) -> UnitError {
+    UnitError {
+      value: value.to_string(),
+      offset,
+      reason: reason.to_string(),
+    }
+  }
+}
+
+impl fmt::Display for UnitError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "invalid value '{}' at offset {}: {}", self.value, self.offset, self.reason)
+  }
+}
+
+impl std::error::Error for UnitError {}
+
+/// Returns the length of the number (digits and decimal points) at the start
+/// of `s`.
+fn number_len(s: &str) -> usize {
+  s.find(|c: char| !(c.is_ascii_digit() || c == '.')).unwrap_or(s.len())
+}
+
+/// Parses a duration like Go's `time.ParseDuration()`, as used by `HEALTHCHECK`
+/// flags, e.g. `30s`, `1m30s`, or `1.5h`.
+///
+/// Valid units are `ns`, `us` (or `µs`), `ms`, `s`, `m`, and `h`. Negative
+/// durations are rejected, as Docker rejects them.
+///
+/// ```
+/// use std::time::Duration;
+/// use dockerfile_parser::parse_duration;
+///
+/// assert_eq!(parse_duration("1m30s").unwrap(), Duration::from_secs(90));
+/// assert_eq!(parse_duration("250ms").unwrap(), Duration::from_millis(250));
+/// assert!(parse_duration("-5s").is_err());
+///
+/// let err = parse_duration("1m5x").unwrap_err();
+/// assert_eq!(err.offset, 3);
+/// assert_eq!(err.reason, "unknown unit, expected one of ns, us, ms, s, m, or h");
+/// ```
+pub fn parse_duration(s: &str) -> Result<Duration, UnitError> {
+  let (negative, mut pos) = match s.chars().next() {
+    Some('-') => (true, 1),
+    Some('+') => (false, 1),
+    _ => (false, 0)
+  };
+
+  if &s[pos..] == "0" {
+    return Ok(Duration::from_secs(0));
+  }
+
+  if pos == s.len() {
+    return Err(UnitError::new(s, pos, "expected e.g. 30s"));
+  }
+
+  let mut nanos: f64 = 0.0;
+  while pos < s.len() {
+    let len = number_len(&s[pos..]);
+    let number: f64 = match &s[pos..pos + len] {
+      "" | "." => return Err(UnitError::new(s, pos, "expected a number")),
+      number => number.parse().map_err(|_| UnitError::new(s, pos, "expected a number"))?
+    };
+
+    let unit_start = pos + len;
+    let unit_len = s[unit_start..]
+      .find(|c: char| c.is_ascii_digit() || c == '.')
+      .unwrap_or(s.len() - unit_start);
+    let scale = match &s[unit_start..unit_start + unit_len] {
+      "ns" => 1.0,
+      "us" | "µs" | "μs" => 1e3,
+      "ms" => 1e6,
+      "s" => 1e9,
+      "m" => 60e9,
+      "h" => 3600e9,
+      "" => return Err(UnitError::new(s, pos, "missing unit, expected e.g. s or m")),
+      _ => return Err(UnitError::new(
+        s, unit_start, "unknown unit, expected one of ns, us, ms, s, m, or h"
+      ))
+    };
+
+    nanos += number * scale;
+    pos = unit_start + unit_len;
+  }
+
+  if negative && nanos > 0.0 {
+    return Err(UnitError::new(s, 0, "durations cannot be negative"));
+  }
+
+  // like Go, durations are limited to the range of a signed 64-bit count of
+  // nanoseconds
+  if nanos.round() > i64::MAX as f64 {
+    return Err(UnitError::new(s, 0, "duration is too large"));
+  }
+
+  Ok(Duration::from_nanos(nanos.round() as u64))
+}
+
+/// Parses a size in bytes like Docker's `RAMInBytes()`, as used by the `size`
+/// option of tmpfs mounts, e.g. `512`, `128m`, or `1.5GiB`.
+///
+/// Units are case-insensitive and binary, i.e. `k` is 1024 bytes, and may be
+/// followed by `i` and `b` (e.g. `kb` or `KiB`, both 1024 bytes). Valid unit
+/// prefixes are `k`, `m`, `g`, `t`, and `p`; without one, the size is in
+/// bytes. A single space may separate the number and unit. Fractional sizes
+/// are rounded down to a whole number of bytes.
+///
+/// ```
+/// use dockerfile_parser::parse_size;
+///
+/// assert_eq!(parse_size("128m").unwrap(), 128 * 1024 * 1024);
+/// assert_eq!(parse_size("1.5k").unwrap(), 1536);
+///
+/// let err = parse_size("64x").unwrap_err();
+/// assert_eq!(err.offset, 2);
+/// assert!(parse_size("-1g").is_err());
+/// ```
+pub fn parse_size(s: &str) -> Result<u64, UnitError> {
+  if s.starts_with('-') {
+    return Err(UnitError::new(s, 0, "sizes cannot be negative"));
+  }
+
+  let len = number_len(s);
+  let number: f64 = match &s[..len] {
+    "" | "." => return Err(UnitError::new(s, 0, "expected a number, e.g. 64m")),
+    number => number.parse().map_err(|_| UnitError::new(s, 0, "expected a number, e.g. 64m"))?
+  };
+
+  let unit_start = if s[len..].starts_with(' ') { len + 1 } else { len };
+  let unit = s[unit_start..].to_ascii_lowercase();
+  let prefix = unit.strip_suffix('b').unwrap_or(&unit);
+  let prefix = prefix.strip_suffix('i').unwrap_or(prefix);
+  let scale: u64 = match prefix {
+    "" => 1,
+    "k" => 1 << 10,
+    "m" => 1 << 20,
+    "g" => 1 << 30,
+    "t" => 1 << 40,
+    "p" => 1 << 50,
+    _ => return Err(UnitError::new(
+      s, unit_start, "unknown unit, expected one of k, m, g, t, or p"
+    ))
+  };
+
+  let bytes = (number * scale as f64).trunc();
+  if bytes > i64::MAX as f64 {
+    return Err(UnitError::new(s, 0, "size is too large"));
+  }
+
+  Ok(bytes as u64)
+}
+
+#[cfg(test)]
+mod tests {
+  use pretty_assertions::assert_eq;
+
+  use super::*;
+
+  fn duration_error(s: &str) -> (usize, String) {
+    let err = parse_duration(s).unwrap_err();
+    assert_eq!(err.value, s);
+    (err.offset, err.reason)
+  }
+
+  fn size_error(s: &str) -> (usize, String) {
+    let err = parse_size(s).unwrap_err();
+    assert_eq!(err.value, s);
+    (err.offset, err.reason)
+  }
+
+  #[test]
+  fn test_parse_duration() {
+    assert_eq!(parse_duration("30s").unwrap(), Duration::from_secs(30));
+    assert_eq!(parse_duration("+30s").unwrap(), Duration::from_secs(30));
+    assert_eq!(parse_duration("1.5h").unwrap(), Duration::from_secs(5400));
+    assert_eq!(parse_duration(".5s").unwrap(), Duration::from_millis(500));
+    assert_eq!(parse_duration("1.s").unwrap(), Duration::from_secs(1));
+    assert_eq!(parse_duration("2m3s4ms").unwrap(), Duration::from_millis(123_004));
+    assert_eq!(parse_duration("1h1m1s").unwrap(), Duration::from_secs(3661));
+    assert_eq!(parse_duration("10us").unwrap(), Duration::from_micros(10));
+    assert_eq!(parse_duration("10µs").unwrap(), Duration::from_micros(10));
+    assert_eq!(parse_duration("100ns").unwrap(), Duration::from_nanos(100));
+    assert_eq!(parse_duration("0").unwrap(), Duration::from_secs(0));
+    assert_eq!(parse_duration("-0").unwrap(), Duration::from_secs(0));
+    assert_eq!(parse_duration("-0s").unwrap(), Duration::from_secs(0));
+    assert_eq!(parse_duration("0.000000001s").unwrap(), Duration::from_nanos(1));
+
+    for invalid in &["", "-", "s", "5", "5x", "1.2.3s", "-1ms", "5s-", "$INTERVAL", "1 s"] {
+      assert!(parse_duration(invalid).is_err(), "{} should be invalid", invalid);
+    }
+  }
+
+  #[test]
+  fn test_parse_duration_errors() {
+    assert_eq!(duration_error(""), (0, "expected e.g. 30s".into()));
+    assert_eq!(duration_error("-"), (1, "expected e.g. 30s".into()));
+    assert_eq!(duration_error("s"), (0, "expected a number".into()));
+    assert_eq!(duration_error("5s."), (2, "expected a number".into()));
+    assert_eq!(duration_error("1.2.3s"), (0, "expected a number".into()));
+    assert_eq!(duration_error("10"), (0, "missing unit, expected e.g. s or m".into()));
+    assert_eq!(duration_error("1m30"), (2, "missing unit, expected e.g. s or m".into()));
+    assert_eq!(
+      duration_error("1m5sec"),
+      (3, "unknown unit, expected one of ns, us, ms, s, m, or h".into())
+    );
+    assert_eq!(
+      duration_error("5S"),
+      (1, "unknown unit, expected one of ns, us, ms, s, m, or h".into())
+    );
+    assert_eq!(duration_error("-1.5s"), (0, "durations cannot be negative".into()));
+    assert_eq!(duration_error("3000000h"), (0, "duration is too large".into()));
+
+    assert_eq!(
+      parse_duration("5x").unwrap_err().to_string(),
+      "invalid value '5x' at offset 1: unknown unit, expected one of ns, us, ms, s, m, or h"
+    );
+  }
+
+  #[test]
+  fn test_parse_size() {
+    assert_eq!(parse_size("0").unwrap(), 0);
+    assert_eq!(parse_size("512").unwrap(), 512);
+    assert_eq!(parse_size("512b").unwrap(), 512);
+    assert_eq!(parse_size("64k").unwrap(), 64 * 1024);
+    assert_eq!(parse_size("64KB").unwrap(), 64 * 1024);
+    assert_eq!(parse_size("64KiB").unwrap(), 64 * 1024);
+    assert_eq!(parse_size("128m").unwrap(), 128 << 20);
+    assert_eq!(parse_size("128 MB").unwrap(), 128 << 20);
+    assert_eq!(parse_size("2g").unwrap(), 2 << 30);
+    assert_eq!(parse_size("1t").unwrap(), 1 << 40);
+    assert_eq!(parse_size("1p").unwrap(), 1 << 50);
+    assert_eq!(parse_size("1.5k").unwrap(), 1536);
+    assert_eq!(parse_size("0.5g").unwrap(), 512 << 20);
+    assert_eq!(parse_size(".5m").unwrap(), 512 << 10);
+    assert_eq!(parse_size("1.5").unwrap(), 1);
+  }
+
+  #[test]
+  fn test_parse_size_errors() {
+    assert_eq!(size_error(""), (0, "expected a number, e.g. 64m".into()));
+    assert_eq!(size_error("m"), (0, "expected a number, e.g. 64m".into()));
+    assert_eq!(size_error("1.2.3m"), (0, "expected a number, e.g. 64m".into()));
+    assert_eq!(size_error("-1g"), (0, "sizes cannot be negative".into()));
+    assert_eq!(size_error("-0.5k"), (0, "sizes cannot be negative".into()));
+    assert_eq!(size_error("64x"), (2, "unknown unit, expected one of k, m, g, t, or p".into()));
+    assert_eq!(size_error("64 mm"), (3, "unknown unit, expected one of k, m, g, t, or p".into()));
+    assert_eq!(size_error("64  m"), (3, "unknown unit, expected one of k, m, g, t, or p".into()));
+    assert_eq!(size_error("1m2k"), (1, "unknown unit, expected one of k, m, g, t, or p".into()));
+    assert_eq!(size_error("$SIZE"), (0, "expected a number, e.g. 64m".into()));
+    assert_eq!(size_error("100000p"), (0, "size is too large".into()));
+  }
+}
@@ -2,13 +2,52 @@
 
 use std::fmt;
 
+use crate::dockerfile_parser::Dockerfile;
 use crate::error::*;
 use crate::parser::*;
-use crate::splicer::Span;
+use crate::splicer::{Span, Splicer};
 
-use enquote::unquote;
+use smallvec::SmallVec;
 use snafu::ResultExt;
 
+/// A list that is usually short, e.g. an instruction's flags, accumulated
+/// inline while parsing so that the final `Vec` is allocated at its exact
+/// length rather than grown (and over-allocated) one push at a time.
+pub(crate) struct ShortList<T>(SmallVec<[T; 4]>);
+
+impl<T> ShortList<T> {
+  pub(crate) fn new() -> Self {
+    ShortList(SmallVec::new())
+  }
+
+  pub(crate) fn len(&self) -> usize {
+    self.0.len()
+  }
+
+  pub(crate) fn push(&mut self, item: T) {
+    self.0.push(item);
+  }
+
+  pub(crate) fn pop(&mut self) -> Option<T> {
+    self.0.pop()
+  }
+
+  pub(crate) fn append(&mut self, other: ShortList<T>) {
+    self.0.extend(other.0);
+  }
+
+  pub(crate) fn into_vec(self) -> Vec<T> {
+    if self.0.spilled() {
+      return self.0.into_vec();
+    }
+
+    // collecting would reserve room for at least 4 items
+    let mut vec = Vec::with_capacity(self.0.len());
+    vec.extend(self.0);
+    vec
+  }
+}
+
 /// Given a node ostensibly containing a string array, returns an unescaped
 /// array of strings
 pub(crate) fn parse_string_array(array: Pair) -> Result<StringArray> {
@@ -17,7 +56,7 @@ pub(crate) fn parse_string_array(array: Pair) -> Result<StringArray> {
 
   for field in array.into_inner() {
     match field.as_rule() {
-      Rule::string => {
+      Rule::array_string => {
         elements.push(parse_string(&field)?);
       },
       Rule::comment => continue,
@@ -34,18 +73,204 @@ pub(crate) fn parse_string_array(array: Pair) -> Result<StringArray> {
 pub(crate) fn parse_string(field: &Pair) -> Result<SpannedString> {
   let str_span = Span::from_pair(field);
   let field_str = field.as_str();
-  let content = if matches!(field_str.chars().next(), Some('"' | '\'' | '`')) {
-    unquote(field_str).context(UnescapeError)?
-  } else {
-    field_str.to_string()
-  };
+  let quoted = matches!(field_str.chars().next(), Some('"' | '\'' | '`'));
+  if quoted {
+    let content = unquote(field_str).context(UnescapeError)?;
+    return Ok(quoted_string(str_span, field_str, content));
+  }
 
   Ok(SpannedString {
     span: str_span,
-    content,
+    content: field_str.to_string(),
+    raw_text: None,
+    quoted,
   })
 }
 
+/// Creates a string that was written as `raw` in the input, quotes included,
+/// where `content` is its unquoted value.
+///
+/// The raw text is kept if it contains escape sequences, so that offsets into
+/// `content` can be mapped back to the input with `raw_span()`.
+pub(crate) fn quoted_string(span: Span, raw: &str, content: String) -> SpannedString {
+  SpannedString {
+    span,
+    content,
+    raw_text: if raw.contains('\\') { Some(raw.to_string()) } else { None },
+    quoted: true,
+  }
+}
+
+/// Returns the length of the escape sequence at the start of `s`, as accepted
+/// by `unquote()`.
+fn escape_len(s: &str) -> usize {
+  let len = match s[1..].chars().next() {
+    Some('0'..='9') | Some('x') => 4,
+    Some('u') => 6,
+    Some('U') => 10,
+    Some(c) => 1 + c.len_utf8(),
+    None => 1
+  };
+
+  len.min(s.len())
+}
+
+/// Maps a span within the unquoted content of a quoted string to the matching
+/// span within its raw text, as written with quotes, escape sequences, and
+/// escaped line breaks.
+pub(crate) fn raw_span(raw: &str, span: Span) -> Span {
+  let end = raw.len().saturating_sub(1);
+  let mut offset = 0;
+  let mut pos = 1;
+  let mut start = None;
+
+  loop {
+    if start.is_none() {
+      while raw[pos..].starts_with("\\\n") {
+        pos += 2;
+      }
+
+      if offset >= span.start {
+        start = Some(pos);
+      }
+    }
+
+    if (start.is_some() && offset >= span.end) || pos >= end {
+      break;
+    }
+
+    if raw[pos..].starts_with("\\\n") {
+      pos += 2;
+      continue;
+    }
+
+    let rest = &raw[pos..end];
+    if rest.starts_with('\\') {
+      let len = escape_len(rest);
+      offset += unquote(&format!("\"{}\"", &rest[..len])).map(|s| s.len()).unwrap_or(1);
+      pos += len;
+    } else {
+      let len = rest.chars().next().map(char::len_utf8).unwrap_or(1);
+      offset += len;
+      pos += len;
+    }
+  }
+
+  Span::new(start.unwrap_or(pos), pos)
+}
+
+/// Unquotes and unescapes a quoted string.
+///
+/// Unlike `enquote::unquote()`, escaped `$` and backtick characters are
+/// accepted in double-quoted strings, as Docker accepts them.
+pub(crate) fn unquote(s: &str) -> std::result::Result<String, enquote::Error> {
+  if !s.starts_with('"') {
+    return enquote::unquote(s);
+  }
+
+  let mut stripped = String::with_capacity(s.len());
+  let mut chars = s.chars().peekable();
+  while let Some(c) = chars.next() {
+    if c == '\\' {
+      match chars.peek() {
+        Some('$' | '`') => continue,
+        Some(&next) => {
+          stripped.push(c);
+          stripped.push(next);
+          chars.next();
+          continue;
+        },
+        None => {}
+      }
+    }
+
+    stripped.push(c);
+  }
+
+  enquote::unquote(&stripped)
+}
+
+/// Reorders flags in place by splicing each flag's raw text into the position
+/// of the flag it replaces, leaving the whitespace, line continuations, and
+/// comments between flags untouched. Flags already in position aren't spliced.
+///
+/// Flags are given as `(span, name, value)` and sorted by the position of
+/// their name in `order` (compared case-insensitively), followed by any other
+/// flags sorted alphabetically. The sort is stable.
+///
+/// Returns an error without splicing anything if a flag's raw text can't be
+/// reproduced exactly from its name and value (e.g. quoted values), or if a
+/// value contains a heredoc marker that would be corrupted by reordering.
+pub(crate) fn reorder_flags(
+  splicer: &mut Splicer,
+  flags: &[(Span, &str, &str)],
+  order: &[&str]
+) -> Result<()> {
+  let mut raw = Vec::with_capacity(flags.len());
+  for (span, name, value) in flags {
+    let unsafe_flag = |reason: &str| Error::UnsafeFlagReorder {
+      flag: name.to_string(),
+      reason: reason.to_string(),
+    };
+
+    if value.contains("<<") {
+      return Err(unsafe_flag("value contains a heredoc marker"));
+    }
+
+    // boolean flags may be given bare, e.g. `--link`
+    let text = if value.is_empty() && span.end - span.start == name.len() + 2 {
+      format!("--{}", name)
+    } else {
+      format!("--{}={}", name, value)
+    };
+    if text.len() != span.end - span.start || text.contains(['\n', '\\']) {
+      return Err(unsafe_flag("flag text can't be reproduced exactly"));
+    }
+
+    raw.push(text);
+  }
+
+  let rank = |name: &str| order
+    .iter()
+    .position(|o| o.eq_ignore_ascii_case(name))
+    .unwrap_or(order.len());
+
+  let mut sorted: Vec<usize> = (0..flags.len()).collect();
+  sorted.sort_by(|a, b| {
+    let (_, a_name, _) = flags[*a];
+    let (_, b_name, _) = flags[*b];
+
+    // unknown flags share the last rank, and are sorted by name
+    let name = |n: &str| if rank(n) == order.len() { n.to_ascii_lowercase() } else { String::new() };
+    rank(a_name).cmp(&rank(b_name)).then_with(|| name(a_name).cmp(&name(b_name)))
+  });
+
+  for (position, index) in sorted.into_iter().enumerate() {
+    if position != index {
+      splicer.splice(&flags[position].0, &raw[index]);
+    }
+  }
+
+  Ok(())
+}
+
+/// Groups the spans of keys appearing more than once, in order of each key's
+/// first appearance. Keys are compared exactly.
+pub(crate) fn find_duplicates<'s>(
+  keys: impl IntoIterator<Item = (&'s str, Span)>
+) -> Vec<(String, Vec<Span>)> {
+  let mut groups: Vec<(String, Vec<Span>)> = Vec::new();
+  for (key, span) in keys {
+    match groups.iter_mut().find(|(k, _)| k == key) {
+      Some((_, spans)) => spans.push(span),
+      None => groups.push((key.to_string(), vec![span]))
+    }
+  }
+
+  groups.retain(|(_, spans)| spans.len() > 1);
+  groups
+}
+
 /// Removes escaped line breaks (\\\n) from a string
 ///
 /// This should be used to clean any input from the any_breakable rule
@@ -53,6 +278,11 @@ pub(crate) fn clean_escaped_breaks(s: &str) -> String {
   s.replace("\\\n", "")
 }
 
+/// Determines if a string starts with the given prefix, ignoring ASCII case.
+pub(crate) fn starts_with_ignore_case(s: &str, prefix: &str) -> bool {
+  s.get(..prefix.len()).is_some_and(|start| start.eq_ignore_ascii_case(prefix))
+}
+
 /// A string that may be broken across many lines or an array of strings.
 #[derive(Debug, PartialEq, Eq, Clone)]
 pub enum ShellOrExecExpr {
@@ -102,6 +332,148 @@ impl ShellOrExecExpr {
   }
 }
 
+/// The quoting style of a value as written in the original input.
+#[derive(Debug, Eq, PartialEq, Clone, Copy, Hash)]
+pub enum QuoteStyle {
+  /// An unquoted value, e.g. `foo`
+  Unquoted,
+
+  /// A single-quoted value, e.g. `'foo'`
+  Single,
+
+  /// A double-quoted value, e.g. `"foo"`
+  Double,
+}
+
+impl QuoteStyle {
+  /// Determines the quoting style of a raw value.
+  pub(crate) fn of(raw: &str) -> QuoteStyle {
+    match raw.chars().next() {
+      Some('\'') => QuoteStyle::Single,
+      Some('"') => QuoteStyle::Double,
+      _ => QuoteStyle::Unquoted
+    }
+  }
+
+  /// Formats a value in this quoting style, escaping it as necessary.
+  ///
+  /// Values that can't be represented in this style are double-quoted
+  /// instead: unquoted values containing whitespace, quotes, or backslashes,
+  /// and single-quoted values containing single quotes or backslashes. As
+  /// single-quoted values are never expanded, `$` and backticks are escaped
+  /// when they're double-quoted instead.
+  pub fn quote(&self, value: &str) -> String {
+    let needs_quotes = value.is_empty() || value.contains(|c: char| {
+      c.is_whitespace() || c == '"' || c == '\'' || c == '\\'
+    });
+
+    let escaped = value.replace('\\', "\\\\").replace('"', "\\\"");
+    match self {
+      QuoteStyle::Unquoted if !needs_quotes => value.to_string(),
+      QuoteStyle::Single if !value.contains(['\'', '\\']) => {
+        format!("'{}'", value)
+      },
+      QuoteStyle::Single => format!(
+        "\"{}\"",
+        escaped.replace('$', "\\$").replace('`', "\\`")
+      ),
+      _ => format!("\"{}\"", escaped)
+    }
+  }
+}
+
+/// The separator between the key and value of a `LABEL` or `ENV` pair, as
+/// written in the original input.
+#[derive(Debug, Eq, PartialEq, Clone, Copy)]
+pub enum PairSeparator {
+  /// An equals sign, e.g. `key=value`
+  Equals(Span),
+
+  /// Whitespace in the legacy single-pair form, e.g. `key value`. The span may
+  /// include line continuations.
+  Whitespace(Span),
+}
+
+impl PairSeparator {
+  /// Returns the span of the separator.
+  pub fn span(&self) -> Span {
+    match self {
+      PairSeparator::Equals(span) | PairSeparator::Whitespace(span) => *span,
+    }
+  }
+}
+
+/// Parses a boolean flag or option value leniently: `true` and `1` are true,
+/// and `false` and `0` are false, ignoring case. Returns None for any other
+/// value.
+pub(crate) fn parse_bool(value: &str) -> Option<bool> {
+  if value == "1" || value.eq_ignore_ascii_case("true") {
+    Some(true)
+  } else if value == "0" || value.eq_ignore_ascii_case("false") {
+    Some(false)
+  } else {
+    None
+  }
+}
+
+/// The value of a `--name[=value]` instruction flag, as returned by e.g.
+/// `CopyFlag::flag_value()`.
+///
+/// Boolean-style flags like `COPY --link` may be given bare or with a value,
+/// e.g. `--link=false`; bare flags are true.
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub enum FlagValue {
+  /// A boolean value, with the span of the value as written (e.g. `TRUE` or
+  /// `0`), or None if the flag was given bare
+  Bool(bool, Option<Span>),
+
+  /// Any other value, as written
+  Str(SpannedString),
+}
+
+impl FlagValue {
+  /// Classifies the value of the flag spanning `span`. Flags without a value
+  /// end with their name.
+  pub(crate) fn of(span: Span, name: &SpannedString, value: &SpannedString) -> FlagValue {
+    if span.end == name.span.end {
+      return FlagValue::Bool(true, None);
+    }
+
+    match parse_bool(&value.content) {
+      Some(b) => FlagValue::Bool(b, Some(value.span)),
+      None => FlagValue::Str(value.clone())
+    }
+  }
+
+  /// Returns the boolean value of this flag, if it is one.
+  pub fn as_bool(&self) -> Option<bool> {
+    match self {
+      FlagValue::Bool(b, _) => Some(*b),
+      FlagValue::Str(_) => None
+    }
+  }
+
+  /// Returns the span of the value, or None for bare flags.
+  pub fn span(&self) -> Option<Span> {
+    match self {
+      FlagValue::Bool(_, span) => *span,
+      FlagValue::Str(s) => Some(s.span)
+    }
+  }
+
+  /// Interprets this value of the flag with the given name as a boolean,
+  /// returning `Error::InvalidBoolFlag` if it isn't one.
+  pub(crate) fn to_bool(&self, name: &str) -> Result<bool> {
+    match self {
+      FlagValue::Bool(b, _) => Ok(*b),
+      FlagValue::Str(s) => Err(Error::InvalidBoolFlag {
+        flag: name.to_string(),
+        value: s.content.clone(),
+      })
+    }
+  }
+}
+
 /// A string array (ex. ["executable", "param1", "param2"])
 #[derive(Debug, Eq, PartialEq, Ord, PartialOrd, Clone)]
 pub struct StringArray {
@@ -113,20 +485,163 @@ impl StringArray {
   pub fn as_str_vec(&self) -> Vec<&str> {
     self.elements.iter().map(|c| c.as_ref()).collect()
   }
+
+  /// Removes the element at `index` from the source text using the given
+  /// splicer, along with its separating comma and whitespace, e.g. turning
+  /// `["a", "b", "c"]` into `["a", "c"]`.
+  ///
+  /// Returns `Error::InvalidRemoval` if there is no such element.
+  pub fn remove_element(&self, splicer: &mut Splicer, index: usize) -> Result<()> {
+    if index >= self.elements.len() {
+      return Err(Error::InvalidRemoval {
+        index,
+        reason: format!("array only has {} elements", self.elements.len()),
+      });
+    }
+
+    let spans: Vec<Span> = self.elements.iter().map(|e| e.span).collect();
+    splicer.splice(&removal_span(&spans, index), "");
+
+    Ok(())
+  }
+}
+
+/// Returns the span removing the token at `index` from a sequence of tokens
+/// along with exactly one adjacent gap, so no doubled whitespace or dangling
+/// line continuation is left behind.
+///
+/// The gap following the token is removed if there is one, otherwise the
+/// preceding gap. Gaps may include separators, like the commas of an exec
+/// array, and any comments within the removed gap are removed as well.
+pub(crate) fn removal_span(spans: &[Span], index: usize) -> Span {
+  let span = spans[index];
+
+  match (index.checked_sub(1).map(|i| spans[i]), spans.get(index + 1)) {
+    (_, Some(next)) => Span::new(span.start, next.start),
+    (Some(prev), None) => Span::new(prev.end, span.end),
+    (None, None) => span
+  }
+}
+
+impl From<Vec<&str>> for StringArray {
+  /// Creates a synthetic string array.
+  fn from(elements: Vec<&str>) -> Self {
+    StringArray {
+      span: Span::SYNTHETIC,
+      elements: elements.into_iter().map(SpannedString::from).collect(),
+    }
+  }
 }
 
 /// A comment with a character span.
+///
+/// The span runs from the `#` through the end of the comment's text, excluding
+/// any leading indentation and the line break (including the `\r` of a CRLF
+/// line ending). `content` is the raw text covered by the span.
 #[derive(Debug, Eq, PartialEq, Ord, PartialOrd, Clone)]
 pub struct SpannedComment {
   pub span: Span,
   pub content: String,
 }
 
+impl SpannedComment {
+  /// Returns the text of this comment without its leading `#` and at most one
+  /// following space, e.g. `foo` for `# foo`.
+  pub fn text(&self) -> &str {
+    let text = self.content.strip_prefix('#').unwrap_or(&self.content);
+    text.strip_prefix(' ').unwrap_or(text)
+  }
+}
+
 /// A string with a character span.
+///
+/// For quoted strings, `span` covers the surrounding quotes while `content` is
+/// the unquoted (and unescaped) value. Use `content_span()` to find the span of
+/// just the text between the quotes.
 #[derive(Debug, Eq, PartialEq, Ord, PartialOrd, Clone)]
 pub struct SpannedString {
   pub span: Span,
   pub content: String,
+
+  /// The original text of a quoted string, including its quotes and escape
+  /// sequences. Set for quoted strings containing escape sequences, and for
+  /// all quoted strings when parsed with `ParseOptions::keep_raw_strings`.
+  pub raw_text: Option<String>,
+
+  /// If true, this string was quoted in the original input and `content` is
+  /// its unquoted value; see `is_quoted()`.
+  pub quoted: bool,
+}
+
+impl SpannedString {
+  /// Determines if this string was quoted in the original input.
+  ///
+  /// This is decided when parsing, from the first and last characters of the
+  /// string as written, so it isn't confused by content that is shorter than
+  /// its span for other reasons, e.g. a removed line continuation.
+  pub fn is_quoted(&self) -> bool {
+    self.quoted
+  }
+
+  /// Returns this string's original text, including any quotes and escape
+  /// sequences, by slicing the content of the Dockerfile it was parsed from.
+  ///
+  /// ```
+  /// use dockerfile_parser::{Dockerfile, Instruction};
+  ///
+  /// let d = Dockerfile::parse(r#"LABEL a="x\"y""#).unwrap();
+  /// if let Instruction::Label(label) = &d.instructions[0] {
+  ///   assert_eq!(label.labels[0].value.content, "x\"y");
+  ///   assert_eq!(label.labels[0].value.raw(&d), r#""x\"y""#);
+  /// }
+  /// ```
+  pub fn raw<'a>(&self, dockerfile: &'a Dockerfile) -> &'a str {
+    dockerfile.text(&self.span)
+  }
+
+  /// Returns the span of this string's content in the original input,
+  /// excluding the surrounding quotes of a quoted string.
+  ///
+  /// Replacing the text at this span preserves the original quoting, though
+  /// note that the replacement must be escaped appropriately by the caller.
+  pub fn content_span(&self) -> Span {
+    if let Some(raw) = &self.raw_text {
+      if self.is_quoted() {
+        return Span::new(self.span.start + 1, self.span.start + raw.len() - 1);
+      }
+    }
+
+    if self.is_quoted() {
+      Span::new(self.span.start + 1, self.span.end - 1)
+    } else {
+      self.span
+    }
+  }
+}
+
+impl SpannedString {
+  /// Creates a synthetic string, i.e. one not parsed from any input, with a
+  /// `Span::SYNTHETIC` span.
+  pub fn synthetic<S: Into<String>>(content: S) -> SpannedString {
+    SpannedString {
+      span: Span::SYNTHETIC,
+      content: content.into(),
+      raw_text: None,
+      quoted: false,
+    }
+  }
+}
+
+impl From<&str> for SpannedString {
+  fn from(s: &str) -> Self {
+    SpannedString::synthetic(s)
+  }
+}
+
+impl From<String> for SpannedString {
+  fn from(s: String) -> Self {
+    SpannedString::synthetic(s)
+  }
 }
 
 impl AsRef<str> for SpannedString {
@@ -148,6 +663,16 @@ pub enum BreakableStringComponent {
   Comment(SpannedComment),
 }
 
+impl BreakableStringComponent {
+  /// Returns the span of this component.
+  pub fn span(&self) -> Span {
+    match self {
+      BreakableStringComponent::String(s) => s.span,
+      BreakableStringComponent::Comment(c) => c.span,
+    }
+  }
+}
+
 impl From<SpannedString> for BreakableStringComponent {
   fn from(s: SpannedString) -> Self {
     BreakableStringComponent::String(s)
@@ -161,6 +686,8 @@ impl From<((usize, usize), &str)> for BreakableStringComponent {
     BreakableStringComponent::String(SpannedString {
       span: (start, end).into(),
       content: content.to_string(),
+      raw_text: None,
+      quoted: false,
     })
   }
 }
@@ -171,6 +698,66 @@ impl From<SpannedComment> for BreakableStringComponent {
   }
 }
 
+/// Which logical line the comments within a `BreakableString` belong to; see
+/// `BreakableString::logical_lines_with()`.
+///
+/// Comments can't share a line with a string component, so they always sit on
+/// their own lines between two continued lines.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CommentAttachment {
+  /// Comments describe the line after them, as is typical in Dockerfiles:
+  ///
+  /// ```text
+  /// RUN apt-get update && \
+  ///   # install curl
+  ///   apt-get install -y curl
+  /// ```
+  #[default]
+  Following,
+
+  /// Comments annotate the line before them
+  Preceding,
+}
+
+/// A single continuation-separated line of a `BreakableString`, with its
+/// comments attached, as returned by `BreakableString::logical_lines()`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LogicalLine<'a> {
+  /// The text of the line. Unquoted text has surrounding whitespace removed,
+  /// i.e. indentation and any space before the line continuation.
+  pub text: String,
+
+  /// The span of `text` in the original input; for quoted strings, this
+  /// includes the quotes
+  pub span: Span,
+
+  /// The comments attached to this line, in order
+  pub comments: Vec<&'a SpannedComment>,
+}
+
+impl<'a> LogicalLine<'a> {
+  /// Creates a logical line from a string component, returning `None` if it
+  /// only contains whitespace.
+  fn from_string(s: &SpannedString) -> Option<LogicalLine<'a>> {
+    if s.span.is_synthetic() || s.is_quoted() {
+      return Some(LogicalLine { text: s.content.clone(), span: s.span, comments: Vec::new() });
+    }
+
+    let text = s.content.trim_start();
+    let start = s.span.start + s.content.len() - text.len();
+    let text = text.trim_end();
+    if text.is_empty() {
+      return None;
+    }
+
+    Some(LogicalLine {
+      text: text.to_string(),
+      span: Span::new(start, start + text.len()),
+      comments: Vec::new(),
+    })
+  }
+}
+
 /// A Docker string that may be broken across several lines, separated by line
 /// continuations (`\\\n`), and possibly intermixed with comments.
 ///
@@ -221,6 +808,21 @@ impl BreakableString {
     self.components.push(SpannedString {
       span: s.into(),
       content: c.into(),
+      raw_text: None,
+      quoted: false,
+    }.into());
+
+    self
+  }
+
+  /// Adds a string that was quoted in the input, where `c` is its unquoted
+  /// content.
+  pub fn add_quoted_string(mut self, s: impl Into<Span>, c: impl Into<String>) -> Self {
+    self.components.push(SpannedString {
+      span: s.into(),
+      content: c.into(),
+      raw_text: None,
+      quoted: true,
     }.into());
 
     self
@@ -238,6 +840,129 @@ impl BreakableString {
   pub fn iter_components(&self) -> impl Iterator<Item = &BreakableStringComponent> {
     self.components.iter()
   }
+
+  /// Returns the spans of the line continuations between this string's
+  /// components, each covering the `\`, any trailing whitespace, and the
+  /// newline.
+  ///
+  /// Gaps between components may also contain the line breaks and
+  /// indentation around comments, so each gap is checked against the
+  /// Dockerfile's content; gaps without a continuation are skipped.
+  pub fn continuation_spans(&self, d: &Dockerfile) -> Vec<Span> {
+    let mut spans = Vec::new();
+
+    for pair in self.components.windows(2) {
+      let (end, start) = (pair[0].span().end, pair[1].span().start);
+      if pair[0].span().is_synthetic() || pair[1].span().is_synthetic() || end > start {
+        continue;
+      }
+
+      let gap = d.text(&Span::new(end, start));
+      let rest = match gap.strip_prefix('\\') {
+        Some(rest) => rest.trim_start_matches([' ', '\t']),
+        None => continue
+      };
+
+      let newline = if rest.starts_with("\r\n") {
+        2
+      } else if rest.starts_with('\n') {
+        1
+      } else {
+        continue
+      };
+
+      spans.push(Span::new(end, end + gap.len() - rest.len() + newline));
+    }
+
+    spans
+  }
+
+  /// Returns the span of each physical line covered by this string, excluding
+  /// line breaks and empty lines. The first span starts at the beginning of
+  /// the string rather than the line, e.g. after a `RUN` keyword.
+  ///
+  /// Unlike the spans of this string's components, these include line
+  /// continuations and comments, e.g. to highlight a full multi-line command.
+  pub fn full_span_lines(&self, d: &Dockerfile) -> Vec<Span> {
+    let mut spans = Vec::new();
+    let mut start = self.span.start;
+
+    for line in d.text(&self.span).split_inclusive('\n') {
+      let content = line.trim_end_matches('\n').trim_end_matches('\r');
+      if !content.is_empty() {
+        spans.push(Span::new(start, start + content.len()));
+      }
+
+      start += line.len();
+    }
+
+    spans
+  }
+
+  /// Splits this string into its logical lines, i.e. the chunks separated by
+  /// line continuations, attaching each comment to the line following it. See
+  /// `logical_lines_with()` to attach comments to the preceding line instead.
+  ///
+  /// Lines containing only whitespace are skipped.
+  ///
+  /// ```
+  /// use dockerfile_parser::Dockerfile;
+  ///
+  /// let d = Dockerfile::parse("RUN apt-get update && \\\n  # install curl\n  apt-get install -y curl\n").unwrap();
+  /// let lines = d.instructions[0].as_run().unwrap().as_shell().unwrap().logical_lines();
+  ///
+  /// assert_eq!(lines[0].text, "apt-get update &&");
+  /// assert!(lines[0].comments.is_empty());
+  /// assert_eq!(lines[1].text, "apt-get install -y curl");
+  /// assert_eq!(lines[1].comments[0].text(), "install curl");
+  /// ```
+  pub fn logical_lines(&self) -> Vec<LogicalLine<'_>> {
+    self.logical_lines_with(CommentAttachment::Following)
+  }
+
+  /// Splits this string into its logical lines like `logical_lines()`,
+  /// attaching comments as given.
+  ///
+  /// Comments with no line in the given direction, e.g. a comment after the
+  /// last line with `CommentAttachment::Following`, are attached to the
+  /// nearest line instead. If there are no lines, comments are dropped.
+  pub fn logical_lines_with(&self, attachment: CommentAttachment) -> Vec<LogicalLine<'_>> {
+    let mut lines: Vec<LogicalLine> = Vec::new();
+    let mut pending = Vec::new();
+
+    for component in &self.components {
+      match component {
+        BreakableStringComponent::Comment(c) => match (attachment, lines.last_mut()) {
+          (CommentAttachment::Preceding, Some(line)) => line.comments.push(c),
+          _ => pending.push(c),
+        },
+        BreakableStringComponent::String(s) => {
+          if let Some(mut line) = LogicalLine::from_string(s) {
+            line.comments.append(&mut pending);
+            lines.push(line);
+          }
+        },
+      }
+    }
+
+    if let Some(line) = lines.last_mut() {
+      line.comments.append(&mut pending);
+    }
+
+    lines
+  }
+
+  /// Returns the span of this string's content in the original input,
+  /// excluding the surrounding quotes if it was a single quoted string (as in
+  /// e.g. `ENV foo="bar"`).
+  ///
+  /// See `SpannedString::content_span()`.
+  pub fn content_span(&self) -> Span {
+    match self.components.as_slice() {
+      [BreakableStringComponent::String(s)] => s.content_span(),
+      _ => self.span
+    }
+  }
 }
 
 impl From<((usize, usize), &str)> for BreakableString {
@@ -249,12 +974,22 @@ impl From<((usize, usize), &str)> for BreakableString {
   }
 }
 
-fn parse_any_breakable_inner(pair: Pair) -> Result<Vec<BreakableStringComponent>> {
-  let mut components = Vec::new();
+impl From<&str> for BreakableString {
+  /// Creates a synthetic breakable string with a single component.
+  fn from(s: &str) -> Self {
+    BreakableString {
+      span: Span::SYNTHETIC,
+      components: vec![SpannedString::synthetic(s).into()],
+    }
+  }
+}
+
+fn parse_any_breakable_inner(pair: Pair) -> Result<ShortList<BreakableStringComponent>> {
+  let mut components = ShortList::new();
 
   for field in pair.into_inner() {
     match field.as_rule() {
-      Rule::any_breakable => components.extend(parse_any_breakable_inner(field)?),
+      Rule::any_breakable => components.append(parse_any_breakable_inner(field)?),
       Rule::comment => components.push(SpannedComment {
         span: (&field).into(),
         content: field.as_str().to_string(),
@@ -262,6 +997,8 @@ fn parse_any_breakable_inner(pair: Pair) -> Result<Vec<BreakableStringComponent>
       Rule::any_content => components.push(SpannedString {
         span: (&field).into(),
         content: field.as_str().to_string(),
+        raw_text: None,
+        quoted: false,
       }.into()),
       _ => return Err(unexpected_token(field))
     }
@@ -273,6 +1010,264 @@ fn parse_any_breakable_inner(pair: Pair) -> Result<Vec<BreakableStringComponent>
 pub(crate) fn parse_any_breakable(pair: Pair) -> Result<BreakableString> {
   Ok(BreakableString {
     span: (&pair).into(),
-    components: parse_any_breakable_inner(pair)?,
+    components: parse_any_breakable_inner(pair)?.into_vec(),
   })
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_quote_style() {
+    assert_eq!(QuoteStyle::of("foo"), QuoteStyle::Unquoted);
+    assert_eq!(QuoteStyle::of("'foo'"), QuoteStyle::Single);
+    assert_eq!(QuoteStyle::of("\"foo\""), QuoteStyle::Double);
+
+    assert_eq!(QuoteStyle::Unquoted.quote("$foo"), "$foo");
+    assert_eq!(QuoteStyle::Unquoted.quote("foo bar"), "\"foo bar\"");
+    assert_eq!(QuoteStyle::Unquoted.quote(""), "\"\"");
+    assert_eq!(QuoteStyle::Single.quote("$foo bar"), "'$foo bar'");
+    assert_eq!(QuoteStyle::Single.quote("it's"), "\"it's\"");
+    assert_eq!(QuoteStyle::Single.quote("it's $HOME"), "\"it's \\$HOME\"");
+    assert_eq!(QuoteStyle::Single.quote("it's `id`"), "\"it's \\`id\\`\"");
+    assert_eq!(QuoteStyle::Double.quote("a \"b\" \\c"), "\"a \\\"b\\\" \\\\c\"");
+  }
+
+  #[test]
+  fn test_remove_element() {
+    use crate::Dockerfile;
+
+    let content = "CMD [\"a\",  \"b\", \\\n  \"c\"]";
+    let d = Dockerfile::parse(content).unwrap();
+    let array = d.instructions[0].as_cmd().unwrap().as_exec().unwrap();
+
+    let remove = |index: usize| {
+      let mut splicer = d.splicer();
+      array.remove_element(&mut splicer, index).map(|_| splicer.content)
+    };
+
+    assert_eq!(remove(0).unwrap(), "CMD [\"b\", \\\n  \"c\"]");
+    assert_eq!(remove(1).unwrap(), "CMD [\"a\",  \"c\"]");
+    assert_eq!(remove(2).unwrap(), "CMD [\"a\",  \"b\"]");
+    assert!(matches!(remove(3), Err(Error::InvalidRemoval { index: 3, .. })));
+
+    let d = Dockerfile::parse("CMD [ \"a\" ]").unwrap();
+    let mut splicer = d.splicer();
+    d.instructions[0].as_cmd().unwrap().as_exec().unwrap().remove_element(&mut splicer, 0).unwrap();
+    assert_eq!(splicer.content, "CMD [  ]");
+  }
+
+  #[test]
+  fn test_synthetic_nodes() {
+    use crate::shift::ShiftSpans;
+
+    let s = SpannedString::from("foo");
+    assert!(s.span.is_synthetic());
+    assert!(!s.is_quoted());
+    assert_eq!(s.content_span(), Span::SYNTHETIC);
+    assert_eq!(SpannedString::from("foo".to_string()), s);
+
+    let b = BreakableString::from("echo hi");
+    assert!(b.span.is_synthetic());
+    assert_eq!(b.to_string(), "echo hi");
+
+    let array = StringArray::from(vec!["sh", "-c"]);
+    assert_eq!(array.as_str_vec(), vec!["sh", "-c"]);
+    assert!(array.elements.iter().all(|e| e.span.is_synthetic()));
+
+    // synthetic spans are never shifted
+    let mut array = array;
+    array.shift_spans(10);
+    assert!(array.span.is_synthetic() && array.elements[0].span.is_synthetic());
+  }
+
+  #[test]
+  fn test_comment_text() {
+    let comment = |content: &str| SpannedComment {
+      span: Span::new(0, content.len()),
+      content: content.to_string(),
+    };
+
+    assert_eq!(comment("# foo").text(), "foo");
+    assert_eq!(comment("#foo").text(), "foo");
+    assert_eq!(comment("#   foo ").text(), "  foo ");
+    assert_eq!(comment("#").text(), "");
+  }
+
+  #[test]
+  fn test_comment_spans() {
+    use crate::{Dockerfile, Instruction};
+
+    // each instruction that may contain comments, with indentation and both
+    // line endings
+    for newline in &["\n", "\r\n"] {
+      let content = [
+        "FROM alpine",
+        "RUN foo \\",
+        "    # run comment",
+        "  bar",
+        "CMD foo \\",
+        "\t# cmd comment\t",
+        "  bar",
+        "ENTRYPOINT foo \\",
+        "# entrypoint comment",
+        "  bar",
+        "ENV foo bar \\",
+        "  #env comment",
+        "  baz",
+        "EXPOSE \\",
+        "  # misc comment",
+        "  8080",
+        "",
+      ].join(newline);
+
+      let dockerfile = Dockerfile::parse(&content).unwrap();
+      let comments: Vec<&SpannedComment> = dockerfile.instructions
+        .iter()
+        .filter_map(|ins| match ins {
+          Instruction::Run(run) => run.as_shell(),
+          Instruction::Cmd(cmd) => cmd.as_shell(),
+          Instruction::Entrypoint(entrypoint) => entrypoint.as_shell(),
+          Instruction::Env(env) => Some(&env.vars[0].value),
+          Instruction::Misc(misc) => Some(&misc.arguments),
+          _ => None
+        })
+        .flat_map(|s| s.iter_components())
+        .filter_map(|c| match c {
+          BreakableStringComponent::Comment(c) => Some(c),
+          _ => None
+        })
+        .collect();
+
+      assert_eq!(
+        comments.iter().map(|c| c.text()).collect::<Vec<_>>(),
+        vec!["run comment", "cmd comment\t", "entrypoint comment", "env comment", "misc comment"],
+        "{:?}", newline
+      );
+
+      for comment in comments {
+        assert_eq!(&content[comment.span.start..comment.span.end], comment.content);
+        assert!(comment.content.starts_with('#'));
+        assert!(!comment.content.ends_with('\r'));
+      }
+    }
+  }
+
+  #[test]
+  fn test_logical_lines() {
+    use crate::Dockerfile;
+
+    for newline in &["\n", "\r\n"] {
+      let content = [
+        "FROM alpine",
+        "RUN apt-get update && \\",
+        "  # install tools",
+        "  # (quietly)",
+        "  apt-get install -y -q curl git \\",
+        "    \\",
+        "  && rm -rf /var/lib/apt/lists/* \\",
+        "  # trailing comment",
+        "",
+      ].join(newline);
+
+      let d = Dockerfile::parse(&content).unwrap();
+      let run = d.instructions[1].as_run().unwrap().as_shell().unwrap();
+
+      let summary = |lines: Vec<LogicalLine>| -> Vec<(String, Vec<String>)> {
+        lines
+          .into_iter()
+          .inspect(|line| assert_eq!(d.text(&line.span), line.text))
+          .map(|line| (line.text, line.comments.iter().map(|c| c.text().to_string()).collect()))
+          .collect()
+      };
+
+      let line = |text: &str, comments: &[&str]| -> (String, Vec<String>) {
+        (text.to_string(), comments.iter().map(|c| c.to_string()).collect())
+      };
+
+      assert_eq!(summary(run.logical_lines()), vec![
+        line("apt-get update &&", &[]),
+        line("apt-get install -y -q curl git", &["install tools", "(quietly)"]),
+        line("&& rm -rf /var/lib/apt/lists/*", &["trailing comment"]),
+      ], "{:?}", newline);
+
+      assert_eq!(summary(run.logical_lines_with(CommentAttachment::Preceding)), vec![
+        line("apt-get update &&", &["install tools", "(quietly)"]),
+        line("apt-get install -y -q curl git", &[]),
+        line("&& rm -rf /var/lib/apt/lists/*", &["trailing comment"]),
+      ], "{:?}", newline);
+    }
+
+    // quoted and synthetic strings are kept whole
+    let d = Dockerfile::parse("ENV foo \"  bar  \"\n").unwrap();
+    let lines = d.instructions[0].as_env().unwrap().vars[0].value.logical_lines();
+    assert_eq!(lines[0].text, "  bar  ");
+    assert_eq!(d.text(&lines[0].span), "\"  bar  \"");
+
+    let synthetic = BreakableString::from(" foo ");
+    let lines = synthetic.logical_lines();
+    assert_eq!(lines[0].text, " foo ");
+    assert!(lines[0].span.is_synthetic());
+  }
+
+  #[test]
+  fn test_raw_strings() {
+    use crate::{Dockerfile, Instruction, ParseOptions};
+
+    let content = r#"LABEL a="x\ny" b="say \"hi\"" c="caf\u00e9" d=plain
+CMD ["a\tb", "\"c\""]
+"#;
+    let options = ParseOptions { keep_raw_strings: true, ..Default::default() };
+    let d = Dockerfile::parse_with_options(content, &options).unwrap();
+
+    let mut strings: Vec<&SpannedString> = Vec::new();
+    if let Instruction::Label(label) = &d.instructions[0] {
+      strings.extend(label.labels.iter().map(|l| &l.value));
+    }
+    if let Some(exec) = d.instructions[1].as_cmd().and_then(|c| c.as_exec()) {
+      strings.extend(exec.elements.iter());
+    }
+
+    assert_eq!(
+      strings.iter().map(|s| s.content.as_str()).collect::<Vec<_>>(),
+      vec!["x\ny", "say \"hi\"", "caf\u{e9}", "plain", "a\tb", "\"c\""]
+    );
+    assert_eq!(
+      strings.iter().map(|s| s.raw_text.as_deref()).collect::<Vec<_>>(),
+      vec![
+        Some(r#""x\ny""#), Some(r#""say \"hi\"""#), Some(r#""caf\u00e9""#), None,
+        Some(r#""a\tb""#), Some(r#""\"c\"""#),
+      ]
+    );
+
+    for s in &strings {
+      assert_eq!(s.raw(&d), &content[s.span.start..s.span.end]);
+      assert_eq!(s.is_quoted(), s.raw_text.is_some());
+    }
+
+    let c = strings[2];
+    assert_eq!(d.text(&c.content_span()), r"caf\u00e9");
+
+    // shell text starting and ending with quotes isn't a quoted string
+    let d = Dockerfile::parse_with_options("RUN \"echo\" 'hi'\n", &options).unwrap();
+    let shell = d.instructions[0].as_run().unwrap().as_shell().unwrap();
+    match &shell.components[0] {
+      BreakableStringComponent::String(s) => {
+        assert_eq!(s.content, "\"echo\" 'hi'");
+        assert_eq!(s.raw_text, None);
+      },
+      other => panic!("unexpected component: {:?}", other),
+    }
+
+    // without the option, raw text is only kept for strings with escapes, but
+    // raw() still works
+    let d = Dockerfile::parse(r#"LABEL a="x" b="say \"hi\"""#).unwrap();
+    if let Instruction::Label(label) = &d.instructions[0] {
+      assert_eq!(label.labels[0].value.raw_text, None);
+      assert_eq!(label.labels[0].value.raw(&d), r#""x""#);
+      assert_eq!(label.labels[1].value.raw_text.as_deref(), Some(r#""say \"hi\"""#));
+      assert_eq!(label.labels[1].value.raw(&d), r#""say \"hi\"""#);
+    }
+  }
+}
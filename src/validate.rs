@@ -0,0 +1,1171 @@
+// (C) Copyright 2026 Hewlett Packard Enterprise Development LP
+
+use crate::diagnostics::{Diagnostic, DiagnosticKind};
+use crate::dockerfile_parser::{
+  closest_keyword, find_pair_gaps, is_instruction_keyword, Dockerfile, Instruction
+};
+use crate::fixes::Fix;
+use crate::image::Digest;
+use crate::instructions::{CopyFromTarget, Signal, HEALTHCHECK_DURATION_FLAGS, MAX_SIGNAL};
+use crate::options::{TargetOs, UnknownPolicy, ValidateOptions};
+use crate::spec::{InstructionSpec, INSTRUCTION_SPECS};
+use crate::splicer::Span;
+use crate::stage::Stage;
+use crate::units::parse_duration;
+use crate::util::SpannedString;
+
+/// Runs all validation checks against the given Dockerfile.
+pub(crate) fn validate(dockerfile: &Dockerfile, options: &ValidateOptions) -> Vec<Diagnostic> {
+  let mut diagnostics = Vec::new();
+  check_stage_references(dockerfile, &mut diagnostics);
+  check_secret_mounts(dockerfile, &mut diagnostics);
+  check_volumes(dockerfile, options.target_os, &mut diagnostics);
+  check_duplicate_keys(dockerfile, &mut diagnostics);
+  check_arg_names(dockerfile, &mut diagnostics);
+  check_digests(dockerfile, &mut diagnostics);
+  check_healthchecks(dockerfile, &mut diagnostics);
+  check_stopsignals(dockerfile, &mut diagnostics);
+
+  if options.strict_flags {
+    check_unknown_flags(dockerfile, INSTRUCTION_SPECS, &mut diagnostics);
+  }
+
+  if dockerfile.options.unknown_instructions == UnknownPolicy::Warn {
+    check_unknown_instructions(dockerfile, &mut diagnostics);
+  }
+
+  if dockerfile.options.lenient {
+    for gap in find_pair_gaps(&dockerfile.content, &dockerfile.instructions) {
+      diagnostics.push(Diagnostic::warning(
+        DiagnosticKind::MisplacedPairSpace,
+        gap.span,
+        gap.message()
+      ).with_suggested_fix(Fix::new(dockerfile, gap.span, "")));
+    }
+  }
+
+  diagnostics
+}
+
+/// Checks that each `COPY --from` flag refers to something that can actually
+/// be copied from.
+///
+/// Stage aliases and image references are indistinguishable without a
+/// registry, so only stage indexes, `scratch`, and references to the
+/// containing stage (including `RUN --mount=from=`) are checked.
+fn check_stage_references(dockerfile: &Dockerfile, diagnostics: &mut Vec<Diagnostic>) {
+  let stages = dockerfile.stages();
+
+  for ctx in dockerfile.iter_with_context() {
+    let (stage, ins) = match ctx.stage {
+      Some(stage) => (&stages[stage], ctx.instruction),
+      None => continue
+    };
+
+    if let Some(run) = ins.as_run() {
+      for mount in run.mounts() {
+        let from = match mount.option("from").and_then(|o| o.value.as_ref()) {
+          Some(from) => from,
+          None => continue
+        };
+
+        if stages.resolve_reference(dockerfile, &from.content) == Some(stage.index) {
+          diagnostics.push(self_reference_diagnostic(stage, "RUN --mount", from, mount.span));
+        }
+      }
+
+      continue;
+    }
+
+    let copy = match ins.as_copy() {
+      Some(copy) => copy,
+      None => continue
+    };
+
+    let (flag, target) = match (copy.from_flag(), copy.from_target()) {
+      (Some(flag), Some(target)) => (flag, target),
+      _ => continue
+    };
+
+    let span = flag.value.span;
+    match target {
+      CopyFromTarget::NegativeIndex(index) => diagnostics.push(Diagnostic::error(
+        DiagnosticKind::NegativeStageIndex,
+        span,
+        format!("invalid COPY --from={}: negative stage index", index)
+      )),
+      CopyFromTarget::Scratch => diagnostics.push(Diagnostic::error(
+        DiagnosticKind::CopyFromScratch,
+        span,
+        "cannot copy from scratch: the scratch image contains no files"
+      )),
+      _ if stages.resolve_reference(dockerfile, &flag.value.content) == Some(stage.index) => {
+        diagnostics.push(self_reference_diagnostic(stage, "COPY --from", &flag.value, flag.span));
+      },
+      CopyFromTarget::Index(index) if index > stage.index => {
+        diagnostics.push(Diagnostic::error(
+          DiagnosticKind::UndefinedStageIndex,
+          span,
+          format!(
+            "invalid COPY --from={}: no stage with this index is defined before stage {}",
+            index, stage.index
+          )
+        ))
+      },
+      _ => ()
+    }
+  }
+}
+
+/// Creates a diagnostic for a reference to the stage containing it, with
+/// related spans pointing at the stage's `FROM` alias (or image, if it has no
+/// alias) and the full flag.
+fn self_reference_diagnostic(
+  stage: &Stage,
+  flag: &str,
+  value: &SpannedString,
+  flag_span: Span
+) -> Diagnostic {
+  let from = stage.instructions[0].as_from();
+  let from_span = from
+    .and_then(|f| f.alias.as_ref().map(|a| a.span))
+    .or_else(|| from.map(|f| f.image.span));
+
+  let mut diagnostic = Diagnostic::warning(
+    DiagnosticKind::SelfStageReference,
+    value.span,
+    format!(
+      "invalid {}={}: stage {} cannot reference itself (circular dependency)",
+      flag, value.content, stage.index
+    )
+  );
+
+  diagnostic.related_spans.extend(from_span);
+  diagnostic.related_spans.push(flag_span);
+  diagnostic
+}
+
+/// Returns the span of the part of `s` following the given byte offset into
+/// its content, or the whole span if its text doesn't match its content, e.g.
+/// because it was quoted.
+fn suffix_span(dockerfile: &Dockerfile, s: &SpannedString, offset: usize) -> Span {
+  if dockerfile.text(&s.span) == s.content {
+    Span::new(s.span.start + offset, s.span.end)
+  } else {
+    s.span
+  }
+}
+
+/// Checks the digest of an image reference (the part after `@`), if any,
+/// ignoring digests containing unresolved variables.
+fn check_image_digest(
+  dockerfile: &Dockerfile,
+  image: &SpannedString,
+  position: &str,
+  diagnostics: &mut Vec<Diagnostic>
+) {
+  let at = match image.content.find('@') {
+    Some(at) => at,
+    None => return
+  };
+
+  let digest = &image.content[at + 1..];
+  if digest.contains('$') {
+    return;
+  }
+
+  if let Err(e) = Digest::parse(digest) {
+    diagnostics.push(Diagnostic::error(
+      DiagnosticKind::InvalidDigest,
+      suffix_span(dockerfile, image, at + 1),
+      format!("{} image has an {}", position, e)
+    ));
+  }
+}
+
+/// Checks that all digests are well-formed: those of `FROM` and `COPY --from`
+/// images, `ADD --checksum` flags, and the `# syntax` directive.
+///
+/// `FROM` digests are normally rejected while parsing, unless
+/// `ParseOptions::strict_digests` is disabled.
+fn check_digests(dockerfile: &Dockerfile, diagnostics: &mut Vec<Diagnostic>) {
+  if let Some(directive) = dockerfile.syntax_directive() {
+    check_image_digest(dockerfile, &directive.value, "syntax directive", diagnostics);
+  }
+
+  for ins in &dockerfile.instructions {
+    match ins {
+      Instruction::From(from) => check_image_digest(dockerfile, &from.image, "FROM", diagnostics),
+      Instruction::Copy(copy) => if let Some(flag) = copy.from_flag() {
+        check_image_digest(dockerfile, &flag.value, "COPY --from", diagnostics);
+      },
+      Instruction::Add(add) => {
+        let checksum = match add.checksum() {
+          Some(checksum) if !checksum.content.contains('$') => checksum,
+          _ => continue
+        };
+
+        if let Err(e) = Digest::parse(&checksum.content) {
+          diagnostics.push(Diagnostic::error(
+            DiagnosticKind::InvalidDigest,
+            checksum.span,
+            format!("ADD --checksum has an {}", e)
+          ));
+        }
+      },
+      _ => ()
+    }
+  }
+}
+
+/// Checks that each stage has at most one `HEALTHCHECK`, and that each has a
+/// non-empty command and valid durations and retry count.
+///
+/// Flag values containing variables are not checked.
+fn check_healthchecks(dockerfile: &Dockerfile, diagnostics: &mut Vec<Diagnostic>) {
+  for stage in dockerfile.iter_stages() {
+    let healthchecks: Vec<_> = stage.instructions
+      .iter()
+      .filter_map(|ins| ins.as_healthcheck())
+      .collect();
+
+    if healthchecks.len() > 1 {
+      let spans: Vec<Span> = healthchecks.iter().map(|h| h.span).collect();
+      diagnostics.push(duplicate_key_diagnostic(
+        DiagnosticKind::DuplicateHealthcheck,
+        &spans,
+        format!(
+          "HEALTHCHECK is set {} times in stage {}; only the last one is used",
+          spans.len(), stage.index
+        )
+      ));
+    }
+
+    for healthcheck in &healthchecks {
+      match (&healthcheck.command, healthcheck.cmd().and_then(|c| c.as_exec())) {
+        (None, _) => diagnostics.push(Diagnostic::error(
+          DiagnosticKind::InvalidHealthcheck,
+          healthcheck.span,
+          "HEALTHCHECK requires either NONE or a CMD"
+        )),
+        (_, Some(exec)) if exec.elements.is_empty() => diagnostics.push(Diagnostic::error(
+          DiagnosticKind::InvalidHealthcheck,
+          exec.span,
+          "HEALTHCHECK CMD has an empty command"
+        )),
+        _ => ()
+      }
+
+      for flag in &healthcheck.flags {
+        let name = flag.name.content.as_str();
+        if flag.value.content.contains('$') {
+          continue;
+        }
+
+        if HEALTHCHECK_DURATION_FLAGS.contains(&name) {
+          if let Err(e) = parse_duration(&flag.value.content) {
+            // flag values are kept as written, so the error's offset points
+            // into the value's span
+            let span = match flag.value.span.start + e.offset {
+              start if start < flag.value.span.end => Span::new(start, flag.value.span.end),
+              _ => flag.value.span
+            };
+            diagnostics.push(Diagnostic::error(
+              DiagnosticKind::InvalidHealthcheck,
+              span,
+              format!("HEALTHCHECK --{} has an invalid duration '{}': {}", name, e.value, e.reason)
+            ));
+          }
+        } else if name == "retries" {
+          match flag.value.content.parse::<i64>() {
+            Ok(retries) if retries < 0 => diagnostics.push(Diagnostic::error(
+              DiagnosticKind::InvalidHealthcheck,
+              flag.value.span,
+              format!("HEALTHCHECK --retries cannot be negative ({})", retries)
+            )),
+            Ok(0) => diagnostics.push(Diagnostic::warning(
+              DiagnosticKind::InvalidHealthcheck,
+              flag.value.span,
+              "HEALTHCHECK --retries=0 has no effect; the default of 3 retries is used"
+            )),
+            Ok(_) => (),
+            Err(_) => diagnostics.push(Diagnostic::error(
+              DiagnosticKind::InvalidHealthcheck,
+              flag.value.span,
+              format!("HEALTHCHECK --retries must be a number, not '{}'", flag.value.content)
+            )),
+          }
+        }
+      }
+    }
+  }
+}
+
+/// Checks that each `STOPSIGNAL` is a known signal name or a valid signal
+/// number. Docker accepts any signal when building, so an invalid one only
+/// fails when the container is stopped.
+///
+/// Signals containing variables are not checked.
+fn check_stopsignals(dockerfile: &Dockerfile, diagnostics: &mut Vec<Diagnostic>) {
+  for stopsignal in dockerfile.instructions.iter().filter_map(|ins| ins.as_stopsignal()) {
+    let signal = &stopsignal.signal;
+    if signal.content.contains('$') {
+      continue;
+    }
+
+    let message = match stopsignal.parsed_signal() {
+      Some(parsed) if parsed.is_valid() => continue,
+      Some(Signal::Number(_)) => format!(
+        "STOPSIGNAL {} is out of range; signal numbers must be between 1 and {}",
+        signal.content, MAX_SIGNAL
+      ),
+      Some(Signal::Name(_)) => format!("STOPSIGNAL has an unknown signal '{}'", signal.content),
+      None => format!("STOPSIGNAL has an invalid signal '{}'", signal.content),
+    };
+
+    diagnostics.push(Diagnostic::error(DiagnosticKind::InvalidStopsignal, signal.span, message));
+  }
+}
+
+/// Checks that secret mounts don't specify both a file `target` and an `env`
+/// variable, which is ambiguous.
+fn check_secret_mounts(dockerfile: &Dockerfile, diagnostics: &mut Vec<Diagnostic>) {
+  for ins in &dockerfile.instructions {
+    let run = match ins.as_run() {
+      Some(run) => run,
+      None => continue
+    };
+
+    for secret in run.secret_mounts() {
+      if let (Some(target), Some(env)) = (&secret.target, &secret.env) {
+        diagnostics.push(Diagnostic::warning(
+          DiagnosticKind::ConflictingSecretMount,
+          secret.span,
+          format!(
+            "secret '{}' specifies both target={} and env={}; use only one",
+            secret.id, target, env
+          )
+        ));
+      }
+    }
+  }
+}
+
+/// Determines if a path is absolute on the given OS. Windows accepts drive
+/// letter paths as well as rooted paths, which use the default drive.
+pub(crate) fn is_absolute_path(path: &str, os: TargetOs) -> bool {
+  match os {
+    TargetOs::Linux => path.starts_with('/'),
+    TargetOs::Windows => {
+      let bytes = path.as_bytes();
+      let has_drive = bytes.len() >= 3
+        && bytes[0].is_ascii_alphabetic()
+        && bytes[1] == b':'
+        && (bytes[2] == b'\\' || bytes[2] == b'/');
+
+      has_drive || path.starts_with('/') || path.starts_with('\\')
+    }
+  }
+}
+
+/// Checks that `VOLUME` instructions declare at least one path, don't repeat
+/// paths, and only use absolute paths.
+///
+/// Paths containing variable references can't be checked for absoluteness
+/// without knowing their values, and are skipped.
+fn check_volumes(dockerfile: &Dockerfile, os: TargetOs, diagnostics: &mut Vec<Diagnostic>) {
+  for ins in &dockerfile.instructions {
+    let volume = match ins.as_volume() {
+      Some(volume) => volume,
+      None => continue
+    };
+
+    if volume.paths.is_empty() {
+      diagnostics.push(Diagnostic::error(
+        DiagnosticKind::EmptyVolume,
+        volume.span,
+        "VOLUME requires at least one path"
+      ));
+    }
+
+    for (i, path) in volume.paths.iter().enumerate() {
+      if let Some(first) = volume.paths[..i].iter().find(|p| p.content == path.content) {
+        diagnostics.push(Diagnostic::warning(
+          DiagnosticKind::DuplicateVolumePath,
+          path.span,
+          format!("volume path '{}' is declared more than once", path.content)
+        ).with_related_span(first.span));
+      }
+
+      if path.content.contains('$') || is_absolute_path(&path.content, os) {
+        continue;
+      }
+
+      let message = format!("volume path '{}' is not absolute", path.content);
+      diagnostics.push(match os {
+        // docker refuses relative volume paths for windows containers
+        TargetOs::Windows => Diagnostic::error(DiagnosticKind::RelativeVolumePath, path.span, message),
+        TargetOs::Linux => Diagnostic::warning(DiagnosticKind::RelativeVolumePath, path.span, message),
+      });
+    }
+  }
+}
+
+/// Checks for `ENV` keys set more than once in a single instruction and
+/// label keys set more than once within a stage, where all but the last
+/// occurrence are silently overridden.
+///
+/// `ENV` keys redefined by later instructions are not reported, as this is
+/// common, e.g. `ENV PATH=/foo:$PATH`.
+fn check_duplicate_keys(dockerfile: &Dockerfile, diagnostics: &mut Vec<Diagnostic>) {
+  for stage in dockerfile.iter_stages() {
+    for ins in &stage.instructions {
+      let env = match ins.as_env() {
+        Some(env) => env,
+        None => continue
+      };
+
+      for (key, spans) in env.duplicate_keys() {
+        let overridden: Vec<String> = env.vars
+          .iter()
+          .filter(|v| v.key.content == key)
+          .map(|v| format!("'{}'", v.value))
+          .collect();
+
+        diagnostics.push(duplicate_key_diagnostic(
+          DiagnosticKind::DuplicateEnvKey,
+          &spans,
+          format!(
+            "ENV key '{}' is set more than once in one instruction; {} overrides {}",
+            key,
+            overridden[overridden.len() - 1],
+            overridden[..overridden.len() - 1].join(", ")
+          )
+        ));
+      }
+    }
+
+    for (key, spans) in stage.duplicate_label_keys() {
+      diagnostics.push(duplicate_key_diagnostic(
+        DiagnosticKind::DuplicateLabelKey,
+        &spans,
+        format!(
+          "label '{}' is set {} times in stage {}; only the last value is used",
+          key, spans.len(), stage.index
+        )
+      ));
+    }
+  }
+}
+
+/// Checks that all flags of `FROM`, `RUN`, `COPY`, and `ADD` instructions are listed
+/// in the given specs.
+fn check_unknown_flags(
+  dockerfile: &Dockerfile,
+  specs: &[InstructionSpec],
+  diagnostics: &mut Vec<Diagnostic>
+) {
+  for ins in &dockerfile.instructions {
+    let keyword = ins.kind().keyword();
+    let spec = match specs.iter().find(|s| Some(s.keyword) == keyword) {
+      Some(spec) => spec,
+      None => continue
+    };
+
+    let known = spec.flag_names();
+    let unknown: Vec<_> = match ins {
+      Instruction::From(from) => from.unknown_flags(&known)
+        .into_iter()
+        .map(|f| (f.span, &f.name.content))
+        .collect(),
+      Instruction::Run(run) => run.unknown_flags(&known)
+        .into_iter()
+        .map(|f| (f.span, &f.name.content))
+        .collect(),
+      Instruction::Copy(copy) => copy.unknown_flags(&known)
+        .into_iter()
+        .map(|f| (f.span, &f.name.content))
+        .collect(),
+      Instruction::Add(add) => add.unknown_flags(&known)
+        .into_iter()
+        .map(|f| (f.span, &f.name.content))
+        .collect(),
+      _ => continue
+    };
+
+    for (span, name) in unknown {
+      diagnostics.push(Diagnostic::error(
+        DiagnosticKind::UnknownFlag,
+        span,
+        format!("unknown flag '--{}' for {}", name, spec.keyword)
+      ));
+    }
+  }
+}
+
+/// Checks that `ARG` names only contain characters Docker allows, reporting
+/// the first invalid character of each name.
+fn check_arg_names(dockerfile: &Dockerfile, diagnostics: &mut Vec<Diagnostic>) {
+  for arg in dockerfile.instructions.iter().filter_map(|ins| ins.as_arg()) {
+    if let Some(span) = arg.invalid_name_char() {
+      diagnostics.push(Diagnostic::warning(
+        DiagnosticKind::InvalidArgName,
+        span,
+        format!(
+          "invalid ARG name '{}': '{}' is not allowed; names may only contain letters, \
+          digits, and underscores and may not begin with a digit",
+          arg.name.content, dockerfile.text(&span)
+        )
+      ));
+    }
+  }
+}
+
+/// Checks for instructions with unrecognized keywords, suggesting a similar
+/// keyword when the unknown one looks like a typo.
+fn check_unknown_instructions(dockerfile: &Dockerfile, diagnostics: &mut Vec<Diagnostic>) {
+  for misc in dockerfile.instructions.iter().filter_map(|ins| ins.as_misc()) {
+    let keyword = &misc.instruction;
+    if is_instruction_keyword(&keyword.content) {
+      continue;
+    }
+
+    let diagnostic = match closest_keyword(&keyword.content) {
+      Some(suggestion) => {
+        let suggestion = if keyword.content.chars().all(|c| c.is_ascii_lowercase()) {
+          suggestion.to_ascii_lowercase()
+        } else {
+          suggestion.to_string()
+        };
+
+        Diagnostic::warning(
+          DiagnosticKind::UnknownInstruction,
+          keyword.span,
+          format!("unknown instruction '{}': did you mean '{}'?", keyword.content, suggestion)
+        ).with_suggested_fix(Fix::new(dockerfile, keyword.span, suggestion))
+      },
+      None => Diagnostic::warning(
+        DiagnosticKind::UnknownInstruction,
+        keyword.span,
+        format!("unknown instruction '{}'", keyword.content)
+      )
+    };
+
+    diagnostics.push(diagnostic);
+  }
+}
+
+/// Creates a warning at the last (effective) occurrence of a duplicated key,
+/// with related spans pointing at every occurrence.
+fn duplicate_key_diagnostic(kind: DiagnosticKind, spans: &[Span], message: String) -> Diagnostic {
+  let mut diagnostic = Diagnostic::warning(kind, spans[spans.len() - 1], message);
+  diagnostic.related_spans = spans.to_vec();
+
+  diagnostic
+}
+
+#[cfg(test)]
+mod tests {
+  use indoc::indoc;
+  use pretty_assertions::assert_eq;
+
+  use crate::*;
+
+  fn validate(s: &str) -> (Dockerfile, Vec<Diagnostic>) {
+    let dockerfile = Dockerfile::parse(s).unwrap();
+    let diagnostics = dockerfile.validate();
+
+    (dockerfile, diagnostics)
+  }
+
+  #[test]
+  fn test_copy_from_valid() {
+    let (_, diagnostics) = validate(indoc!(r#"
+      FROM alpine:3.12 as build
+      FROM alpine:3.12
+      COPY --from=0 /a /a
+      COPY --from=build /b /b
+      COPY --from=alpine:3.12 /c /c
+    "#));
+
+    assert_eq!(diagnostics, vec![]);
+  }
+
+  #[test]
+  fn test_copy_from_negative_index() {
+    let (d, diagnostics) = validate(indoc!(r#"
+      FROM alpine:3.12
+      FROM alpine:3.12
+      COPY --from=-1 /a /a
+    "#));
+
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0].kind, DiagnosticKind::NegativeStageIndex);
+    assert_eq!(diagnostics[0].severity, Severity::Error);
+    assert!(diagnostics[0].message.contains("negative stage index"));
+
+    let span = diagnostics[0].span;
+    assert_eq!(&d.content[span.start..span.end], "-1");
+  }
+
+  #[test]
+  fn test_copy_from_scratch() {
+    let (d, diagnostics) = validate(indoc!(r#"
+      FROM alpine:3.12
+      COPY --from=scratch /a /a
+    "#));
+
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0].kind, DiagnosticKind::CopyFromScratch);
+    assert!(diagnostics[0].message.starts_with("cannot copy from scratch"));
+
+    let span = diagnostics[0].span;
+    assert_eq!(&d.content[span.start..span.end], "scratch");
+  }
+
+  #[test]
+  fn test_copy_from_undefined_index() {
+    let (_, diagnostics) = validate(indoc!(r#"
+      FROM alpine:3.12
+      COPY --from=1 /a /a
+      FROM alpine:3.12
+    "#));
+
+    assert_eq!(
+      diagnostics.iter().map(|d| d.kind).collect::<Vec<_>>(),
+      vec![DiagnosticKind::UndefinedStageIndex]
+    );
+  }
+
+  #[test]
+  fn test_self_stage_reference_alias() {
+    let (d, diagnostics) = validate(indoc!(r#"
+      ARG SELF=app
+      FROM alpine:3.12 AS build
+      FROM build AS app
+      COPY --from=app /a /a
+      COPY --from=${SELF} /b /b
+      RUN --mount=type=bind,from=App,target=/src make
+    "#));
+
+    let text = |span: &Span| &d.content[span.start..span.end];
+
+    assert_eq!(
+      diagnostics.iter().map(|d| (d.kind, d.severity)).collect::<Vec<_>>(),
+      vec![(DiagnosticKind::SelfStageReference, Severity::Warning); 3]
+    );
+    assert_eq!(diagnostics[0].code(), "DFP1014");
+    assert_eq!(text(&diagnostics[0].span), "app");
+    assert_eq!(
+      diagnostics[0].related_spans.iter().map(text).collect::<Vec<_>>(),
+      vec!["app", "--from=app"]
+    );
+    assert!(diagnostics[0].message.contains("circular dependency"));
+
+    assert_eq!(text(&diagnostics[1].span), "${SELF}");
+    assert_eq!(text(&diagnostics[2].span), "App");
+    assert_eq!(text(&diagnostics[2].related_spans[1]), "type=bind,from=App,target=/src");
+  }
+
+  #[test]
+  fn test_self_stage_reference_index() {
+    let (d, diagnostics) = validate(indoc!(r#"
+      FROM alpine:3.12
+      FROM alpine:3.12
+      COPY --from=1 /a /a
+      COPY --from=2 /b /b
+    "#));
+
+    assert_eq!(
+      diagnostics.iter().map(|d| d.kind).collect::<Vec<_>>(),
+      vec![DiagnosticKind::SelfStageReference, DiagnosticKind::UndefinedStageIndex]
+    );
+
+    // without an alias, the FROM image is related instead
+    let span = diagnostics[0].related_spans[0];
+    assert_eq!(&d.content[span.start..span.end], "alpine:3.12");
+    assert_eq!(span.start, d.instructions[1].span().start + "FROM ".len());
+  }
+
+  #[test]
+  fn test_self_stage_reference_reused_alias() {
+    // a later stage reusing an alias refers to the earlier stage
+    let (_, diagnostics) = validate(indoc!(r#"
+      FROM alpine:3.12 AS app
+      RUN touch /a
+      FROM alpine:3.12 AS app
+      COPY --from=app /a /a
+      RUN --mount=from=app,target=/mnt cat /mnt/a
+    "#));
+
+    assert_eq!(diagnostics, vec![]);
+  }
+
+  #[test]
+  fn test_invalid_digests() {
+    let sha = "a".repeat(64);
+    let content = format!(indoc!(r#"
+      # syntax=docker/dockerfile:1@sha256:abc
+      FROM alpine@sha256:{sha}
+      COPY --from=alpine@sha256:{sha} /a /a
+      COPY --from=alpine@sha256:zzz /b /b
+      COPY --from=alpine@${{DIGEST}} /c /c
+      ADD --checksum=sha256:{sha} https://example.com/a.tar /
+      ADD --checksum=md5:d41d8cd98f00b204e9800998ecf8427e https://example.com/b.tar /
+    "#), sha = sha);
+
+    let (d, diagnostics) = validate(&content);
+    let found: Vec<(&str, &str)> = diagnostics
+      .iter()
+      .inspect(|diagnostic| assert_eq!(diagnostic.kind, DiagnosticKind::InvalidDigest))
+      .map(|diagnostic| (&d.content[diagnostic.span.start..diagnostic.span.end], diagnostic.message.as_str()))
+      .collect();
+
+    assert_eq!(found, vec![
+      (
+        "sha256:abc",
+        "syntax directive image has an invalid digest 'sha256:abc': expected 64 hex characters for sha256"
+      ),
+      (
+        "sha256:zzz",
+        "COPY --from image has an invalid digest 'sha256:zzz': expected 64 hex characters for sha256"
+      ),
+      (
+        "md5:d41d8cd98f00b204e9800998ecf8427e",
+        "ADD --checksum has an invalid digest 'md5:d41d8cd98f00b204e9800998ecf8427e': unsupported algorithm"
+      ),
+    ]);
+  }
+
+  #[test]
+  fn test_strict_digests() {
+    let content = "FROM alpine@sha256:1234 AS base\n";
+    assert!(matches!(Dockerfile::parse(content), Err(Error::GenericParseError { .. })));
+
+    let options = ParseOptions { strict_digests: false, ..Default::default() };
+    let d = Dockerfile::parse_with_options(content, &options).unwrap();
+    let diagnostics = d.validate();
+
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0].kind, DiagnosticKind::InvalidDigest);
+    assert_eq!(diagnostics[0].severity, Severity::Error);
+    assert_eq!(d.text(&diagnostics[0].span), "sha256:1234");
+    assert!(diagnostics[0].message.starts_with("FROM image has an invalid digest"));
+  }
+
+  #[test]
+  fn test_conflicting_secret_mount() {
+    let (d, diagnostics) = validate(indoc!(r#"
+      FROM alpine:3.12
+      RUN --mount=type=secret,id=a,target=/a,env=A true
+      RUN --mount=type=secret,id=b,env=B --mount=type=secret,id=c true
+    "#));
+
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0].kind, DiagnosticKind::ConflictingSecretMount);
+    assert_eq!(diagnostics[0].severity, Severity::Warning);
+    assert_eq!(
+      &d.content[diagnostics[0].span.start..diagnostics[0].span.end],
+      "type=secret,id=a,target=/a,env=A"
+    );
+  }
+
+  #[test]
+  fn test_empty_volume() {
+    let (d, diagnostics) = validate(indoc!(r#"
+      FROM alpine:3.12
+      VOLUME []
+      VOLUME ["/data"]
+    "#));
+
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0].kind, DiagnosticKind::EmptyVolume);
+    assert_eq!(diagnostics[0].severity, Severity::Error);
+    assert_eq!(&d.content[diagnostics[0].span.start..diagnostics[0].span.end], "VOLUME []");
+  }
+
+  #[test]
+  fn test_duplicate_volume_path() {
+    let (d, diagnostics) = validate(indoc!(r#"
+      FROM alpine:3.12
+      VOLUME /data /logs /data
+      VOLUME ["/logs"]
+    "#));
+
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0].kind, DiagnosticKind::DuplicateVolumePath);
+    assert_eq!(diagnostics[0].severity, Severity::Warning);
+    assert_eq!(diagnostics[0].span.start, d.content.rfind("/data").unwrap());
+    assert_eq!(diagnostics[0].related_spans[0].start, d.content.find("/data").unwrap());
+  }
+
+  #[test]
+  fn test_relative_volume_path() {
+    let d = Dockerfile::parse(indoc!(r#"
+      FROM alpine:3.12
+      VOLUME data /logs $HOME/cache
+      VOLUME ["C:\\data", "\\logs", "cache"]
+    "#)).unwrap();
+
+    let linux = d.validate();
+    assert_eq!(
+      linux.iter().map(|d| (d.severity, d.message.as_str())).collect::<Vec<_>>(),
+      vec![
+        (Severity::Warning, "volume path 'data' is not absolute"),
+        (Severity::Warning, "volume path 'C:\\data' is not absolute"),
+        (Severity::Warning, "volume path '\\logs' is not absolute"),
+        (Severity::Warning, "volume path 'cache' is not absolute"),
+      ]
+    );
+    assert!(linux.iter().all(|d| d.kind == DiagnosticKind::RelativeVolumePath));
+
+    let windows = d.validate_with_options(&ValidateOptions {
+      target_os: TargetOs::Windows,
+      ..Default::default()
+    });
+    assert_eq!(
+      windows.iter().map(|d| (d.severity, d.message.as_str())).collect::<Vec<_>>(),
+      vec![
+        (Severity::Error, "volume path 'data' is not absolute"),
+        (Severity::Error, "volume path 'cache' is not absolute"),
+      ]
+    );
+
+    let span = windows[1].span;
+    assert_eq!(&d.content[span.start..span.end], "\"cache\"");
+  }
+
+  #[test]
+  fn test_duplicate_env_key() {
+    let (d, diagnostics) = validate(indoc!(r#"
+      FROM alpine:3.12
+      ENV PATH=/a \
+        path=/b \
+        PATH=/c
+      ENV PATH=/d:$PATH
+    "#));
+
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0].kind, DiagnosticKind::DuplicateEnvKey);
+    assert_eq!(diagnostics[0].severity, Severity::Warning);
+    assert_eq!(diagnostics[0].span.start, d.content.find("PATH=/c").unwrap());
+    assert_eq!(
+      diagnostics[0].related_spans.iter().map(|s| s.start).collect::<Vec<_>>(),
+      vec![d.content.find("PATH=/a").unwrap(), d.content.find("PATH=/c").unwrap()]
+    );
+    assert_eq!(
+      diagnostics[0].message,
+      "ENV key 'PATH' is set more than once in one instruction; '/c' overrides '/a'"
+    );
+  }
+
+  #[test]
+  fn test_duplicate_label_key() {
+    let (d, diagnostics) = validate(indoc!(r#"
+      FROM alpine:3.12
+      LABEL version=1 \
+        Version=2
+      LABEL "version"=3
+
+      FROM alpine:3.12
+      LABEL version=4
+    "#));
+
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0].kind, DiagnosticKind::DuplicateLabelKey);
+    assert_eq!(
+      diagnostics[0].related_spans
+        .iter()
+        .map(|s| &d.content[s.start..s.end])
+        .collect::<Vec<_>>(),
+      vec!["version", "\"version\""]
+    );
+    assert_eq!(diagnostics[0].span, diagnostics[0].related_spans[1]);
+    assert_eq!(
+      diagnostics[0].message,
+      "label 'version' is set 2 times in stage 0; only the last value is used"
+    );
+  }
+
+  #[test]
+  fn test_healthchecks() {
+    let (d, diagnostics) = validate(indoc!(r#"
+      FROM alpine:3.12 AS base
+      HEALTHCHECK CMD true
+      HEALTHCHECK --interval=-5s --timeout=10 --start-period=1m5sec CMD []
+      HEALTHCHECK --retries=0 --timeout=$TIMEOUT NONE
+
+      FROM base
+      HEALTHCHECK NONE
+      HEALTHCHECK --retries=-1 --interval=1.5s
+      HEALTHCHECK --retries=many CMD ["curl", "-f", "localhost"]
+    "#));
+
+    let summary: Vec<(DiagnosticKind, Severity, &str)> = diagnostics
+      .iter()
+      .map(|d| (d.kind, d.severity, d.message.as_str()))
+      .collect();
+
+    assert_eq!(summary, vec![
+      (
+        DiagnosticKind::DuplicateHealthcheck,
+        Severity::Warning,
+        "HEALTHCHECK is set 3 times in stage 0; only the last one is used"
+      ),
+      (
+        DiagnosticKind::InvalidHealthcheck,
+        Severity::Error,
+        "HEALTHCHECK CMD has an empty command"
+      ),
+      (
+        DiagnosticKind::InvalidHealthcheck,
+        Severity::Error,
+        "HEALTHCHECK --interval has an invalid duration '-5s': durations cannot be negative"
+      ),
+      (
+        DiagnosticKind::InvalidHealthcheck,
+        Severity::Error,
+        "HEALTHCHECK --timeout has an invalid duration '10': missing unit, expected e.g. s or m"
+      ),
+      (
+        DiagnosticKind::InvalidHealthcheck,
+        Severity::Error,
+        "HEALTHCHECK --start-period has an invalid duration '1m5sec': unknown unit, expected one of ns, us, ms, s, m, or h"
+      ),
+      (
+        DiagnosticKind::InvalidHealthcheck,
+        Severity::Warning,
+        "HEALTHCHECK --retries=0 has no effect; the default of 3 retries is used"
+      ),
+      (
+        DiagnosticKind::DuplicateHealthcheck,
+        Severity::Warning,
+        "HEALTHCHECK is set 3 times in stage 1; only the last one is used"
+      ),
+      (
+        DiagnosticKind::InvalidHealthcheck,
+        Severity::Error,
+        "HEALTHCHECK requires either NONE or a CMD"
+      ),
+      (
+        DiagnosticKind::InvalidHealthcheck,
+        Severity::Error,
+        "HEALTHCHECK --retries cannot be negative (-1)"
+      ),
+      (
+        DiagnosticKind::InvalidHealthcheck,
+        Severity::Error,
+        "HEALTHCHECK --retries must be a number, not 'many'"
+      ),
+    ]);
+
+    // duplicates point at every HEALTHCHECK, with the effective one first
+    assert_eq!(diagnostics[0].related_spans.len(), 3);
+    assert_eq!(diagnostics[0].span, diagnostics[0].related_spans[2]);
+    assert!(d.text(&diagnostics[0].span).starts_with("HEALTHCHECK --retries=0"));
+
+    assert_eq!(d.text(&diagnostics[1].span), "[]");
+    assert_eq!(d.text(&diagnostics[2].span), "-5s");
+    assert_eq!(d.text(&diagnostics[3].span), "10");
+    assert_eq!(d.text(&diagnostics[5].span), "0");
+
+    // invalid durations point at the offending part of the value
+    assert_eq!(d.text(&diagnostics[4].span), "sec");
+  }
+
+  #[test]
+  fn test_stopsignals() {
+    let (d, diagnostics) = validate(indoc!(r#"
+      FROM alpine:3.12
+      STOPSIGNAL SIGTERM
+      STOPSIGNAL 9
+      STOPSIGNAL sigrtmin+3
+      STOPSIGNAL $SIG
+      STOPSIGNAL 65
+      STOPSIGNAL SIGFOO
+      STOPSIGNAL 9;
+    "#));
+
+    let summary: Vec<(DiagnosticKind, Severity, &str)> = diagnostics
+      .iter()
+      .map(|d| (d.kind, d.severity, d.message.as_str()))
+      .collect();
+
+    assert_eq!(summary, vec![
+      (
+        DiagnosticKind::InvalidStopsignal,
+        Severity::Error,
+        "STOPSIGNAL 65 is out of range; signal numbers must be between 1 and 64"
+      ),
+      (
+        DiagnosticKind::InvalidStopsignal,
+        Severity::Error,
+        "STOPSIGNAL has an unknown signal 'SIGFOO'"
+      ),
+      (
+        DiagnosticKind::InvalidStopsignal,
+        Severity::Error,
+        "STOPSIGNAL has an invalid signal '9;'"
+      ),
+    ]);
+
+    assert_eq!(d.text(&diagnostics[0].span), "65");
+  }
+
+  #[test]
+  fn test_strict_flags() {
+    let (d, diagnostics) = validate(indoc!(r#"
+      FROM --platform=linux/amd64 alpine:3.12
+      RUN --mount=type=cache,target=/root/.cache --network=none --mnt=type=tmpfs make
+      COPY --from=build --chmown=1000 /a /a
+      LABEL a=b
+    "#));
+    assert_eq!(diagnostics, vec![]);
+
+    let strict = d.validate_with_options(&ValidateOptions {
+      strict_flags: true,
+      ..Default::default()
+    });
+    assert!(strict.iter().all(|d| d.kind == DiagnosticKind::UnknownFlag));
+    assert_eq!(
+      strict.iter().map(|diag| (d.text(&diag.span), diag.message.as_str())).collect::<Vec<_>>(),
+      vec![
+        ("--mnt=type=tmpfs", "unknown flag '--mnt' for RUN"),
+        ("--chmown=1000", "unknown flag '--chmown' for COPY"),
+      ]
+    );
+
+    // validation follows changes to the table
+    let specs: Vec<InstructionSpec> = INSTRUCTION_SPECS
+      .iter()
+      .map(|s| match s.keyword {
+        "RUN" => InstructionSpec {
+          flags: &[FlagSpec { name: "mnt", takes_value: true }],
+          ..*s
+        },
+        _ => *s
+      })
+      .collect();
+
+    let mut diagnostics = Vec::new();
+    super::check_unknown_flags(&d, &specs, &mut diagnostics);
+    assert_eq!(
+      diagnostics.iter().map(|diag| d.text(&diag.span)).collect::<Vec<_>>(),
+      vec!["--mount=type=cache,target=/root/.cache", "--network=none", "--chmown=1000"]
+    );
+  }
+
+  #[test]
+  fn test_unknown_instructions() {
+    let content = indoc!(r#"
+      FROM alpine:3.12
+      COPPY foo /bar
+      ONBUILD RUN echo hi
+      frobnicate --all
+    "#);
+
+    let parse = |policy| Dockerfile::parse_with_options(content, &ParseOptions {
+      unknown_instructions: policy,
+      ..Default::default()
+    });
+
+    // allowed, and not reported
+    let d = parse(UnknownPolicy::Allow).unwrap();
+    assert_eq!(d.instructions[1].as_misc().unwrap().instruction.content, "COPPY");
+    assert_eq!(d.validate(), vec![]);
+
+    let d = parse(UnknownPolicy::Warn).unwrap();
+    let diagnostics = d.validate();
+    assert_eq!(
+      diagnostics.iter().map(|diag| (d.text(&diag.span), diag.message.as_str())).collect::<Vec<_>>(),
+      vec![
+        ("COPPY", "unknown instruction 'COPPY': did you mean 'COPY'?"),
+        ("frobnicate", "unknown instruction 'frobnicate'"),
+      ]
+    );
+    assert!(diagnostics.iter().all(|diag| diag.severity == Severity::Warning));
+    assert_eq!(
+      fixes::apply(&d, diagnostics.into_iter().filter_map(|diag| diag.suggested_fix)).unwrap(),
+      content.replace("COPPY", "COPY")
+    );
+
+    match parse(UnknownPolicy::Deny) {
+      Err(Error::UnknownInstruction { keyword, span }) => {
+        assert_eq!(keyword, "COPPY");
+        assert_eq!(&content[span.start..span.end], "COPPY");
+      },
+      other => panic!("expected UnknownInstruction, got {:?}", other)
+    }
+
+    // recognized but unmodeled keywords are always allowed
+    let d = Dockerfile::parse_with_options("FROM alpine\nEXPOSE 8080\n", &ParseOptions {
+      unknown_instructions: UnknownPolicy::Deny,
+      ..Default::default()
+    }).unwrap();
+    assert!(d.instructions[1].as_misc().is_some());
+  }
+
+  #[test]
+  fn test_lenient_pairs() {
+    let content = indoc!(r#"
+      ARG version= 1.0
+      FROM alpine:3.12
+      ENV foo =bar baz = "a b"
+      LABEL "maintainer" =me
+    "#);
+
+    let options = ParseOptions { lenient: true, ..Default::default() };
+    let d = Dockerfile::parse_with_options(content, &options).unwrap();
+
+    let arg = d.instructions[0].as_arg().unwrap();
+    assert_eq!(arg.value.as_ref().map(|v| v.content.as_str()), Some("1.0"));
+
+    let env = d.instructions[2].as_env().unwrap();
+    assert_eq!(
+      env.vars.iter().map(|v| (v.key.content.as_str(), v.value.to_string())).collect::<Vec<_>>(),
+      vec![("foo", "bar".to_string()), ("baz", "a b".to_string())]
+    );
+
+    let label = d.instructions[3].as_label().unwrap();
+    assert_eq!(label.labels[0].name.content, "maintainer");
+    assert_eq!(label.labels[0].value.content, "me");
+
+    let diagnostics = d.validate();
+    assert_eq!(
+      diagnostics.iter().map(|diag| diag.message.as_str()).collect::<Vec<_>>(),
+      vec![
+        "unexpected space after '=' in ARG pair",
+        "unexpected space before '=' in ENV pair",
+        "unexpected space before '=' in ENV pair",
+        "unexpected space after '=' in ENV pair",
+        "unexpected space before '=' in LABEL pair",
+      ]
+    );
+    assert!(diagnostics.iter().all(|diag| diag.kind == DiagnosticKind::MisplacedPairSpace));
+
+    let fixed = fixes::apply(&d, diagnostics.into_iter().filter_map(|diag| diag.suggested_fix)).unwrap();
+    assert_eq!(fixed, indoc!(r#"
+      ARG version=1.0
+      FROM alpine:3.12
+      ENV foo=bar baz="a b"
+      LABEL "maintainer"=me
+    "#));
+    assert!(Dockerfile::parse(&fixed).is_ok());
+  }
+
+  #[test]
+  fn test_invalid_arg_names() {
+    let (d, diagnostics) = validate(indoc!(r#"
+      ARG foo-bar=1
+      ARG HTTP_PROXY
+      FROM alpine:3.12
+      ARG _ok=2
+    "#));
+
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0].kind, DiagnosticKind::InvalidArgName);
+    assert_eq!(diagnostics[0].severity, Severity::Warning);
+    assert_eq!(d.text(&diagnostics[0].span), "-");
+    assert!(diagnostics[0].message.contains("'foo-bar'"));
+  }
+}
@@ -0,0 +1,841 @@
+// (C) Copyright 2026 Hewlett Packard Enterprise Development LP
+
+use std::collections::{HashMap, HashSet};
+
+use crate::error::*;
+use crate::options::DEFAULT_MAX_EXPANSION_LEN;
+use crate::splicer::{Span, Splicer};
+use crate::util::{raw_span, SpannedString};
+
+/// An operator applied to a braced variable expansion, e.g. the `:-` in
+/// `${tag:-latest}`.
+///
+/// Each operator comes in two flavors: with a colon (`:-`, `:+`, `:?`) the
+/// operator treats an empty value the same as an unset one; without a colon
+/// (`-`, `+`, `?`) only unset variables are considered.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ExpansionOp {
+  /// `${name:-word}`: expands to `word` if `name` is unset
+  Default { word: String, check_empty: bool },
+
+  /// `${name:+word}`: expands to `word` if `name` is set, otherwise to nothing
+  Alternate { word: String, check_empty: bool },
+
+  /// `${name:?message}`: fails with `message` if `name` is unset
+  Required { message: String, check_empty: bool },
+}
+
+/// A single `$name` or `${name...}` expansion found in a string.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Expansion {
+  /// The span of the full expansion, relative to the scanned string
+  pub span: Span,
+  pub name: String,
+  pub braced: bool,
+  pub operator: Option<ExpansionOp>,
+}
+
+/// A variable reference found in a Dockerfile, e.g. `$foo` or
+/// `${foo:-default}`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VarRef {
+  /// The name of the referenced variable
+  pub name: String,
+
+  /// The span of the full reference in the original Dockerfile
+  pub span: Span,
+
+  /// If true, the reference was written in braced (`${foo}`) form
+  pub braced: bool,
+
+  /// The operator applied to a braced reference, if any
+  pub operator: Option<ExpansionOp>,
+
+  /// How the reference is expanded, e.g. `ExpansionBehavior::ShellExpands`
+  /// for references in a heredoc body
+  pub expansion: ExpansionBehavior,
+}
+
+/// Describes whether, and by what, variable references in an instruction's
+/// arguments are expanded.
+///
+/// See the [environment replacement] section of the Dockerfile reference.
+///
+/// [environment replacement]: https://docs.docker.com/engine/reference/builder/#environment-replacement
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ExpansionBehavior {
+  /// `ARG` and `ENV` references are substituted by Docker itself, e.g. in
+  /// `COPY`, `ENV`, or `WORKDIR`
+  DockerExpands,
+
+  /// References are passed through as-is and may be expanded by the shell at
+  /// build or run time, e.g. in shell-form `RUN` or `CMD`
+  ShellExpands,
+
+  /// References are never expanded, e.g. in exec-form `RUN` or `CMD`
+  NoExpansion,
+}
+
+fn is_name_char(c: char) -> bool {
+  c.is_ascii_alphanumeric() || c == '_'
+}
+
+/// Parses the operator and trailing word of a braced expansion, starting just
+/// after the variable name. Returns the operator and the byte offset just past
+/// the closing brace.
+fn parse_operator(s: &str, start: usize) -> Option<(ExpansionOp, usize)> {
+  let rest = &s[start..];
+  let (check_empty, rest) = match rest.strip_prefix(':') {
+    Some(r) => (true, r),
+    None => (false, rest),
+  };
+
+  let mut chars = rest.char_indices();
+  let (_, op) = chars.next()?;
+  if !matches!(op, '-' | '+' | '?') {
+    return None;
+  }
+
+  // the word runs to the matching closing brace; nested expansions (e.g.
+  // `${foo:-${bar}}`) are kept intact, and a backslash escapes the following
+  // character so messages may contain a literal `}`
+  let mut word = String::new();
+  let mut depth = 0;
+  let mut prev_dollar = false;
+  while let Some((i, c)) = chars.next() {
+    match c {
+      '\\' => {
+        let (_, escaped) = chars.next()?;
+        word.push(escaped);
+        prev_dollar = false;
+        continue;
+      },
+      '{' if prev_dollar => depth += 1,
+      '}' if depth > 0 => depth -= 1,
+      '}' => {
+        let end = s.len() - rest.len() + i + 1;
+        let op = match op {
+          '-' => ExpansionOp::Default { word, check_empty },
+          '+' => ExpansionOp::Alternate { word, check_empty },
+          _ => ExpansionOp::Required { message: word, check_empty },
+        };
+
+        return Some((op, end));
+      },
+      _ => ()
+    }
+
+    prev_dollar = c == '$';
+    word.push(c);
+  }
+
+  // no closing brace
+  None
+}
+
+/// Attempts to parse a single expansion beginning at `start`, which must point
+/// at a `$`.
+fn parse_expansion(s: &str, start: usize) -> Option<Expansion> {
+  let rest = &s[start + 1..];
+
+  if let Some(inner) = rest.strip_prefix('{') {
+    let name_len = inner.find(|c: char| !is_name_char(c)).unwrap_or(inner.len());
+    if name_len == 0 {
+      return None;
+    }
+
+    let name_start = start + 2;
+    let name_end = name_start + name_len;
+    let name = s[name_start..name_end].to_string();
+
+    if s[name_end..].starts_with('}') {
+      return Some(Expansion {
+        span: Span::new(start, name_end + 1),
+        name,
+        braced: true,
+        operator: None,
+      });
+    }
+
+    let (operator, end) = parse_operator(s, name_end)?;
+    Some(Expansion {
+      span: Span::new(start, end),
+      name,
+      braced: true,
+      operator: Some(operator),
+    })
+  } else {
+    let name_len = rest.find(|c: char| !is_name_char(c)).unwrap_or(rest.len());
+    if name_len == 0 {
+      return None;
+    }
+
+    Some(Expansion {
+      span: Span::new(start, start + 1 + name_len),
+      name: rest[..name_len].to_string(),
+      braced: false,
+      operator: None,
+    })
+  }
+}
+
+/// Finds all variable expansions in a string, in order.
+///
+/// Malformed or unsupported expansions (e.g. an unterminated `${foo` or
+/// `${foo%bar}`) are not considered variable references and are skipped.
+pub(crate) fn find_expansions(s: &str) -> Vec<Expansion> {
+  let mut expansions = Vec::new();
+  let mut pos = 0;
+
+  while let Some(offset) = s[pos..].find('$') {
+    let start = pos + offset;
+    match parse_expansion(s, start) {
+      Some(expansion) => {
+        pos = expansion.span.end;
+        expansions.push(expansion);
+      },
+      None => pos = start + 1
+    }
+  }
+
+  expansions
+}
+
+/// Finds all variable references in a string, in order. References are
+/// assumed to be expanded by Docker.
+///
+/// Spans are absolute offsets into the original Dockerfile, excluding the
+/// quotes of a quoted string and accounting for any escape sequences in it.
+///
+/// ```
+/// use dockerfile_parser::{find_references, Dockerfile, Span};
+///
+/// let dockerfile = Dockerfile::parse("FROM alpine:${TAG:-latest}").unwrap();
+/// let from = dockerfile.instructions[0].as_from().unwrap();
+///
+/// let refs = find_references(&from.image);
+/// assert_eq!(refs[0].name, "TAG");
+/// assert_eq!(refs[0].span, Span::new(12, 26));
+/// ```
+pub fn find_references(s: &SpannedString) -> Vec<VarRef> {
+  let start = s.content_span().start;
+  let raw = s.raw_text.as_deref().filter(|_| s.is_quoted());
+
+  find_expansions(&s.content)
+    .into_iter()
+    .map(|e| VarRef {
+      name: e.name,
+      span: match raw {
+        Some(raw) => {
+          let span = raw_span(raw, e.span);
+          Span::new(s.span.start + span.start, s.span.start + span.end)
+        },
+        None => Span::new(start + e.span.start, start + e.span.end)
+      },
+      braced: e.braced,
+      operator: e.operator,
+      expansion: ExpansionBehavior::DockerExpands,
+    })
+    .collect()
+}
+
+/// Given a map of key/value pairs, perform variable substitution on a given
+/// input string, returning an error describing why substitution was
+/// impossible, if so.
+///
+/// In addition to plain `$foo` and `${foo}` references, the `${foo:-default}`,
+/// `${foo:+alternate}`, and `${foo:?message}` forms (and their colon-less
+/// variants) are supported. A required (`?`) reference to an unset variable
+/// fails with `Error::RequiredVariableUnset`, passing through the
+/// author-provided message.
+///
+/// `max_recursion_depth` controls the maximum allowed recursion depth if
+/// variables refer to other strings themselves containing variable references.
+///
+/// Output is limited to `DEFAULT_MAX_EXPANSION_LEN` bytes; longer results fail
+/// with `Error::ExpansionTooLarge`. See `try_substitute_with_max_len()` to
+/// configure the limit.
+pub fn try_substitute(
+  s: &str,
+  vars: &HashMap<&str, &str>,
+  used_vars: &mut HashSet<String>,
+  max_recursion_depth: u8
+) -> Result<String> {
+  try_substitute_with_max_len(s, vars, used_vars, max_recursion_depth, DEFAULT_MAX_EXPANSION_LEN)
+}
+
+/// Like `try_substitute()`, but fails with `Error::ExpansionTooLarge` if any
+/// intermediate or final result would exceed `max_len` bytes.
+///
+/// The limit is checked before each substitution is spliced in, so
+/// exponentially growing expansions (e.g. variables that each reference the
+/// previous one twice) fail without building the oversized string.
+pub fn try_substitute_with_max_len(
+  s: &str,
+  vars: &HashMap<&str, &str>,
+  used_vars: &mut HashSet<String>,
+  max_recursion_depth: u8,
+  max_len: usize
+) -> Result<String> {
+  try_substitute_with_literals(s, vars, &HashSet::new(), used_vars, max_recursion_depth, max_len)
+}
+
+/// Like `try_substitute()`, but the values of variables named in `literals`
+/// are substituted verbatim, without expanding any references they contain,
+/// e.g. for single-quoted `ARG` defaults.
+pub(crate) fn try_substitute_with_literals(
+  s: &str,
+  vars: &HashMap<&str, &str>,
+  literals: &HashSet<&str>,
+  used_vars: &mut HashSet<String>,
+  max_recursion_depth: u8,
+  max_len: usize
+) -> Result<String> {
+  let mut splicer = Splicer::new(s);
+
+  for expansion in find_expansions(s) {
+    if max_recursion_depth == 0 {
+      // can't substitute, so give up
+      return Err(Error::SubstitutionDepthExceeded);
+    }
+
+    let depth = max_recursion_depth.saturating_sub(1);
+    let name = expansion.name.as_str();
+
+    // an empty value is only treated as unset by the colon forms
+    let value = |check_empty: bool| vars.get(name)
+      .filter(|v| !(check_empty && v.is_empty()));
+
+    let expand_value = |v: &str, used_vars: &mut HashSet<String>| {
+      if literals.contains(name) {
+        Ok(v.to_string())
+      } else {
+        try_substitute_with_literals(v, vars, literals, used_vars, depth, max_len)
+      }
+    };
+
+    let substituted = match &expansion.operator {
+      None => match vars.get(name) {
+        Some(v) => {
+          let content = expand_value(v, used_vars)?;
+          used_vars.insert(name.to_string());
+          content
+        },
+        None => return Err(Error::UndefinedVariable {
+          name: name.to_string()
+        })
+      },
+      Some(ExpansionOp::Default { word, check_empty }) => match value(*check_empty) {
+        Some(v) => {
+          let content = expand_value(v, used_vars)?;
+          used_vars.insert(name.to_string());
+          content
+        },
+        None => try_substitute_with_literals(word, vars, literals, used_vars, depth, max_len)?
+      },
+      Some(ExpansionOp::Alternate { word, check_empty }) => match value(*check_empty) {
+        Some(_) => try_substitute_with_literals(word, vars, literals, used_vars, depth, max_len)?,
+        None => String::new()
+      },
+      Some(ExpansionOp::Required { message, check_empty }) => match value(*check_empty) {
+        Some(v) => {
+          let content = expand_value(v, used_vars)?;
+          used_vars.insert(name.to_string());
+          content
+        },
+        None => return Err(Error::RequiredVariableUnset {
+          name: name.to_string(),
+          message: message.clone(),
+        })
+      },
+    };
+
+    // splice the substituted content back into the output string, as long as
+    // the result stays within the limit
+    let len = splicer.content.len() - (expansion.span.end - expansion.span.start) + substituted.len();
+    if len > max_len {
+      return Err(Error::ExpansionTooLarge { limit: max_len });
+    }
+
+    splicer.splice(&expansion.span, &substituted);
+  }
+
+  Ok(splicer.content)
+}
+
+/// Given a map of key/value pairs, perform variable substitution on a given
+/// input string. `max_recursion_depth` controls the maximum allowed recursion
+/// depth if variables refer to other strings themselves containing variable
+/// references. A small number but reasonable is recommended by default, e.g.
+/// 16.
+/// If None is returned, substitution was impossible, either because a
+/// referenced variable did not exist, or recursion depth was exceeded.
+///
+/// See `try_substitute()` for a variant that reports why substitution failed.
+pub fn substitute(
+  s: &str,
+  vars: &HashMap<&str, &str>,
+  used_vars: &mut HashSet<String>,
+  max_recursion_depth: u8
+) -> Option<String> {
+  try_substitute(s, vars, used_vars, max_recursion_depth).ok()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  use pretty_assertions::assert_eq;
+
+  #[test]
+  fn test_find_expansions() {
+    assert_eq!(
+      find_expansions("$foo ${bar} $ ${} ${baz"),
+      vec![
+        Expansion {
+          span: Span::new(0, 4),
+          name: "foo".into(),
+          braced: false,
+          operator: None,
+        },
+        Expansion {
+          span: Span::new(5, 11),
+          name: "bar".into(),
+          braced: true,
+          operator: None,
+        },
+      ]
+    );
+
+    assert_eq!(
+      find_expansions(r"${foo:?must set \} foo} ${bar?} ${baz:-${qux}}"),
+      vec![
+        Expansion {
+          span: Span::new(0, 23),
+          name: "foo".into(),
+          braced: true,
+          operator: Some(ExpansionOp::Required {
+            message: "must set } foo".into(),
+            check_empty: true,
+          }),
+        },
+        Expansion {
+          span: Span::new(24, 31),
+          name: "bar".into(),
+          braced: true,
+          operator: Some(ExpansionOp::Required {
+            message: "".into(),
+            check_empty: false,
+          }),
+        },
+        Expansion {
+          span: Span::new(32, 46),
+          name: "baz".into(),
+          braced: true,
+          operator: Some(ExpansionOp::Default {
+            word: "${qux}".into(),
+            check_empty: true,
+          }),
+        },
+      ]
+    );
+  }
+
+  #[test]
+  fn test_substitute() {
+    let mut vars = HashMap::new();
+    vars.insert("foo", "bar");
+    vars.insert("baz", "qux");
+    vars.insert("lorem", "$foo");
+    vars.insert("ipsum", "${lorem}");
+    vars.insert("recursion1", "$recursion2");
+    vars.insert("recursion2", "$recursion1");
+
+    let mut used_vars = HashSet::new();
+    assert_eq!(
+      substitute("hello world", &vars, &mut used_vars, 16).as_deref(),
+      Some("hello world")
+    );
+
+    let mut used_vars = HashSet::new();
+    assert_eq!(
+      substitute("hello $foo", &vars, &mut used_vars, 16).as_deref(),
+      Some("hello bar")
+    );
+    assert_eq!(used_vars, {
+      let mut h = HashSet::new();
+      h.insert("foo".to_string());
+      h
+    });
+
+    let mut used_vars = HashSet::new();
+    assert_eq!(
+      substitute("hello $foo", &vars, &mut used_vars, 0).as_deref(),
+      None
+    );
+    assert!(used_vars.is_empty());
+
+    let mut used_vars = HashSet::new();
+    assert_eq!(
+      substitute("hello ${foo}", &vars, &mut used_vars, 16).as_deref(),
+      Some("hello bar")
+    );
+    assert_eq!(used_vars, {
+      let mut h = HashSet::new();
+      h.insert("foo".to_string());
+      h
+    });
+
+    let mut used_vars = HashSet::new();
+    assert_eq!(
+      substitute("$baz $foo", &vars, &mut used_vars, 16).as_deref(),
+      Some("qux bar")
+    );
+    assert_eq!(used_vars, {
+      let mut h = HashSet::new();
+      h.insert("baz".to_string());
+      h.insert("foo".to_string());
+      h
+    });
+
+    let mut used_vars = HashSet::new();
+    assert_eq!(
+      substitute("hello $lorem", &vars, &mut used_vars, 16).as_deref(),
+      Some("hello bar")
+    );
+    assert_eq!(used_vars, {
+      let mut h = HashSet::new();
+      h.insert("foo".to_string());
+      h.insert("lorem".to_string());
+      h
+    });
+
+    let mut used_vars = HashSet::new();
+    assert_eq!(
+      substitute("hello $lorem", &vars, &mut used_vars, 1).as_deref(),
+      None
+    );
+    assert!(used_vars.is_empty());
+
+    let mut used_vars = HashSet::new();
+    assert_eq!(
+      substitute("hello $ipsum", &vars, &mut used_vars, 16).as_deref(),
+      Some("hello bar")
+    );
+    assert_eq!(used_vars, {
+      let mut h = HashSet::new();
+      h.insert("foo".to_string());
+      h.insert("lorem".to_string());
+      h.insert("ipsum".to_string());
+      h
+    });
+
+    let mut used_vars = HashSet::new();
+    assert_eq!(
+      substitute("hello $ipsum", &vars, &mut used_vars, 2).as_deref(),
+      None
+    );
+    assert!(used_vars.is_empty());
+
+    let mut used_vars = HashSet::new();
+    assert_eq!(
+      substitute("hello $recursion1", &vars, &mut used_vars, 16).as_deref(),
+      None
+    );
+    assert!(used_vars.is_empty());
+  }
+
+  #[test]
+  fn test_substitute_operators() {
+    let mut vars = HashMap::new();
+    vars.insert("foo", "bar");
+    vars.insert("empty", "");
+
+    let mut used_vars = HashSet::new();
+    assert_eq!(
+      substitute("${foo:-x} ${unset:-x} ${empty:-x} ${empty-x}", &vars, &mut used_vars, 16).as_deref(),
+      Some("bar x x ")
+    );
+
+    let mut used_vars = HashSet::new();
+    assert_eq!(
+      substitute("${foo:+x} ${unset:+x} ${empty:+x} ${empty+x}", &vars, &mut used_vars, 16).as_deref(),
+      Some("x   x")
+    );
+
+    let mut used_vars = HashSet::new();
+    assert_eq!(
+      substitute("${unset:-$foo}", &vars, &mut used_vars, 16).as_deref(),
+      Some("bar")
+    );
+  }
+
+  #[test]
+  fn test_try_substitute_required() {
+    let mut vars = HashMap::new();
+    vars.insert("foo", "bar");
+    vars.insert("empty", "");
+
+    let mut used_vars = HashSet::new();
+    assert_eq!(
+      try_substitute("${foo:?foo is required}", &vars, &mut used_vars, 16).unwrap(),
+      "bar"
+    );
+
+    let mut used_vars = HashSet::new();
+    match try_substitute("alpine:${tag:?set a tag, e.g. 3.12}", &vars, &mut used_vars, 16) {
+      Err(Error::RequiredVariableUnset { name, message }) => {
+        assert_eq!(name, "tag");
+        assert_eq!(message, "set a tag, e.g. 3.12");
+      },
+      other => panic!("expected RequiredVariableUnset, got {:?}", other)
+    }
+
+    let mut used_vars = HashSet::new();
+    match try_substitute(r"${tag:?use \{braces\} sparingly}", &vars, &mut used_vars, 16) {
+      Err(Error::RequiredVariableUnset { message, .. }) => {
+        assert_eq!(message, "use {braces} sparingly");
+      },
+      other => panic!("expected RequiredVariableUnset, got {:?}", other)
+    }
+
+    // without a colon, only unset variables are an error
+    let mut used_vars = HashSet::new();
+    assert_eq!(
+      try_substitute("${empty?must be set}", &vars, &mut used_vars, 16).unwrap(),
+      ""
+    );
+
+    let mut used_vars = HashSet::new();
+    assert!(matches!(
+      try_substitute("${empty:?must not be empty}", &vars, &mut used_vars, 16),
+      Err(Error::RequiredVariableUnset { .. })
+    ));
+
+    let mut used_vars = HashSet::new();
+    assert!(matches!(
+      try_substitute("$unset", &vars, &mut used_vars, 16),
+      Err(Error::UndefinedVariable { .. })
+    ));
+  }
+
+  #[test]
+  fn test_expands_variables() {
+    use crate::Dockerfile;
+    use ExpansionBehavior::*;
+
+    let d = Dockerfile::parse(indoc::indoc!(r#"
+      ARG base=alpine
+      FROM $base
+      LABEL version=$version
+      ENV PATH=$PATH:/foo
+      COPY $src /dst
+      RUN echo $HOME
+      RUN ["echo", "$HOME"]
+      CMD echo $HOME
+      CMD ["echo", "$HOME"]
+      ENTRYPOINT echo $HOME
+      ENTRYPOINT ["echo", "$HOME"]
+      ADD $src /dst
+      EXPOSE $port
+      STOPSIGNAL $signal
+      user $user
+      VOLUME $dir
+      WORKDIR $dir
+      HEALTHCHECK --interval=5s CMD curl $url
+      HEALTHCHECK CMD ["curl", "$url"]
+      HEALTHCHECK NONE
+      SHELL ["/bin/sh", "-c"]
+      ONBUILD RUN echo $HOME
+      MAINTAINER $me
+    "#)).unwrap();
+
+    assert_eq!(
+      d.instructions.iter().map(|i| i.expands_variables()).collect::<Vec<_>>(),
+      vec![
+        DockerExpands, DockerExpands, DockerExpands, DockerExpands, DockerExpands,
+        ShellExpands, NoExpansion,
+        ShellExpands, NoExpansion,
+        ShellExpands, NoExpansion,
+        DockerExpands, DockerExpands, DockerExpands, DockerExpands,
+        DockerExpands, DockerExpands,
+        ShellExpands, NoExpansion, NoExpansion,
+        NoExpansion, NoExpansion, NoExpansion,
+      ]
+    );
+  }
+
+  #[test]
+  fn test_substitute_max_len() {
+    use crate::{Dockerfile, ParseOptions};
+
+    // each variable references the previous one twice, doubling its length
+    let names: Vec<String> = (0..16).map(|i| format!("v{}", i)).collect();
+    let values: Vec<String> = (1..16)
+      .map(|i| format!("${{{0}}}${{{0}}}", names[i - 1]))
+      .collect();
+
+    let base = "x".repeat(256);
+    let mut vars: HashMap<&str, &str> = HashMap::new();
+    vars.insert(&names[0], &base);
+    for (name, value) in names[1..].iter().zip(&values) {
+      vars.insert(name, value);
+    }
+
+    assert_eq!(
+      try_substitute_with_max_len("$v4", &vars, &mut HashSet::new(), 16, 4096).unwrap().len(),
+      4096
+    );
+    assert!(matches!(
+      try_substitute_with_max_len("$v5", &vars, &mut HashSet::new(), 16, 4096),
+      Err(Error::ExpansionTooLarge { limit: 4096 })
+    ));
+    assert!(matches!(
+      try_substitute_with_max_len("x$v4", &vars, &mut HashSet::new(), 16, 4096),
+      Err(Error::ExpansionTooLarge { limit: 4096 })
+    ));
+
+    // 256 * 2^15 bytes exceeds the default limit
+    assert!(matches!(
+      try_substitute("$v15", &vars, &mut HashSet::new(), 16),
+      Err(Error::ExpansionTooLarge { limit: DEFAULT_MAX_EXPANSION_LEN })
+    ));
+    assert_eq!(substitute("$v15", &vars, &mut HashSet::new(), 16), None);
+
+    let content = "ARG a=aaaaaaaa\nARG b=$a$a\nARG c=$b$b\nFROM $c\n";
+    let d = Dockerfile::parse_with_options(content, &ParseOptions {
+      max_expansion_len: 16,
+      ..Default::default()
+    }).unwrap();
+
+    let from = d.instructions[3].as_from().unwrap();
+    assert!(matches!(
+      from.image_parsed.try_resolve_vars(&d),
+      Err(Error::ExpansionTooLarge { limit: 16 })
+    ));
+  }
+
+  #[test]
+  fn test_variable_references() {
+    use crate::Dockerfile;
+
+    let d = Dockerfile::parse(indoc::indoc!(r#"
+      ARG image=alpine
+      ARG tag=${base_tag:-3.12}
+      FROM --platform=$BUILDPLATFORM ${image}:$tag AS build
+      ENV PATH="$HOME/bin:$PATH" ${prefix}_HOME=/opt
+      COPY --from=$stage --chown=${user}:${group} $src /dst/$name
+      RUN ["echo", "$HOME"]
+      RUN echo $HOME
+      WORKDIR \
+        /src/$dir
+    "#)).unwrap();
+
+    let refs = |i: usize| d.instructions[i]
+      .variable_references()
+      .into_iter()
+      .map(|r| (r.name, &d.content[r.span.start..r.span.end]))
+      .collect::<Vec<_>>();
+
+    assert_eq!(refs(0), vec![]);
+    assert_eq!(refs(1), vec![("base_tag".to_string(), "${base_tag:-3.12}")]);
+    assert_eq!(refs(2), vec![
+      ("BUILDPLATFORM".to_string(), "$BUILDPLATFORM"),
+      ("image".to_string(), "${image}"),
+      ("tag".to_string(), "$tag"),
+    ]);
+    assert_eq!(refs(3), vec![
+      ("HOME".to_string(), "$HOME"),
+      ("PATH".to_string(), "$PATH"),
+      ("prefix".to_string(), "${prefix}"),
+    ]);
+    assert_eq!(refs(4), vec![
+      ("stage".to_string(), "$stage"),
+      ("user".to_string(), "${user}"),
+      ("group".to_string(), "${group}"),
+      ("src".to_string(), "$src"),
+      ("name".to_string(), "$name"),
+    ]);
+    assert_eq!(refs(5), vec![]);
+    assert_eq!(refs(6), vec![]);
+    assert_eq!(refs(7), vec![("dir".to_string(), "$dir")]);
+
+    // offsets following escape sequences still line up with the input
+    let d = Dockerfile::parse(indoc::indoc!(r#"
+      ENV A="x\"y$HOME" B=z
+      LABEL a="x\"y $HOME" "b\tc"="é${HOME}"
+      ARG a="x\"y${HOME}"
+      LABEL c="x\
+      y $HOME"
+    "#)).unwrap();
+
+    let refs = |i: usize| d.instructions[i]
+      .variable_references()
+      .into_iter()
+      .map(|r| &d.content[r.span.start..r.span.end])
+      .collect::<Vec<_>>();
+
+    assert_eq!(refs(0), vec!["$HOME"]);
+    assert_eq!(refs(1), vec!["$HOME", "${HOME}"]);
+    assert_eq!(refs(2), vec!["${HOME}"]);
+    assert_eq!(refs(3), vec!["$HOME"]);
+
+    let d = Dockerfile::parse("ARG tag=${base_tag:-3.12}").unwrap();
+    let arg = d.instructions[0].variable_references().remove(0);
+    assert!(arg.braced);
+    assert_eq!(arg.operator, Some(ExpansionOp::Default {
+      word: "3.12".into(),
+      check_empty: true,
+    }));
+  }
+
+  #[test]
+  fn test_shell_variable_references() {
+    use crate::Dockerfile;
+
+    let d = Dockerfile::parse(indoc::indoc!(r#"
+      FROM alpine
+      RUN <<'EOF' cat >/literal && <<EOF2 cat >/expanded
+      echo $LITERAL
+      EOF
+      echo ${EXPANDED}
+      EOF2
+      RUN ["echo", "$HOME"]
+      CMD echo $USER
+    "#)).unwrap();
+
+    let refs = |i: usize, all: bool| {
+      let refs = if all {
+        d.instructions[i].all_variable_references()
+      } else {
+        d.instructions[i].variable_references()
+      };
+
+      refs
+        .into_iter()
+        .map(|r| (&d.content[r.span.start..r.span.end], r.expansion))
+        .collect::<Vec<_>>()
+    };
+
+    // only the unquoted heredoc's body is expanded
+    assert_eq!(refs(1, false), vec![("${EXPANDED}", ExpansionBehavior::ShellExpands)]);
+    assert_eq!(refs(1, true), vec![
+      ("$LITERAL", ExpansionBehavior::NoExpansion),
+      ("${EXPANDED}", ExpansionBehavior::ShellExpands),
+    ]);
+    let expanded = d.instructions[1].variable_references().remove(0);
+    assert_eq!(Some(expanded.span.start), d.content.find("${EXPANDED}"));
+
+    assert_eq!(refs(2, false), vec![]);
+    assert_eq!(refs(2, true), vec![("$HOME", ExpansionBehavior::NoExpansion)]);
+    assert_eq!(refs(3, false), vec![]);
+    assert_eq!(refs(3, true), vec![("$USER", ExpansionBehavior::ShellExpands)]);
+
+    // references expanded by Docker are tagged as such
+    let d = Dockerfile::parse("FROM alpine\nWORKDIR /$dir").unwrap();
+    assert_eq!(
+      d.instructions[1].all_variable_references()[0].expansion,
+      ExpansionBehavior::DockerExpands
+    );
+  }
+}
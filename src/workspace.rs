@@ -0,0 +1,197 @@
+// (C) Copyright 2026 Hewlett Packard Enterprise Development LP
+
+use std::collections::BTreeMap;
+
+use crate::dockerfile_parser::{Dockerfile, Instruction};
+use crate::image::ImageRef;
+use crate::instructions::ArgInstruction;
+use crate::splicer::Span;
+use crate::stage::StageParent;
+
+/// A set of named, already-parsed Dockerfiles analyzed together, e.g. all
+/// Dockerfiles in a monorepo.
+///
+/// Files are kept ordered by name, so all aggregate queries return results in
+/// a stable order regardless of the order files were added in.
+///
+/// ```
+/// use dockerfile_parser::{Dockerfile, ImageRef, Workspace};
+///
+/// let mut workspace = Workspace::new();
+/// workspace.add("web/Dockerfile", Dockerfile::parse("FROM alpine").unwrap());
+/// workspace.add("api/Dockerfile", Dockerfile::parse("FROM docker.io/library/alpine:latest").unwrap());
+///
+/// let images = workspace.images();
+/// assert_eq!(images[0].0, "api/Dockerfile");
+/// assert_eq!(images[1].0, "web/Dockerfile");
+/// assert_eq!(images[0].1, images[1].1);
+/// assert_eq!(images[0].1, ImageRef::parse("docker.io/library/alpine:latest"));
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Workspace {
+  files: BTreeMap<String, Dockerfile>,
+}
+
+impl Workspace {
+  /// Creates an empty workspace.
+  pub fn new() -> Workspace {
+    Workspace::default()
+  }
+
+  /// Adds a Dockerfile to the workspace under the given name, typically its
+  /// path. If a file with the same name was already added, it is replaced and
+  /// returned.
+  pub fn add<S: Into<String>>(&mut self, name: S, dockerfile: Dockerfile) -> Option<Dockerfile> {
+    self.files.insert(name.into(), dockerfile)
+  }
+
+  /// Returns the Dockerfile added under the given name, if any.
+  pub fn find(&self, name: &str) -> Option<&Dockerfile> {
+    self.files.get(name)
+  }
+
+  /// Returns an iterator over all files and their names, ordered by name.
+  pub fn iter(&self) -> impl Iterator<Item = (&str, &Dockerfile)> {
+    self.files.iter().map(|(name, dockerfile)| (name.as_str(), dockerfile))
+  }
+
+  /// Returns the number of files in the workspace.
+  pub fn len(&self) -> usize {
+    self.files.len()
+  }
+
+  /// Returns true if the workspace contains no files.
+  pub fn is_empty(&self) -> bool {
+    self.files.is_empty()
+  }
+
+  /// Returns every external base image used by a `FROM` instruction, as
+  /// `(file name, image, span)`, where `span` is the image as written.
+  ///
+  /// Global `ARG`s are substituted where possible, and images are
+  /// canonicalized (see `ImageRef::canonicalize()`) so differently written
+  /// references to the same image compare equal. Results are sorted by image,
+  /// then file name, then position, so uses of the same image are adjacent.
+  /// References to other stages and `scratch` are not included.
+  pub fn images(&self) -> Vec<(&str, ImageRef, Span)> {
+    let mut images = Vec::new();
+
+    for (name, dockerfile) in self.iter() {
+      for stage in dockerfile.iter_stages() {
+        let (image, from) = match (&stage.parent, stage.instructions[0].as_from()) {
+          (StageParent::Image(image), Some(from)) => (image, from),
+          _ => continue
+        };
+
+        let resolved = image
+          .resolve_vars(dockerfile)
+          .unwrap_or_else(|| (*image).clone());
+
+        images.push((name, resolved.canonicalize(), from.image.span));
+      }
+    }
+
+    images.sort_by(|a, b| {
+      (a.1.to_string(), a.0, a.2.start).cmp(&(b.1.to_string(), b.0, b.2.start))
+    });
+
+    images
+  }
+
+  /// Returns every `ARG` declared in the workspace, global or within a stage,
+  /// grouped by name. Each group lists the declaring file names and
+  /// instructions, ordered by file name and then position, e.g. to check
+  /// that a build arg has a consistent default everywhere.
+  pub fn args_by_name(&self) -> BTreeMap<&str, Vec<(&str, &ArgInstruction)>> {
+    let mut args: BTreeMap<&str, Vec<(&str, &ArgInstruction)>> = BTreeMap::new();
+
+    for (name, dockerfile) in self.iter() {
+      for ins in &dockerfile.instructions {
+        if let Instruction::Arg(arg) = ins {
+          args.entry(arg.name.content.as_str()).or_default().push((name, arg));
+        }
+      }
+    }
+
+    args
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use indoc::indoc;
+  use pretty_assertions::assert_eq;
+
+  use crate::*;
+
+  fn workspace() -> Workspace {
+    let mut workspace = Workspace::new();
+
+    workspace.add("web/Dockerfile", Dockerfile::parse(indoc!(r#"
+      ARG NODE_VERSION=20
+      FROM node:${NODE_VERSION} AS build
+      RUN npm ci
+
+      FROM build
+      ARG PORT=8080
+    "#)).unwrap());
+
+    workspace.add("api/Dockerfile", Dockerfile::parse(indoc!(r#"
+      FROM docker.io/library/node:20
+      ARG PORT=3000
+    "#)).unwrap());
+
+    workspace.add("worker/Dockerfile", Dockerfile::parse(indoc!(r#"
+      FROM index.docker.io/library/node:20 AS base
+      FROM scratch
+      COPY --from=base /app /app
+      FROM alpine
+    "#)).unwrap());
+
+    workspace
+  }
+
+  #[test]
+  fn test_images() {
+    let workspace = workspace();
+    assert_eq!(workspace.len(), 3);
+
+    let images = workspace.images();
+    let summary: Vec<(&str, String)> = images
+      .iter()
+      .map(|(name, image, _)| (*name, image.to_string()))
+      .collect();
+
+    assert_eq!(summary, vec![
+      ("worker/Dockerfile", "docker.io/library/alpine:latest".to_string()),
+      ("api/Dockerfile", "docker.io/library/node:20".to_string()),
+      ("web/Dockerfile", "docker.io/library/node:20".to_string()),
+      ("worker/Dockerfile", "docker.io/library/node:20".to_string()),
+    ]);
+
+    // spans point at the image as written in each file
+    let (name, _, span) = &images[2];
+    assert_eq!(workspace.find(name).unwrap().text(span), "node:${NODE_VERSION}");
+
+    let mut distinct: Vec<&ImageRef> = images.iter().map(|(_, image, _)| image).collect();
+    distinct.dedup();
+    assert_eq!(distinct.len(), 2);
+  }
+
+  #[test]
+  fn test_args_by_name() {
+    let workspace = workspace();
+    let args = workspace.args_by_name();
+
+    assert_eq!(args.keys().copied().collect::<Vec<_>>(), vec!["NODE_VERSION", "PORT"]);
+    assert_eq!(
+      args["PORT"]
+        .iter()
+        .map(|(name, arg)| (*name, arg.value.as_ref().map(|v| v.content.as_str())))
+        .collect::<Vec<_>>(),
+      vec![("api/Dockerfile", Some("3000")), ("web/Dockerfile", Some("8080"))]
+    );
+
+    assert!(workspace.find("missing/Dockerfile").is_none());
+  }
+}
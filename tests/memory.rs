@@ -0,0 +1,86 @@
+// (C) Copyright 2026 Hewlett Packard Enterprise Development LP
+
+//! Measures the memory retained by parsed Dockerfiles. This is kept in its own
+//! test binary, with a single test, so that no other tests allocate while it
+//! runs.
+
+extern crate dockerfile_parser;
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use dockerfile_parser::{Dockerfile, ParseOptions};
+
+/// Tracks the number of bytes currently allocated.
+struct CountingAllocator;
+
+static ALLOCATED: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATED.fetch_add(layout.size(), Ordering::SeqCst);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        ALLOCATED.fetch_sub(layout.size(), Ordering::SeqCst);
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCATED.fetch_add(new_size, Ordering::SeqCst);
+        ALLOCATED.fetch_sub(layout.size(), Ordering::SeqCst);
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+/// Returns the average number of bytes retained by each of many copies of the
+/// given Dockerfile, parsed and held at once.
+fn retained_bytes(content: &str, options: &ParseOptions) -> usize {
+    const COPIES: usize = 100;
+
+    let before = ALLOCATED.load(Ordering::SeqCst);
+    let parsed: Vec<Dockerfile> = (0..COPIES)
+        .map(|_| Dockerfile::parse_with_options(content, options).unwrap())
+        .collect();
+    let retained = ALLOCATED.load(Ordering::SeqCst) - before;
+
+    assert_eq!(parsed.len(), COPIES);
+    retained / COPIES
+}
+
+#[test]
+fn compact_parse_memory() {
+    let content = include_str!("../Dockerfile.test");
+
+    let default = retained_bytes(content, &ParseOptions::default());
+    let compact = retained_bytes(content, &ParseOptions {
+        compact: true,
+        ..Default::default()
+    });
+
+    // at the time of writing, 27553 bytes by default (33273 before short lists
+    // like flags were allocated at their exact length) and 16628 compacted
+    assert!(default < 30_000, "Dockerfile retains {} bytes", default);
+    assert!(
+        compact * 4 < default * 3,
+        "compacting saved too little: {} bytes by default, {} compacted",
+        default,
+        compact
+    );
+    assert!(compact < 20_000, "compacted Dockerfile retains {} bytes", compact);
+
+    // compacting doesn't change the parse result
+    assert_eq!(
+        Dockerfile::parse(content).unwrap().instructions,
+        Dockerfile::parse_with_options(content, &ParseOptions {
+            compact: true,
+            ..Default::default()
+        })
+        .unwrap()
+        .instructions
+    );
+}
@@ -2,7 +2,11 @@
 
 extern crate dockerfile_parser;
 
-use dockerfile_parser::*;
+use dockerfile_parser::prelude::*;
+use dockerfile_parser::{
+    BreakableStringComponent, CommentAttachment, EnvVar, InstructionKind, InstructionMetrics,
+    Label, ShellOrExecExpr, SpannedComment,
+};
 use indoc::indoc;
 use pretty_assertions::assert_eq;
 
@@ -25,14 +29,17 @@ fn parse_basic() -> Result<(), dockerfile_parser::Error> {
             image: SpannedString {
                 span: Span { start: 10, end: 21 },
                 content: "alpine:3.10".into(),
+                raw_text: None,
+                quoted: false,
             },
             image_parsed: ImageRef {
+                scheme: None,
                 registry: None,
                 image: "alpine".into(),
                 tag: Some("3.10".into()),
                 hash: None
             },
-            index: 0,
+            index: Some(0),
             alias: None,
             flags: vec![],
         })
@@ -155,10 +162,14 @@ fn parse_label() -> Result<(), dockerfile_parser::Error> {
                 SpannedString {
                     span: Span::new(11, 14),
                     content: "foo".to_string(),
+                    raw_text: None,
+                    quoted: false,
                 },
                 SpannedString {
                     span: Span::new(15, 18),
                     content: "bar".to_string(),
+                    raw_text: None,
+                    quoted: false,
                 },
             )]
         }
@@ -173,10 +184,14 @@ fn parse_label() -> Result<(), dockerfile_parser::Error> {
                 SpannedString {
                     span: Span::new(30, 35),
                     content: "foo".to_string(),
+                    raw_text: None,
+                    quoted: true,
                 },
                 SpannedString {
                     span: Span::new(36, 41),
                     content: "bar".to_string(),
+                    raw_text: None,
+                    quoted: true,
                 },
             )]
         })
@@ -191,10 +206,14 @@ fn parse_label() -> Result<(), dockerfile_parser::Error> {
                 SpannedString {
                     span: Span::new(53, 62),
                     content: "foo=bar".to_string(),
+                    raw_text: None,
+                    quoted: true,
                 },
                 SpannedString {
                     span: Span::new(63, 66),
                     content: "bar".to_string(),
+                    raw_text: None,
+                    quoted: false,
                 },
             )]
         })
@@ -209,10 +228,14 @@ fn parse_label() -> Result<(), dockerfile_parser::Error> {
                 SpannedString {
                     span: Span::new(78, 81),
                     content: "foo".to_string(),
+                    raw_text: None,
+                    quoted: false,
                 },
                 SpannedString {
                     span: Span::new(82, 102),
                     content: "bar          baz".to_string(),
+                    raw_text: Some("\"bar\\\n          baz\"".to_string()),
+                    quoted: true,
                 },
             )]
         })
@@ -303,6 +326,8 @@ fn parse_comment() -> Result<(), dockerfile_parser::Error> {
                 SpannedString {
                     span: Span::new(396, 399),
                     content: "foo".to_string(),
+                    raw_text: None,
+                    quoted: false,
                 },
                 ((400, 401), "a")
             ),
@@ -311,6 +336,8 @@ fn parse_comment() -> Result<(), dockerfile_parser::Error> {
                 SpannedString {
                     span: Span::new(433, 436),
                     content: "bar".to_string(),
+                    raw_text: None,
+                    quoted: false,
                 },
                 ((437, 438), "b")
             ),
@@ -353,15 +380,18 @@ fn parse_from_sha256_digest() -> Result<(), dockerfile_parser::Error> {
     assert_eq!(
         dockerfile.instructions[0].as_from(),
         Some(&FromInstruction {
-            index: 0,
+            index: Some(0),
             span: (5, 95).into(),
             image: SpannedString {
                 span: Span { start: 10, end: 88 },
                 content:
                     "alpine@sha256:074d3636ebda6dd446d0d00304c4454f468237fdacf08fb0eeac90bdbfa1bac7"
                         .into(),
+                raw_text: None,
+                quoted: false,
             },
             image_parsed: ImageRef {
+                scheme: None,
                 registry: None,
                 image: "alpine".into(),
                 tag: None,
@@ -373,6 +403,8 @@ fn parse_from_sha256_digest() -> Result<(), dockerfile_parser::Error> {
             alias: Some(SpannedString {
                 span: Span { start: 92, end: 95 },
                 content: "foo".into(),
+                raw_text: None,
+                quoted: false,
             }),
             flags: vec![],
         })
@@ -380,3 +412,495 @@ fn parse_from_sha256_digest() -> Result<(), dockerfile_parser::Error> {
 
     Ok(())
 }
+
+#[test]
+fn splice_quoted_content_span() -> Result<(), dockerfile_parser::Error> {
+    let dockerfile = Dockerfile::parse(indoc!(
+        r#"
+        ARG version="1.0"
+        FROM alpine:3.12
+        LABEL unquoted=foo "multi \
+          line"="lorem \
+          ipsum"
+        ENV quoted='bar'
+        ENV single "baz"
+        RUN ["echo", "hello world"]
+      "#
+    ))?;
+
+    let arg = dockerfile.instructions[0].as_arg().unwrap();
+    let label = dockerfile.instructions[2].as_label().unwrap();
+    let env = dockerfile.instructions[3].as_env().unwrap();
+    let env_single = dockerfile.instructions[4].as_env().unwrap();
+    let run = dockerfile.instructions[5].as_run().unwrap();
+    let exec = run.as_exec().unwrap();
+
+    assert!(arg.value.as_ref().unwrap().is_quoted());
+    assert!(!label.labels[0].value.is_quoted());
+    assert!(label.labels[1].name.is_quoted());
+    assert!(label.labels[1].value.is_quoted());
+
+    let mut splicer = dockerfile.splicer();
+    splicer.splice(&arg.value.as_ref().unwrap().content_span(), "2.0");
+    splicer.splice(&label.labels[0].value.content_span(), "foo2");
+    splicer.splice(&label.labels[1].value.content_span(), "dolor");
+    splicer.splice(&env.vars[0].value.content_span(), "bar2");
+    splicer.splice(&env_single.vars[0].value.content_span(), "baz2");
+    splicer.splice(&exec.elements[1].content_span(), "goodbye");
+
+    assert_eq!(
+        splicer.content,
+        indoc!(
+            r#"
+            ARG version="2.0"
+            FROM alpine:3.12
+            LABEL unquoted=foo2 "multi \
+              line"="dolor"
+            ENV quoted='bar2'
+            ENV single "baz2"
+            RUN ["echo", "goodbye"]
+          "#
+        )
+    );
+
+    let spliced = Dockerfile::parse(&splicer.content)?;
+    let label = spliced.instructions[2].as_label().unwrap();
+    assert_eq!(label.labels[1].value.content, "dolor");
+
+    let env = spliced.instructions[3].as_env().unwrap();
+    assert_eq!(env.vars[0].value.to_string(), "bar2");
+    let env = spliced.instructions[4].as_env().unwrap();
+    assert_eq!(env.vars[0].value.to_string(), "baz2");
+
+    // unquoted text may also be shorter than its span, e.g. with a line
+    // continuation removed
+    let dockerfile = Dockerfile::parse("MAINTAINER jane \\\n  doe\nMAINTAINER 'x'\n")?;
+    let name = &dockerfile.instructions[0].as_maintainer().unwrap().name;
+    assert!(name.content.len() < name.span.end - name.span.start);
+    assert!(!name.is_quoted());
+    assert_eq!(name.content_span(), name.span);
+    assert!(dockerfile.instructions[1].as_maintainer().unwrap().name.is_quoted());
+
+    Ok(())
+}
+
+/// Returns the text of an instruction's first parsed argument.
+fn first_argument(ins: &Instruction) -> String {
+    let expr = |expr: &ShellOrExecExpr| match expr {
+        ShellOrExecExpr::Shell(shell) => shell.to_string(),
+        ShellOrExecExpr::Exec(exec) => exec.elements[0].content.clone(),
+    };
+
+    match ins {
+        Instruction::From(from) => from.image.content.clone(),
+        Instruction::Arg(arg) => arg.name.content.clone(),
+        Instruction::Label(label) => label.labels[0].name.content.clone(),
+        Instruction::Run(run) => expr(&run.expr),
+        Instruction::Entrypoint(entrypoint) => expr(&entrypoint.expr),
+        Instruction::Cmd(cmd) => expr(&cmd.expr),
+        Instruction::Copy(copy) => copy.sources[0].content.clone(),
+        Instruction::Add(add) => add.sources[0].content.clone(),
+        Instruction::Env(env) => env.vars[0].key.content.clone(),
+        Instruction::Volume(volume) => volume.paths[0].content.clone(),
+        Instruction::User(user) => user.user.content.clone(),
+        Instruction::Workdir(workdir) => workdir.path.content.clone(),
+        Instruction::Healthcheck(healthcheck) => expr(&healthcheck.cmd().unwrap().expr),
+        Instruction::Shell(shell) => shell.shell.elements[0].content.clone(),
+        Instruction::Stopsignal(stopsignal) => stopsignal.signal.content.clone(),
+        Instruction::Onbuild(onbuild) => first_argument(&onbuild.trigger),
+        Instruction::Maintainer(maintainer) => maintainer.name.content.clone(),
+        Instruction::Misc(misc) => misc.arguments.to_string(),
+    }
+}
+
+#[test]
+fn parse_keyword_continuation() {
+    use InstructionKind as K;
+
+    let cases = [
+        ("FROM\\\n  alpine:3.12", K::From, "alpine:3.12"),
+        ("ARG\\\n  foo=bar", K::Arg, "foo"),
+        ("LABEL\\\n  foo=bar", K::Label, "foo"),
+        ("LABEL\\\n  foo bar", K::Label, "foo"),
+        ("RUN\\\n  echo hello", K::Run, "echo hello"),
+        ("RUN\\\n  [\"echo\", \"hello\"]", K::Run, "echo"),
+        ("ENTRYPOINT\\\n  echo hello", K::Entrypoint, "echo hello"),
+        ("ENTRYPOINT\\\n  [\"echo\", \"hello\"]", K::Entrypoint, "echo"),
+        ("CMD\\\n  echo hello", K::Cmd, "echo hello"),
+        ("CMD\\\n  [\"echo\", \"hello\"]", K::Cmd, "echo"),
+        ("COPY\\\n  --from=foo /foo /bar", K::Copy, "/foo"),
+        ("COPY\\\n  /foo /bar", K::Copy, "/foo"),
+        ("copy\\\n  /foo /bar", K::Copy, "/foo"),
+        ("ADD\\\n  /foo /bar", K::Add, "/foo"),
+        ("ENV\\\n  foo=bar", K::Env, "foo"),
+        ("ENV\\\n  foo bar", K::Env, "foo"),
+        ("env\\\n  foo=bar", K::Env, "foo"),
+        ("VOLUME\\\n  /data", K::Volume, "/data"),
+        ("USER\\\n  nobody", K::User, "nobody"),
+        ("WORKDIR\\\n  /app", K::Workdir, "/app"),
+        ("HEALTHCHECK\\\n  CMD true", K::Healthcheck, "true"),
+        ("SHELL\\\n  [\"/bin/sh\", \"-c\"]", K::Shell, "/bin/sh"),
+        ("STOPSIGNAL\\\n  SIGTERM", K::Stopsignal, "SIGTERM"),
+        ("ONBUILD\\\n  RUN echo hello", K::Onbuild, "echo hello"),
+        ("MAINTAINER\\\n  someone", K::Maintainer, "someone"),
+        ("EXPOSE\\\n  8080", K::Misc, "8080"),
+    ];
+
+    for (case, kind, argument) in &cases {
+        let dockerfile = match Dockerfile::parse(&format!("FROM alpine\n{}\n", case)) {
+            Ok(d) => d,
+            Err(e) => panic!("failed to parse {:?}: {}", case, e),
+        };
+        assert_eq!(dockerfile.instructions.len(), 2, "{:?}", case);
+
+        let ins = &dockerfile.instructions[1];
+        assert_eq!(ins.kind(), *kind, "{:?}", case);
+        assert_eq!(first_argument(ins).trim(), *argument, "{:?}", case);
+    }
+}
+
+#[test]
+fn parse_multiple_instructions_on_line() {
+    let err = Dockerfile::parse("FROM alpine:3.12\nfrom alpine RUN echo hi\n").unwrap_err();
+    match &err {
+        Error::MultipleInstructionsOnLine { line, first, second } => {
+            assert_eq!(*line, 2);
+            assert_eq!(first, "FROM");
+            assert_eq!(second, "RUN");
+        }
+        _ => panic!("unexpected error: {}", err),
+    }
+    assert_eq!(
+        err.to_string(),
+        "unable to parse Dockerfile: line 2 contains both FROM and RUN instructions; \
+         each instruction must start on a new line"
+    );
+
+    // unrelated parse errors are unaffected
+    assert!(matches!(
+        Dockerfile::parse("FROM alpine as\n"),
+        Err(Error::ParseError { .. })
+    ));
+
+    // as are keywords other than the one where parsing failed
+    assert!(matches!(
+        Dockerfile::parse("FROM alpine AS user extra\n"),
+        Err(Error::ParseError { .. })
+    ));
+}
+
+#[test]
+fn parse_misplaced_pair_space() {
+    let cases = [
+        ("ENV foo= bar", "unexpected space after '=' in ENV pair on line 2"),
+        ("ENV foo =bar", "unexpected space before '=' in ENV pair on line 2"),
+        ("ENV a=b foo = bar", "unexpected space before '=' in ENV pair on line 2"),
+        ("ARG foo= bar", "unexpected space after '=' in ARG pair on line 2"),
+        ("ARG foo =\"bar\"", "unexpected space before '=' in ARG pair on line 2"),
+        ("LABEL foo= bar", "unexpected space after '=' in LABEL pair on line 2"),
+        ("LABEL \"foo\" =bar", "unexpected space before '=' in LABEL pair on line 2"),
+    ];
+
+    for (case, message) in &cases {
+        match Dockerfile::parse(&format!("FROM alpine\n{}\n", case)) {
+            Err(Error::GenericParseError { message: m }) => assert_eq!(&m, message, "{:?}", case),
+            other => panic!("unexpected result for {:?}: {:?}", case, other),
+        }
+    }
+
+    // a space after an empty value isn't mistaken for a gap when another pair
+    // follows
+    assert!(matches!(
+        Dockerfile::parse("FROM alpine\nENV foo= bar=baz\n"),
+        Err(Error::ParseError { .. })
+    ));
+}
+
+#[test]
+fn instruction_span_bounds() {
+    let fixtures = [
+        include_str!("../Dockerfile.test"),
+        indoc!(r#"
+            FROM alpine:3.12 AS base
+              RUN echo hi   
+            	LABEL foo.bar baz  
+              LABEL "a b" "c d"
+            ENV a b  
+            ENV a=b  
+            ENTRYPOINT /bin/sh -c  
+            EXPOSE 80 443  
+            RUN apk add \
+              curl \
+              git  
+             CMD ["sh"]  
+            RUN <<EOF
+              echo hi
+            EOF
+        "#),
+        "\u{feff}  FROM alpine  \r\n  COPY a b  \r\n",
+    ];
+
+    for fixture in &fixtures {
+        let dockerfile = Dockerfile::parse(fixture).unwrap();
+        assert!(!dockerfile.instructions.is_empty());
+
+        for ins in &dockerfile.instructions {
+            let span = ins.span();
+            let text = &dockerfile.content[span.start..span.end];
+
+            assert!(text.starts_with(|c: char| c.is_ascii_alphabetic()), "{:?}", text);
+            assert!(!text.ends_with(char::is_whitespace), "{:?}", text);
+            assert!(ins.indentation(&dockerfile).chars().all(|c| c == ' ' || c == '\t'));
+
+            if let Instruction::Label(label) = ins {
+                for l in &label.labels {
+                    assert!(!dockerfile.content[l.span.start..l.span.end].starts_with(' '));
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn parse_shebang() -> Result<(), dockerfile_parser::Error> {
+    let dockerfile = Dockerfile::parse(indoc!(
+        r#"
+        #!/usr/bin/env -S docker build . -f
+        # syntax=docker/dockerfile:1
+        FROM alpine:3.19
+        RUN echo hi
+    "#
+    ))?;
+
+    let shebang = dockerfile.shebang().unwrap();
+    assert_eq!(shebang.content, "/usr/bin/env -S docker build . -f");
+    assert_eq!(shebang.span, Span::new(0, 35));
+
+    assert_eq!(
+        dockerfile.syntax_directive().unwrap().value.content,
+        "docker/dockerfile:1"
+    );
+
+    assert_eq!(dockerfile.instructions.len(), 2);
+    assert_eq!(
+        dockerfile.instructions[0].as_from().unwrap().image.content,
+        "alpine:3.19"
+    );
+
+    // the shebang and directives are part of the preamble
+    assert!(dockerfile.preamble_slice().1.starts_with("#!"));
+
+    // without a shebang, a `#!` comment elsewhere isn't one
+    let dockerfile = Dockerfile::parse("FROM alpine\n#!/bin/sh\n")?;
+    assert_eq!(dockerfile.shebang(), None);
+
+    Ok(())
+}
+
+#[test]
+fn logical_line_comments() {
+    let fixtures = [
+        include_str!("../Dockerfile.test"),
+        indoc!(r#"
+            FROM debian:bookworm AS build
+            RUN set -eux; \
+                # refresh the package index
+                apt-get update; \
+                # build dependencies
+                # (removed below)
+                apt-get install -y --no-install-recommends \
+                    build-essential \
+                    # for fetching sources
+                    curl \
+                    git; \
+                make -C /src; \
+                apt-get purge -y build-essential
+            CMD ./server \
+              # listen everywhere
+              --host 0.0.0.0 \
+              --port 8080
+            ENTRYPOINT exec \
+            # no indentation
+            /entrypoint.sh
+            ONBUILD RUN make \
+              # onbuild comment
+              install
+        "#),
+    ];
+
+    for fixture in &fixtures {
+        let dockerfile = Dockerfile::parse(fixture).unwrap();
+
+        let breakables = dockerfile.instructions.iter().filter_map(|ins| match ins {
+            Instruction::Run(run) => run.as_shell(),
+            Instruction::Cmd(cmd) => cmd.as_shell(),
+            Instruction::Entrypoint(entrypoint) => entrypoint.as_shell(),
+            Instruction::Onbuild(onbuild) => onbuild.trigger.as_run().and_then(|r| r.as_shell()),
+            Instruction::Misc(misc) => Some(&misc.arguments),
+            _ => None,
+        });
+
+        for breakable in breakables {
+            let comments: Vec<&SpannedComment> = breakable
+                .iter_components()
+                .filter_map(|c| match c {
+                    BreakableStringComponent::Comment(c) => Some(c),
+                    _ => None,
+                })
+                .collect();
+
+            for attachment in &[CommentAttachment::Following, CommentAttachment::Preceding] {
+                let lines = breakable.logical_lines_with(*attachment);
+
+                // every comment is attached exactly once, in order
+                let attached: Vec<&SpannedComment> =
+                    lines.iter().flat_map(|l| l.comments.iter().copied()).collect();
+                assert_eq!(attached, comments);
+
+                // ... and sits between its line and the neighboring line
+                for (i, line) in lines.iter().enumerate() {
+                    let text = &dockerfile.content[line.span.start..line.span.end];
+                    assert_eq!(text, line.text);
+                    assert!(!text.starts_with(char::is_whitespace));
+                    assert!(!text.ends_with(char::is_whitespace));
+
+                    let previous_end = match i {
+                        0 => breakable.span.start,
+                        _ => lines[i - 1].span.end,
+                    };
+                    let next_start = lines
+                        .get(i + 1)
+                        .map(|l| l.span.start)
+                        .unwrap_or(breakable.span.end);
+
+                    for comment in &line.comments {
+                        let before = comment.span.start >= previous_end
+                            && comment.span.end <= line.span.start;
+                        let after = comment.span.start >= line.span.end
+                            && comment.span.end <= next_start;
+
+                        match attachment {
+                            CommentAttachment::Following => {
+                                assert!(before || (after && i == lines.len() - 1), "{:?}", comment)
+                            }
+                            CommentAttachment::Preceding => {
+                                assert!(after || (before && i == 0), "{:?}", comment)
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    let dockerfile = Dockerfile::parse(fixtures[1]).unwrap();
+    let lines = dockerfile.instructions[1]
+        .as_run()
+        .unwrap()
+        .as_shell()
+        .unwrap()
+        .logical_lines();
+
+    assert_eq!(
+        lines
+            .iter()
+            .map(|l| (l.text.as_str(), l.comments.iter().map(|c| c.text()).collect::<Vec<_>>()))
+            .collect::<Vec<_>>(),
+        vec![
+            ("set -eux;", vec![]),
+            ("apt-get update;", vec!["refresh the package index"]),
+            (
+                "apt-get install -y --no-install-recommends",
+                vec!["build dependencies", "(removed below)"]
+            ),
+            ("build-essential", vec![]),
+            ("curl", vec!["for fetching sources"]),
+            ("git;", vec![]),
+            ("make -C /src;", vec![]),
+            ("apt-get purge -y build-essential", vec![]),
+        ]
+    );
+}
+
+#[test]
+fn parse_many_documents() {
+    let input = indoc!(
+        r#"
+        FROM alpine:3.19
+        RUN true
+        # ---
+        FROM ubuntu as
+        RUN true
+          # ---
+        FROM debian
+        COPY a /b
+      "#
+    );
+
+    let documents = Dockerfile::parse_many(input, "# ---");
+    assert_eq!(documents.len(), 3);
+
+    let (span, first) = &documents[0];
+    assert_eq!(
+        &input[span.start..span.end],
+        "FROM alpine:3.19\nRUN true\n"
+    );
+    assert_eq!(first.as_ref().unwrap().instructions.len(), 2);
+
+    // a syntax error in one document doesn't affect the others
+    let (span, second) = &documents[1];
+    assert_eq!(&input[span.start..span.end], "FROM ubuntu as\nRUN true\n");
+    match second {
+        Err(Error::ParseError { source }) => assert!(matches!(
+            source.line_col,
+            pest::error::LineColLocation::Pos((4, _))
+                | pest::error::LineColLocation::Span((4, _), _)
+        )),
+        _ => panic!("expected a parse error, got {:?}", second),
+    }
+
+    // spans and line numbers are relative to the whole input
+    let (span, third) = &documents[2];
+    let third = third.as_ref().unwrap();
+    assert_eq!(&input[span.start..span.end], "FROM debian\nCOPY a /b\n");
+
+    let copy = third.instructions[1].as_copy().unwrap();
+    assert_eq!(
+        &input[copy.destination.span.start..copy.destination.span.end],
+        "/b"
+    );
+    assert_eq!(copy.span.relative_span(third).0, 7);
+}
+
+#[test]
+fn instruction_metrics_fixtures() {
+    let dockerfile = Dockerfile::parse(include_str!("../Dockerfile.test")).unwrap();
+
+    let mut totals = (0, 0, 0);
+    for ins in &dockerfile.instructions {
+        let span = ins.span();
+        let text = &dockerfile.content[span.start..span.end];
+        let lines: Vec<&str> = text.split('\n').collect();
+        let metrics = ins.metrics(&dockerfile);
+
+        // instructions start at the beginning of a line in this fixture
+        assert_eq!(
+            metrics,
+            InstructionMetrics {
+                bytes: text.len(),
+                physical_lines: lines.len(),
+                longest_line: lines.iter().map(|l| l.chars().count()).max().unwrap(),
+                continuation_count: lines.iter().filter(|l| l.ends_with('\\')).count(),
+                comment_count: 0,
+            },
+            "{:?}", text
+        );
+
+        totals.0 += metrics.physical_lines;
+        totals.1 += metrics.continuation_count;
+        totals.2 = totals.2.max(metrics.longest_line);
+    }
+
+    // every line that isn't blank or a comment belongs to one instruction
+    assert_eq!(totals, (48, 14, 48));
+}